@@ -0,0 +1,16 @@
+CREATE TABLE IF NOT EXISTS otp_codes (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    messenger TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    code_hash TEXT NOT NULL,
+    message_id UUID NOT NULL REFERENCES message_history (id) ON DELETE CASCADE,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at TIMESTAMPTZ NOT NULL,
+    verified_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS otp_codes_active_recipient_idx
+    ON otp_codes (user_id, messenger, recipient)
+    WHERE verified_at IS NULL;
@@ -0,0 +1,2 @@
+ALTER TABLE message_history
+    ADD COLUMN IF NOT EXISTS message_class TEXT NOT NULL DEFAULT 'transactional';
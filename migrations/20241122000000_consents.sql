@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS consents (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    messenger TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    status TEXT NOT NULL,
+    source TEXT NOT NULL,
+    proof TEXT,
+    confirmation_hash TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (user_id, messenger, recipient)
+);
@@ -0,0 +1,10 @@
+ALTER TABLE message_history
+    ADD COLUMN IF NOT EXISTS scheduled_for TIMESTAMPTZ;
+
+UPDATE message_history SET scheduled_for = created_at WHERE scheduled_for IS NULL;
+
+ALTER TABLE message_history
+    ALTER COLUMN scheduled_for SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS message_history_recipient_schedule_idx
+    ON message_history (user_id, messenger, recipient, scheduled_for);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS message_links (
+    source_id UUID NOT NULL REFERENCES message_history (id) ON DELETE CASCADE,
+    target_id UUID NOT NULL REFERENCES message_history (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source_id, target_id, kind)
+);
+
+CREATE INDEX IF NOT EXISTS message_links_target_idx
+    ON message_links (target_id);
@@ -0,0 +1,2 @@
+ALTER TABLE message_history
+    ADD COLUMN IF NOT EXISTS priority TEXT NOT NULL DEFAULT 'normal';
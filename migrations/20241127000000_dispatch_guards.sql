@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS dispatch_guards (
+    message_id UUID NOT NULL REFERENCES message_history (id) ON DELETE CASCADE,
+    attempt INTEGER NOT NULL,
+    sent_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (message_id, attempt)
+);
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS routing_rules (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    match_class TEXT,
+    match_tags TEXT[] NOT NULL DEFAULT '{}',
+    match_metadata JSONB NOT NULL DEFAULT '{}',
+    messenger TEXT NOT NULL,
+    recipient TEXT NOT NULL,
+    template TEXT,
+    priority TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS routing_rules_user_position_idx ON routing_rules (user_id, position);
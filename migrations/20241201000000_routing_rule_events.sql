@@ -0,0 +1 @@
+ALTER TABLE routing_rules ADD COLUMN IF NOT EXISTS match_event TEXT;
@@ -0,0 +1,87 @@
+-- Monthly range partitions on created_at for message_history and
+-- message_attempts. A partitioned table can only be referenced by foreign
+-- keys that include its partition key, so the references to
+-- message_history (id) are dropped; rows left pointing at a dropped
+-- partition are removed by the retention job instead of by cascade.
+
+CREATE OR REPLACE FUNCTION ensure_monthly_partition(parent TEXT, month DATE)
+RETURNS TEXT AS $$
+DECLARE
+    lower_bound TIMESTAMPTZ := date_trunc('month', month::TIMESTAMP) AT TIME ZONE 'UTC';
+    upper_bound TIMESTAMPTZ :=
+        (date_trunc('month', month::TIMESTAMP) + INTERVAL '1 month') AT TIME ZONE 'UTC';
+    partition_name TEXT := format('%s_%s', parent, to_char(month, 'YYYYMM'));
+BEGIN
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+        partition_name, parent, lower_bound, upper_bound
+    );
+    RETURN partition_name;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE message_attempts DROP CONSTRAINT IF EXISTS message_attempts_message_id_fkey;
+ALTER TABLE otp_codes DROP CONSTRAINT IF EXISTS otp_codes_message_id_fkey;
+ALTER TABLE message_links DROP CONSTRAINT IF EXISTS message_links_source_id_fkey;
+ALTER TABLE message_links DROP CONSTRAINT IF EXISTS message_links_target_id_fkey;
+ALTER TABLE idempotency_keys DROP CONSTRAINT IF EXISTS idempotency_keys_message_id_fkey;
+ALTER TABLE dispatch_guards DROP CONSTRAINT IF EXISTS dispatch_guards_message_id_fkey;
+
+ALTER TABLE message_history RENAME TO message_history_unpartitioned;
+ALTER TABLE message_history_unpartitioned
+    RENAME CONSTRAINT message_history_pkey TO message_history_unpartitioned_pkey;
+ALTER TABLE message_attempts RENAME TO message_attempts_unpartitioned;
+ALTER TABLE message_attempts_unpartitioned
+    RENAME CONSTRAINT message_attempts_pkey TO message_attempts_unpartitioned_pkey;
+
+CREATE TABLE message_history (
+    LIKE message_history_unpartitioned INCLUDING DEFAULTS,
+    PRIMARY KEY (id, created_at),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+) PARTITION BY RANGE (created_at);
+
+CREATE TABLE message_attempts (
+    LIKE message_attempts_unpartitioned INCLUDING DEFAULTS,
+    PRIMARY KEY (id, created_at)
+) PARTITION BY RANGE (created_at);
+
+-- Every month that holds rows, through three months ahead.
+DO $$
+DECLARE
+    parent TEXT;
+    earliest TIMESTAMPTZ;
+    month DATE;
+BEGIN
+    FOREACH parent IN ARRAY ARRAY['message_history', 'message_attempts'] LOOP
+        EXECUTE format('SELECT MIN(created_at) FROM %I', parent || '_unpartitioned')
+            INTO earliest;
+        FOR month IN
+            SELECT generate_series(
+                date_trunc('month', COALESCE(earliest, NOW()) AT TIME ZONE 'UTC'),
+                date_trunc('month', NOW() AT TIME ZONE 'UTC') + INTERVAL '3 months',
+                INTERVAL '1 month'
+            )::DATE
+        LOOP
+            PERFORM ensure_monthly_partition(parent, month);
+        END LOOP;
+    END LOOP;
+END;
+$$;
+
+INSERT INTO message_history SELECT * FROM message_history_unpartitioned;
+INSERT INTO message_attempts SELECT * FROM message_attempts_unpartitioned;
+
+DROP TABLE message_history_unpartitioned;
+DROP TABLE message_attempts_unpartitioned;
+
+CREATE INDEX IF NOT EXISTS message_history_user_idx
+    ON message_history (user_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS message_history_recipient_schedule_idx
+    ON message_history (user_id, messenger, recipient, scheduled_for);
+
+CREATE INDEX IF NOT EXISTS message_attempts_message_id_idx
+    ON message_attempts (message_id, created_at DESC);
+
+CREATE INDEX IF NOT EXISTS message_attempts_created_at_idx
+    ON message_attempts (created_at DESC);
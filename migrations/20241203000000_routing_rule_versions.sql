@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS routing_rule_versions (
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    version INTEGER NOT NULL,
+    rules JSONB NOT NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, version)
+);
+
+CREATE TABLE IF NOT EXISTS routing_rule_sets (
+    user_id UUID PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+    active_version INTEGER NOT NULL
+);
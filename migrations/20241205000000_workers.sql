@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS workers (
+    id UUID PRIMARY KEY,
+    hostname TEXT NOT NULL,
+    version TEXT NOT NULL,
+    bus TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    heartbeat_at TIMESTAMPTZ NOT NULL,
+    in_flight BIGINT NOT NULL DEFAULT 0,
+    handled BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0
+);
+
+ALTER TABLE dispatch_guards ADD COLUMN IF NOT EXISTS worker_id UUID;
@@ -0,0 +1,4 @@
+-- Set when the sender deletes the message; it then drops out of their
+-- history but stays for admins until purged.
+ALTER TABLE message_history
+    ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
@@ -0,0 +1,3 @@
+ALTER TABLE messenger_tokens
+    ADD COLUMN IF NOT EXISTS expires_at TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS device_id TEXT;
@@ -0,0 +1,5 @@
+ALTER TABLE messenger_tokens
+    ADD COLUMN IF NOT EXISTS label TEXT;
+
+ALTER TABLE message_history
+    ADD COLUMN IF NOT EXISTS token_id UUID;
@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS message_history_recipient_idx
+    ON message_history (user_id, messenger, recipient, created_at DESC);
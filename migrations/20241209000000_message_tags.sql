@@ -0,0 +1,5 @@
+ALTER TABLE message_history
+    ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE INDEX IF NOT EXISTS message_history_tags_idx
+    ON message_history USING GIN (tags);
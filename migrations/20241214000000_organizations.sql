@@ -0,0 +1,64 @@
+CREATE TABLE IF NOT EXISTS organizations (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    organization_id UUID NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    role TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (organization_id, user_id)
+);
+
+CREATE INDEX IF NOT EXISTS organization_members_user_idx ON organization_members (user_id);
+
+CREATE TABLE IF NOT EXISTS organization_invitations (
+    id UUID PRIMARY KEY,
+    organization_id UUID NOT NULL REFERENCES organizations (id) ON DELETE CASCADE,
+    email TEXT NOT NULL,
+    role TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    invited_by UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    accepted_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS organization_invitations_organization_idx
+    ON organization_invitations (organization_id, created_at DESC);
+
+-- Every user has a personal organization with the user's id, so existing
+-- tokens and messages move into it as they are.
+INSERT INTO organizations (id, name, created_at)
+SELECT id, COALESCE(display_name, email), created_at
+FROM users
+ON CONFLICT (id) DO NOTHING;
+
+INSERT INTO organization_members (organization_id, user_id, role, created_at)
+SELECT id, id, 'owner', created_at
+FROM users
+ON CONFLICT (organization_id, user_id) DO NOTHING;
+
+ALTER TABLE messenger_tokens
+    ADD COLUMN IF NOT EXISTS organization_id UUID REFERENCES organizations (id) ON DELETE CASCADE;
+UPDATE messenger_tokens SET organization_id = user_id WHERE organization_id IS NULL;
+ALTER TABLE messenger_tokens
+    ALTER COLUMN organization_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS messenger_tokens_organization_messenger_idx
+    ON messenger_tokens (organization_id, messenger);
+
+ALTER TABLE message_history
+    ADD COLUMN IF NOT EXISTS organization_id UUID;
+UPDATE message_history SET organization_id = user_id WHERE organization_id IS NULL;
+ALTER TABLE message_history
+    ALTER COLUMN organization_id SET NOT NULL;
+
+CREATE INDEX IF NOT EXISTS message_history_organization_idx
+    ON message_history (organization_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS message_history_organization_schedule_idx
+    ON message_history (organization_id, messenger, recipient, scheduled_for);
+CREATE INDEX IF NOT EXISTS message_history_organization_recipient_idx
+    ON message_history (organization_id, messenger, recipient, created_at DESC);
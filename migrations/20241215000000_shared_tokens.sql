@@ -0,0 +1,2 @@
+ALTER TABLE messenger_tokens
+    ADD COLUMN IF NOT EXISTS shared BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,3 @@
+-- Space-separated; keys from before scopes keep every scope.
+ALTER TABLE api_keys
+    ADD COLUMN IF NOT EXISTS scopes TEXT NOT NULL DEFAULT 'messages:send messages:read tokens:manage';
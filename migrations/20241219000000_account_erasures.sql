@@ -0,0 +1,13 @@
+-- No foreign key to users: the record is kept after the user is deleted.
+CREATE TABLE IF NOT EXISTS account_erasures (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    email_hash TEXT NOT NULL,
+    requested_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    messages_purged BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS account_erasures_pending_idx
+    ON account_erasures (user_id)
+    WHERE completed_at IS NULL;
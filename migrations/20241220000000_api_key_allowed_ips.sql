@@ -0,0 +1,3 @@
+-- Space-separated CIDRs the key may be used from; empty allows any address.
+ALTER TABLE api_keys
+    ADD COLUMN IF NOT EXISTS allowed_ips TEXT NOT NULL DEFAULT '';
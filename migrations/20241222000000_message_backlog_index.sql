@@ -0,0 +1,5 @@
+-- Undelivered messages are few next to the history, so the admin overview
+-- finds them through a partial index instead of scanning every partition.
+CREATE INDEX IF NOT EXISTS message_history_unsent_idx
+    ON message_history (status, scheduled_for)
+    WHERE status IN ('pending', 'scheduled', 'in_flight', 'retrying', 'deferred');
@@ -0,0 +1,12 @@
+-- Consents and idempotency keys belong to the organization that sends.
+-- Every user's personal organization shares their id, so existing rows move
+-- into it as they are.
+ALTER TABLE consents RENAME COLUMN user_id TO organization_id;
+ALTER TABLE consents
+    DROP CONSTRAINT IF EXISTS consents_user_id_fkey,
+    ADD FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE;
+
+ALTER TABLE idempotency_keys RENAME COLUMN user_id TO organization_id;
+ALTER TABLE idempotency_keys
+    DROP CONSTRAINT IF EXISTS idempotency_keys_user_id_fkey,
+    ADD FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE;
@@ -0,0 +1,6 @@
+-- Messages an erased user sent in shared organizations stay with those
+-- organizations, without their sender.
+ALTER TABLE message_history
+    ALTER COLUMN user_id DROP NOT NULL,
+    DROP CONSTRAINT IF EXISTS message_history_user_id_fkey,
+    ADD FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL;
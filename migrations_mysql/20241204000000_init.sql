@@ -0,0 +1,188 @@
+-- The MySQL/MariaDB schema, matching the PostgreSQL migrations up to
+-- routing rule versions. UUIDs are stored as BINARY(16), timestamps as
+-- UTC DATETIME(6) and text that is part of a key as VARCHAR.
+
+CREATE TABLE IF NOT EXISTS users (
+    id BINARY(16) PRIMARY KEY,
+    email VARCHAR(320) NOT NULL UNIQUE,
+    display_name TEXT,
+    created_at DATETIME(6) NOT NULL,
+    updated_at DATETIME(6) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS messenger_tokens (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    messenger VARCHAR(32) NOT NULL,
+    access_token TEXT NOT NULL,
+    refresh_token TEXT,
+    status VARCHAR(32) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    updated_at DATETIME(6) NOT NULL,
+    INDEX messenger_tokens_user_messenger_idx (user_id, messenger),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS message_history (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    messenger VARCHAR(32) NOT NULL,
+    recipient VARCHAR(255) NOT NULL,
+    body MEDIUMTEXT NOT NULL,
+    message_type VARCHAR(32) NOT NULL,
+    status VARCHAR(32) NOT NULL,
+    status_reason TEXT,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    requested_by VARCHAR(32) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    updated_at DATETIME(6) NOT NULL,
+    deadline DATETIME(6),
+    message_class VARCHAR(32) NOT NULL DEFAULT 'transactional',
+    scheduled_for DATETIME(6) NOT NULL,
+    priority VARCHAR(32) NOT NULL DEFAULT 'normal',
+    INDEX message_history_user_idx (user_id, created_at DESC),
+    INDEX message_history_recipient_schedule_idx (user_id, messenger, recipient, scheduled_for),
+    INDEX message_history_created_at_idx (created_at),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS message_attempts (
+    id BINARY(16) PRIMARY KEY,
+    message_id BINARY(16) NOT NULL,
+    attempt_number INTEGER NOT NULL,
+    status VARCHAR(32) NOT NULL,
+    status_reason TEXT,
+    requested_by VARCHAR(32) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    INDEX message_attempts_message_id_idx (message_id, created_at DESC),
+    INDEX message_attempts_created_at_idx (created_at DESC),
+    FOREIGN KEY (message_id) REFERENCES message_history (id) ON DELETE CASCADE
+);
+
+-- active_recipient is NULL once a code is verified, and NULLs never
+-- collide in a unique index, so only unverified codes are kept unique.
+CREATE TABLE IF NOT EXISTS otp_codes (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    messenger VARCHAR(32) NOT NULL,
+    recipient VARCHAR(255) NOT NULL,
+    code_hash VARCHAR(128) NOT NULL,
+    message_id BINARY(16) NOT NULL,
+    failed_attempts INTEGER NOT NULL DEFAULT 0,
+    expires_at DATETIME(6) NOT NULL,
+    verified_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL,
+    active_recipient TINYINT GENERATED ALWAYS AS (IF(verified_at IS NULL, 1, NULL)) STORED,
+    UNIQUE INDEX otp_codes_active_recipient_idx (user_id, messenger, recipient, active_recipient),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (message_id) REFERENCES message_history (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS consents (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    messenger VARCHAR(32) NOT NULL,
+    recipient VARCHAR(255) NOT NULL,
+    status VARCHAR(32) NOT NULL,
+    source TEXT NOT NULL,
+    proof TEXT,
+    confirmation_hash VARCHAR(128),
+    created_at DATETIME(6) NOT NULL,
+    updated_at DATETIME(6) NOT NULL,
+    UNIQUE (user_id, messenger, recipient),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS message_links (
+    source_id BINARY(16) NOT NULL,
+    target_id BINARY(16) NOT NULL,
+    kind VARCHAR(32) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    PRIMARY KEY (source_id, target_id, kind),
+    INDEX message_links_target_idx (target_id),
+    FOREIGN KEY (source_id) REFERENCES message_history (id) ON DELETE CASCADE,
+    FOREIGN KEY (target_id) REFERENCES message_history (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id BINARY(16) NOT NULL,
+    `key` VARCHAR(255) NOT NULL,
+    request_hash VARCHAR(128) NOT NULL,
+    message_id BINARY(16),
+    created_at DATETIME(6) NOT NULL,
+    PRIMARY KEY (user_id, `key`),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
+    FOREIGN KEY (message_id) REFERENCES message_history (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS dispatch_guards (
+    message_id BINARY(16) NOT NULL,
+    attempt INTEGER NOT NULL,
+    sent_at DATETIME(6),
+    created_at DATETIME(6) NOT NULL,
+    PRIMARY KEY (message_id, attempt),
+    FOREIGN KEY (message_id) REFERENCES message_history (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS user_passwords (
+    user_id BINARY(16) PRIMARY KEY,
+    password_hash TEXT NOT NULL,
+    updated_at DATETIME(6) NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS password_resets (
+    token_hash VARCHAR(128) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    expires_at DATETIME(6) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    INDEX password_resets_user_idx (user_id),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS user_identities (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    provider VARCHAR(64) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    UNIQUE (provider, subject),
+    INDEX user_identities_user_idx (user_id),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS routing_rules (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    name TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    match_event VARCHAR(255),
+    match_class VARCHAR(32),
+    match_tags JSON NOT NULL,
+    match_metadata JSON NOT NULL,
+    messenger VARCHAR(32) NOT NULL,
+    recipient TEXT NOT NULL,
+    template TEXT,
+    priority VARCHAR(32),
+    created_at DATETIME(6) NOT NULL,
+    updated_at DATETIME(6) NOT NULL,
+    INDEX routing_rules_user_position_idx (user_id, position),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS routing_rule_versions (
+    user_id BINARY(16) NOT NULL,
+    version INTEGER NOT NULL,
+    rules JSON NOT NULL,
+    reason TEXT NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    PRIMARY KEY (user_id, version),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS routing_rule_sets (
+    user_id BINARY(16) PRIMARY KEY,
+    active_version INTEGER NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS workers (
+    id BINARY(16) PRIMARY KEY,
+    hostname VARCHAR(255) NOT NULL,
+    version VARCHAR(64) NOT NULL,
+    bus VARCHAR(32) NOT NULL,
+    started_at DATETIME(6) NOT NULL,
+    heartbeat_at DATETIME(6) NOT NULL,
+    in_flight BIGINT NOT NULL DEFAULT 0,
+    handled BIGINT NOT NULL DEFAULT 0,
+    failed BIGINT NOT NULL DEFAULT 0
+);
+
+ALTER TABLE dispatch_guards ADD COLUMN worker_id BINARY(16);
@@ -0,0 +1,3 @@
+ALTER TABLE messenger_tokens
+    ADD COLUMN expires_at DATETIME(6),
+    ADD COLUMN device_id VARCHAR(255);
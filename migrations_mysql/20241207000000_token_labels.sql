@@ -0,0 +1,5 @@
+ALTER TABLE messenger_tokens
+    ADD COLUMN label VARCHAR(64);
+
+ALTER TABLE message_history
+    ADD COLUMN token_id BINARY(16);
@@ -0,0 +1,2 @@
+ALTER TABLE message_history
+    ADD COLUMN tags JSON NOT NULL DEFAULT (JSON_ARRAY());
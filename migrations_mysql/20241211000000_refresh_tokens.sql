@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id BINARY(16) PRIMARY KEY,
+    family_id BINARY(16) NOT NULL,
+    user_id BINARY(16) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    expires_at DATETIME(6) NOT NULL,
+    used_at DATETIME(6),
+    revoked_at DATETIME(6),
+    UNIQUE (token_hash),
+    INDEX refresh_tokens_family_idx (family_id),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
@@ -0,0 +1,13 @@
+CREATE TABLE IF NOT EXISTS sessions (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    device VARCHAR(256),
+    ip VARCHAR(64),
+    created_at DATETIME(6) NOT NULL,
+    last_seen_at DATETIME(6) NOT NULL,
+    expires_at DATETIME(6) NOT NULL,
+    revoked_at DATETIME(6),
+    INDEX sessions_user_idx (user_id, last_seen_at),
+    INDEX sessions_revoked_idx (revoked_at),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
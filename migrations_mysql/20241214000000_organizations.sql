@@ -0,0 +1,60 @@
+CREATE TABLE IF NOT EXISTS organizations (
+    id BINARY(16) PRIMARY KEY,
+    name VARCHAR(320) NOT NULL,
+    created_at DATETIME(6) NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS organization_members (
+    organization_id BINARY(16) NOT NULL,
+    user_id BINARY(16) NOT NULL,
+    role VARCHAR(16) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    PRIMARY KEY (organization_id, user_id),
+    INDEX organization_members_user_idx (user_id),
+    FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS organization_invitations (
+    id BINARY(16) PRIMARY KEY,
+    organization_id BINARY(16) NOT NULL,
+    email VARCHAR(320) NOT NULL,
+    role VARCHAR(16) NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    invited_by BINARY(16) NOT NULL,
+    created_at DATETIME(6) NOT NULL,
+    expires_at DATETIME(6) NOT NULL,
+    accepted_at DATETIME(6),
+    INDEX organization_invitations_organization_idx (organization_id, created_at DESC),
+    FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE,
+    FOREIGN KEY (invited_by) REFERENCES users (id) ON DELETE CASCADE
+);
+
+-- Every user has a personal organization with the user's id, so existing
+-- tokens and messages move into it as they are.
+INSERT INTO organizations (id, name, created_at)
+SELECT id, COALESCE(display_name, email), created_at
+FROM users;
+
+INSERT INTO organization_members (organization_id, user_id, role, created_at)
+SELECT id, id, 'owner', created_at
+FROM users;
+
+ALTER TABLE messenger_tokens
+    ADD COLUMN organization_id BINARY(16);
+UPDATE messenger_tokens SET organization_id = user_id WHERE organization_id IS NULL;
+ALTER TABLE messenger_tokens
+    MODIFY organization_id BINARY(16) NOT NULL,
+    ADD INDEX messenger_tokens_organization_messenger_idx (organization_id, messenger),
+    ADD FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE;
+
+ALTER TABLE message_history
+    ADD COLUMN organization_id BINARY(16);
+UPDATE message_history SET organization_id = user_id WHERE organization_id IS NULL;
+ALTER TABLE message_history
+    MODIFY organization_id BINARY(16) NOT NULL,
+    ADD INDEX message_history_organization_idx (organization_id, created_at DESC),
+    ADD INDEX message_history_organization_schedule_idx
+        (organization_id, messenger, recipient, scheduled_for),
+    ADD INDEX message_history_organization_recipient_idx
+        (organization_id, messenger, recipient, created_at DESC);
@@ -0,0 +1,2 @@
+ALTER TABLE messenger_tokens
+    ADD COLUMN shared BOOLEAN NOT NULL DEFAULT FALSE;
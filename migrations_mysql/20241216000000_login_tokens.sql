@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS login_tokens (
+    token_hash VARCHAR(128) PRIMARY KEY,
+    email VARCHAR(320) NOT NULL,
+    expires_at DATETIME(6) NOT NULL,
+    created_at DATETIME(6) NOT NULL
+);
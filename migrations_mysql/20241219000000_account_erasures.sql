@@ -0,0 +1,11 @@
+-- No foreign key to users: the record is kept after the user is deleted.
+CREATE TABLE IF NOT EXISTS account_erasures (
+    id BINARY(16) PRIMARY KEY,
+    user_id BINARY(16) NOT NULL,
+    email_hash VARCHAR(64) NOT NULL,
+    requested_at DATETIME(6) NOT NULL,
+    completed_at DATETIME(6),
+    messages_purged BIGINT NOT NULL DEFAULT 0,
+    INDEX account_erasures_user_idx (user_id, completed_at),
+    INDEX account_erasures_requested_idx (requested_at)
+);
@@ -0,0 +1,3 @@
+-- Space-separated CIDRs the key may be used from; empty allows any address.
+ALTER TABLE api_keys
+    ADD COLUMN allowed_ips VARCHAR(1024) NOT NULL DEFAULT '';
@@ -0,0 +1,4 @@
+-- Lets the admin overview find undelivered messages without scanning the
+-- history.
+ALTER TABLE message_history
+    ADD INDEX message_history_status_schedule_idx (status, scheduled_for);
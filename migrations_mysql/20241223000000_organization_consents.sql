@@ -0,0 +1,14 @@
+-- Consents and idempotency keys belong to the organization that sends.
+-- Every user's personal organization shares their id, so existing rows move
+-- into it as they are.
+ALTER TABLE consents
+    DROP FOREIGN KEY consents_ibfk_1;
+ALTER TABLE consents
+    RENAME COLUMN user_id TO organization_id,
+    ADD FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE;
+
+ALTER TABLE idempotency_keys
+    DROP FOREIGN KEY idempotency_keys_ibfk_1;
+ALTER TABLE idempotency_keys
+    RENAME COLUMN user_id TO organization_id,
+    ADD FOREIGN KEY (organization_id) REFERENCES organizations (id) ON DELETE CASCADE;
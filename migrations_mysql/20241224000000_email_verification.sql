@@ -0,0 +1,16 @@
+ALTER TABLE users
+    ADD COLUMN email_verified_at DATETIME(6);
+
+-- Accounts that signed in with a provider proved their email then. Accounts
+-- that only ever had a password stay unconfirmed until they confirm it.
+UPDATE users
+SET email_verified_at = created_at
+WHERE email_verified_at IS NULL
+  AND (
+      NOT EXISTS (SELECT 1 FROM user_passwords WHERE user_passwords.user_id = users.id)
+      OR EXISTS (
+          SELECT 1 FROM user_identities
+          WHERE user_identities.user_id = users.id
+            AND user_identities.provider <> 'password'
+      )
+  );
@@ -0,0 +1,7 @@
+-- Messages an erased user sent in shared organizations stay with those
+-- organizations, without their sender.
+ALTER TABLE message_history
+    DROP FOREIGN KEY message_history_ibfk_1;
+ALTER TABLE message_history
+    MODIFY user_id BINARY(16) NULL,
+    ADD FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE SET NULL;
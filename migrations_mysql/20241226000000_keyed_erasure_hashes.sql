@@ -0,0 +1,5 @@
+-- Email hashes are keyed from now on. The unkeyed ones could be matched
+-- against a list of known addresses, so they go.
+ALTER TABLE account_erasures
+    MODIFY email_hash VARCHAR(64) NULL;
+UPDATE account_erasures SET email_hash = NULL;
@@ -1,34 +1,104 @@
 use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use opentelemetry::{
+    Context, KeyValue,
+    trace::{SpanKind, TraceContextExt},
+};
+use tracing::{Instrument, info_span};
 
 use crate::{
-    application::services::messenger::MessengerGateway,
+    application::services::{
+        messenger::{MessengerClient, MessengerGateway, Unauthorized},
+        telemetry,
+        token_refresh::TokenRefresher,
+        worker_registry::WorkerHeartbeat,
+    },
     domain::{
         events::OutboundMessageEvent,
-        models::{MessageStatus, MessageType},
-        repositories::{MessageHistoryRepository, MessengerTokenRepository},
+        models::{
+            ChatAction, DispatchClaim, MessageStatus, MessageType, MessengerToken, RequestedBy,
+            TokenSelector,
+        },
+        repositories::{
+            DispatchGuardRepository, MessageHistoryRepository, MessengerTokenRepository,
+        },
     },
 };
 
 pub struct MessageDispatchHandler {
     token_repo: Arc<dyn MessengerTokenRepository>,
     history_repo: Arc<dyn MessageHistoryRepository>,
+    guard_repo: Arc<dyn DispatchGuardRepository>,
     gateway: MessengerGateway,
+    refresher: Arc<TokenRefresher>,
+    /// This process in the worker registry; claims are recorded under it.
+    worker: Arc<WorkerHeartbeat>,
+    /// Sends that take longer than this show "typing…" in the chat.
+    typing_threshold: Option<Duration>,
 }
 
 impl MessageDispatchHandler {
     pub fn new(
         token_repo: Arc<dyn MessengerTokenRepository>,
         history_repo: Arc<dyn MessageHistoryRepository>,
+        guard_repo: Arc<dyn DispatchGuardRepository>,
         gateway: MessengerGateway,
+        refresher: Arc<TokenRefresher>,
+        worker: Arc<WorkerHeartbeat>,
+        typing_threshold: Option<Duration>,
     ) -> Self {
         Self {
             token_repo,
             history_repo,
+            guard_repo,
             gateway,
+            refresher,
+            worker,
+            typing_threshold,
         }
     }
 
+    /// Traced under the current span, such as the bus consumer's, or else
+    /// under the trace the event carries. Every log line written while
+    /// dispatching names the message and event.
     pub async fn handle(&self, event: OutboundMessageEvent) -> anyhow::Result<()> {
+        let counters = self.worker.counters();
+        counters.start();
+        let parent = match Context::current() {
+            cx if cx.has_active_span() => cx,
+            _ => telemetry::extract(&event.trace_context),
+        };
+        let attributes = vec![
+            KeyValue::new("messaging.message.id", event.message_id.to_string()),
+            KeyValue::new("messaging.messenger", event.messenger.as_str()),
+            KeyValue::new("messaging.attempt", i64::from(event.attempt)),
+        ];
+        let span = info_span!(
+            "dispatch",
+            message_id = %event.message_id,
+            event_id = %event.event_id,
+            messenger = event.messenger.as_str(),
+            attempt = event.attempt,
+        );
+        let result = telemetry::in_span(
+            &parent,
+            "dispatch message",
+            SpanKind::Internal,
+            attributes,
+            self.dispatch(event).instrument(span.clone()),
+        )
+        .await;
+        span.in_scope(|| match &result {
+            Ok(()) => tracing::info!("message dispatched"),
+            Err(err) => tracing::warn!(error = %err, "message dispatch failed"),
+        });
+        counters.finish(result.is_ok());
+        result
+    }
+
+    async fn dispatch(&self, event: OutboundMessageEvent) -> anyhow::Result<()> {
         // Get message entry to know who requested it
         let message_entry = self
             .history_repo
@@ -44,26 +114,82 @@ impl MessageDispatchHandler {
                 attempts: event.attempt,
             };
             self.history_repo
-                .update_status(event.message_id, status.clone(), event.attempt)
+                .record_attempt(event.message_id, status, event.attempt, requested_by)
                 .await?;
-            // Log attempt
+            anyhow::bail!("unsupported message type");
+        }
+
+        if event.deadline_exceeded(Utc::now()) {
+            let status = MessageStatus::Failed {
+                reason: "deadline_exceeded".to_string(),
+                attempts: event.attempt,
+            };
             self.history_repo
-                .log_attempt(event.message_id, event.attempt, status, requested_by)
+                .record_attempt(event.message_id, status, event.attempt, requested_by)
                 .await?;
-            anyhow::bail!("unsupported message type");
+            anyhow::bail!("deadline_exceeded");
         }
 
         let token = self
             .token_repo
-            .find_active(&event.user_id, event.messenger)
+            .find_active(
+                &event.organization(),
+                &event.user_id,
+                event.messenger,
+                &TokenSelector::from_token_id(event.token_id),
+            )
             .await?
             .ok_or_else(|| anyhow::anyhow!("missing active token for messenger"))?;
+        let token = self.refresher.ensure_fresh(token).await;
 
         let client = self
             .gateway
             .get(event.messenger)
             .ok_or_else(|| anyhow::anyhow!("no client registered for messenger"))?;
 
+        // A redelivery after a crash between the send and the ack must not
+        // reach the messenger a second time.
+        let claim = self
+            .guard_repo
+            .claim(event.message_id, event.attempt, self.worker.id())
+            .await?;
+        let claim = match claim {
+            // The worker holding the claim stopped heartbeating without
+            // recording an outcome, so the send is taken over.
+            DispatchClaim::Interrupted {
+                worker_id: Some(owner),
+            } if !self.worker.is_alive(owner).await? => {
+                self.guard_repo.release(event.message_id).await?;
+                self.guard_repo
+                    .claim(event.message_id, event.attempt, self.worker.id())
+                    .await?
+            }
+            claim => claim,
+        };
+        match claim {
+            DispatchClaim::Claimed => {}
+            DispatchClaim::AlreadySent => {
+                return self.record_sent(&event, requested_by).await;
+            }
+            DispatchClaim::Interrupted { .. } if client.deduplicates_sends() => {}
+            // A live worker is still sending; the redelivery will find out
+            // how that went.
+            DispatchClaim::Interrupted { worker_id: Some(_) } => {
+                anyhow::bail!("message is being delivered by another worker");
+            }
+            DispatchClaim::Interrupted { worker_id: None } => {
+                let status = MessageStatus::Failed {
+                    reason: "delivery_unknown: an earlier delivery was interrupted while sending"
+                        .to_string(),
+                    attempts: event.attempt,
+                };
+                self.history_repo
+                    .record_attempt(event.message_id, status, event.attempt, requested_by)
+                    .await?;
+                return Ok(());
+            }
+        }
+
         // Log attempt start (InFlight status)
         let in_flight_status = MessageStatus::InFlight;
         self.history_repo
@@ -75,9 +201,31 @@ impl MessageDispatchHandler {
             )
             .await?;
 
-        if let Err(err) = client.send(&token, &event.recipient, &event.content).await {
+        let mut result = self.send(client.as_ref(), &token, &event).await;
+        // The platform rejected the token; a refreshed one gets one more try.
+        if result.as_ref().is_err_and(|err| err.is::<Unauthorized>()) {
+            match self.refresher.refresh(&token).await {
+                Ok(Some(token)) => result = self.send(client.as_ref(), &token, &event).await,
+                Ok(None) => {}
+                Err(err) => {
+                    tracing::warn!(token_id = %token.id, error = ?err, "failed to refresh token");
+                }
+            }
+        }
+        self.worker
+            .counters()
+            .deliveries()
+            .record(event.messenger, result.is_ok());
+
+        if let Err(err) = result {
+            self.guard_repo.release(event.message_id).await?;
             let reason = err.to_string();
-            let status = if event.attempt >= event.max_attempts {
+            let status = if event.deadline_exceeded(Utc::now()) {
+                MessageStatus::Failed {
+                    reason: format!("deadline_exceeded: {reason}"),
+                    attempts: event.attempt,
+                }
+            } else if event.attempt >= event.max_attempts {
                 MessageStatus::Failed {
                     reason: reason.clone(),
                     attempts: event.attempt,
@@ -89,24 +237,93 @@ impl MessageDispatchHandler {
                 }
             };
             self.history_repo
-                .update_status(event.message_id, status.clone(), event.attempt)
-                .await?;
-            // Log failed/retrying attempt
-            self.history_repo
-                .log_attempt(event.message_id, event.attempt, status, requested_by)
+                .record_attempt(event.message_id, status, event.attempt, requested_by)
                 .await?;
             return Err(err);
         }
 
-        let sent_status = MessageStatus::Sent;
-        self.history_repo
-            .update_status(event.message_id, sent_status.clone(), event.attempt)
+        self.guard_repo
+            .mark_sent(event.message_id, event.attempt)
             .await?;
-        // Log successful attempt
+        self.record_sent(&event, requested_by).await
+    }
+
+    async fn send(
+        &self,
+        client: &dyn MessengerClient,
+        token: &MessengerToken,
+        event: &OutboundMessageEvent,
+    ) -> anyhow::Result<()> {
+        let send = client.send(token, &event.recipient, &event.content, event.message_id);
+        let send = async {
+            match self.typing_threshold {
+                Some(threshold) => {
+                    tokio::pin!(send);
+                    match tokio::time::timeout(threshold, &mut send).await {
+                        Ok(result) => result,
+                        Err(_) => {
+                            let typing = client.send_chat_action(
+                                token,
+                                &event.recipient,
+                                ChatAction::Typing,
+                            );
+                            let (typing, result) = tokio::join!(typing, send);
+                            if let Err(err) = typing {
+                                tracing::warn!(error = ?err, "failed to send typing indicator");
+                            }
+                            result
+                        }
+                    }
+                }
+                None => send.await,
+            }
+        };
+        telemetry::in_span(
+            &Context::current(),
+            format!("{} send", event.messenger.as_str()),
+            SpanKind::Client,
+            vec![KeyValue::new(
+                "messaging.message.id",
+                event.message_id.to_string(),
+            )],
+            send,
+        )
+        .await
+    }
+
+    async fn record_sent(
+        &self,
+        event: &OutboundMessageEvent,
+        requested_by: RequestedBy,
+    ) -> anyhow::Result<()> {
         self.history_repo
-            .log_attempt(event.message_id, event.attempt, sent_status, requested_by)
+            .record_attempt(
+                event.message_id,
+                MessageStatus::Sent,
+                event.attempt,
+                requested_by,
+            )
             .await?;
 
         Ok(())
     }
+
+    /// Marks the message as terminally failed once its event is moved to the
+    /// dead-letter queue.
+    pub async fn record_dead_letter(
+        &self,
+        event: &OutboundMessageEvent,
+        reason: &str,
+    ) -> anyhow::Result<()> {
+        self.history_repo
+            .update_status(
+                event.message_id,
+                MessageStatus::Failed {
+                    reason: format!("dead_lettered: {reason}"),
+                    attempts: event.attempt,
+                },
+                event.attempt,
+            )
+            .await
+    }
 }
@@ -0,0 +1,86 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use tokio::task::JoinHandle;
+
+use crate::domain::{models::AccountErasure, repositories::AccountErasureRepository};
+
+const ERASURE_INTERVAL: Duration = Duration::from_secs(60);
+/// Room left for other queries between batches.
+const BATCH_PAUSE: Duration = Duration::from_millis(100);
+
+/// Works through requested account erasures: purges the personal history
+/// and takes the user off what they sent in shared organizations, in
+/// batches, then deletes the user.
+pub struct AccountEraser {
+    repo: Arc<dyn AccountErasureRepository>,
+    batch_size: u32,
+    /// The user is only deleted once access tokens issued before the
+    /// request have expired, since deleting it also deletes the revoked
+    /// sessions that keep those tokens out.
+    grace: Duration,
+}
+
+impl AccountEraser {
+    pub fn new(
+        repo: Arc<dyn AccountErasureRepository>,
+        batch_size: u32,
+        access_token_lifetime: Duration,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            repo,
+            batch_size: batch_size.max(1),
+            grace: access_token_lifetime,
+        })
+    }
+
+    pub async fn run_once(&self) -> anyhow::Result<()> {
+        for erasure in self.repo.list_pending().await? {
+            self.erase(&erasure).await?;
+        }
+        Ok(())
+    }
+
+    async fn erase(&self, erasure: &AccountErasure) -> anyhow::Result<()> {
+        let mut purged = erasure.messages_purged;
+        let mut anonymized = 0;
+        loop {
+            let deleted = self.repo.purge_messages(erasure, self.batch_size).await?;
+            let kept = self
+                .repo
+                .anonymize_messages(erasure, self.batch_size)
+                .await?;
+            purged += deleted;
+            anonymized += kept;
+            if deleted < self.batch_size as u64 && kept < self.batch_size as u64 {
+                break;
+            }
+            tokio::time::sleep(BATCH_PAUSE).await;
+        }
+
+        if Utc::now() - chrono::Duration::from_std(self.grace)? < erasure.requested_at {
+            return Ok(());
+        }
+        self.repo.complete(erasure).await?;
+        tracing::info!(
+            user_id = %erasure.user_id,
+            erasure_id = %erasure.id,
+            purged,
+            anonymized,
+            "erased account"
+        );
+        Ok(())
+    }
+
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(ERASURE_INTERVAL);
+            loop {
+                interval.tick().await;
+                if let Err(err) = self.run_once().await {
+                    tracing::error!(error = ?err, "account erasure failed");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use uuid::Uuid;
+
+/// What a client presents to sign in. Each provider accepts one kind.
+pub enum LoginCredentials {
+    /// The email is trusted as given.
+    Email {
+        email: String,
+        display_name: Option<String>,
+    },
+    Password {
+        email: String,
+        password: String,
+    },
+    /// An ID token issued by the deployment's OpenID Connect provider.
+    IdToken {
+        id_token: String,
+    },
+    /// A code an OAuth provider redirected back with, redeemed with the PKCE
+    /// verifier of the attempt that asked for it.
+    AuthorizationCode {
+        provider: String,
+        code: String,
+        code_verifier: String,
+    },
+    /// A token from a mailed sign-in link.
+    MagicLink {
+        token: String,
+    },
+}
+
+impl LoginCredentials {
+    /// Name of the provider that accepts these credentials.
+    pub fn provider(&self) -> &str {
+        match self {
+            LoginCredentials::Email { .. } => "email",
+            LoginCredentials::Password { .. } => "password",
+            LoginCredentials::IdToken { .. } => "oidc",
+            LoginCredentials::AuthorizationCode { provider, .. } => provider,
+            LoginCredentials::MagicLink { .. } => "magic_link",
+        }
+    }
+}
+
+/// Who the credentials belong to. Users are found through their linked
+/// identities, then by email, and created on their first sign-in.
+pub struct VerifiedIdentity {
+    /// The provider's stable id for the person.
+    pub subject: String,
+    /// Proven to be the person's, unless `user_id` is set.
+    pub email: String,
+    pub display_name: Option<String>,
+    /// The account the credentials were checked against, for providers
+    /// that keep them per account.
+    pub user_id: Option<Uuid>,
+}
+
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Where to send the browser to sign in, for providers using the
+    /// authorization-code flow.
+    fn authorization_url(&self, _state: &str, _code_challenge: &str) -> Option<String> {
+        None
+    }
+
+    async fn authenticate(
+        &self,
+        credentials: &LoginCredentials,
+    ) -> anyhow::Result<VerifiedIdentity>;
+}
+
+pub fn select_provider<'a>(
+    providers: &'a [Arc<dyn AuthProvider>],
+    credentials: &LoginCredentials,
+) -> anyhow::Result<&'a Arc<dyn AuthProvider>> {
+    providers
+        .iter()
+        .find(|provider| provider.name() == credentials.provider())
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "unsupported credentials: {} sign-in is not enabled",
+                credentials.provider()
+            )
+        })
+}
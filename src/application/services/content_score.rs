@@ -0,0 +1,144 @@
+use crate::domain::models::MessengerType;
+
+/// Heuristic spam scoring of message text, so content that platforms are
+/// likely to flag can be reworked before it is sent.
+pub struct ContentScorer {
+    banned_phrases: Vec<BannedPhrase>,
+    max_link_density: f64,
+    max_caps_ratio: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct BannedPhrase {
+    /// `None` applies the phrase to every messenger.
+    pub messenger: Option<MessengerType>,
+    pub phrase: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentScore {
+    /// 0 (clean) to 100 (almost certainly flagged).
+    pub score: u32,
+    pub findings: Vec<ContentFinding>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContentFinding {
+    pub rule: &'static str,
+    pub penalty: u32,
+    pub recommendation: String,
+}
+
+const LINK_PENALTY: u32 = 30;
+const CAPS_PENALTY: u32 = 25;
+const PUNCTUATION_PENALTY: u32 = 10;
+const BANNED_PHRASE_PENALTY: u32 = 40;
+// Short texts such as "OK" or an acronym say nothing about shouting.
+const MIN_LETTERS_FOR_CAPS: usize = 12;
+
+impl BannedPhrase {
+    /// Parses `messenger:phrase`, or `*:phrase` for all messengers.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let (messenger, phrase) = value
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("invalid banned phrase {value}"))?;
+        let messenger = match messenger.trim() {
+            "*" => None,
+            name => Some(
+                MessengerType::from_str(name)
+                    .ok_or_else(|| anyhow::anyhow!("unknown messenger in banned phrase {value}"))?,
+            ),
+        };
+        let phrase = phrase.trim().to_lowercase();
+        if phrase.is_empty() {
+            anyhow::bail!("empty banned phrase {value}");
+        }
+        Ok(Self { messenger, phrase })
+    }
+}
+
+impl ContentScorer {
+    pub fn new(
+        banned_phrases: Vec<BannedPhrase>,
+        max_link_density: f64,
+        max_caps_ratio: f64,
+    ) -> Self {
+        Self {
+            banned_phrases,
+            max_link_density,
+            max_caps_ratio,
+        }
+    }
+
+    pub fn evaluate(&self, messenger: MessengerType, text: &str) -> ContentScore {
+        let mut findings = Vec::new();
+
+        let words: Vec<&str> = text.split_whitespace().collect();
+        let links = words.iter().filter(|word| is_link(word)).count();
+        if !words.is_empty() && links as f64 / words.len() as f64 > self.max_link_density {
+            findings.push(ContentFinding {
+                rule: "link_density",
+                penalty: LINK_PENALTY,
+                recommendation: format!(
+                    "{links} of {} words are links; keep one link and move the rest to a landing page",
+                    words.len()
+                ),
+            });
+        }
+
+        let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+        let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+        if letters.len() >= MIN_LETTERS_FOR_CAPS
+            && upper as f64 / letters.len() as f64 > self.max_caps_ratio
+        {
+            findings.push(ContentFinding {
+                rule: "caps_ratio",
+                penalty: CAPS_PENALTY,
+                recommendation: "Avoid writing in capitals; use sentence case".to_string(),
+            });
+        }
+
+        if text.contains("!!!") || text.contains("???") {
+            findings.push(ContentFinding {
+                rule: "punctuation",
+                penalty: PUNCTUATION_PENALTY,
+                recommendation: "Drop repeated exclamation or question marks".to_string(),
+            });
+        }
+
+        let lowered = text.to_lowercase();
+        for banned in self
+            .banned_phrases
+            .iter()
+            .filter(|banned| banned.messenger.is_none_or(|m| m == messenger))
+        {
+            if lowered.contains(&banned.phrase) {
+                findings.push(ContentFinding {
+                    rule: "banned_phrase",
+                    penalty: BANNED_PHRASE_PENALTY,
+                    recommendation: format!(
+                        "Remove \"{}\", which {} policy flags",
+                        banned.phrase,
+                        messenger.as_str()
+                    ),
+                });
+            }
+        }
+
+        let score = findings
+            .iter()
+            .map(|finding| finding.penalty)
+            .sum::<u32>()
+            .min(100);
+        ContentScore { score, findings }
+    }
+}
+
+fn is_link(word: &str) -> bool {
+    let word = word.to_lowercase();
+    word.starts_with("http://")
+        || word.starts_with("https://")
+        || word.starts_with("www.")
+        || word.starts_with("t.me/")
+        || word.starts_with("vk.com/")
+}
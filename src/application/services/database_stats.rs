@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+
+pub struct PoolStats {
+    /// `primary` or `replica`.
+    pub name: &'static str,
+    /// Open connections, idle or in use.
+    pub size: u32,
+    pub idle: u32,
+    pub max_connections: u32,
+}
+
+pub struct DatabaseStats {
+    pub pools: Vec<PoolStats>,
+    /// Repository calls repeated after a transient error.
+    pub retries: u64,
+    /// Calls that gave up waiting for a free connection.
+    pub pool_timeouts: u64,
+}
+
+#[async_trait]
+pub trait DatabaseMonitor: Send + Sync {
+    fn stats(&self) -> DatabaseStats;
+
+    /// Fails unless every pool answers and the primary has all migrations
+    /// this build knows of applied.
+    async fn check(&self) -> anyhow::Result<()>;
+
+    /// Opens new connections with the credentials of these URLs, e.g. after
+    /// a rotation; open connections are kept until the pool retires them.
+    fn set_urls(&self, url: &str, read_url: Option<&str>) -> anyhow::Result<()>;
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use crate::domain::events::DeadLetterEvent;
+
+pub struct DeadLetter {
+    /// Queue-specific position, used to address the entry for redrive.
+    pub id: u64,
+    pub entry: DeadLetterEvent,
+}
+
+#[async_trait]
+pub trait DeadLetterQueue: Send + Sync {
+    /// The organization's most recent entries first.
+    async fn list(&self, organization_id: Uuid, limit: u32) -> anyhow::Result<Vec<DeadLetter>>;
+    async fn get(&self, id: u64) -> anyhow::Result<Option<DeadLetter>>;
+    async fn remove(&self, id: u64) -> anyhow::Result<()>;
+}
@@ -0,0 +1,97 @@
+use std::time::Duration;
+
+use crate::domain::models::RetryBackoff;
+
+// SQS retries wait out a visibility timeout, which stops at 12 hours.
+const SQS_MAX_VISIBILITY: Duration = Duration::from_secs(43_200);
+
+/// What the configured bus promises about delivery, worked out from the
+/// settings that together decide it.
+#[derive(Debug, Clone)]
+pub struct DeliverySemantics {
+    pub bus: String,
+    /// Whether queued and deferred events survive a restart.
+    pub durable: bool,
+    pub max_attempts: u32,
+    /// Deliveries JetStream allows per event; `None` when unlimited or when
+    /// the bus does not count them.
+    pub max_deliver: Option<u32>,
+    /// How long JetStream waits for an ack before redelivering.
+    pub ack_wait: Option<Duration>,
+    pub retry_backoff: RetryBackoff,
+}
+
+impl DeliverySemantics {
+    pub fn new(
+        bus: &str,
+        max_attempts: u32,
+        max_deliver: i64,
+        ack_wait_seconds: u64,
+        retry_backoff: RetryBackoff,
+    ) -> Self {
+        let bus = match bus {
+            "rabbitmq" | "memory" | "sqs" => bus,
+            _ => "jetstream",
+        };
+        let jetstream = bus == "jetstream";
+        Self {
+            bus: bus.to_string(),
+            durable: bus != "memory",
+            max_attempts,
+            max_deliver: (jetstream && max_deliver > 0).then_some(max_deliver as u32),
+            ack_wait: jetstream.then_some(Duration::from_secs(ack_wait_seconds)),
+            retry_backoff,
+        }
+    }
+
+    /// Nominal wait after each failed attempt that is followed by another.
+    pub fn retry_delays(&self) -> Vec<Duration> {
+        (1..self.max_attempts)
+            .map(|attempt| self.retry_backoff.nominal_delay(attempt))
+            .collect()
+    }
+
+    /// Longest wait a retry can get once jitter is applied.
+    pub fn longest_retry_delay(&self) -> Duration {
+        let nominal = self.retry_delays().into_iter().max().unwrap_or_default();
+        nominal
+            .mul_f64(1.0 + self.retry_backoff.jitter)
+            .min(Duration::from_millis(self.retry_backoff.max_ms))
+    }
+
+    /// Rejects settings that contradict each other, listing every problem.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let mut problems = Vec::new();
+        if self.max_attempts == 0 {
+            problems.push("SYSTEM_RETRY_LIMIT must be at least 1".to_string());
+        }
+        if self.ack_wait.is_some_and(|ack_wait| ack_wait.is_zero()) {
+            problems.push("NATS_ACK_WAIT_SECONDS must be positive".to_string());
+        }
+        // A deferred event spends one delivery waiting for its slot, so the
+        // stream needs one delivery more than there are attempts.
+        if let Some(max_deliver) = self
+            .max_deliver
+            .filter(|&max_deliver| max_deliver <= self.max_attempts)
+        {
+            problems.push(format!(
+                "NATS_MAX_DELIVER ({max_deliver}) must exceed SYSTEM_RETRY_LIMIT ({}), \
+                 or deferred messages are dead-lettered before their last attempt",
+                self.max_attempts
+            ));
+        }
+        if self.bus == "sqs" && self.longest_retry_delay() > SQS_MAX_VISIBILITY {
+            problems.push(format!(
+                "retries may wait {}s, but SQS cannot hide a message for more than {}s; \
+                 lower RETRY_BACKOFF_MAX_MS",
+                self.longest_retry_delay().as_secs(),
+                SQS_MAX_VISIBILITY.as_secs()
+            ));
+        }
+
+        if !problems.is_empty() {
+            anyhow::bail!("contradictory delivery settings: {}", problems.join("; "));
+        }
+        Ok(())
+    }
+}
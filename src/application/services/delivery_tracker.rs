@@ -0,0 +1,110 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Mutex, PoisonError},
+};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::domain::models::{DeliveryCounts, MessengerType};
+
+/// Rolling windows outcomes are reported over, shortest first.
+pub const WINDOWS: [Duration; 2] = [Duration::minutes(5), Duration::hours(1)];
+
+/// Width of the slots outcomes are counted in; windows are whole slots.
+const SLOT_SECONDS: i64 = 60;
+/// Slots kept, enough for the longest window.
+const SLOTS: i64 = 60;
+
+/// Outcomes of the sends to each messenger made by this process.
+#[derive(Default)]
+pub struct DeliveryTracker {
+    messengers: Mutex<HashMap<MessengerType, Outcomes>>,
+}
+
+#[derive(Default)]
+struct Outcomes {
+    total: DeliveryCounts,
+    /// The last hour per minute, oldest first; minutes without sends are
+    /// left out.
+    slots: VecDeque<(i64, DeliveryCounts)>,
+}
+
+/// A messenger's send outcomes, since startup and over each of [`WINDOWS`].
+pub struct MessengerDeliveries {
+    pub messenger: MessengerType,
+    pub total: DeliveryCounts,
+    pub windows: Vec<(Duration, DeliveryCounts)>,
+}
+
+impl DeliveryTracker {
+    pub fn record(&self, messenger: MessengerType, sent: bool) {
+        let slot = Utc::now().timestamp().div_euclid(SLOT_SECONDS);
+        let mut messengers = self
+            .messengers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let outcomes = messengers.entry(messenger).or_default();
+        count(&mut outcomes.total, sent);
+        match outcomes.slots.back_mut() {
+            Some((last, counts)) if *last == slot => count(counts, sent),
+            _ => {
+                let mut counts = DeliveryCounts::default();
+                count(&mut counts, sent);
+                outcomes.slots.push_back((slot, counts));
+            }
+        }
+        while outcomes
+            .slots
+            .front()
+            .is_some_and(|(first, _)| *first <= slot - SLOTS)
+        {
+            outcomes.slots.pop_front();
+        }
+    }
+
+    /// Every messenger, including those nothing was sent to yet.
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Vec<MessengerDeliveries> {
+        let current = now.timestamp().div_euclid(SLOT_SECONDS);
+        let messengers = self
+            .messengers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        MessengerType::ALL
+            .into_iter()
+            .map(|messenger| {
+                let outcomes = messengers.get(&messenger);
+                let windows = WINDOWS
+                    .into_iter()
+                    .map(|window| {
+                        let first = current - window.num_seconds() / SLOT_SECONDS;
+                        let mut counts = DeliveryCounts::default();
+                        for (_, slot) in outcomes
+                            .into_iter()
+                            .flat_map(|outcomes| &outcomes.slots)
+                            .filter(|(slot, _)| *slot > first)
+                        {
+                            counts.total += slot.total;
+                            counts.sent += slot.sent;
+                            counts.failed += slot.failed;
+                        }
+                        (window, counts)
+                    })
+                    .collect();
+                MessengerDeliveries {
+                    messenger,
+                    total: outcomes.map(|outcomes| outcomes.total).unwrap_or_default(),
+                    windows,
+                }
+            })
+            .collect()
+    }
+}
+
+fn count(counts: &mut DeliveryCounts, sent: bool) {
+    counts.total += 1;
+    if sent {
+        counts.sent += 1;
+    } else {
+        counts.failed += 1;
+    }
+}
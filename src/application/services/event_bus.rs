@@ -1,8 +1,36 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 
 use crate::domain::events::OutboundMessageEvent;
 
 #[async_trait]
 pub trait MessageBus: Send + Sync {
     async fn publish(&self, event: OutboundMessageEvent) -> anyhow::Result<()>;
+
+    /// Publishes the event to be delivered no earlier than `when`. Buses
+    /// without a native delay rely on their worker holding back events whose
+    /// `scheduled_at` is still ahead.
+    async fn publish_at(
+        &self,
+        mut event: OutboundMessageEvent,
+        when: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        event.scheduled_at = when;
+        self.publish(event).await
+    }
+
+    /// Publishes several events at once. Fails if any of them could not be
+    /// published, in which case some of the others may already be queued.
+    async fn publish_batch(&self, events: Vec<OutboundMessageEvent>) -> anyhow::Result<()> {
+        for event in events {
+            self.publish(event).await?;
+        }
+        Ok(())
+    }
+
+    /// Fails while the bus cannot be published to, such as during a broker
+    /// reconnect. Buses without a connection of their own are always up.
+    async fn check(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
 }
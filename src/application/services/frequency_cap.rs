@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::domain::models::MessageClass;
+
+#[derive(Debug, Clone)]
+pub struct FrequencyCap {
+    pub class: MessageClass,
+    pub max_messages: u32,
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrequencyCapMode {
+    /// Accept the message but hold it until the window has room.
+    Defer,
+    /// Record the message as suppressed and never send it.
+    Suppress,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CapDecision {
+    Allow,
+    Defer(DateTime<Utc>),
+    Suppress,
+}
+
+impl FrequencyCap {
+    /// Parses `class:max/window_seconds`, e.g. `marketing:3/3600`.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let (class, limit) = value
+            .split_once(':')
+            .ok_or_else(|| anyhow::anyhow!("invalid frequency cap {value}"))?;
+        let (max_messages, window) = limit
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("invalid frequency cap {value}"))?;
+
+        let class = MessageClass::from_str(class.trim())
+            .ok_or_else(|| anyhow::anyhow!("unknown message class in frequency cap {value}"))?;
+        let max_messages = max_messages.trim().parse::<u32>()?;
+        if max_messages == 0 {
+            anyhow::bail!("frequency cap must allow at least one message: {value}");
+        }
+        let window = Duration::from_secs(window.trim().parse::<u64>()?);
+
+        Ok(Self {
+            class,
+            max_messages,
+            window,
+        })
+    }
+
+    /// `slots` are the send times of the chat's messages in this class that
+    /// fall after `now - window`, including ones already deferred into the
+    /// future, sorted ascending. A deferred message is placed after all of
+    /// them so that no window ever holds more than `max_messages`.
+    pub fn evaluate(
+        &self,
+        mode: FrequencyCapMode,
+        slots: &[DateTime<Utc>],
+        now: DateTime<Utc>,
+    ) -> CapDecision {
+        let max = self.max_messages as usize;
+        if slots.len() < max {
+            return CapDecision::Allow;
+        }
+
+        match mode {
+            FrequencyCapMode::Suppress => CapDecision::Suppress,
+            FrequencyCapMode::Defer => {
+                let window = chrono::Duration::from_std(self.window).unwrap_or_default();
+                let freed_at = slots[slots.len() - max] + window;
+                let last = slots[slots.len() - 1];
+                let until = freed_at.max(last).max(now);
+                if until <= now {
+                    CapDecision::Allow
+                } else {
+                    CapDecision::Defer(until)
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} per {}s for {} messages",
+            self.max_messages,
+            self.window.as_secs(),
+            self.class.as_str()
+        )
+    }
+}
+
+impl FrequencyCapMode {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "defer" => Ok(FrequencyCapMode::Defer),
+            "suppress" => Ok(FrequencyCapMode::Suppress),
+            other => anyhow::bail!("unknown frequency cap mode {other}"),
+        }
+    }
+}
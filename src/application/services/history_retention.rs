@@ -0,0 +1,66 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use tokio::task::JoinHandle;
+
+use crate::domain::repositories::HistoryPartitionRepository;
+
+const MAINTENANCE_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Keeps monthly history partitions ahead of the clock and, when a
+/// retention is configured, drops whole months that fell out of it.
+pub struct HistoryRetention {
+    repo: Arc<dyn HistoryPartitionRepository>,
+    months_ahead: u32,
+    /// Full months kept before the current one; unset keeps everything.
+    retention_months: Option<u32>,
+}
+
+impl HistoryRetention {
+    pub fn new(
+        repo: Arc<dyn HistoryPartitionRepository>,
+        months_ahead: u32,
+        retention_months: Option<u32>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            repo,
+            // A month rollover between two runs must still find a partition.
+            months_ahead: months_ahead.max(1),
+            retention_months,
+        })
+    }
+
+    pub async fn run_once(&self) -> anyhow::Result<()> {
+        self.repo.ensure_partitions(self.months_ahead).await?;
+
+        let Some(retention_months) = self.retention_months else {
+            return Ok(());
+        };
+        let today = Utc::now().date_naive();
+        let cutoff = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+            .and_then(|month| month.checked_sub_months(Months::new(retention_months)))
+            .and_then(|month| month.and_hms_opt(0, 0, 0))
+            .ok_or_else(|| anyhow::anyhow!("history retention cutoff out of range"))?
+            .and_utc();
+        let dropped = self.repo.drop_partitions_before(cutoff).await?;
+        if !dropped.is_empty() {
+            tracing::info!(partitions = %dropped.join(", "), "dropped expired history partitions");
+        }
+        Ok(())
+    }
+
+    /// Repeats the maintenance daily. The first run is expected to have
+    /// happened at startup.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(MAINTENANCE_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(err) = self.run_once().await {
+                    tracing::error!(error = ?err, "history partition maintenance failed");
+                }
+            }
+        })
+    }
+}
@@ -1,24 +1,80 @@
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Context;
-use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
-use serde::{Deserialize, Serialize};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use uuid::Uuid;
 
-use crate::domain::models::User;
+use crate::domain::models::{User, UserRole};
 
 #[derive(Clone)]
 pub struct JwtServiceConfig {
-    pub secret: String,
+    pub keys: Arc<JwtKeys>,
     pub expiration: Duration,
     pub refresh_expiration: Duration,
+    /// Stamped into issued tokens as `iss`/`aud` and required when
+    /// verifying, so environments sharing keys don't honour each other's
+    /// tokens.
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+}
+
+/// A key tokens are signed or verified with. Keys without an id stand for
+/// `JWT_SECRET` and match tokens whose header names no `kid`.
+pub struct JwtKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    /// `None` for keys kept only to verify tokens signed before a rotation.
+    encoding: Option<EncodingKey>,
+    decoding: DecodingKey,
+    /// Published in the JWKS; only asymmetric keys have one.
+    public: Option<JsonWebKey>,
+}
+
+/// Public part of an asymmetric key, as RFC 7517 lays it out.
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonWebKey {
+    pub kty: &'static str,
+    #[serde(rename = "use")]
+    pub usage: &'static str,
+    pub kid: String,
+    pub alg: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crv: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JsonWebKeySet {
+    pub keys: Vec<JsonWebKey>,
+}
+
+/// Every key tokens may carry, and the one new tokens are signed with.
+/// Rotating means adding a key, signing with it, and dropping the old one
+/// once the tokens it signed have expired.
+pub struct JwtKeySet {
+    keys: Vec<JwtKey>,
+    signing: usize,
+}
+
+/// The key set in use, swapped whole when the secrets it is built from are
+/// refreshed.
+pub struct JwtKeys {
+    current: RwLock<Arc<JwtKeySet>>,
 }
 
 #[derive(Clone)]
 pub struct JwtService {
-    encoding: EncodingKey,
-    decoding: DecodingKey,
-    validation: Validation,
     config: JwtServiceConfig,
 }
 
@@ -28,49 +84,167 @@ pub struct Claims {
     pub email: String,
     pub exp: usize,
     pub iat: usize,
+    /// The session the token was issued for. Tokens from before sessions
+    /// were tracked have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<Uuid>,
+    #[serde(default)]
+    pub role: UserRole,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
 }
 
-impl JwtService {
-    pub fn new(config: JwtServiceConfig) -> Self {
-        let validation = Validation::default();
-        let encoding = EncodingKey::from_secret(config.secret.as_bytes());
-        let decoding = DecodingKey::from_secret(config.secret.as_bytes());
-
+impl JwtKey {
+    pub fn new(
+        kid: Option<String>,
+        algorithm: Algorithm,
+        encoding: Option<EncodingKey>,
+        decoding: DecodingKey,
+        public: Option<JsonWebKey>,
+    ) -> Self {
         Self {
+            kid,
+            algorithm,
             encoding,
             decoding,
-            validation,
-            config,
+            public,
+        }
+    }
+}
+
+impl JwtKeySet {
+    /// The single HS256 key of deployments that don't rotate keys.
+    pub fn from_secret(secret: &str) -> Arc<Self> {
+        Arc::new(Self {
+            keys: vec![JwtKey::new(
+                None,
+                Algorithm::HS256,
+                Some(EncodingKey::from_secret(secret.as_bytes())),
+                DecodingKey::from_secret(secret.as_bytes()),
+                None,
+            )],
+            signing: 0,
+        })
+    }
+
+    /// Signs with the key named `signing_kid`, or with the first one.
+    pub fn new(keys: Vec<JwtKey>, signing_kid: Option<&str>) -> anyhow::Result<Arc<Self>> {
+        if keys.is_empty() {
+            anyhow::bail!("at least one JWT key is required");
+        }
+        for (index, key) in keys.iter().enumerate() {
+            if keys[..index].iter().any(|other| other.kid == key.kid) {
+                anyhow::bail!("duplicate JWT key id {}", key.kid.as_deref().unwrap_or(""));
+            }
+        }
+        let signing = match signing_kid {
+            Some(kid) => keys
+                .iter()
+                .position(|key| key.kid.as_deref() == Some(kid))
+                .ok_or_else(|| anyhow::anyhow!("unknown JWT signing key {kid}"))?,
+            None => 0,
+        };
+        if keys[signing].encoding.is_none() {
+            anyhow::bail!("the JWT signing key needs its private key");
         }
+
+        Ok(Arc::new(Self { keys, signing }))
+    }
+
+    pub fn jwks(&self) -> JsonWebKeySet {
+        JsonWebKeySet {
+            keys: self
+                .keys
+                .iter()
+                .filter_map(|key| key.public.clone())
+                .collect(),
+        }
+    }
+
+    /// Signs `claims` with the signing key, named in the header by its id.
+    pub fn encode<T: Serialize>(&self, claims: &T) -> anyhow::Result<String> {
+        let key = &self.keys[self.signing];
+        let encoding = key
+            .encoding
+            .as_ref()
+            .context("JWT signing key has no private key")?;
+        let mut header = Header::new(key.algorithm);
+        header.kid = key.kid.clone();
+        jsonwebtoken::encode(&header, claims, encoding).context("failed to encode JWT")
+    }
+
+    /// Verifies the token with the key its header names, checking the
+    /// claims `validation` asks for with that key's algorithm.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        mut validation: Validation,
+    ) -> anyhow::Result<T> {
+        let header = jsonwebtoken::decode_header(token).context("failed to verify JWT")?;
+        let key = self
+            .find(header.kid.as_deref())
+            .context("JWT signed with an unknown key")?;
+        validation.algorithms = vec![key.algorithm];
+        jsonwebtoken::decode::<T>(token, &key.decoding, &validation)
+            .map(|data| data.claims)
+            .context("failed to verify JWT")
+    }
+
+    fn find(&self, kid: Option<&str>) -> Option<&JwtKey> {
+        self.keys.iter().find(|key| key.kid.as_deref() == kid)
+    }
+}
+
+impl JwtKeys {
+    pub fn new(keys: Arc<JwtKeySet>) -> Arc<Self> {
+        Arc::new(Self {
+            current: RwLock::new(keys),
+        })
     }
 
-    pub fn issue(&self, user: &User) -> anyhow::Result<String> {
-        self.issue_with_expiration(user, self.config.expiration)
+    pub fn current(&self) -> Arc<JwtKeySet> {
+        self.current.read().expect("jwt keys lock poisoned").clone()
     }
 
-    pub fn issue_refresh(&self, user: &User) -> anyhow::Result<String> {
-        self.issue_with_expiration(user, self.config.refresh_expiration)
+    pub fn replace(&self, keys: Arc<JwtKeySet>) {
+        *self.current.write().expect("jwt keys lock poisoned") = keys;
+    }
+}
+
+impl JwtService {
+    pub fn new(config: JwtServiceConfig) -> Self {
+        Self { config }
     }
 
-    fn issue_with_expiration(&self, user: &User, expiration: Duration) -> anyhow::Result<String> {
+    pub fn issue(&self, user: &User, session_id: Uuid) -> anyhow::Result<String> {
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .context("failed to calculate current timestamp")?;
-        let exp = now + expiration;
+        let exp = now + self.config.expiration;
         let claims = Claims {
             sub: user.id,
             email: user.email.clone(),
             exp: exp.as_secs() as usize,
             iat: now.as_secs() as usize,
+            sid: Some(session_id),
+            role: user.role,
+            iss: self.config.issuer.clone(),
+            aud: self.config.audience.clone(),
         };
 
-        jsonwebtoken::encode(&Header::default(), &claims, &self.encoding)
-            .context("failed to encode JWT")
+        self.config.keys.current().encode(&claims)
     }
 
     pub fn verify(&self, token: &str) -> anyhow::Result<Claims> {
-        jsonwebtoken::decode::<Claims>(token, &self.decoding, &self.validation)
-            .map(|data| data.claims)
-            .context("failed to verify JWT")
+        let mut validation = Validation::default();
+        if let Some(issuer) = &self.config.issuer {
+            validation.set_issuer(&[issuer]);
+        }
+        if let Some(audience) = &self.config.audience {
+            validation.set_audience(&[audience]);
+        }
+        self.config.keys.current().decode(token, validation)
     }
 }
@@ -0,0 +1,140 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+
+/// Failures are forgotten after this long without another one.
+const FAILURE_MEMORY: Duration = Duration::from_secs(60 * 60);
+
+/// Failed sign-ins per key and the lockouts they earned.
+#[async_trait]
+pub trait LoginAttemptStore: Send + Sync {
+    /// How much longer `key` is locked out, if it is.
+    async fn locked_for(&self, key: &str) -> anyhow::Result<Option<Duration>>;
+
+    /// Counts a failure and returns the failures so far, forgetting them
+    /// once `memory` passes without one.
+    async fn record_failure(&self, key: &str, memory: Duration) -> anyhow::Result<u32>;
+
+    async fn lock(&self, key: &str, duration: Duration) -> anyhow::Result<()>;
+
+    /// Forgets the failures and lockout of `key`.
+    async fn reset(&self, key: &str) -> anyhow::Result<()>;
+}
+
+/// Locks a key out for `base` once it reaches `after` failures, doubling
+/// with every further failure up to `max`. `after` of 0 never locks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LockoutPolicy {
+    pub after: u32,
+    pub base: Duration,
+    pub max: Duration,
+}
+
+impl LockoutPolicy {
+    pub fn lockout(&self, failures: u32) -> Option<Duration> {
+        if self.after == 0 || failures < self.after {
+            return None;
+        }
+        let doublings = (failures - self.after).min(31);
+        Some(self.base.saturating_mul(1 << doublings).min(self.max))
+    }
+}
+
+/// Refusal of a sign-in while its email or address is locked out.
+#[derive(Debug)]
+pub struct LoginLocked {
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for LoginLocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "too many failed sign-in attempts, try again in {}s",
+            self.retry_after.as_secs().max(1)
+        )
+    }
+}
+
+impl std::error::Error for LoginLocked {}
+
+/// Slows down credential guessing by locking out emails and client
+/// addresses that keep failing to sign in. An unavailable store lets
+/// sign-ins through rather than locking everyone out.
+pub struct LoginThrottle {
+    store: Arc<dyn LoginAttemptStore>,
+    per_email: LockoutPolicy,
+    per_ip: LockoutPolicy,
+}
+
+impl LoginThrottle {
+    pub fn new(
+        store: Arc<dyn LoginAttemptStore>,
+        per_email: LockoutPolicy,
+        per_ip: LockoutPolicy,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            store,
+            per_email,
+            per_ip,
+        })
+    }
+
+    /// Fails with [`LoginLocked`] while the email or the address is locked
+    /// out.
+    pub async fn check(&self, email: Option<&str>, ip: Option<&str>) -> anyhow::Result<()> {
+        let mut retry_after: Option<Duration> = None;
+        for (key, _) in self.keys(email, ip) {
+            match self.store.locked_for(&key).await {
+                Ok(Some(locked_for)) => {
+                    retry_after = Some(retry_after.map_or(locked_for, |wait| wait.max(locked_for)));
+                }
+                Ok(None) => {}
+                Err(err) => tracing::error!(error = ?err, "login throttle unavailable"),
+            }
+        }
+        match retry_after {
+            Some(retry_after) => Err(LoginLocked { retry_after }.into()),
+            None => Ok(()),
+        }
+    }
+
+    pub async fn record_failure(&self, email: Option<&str>, ip: Option<&str>) {
+        for (key, policy) in self.keys(email, ip) {
+            let result = async {
+                let failures = self.store.record_failure(&key, FAILURE_MEMORY).await?;
+                if let Some(lockout) = policy.lockout(failures) {
+                    self.store.lock(&key, lockout).await?;
+                }
+                anyhow::Ok(())
+            }
+            .await;
+            if let Err(err) = result {
+                tracing::error!(error = ?err, "login throttle unavailable");
+            }
+        }
+    }
+
+    /// Clears the email's failures. The address keeps its count, so one
+    /// account it controls doesn't let it keep guessing others.
+    pub async fn record_success(&self, email: Option<&str>) {
+        for (key, _) in self.keys(email, None) {
+            if let Err(err) = self.store.reset(&key).await {
+                tracing::error!(error = ?err, "login throttle unavailable");
+            }
+        }
+    }
+
+    fn keys(&self, email: Option<&str>, ip: Option<&str>) -> Vec<(String, LockoutPolicy)> {
+        let mut keys = Vec::new();
+        if let Some(email) = email.filter(|_| self.per_email.after > 0) {
+            let digest = Sha256::digest(email.trim().to_lowercase().as_bytes());
+            keys.push((format!("email:{}", hex::encode(digest)), self.per_email));
+        }
+        if let Some(ip) = ip.filter(|_| self.per_ip.after > 0) {
+            keys.push((format!("ip:{ip}"), self.per_ip));
+        }
+        keys
+    }
+}
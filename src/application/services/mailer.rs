@@ -0,0 +1,7 @@
+use async_trait::async_trait;
+
+/// Sends plain-text mail to users, e.g. password reset links.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, to: &str, subject: &str, body: &str) -> anyhow::Result<()>;
+}
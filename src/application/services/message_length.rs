@@ -0,0 +1,59 @@
+use crate::domain::models::MessengerType;
+
+/// How a platform counts towards its per-message length limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// Telegram measures text and entity offsets in UTF-16 code units, so
+    /// most emoji count twice.
+    Utf16CodeUnits,
+    Characters,
+}
+
+#[derive(Debug, Clone)]
+pub struct MessageLength {
+    pub used: usize,
+    pub limit: usize,
+    pub unit: LengthUnit,
+    /// Messages needed to deliver the text when it is split at the limit.
+    pub parts: usize,
+}
+
+impl LengthUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LengthUnit::Utf16CodeUnits => "utf16_code_units",
+            LengthUnit::Characters => "characters",
+        }
+    }
+}
+
+pub fn measure(messenger: MessengerType, text: &str) -> MessageLength {
+    let (unit, limit) = match messenger {
+        MessengerType::Telegram => (LengthUnit::Utf16CodeUnits, 4096),
+        MessengerType::Vk => (LengthUnit::Characters, 4096),
+    };
+    let used = match unit {
+        LengthUnit::Utf16CodeUnits => text.encode_utf16().count(),
+        LengthUnit::Characters => text.chars().count(),
+    };
+    MessageLength {
+        used,
+        limit,
+        unit,
+        parts: used.div_ceil(limit).max(1),
+    }
+}
+
+/// Parses `messenger:price`, the price charged for one message part.
+pub fn parse_part_price(value: &str) -> anyhow::Result<(MessengerType, f64)> {
+    let (messenger, price) = value
+        .split_once(':')
+        .ok_or_else(|| anyhow::anyhow!("invalid message part price {value}"))?;
+    let messenger = MessengerType::from_str(messenger.trim())
+        .ok_or_else(|| anyhow::anyhow!("unknown messenger in message part price {value}"))?;
+    let price = price.trim().parse::<f64>()?;
+    if price < 0.0 {
+        anyhow::bail!("message part price must not be negative: {value}");
+    }
+    Ok((messenger, price))
+}
@@ -2,8 +2,12 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
 
-use crate::domain::models::{MessageContent, MessengerChat, MessengerToken, MessengerType};
+use crate::domain::models::{
+    ChatAction, MessageContent, MessengerChat, MessengerToken, MessengerType,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub struct PaginationParams {
@@ -27,20 +31,72 @@ pub struct PaginatedChats {
     pub next_offset: Option<u32>,
 }
 
+/// Returned by a client when the platform rejected the access token, so the
+/// caller can refresh it and try again.
+#[derive(Debug)]
+pub struct Unauthorized(pub String);
+
+impl std::fmt::Display for Unauthorized {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unauthorized: {}", self.0)
+    }
+}
+
+impl std::error::Error for Unauthorized {}
+
+#[derive(Debug, Clone)]
+pub struct RefreshedToken {
+    pub access_token: String,
+    /// `None` keeps the stored refresh token.
+    pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Outbound requests to one messenger's API against the connections they
+/// needed; the fewer connections per request, the more were reused.
+#[derive(Debug, Clone, Copy)]
+pub struct ConnectionPoolStats {
+    pub messenger: MessengerType,
+    pub requests: u64,
+    /// Connections dialled, failed attempts included.
+    pub connections: u64,
+}
+
 #[async_trait]
 pub trait MessengerClient: Send + Sync {
     fn messenger(&self) -> MessengerType;
+    /// Whether the messenger drops a repeated send of the same `message_id`,
+    /// which makes resending after an interrupted delivery safe.
+    fn deduplicates_sends(&self) -> bool {
+        false
+    }
     async fn send(
         &self,
         token: &MessengerToken,
         recipient: &str,
         content: &MessageContent,
+        message_id: Uuid,
+    ) -> anyhow::Result<()>;
+    async fn send_chat_action(
+        &self,
+        token: &MessengerToken,
+        recipient: &str,
+        action: ChatAction,
     ) -> anyhow::Result<()>;
     async fn list_chats(
         &self,
         token: &MessengerToken,
         pagination: PaginationParams,
     ) -> anyhow::Result<PaginatedChats>;
+    /// Exchanges the stored refresh token for a new access token. `None` means
+    /// the platform has no refresh flow or the token has nothing to refresh with.
+    async fn refresh(&self, _token: &MessengerToken) -> anyhow::Result<Option<RefreshedToken>> {
+        Ok(None)
+    }
+    /// `None` for clients that keep no connections of their own.
+    fn pool_stats(&self) -> Option<ConnectionPoolStats> {
+        None
+    }
 }
 
 #[derive(Clone)]
@@ -60,4 +116,14 @@ impl MessengerGateway {
     pub fn get(&self, messenger: MessengerType) -> Option<Arc<dyn MessengerClient>> {
         self.clients.get(&messenger).cloned()
     }
+
+    pub fn pool_stats(&self) -> Vec<ConnectionPoolStats> {
+        let mut stats: Vec<_> = self
+            .clients
+            .values()
+            .filter_map(|client| client.pool_stats())
+            .collect();
+        stats.sort_by_key(|stats| stats.messenger.as_str());
+        stats
+    }
 }
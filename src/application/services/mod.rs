@@ -1,3 +1,30 @@
+pub mod account_eraser;
+pub mod auth_provider;
+pub mod content_score;
+pub mod database_stats;
+pub mod dead_letter;
+pub mod delivery_semantics;
+pub mod delivery_tracker;
 pub mod event_bus;
+pub mod frequency_cap;
+pub mod history_retention;
 pub mod jwt;
+pub mod login_throttle;
+pub mod mailer;
+pub mod message_length;
 pub mod messenger;
+pub mod oauth;
+pub mod otp;
+pub mod password;
+pub mod public_id;
+pub mod queue_stats;
+pub mod rate_limit;
+pub mod readiness;
+pub mod secret_token;
+pub mod secrets;
+pub mod session_revocations;
+pub mod status_feed;
+pub mod status_token;
+pub mod telemetry;
+pub mod token_refresh;
+pub mod worker_registry;
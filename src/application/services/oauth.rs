@@ -0,0 +1,34 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::application::services::secret_token::generate_secret_token;
+
+/// One authorization-code sign-in, kept by the browser until the provider
+/// redirects back. `state` ties the redirect to the browser that started
+/// it; `code_verifier` proves to the provider that the code is redeemed by
+/// whoever asked for it (PKCE, RFC 7636).
+pub struct OAuthAttempt {
+    pub state: String,
+    pub code_verifier: String,
+}
+
+impl OAuthAttempt {
+    pub fn new() -> Self {
+        Self {
+            state: Uuid::new_v4().simple().to_string(),
+            code_verifier: generate_secret_token(),
+        }
+    }
+
+    /// The `S256` challenge sent with the authorization request.
+    pub fn code_challenge(&self) -> String {
+        URL_SAFE_NO_PAD.encode(Sha256::digest(self.code_verifier.as_bytes()))
+    }
+}
+
+impl Default for OAuthAttempt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,17 @@
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Numeric code of the requested length drawn from a v4 UUID's random bits.
+pub fn generate_code(length: u32) -> String {
+    let length = length.clamp(4, 10);
+    let value = Uuid::new_v4().as_u128() % 10u128.pow(length);
+    format!("{:0width$}", value, width = length as usize)
+}
+
+/// Codes are short, so the hash is salted with the code's own id.
+pub fn hash_code(id: &Uuid, code: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(id.as_bytes());
+    hasher.update(code.as_bytes());
+    hex::encode(hasher.finalize())
+}
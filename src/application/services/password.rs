@@ -0,0 +1,30 @@
+use argon2::{
+    Argon2, PasswordHash, PasswordHasher, PasswordVerifier,
+    password_hash::{SaltString, rand_core::OsRng},
+};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Argon2id in PHC string format, so the parameters travel with the hash.
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| anyhow::anyhow!("failed to hash password: {e}"))
+}
+
+pub fn verify_password(password: &str, hash: &str) -> anyhow::Result<bool> {
+    let hash =
+        PasswordHash::new(hash).map_err(|e| anyhow::anyhow!("invalid password hash: {e}"))?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok())
+}
+
+pub fn validate_password(password: &str) -> anyhow::Result<()> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        anyhow::bail!("password must be at least {MIN_PASSWORD_LENGTH} characters");
+    }
+    Ok(())
+}
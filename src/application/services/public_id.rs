@@ -0,0 +1,125 @@
+use aes::{
+    Aes128, Block,
+    cipher::{BlockDecrypt, BlockEncrypt, KeyInit},
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+const ALPHABET: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const ENCODED_LEN: usize = 22;
+
+/// Turns internal UUIDs into references that are safe to hand out in links and
+/// back again. Implementations must be reversible.
+pub trait PublicIdCodec: Send + Sync {
+    fn encode(&self, id: &Uuid) -> String;
+    fn decode(&self, value: &str) -> Option<Uuid>;
+}
+
+/// Exposes the raw UUID, for deployments that don't need obfuscation.
+pub struct UuidCodec;
+
+impl PublicIdCodec for UuidCodec {
+    fn encode(&self, id: &Uuid) -> String {
+        id.to_string()
+    }
+
+    fn decode(&self, value: &str) -> Option<Uuid> {
+        Uuid::parse_str(value).ok()
+    }
+}
+
+/// Fixed-width base62 of the UUID run through AES-128 under a key derived from
+/// a secret, so references are short and neither reveal the underlying
+/// identifier nor, from any number of them, the key.
+pub struct Base62Codec {
+    cipher: Aes128,
+}
+
+impl Base62Codec {
+    pub fn new(secret: &str) -> anyhow::Result<Self> {
+        if secret.is_empty() {
+            anyhow::bail!("PUBLIC_ID_SECRET is required for base62 public ids");
+        }
+        let digest = Sha256::digest(secret.as_bytes());
+        Ok(Self {
+            cipher: Aes128::new_from_slice(&digest[..16]).expect("AES-128 takes a 16-byte key"),
+        })
+    }
+}
+
+impl PublicIdCodec for Base62Codec {
+    fn encode(&self, id: &Uuid) -> String {
+        let mut block = Block::from(*id.as_bytes());
+        self.cipher.encrypt_block(&mut block);
+        let mut value = u128::from_be_bytes(block.into());
+        let mut buf = [ALPHABET[0]; ENCODED_LEN];
+        for slot in buf.iter_mut().rev() {
+            *slot = ALPHABET[(value % 62) as usize];
+            value /= 62;
+        }
+        String::from_utf8(buf.to_vec()).expect("base62 alphabet is ascii")
+    }
+
+    fn decode(&self, value: &str) -> Option<Uuid> {
+        if value.len() != ENCODED_LEN {
+            return None;
+        }
+        let mut result: u128 = 0;
+        for byte in value.bytes() {
+            let digit = ALPHABET.iter().position(|c| *c == byte)? as u128;
+            result = result.checked_mul(62)?.checked_add(digit)?;
+        }
+        let mut block = Block::from(result.to_be_bytes());
+        self.cipher.decrypt_block(&mut block);
+        Some(Uuid::from_bytes(block.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base62_round_trips() {
+        let codec = Base62Codec::new("secret").unwrap();
+        for id in [Uuid::nil(), Uuid::max(), Uuid::new_v4()] {
+            let encoded = codec.encode(&id);
+            assert_eq!(encoded.len(), ENCODED_LEN);
+            assert!(encoded.bytes().all(|byte| ALPHABET.contains(&byte)));
+            assert_eq!(codec.decode(&encoded), Some(id));
+        }
+    }
+
+    #[test]
+    fn base62_hides_the_uuid_behind_the_secret() {
+        let id = Uuid::new_v4();
+        let encoded = Base62Codec::new("secret").unwrap().encode(&id);
+        let other = Base62Codec::new("other secret").unwrap();
+        assert_ne!(other.encode(&id), encoded);
+        assert_ne!(other.decode(&encoded), Some(id));
+    }
+
+    #[test]
+    fn base62_rejects_malformed_values() {
+        let codec = Base62Codec::new("secret").unwrap();
+        let encoded = codec.encode(&Uuid::new_v4());
+        assert_eq!(codec.decode(&encoded[1..]), None);
+        assert_eq!(codec.decode(&format!("{encoded}0")), None);
+        assert_eq!(codec.decode(&format!("{}-", &encoded[1..])), None);
+        assert_eq!(codec.decode(""), None);
+        // Past u128::MAX.
+        assert_eq!(codec.decode(&"z".repeat(ENCODED_LEN)), None);
+    }
+
+    #[test]
+    fn base62_requires_a_secret() {
+        assert!(Base62Codec::new("").is_err());
+    }
+
+    #[test]
+    fn uuid_codec_round_trips_and_rejects_garbage() {
+        let id = Uuid::new_v4();
+        assert_eq!(UuidCodec.decode(&UuidCodec.encode(&id)), Some(id));
+        assert_eq!(UuidCodec.decode("not-a-uuid"), None);
+    }
+}
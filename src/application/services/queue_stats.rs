@@ -0,0 +1,28 @@
+use async_trait::async_trait;
+
+/// Backlog of the outbound stream or queue. Figures a bus does not report
+/// are `None`.
+pub struct QueueStats {
+    pub stream: String,
+    /// Messages held by the bus, delivered or not.
+    pub messages: u64,
+    pub bytes: Option<u64>,
+    pub consumers: Vec<ConsumerStats>,
+}
+
+pub struct ConsumerStats {
+    pub name: String,
+    /// Messages not yet delivered to the consumer.
+    pub pending: u64,
+    /// Delivered but not yet acknowledged.
+    pub ack_pending: Option<u64>,
+    /// Delivered at least twice and still unacknowledged.
+    pub redelivered: Option<u64>,
+    /// Pull requests waiting for messages.
+    pub waiting: Option<u64>,
+}
+
+#[async_trait]
+pub trait QueueMonitor: Send + Sync {
+    async fn stats(&self) -> anyhow::Result<QueueStats>;
+}
@@ -0,0 +1,99 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+/// A token bucket holding up to `capacity` requests and refilling all of
+/// them over `window`, so bursts of `capacity` are allowed but the rate
+/// averages out to `capacity` per `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateDecision {
+    Allow,
+    /// The bucket is empty; one request fits again after this long.
+    Limited(Duration),
+}
+
+impl RateLimit {
+    /// Parses `requests/window_seconds`, e.g. `120/60`.
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        let (capacity, window) = value
+            .split_once('/')
+            .ok_or_else(|| anyhow::anyhow!("invalid rate limit {value}"))?;
+        let capacity = capacity.trim().parse::<u32>()?;
+        let window = window.trim().parse::<u64>()?;
+        if capacity == 0 || window == 0 {
+            anyhow::bail!("rate limit must allow requests over a positive window: {value}");
+        }
+
+        Ok(Self {
+            capacity,
+            window: Duration::from_secs(window),
+        })
+    }
+
+    /// Tokens regained per second.
+    pub fn refill_rate(&self) -> f64 {
+        f64::from(self.capacity) / self.window.as_secs_f64()
+    }
+
+    /// Takes a token from a bucket that held `tokens` `elapsed` ago, and
+    /// returns what it holds now along with the decision.
+    pub fn take(&self, tokens: f64, elapsed: Duration) -> (f64, RateDecision) {
+        let rate = self.refill_rate();
+        let tokens = (tokens + elapsed.as_secs_f64() * rate).min(f64::from(self.capacity));
+        if tokens >= 1.0 {
+            (tokens - 1.0, RateDecision::Allow)
+        } else {
+            let wait = Duration::from_secs_f64((1.0 - tokens) / rate);
+            (tokens, RateDecision::Limited(wait))
+        }
+    }
+}
+
+/// Starts new buckets partially full for a while after startup, so the
+/// callers whose buckets were lost in a restart, or expired during a long
+/// downtime, don't all burst at once.
+#[derive(Debug, Clone, Copy)]
+pub struct WarmUp {
+    /// Share of the capacity new buckets start with right after startup.
+    initial: f64,
+    period: Duration,
+    started: Instant,
+}
+
+impl WarmUp {
+    pub fn new(initial: f64, period: Duration) -> anyhow::Result<Self> {
+        if !(0.0..=1.0).contains(&initial) {
+            anyhow::bail!("rate limit warm-up fill must be between 0 and 1: {initial}");
+        }
+        Ok(Self {
+            initial,
+            period,
+            started: Instant::now(),
+        })
+    }
+
+    /// Share of the capacity a bucket the store doesn't hold starts with:
+    /// the initial share at startup, rising to a full bucket over the
+    /// period.
+    pub fn fill(&self) -> f64 {
+        let elapsed = self.started.elapsed();
+        if elapsed >= self.period {
+            return 1.0;
+        }
+        self.initial + (1.0 - self.initial) * elapsed.as_secs_f64() / self.period.as_secs_f64()
+    }
+}
+
+/// Where buckets live. Instances sharing a store share the limits, and
+/// only stores outside the process keep them across restarts.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Buckets the store doesn't hold start with `fill` of the capacity.
+    async fn take(&self, key: &str, limit: &RateLimit, fill: f64) -> anyhow::Result<RateDecision>;
+}
@@ -0,0 +1,87 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use tokio::task::JoinHandle;
+
+use crate::application::services::{database_stats::DatabaseMonitor, event_bus::MessageBus};
+
+/// Longest a single check may take; a dependency that hangs fails the probe
+/// instead of holding it past the orchestrator's own timeout.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct ReadinessCheck {
+    pub name: &'static str,
+    /// Why the check failed; `None` when it passed.
+    pub error: Option<String>,
+}
+
+pub struct Readiness {
+    pub checks: Vec<ReadinessCheck>,
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.checks.iter().all(|check| check.error.is_none())
+    }
+}
+
+/// Whether this process can do its work: the database answers with every
+/// migration applied, the bus is connected and the background workers are
+/// still running. Unlike liveness, failing it only takes the process out of
+/// rotation, so a database blip doesn't get it restarted.
+pub struct ReadinessProbe {
+    database: Arc<dyn DatabaseMonitor>,
+    bus: Arc<dyn MessageBus>,
+    /// Bus consumers and the worker heartbeat; none of them should ever end.
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ReadinessProbe {
+    pub fn new(
+        database: Arc<dyn DatabaseMonitor>,
+        bus: Arc<dyn MessageBus>,
+        workers: Vec<JoinHandle<()>>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            database,
+            bus,
+            workers,
+        })
+    }
+
+    pub async fn check(&self) -> Readiness {
+        let (database, bus) = tokio::join!(
+            run_check(self.database.check()),
+            run_check(self.bus.check()),
+        );
+        let stopped = self
+            .workers
+            .iter()
+            .filter(|worker| worker.is_finished())
+            .count();
+        let workers = (stopped > 0).then(|| format!("{stopped} worker tasks stopped"));
+        Readiness {
+            checks: vec![
+                ReadinessCheck {
+                    name: "database",
+                    error: database,
+                },
+                ReadinessCheck {
+                    name: "bus",
+                    error: bus,
+                },
+                ReadinessCheck {
+                    name: "workers",
+                    error: workers,
+                },
+            ],
+        }
+    }
+}
+
+async fn run_check(check: impl Future<Output = anyhow::Result<()>>) -> Option<String> {
+    match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(Ok(())) => None,
+        Ok(Err(err)) => Some(err.to_string()),
+        Err(_) => Some("timed out".to_string()),
+    }
+}
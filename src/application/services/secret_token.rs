@@ -0,0 +1,14 @@
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// A bearer secret such as a sign-in link, reset or invitation token: two
+/// random UUIDs, 244 random bits in all.
+pub fn generate_secret_token() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// What is stored in place of a token from `generate_secret_token`. It is
+/// random enough that an unsalted hash is safe.
+pub fn hash_secret_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
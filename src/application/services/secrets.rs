@@ -0,0 +1,48 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+
+/// Configuration values kept outside the environment, keyed by the env var
+/// they stand in for (`JWT_SECRET`, `DATABASE_URL`, ...).
+#[async_trait]
+pub trait SecretStore: Send + Sync {
+    async fn fetch(&self) -> anyhow::Result<HashMap<String, String>>;
+}
+
+/// What is done when a refresh finds the secrets changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretsOnChange {
+    /// The JWT keys and database credentials take the new values in place;
+    /// everything else keeps what was read at startup.
+    Reload,
+    /// The server shuts down so a supervisor restarts it with every value
+    /// new.
+    Restart,
+}
+
+impl SecretsOnChange {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "reload" => Ok(SecretsOnChange::Reload),
+            "restart" => Ok(SecretsOnChange::Restart),
+            other => anyhow::bail!("unknown SECRETS_ON_CHANGE {other}"),
+        }
+    }
+}
+
+/// Re-fetches the secrets every `interval` and returns them once they
+/// differ from `current`.
+pub async fn watch_secrets(
+    store: &dyn SecretStore,
+    current: &HashMap<String, String>,
+    interval: Duration,
+) -> HashMap<String, String> {
+    loop {
+        tokio::time::sleep(interval).await;
+        match store.fetch().await {
+            Ok(secrets) if secrets != *current => return secrets,
+            Ok(_) => {}
+            Err(err) => tracing::error!(error = ?err, "failed to refresh secrets"),
+        }
+    }
+}
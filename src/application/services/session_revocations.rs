@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::domain::repositories::SessionRepository;
+
+/// How long a session revoked by another instance may go on working here.
+const RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Sessions whose access tokens must no longer be accepted. An access token
+/// outlives the revocation of its session by at most its own lifetime, so
+/// only sessions revoked within that window are kept.
+pub struct SessionRevocations {
+    repo: Arc<dyn SessionRepository>,
+    window: Duration,
+    revoked: RwLock<HashMap<Uuid, DateTime<Utc>>>,
+}
+
+impl SessionRevocations {
+    pub fn new(repo: Arc<dyn SessionRepository>, access_token_lifetime: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            repo,
+            window: access_token_lifetime,
+            revoked: RwLock::new(HashMap::new()),
+        })
+    }
+
+    pub fn is_revoked(&self, session_id: Uuid) -> bool {
+        self.revoked
+            .read()
+            .expect("session revocations lock poisoned")
+            .contains_key(&session_id)
+    }
+
+    /// Applies a revocation made by this instance without waiting for the
+    /// next reload.
+    pub fn insert(&self, session_id: Uuid) {
+        self.revoked
+            .write()
+            .expect("session revocations lock poisoned")
+            .insert(session_id, Utc::now());
+    }
+
+    /// Adds the revocations made since the window opened. Nothing already
+    /// known is dropped here, so a revocation this instance has applied but
+    /// not yet written can't be lost to a reload that raced it.
+    pub async fn reload(&self) -> anyhow::Result<()> {
+        let since = Utc::now() - chrono::Duration::from_std(self.window)?;
+        let loaded = self.repo.revoked_since(since).await?;
+        let mut revoked = self
+            .revoked
+            .write()
+            .expect("session revocations lock poisoned");
+        for (session_id, revoked_at) in loaded {
+            revoked.entry(session_id).or_insert(revoked_at);
+        }
+        Ok(())
+    }
+
+    /// Forgets the revocations older than the window, whose access tokens
+    /// have all expired by now.
+    pub fn prune(&self) -> anyhow::Result<()> {
+        let since = Utc::now() - chrono::Duration::from_std(self.window)?;
+        self.revoked
+            .write()
+            .expect("session revocations lock poisoned")
+            .retain(|_, revoked_at| *revoked_at >= since);
+        Ok(())
+    }
+
+    /// Picks up revocations made by other instances. The first load is
+    /// expected to have happened at startup.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(err) = self.reload().await {
+                    tracing::error!(error = ?err, "session revocation reload failed");
+                }
+                if let Err(err) = self.prune() {
+                    tracing::error!(error = ?err, "session revocation prune failed");
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::Arc;
+
+use tokio::{
+    sync::{broadcast, mpsc},
+    task::JoinHandle,
+};
+use uuid::Uuid;
+
+use crate::domain::models::MessageStatusKind;
+
+/// Changes a subscriber may fall behind by before it misses some.
+const FEED_CAPACITY: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct MessageStatusChange {
+    pub message_id: Uuid,
+    pub organization_id: Uuid,
+    pub status: MessageStatusKind,
+    pub attempts: u32,
+}
+
+/// Fans status changes out to everyone watching within this instance.
+pub struct StatusFeed {
+    sender: broadcast::Sender<MessageStatusChange>,
+}
+
+impl StatusFeed {
+    pub fn new() -> Arc<Self> {
+        let (sender, _) = broadcast::channel(FEED_CAPACITY);
+        Arc::new(Self { sender })
+    }
+
+    pub fn publish(&self, change: MessageStatusChange) {
+        // Nobody watching is not an error.
+        let _ = self.sender.send(change);
+    }
+
+    /// Changes to the organization's messages from now on. Changes missed
+    /// by a slow reader are skipped rather than ending the subscription.
+    pub fn subscribe(&self, organization_id: Uuid) -> mpsc::Receiver<MessageStatusChange> {
+        let mut changes = self.sender.subscribe();
+        let (sender, receiver) = mpsc::channel(64);
+        tokio::spawn(async move {
+            loop {
+                let change = tokio::select! {
+                    _ = sender.closed() => break,
+                    change = changes.recv() => change,
+                };
+                match change {
+                    Ok(change) if change.organization_id == organization_id => {
+                        if sender.send(change).await.is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+        receiver
+    }
+}
+
+/// Feeds status changes made by any instance into the local feed.
+pub trait StatusListener: Send + Sync {
+    fn spawn(self: Arc<Self>, feed: Arc<StatusFeed>) -> JoinHandle<()>;
+}
@@ -0,0 +1,73 @@
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use jsonwebtoken::Validation;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::application::services::jwt::JwtKeys;
+
+/// Status links are signed with the access token keys but carry their own
+/// audience, so neither kind of token is accepted in place of the other.
+const AUDIENCE: &str = "message-status";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StatusClaims {
+    message_id: Uuid,
+    aud: String,
+    exp: usize,
+}
+
+/// Issues and verifies tokens that grant read access to one message's
+/// delivery status without an account. They follow the access tokens' key
+/// rotation: signed with the current signing key and verified by `kid`.
+pub struct StatusTokenService {
+    keys: Arc<JwtKeys>,
+    validation: Validation,
+    ttl: Duration,
+}
+
+impl StatusTokenService {
+    pub fn new(keys: Arc<JwtKeys>, ttl: Duration) -> Self {
+        let mut validation = Validation::default();
+        validation.set_audience(&[AUDIENCE]);
+        validation.set_required_spec_claims(&["exp", "aud"]);
+
+        Self {
+            keys,
+            validation,
+            ttl,
+        }
+    }
+
+    /// Returns the token and its expiry as a unix timestamp.
+    pub fn issue(&self, message_id: Uuid) -> anyhow::Result<(String, u64)> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("failed to calculate current timestamp")?;
+        let exp = (now + self.ttl).as_secs();
+        let claims = StatusClaims {
+            message_id,
+            aud: AUDIENCE.to_string(),
+            exp: exp as usize,
+        };
+
+        let token = self
+            .keys
+            .current()
+            .encode(&claims)
+            .context("failed to encode status token")?;
+        Ok((token, exp))
+    }
+
+    pub fn verify(&self, token: &str) -> anyhow::Result<Uuid> {
+        self.keys
+            .current()
+            .decode::<StatusClaims>(token, self.validation.clone())
+            .map(|claims| claims.message_id)
+            .context("failed to verify status token")
+    }
+}
@@ -0,0 +1,55 @@
+use std::{borrow::Cow, collections::HashMap, future::Future};
+
+use opentelemetry::{
+    Context, KeyValue, global,
+    trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer},
+};
+
+pub const TRACER: &str = "messaging";
+
+/// W3C headers a trace is carried in between processes.
+pub const TRACE_FIELDS: [&str; 2] = ["traceparent", "tracestate"];
+
+/// A trace context as `TRACE_FIELDS` entries, ready to travel with an event
+/// or in message headers.
+pub type TraceCarrier = HashMap<String, String>;
+
+/// Runs `work` in a new span under `parent`, marked as failed when it
+/// returns an error. Spans started within `work` nest under it.
+pub async fn in_span<T>(
+    parent: &Context,
+    name: impl Into<Cow<'static, str>>,
+    kind: SpanKind,
+    attributes: Vec<KeyValue>,
+    work: impl Future<Output = anyhow::Result<T>>,
+) -> anyhow::Result<T> {
+    let tracer = global::tracer(TRACER);
+    let span = tracer
+        .span_builder(name)
+        .with_kind(kind)
+        .with_attributes(attributes)
+        .start_with_context(&tracer, parent);
+    let cx = parent.with_span(span);
+    let result = work.with_context(cx.clone()).await;
+    if let Err(err) = &result {
+        cx.span().set_status(Status::error(err.to_string()));
+    }
+    cx.span().end();
+    result
+}
+
+/// The trace context of the current span.
+pub fn current() -> TraceCarrier {
+    inject(&Context::current())
+}
+
+pub fn inject(cx: &Context) -> TraceCarrier {
+    let mut carrier = TraceCarrier::new();
+    global::get_text_map_propagator(|propagator| propagator.inject_context(cx, &mut carrier));
+    carrier
+}
+
+/// The context to continue a trace from; an empty carrier starts a new one.
+pub fn extract(carrier: &TraceCarrier) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(carrier))
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::{
+    application::services::messenger::MessengerGateway,
+    domain::{
+        models::{MessengerToken, TokenSelector},
+        repositories::MessengerTokenRepository,
+    },
+};
+
+/// Tokens expiring sooner than this are refreshed before a send.
+const REFRESH_MARGIN_MINUTES: i64 = 5;
+
+pub struct TokenRefresher {
+    token_repo: Arc<dyn MessengerTokenRepository>,
+    gateway: MessengerGateway,
+}
+
+impl TokenRefresher {
+    pub fn new(token_repo: Arc<dyn MessengerTokenRepository>, gateway: MessengerGateway) -> Self {
+        Self {
+            token_repo,
+            gateway,
+        }
+    }
+
+    /// Refreshes the token if it is about to expire. A failed refresh keeps
+    /// the current token; the send will tell whether it still works.
+    pub async fn ensure_fresh(&self, token: MessengerToken) -> MessengerToken {
+        match token.expires_at {
+            Some(expires_at)
+                if expires_at - Utc::now() <= Duration::minutes(REFRESH_MARGIN_MINUTES) => {}
+            _ => return token,
+        }
+        match self.refresh(&token).await {
+            Ok(Some(refreshed)) => refreshed,
+            Ok(None) => token,
+            Err(err) => {
+                tracing::warn!(
+                    messenger = token.messenger.as_str(),
+                    token_id = %token.id,
+                    error = ?err,
+                    "failed to refresh token"
+                );
+                token
+            }
+        }
+    }
+
+    /// Exchanges the refresh token and stores the result. `None` means the
+    /// messenger cannot refresh this token.
+    pub async fn refresh(&self, token: &MessengerToken) -> anyhow::Result<Option<MessengerToken>> {
+        let Some(client) = self.gateway.get(token.messenger) else {
+            return Ok(None);
+        };
+
+        match client.refresh(token).await {
+            Ok(Some(refreshed)) => {
+                let updated = MessengerToken {
+                    access_token: refreshed.access_token,
+                    refresh_token: refreshed
+                        .refresh_token
+                        .or_else(|| token.refresh_token.clone()),
+                    expires_at: refreshed.expires_at,
+                    updated_at: Utc::now(),
+                    ..token.clone()
+                };
+                self.token_repo.upsert(updated).await.map(Some)
+            }
+            Ok(None) => Ok(None),
+            Err(err) => {
+                // Refresh tokens are single-use, so a concurrent worker that
+                // refreshed first makes ours fail; use what it stored.
+                let current = self
+                    .token_repo
+                    .find_active(
+                        &token.organization_id,
+                        &token.user_id,
+                        token.messenger,
+                        &TokenSelector::Id(token.id),
+                    )
+                    .await?;
+                match current {
+                    Some(current) if current.access_token != token.access_token => {
+                        Ok(Some(current))
+                    }
+                    _ => Err(err),
+                }
+            }
+        }
+    }
+}
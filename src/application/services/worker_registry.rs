@@ -0,0 +1,151 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+use crate::{
+    application::services::delivery_tracker::DeliveryTracker,
+    domain::{
+        models::{Worker, WorkerStats},
+        repositories::WorkerRepository,
+    },
+};
+
+/// Workers silent for this long are removed from the registry.
+const PRUNE_AFTER: chrono::Duration = chrono::Duration::days(1);
+
+/// Heartbeats missed before a worker is taken for dead.
+const MISSED_HEARTBEATS: u32 = 3;
+
+/// Deliveries counted by the dispatcher of this process.
+#[derive(Default)]
+pub struct DispatchCounters {
+    in_flight: AtomicU64,
+    handled: AtomicU64,
+    failed: AtomicU64,
+    deliveries: DeliveryTracker,
+}
+
+impl DispatchCounters {
+    pub fn start(&self) {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn finish(&self, succeeded: bool) {
+        self.in_flight.fetch_sub(1, Ordering::Relaxed);
+        self.handled.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            self.failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Outcomes of the sends to the messengers, which unlike `handled`
+    /// leave out events that never reached one.
+    pub fn deliveries(&self) -> &DeliveryTracker {
+        &self.deliveries
+    }
+
+    pub fn snapshot(&self) -> WorkerStats {
+        WorkerStats {
+            in_flight: self.in_flight.load(Ordering::Relaxed),
+            handled: self.handled.load(Ordering::Relaxed),
+            failed: self.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Keeps this process registered as a live worker.
+pub struct WorkerHeartbeat {
+    repo: Arc<dyn WorkerRepository>,
+    id: Uuid,
+    hostname: String,
+    version: String,
+    bus: String,
+    started_at: DateTime<Utc>,
+    counters: Arc<DispatchCounters>,
+    interval: Duration,
+}
+
+impl WorkerHeartbeat {
+    pub fn new(
+        repo: Arc<dyn WorkerRepository>,
+        hostname: String,
+        version: String,
+        bus: String,
+        counters: Arc<DispatchCounters>,
+        interval: Duration,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            repo,
+            id: Uuid::new_v4(),
+            hostname,
+            version,
+            bus,
+            started_at: Utc::now(),
+            counters,
+            interval,
+        })
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    pub fn counters(&self) -> &DispatchCounters {
+        &self.counters
+    }
+
+    /// Whether the worker `id` may still be working on what it claimed. This
+    /// process always is; others are as long as their heartbeat is fresh.
+    pub async fn is_alive(&self, id: Uuid) -> anyhow::Result<bool> {
+        if id == self.id {
+            return Ok(true);
+        }
+        let worker = self.repo.get(id).await?;
+        Ok(worker.is_some_and(|worker| worker.is_alive(Utc::now(), worker_ttl(self.interval))))
+    }
+
+    pub async fn beat(&self) -> anyhow::Result<()> {
+        let worker = Worker {
+            id: self.id,
+            hostname: self.hostname.clone(),
+            version: self.version.clone(),
+            bus: self.bus.clone(),
+            started_at: self.started_at,
+            heartbeat_at: Utc::now(),
+            stats: self.counters.snapshot(),
+        };
+        self.repo.heartbeat(&worker).await
+    }
+
+    /// Repeats the heartbeat and forgets workers gone for a day. The first
+    /// beat is expected to have happened at startup.
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.interval);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(err) = self.beat().await {
+                    tracing::error!(error = ?err, "worker heartbeat failed");
+                }
+                if let Err(err) = self.repo.prune(Utc::now() - PRUNE_AFTER).await {
+                    tracing::error!(error = ?err, "worker registry pruning failed");
+                }
+            }
+        })
+    }
+}
+
+/// How long a worker heartbeating every `interval` may stay silent before
+/// its claims are recoverable.
+pub fn worker_ttl(interval: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(interval * MISSED_HEARTBEATS).unwrap_or(PRUNE_AFTER)
+}
@@ -1,22 +1,44 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use chrono::Utc;
 use uuid::Uuid;
 
 use crate::{
-    application::services::jwt::{JwtService, JwtServiceConfig},
-    domain::models::User,
-    domain::repositories::UserRepository,
+    application::services::{
+        auth_provider::{AuthProvider, LoginCredentials, select_provider},
+        jwt::{JwtService, JwtServiceConfig},
+        login_throttle::LoginThrottle,
+        oauth::OAuthAttempt,
+        secret_token::{generate_secret_token, hash_secret_token},
+        session_revocations::SessionRevocations,
+    },
+    domain::models::{RefreshToken, Session, SessionClient, User, UserIdentity, UserRole},
+    domain::repositories::{
+        IdentityRepository, RefreshTokenRepository, SessionRepository, UserRepository,
+    },
 };
 
 pub struct AuthenticateUserUseCase {
     user_repo: Arc<dyn UserRepository>,
+    identity_repo: Arc<dyn IdentityRepository>,
+    refresh_repo: Arc<dyn RefreshTokenRepository>,
+    session_repo: Arc<dyn SessionRepository>,
+    revocations: Arc<SessionRevocations>,
+    providers: Vec<Arc<dyn AuthProvider>>,
+    throttle: Arc<LoginThrottle>,
     jwt: JwtService,
+    refresh_expiration: Duration,
+    /// Made admins when they sign in, so the first admin needs no database
+    /// access.
+    admin_emails: Vec<String>,
 }
 
-pub struct AuthRequest {
-    pub email: String,
-    pub display_name: Option<String>,
+/// Where accounts, their sign-in identities and their sessions are kept.
+pub struct AuthRepositories {
+    pub users: Arc<dyn UserRepository>,
+    pub identities: Arc<dyn IdentityRepository>,
+    pub refresh_tokens: Arc<dyn RefreshTokenRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
 }
 
 pub struct AuthResponse {
@@ -25,48 +47,246 @@ pub struct AuthResponse {
 }
 
 impl AuthenticateUserUseCase {
-    pub fn new(user_repo: Arc<dyn UserRepository>, jwt_config: JwtServiceConfig) -> Self {
+    pub fn new(
+        repositories: AuthRepositories,
+        revocations: Arc<SessionRevocations>,
+        providers: Vec<Arc<dyn AuthProvider>>,
+        throttle: Arc<LoginThrottle>,
+        jwt_config: JwtServiceConfig,
+        admin_emails: Vec<String>,
+    ) -> Self {
+        let refresh_expiration = jwt_config.refresh_expiration;
         let jwt = JwtService::new(jwt_config);
-        Self { user_repo, jwt }
+        Self {
+            user_repo: repositories.users,
+            identity_repo: repositories.identities,
+            refresh_repo: repositories.refresh_tokens,
+            session_repo: repositories.sessions,
+            revocations,
+            providers,
+            throttle,
+            jwt,
+            refresh_expiration,
+            admin_emails,
+        }
     }
 
-    pub async fn execute(&self, request: AuthRequest) -> anyhow::Result<AuthResponse> {
-        let mut user = if let Some(existing) = self.user_repo.find_by_email(&request.email).await? {
+    /// Starts an authorization-code sign-in with `provider`: returns where to
+    /// send the browser and the attempt to keep until it comes back.
+    pub fn authorize(&self, provider: &str) -> anyhow::Result<(String, OAuthAttempt)> {
+        let attempt = OAuthAttempt::new();
+        let url = self
+            .providers
+            .iter()
+            .find(|candidate| candidate.name() == provider)
+            .and_then(|provider| {
+                provider.authorization_url(&attempt.state, &attempt.code_challenge())
+            })
+            .ok_or_else(|| {
+                anyhow::anyhow!("unsupported credentials: {provider} sign-in is not enabled")
+            })?;
+        Ok((url, attempt))
+    }
+
+    /// Verifies the credentials with the provider that accepts them. The
+    /// account is the one the identity is linked to; an identity seen for
+    /// the first time joins the confirmed account with its email, or a new
+    /// one.
+    /// Every sign-in starts a session of its own.
+    ///
+    /// Emails and addresses that keep failing are locked out for a while,
+    /// see [`LoginThrottle`].
+    pub async fn execute(
+        &self,
+        credentials: LoginCredentials,
+        client: SessionClient,
+    ) -> anyhow::Result<AuthResponse> {
+        let email = match &credentials {
+            LoginCredentials::Password { email, .. } => Some(email.as_str()),
+            _ => None,
+        };
+        self.throttle.check(email, client.ip.as_deref()).await?;
+        let provider = select_provider(&self.providers, &credentials)?;
+        let identity = match provider.authenticate(&credentials).await {
+            Ok(identity) => identity,
+            Err(err) => {
+                self.throttle
+                    .record_failure(email, client.ip.as_deref())
+                    .await;
+                return Err(err);
+            }
+        };
+        self.throttle.record_success(email).await;
+
+        let linked = self
+            .identity_repo
+            .find(provider.name(), &identity.subject)
+            .await?;
+        let account = linked
+            .as_ref()
+            .map(|linked| linked.user_id)
+            .or(identity.user_id);
+        let existing = match account {
+            Some(user_id) => Some(
+                self.user_repo
+                    .get(&user_id)
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("user not found"))?,
+            ),
+            // Someone could sign up with an address that isn't theirs, so
+            // an account joins the identity only once it confirmed it.
+            None => match self.user_repo.find_by_email(&identity.email).await? {
+                Some(user) if user.email_verified_at.is_none() => anyhow::bail!(
+                    "invalid credentials: the account with this email has not confirmed it; \
+                     reset its password to claim it"
+                ),
+                found => found,
+            },
+        };
+
+        let mut user = if let Some(existing) = existing {
+            ensure_active(&existing)?;
             existing
         } else {
             User {
                 id: Uuid::new_v4(),
-                email: request.email.clone(),
-                display_name: request.display_name.clone(),
+                email: identity.email.clone(),
+                display_name: identity.display_name.clone(),
+                role: UserRole::Member,
+                disabled_at: None,
+                email_verified_at: Some(Utc::now()),
                 created_at: Utc::now(),
                 updated_at: Utc::now(),
             }
         };
 
-        user.display_name = user.display_name.or(request.display_name.clone());
+        user.display_name = user.display_name.or(identity.display_name);
+        if self
+            .admin_emails
+            .iter()
+            .any(|email| email.eq_ignore_ascii_case(&user.email))
+        {
+            user.role = UserRole::Admin;
+        }
         user.updated_at = Utc::now();
         self.user_repo.upsert(&user).await?;
 
-        let access_token = self.jwt.issue(&user)?;
-        let refresh_token = self.jwt.issue_refresh(&user)?;
+        if linked.is_none() {
+            self.identity_repo
+                .link(&UserIdentity {
+                    id: Uuid::new_v4(),
+                    user_id: user.id,
+                    provider: provider.name().to_string(),
+                    subject: identity.subject,
+                    created_at: Utc::now(),
+                })
+                .await?;
+        }
+
+        let (token, refresh_token) = self.new_refresh_token(user.id, Uuid::new_v4())?;
+        let session = Session {
+            id: token.family_id,
+            user_id: user.id,
+            device: client.device,
+            ip: client.ip,
+            created_at: token.created_at,
+            last_seen_at: token.created_at,
+            expires_at: token.expires_at,
+        };
+        self.session_repo.insert(&session, &token).await?;
+
+        let access_token = self.jwt.issue(&user, session.id)?;
         Ok(AuthResponse {
             access_token,
             refresh_token,
         })
     }
 
-    pub async fn refresh(&self, user_id: Uuid) -> anyhow::Result<AuthResponse> {
+    /// Exchanges the refresh token for new tokens. Each refresh token is
+    /// good once: presenting one that was already exchanged means it leaked
+    /// or was replayed, so its whole session is revoked and the user has to
+    /// sign in again.
+    pub async fn refresh(&self, refresh_token: &str) -> anyhow::Result<AuthResponse> {
+        let current = self
+            .refresh_repo
+            .find(&hash_secret_token(refresh_token))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("invalid or expired refresh token"))?;
+
+        if current.used_at.is_some() {
+            self.revoke_session(&current).await?;
+            anyhow::bail!("refresh token reuse detected");
+        }
+        if current.revoked_at.is_some() || current.expires_at <= Utc::now() {
+            anyhow::bail!("invalid or expired refresh token");
+        }
+
         let user = self
             .user_repo
-            .get(&user_id)
+            .get(&current.user_id)
             .await?
             .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+        ensure_active(&user)?;
+
+        let (next, refresh_token) = self.new_refresh_token(user.id, current.family_id)?;
+        if !self.refresh_repo.rotate(current.id, &next).await? {
+            // Another request exchanged the same token first.
+            self.revoke_session(&current).await?;
+            anyhow::bail!("refresh token reuse detected");
+        }
 
-        let access_token = self.jwt.issue(&user)?;
-        let refresh_token = self.jwt.issue_refresh(&user)?;
+        let access_token = self.jwt.issue(&user, current.family_id)?;
         Ok(AuthResponse {
             access_token,
             refresh_token,
         })
     }
+
+    /// Revokes the session of the refresh token. Unknown tokens are
+    /// ignored.
+    pub async fn logout(&self, refresh_token: &str) -> anyhow::Result<()> {
+        if let Some(token) = self
+            .refresh_repo
+            .find(&hash_secret_token(refresh_token))
+            .await?
+        {
+            self.revoke_session(&token).await?;
+        }
+        Ok(())
+    }
+
+    async fn revoke_session(&self, token: &RefreshToken) -> anyhow::Result<()> {
+        self.session_repo
+            .revoke(token.user_id, token.family_id)
+            .await?;
+        self.revocations.insert(token.family_id);
+        Ok(())
+    }
+
+    fn new_refresh_token(
+        &self,
+        user_id: Uuid,
+        family_id: Uuid,
+    ) -> anyhow::Result<(RefreshToken, String)> {
+        let secret = generate_secret_token();
+        let now = Utc::now();
+        let token = RefreshToken {
+            id: Uuid::new_v4(),
+            family_id,
+            user_id,
+            token_hash: hash_secret_token(&secret),
+            created_at: now,
+            expires_at: now + chrono::Duration::from_std(self.refresh_expiration)?,
+            used_at: None,
+            revoked_at: None,
+        };
+        Ok((token, secret))
+    }
+}
+
+fn ensure_active(user: &User) -> anyhow::Result<()> {
+    if user.disabled_at.is_some() {
+        anyhow::bail!("account disabled");
+    }
+    Ok(())
 }
@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::domain::repositories::MessageHistoryRepository;
+
+pub struct DeleteMessageUseCase {
+    repo: Arc<dyn MessageHistoryRepository>,
+}
+
+impl DeleteMessageUseCase {
+    pub fn new(repo: Arc<dyn MessageHistoryRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Removes the message from the organization's history. Messages still
+    /// on their way are refused, so nothing is sent that nobody can see.
+    pub async fn execute(&self, message_id: Uuid, organization_id: Uuid) -> anyhow::Result<()> {
+        let message = self
+            .repo
+            .get(message_id)
+            .await?
+            .filter(|message| message.deleted_at.is_none())
+            .ok_or_else(|| anyhow::anyhow!("message not found"))?;
+
+        if message.organization_id != organization_id {
+            anyhow::bail!("forbidden: message does not belong to organization");
+        }
+        if !message.status.is_final() {
+            anyhow::bail!("conflict: message is still being delivered");
+        }
+
+        if !self.repo.soft_delete(message_id).await? {
+            anyhow::bail!("message not found");
+        }
+        Ok(())
+    }
+
+    /// Deletes any message for good, deleted or not, with its attempts,
+    /// links and the rest.
+    pub async fn purge(&self, message_id: Uuid) -> anyhow::Result<()> {
+        if !self.repo.purge(message_id).await? {
+            anyhow::bail!("message not found");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,83 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::{
+    application::usecases::manage_sessions::SessionUseCase,
+    domain::{
+        models::AccountErasure,
+        repositories::{AccountErasureRepository, UserRepository},
+    },
+};
+
+/// Most recent erasures listed for admins.
+const LIST_LIMIT: u32 = 200;
+
+pub struct AccountErasureUseCase {
+    user_repo: Arc<dyn UserRepository>,
+    erasure_repo: Arc<dyn AccountErasureRepository>,
+    sessions: Arc<SessionUseCase>,
+    email_key: Option<String>,
+}
+
+impl AccountErasureUseCase {
+    pub fn new(
+        user_repo: Arc<dyn UserRepository>,
+        erasure_repo: Arc<dyn AccountErasureRepository>,
+        sessions: Arc<SessionUseCase>,
+        email_key: Option<String>,
+    ) -> Self {
+        Self {
+            user_repo,
+            erasure_repo,
+            sessions,
+            email_key,
+        }
+    }
+
+    /// Signs the user out, deletes their credentials and leaves the rest
+    /// to [`AccountEraser`](crate::application::services::account_eraser::AccountEraser).
+    /// Asking again while the erasure is under way returns it unchanged.
+    pub async fn request(&self, user_id: Uuid) -> anyhow::Result<AccountErasure> {
+        if let Some(pending) = self.erasure_repo.find_pending(user_id).await? {
+            return Ok(pending);
+        }
+        let user = self
+            .user_repo
+            .get(&user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+
+        let erasure = AccountErasure {
+            id: Uuid::new_v4(),
+            user_id,
+            email_hash: self
+                .email_key
+                .as_deref()
+                .map(|key| hash_email(key, &user.email)),
+            requested_at: Utc::now(),
+            completed_at: None,
+            messages_purged: 0,
+        };
+        self.erasure_repo.start(&erasure).await?;
+        self.sessions.revoke_all(user_id).await?;
+        Ok(erasure)
+    }
+
+    pub async fn list(&self) -> anyhow::Result<Vec<AccountErasure>> {
+        self.erasure_repo.list(LIST_LIMIT).await
+    }
+}
+
+/// Lets an operator holding the key check an address against the audit
+/// records without the records holding it. Keyed, so a list of known
+/// addresses can't be hashed to find whose record it is.
+fn hash_email(key: &str, email: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(email.trim().to_lowercase().as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
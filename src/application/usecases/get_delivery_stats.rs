@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::domain::{models::DeliveryStats, repositories::MessageHistoryRepository};
+
+/// Window used when the caller gives no start.
+const DEFAULT_WINDOW_DAYS: i64 = 30;
+const MAX_WINDOW_DAYS: i64 = 366;
+
+pub struct GetDeliveryStatsUseCase {
+    repo: Arc<dyn MessageHistoryRepository>,
+}
+
+impl GetDeliveryStatsUseCase {
+    pub fn new(repo: Arc<dyn MessageHistoryRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Statistics of the organization's messages created in `[from, to)`. The window
+    /// ends now and spans 30 days unless given.
+    pub async fn execute(
+        &self,
+        organization_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<(DeliveryStats, DateTime<Utc>, DateTime<Utc>)> {
+        let (from, to) = window(from, to)?;
+        let buckets = self.repo.delivery_stats(organization_id, from, to).await?;
+        Ok((DeliveryStats { buckets }, from, to))
+    }
+
+    /// Statistics of the messages `user_id` sent, across organizations, over
+    /// the same window as [`Self::execute`].
+    pub async fn for_sender(
+        &self,
+        user_id: Uuid,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<(DeliveryStats, DateTime<Utc>, DateTime<Utc>)> {
+        let (from, to) = window(from, to)?;
+        let buckets = self.repo.sender_delivery_stats(user_id, from, to).await?;
+        Ok((DeliveryStats { buckets }, from, to))
+    }
+}
+
+fn window(
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> anyhow::Result<(DateTime<Utc>, DateTime<Utc>)> {
+    let to = to.unwrap_or_else(Utc::now);
+    let from = from.unwrap_or(to - Duration::days(DEFAULT_WINDOW_DAYS));
+    if from >= to {
+        anyhow::bail!("invalid window: from must be before to");
+    }
+    if to - from > Duration::days(MAX_WINDOW_DAYS) {
+        anyhow::bail!("invalid window: at most {MAX_WINDOW_DAYS} days");
+    }
+    Ok((from, to))
+}
@@ -13,10 +13,13 @@ impl GetMessageUseCase {
         Self { repo }
     }
 
+    /// The message, if it belongs to the organization `owner`; any message
+    /// when `owner` is `None`, as for admins. Deleted messages are only
+    /// shown to admins.
     pub async fn execute(
         &self,
         message_id: Uuid,
-        user_id: Uuid,
+        owner: Option<Uuid>,
     ) -> anyhow::Result<MessageHistoryEntry> {
         let message = self
             .repo
@@ -24,8 +27,11 @@ impl GetMessageUseCase {
             .await?
             .ok_or_else(|| anyhow::anyhow!("message not found"))?;
 
-        if message.user_id != user_id {
-            anyhow::bail!("forbidden: message does not belong to user");
+        if owner.is_some_and(|owner| owner != message.organization_id) {
+            anyhow::bail!("forbidden: message does not belong to organization");
+        }
+        if owner.is_some() && message.deleted_at.is_some() {
+            anyhow::bail!("message not found");
         }
 
         Ok(message)
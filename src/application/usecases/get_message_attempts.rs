@@ -16,7 +16,7 @@ impl GetMessageAttemptsUseCase {
     pub async fn execute(
         &self,
         message_id: Uuid,
-        user_id: Uuid,
+        owner: Option<Uuid>,
     ) -> anyhow::Result<Vec<MessageAttempt>> {
         // Verify ownership
         let message = self
@@ -25,8 +25,11 @@ impl GetMessageAttemptsUseCase {
             .await?
             .ok_or_else(|| anyhow::anyhow!("message not found"))?;
 
-        if message.user_id != user_id {
-            anyhow::bail!("forbidden: message does not belong to user");
+        if owner.is_some_and(|owner| owner != message.organization_id) {
+            anyhow::bail!("forbidden: message does not belong to organization");
+        }
+        if owner.is_some() && message.deleted_at.is_some() {
+            anyhow::bail!("message not found");
         }
 
         self.repo.get_attempts(message_id).await
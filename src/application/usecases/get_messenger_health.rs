@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+
+use crate::{
+    application::services::{
+        delivery_tracker::MessengerDeliveries, worker_registry::DispatchCounters,
+    },
+    domain::models::DeliveryCounts,
+};
+
+/// Send outcomes per messenger, held against the success rate they should
+/// reach.
+pub struct MessengerHealthReport {
+    /// Share of sends expected to succeed, such as 0.95.
+    pub target: f64,
+    pub messengers: Vec<MessengerDeliveries>,
+}
+
+impl MessengerHealthReport {
+    /// Whether `counts` reach the target; unset while nothing was sent.
+    pub fn meets_target(&self, counts: &DeliveryCounts) -> Option<bool> {
+        counts.success_rate().map(|rate| rate >= self.target)
+    }
+}
+
+/// Reports the sends of this process. Each instance keeps its own windows,
+/// so the metrics are what alerts should be built on across instances.
+pub struct GetMessengerHealthUseCase {
+    counters: Arc<DispatchCounters>,
+    target: f64,
+}
+
+impl GetMessengerHealthUseCase {
+    pub fn new(counters: Arc<DispatchCounters>, target: f64) -> Self {
+        Self { counters, target }
+    }
+
+    pub fn execute(&self) -> MessengerHealthReport {
+        MessengerHealthReport {
+            target: self.target,
+            messengers: self.counters.deliveries().snapshot(Utc::now()),
+        }
+    }
+}
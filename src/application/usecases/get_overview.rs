@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{
+    application::services::queue_stats::QueueMonitor,
+    domain::{
+        models::{AttemptOutcomes, Backlog, TokenHealth},
+        repositories::{MessageHistoryRepository, MessengerTokenRepository},
+    },
+};
+
+/// Window the failure rate is taken over.
+const FAILURE_WINDOW: Duration = Duration::hours(1);
+/// Active tokens expiring within this are reported as expiring.
+const EXPIRY_WARNING: Duration = Duration::days(1);
+
+/// The state of delivery across every organization, at `generated_at`.
+pub struct OperationalOverview {
+    pub generated_at: DateTime<Utc>,
+    /// Messages in the bus not yet handed to a worker; `None` when the bus
+    /// reports no backlog or could not be asked.
+    pub queue_depth: Option<u64>,
+    pub backlog: Backlog,
+    /// Send attempts over the last hour.
+    pub attempts: AttemptOutcomes,
+    pub tokens: Vec<TokenHealth>,
+}
+
+impl OperationalOverview {
+    /// How long the longest-waiting due message has waited.
+    pub fn oldest_unsent_age(&self) -> Option<Duration> {
+        self.backlog
+            .oldest_due_at
+            .map(|due_at| (self.generated_at - due_at).max(Duration::zero()))
+    }
+}
+
+pub struct GetOverviewUseCase {
+    history_repo: Arc<dyn MessageHistoryRepository>,
+    token_repo: Arc<dyn MessengerTokenRepository>,
+    /// `None` when the configured bus exposes no backlog statistics.
+    queue_monitor: Option<Arc<dyn QueueMonitor>>,
+}
+
+impl GetOverviewUseCase {
+    pub fn new(
+        history_repo: Arc<dyn MessageHistoryRepository>,
+        token_repo: Arc<dyn MessengerTokenRepository>,
+        queue_monitor: Option<Arc<dyn QueueMonitor>>,
+    ) -> Self {
+        Self {
+            history_repo,
+            token_repo,
+            queue_monitor,
+        }
+    }
+
+    pub async fn execute(&self) -> anyhow::Result<OperationalOverview> {
+        let now = Utc::now();
+        let queue_depth = async {
+            let monitor = self.queue_monitor.as_ref()?;
+            let stats = monitor.stats().await.ok()?;
+            Some(
+                stats
+                    .consumers
+                    .iter()
+                    .map(|consumer| consumer.pending)
+                    .sum(),
+            )
+        };
+        let (queue_depth, backlog, attempts, tokens) = tokio::join!(
+            queue_depth,
+            self.history_repo.backlog(now),
+            self.history_repo.attempt_outcomes(now - FAILURE_WINDOW),
+            self.token_repo.health(now, now + EXPIRY_WARNING),
+        );
+        Ok(OperationalOverview {
+            generated_at: now,
+            queue_depth,
+            backlog: backlog?,
+            attempts: attempts?,
+            tokens: tokens?,
+        })
+    }
+}
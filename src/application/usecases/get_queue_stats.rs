@@ -0,0 +1,21 @@
+use std::sync::Arc;
+
+use crate::application::services::queue_stats::{QueueMonitor, QueueStats};
+
+pub struct GetQueueStatsUseCase {
+    /// `None` when the configured bus exposes no backlog statistics.
+    monitor: Option<Arc<dyn QueueMonitor>>,
+}
+
+impl GetQueueStatsUseCase {
+    pub fn new(monitor: Option<Arc<dyn QueueMonitor>>) -> Self {
+        Self { monitor }
+    }
+
+    pub async fn execute(&self) -> anyhow::Result<QueueStats> {
+        match &self.monitor {
+            Some(monitor) => monitor.stats().await,
+            None => anyhow::bail!("queue stats not available for this message bus"),
+        }
+    }
+}
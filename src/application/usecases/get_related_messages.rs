@@ -0,0 +1,79 @@
+use std::{collections::BTreeSet, sync::Arc};
+
+use uuid::Uuid;
+
+use crate::domain::{
+    models::{MessageHistoryEntry, MessageLink},
+    repositories::{MessageHistoryRepository, MessageLinkRepository},
+};
+
+const MAX_LINKS: u32 = 200;
+
+pub struct GetRelatedMessagesUseCase {
+    history_repo: Arc<dyn MessageHistoryRepository>,
+    link_repo: Arc<dyn MessageLinkRepository>,
+}
+
+pub struct RelatedMessages {
+    pub messages: Vec<MessageHistoryEntry>,
+    pub links: Vec<MessageLink>,
+}
+
+impl GetRelatedMessagesUseCase {
+    pub fn new(
+        history_repo: Arc<dyn MessageHistoryRepository>,
+        link_repo: Arc<dyn MessageLinkRepository>,
+    ) -> Self {
+        Self {
+            history_repo,
+            link_repo,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        message_id: Uuid,
+        owner: Option<Uuid>,
+    ) -> anyhow::Result<RelatedMessages> {
+        let message = self
+            .history_repo
+            .get(message_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("message not found"))?;
+
+        if owner.is_some_and(|owner| owner != message.organization_id) {
+            anyhow::bail!("forbidden: message does not belong to organization");
+        }
+        if owner.is_some() && message.deleted_at.is_some() {
+            anyhow::bail!("message not found");
+        }
+
+        let links = self.link_repo.connected(message_id, MAX_LINKS).await?;
+        let ids: BTreeSet<Uuid> = links
+            .iter()
+            .flat_map(|link| [link.source_id, link.target_id])
+            .filter(|id| *id != message_id)
+            .collect();
+
+        let organization_id = message.organization_id;
+        let mut messages = vec![message];
+        for id in ids {
+            if let Some(entry) = self.history_repo.get(id).await?
+                && entry.organization_id == organization_id
+                && (owner.is_none() || entry.deleted_at.is_none())
+            {
+                messages.push(entry);
+            }
+        }
+
+        // Links pointing at another organization's message are not part of the
+        // graph.
+        let visible: BTreeSet<Uuid> = messages.iter().map(|entry| entry.id).collect();
+        let links = links
+            .into_iter()
+            .filter(|link| visible.contains(&link.source_id) && visible.contains(&link.target_id))
+            .collect();
+
+        Ok(RelatedMessages { messages, links })
+    }
+}
@@ -0,0 +1,108 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    application::usecases::schedule_message::{
+        ScheduleMessageRequest, ScheduleMessageResponse, ScheduleMessageUseCase,
+    },
+    domain::{
+        models::{MessageClass, RequestedBy, RoutingInput, TokenSelector},
+        repositories::RoutingRuleRepository,
+    },
+};
+
+pub struct IngestEventUseCase {
+    routing_repo: Arc<dyn RoutingRuleRepository>,
+    schedule: Arc<ScheduleMessageUseCase>,
+}
+
+pub struct IngestEventRequest {
+    pub user_id: Uuid,
+    pub name: String,
+    pub class: MessageClass,
+    pub tags: Vec<String>,
+    /// The event payload, flattened to strings for matching and templates.
+    pub metadata: BTreeMap<String, String>,
+    pub deadline: Option<DateTime<Utc>>,
+    /// Replays with the same key return the messages of the first call.
+    pub idempotency_key: Option<String>,
+}
+
+/// A rule that matched the event and what came of it.
+pub struct FiredRule {
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub result: anyhow::Result<ScheduleMessageResponse>,
+}
+
+impl IngestEventUseCase {
+    pub fn new(
+        routing_repo: Arc<dyn RoutingRuleRepository>,
+        schedule: Arc<ScheduleMessageUseCase>,
+    ) -> Self {
+        Self {
+            routing_repo,
+            schedule,
+        }
+    }
+
+    /// Schedules one message for every enabled rule that matches the event,
+    /// in rule order. An event no rule matches schedules nothing.
+    pub async fn execute(&self, request: IngestEventRequest) -> anyhow::Result<Vec<FiredRule>> {
+        let input = RoutingInput {
+            event: Some(request.name.clone()),
+            class: request.class,
+            tags: request.tags,
+            metadata: request.metadata,
+            text: String::new(),
+        };
+        let rules = self.routing_repo.list_by_user(request.user_id).await?;
+
+        let mut fired = Vec::new();
+        let mut payloads = Vec::new();
+        for rule in rules.iter().filter(|rule| rule.matches(&input)) {
+            let routed = match rule.apply(&input) {
+                Ok(routed) => routed,
+                Err(err) => {
+                    fired.push((rule, Some(Err(err))));
+                    continue;
+                }
+            };
+            fired.push((rule, None));
+            payloads.push(ScheduleMessageRequest {
+                // Routing rules are the user's own, so their personal
+                // organization sends what they route.
+                organization_id: request.user_id,
+                user_id: request.user_id,
+                messenger: routed.messenger,
+                recipient: routed.recipient,
+                text: routed.text,
+                class: request.class,
+                priority: routed.priority.unwrap_or_default(),
+                requested_by: RequestedBy::System,
+                deadline: request.deadline,
+                thread_parent: None,
+                idempotency_key: request
+                    .idempotency_key
+                    .as_ref()
+                    .map(|key| format!("{key}:{}", rule.id)),
+                token: TokenSelector::Latest,
+                tags: input.tags.clone(),
+            });
+        }
+
+        let mut scheduled = self.schedule.execute_batch(payloads).await.into_iter();
+        Ok(fired
+            .into_iter()
+            .map(|(rule, result)| FiredRule {
+                rule_id: rule.id,
+                rule_name: rule.name.clone(),
+                result: result
+                    .or_else(|| scheduled.next())
+                    .unwrap_or_else(|| Err(anyhow::anyhow!("message was not scheduled"))),
+            })
+            .collect())
+    }
+}
@@ -4,7 +4,10 @@ use uuid::Uuid;
 
 use crate::{
     application::services::messenger::{MessengerGateway, PaginatedChats, PaginationParams},
-    domain::{models::MessengerType, repositories::MessengerTokenRepository},
+    domain::{
+        models::{MessengerType, TokenSelector},
+        repositories::MessengerTokenRepository,
+    },
 };
 
 pub struct ListChatsUseCase {
@@ -22,13 +25,19 @@ impl ListChatsUseCase {
 
     pub async fn execute(
         &self,
+        organization_id: Uuid,
         user_id: Uuid,
         messenger: MessengerType,
         pagination: PaginationParams,
     ) -> anyhow::Result<PaginatedChats> {
         let token = self
             .token_repo
-            .find_active(&user_id, messenger)
+            .find_active(
+                &organization_id,
+                &user_id,
+                messenger,
+                &TokenSelector::Latest,
+            )
             .await?
             .ok_or_else(|| anyhow::anyhow!("no active token for messenger"))?;
 
@@ -1,8 +1,15 @@
 use std::sync::Arc;
 
+use tokio::sync::mpsc::{self, Receiver};
 use uuid::Uuid;
 
-use crate::domain::{models::MessageHistoryEntry, repositories::MessageHistoryRepository};
+use crate::domain::{
+    models::{MessageHistoryEntry, MessageHistoryFilter, MessageStatusKind, MessengerType},
+    repositories::MessageHistoryRepository,
+};
+
+/// Rows read ahead of a slow client.
+const EXPORT_BUFFER: usize = 256;
 
 pub struct ListMessagesUseCase {
     repo: Arc<dyn MessageHistoryRepository>,
@@ -21,11 +28,17 @@ impl ListMessagesUseCase {
 
     pub async fn execute(
         &self,
-        user_id: Uuid,
+        organization_id: Uuid,
+        filter: MessageHistoryFilter,
         limit: Option<u32>,
         offset: Option<u32>,
     ) -> anyhow::Result<PaginatedMessages> {
-        let (messages, has_more) = self.repo.list_by_user(user_id, limit, offset).await?;
+        validate_filter(&filter)?;
+
+        let (messages, has_more) = self
+            .repo
+            .list_by_organization(organization_id, &filter, limit, offset)
+            .await?;
         let current_offset = offset.unwrap_or(0);
         let next_offset = if has_more {
             Some(current_offset + messages.len() as u32)
@@ -39,4 +52,76 @@ impl ListMessagesUseCase {
             next_offset,
         })
     }
+
+    /// Every message matching `filter`, newest first, delivered as the
+    /// database produces them. A failure part way ends the stream with an
+    /// error.
+    pub fn export(
+        &self,
+        organization_id: Uuid,
+        filter: MessageHistoryFilter,
+    ) -> anyhow::Result<Receiver<anyhow::Result<MessageHistoryEntry>>> {
+        validate_filter(&filter)?;
+
+        let (sender, receiver) = mpsc::channel(EXPORT_BUFFER);
+        let repo = self.repo.clone();
+        tokio::spawn(async move {
+            if let Err(err) = repo.export(organization_id, &filter, sender.clone()).await {
+                let _ = sender.send(Err(err)).await;
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    /// What was sent to one chat, newest first.
+    pub async fn by_recipient(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<PaginatedMessages> {
+        let (messages, has_more) = self
+            .repo
+            .list_by_recipient(organization_id, messenger, recipient, limit, offset)
+            .await?;
+        let next_offset = has_more.then(|| offset.unwrap_or(0) + messages.len() as u32);
+
+        Ok(PaginatedMessages {
+            messages,
+            has_more,
+            next_offset,
+        })
+    }
+
+    /// Messages of the organization per status, every status included.
+    pub async fn count_by_status(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<(MessageStatusKind, u64)>> {
+        let counts = self.repo.count_by_status(organization_id).await?;
+        Ok(MessageStatusKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let count = counts
+                    .iter()
+                    .find(|(counted, _)| *counted == kind)
+                    .map_or(0, |(_, count)| *count);
+                (kind, count)
+            })
+            .collect())
+    }
+}
+
+fn validate_filter(filter: &MessageHistoryFilter) -> anyhow::Result<()> {
+    if filter
+        .created_after
+        .zip(filter.created_before)
+        .is_some_and(|(after, before)| after >= before)
+    {
+        anyhow::bail!("created_after must be earlier than created_before");
+    }
+    Ok(())
 }
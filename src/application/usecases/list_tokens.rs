@@ -13,7 +13,16 @@ impl ListTokensUseCase {
         Self { repo }
     }
 
-    pub async fn execute(&self, user_id: Uuid) -> anyhow::Result<Vec<MessengerToken>> {
-        self.repo.list_by_user(&user_id).await
+    /// The organization's tokens `user_id` may send with.
+    pub async fn execute(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> anyhow::Result<Vec<MessengerToken>> {
+        let tokens = self.repo.list_by_organization(&organization_id).await?;
+        Ok(tokens
+            .into_iter()
+            .filter(|token| token.shared || token.user_id == user_id)
+            .collect())
     }
 }
@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use crate::domain::{models::Worker, repositories::WorkerRepository};
+
+pub struct ListWorkersUseCase {
+    repo: Arc<dyn WorkerRepository>,
+    /// Heartbeat silence after which a worker is reported dead.
+    ttl: Duration,
+}
+
+impl ListWorkersUseCase {
+    pub fn new(repo: Arc<dyn WorkerRepository>, ttl: Duration) -> Self {
+        Self { repo, ttl }
+    }
+
+    /// Registered workers, most recent heartbeat first, each with whether
+    /// it is still alive.
+    pub async fn execute(&self) -> anyhow::Result<Vec<(Worker, bool)>> {
+        let now = Utc::now();
+        let workers = self.repo.list().await?;
+        Ok(workers
+            .into_iter()
+            .map(|worker| {
+                let alive = worker.is_alive(now, self.ttl);
+                (worker, alive)
+            })
+            .collect())
+    }
+}
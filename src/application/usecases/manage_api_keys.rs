@@ -0,0 +1,272 @@
+use std::{net::IpAddr, sync::Arc};
+
+use chrono::{Duration, Utc};
+use ipnet::IpNet;
+use uuid::Uuid;
+
+use crate::{
+    application::services::secret_token::{generate_secret_token, hash_secret_token},
+    domain::{
+        models::{ApiKey, ApiKeyScope, User},
+        repositories::{ApiKeyRepository, UserRepository},
+    },
+};
+
+const KEY_PREFIX: &str = "msk_";
+/// Characters of the key kept in clear, `KEY_PREFIX` included.
+const SHOWN_PREFIX_LENGTH: usize = 12;
+/// `last_used_at` is only rewritten once this much has passed, so a busy key
+/// does not cost a write per request.
+const LAST_USED_RESOLUTION: Duration = Duration::minutes(1);
+
+/// A key was used from an address outside its allowlist.
+#[derive(Debug)]
+pub struct ApiKeyNotAllowed;
+
+impl std::fmt::Display for ApiKeyNotAllowed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("api key not allowed from this address")
+    }
+}
+
+impl std::error::Error for ApiKeyNotAllowed {}
+
+/// A key as returned when it is created; `secret` is not kept anywhere.
+pub struct IssuedApiKey {
+    pub key: ApiKey,
+    pub secret: String,
+}
+
+pub struct ApiKeyUseCase {
+    repo: Arc<dyn ApiKeyRepository>,
+    user_repo: Arc<dyn UserRepository>,
+}
+
+impl ApiKeyUseCase {
+    pub fn new(repo: Arc<dyn ApiKeyRepository>, user_repo: Arc<dyn UserRepository>) -> Self {
+        Self { repo, user_repo }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> anyhow::Result<Vec<ApiKey>> {
+        self.repo.list_by_user(user_id).await
+    }
+
+    pub async fn issue(
+        &self,
+        user_id: Uuid,
+        name: &str,
+        scopes: Vec<ApiKeyScope>,
+        allowed_ips: &[String],
+    ) -> anyhow::Result<IssuedApiKey> {
+        if scopes.is_empty() {
+            anyhow::bail!("invalid scopes: a key needs at least one");
+        }
+        let allowed_ips = parse_networks(allowed_ips)?;
+        let issued = new_key(user_id, name.trim(), scopes, allowed_ips);
+        self.repo.insert(&issued.key).await?;
+        Ok(issued)
+    }
+
+    /// Replaces the key with a new one of the same name, scopes and
+    /// allowlist. The old key stops working at once.
+    pub async fn rotate(&self, user_id: Uuid, key_id: Uuid) -> anyhow::Result<IssuedApiKey> {
+        let current = self
+            .repo
+            .list_by_user(user_id)
+            .await?
+            .into_iter()
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| anyhow::anyhow!("api key not found"))?;
+
+        let issued = new_key(user_id, &current.name, current.scopes, current.allowed_ips);
+        if !self.repo.rotate(user_id, key_id, &issued.key).await? {
+            anyhow::bail!("api key not found");
+        }
+        Ok(issued)
+    }
+
+    pub async fn revoke(&self, user_id: Uuid, key_id: Uuid) -> anyhow::Result<()> {
+        if !self.repo.revoke(user_id, key_id).await? {
+            anyhow::bail!("api key not found");
+        }
+        Ok(())
+    }
+
+    /// The user the key acts for with the key's scopes, or `None` for an
+    /// unknown or revoked key. Fails for a key used from outside its
+    /// allowlist.
+    pub async fn authenticate(
+        &self,
+        secret: &str,
+        client_ip: Option<IpAddr>,
+    ) -> anyhow::Result<Option<(User, Vec<ApiKeyScope>)>> {
+        if !secret.starts_with(KEY_PREFIX) {
+            return Ok(None);
+        }
+        let Some(key) = self.repo.find_active(&hash_secret_token(secret)).await? else {
+            return Ok(None);
+        };
+        if !key.allows(client_ip) {
+            return Err(ApiKeyNotAllowed.into());
+        }
+
+        let now = Utc::now();
+        if key
+            .last_used_at
+            .is_none_or(|used_at| now - used_at >= LAST_USED_RESOLUTION)
+        {
+            self.repo.touch(key.id, now).await?;
+        }
+        Ok(self
+            .user_repo
+            .get(&key.user_id)
+            .await?
+            .filter(|user| user.disabled_at.is_none())
+            .map(|user| (user, key.scopes)))
+    }
+}
+
+fn new_key(
+    user_id: Uuid,
+    name: &str,
+    scopes: Vec<ApiKeyScope>,
+    allowed_ips: Vec<IpNet>,
+) -> IssuedApiKey {
+    let secret = format!("{KEY_PREFIX}{}", generate_secret_token());
+    IssuedApiKey {
+        key: ApiKey {
+            id: Uuid::new_v4(),
+            user_id,
+            name: name.to_string(),
+            prefix: secret[..SHOWN_PREFIX_LENGTH].to_string(),
+            key_hash: hash_secret_token(&secret),
+            created_at: Utc::now(),
+            last_used_at: None,
+            // In a fixed order, without repeats.
+            scopes: ApiKeyScope::ALL
+                .into_iter()
+                .filter(|scope| scopes.contains(scope))
+                .collect(),
+            allowed_ips,
+        },
+        secret,
+    }
+}
+
+/// CIDRs such as `10.0.0.0/8`; a bare address stands for itself alone.
+fn parse_networks(networks: &[String]) -> anyhow::Result<Vec<IpNet>> {
+    let mut parsed: Vec<IpNet> = Vec::new();
+    for network in networks {
+        let network = network.trim();
+        let net = match network.parse::<IpNet>() {
+            Ok(net) => net.trunc(),
+            Err(_) => network
+                .parse::<IpAddr>()
+                .map(IpNet::from)
+                .map_err(|_| anyhow::anyhow!("invalid allowed_ips: {network} is not a CIDR"))?,
+        };
+        if !parsed.contains(&net) {
+            parsed.push(net);
+        }
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn networks(values: &[&str]) -> anyhow::Result<Vec<IpNet>> {
+        parse_networks(&values.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+    }
+
+    fn key_for(allowed_ips: &[&str]) -> ApiKey {
+        new_key(
+            Uuid::new_v4(),
+            "ci",
+            vec![ApiKeyScope::MessagesSend],
+            networks(allowed_ips).unwrap(),
+        )
+        .key
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn networks_are_truncated_and_deduplicated() {
+        let parsed =
+            networks(&[" 10.1.2.3/8", "10.0.0.0/8", "192.0.2.1", "2001:db8::1/32"]).unwrap();
+        assert_eq!(
+            parsed,
+            vec![
+                "10.0.0.0/8".parse::<IpNet>().unwrap(),
+                "192.0.2.1/32".parse().unwrap(),
+                "2001:db8::/32".parse().unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_networks_are_rejected() {
+        for value in ["10.0.0.0/33", "example.com", ""] {
+            assert!(networks(&[value]).is_err(), "{value} was accepted");
+        }
+    }
+
+    #[test]
+    fn allowlist_accepts_addresses_inside_it() {
+        let key = key_for(&["10.0.0.0/8", "2001:db8::/32"]);
+        assert!(key.allows(ip("10.200.0.1")));
+        assert!(key.allows(ip("2001:db8::42")));
+    }
+
+    #[test]
+    fn allowlist_rejects_addresses_outside_it_and_unknown_ones() {
+        let key = key_for(&["10.0.0.0/8", "192.0.2.1"]);
+        assert!(!key.allows(ip("11.0.0.1")));
+        assert!(!key.allows(ip("192.0.2.2")));
+        assert!(!key.allows(None));
+    }
+
+    #[test]
+    fn empty_allowlist_accepts_any_address() {
+        let key = key_for(&[]);
+        assert!(key.allows(ip("203.0.113.9")));
+        assert!(key.allows(None));
+    }
+
+    #[test]
+    fn scopes_are_kept_in_order_without_repeats() {
+        let issued = new_key(
+            Uuid::new_v4(),
+            "ci",
+            vec![
+                ApiKeyScope::TokensManage,
+                ApiKeyScope::MessagesSend,
+                ApiKeyScope::TokensManage,
+            ],
+            Vec::new(),
+        );
+        assert_eq!(
+            issued.key.scopes,
+            vec![ApiKeyScope::MessagesSend, ApiKeyScope::TokensManage]
+        );
+        assert!(!issued.key.scopes.contains(&ApiKeyScope::MessagesRead));
+    }
+
+    #[test]
+    fn issued_secret_matches_stored_hash_and_prefix() {
+        let issued = new_key(
+            Uuid::new_v4(),
+            "ci",
+            vec![ApiKeyScope::MessagesRead],
+            Vec::new(),
+        );
+        assert!(issued.secret.starts_with(KEY_PREFIX));
+        assert!(issued.secret.starts_with(&issued.key.prefix));
+        assert_eq!(issued.key.prefix.len(), SHOWN_PREFIX_LENGTH);
+        assert_eq!(issued.key.key_hash, hash_secret_token(&issued.secret));
+    }
+}
@@ -0,0 +1,212 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        services::otp::{generate_code, hash_code},
+        usecases::schedule_message::{ScheduleMessageRequest, ScheduleMessageUseCase},
+    },
+    domain::{
+        models::{
+            ConsentRecord, ConsentStatus, MessageClass, MessagePriority, MessengerType,
+            RequestedBy, TokenSelector,
+        },
+        repositories::ConsentRepository,
+    },
+};
+
+pub struct ConsentConfig {
+    pub confirmation_template: String,
+}
+
+pub struct ConsentUseCase {
+    repo: Arc<dyn ConsentRepository>,
+    schedule: Arc<ScheduleMessageUseCase>,
+    config: ConsentConfig,
+}
+
+pub struct RecordConsentRequest {
+    pub organization_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub source: String,
+    pub proof: Option<String>,
+}
+
+pub struct RequestOptInRequest {
+    pub organization_id: Uuid,
+    /// Who asks; the confirmation is sent on their behalf.
+    pub user_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub source: String,
+}
+
+pub struct ConfirmOptInRequest {
+    pub organization_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub reply: String,
+}
+
+impl ConsentUseCase {
+    pub fn new(
+        repo: Arc<dyn ConsentRepository>,
+        schedule: Arc<ScheduleMessageUseCase>,
+        config: ConsentConfig,
+    ) -> Self {
+        Self {
+            repo,
+            schedule,
+            config,
+        }
+    }
+
+    pub async fn list(&self, organization_id: Uuid) -> anyhow::Result<Vec<ConsentRecord>> {
+        self.repo.list_by_organization(organization_id).await
+    }
+
+    /// Records consent collected elsewhere (signup form, paper, CRM).
+    pub async fn record(&self, request: RecordConsentRequest) -> anyhow::Result<ConsentRecord> {
+        let mut record = self
+            .existing_or_new(
+                request.organization_id,
+                request.messenger,
+                &request.recipient,
+            )
+            .await?;
+        record.status = ConsentStatus::Confirmed;
+        record.source = request.source;
+        record.proof = request.proof;
+        record.confirmation_hash = None;
+        record.updated_at = Utc::now();
+
+        self.repo.upsert(&record).await?;
+        Ok(record)
+    }
+
+    /// Sends a confirmation code to the recipient; consent stays pending until
+    /// their reply is confirmed.
+    pub async fn request_opt_in(
+        &self,
+        request: RequestOptInRequest,
+    ) -> anyhow::Result<ConsentRecord> {
+        let mut record = self
+            .existing_or_new(
+                request.organization_id,
+                request.messenger,
+                &request.recipient,
+            )
+            .await?;
+        if record.status == ConsentStatus::Confirmed {
+            return Ok(record);
+        }
+
+        let code = generate_code(6);
+        self.schedule
+            .execute(ScheduleMessageRequest {
+                organization_id: request.organization_id,
+                user_id: request.user_id,
+                messenger: request.messenger,
+                recipient: request.recipient.clone(),
+                text: self.config.confirmation_template.replace("{code}", &code),
+                class: MessageClass::Transactional,
+                priority: MessagePriority::Normal,
+                requested_by: RequestedBy::System,
+                deadline: None,
+                thread_parent: None,
+                idempotency_key: None,
+                token: TokenSelector::Latest,
+                tags: Vec::new(),
+            })
+            .await?;
+
+        record.status = ConsentStatus::Pending;
+        record.source = request.source;
+        record.proof = None;
+        record.confirmation_hash = Some(hash_code(&record.id, &code));
+        record.updated_at = Utc::now();
+
+        self.repo.upsert(&record).await?;
+        Ok(record)
+    }
+
+    /// Checks the recipient's reply against the pending code and stores the
+    /// reply itself as proof of consent.
+    pub async fn confirm(&self, request: ConfirmOptInRequest) -> anyhow::Result<ConsentRecord> {
+        let mut record = self
+            .repo
+            .find(
+                request.organization_id,
+                request.messenger,
+                &request.recipient,
+            )
+            .await?
+            .filter(|record| record.status == ConsentStatus::Pending)
+            .ok_or_else(|| anyhow::anyhow!("pending opt-in not found"))?;
+
+        let reply = request.reply.trim();
+        if record.confirmation_hash.as_deref() != Some(hash_code(&record.id, reply).as_str()) {
+            anyhow::bail!("confirmation reply does not match");
+        }
+
+        record.status = ConsentStatus::Confirmed;
+        record.proof = Some(format!("reply: {reply}"));
+        record.confirmation_hash = None;
+        record.updated_at = Utc::now();
+
+        self.repo.upsert(&record).await?;
+        Ok(record)
+    }
+
+    pub async fn revoke(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<ConsentRecord> {
+        let mut record = self
+            .repo
+            .find(organization_id, messenger, recipient)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("consent not found"))?;
+
+        record.status = ConsentStatus::Revoked;
+        record.confirmation_hash = None;
+        record.updated_at = Utc::now();
+
+        self.repo.upsert(&record).await?;
+        Ok(record)
+    }
+
+    async fn existing_or_new(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<ConsentRecord> {
+        if let Some(existing) = self
+            .repo
+            .find(organization_id, messenger, recipient)
+            .await?
+        {
+            return Ok(existing);
+        }
+
+        let now = Utc::now();
+        Ok(ConsentRecord {
+            id: Uuid::new_v4(),
+            organization_id,
+            messenger,
+            recipient: recipient.to_string(),
+            status: ConsentStatus::Pending,
+            source: String::new(),
+            proof: None,
+            confirmation_hash: None,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+}
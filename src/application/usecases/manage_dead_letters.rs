@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::services::{
+        dead_letter::{DeadLetter, DeadLetterQueue},
+        event_bus::MessageBus,
+    },
+    domain::{models::MessageStatus, repositories::MessageHistoryRepository},
+};
+
+pub struct DeadLetterConfig {
+    pub max_attempts: u32,
+}
+
+pub struct DeadLetterUseCase {
+    /// `None` when the configured bus keeps no inspectable dead-letter queue.
+    queue: Option<Arc<dyn DeadLetterQueue>>,
+    history_repo: Arc<dyn MessageHistoryRepository>,
+    bus: Arc<dyn MessageBus>,
+    config: DeadLetterConfig,
+}
+
+impl DeadLetterUseCase {
+    pub fn new(
+        queue: Option<Arc<dyn DeadLetterQueue>>,
+        history_repo: Arc<dyn MessageHistoryRepository>,
+        bus: Arc<dyn MessageBus>,
+        config: DeadLetterConfig,
+    ) -> Self {
+        Self {
+            queue,
+            history_repo,
+            bus,
+            config,
+        }
+    }
+
+    pub async fn list(&self, organization_id: Uuid, limit: u32) -> anyhow::Result<Vec<DeadLetter>> {
+        self.queue()?.list(organization_id, limit).await
+    }
+
+    /// Puts the event back on the bus with a fresh attempt budget and drops it
+    /// from the dead-letter queue.
+    pub async fn redrive(&self, organization_id: Uuid, id: u64) -> anyhow::Result<Uuid> {
+        let queue = self.queue()?;
+        let dead_letter = queue
+            .get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("dead letter not found"))?;
+
+        let mut event = dead_letter.entry.event;
+        if event.organization() != organization_id {
+            anyhow::bail!("forbidden: dead letter does not belong to organization");
+        }
+        if event.deadline_exceeded(Utc::now()) {
+            anyhow::bail!("message deadline has passed");
+        }
+
+        event.event_id = Uuid::new_v4();
+        event.attempt += 1;
+        event.max_attempts = event.attempt + self.config.max_attempts.saturating_sub(1);
+        event.scheduled_at = Utc::now();
+        let message_id = event.message_id;
+
+        self.history_repo
+            .update_status(message_id, MessageStatus::Scheduled, event.attempt)
+            .await?;
+        self.bus.publish(event).await?;
+        queue.remove(id).await?;
+
+        Ok(message_id)
+    }
+
+    fn queue(&self) -> anyhow::Result<&Arc<dyn DeadLetterQueue>> {
+        self.queue
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("dead-letter queue not available for this message bus"))
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::services::auth_provider::{AuthProvider, LoginCredentials, select_provider},
+    domain::{
+        models::UserIdentity,
+        repositories::{IdentityRepository, UserRepository},
+    },
+};
+
+pub struct IdentityUseCase {
+    user_repo: Arc<dyn UserRepository>,
+    identity_repo: Arc<dyn IdentityRepository>,
+    providers: Vec<Arc<dyn AuthProvider>>,
+}
+
+impl IdentityUseCase {
+    pub fn new(
+        user_repo: Arc<dyn UserRepository>,
+        identity_repo: Arc<dyn IdentityRepository>,
+        providers: Vec<Arc<dyn AuthProvider>>,
+    ) -> Self {
+        Self {
+            user_repo,
+            identity_repo,
+            providers,
+        }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> anyhow::Result<Vec<UserIdentity>> {
+        self.identity_repo.list_by_user(user_id).await
+    }
+
+    /// Links the identity the credentials prove to the user's account, so
+    /// signing in with it reaches the same tokens and history. Identities
+    /// that already lead to another account, directly or through its
+    /// email, are refused rather than moved.
+    pub async fn link(
+        &self,
+        user_id: Uuid,
+        credentials: LoginCredentials,
+    ) -> anyhow::Result<UserIdentity> {
+        let provider = select_provider(&self.providers, &credentials)?;
+        let verified = provider.authenticate(&credentials).await?;
+
+        if let Some(existing) = self
+            .identity_repo
+            .find(provider.name(), &verified.subject)
+            .await?
+        {
+            if existing.user_id == user_id {
+                return Ok(existing);
+            }
+            anyhow::bail!("identity already linked to another account");
+        }
+        let owner = self.user_repo.find_by_email(&verified.email).await?;
+        if owner.is_some_and(|owner| owner.id != user_id) {
+            anyhow::bail!("identity already linked to another account");
+        }
+
+        let identity = UserIdentity {
+            id: Uuid::new_v4(),
+            user_id,
+            provider: provider.name().to_string(),
+            subject: verified.subject,
+            created_at: Utc::now(),
+        };
+        if !self.identity_repo.link(&identity).await? {
+            anyhow::bail!("identity already linked to another account");
+        }
+        Ok(identity)
+    }
+
+    pub async fn unlink(&self, user_id: Uuid, identity_id: Uuid) -> anyhow::Result<()> {
+        let identities = self.identity_repo.list_by_user(user_id).await?;
+        if !identities.iter().any(|identity| identity.id == identity_id) {
+            anyhow::bail!("identity not found");
+        }
+        if !self.identity_repo.unlink(user_id, identity_id).await? {
+            anyhow::bail!("cannot unlink the last sign-in method");
+        }
+        Ok(())
+    }
+}
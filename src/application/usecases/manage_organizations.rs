@@ -0,0 +1,145 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::services::secret_token::{generate_secret_token, hash_secret_token},
+    domain::{
+        models::{MemberRole, Organization, OrganizationInvitation, OrganizationMember},
+        repositories::OrganizationRepository,
+    },
+};
+
+/// An invitation as returned when it is created; `token` is not kept
+/// anywhere.
+pub struct IssuedInvitation {
+    pub invitation: OrganizationInvitation,
+    pub token: String,
+}
+
+pub struct OrganizationUseCase {
+    repo: Arc<dyn OrganizationRepository>,
+    invitation_ttl: Duration,
+}
+
+impl OrganizationUseCase {
+    pub fn new(repo: Arc<dyn OrganizationRepository>, invitation_ttl: Duration) -> Self {
+        Self {
+            repo,
+            invitation_ttl,
+        }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> anyhow::Result<Vec<(Organization, MemberRole)>> {
+        self.repo.list_for_user(user_id).await
+    }
+
+    pub async fn create(&self, user_id: Uuid, name: &str) -> anyhow::Result<Organization> {
+        let now = Utc::now();
+        let organization = Organization {
+            id: Uuid::new_v4(),
+            name: name.trim().to_string(),
+            created_at: now,
+        };
+        self.repo
+            .create(
+                &organization,
+                &OrganizationMember {
+                    organization_id: organization.id,
+                    user_id,
+                    role: MemberRole::Owner,
+                    created_at: now,
+                },
+            )
+            .await?;
+        Ok(organization)
+    }
+
+    pub async fn is_member(&self, organization_id: Uuid, user_id: Uuid) -> anyhow::Result<bool> {
+        Ok(self
+            .repo
+            .find_member(organization_id, user_id)
+            .await?
+            .is_some())
+    }
+
+    pub async fn members(&self, organization_id: Uuid) -> anyhow::Result<Vec<OrganizationMember>> {
+        self.repo.list_members(organization_id).await
+    }
+
+    pub async fn invitations(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<OrganizationInvitation>> {
+        self.repo.list_invitations(organization_id).await
+    }
+
+    /// Only owners may invite. The caller hands the token to the invitee
+    /// out of band.
+    pub async fn invite(
+        &self,
+        organization_id: Uuid,
+        invited_by: Uuid,
+        email: &str,
+        role: MemberRole,
+    ) -> anyhow::Result<IssuedInvitation> {
+        let inviter = self.repo.find_member(organization_id, invited_by).await?;
+        if !inviter.is_some_and(|inviter| inviter.role == MemberRole::Owner) {
+            anyhow::bail!("forbidden: only owners may invite");
+        }
+
+        let token = generate_secret_token();
+        let now = Utc::now();
+        let invitation = OrganizationInvitation {
+            id: Uuid::new_v4(),
+            organization_id,
+            email: email.trim().to_string(),
+            role,
+            token_hash: hash_secret_token(&token),
+            invited_by,
+            created_at: now,
+            expires_at: now + chrono::Duration::from_std(self.invitation_ttl)?,
+            accepted_at: None,
+        };
+        self.repo.insert_invitation(&invitation).await?;
+        Ok(IssuedInvitation { invitation, token })
+    }
+
+    /// Joins the organization the invitation is for with the role it
+    /// grants. Only the user the invitation was addressed to may accept it.
+    pub async fn accept(
+        &self,
+        user_id: Uuid,
+        email: &str,
+        token: &str,
+    ) -> anyhow::Result<(Organization, MemberRole)> {
+        let invitation = self
+            .repo
+            .find_invitation(&hash_secret_token(token.trim()))
+            .await?
+            .filter(|invitation| {
+                invitation.accepted_at.is_none() && invitation.expires_at > Utc::now()
+            })
+            .ok_or_else(|| anyhow::anyhow!("invalid or expired invitation"))?;
+        if !invitation.email.eq_ignore_ascii_case(email) {
+            anyhow::bail!("forbidden: invitation is for another email");
+        }
+
+        let member = OrganizationMember {
+            organization_id: invitation.organization_id,
+            user_id,
+            role: invitation.role,
+            created_at: Utc::now(),
+        };
+        if !self.repo.accept_invitation(invitation.id, &member).await? {
+            anyhow::bail!("invalid or expired invitation");
+        }
+        let organization = self
+            .repo
+            .get(invitation.organization_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("organization not found"))?;
+        Ok((organization, invitation.role))
+    }
+}
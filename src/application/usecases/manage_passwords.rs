@@ -0,0 +1,235 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        services::{
+            mailer::Mailer,
+            password::{hash_password, validate_password, verify_password},
+            secret_token::{generate_secret_token, hash_secret_token},
+        },
+        usecases::manage_sessions::SessionUseCase,
+    },
+    domain::{
+        models::{User, UserRole},
+        repositories::{PasswordRepository, UserRepository},
+    },
+};
+
+pub struct PasswordConfig {
+    pub enabled: bool,
+    /// How long reset and confirmation tokens stay good.
+    pub reset_ttl: Duration,
+    /// Link mailed for resets, with `{token}` standing for the token.
+    pub reset_url: Option<String>,
+    /// Link mailed to confirm the email of a new account, with `{token}`
+    /// standing for the token.
+    pub confirm_url: Option<String>,
+}
+
+pub struct PasswordUseCase {
+    user_repo: Arc<dyn UserRepository>,
+    password_repo: Arc<dyn PasswordRepository>,
+    sessions: Arc<SessionUseCase>,
+    mailer: Option<Arc<dyn Mailer>>,
+    config: PasswordConfig,
+}
+
+pub struct PasswordReset {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl PasswordUseCase {
+    pub fn new(
+        user_repo: Arc<dyn UserRepository>,
+        password_repo: Arc<dyn PasswordRepository>,
+        sessions: Arc<SessionUseCase>,
+        mailer: Option<Arc<dyn Mailer>>,
+        config: PasswordConfig,
+    ) -> Self {
+        Self {
+            user_repo,
+            password_repo,
+            sessions,
+            mailer,
+            config,
+        }
+    }
+
+    /// The account starts with its email unconfirmed; when mail is set up
+    /// the user is sent a token to confirm it with.
+    pub async fn register(
+        &self,
+        email: &str,
+        password: &str,
+        display_name: Option<String>,
+    ) -> anyhow::Result<()> {
+        self.ensure_enabled()?;
+        validate_password(password)?;
+        if self.user_repo.find_by_email(email).await?.is_some() {
+            anyhow::bail!("email already registered");
+        }
+
+        let user = User {
+            id: Uuid::new_v4(),
+            email: email.to_string(),
+            display_name,
+            role: UserRole::Member,
+            disabled_at: None,
+            email_verified_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        self.user_repo.upsert(&user).await?;
+        self.password_repo
+            .upsert(user.id, &hash_password(password)?)
+            .await?;
+
+        let Some(mailer) = &self.mailer else {
+            return Ok(());
+        };
+        let confirmation = self.create_reset(user.id).await?;
+        let link = match &self.config.confirm_url {
+            Some(url) => url.replace("{token}", &confirmation.token),
+            None => confirmation.token,
+        };
+        mailer
+            .send(
+                &user.email,
+                "Confirm your email",
+                &format!(
+                    "Confirm this is your email with\n\n{link}\n\nIt is valid until {}. If \
+                     you didn't sign up, ignore this mail.\n",
+                    confirmation.expires_at.to_rfc3339()
+                ),
+            )
+            .await
+    }
+
+    /// Confirms the account's email with a token mailed on registration.
+    /// Tokens mailed for resets prove the same and are accepted too.
+    pub async fn confirm(&self, token: &str) -> anyhow::Result<()> {
+        self.ensure_enabled()?;
+        let user_id = self
+            .password_repo
+            .consume_reset(&hash_secret_token(token.trim()))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("invalid or expired confirmation token"))?;
+        self.mark_email_verified(user_id).await
+    }
+
+    /// Signs every session of the user out, the calling one included.
+    pub async fn change(
+        &self,
+        user_id: Uuid,
+        current_password: &str,
+        new_password: &str,
+    ) -> anyhow::Result<()> {
+        self.ensure_enabled()?;
+        let credential = self
+            .password_repo
+            .find(user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("invalid credentials"))?;
+        if !verify_password(current_password, &credential.password_hash)? {
+            anyhow::bail!("invalid credentials");
+        }
+        validate_password(new_password)?;
+        self.password_repo
+            .upsert(user_id, &hash_password(new_password)?)
+            .await?;
+        self.sessions.revoke_all(user_id).await
+    }
+
+    /// Issues a single-use reset token for the caller to hand to the user
+    /// out of band; only its hash is stored.
+    pub async fn issue_reset(&self, email: &str) -> anyhow::Result<PasswordReset> {
+        self.ensure_enabled()?;
+        let user = self
+            .user_repo
+            .find_by_email(email)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+        self.create_reset(user.id).await
+    }
+
+    /// Mails a reset token to the user. Unknown emails are accepted alike,
+    /// so the answer doesn't tell which addresses have an account.
+    pub async fn request_reset(&self, email: &str) -> anyhow::Result<()> {
+        self.ensure_enabled()?;
+        let mailer = self
+            .mailer
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("password reset by email not available"))?;
+        let Some(user) = self.user_repo.find_by_email(email).await? else {
+            return Ok(());
+        };
+
+        let reset = self.create_reset(user.id).await?;
+        let link = match &self.config.reset_url {
+            Some(url) => url.replace("{token}", &reset.token),
+            None => reset.token,
+        };
+        mailer
+            .send(
+                &user.email,
+                "Reset your password",
+                &format!(
+                    "Set a new password with\n\n{link}\n\nIt is valid until {}. If you \
+                     didn't ask for it, ignore this mail.\n",
+                    reset.expires_at.to_rfc3339()
+                ),
+            )
+            .await
+    }
+
+    /// Redeeming the token proves the email too. Every session of the user
+    /// is signed out, since whoever held the old password may have one.
+    pub async fn reset(&self, token: &str, new_password: &str) -> anyhow::Result<()> {
+        self.ensure_enabled()?;
+        validate_password(new_password)?;
+        let user_id = self
+            .password_repo
+            .consume_reset(&hash_secret_token(token.trim()))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("invalid or expired reset token"))?;
+        self.password_repo
+            .upsert(user_id, &hash_password(new_password)?)
+            .await?;
+        self.mark_email_verified(user_id).await?;
+        self.sessions.revoke_all(user_id).await
+    }
+
+    async fn create_reset(&self, user_id: Uuid) -> anyhow::Result<PasswordReset> {
+        let token = generate_secret_token();
+        let expires_at = Utc::now() + chrono::Duration::from_std(self.config.reset_ttl)?;
+        self.password_repo
+            .create_reset(&hash_secret_token(&token), user_id, expires_at)
+            .await?;
+        Ok(PasswordReset { token, expires_at })
+    }
+
+    async fn mark_email_verified(&self, user_id: Uuid) -> anyhow::Result<()> {
+        let mut user = self
+            .user_repo
+            .get(&user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+        if user.email_verified_at.is_none() {
+            user.email_verified_at = Some(Utc::now());
+            user.updated_at = Utc::now();
+            self.user_repo.upsert(&user).await?;
+        }
+        Ok(())
+    }
+
+    fn ensure_enabled(&self) -> anyhow::Result<()> {
+        if !self.config.enabled {
+            anyhow::bail!("password authentication not available");
+        }
+        Ok(())
+    }
+}
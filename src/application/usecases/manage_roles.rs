@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::usecases::manage_sessions::SessionUseCase,
+    domain::{
+        models::{User, UserRole},
+        repositories::UserRepository,
+    },
+};
+
+pub struct RoleUseCase {
+    user_repo: Arc<dyn UserRepository>,
+    sessions: Arc<SessionUseCase>,
+}
+
+impl RoleUseCase {
+    pub fn new(user_repo: Arc<dyn UserRepository>, sessions: Arc<SessionUseCase>) -> Self {
+        Self {
+            user_repo,
+            sessions,
+        }
+    }
+
+    /// Access tokens carry the role, so a changed role signs the user out
+    /// everywhere; API keys pick it up at once.
+    pub async fn assign(&self, user_id: Uuid, role: UserRole) -> anyhow::Result<User> {
+        let mut user = self
+            .user_repo
+            .get(&user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("user not found"))?;
+        if user.role == role {
+            return Ok(user);
+        }
+        user.role = role;
+        user.updated_at = Utc::now();
+        self.user_repo.upsert(&user).await?;
+        self.sessions.revoke_all(user_id).await?;
+        Ok(user)
+    }
+}
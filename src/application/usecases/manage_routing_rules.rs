@@ -0,0 +1,202 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::domain::{
+    models::{
+        MessageClass, MessagePriority, MessengerType, RoutedMessage, RoutingInput, RoutingRule,
+        RoutingRuleDiff, RoutingRuleVersion,
+    },
+    repositories::RoutingRuleRepository,
+};
+
+pub struct RoutingRuleUseCase {
+    repo: Arc<dyn RoutingRuleRepository>,
+}
+
+/// Fields of a rule set on create and replaced on update.
+pub struct RoutingRuleDraft {
+    pub name: String,
+    /// Appended after the user's last rule when unset.
+    pub position: Option<i32>,
+    pub enabled: bool,
+    pub match_event: Option<String>,
+    pub match_class: Option<MessageClass>,
+    pub match_tags: Vec<String>,
+    pub match_metadata: BTreeMap<String, String>,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub template: Option<String>,
+    pub priority: Option<MessagePriority>,
+}
+
+/// A rule that matches the simulated input.
+pub struct SimulatedRule {
+    pub rule: RoutingRule,
+    /// Whether the rule would deliver. A send only takes the first match;
+    /// an event takes them all.
+    pub selected: bool,
+    pub result: anyhow::Result<RoutedMessage>,
+}
+
+impl RoutingRuleUseCase {
+    pub fn new(repo: Arc<dyn RoutingRuleRepository>) -> Self {
+        Self { repo }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> anyhow::Result<Vec<RoutingRule>> {
+        self.repo.list_by_user(user_id).await
+    }
+
+    pub async fn create(
+        &self,
+        user_id: Uuid,
+        draft: RoutingRuleDraft,
+    ) -> anyhow::Result<RoutingRule> {
+        let position = match draft.position {
+            Some(position) => position,
+            None => self
+                .repo
+                .list_by_user(user_id)
+                .await?
+                .iter()
+                .map(|rule| rule.position + 1)
+                .max()
+                .unwrap_or(0),
+        };
+        let now = Utc::now();
+        let rule = RoutingRule {
+            id: Uuid::new_v4(),
+            user_id,
+            name: draft.name,
+            position,
+            enabled: draft.enabled,
+            match_event: draft.match_event,
+            match_class: draft.match_class,
+            match_tags: draft.match_tags,
+            match_metadata: draft.match_metadata,
+            messenger: draft.messenger,
+            recipient: draft.recipient,
+            template: draft.template,
+            priority: draft.priority,
+            created_at: now,
+            updated_at: now,
+        };
+        self.repo.upsert(&rule).await?;
+        self.repo
+            .snapshot(user_id, &format!("created rule {}", rule.name))
+            .await?;
+        Ok(rule)
+    }
+
+    pub async fn update(
+        &self,
+        user_id: Uuid,
+        rule_id: Uuid,
+        draft: RoutingRuleDraft,
+    ) -> anyhow::Result<RoutingRule> {
+        let existing = self
+            .repo
+            .get(user_id, rule_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("routing rule not found"))?;
+        let rule = RoutingRule {
+            name: draft.name,
+            position: draft.position.unwrap_or(existing.position),
+            enabled: draft.enabled,
+            match_event: draft.match_event,
+            match_class: draft.match_class,
+            match_tags: draft.match_tags,
+            match_metadata: draft.match_metadata,
+            messenger: draft.messenger,
+            recipient: draft.recipient,
+            template: draft.template,
+            priority: draft.priority,
+            updated_at: Utc::now(),
+            ..existing
+        };
+        self.repo.upsert(&rule).await?;
+        self.repo
+            .snapshot(user_id, &format!("updated rule {}", rule.name))
+            .await?;
+        Ok(rule)
+    }
+
+    /// Evaluates the rules as a send or event would, without scheduling
+    /// anything.
+    pub async fn simulate(
+        &self,
+        user_id: Uuid,
+        input: &RoutingInput,
+    ) -> anyhow::Result<Vec<SimulatedRule>> {
+        let rules = self.repo.list_by_user(user_id).await?;
+        Ok(rules
+            .into_iter()
+            .filter(|rule| rule.matches(input))
+            .enumerate()
+            .map(|(index, rule)| SimulatedRule {
+                selected: input.event.is_some() || index == 0,
+                result: rule.apply(input),
+                rule,
+            })
+            .collect())
+    }
+
+    pub async fn delete(&self, user_id: Uuid, rule_id: Uuid) -> anyhow::Result<()> {
+        let rule = self
+            .repo
+            .get(user_id, rule_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("routing rule not found"))?;
+        if !self.repo.delete(user_id, rule_id).await? {
+            anyhow::bail!("routing rule not found");
+        }
+        self.repo
+            .snapshot(user_id, &format!("deleted rule {}", rule.name))
+            .await?;
+        Ok(())
+    }
+
+    /// Every recorded version, newest first, and the active one.
+    pub async fn list_versions(
+        &self,
+        user_id: Uuid,
+    ) -> anyhow::Result<(Vec<RoutingRuleVersion>, Option<i32>)> {
+        let versions = self.repo.list_versions(user_id).await?;
+        let active = self.repo.active_version(user_id).await?;
+        Ok((versions, active))
+    }
+
+    /// Changes from `against` to `version`. Compares with the version
+    /// before it when `against` is unset; version 1 is compared with an
+    /// empty rule set.
+    pub async fn diff(
+        &self,
+        user_id: Uuid,
+        version: i32,
+        against: Option<i32>,
+    ) -> anyhow::Result<RoutingRuleDiff> {
+        let target = self.version(user_id, version).await?;
+        let base = match against.or(Some(version - 1)).filter(|base| *base > 0) {
+            Some(base) => self.version(user_id, base).await?.rules,
+            None => Vec::new(),
+        };
+        Ok(RoutingRuleDiff::between(&base, &target.rules))
+    }
+
+    /// Rolls the rules back, or forward, to `version`.
+    pub async fn activate(&self, user_id: Uuid, version: i32) -> anyhow::Result<()> {
+        if !self.repo.activate(user_id, version).await? {
+            anyhow::bail!("routing rule version not found");
+        }
+        Ok(())
+    }
+
+    async fn version(&self, user_id: Uuid, version: i32) -> anyhow::Result<RoutingRuleVersion> {
+        self.repo
+            .get_version(user_id, version)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("routing rule version not found"))
+    }
+}
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    application::services::session_revocations::SessionRevocations,
+    domain::{models::Session, repositories::SessionRepository},
+};
+
+pub struct SessionUseCase {
+    repo: Arc<dyn SessionRepository>,
+    revocations: Arc<SessionRevocations>,
+}
+
+impl SessionUseCase {
+    pub fn new(repo: Arc<dyn SessionRepository>, revocations: Arc<SessionRevocations>) -> Self {
+        Self { repo, revocations }
+    }
+
+    pub async fn list(&self, user_id: Uuid) -> anyhow::Result<Vec<Session>> {
+        self.repo.list_active(user_id).await
+    }
+
+    /// Signs the session out: its refresh token stops working and so do
+    /// the access tokens issued for it.
+    pub async fn revoke(&self, user_id: Uuid, session_id: Uuid) -> anyhow::Result<()> {
+        if !self.repo.revoke(user_id, session_id).await? {
+            anyhow::bail!("session not found");
+        }
+        self.revocations.insert(session_id);
+        Ok(())
+    }
+
+    /// Signs the user out everywhere, the calling session included.
+    pub async fn revoke_all(&self, user_id: Uuid) -> anyhow::Result<()> {
+        self.repo.revoke_all(user_id).await?;
+        self.revocations.reload().await
+    }
+}
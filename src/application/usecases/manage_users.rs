@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::usecases::manage_sessions::SessionUseCase,
+    domain::{models::User, repositories::UserRepository},
+};
+
+pub struct UserAdminUseCase {
+    user_repo: Arc<dyn UserRepository>,
+    sessions: Arc<SessionUseCase>,
+}
+
+pub struct PaginatedUsers {
+    pub users: Vec<User>,
+    pub has_more: bool,
+    pub next_offset: Option<u32>,
+}
+
+impl UserAdminUseCase {
+    pub fn new(user_repo: Arc<dyn UserRepository>, sessions: Arc<SessionUseCase>) -> Self {
+        Self {
+            user_repo,
+            sessions,
+        }
+    }
+
+    pub async fn list(
+        &self,
+        search: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<PaginatedUsers> {
+        let (users, has_more) = self.user_repo.list(search, limit, offset).await?;
+        let next_offset = has_more.then(|| offset.unwrap_or(0) + users.len() as u32);
+        Ok(PaginatedUsers {
+            users,
+            has_more,
+            next_offset,
+        })
+    }
+
+    pub async fn get(&self, user_id: Uuid) -> anyhow::Result<User> {
+        self.user_repo
+            .get(&user_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("user not found"))
+    }
+
+    /// A disabled user is signed out everywhere and can neither sign in
+    /// again nor use their API keys until enabled.
+    pub async fn set_disabled(&self, user_id: Uuid, disabled: bool) -> anyhow::Result<User> {
+        let mut user = self.get(user_id).await?;
+        if user.disabled_at.is_some() != disabled {
+            user.disabled_at = disabled.then(Utc::now);
+            user.updated_at = Utc::now();
+            self.user_repo.upsert(&user).await?;
+        }
+        if disabled {
+            self.sessions.revoke_all(user_id).await?;
+        }
+        Ok(user)
+    }
+
+    /// Signs the user out everywhere; their API keys keep working.
+    pub async fn reset_sessions(&self, user_id: Uuid) -> anyhow::Result<()> {
+        self.get(user_id).await?;
+        self.sessions.revoke_all(user_id).await
+    }
+}
@@ -0,0 +1,132 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    application::services::status_token::StatusTokenService,
+    domain::{
+        models::{MessageStatus, MessengerType},
+        repositories::MessageHistoryRepository,
+    },
+};
+
+/// How much a status link reveals to whoever holds it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusExposure {
+    /// Only whether the message was delivered.
+    Minimal,
+    /// Also the exact status, the messenger and when it last changed.
+    Detailed,
+}
+
+impl StatusExposure {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "minimal" => Ok(StatusExposure::Minimal),
+            "detailed" => Ok(StatusExposure::Detailed),
+            other => anyhow::bail!("unknown status exposure {other}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryState {
+    Pending,
+    Delivered,
+    NotDelivered,
+}
+
+pub struct StatusLink {
+    pub token: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+pub struct PublicMessageStatus {
+    pub state: DeliveryState,
+    /// The fields below are only filled with detailed exposure.
+    pub status: Option<MessageStatus>,
+    pub messenger: Option<MessengerType>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+pub struct MessageStatusLinkUseCase {
+    history_repo: Arc<dyn MessageHistoryRepository>,
+    tokens: Arc<StatusTokenService>,
+    exposure: StatusExposure,
+}
+
+impl MessageStatusLinkUseCase {
+    pub fn new(
+        history_repo: Arc<dyn MessageHistoryRepository>,
+        tokens: Arc<StatusTokenService>,
+        exposure: StatusExposure,
+    ) -> Self {
+        Self {
+            history_repo,
+            tokens,
+            exposure,
+        }
+    }
+
+    pub async fn issue(
+        &self,
+        organization_id: Uuid,
+        message_id: Uuid,
+    ) -> anyhow::Result<StatusLink> {
+        let message = self
+            .history_repo
+            .get(message_id)
+            .await?
+            .filter(|message| message.deleted_at.is_none())
+            .ok_or_else(|| anyhow::anyhow!("message not found"))?;
+        if message.organization_id != organization_id {
+            anyhow::bail!("forbidden: message does not belong to organization");
+        }
+
+        let (token, expires_at) = self.tokens.issue(message_id)?;
+        Ok(StatusLink {
+            token,
+            expires_at: DateTime::from_timestamp(expires_at as i64, 0)
+                .ok_or_else(|| anyhow::anyhow!("invalid status link expiry"))?,
+        })
+    }
+
+    /// Invalid, expired and dangling tokens all read as "not found" so the
+    /// endpoint reveals nothing about which messages exist.
+    pub async fn lookup(&self, token: &str) -> anyhow::Result<PublicMessageStatus> {
+        let message_id = self
+            .tokens
+            .verify(token)
+            .map_err(|_| anyhow::anyhow!("status not found"))?;
+        let message = self
+            .history_repo
+            .get(message_id)
+            .await?
+            .filter(|message| message.deleted_at.is_none())
+            .ok_or_else(|| anyhow::anyhow!("status not found"))?;
+
+        let state = match message.status {
+            MessageStatus::Sent => DeliveryState::Delivered,
+            MessageStatus::Failed { .. }
+            | MessageStatus::Cancelled
+            | MessageStatus::Suppressed { .. } => DeliveryState::NotDelivered,
+            _ => DeliveryState::Pending,
+        };
+
+        Ok(match self.exposure {
+            StatusExposure::Minimal => PublicMessageStatus {
+                state,
+                status: None,
+                messenger: None,
+                updated_at: None,
+            },
+            StatusExposure::Detailed => PublicMessageStatus {
+                state,
+                status: Some(message.status),
+                messenger: Some(message.messenger),
+                updated_at: Some(message.updated_at),
+            },
+        })
+    }
+}
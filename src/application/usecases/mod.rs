@@ -1,9 +1,36 @@
 pub mod authenticate_user;
+pub mod delete_message;
+pub mod erase_account;
+pub mod get_delivery_stats;
 pub mod get_message;
 pub mod get_message_attempts;
+pub mod get_messenger_health;
+pub mod get_overview;
+pub mod get_queue_stats;
+pub mod get_related_messages;
+pub mod ingest_event;
 pub mod list_chats;
 pub mod list_messages;
 pub mod list_tokens;
+pub mod list_workers;
+pub mod manage_api_keys;
+pub mod manage_consent;
+pub mod manage_dead_letters;
+pub mod manage_identities;
+pub mod manage_organizations;
+pub mod manage_passwords;
+pub mod manage_roles;
+pub mod manage_routing_rules;
+pub mod manage_sessions;
+pub mod manage_users;
+pub mod message_status_link;
+pub mod preview_message;
 pub mod register_token;
 pub mod retry_message;
 pub mod schedule_message;
+pub mod send_chat_action;
+pub mod send_magic_link;
+pub mod send_otp;
+pub mod share_token;
+pub mod trace_replay;
+pub mod verify_otp;
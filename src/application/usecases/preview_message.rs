@@ -0,0 +1,49 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    application::services::{
+        content_score::{ContentScore, ContentScorer},
+        message_length::{MessageLength, measure},
+    },
+    domain::models::MessengerType,
+};
+
+pub struct PreviewMessageUseCase {
+    scorer: Arc<ContentScorer>,
+    part_prices: HashMap<MessengerType, f64>,
+}
+
+pub struct PreviewMessageRequest {
+    pub messenger: MessengerType,
+    pub text: String,
+}
+
+pub struct MessagePreview {
+    pub spam: ContentScore,
+    pub length: MessageLength,
+    /// `None` when no price is configured for the messenger.
+    pub estimated_cost: Option<f64>,
+}
+
+impl PreviewMessageUseCase {
+    pub fn new(scorer: Arc<ContentScorer>, part_prices: HashMap<MessengerType, f64>) -> Self {
+        Self {
+            scorer,
+            part_prices,
+        }
+    }
+
+    /// Evaluates the text without scheduling anything.
+    pub fn execute(&self, request: PreviewMessageRequest) -> MessagePreview {
+        let length = measure(request.messenger, &request.text);
+        let estimated_cost = self
+            .part_prices
+            .get(&request.messenger)
+            .map(|price| price * length.parts as f64);
+        MessagePreview {
+            spam: self.scorer.evaluate(request.messenger, &request.text),
+            length,
+            estimated_cost,
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
 use crate::domain::{
@@ -13,10 +13,16 @@ pub struct RegisterTokenUseCase {
 }
 
 pub struct RegisterTokenRequest {
+    pub organization_id: Uuid,
     pub user_id: Uuid,
     pub messenger: MessengerType,
+    pub label: Option<String>,
     pub access_token: String,
     pub refresh_token: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub device_id: Option<String>,
+    /// Kept as it was when replacing a token unless given.
+    pub shared: Option<bool>,
 }
 
 impl RegisterTokenUseCase {
@@ -25,23 +31,35 @@ impl RegisterTokenUseCase {
     }
 
     pub async fn execute(&self, request: RegisterTokenRequest) -> anyhow::Result<MessengerToken> {
-        let existing_tokens = self.repo.list_by_user(&request.user_id).await?;
-        let existing_token = existing_tokens
-            .into_iter()
-            .find(|t| t.messenger == request.messenger);
+        let existing_tokens = self
+            .repo
+            .list_by_organization(&request.organization_id)
+            .await?;
+        // Only the member's own token is replaced; another member's with the
+        // same label stays theirs.
+        let existing_token = existing_tokens.into_iter().find(|t| {
+            t.user_id == request.user_id
+                && t.messenger == request.messenger
+                && t.label == request.label
+        });
 
-        let (id, created_at) = if let Some(existing) = existing_token {
-            (existing.id, existing.created_at)
+        let (id, created_at, shared) = if let Some(existing) = existing_token {
+            (existing.id, existing.created_at, existing.shared)
         } else {
-            (Uuid::new_v4(), Utc::now())
+            (Uuid::new_v4(), Utc::now(), false)
         };
 
         let token = MessengerToken {
             id,
+            organization_id: request.organization_id,
             user_id: request.user_id,
+            shared: request.shared.unwrap_or(shared),
             messenger: request.messenger,
+            label: request.label,
             access_token: request.access_token,
             refresh_token: request.refresh_token,
+            expires_at: request.expires_at,
+            device_id: request.device_id,
             status: MessengerTokenStatus::Active,
             created_at,
             updated_at: Utc::now(),
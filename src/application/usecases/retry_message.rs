@@ -4,16 +4,19 @@ use chrono::Utc;
 use uuid::Uuid;
 
 use crate::{
-    application::services::event_bus::MessageBus,
+    application::services::{event_bus::MessageBus, telemetry},
     domain::{
         events::OutboundMessageEvent,
-        models::MessageStatus,
+        models::{
+            FailedMessageFilter, MessageHistoryEntry, MessageStatus, RetryBackoff, TokenSelector,
+        },
         repositories::{MessageHistoryRepository, MessengerTokenRepository},
     },
 };
 
 pub struct RetryMessageConfig {
     pub max_attempts: u32,
+    pub retry_backoff: RetryBackoff,
 }
 
 pub struct RetryMessageUseCase {
@@ -24,10 +27,17 @@ pub struct RetryMessageUseCase {
 }
 
 pub struct RetryMessageRequest {
-    pub user_id: Uuid,
+    pub organization_id: Uuid,
     pub message_id: Uuid,
 }
 
+pub struct RequeueFailedResult {
+    pub requeued: Vec<Uuid>,
+    /// Matching messages left failed because their deadline passed or the
+    /// organization has no active token for the messenger.
+    pub skipped: Vec<Uuid>,
+}
+
 impl RetryMessageUseCase {
     pub fn new(
         history_repo: Arc<dyn MessageHistoryRepository>,
@@ -50,35 +60,101 @@ impl RetryMessageUseCase {
             .await?
             .ok_or_else(|| anyhow::anyhow!("message not found"))?;
 
-        if message.user_id != request.user_id {
-            anyhow::bail!("message does not belong to user");
+        if message.organization_id != request.organization_id {
+            anyhow::bail!("message does not belong to organization");
+        }
+
+        if message
+            .deadline
+            .is_some_and(|deadline| deadline <= Utc::now())
+        {
+            anyhow::bail!("message deadline has passed");
         }
 
         let token = self
             .token_repo
-            .find_active(&message.user_id, message.messenger)
+            .find_active(
+                &message.organization_id,
+                &message.sender(),
+                message.messenger,
+                &TokenSelector::from_token_id(message.token_id),
+            )
             .await?;
         if token.is_none() {
             anyhow::bail!("no active token for messenger");
         }
 
+        self.requeue(&message).await
+    }
+
+    /// Requeues up to `limit` failed messages of any user, e.g. after an
+    /// outage. Messages that could not be retried one by one are skipped.
+    pub async fn requeue_failed(
+        &self,
+        filter: &FailedMessageFilter,
+        limit: Option<u32>,
+    ) -> anyhow::Result<RequeueFailedResult> {
+        let limit = limit.unwrap_or(500).min(1000);
+        let messages = self.history_repo.list_failed(filter, limit).await?;
+
+        let mut result = RequeueFailedResult {
+            requeued: Vec::new(),
+            skipped: Vec::new(),
+        };
+        let now = Utc::now();
+        for message in messages {
+            let expired = message.deadline.is_some_and(|deadline| deadline <= now);
+            let token = self
+                .token_repo
+                .find_active(
+                    &message.organization_id,
+                    &message.sender(),
+                    message.messenger,
+                    &TokenSelector::from_token_id(message.token_id),
+                )
+                .await?;
+            if expired || token.is_none() {
+                result.skipped.push(message.id);
+                continue;
+            }
+
+            self.requeue(&message).await.map_err(|err| {
+                anyhow::anyhow!(
+                    "failed to requeue message {} after requeueing {}: {err}",
+                    message.id,
+                    result.requeued.len()
+                )
+            })?;
+            result.requeued.push(message.id);
+        }
+        Ok(result)
+    }
+
+    async fn requeue(&self, message: &MessageHistoryEntry) -> anyhow::Result<()> {
         let next_attempt = message.attempts + 1;
 
         self.history_repo
-            .update_status(request.message_id, MessageStatus::Scheduled, next_attempt)
+            .update_status(message.id, MessageStatus::Scheduled, next_attempt)
             .await?;
 
         let event = OutboundMessageEvent {
             event_id: Uuid::new_v4(),
-            message_id: request.message_id,
-            user_id: message.user_id,
+            message_id: message.id,
+            user_id: message.sender(),
+            organization_id: Some(message.organization_id),
             messenger: message.messenger,
             recipient: message.recipient.clone(),
             message_type: message.content.message_type.clone(),
             content: message.content.clone(),
+            class: message.class,
+            priority: message.priority,
             attempt: next_attempt,
             max_attempts: self.config.max_attempts,
+            retry_backoff: self.config.retry_backoff,
             scheduled_at: Utc::now(),
+            deadline: message.deadline,
+            token_id: message.token_id,
+            trace_context: telemetry::current(),
         };
 
         self.bus.publish(event).await?;
@@ -1,51 +1,119 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use opentelemetry::{
+    Context, KeyValue,
+    trace::{FutureExt, SpanKind},
+};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use crate::{
-    application::services::event_bus::MessageBus,
+    application::{
+        handlers::message_dispatcher::MessageDispatchHandler,
+        services::{
+            event_bus::MessageBus,
+            frequency_cap::{CapDecision, FrequencyCap, FrequencyCapMode},
+            telemetry,
+        },
+    },
     domain::{
         events::OutboundMessageEvent,
-        models::{MessageContent, MessageStatus, MessageType, MessengerType, RequestedBy},
-        repositories::{MessageHistoryRepository, MessengerTokenRepository},
+        models::{
+            ConsentStatus, MessageClass, MessageContent, MessageLink, MessageLinkKind,
+            MessagePriority, MessageStatus, MessageType, MessengerType, NewMessageHistoryEntry,
+            RequestedBy, RetryBackoff, RoutedMessage, RoutingInput, TokenSelector,
+        },
+        repositories::{
+            ConsentRepository, IdempotencyKeyRepository, MessageHistoryRepository,
+            MessageLinkRepository, MessengerTokenRepository, RoutingRuleRepository,
+        },
     },
 };
 
 pub struct ScheduleMessageConfig {
     pub max_attempts: u32,
+    pub frequency_caps: Vec<FrequencyCap>,
+    pub frequency_cap_mode: FrequencyCapMode,
+    pub retry_backoff: RetryBackoff,
+    /// How long a synchronous send may hold the request before the message
+    /// falls back to the queue.
+    pub sync_timeout: Duration,
 }
 
 pub struct ScheduleMessageUseCase {
     token_repo: Arc<dyn MessengerTokenRepository>,
     history_repo: Arc<dyn MessageHistoryRepository>,
+    consent_repo: Arc<dyn ConsentRepository>,
+    link_repo: Arc<dyn MessageLinkRepository>,
+    idempotency_repo: Arc<dyn IdempotencyKeyRepository>,
+    routing_repo: Arc<dyn RoutingRuleRepository>,
     bus: Arc<dyn MessageBus>,
+    dispatcher: Arc<MessageDispatchHandler>,
     config: ScheduleMessageConfig,
 }
 
+/// Where tokens, messages and what decides how they are sent are kept.
+pub struct ScheduleMessageRepositories {
+    pub tokens: Arc<dyn MessengerTokenRepository>,
+    pub history: Arc<dyn MessageHistoryRepository>,
+    pub consents: Arc<dyn ConsentRepository>,
+    pub links: Arc<dyn MessageLinkRepository>,
+    pub idempotency_keys: Arc<dyn IdempotencyKeyRepository>,
+    pub routing_rules: Arc<dyn RoutingRuleRepository>,
+}
+
 pub struct ScheduleMessageRequest {
+    /// Whose tokens deliver the message and whose history it joins.
+    pub organization_id: Uuid,
     pub user_id: Uuid,
     pub messenger: MessengerType,
     pub recipient: String,
     pub text: String,
+    pub class: MessageClass,
+    pub priority: MessagePriority,
     pub requested_by: RequestedBy,
+    pub deadline: Option<DateTime<Utc>>,
+    /// Earlier message of the same organization this one continues.
+    pub thread_parent: Option<Uuid>,
+    /// Replays of a request with the same key return the message created
+    /// by the first one instead of scheduling another.
+    pub idempotency_key: Option<String>,
+    /// Which of the organization's tokens for the messenger delivers the
+    /// message.
+    pub token: TokenSelector,
+    /// Stored with the message so history can be filtered by them.
+    pub tags: Vec<String>,
 }
 
 pub struct ScheduleMessageResponse {
     pub message_id: Uuid,
 }
 
+struct BatchItem {
+    organization_id: Uuid,
+    /// Key claimed for this item, settled once the batch is published.
+    claimed_key: Option<String>,
+    result: anyhow::Result<ScheduleMessageResponse>,
+    queued: bool,
+}
+
 impl ScheduleMessageUseCase {
     pub fn new(
-        token_repo: Arc<dyn MessengerTokenRepository>,
-        history_repo: Arc<dyn MessageHistoryRepository>,
+        repositories: ScheduleMessageRepositories,
         bus: Arc<dyn MessageBus>,
+        dispatcher: Arc<MessageDispatchHandler>,
         config: ScheduleMessageConfig,
     ) -> Self {
         Self {
-            token_repo,
-            history_repo,
+            token_repo: repositories.tokens,
+            history_repo: repositories.history,
+            consent_repo: repositories.consents,
+            link_repo: repositories.links,
+            idempotency_repo: repositories.idempotency_keys,
+            routing_repo: repositories.routing_rules,
             bus,
+            dispatcher,
             config,
         }
     }
@@ -54,7 +122,277 @@ impl ScheduleMessageUseCase {
         &self,
         request: ScheduleMessageRequest,
     ) -> anyhow::Result<ScheduleMessageResponse> {
-        self.ensure_token_exists(&request).await?;
+        if let Some(response) = self.replay(&request).await? {
+            return Ok(response);
+        }
+
+        let (organization_id, key) = (request.organization_id, request.idempotency_key.clone());
+        let result = match self.prepare(request).await {
+            Ok((response, Some(event))) => {
+                let when = event.scheduled_at;
+                self.bus.publish_at(event, when).await.map(|_| response)
+            }
+            Ok((response, None)) => Ok(response),
+            Err(err) => Err(err),
+        };
+        self.settle(organization_id, key, result).await
+    }
+
+    /// Like `execute`, but sends the message within the call instead of
+    /// queueing it. Also returns whether it went out; when it did not in
+    /// time, or failed, it is queued as usual.
+    pub async fn execute_sync(
+        &self,
+        request: ScheduleMessageRequest,
+    ) -> anyhow::Result<(ScheduleMessageResponse, bool)> {
+        if let Some(response) = self.replay(&request).await? {
+            let sent = self
+                .history_repo
+                .get(response.message_id)
+                .await?
+                .is_some_and(|entry| matches!(entry.status, MessageStatus::Sent));
+            return Ok((response, sent));
+        }
+
+        let (organization_id, key) = (request.organization_id, request.idempotency_key.clone());
+        let mut sent = false;
+        let result = match self.prepare(request).await {
+            // Messages deferred by a frequency cap wait in the queue.
+            Ok((response, Some(event))) if event.scheduled_at <= Utc::now() => {
+                match self.dispatch_inline(event).await {
+                    Ok(dispatched) => {
+                        sent = dispatched;
+                        Ok(response)
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            Ok((response, Some(event))) => {
+                let when = event.scheduled_at;
+                self.bus.publish_at(event, when).await.map(|_| response)
+            }
+            Ok((response, None)) => Ok(response),
+            Err(err) => Err(err),
+        };
+        self.settle(organization_id, key, result)
+            .await
+            .map(|response| (response, sent))
+    }
+
+    /// Picks the destination of a send that names none: the first of the
+    /// user's routing rules that matches decides messenger, recipient and
+    /// text.
+    pub async fn route(
+        &self,
+        user_id: Uuid,
+        input: &RoutingInput,
+    ) -> anyhow::Result<RoutedMessage> {
+        let rules = self.routing_repo.list_by_user(user_id).await?;
+        rules
+            .iter()
+            .find(|rule| rule.matches(input))
+            .ok_or_else(|| anyhow::anyhow!("no routing rule matches the message"))?
+            .apply(input)
+    }
+
+    /// Schedules every request, then hands all resulting events to the bus
+    /// in a single publish. Results are returned in request order.
+    pub async fn execute_batch(
+        &self,
+        requests: Vec<ScheduleMessageRequest>,
+    ) -> Vec<anyhow::Result<ScheduleMessageResponse>> {
+        let mut items = Vec::with_capacity(requests.len());
+        let mut events = Vec::new();
+        for request in requests {
+            let organization_id = request.organization_id;
+            match self.replay(&request).await {
+                Ok(Some(response)) => {
+                    items.push(BatchItem {
+                        organization_id,
+                        claimed_key: None,
+                        result: Ok(response),
+                        queued: false,
+                    });
+                    continue;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    items.push(BatchItem {
+                        organization_id,
+                        claimed_key: None,
+                        result: Err(err),
+                        queued: false,
+                    });
+                    continue;
+                }
+            }
+
+            let claimed_key = request.idempotency_key.clone();
+            let (result, queued) = match self.prepare(request).await {
+                Ok((response, event)) => {
+                    let queued = event.is_some();
+                    events.extend(event);
+                    (Ok(response), queued)
+                }
+                Err(err) => (Err(err), false),
+            };
+            items.push(BatchItem {
+                organization_id,
+                claimed_key,
+                result,
+                queued,
+            });
+        }
+
+        let publish_error = if events.is_empty() {
+            None
+        } else {
+            self.bus.publish_batch(events).await.err()
+        };
+
+        let mut results = Vec::with_capacity(items.len());
+        for item in items {
+            let result = match &publish_error {
+                Some(err) if item.queued => {
+                    Err(anyhow::anyhow!("failed to publish message: {err}"))
+                }
+                _ => item.result,
+            };
+            results.push(
+                self.settle(item.organization_id, item.claimed_key, result)
+                    .await,
+            );
+        }
+        results
+    }
+
+    /// Dispatches the event within `sync_timeout`. Returns whether it was
+    /// sent; otherwise it has been handed to the bus.
+    async fn dispatch_inline(&self, event: OutboundMessageEvent) -> anyhow::Result<bool> {
+        // A send cut off midway cannot tell whether it reached the
+        // messenger, so it runs to completion in the background. The queued
+        // delivery learns its outcome from the dispatch guard.
+        let dispatcher = self.dispatcher.clone();
+        let inline = event.clone();
+        let dispatch =
+            tokio::spawn(async move { dispatcher.handle(inline).await }.with_current_context());
+
+        let queued = match tokio::time::timeout(self.config.sync_timeout, dispatch).await {
+            Ok(Ok(Ok(()))) => return Ok(true),
+            Ok(Ok(Err(err))) => {
+                tracing::warn!(
+                    message_id = %event.message_id,
+                    event_id = %event.event_id,
+                    error = %err,
+                    "synchronous dispatch failed"
+                );
+                // The dispatcher already recorded the failure.
+                if event.attempt >= event.max_attempts || event.deadline_exceeded(Utc::now()) {
+                    return Ok(false);
+                }
+                let mut next = event;
+                next.scheduled_at = Utc::now()
+                    + chrono::Duration::from_std(next.retry_backoff.delay(next.attempt))?;
+                next.attempt += 1;
+                next
+            }
+            Ok(Err(err)) => return Err(err.into()),
+            Err(_) => event,
+        };
+        let when = queued.scheduled_at;
+        self.bus.publish_at(queued, when).await?;
+        Ok(false)
+    }
+
+    /// Claims the request's idempotency key. Returns the original response
+    /// when the request is a replay of one that already completed.
+    async fn replay(
+        &self,
+        request: &ScheduleMessageRequest,
+    ) -> anyhow::Result<Option<ScheduleMessageResponse>> {
+        let Some(key) = request.idempotency_key.as_deref() else {
+            return Ok(None);
+        };
+
+        let request_hash = request_fingerprint(request);
+        let Some(record) = self
+            .idempotency_repo
+            .claim(request.organization_id, key, &request_hash)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        if record.request_hash != request_hash {
+            anyhow::bail!("idempotency key was already used with a different request");
+        }
+        match record.message_id {
+            Some(message_id) => Ok(Some(ScheduleMessageResponse { message_id })),
+            None => anyhow::bail!("idempotency key conflict: original request in progress"),
+        }
+    }
+
+    /// Records the outcome against the claimed key: a success is kept for
+    /// replays, a failure frees the key so the request can be retried.
+    async fn settle(
+        &self,
+        organization_id: Uuid,
+        key: Option<String>,
+        result: anyhow::Result<ScheduleMessageResponse>,
+    ) -> anyhow::Result<ScheduleMessageResponse> {
+        let Some(key) = key else {
+            return result;
+        };
+        match &result {
+            Ok(response) => {
+                self.idempotency_repo
+                    .complete(organization_id, &key, response.message_id)
+                    .await?
+            }
+            Err(_) => self.idempotency_repo.release(organization_id, &key).await?,
+        }
+        result
+    }
+
+    /// Records the message and builds its event without publishing it.
+    /// Suppressed messages have no event. Runs in its own span, which the
+    /// event carries on to the worker.
+    async fn prepare(
+        &self,
+        request: ScheduleMessageRequest,
+    ) -> anyhow::Result<(ScheduleMessageResponse, Option<OutboundMessageEvent>)> {
+        let attributes = vec![KeyValue::new(
+            "messaging.messenger",
+            request.messenger.as_str(),
+        )];
+        telemetry::in_span(
+            &Context::current(),
+            "schedule message",
+            SpanKind::Internal,
+            attributes,
+            self.prepare_message(request),
+        )
+        .await
+    }
+
+    async fn prepare_message(
+        &self,
+        request: ScheduleMessageRequest,
+    ) -> anyhow::Result<(ScheduleMessageResponse, Option<OutboundMessageEvent>)> {
+        if request
+            .deadline
+            .is_some_and(|deadline| deadline <= Utc::now())
+        {
+            anyhow::bail!("deadline must be in the future");
+        }
+
+        let token_id = self.resolve_token(&request).await?;
+        if let Some(parent_id) = request.thread_parent {
+            self.ensure_thread_parent(&request, parent_id).await?;
+        }
+        if request.class == MessageClass::Marketing {
+            self.ensure_consent(&request).await?;
+        }
 
         let content = MessageContent {
             body: request.text.clone(),
@@ -62,49 +400,220 @@ impl ScheduleMessageUseCase {
         };
         let message_type = content.message_type.clone();
 
+        let now = Utc::now();
+        let (decision, cap_reason) = self.check_frequency_caps(&request, now).await?;
+        let (scheduled_for, status) = match decision {
+            CapDecision::Allow => (now, MessageStatus::Scheduled),
+            CapDecision::Defer(until) if request.deadline.is_some_and(|d| d < until) => (
+                now,
+                MessageStatus::Suppressed {
+                    reason: format!("frequency cap reached before deadline: {cap_reason}"),
+                },
+            ),
+            CapDecision::Defer(until) => (
+                until,
+                MessageStatus::Deferred {
+                    reason: format!(
+                        "frequency cap reached ({cap_reason}), deferred until {}",
+                        until.to_rfc3339()
+                    ),
+                },
+            ),
+            CapDecision::Suppress => (
+                now,
+                MessageStatus::Suppressed {
+                    reason: format!("frequency cap reached: {cap_reason}"),
+                },
+            ),
+        };
+
         let history_entry = self
             .history_repo
-            .insert(
-                request.user_id,
-                request.messenger,
-                request.recipient.clone(),
-                content.clone(),
-                request.requested_by,
-            )
+            .insert(NewMessageHistoryEntry {
+                organization_id: request.organization_id,
+                user_id: request.user_id,
+                messenger: request.messenger,
+                recipient: request.recipient.clone(),
+                content: content.clone(),
+                class: request.class,
+                priority: request.priority,
+                requested_by: request.requested_by,
+                deadline: request.deadline,
+                scheduled_for,
+                token_id,
+                tags: normalize_tags(&request.tags),
+            })
             .await?;
 
+        if let Some(parent_id) = request.thread_parent {
+            self.link_repo
+                .insert(&MessageLink {
+                    source_id: history_entry.id,
+                    target_id: parent_id,
+                    kind: MessageLinkKind::ThreadParent,
+                    created_at: now,
+                })
+                .await?;
+        }
+
+        let suppressed = matches!(status, MessageStatus::Suppressed { .. });
         self.history_repo
-            .update_status(history_entry.id, MessageStatus::Scheduled, 0)
+            .update_status(history_entry.id, status, 0)
             .await?;
+        let response = ScheduleMessageResponse {
+            message_id: history_entry.id,
+        };
+        if suppressed {
+            return Ok((response, None));
+        }
 
         let event = OutboundMessageEvent {
             event_id: Uuid::new_v4(),
             message_id: history_entry.id,
             user_id: request.user_id,
+            organization_id: Some(request.organization_id),
             messenger: request.messenger,
             recipient: request.recipient,
             message_type,
             content,
+            class: request.class,
+            priority: request.priority,
             attempt: 1,
             max_attempts: self.config.max_attempts,
-            scheduled_at: Utc::now(),
+            retry_backoff: self.config.retry_backoff,
+            scheduled_at: scheduled_for,
+            deadline: request.deadline,
+            token_id,
+            trace_context: telemetry::current(),
         };
 
-        self.bus.publish(event).await?;
-
-        Ok(ScheduleMessageResponse {
-            message_id: history_entry.id,
-        })
+        Ok((response, Some(event)))
     }
 
-    async fn ensure_token_exists(&self, request: &ScheduleMessageRequest) -> anyhow::Result<()> {
+    /// Returns the id of an explicitly selected token, to be kept with the
+    /// message; the latest token is looked up again at delivery.
+    async fn resolve_token(
+        &self,
+        request: &ScheduleMessageRequest,
+    ) -> anyhow::Result<Option<Uuid>> {
         let token = self
             .token_repo
-            .find_active(&request.user_id, request.messenger)
+            .find_active(
+                &request.organization_id,
+                &request.user_id,
+                request.messenger,
+                &request.token,
+            )
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("no active token for messenger"))?;
+        Ok(match request.token {
+            TokenSelector::Latest => None,
+            _ => Some(token.id),
+        })
+    }
+
+    /// Returns the most restrictive decision among the caps for the message's
+    /// class, together with a description of the cap that produced it.
+    pub async fn check_frequency_caps(
+        &self,
+        request: &ScheduleMessageRequest,
+        now: DateTime<Utc>,
+    ) -> anyhow::Result<(CapDecision, String)> {
+        let mut result = (CapDecision::Allow, String::new());
+        for cap in self
+            .config
+            .frequency_caps
+            .iter()
+            .filter(|cap| cap.class == request.class)
+        {
+            let since = now - chrono::Duration::from_std(cap.window)?;
+            let slots = self
+                .history_repo
+                .scheduled_times_since(
+                    request.organization_id,
+                    request.messenger,
+                    &request.recipient,
+                    request.class,
+                    since,
+                )
+                .await?;
+
+            let decision = cap.evaluate(self.config.frequency_cap_mode, &slots, now);
+            let stricter = match (result.0, decision) {
+                (_, CapDecision::Suppress) => true,
+                (CapDecision::Allow, CapDecision::Defer(_)) => true,
+                (CapDecision::Defer(current), CapDecision::Defer(until)) => until > current,
+                _ => false,
+            };
+            if stricter {
+                result = (decision, cap.describe());
+            }
+        }
+        Ok(result)
+    }
+
+    async fn ensure_thread_parent(
+        &self,
+        request: &ScheduleMessageRequest,
+        parent_id: Uuid,
+    ) -> anyhow::Result<()> {
+        let parent = self.history_repo.get(parent_id).await?;
+        if parent.is_none_or(|parent| parent.organization_id != request.organization_id) {
+            anyhow::bail!("thread parent message not found");
+        }
+        Ok(())
+    }
+
+    async fn ensure_consent(&self, request: &ScheduleMessageRequest) -> anyhow::Result<()> {
+        let consent = self
+            .consent_repo
+            .find(
+                request.organization_id,
+                request.messenger,
+                &request.recipient,
+            )
             .await?;
-        if token.is_none() {
-            anyhow::bail!("no active token for messenger");
+        if !consent.is_some_and(|consent| consent.status == ConsentStatus::Confirmed) {
+            anyhow::bail!("marketing message requires confirmed recipient consent");
         }
         Ok(())
     }
 }
+
+/// Sorted and without duplicates, so the order tags were given in does not
+/// matter.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut tags = tags.to_vec();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Hash of everything that decides what gets sent, so a reused key can be
+/// told apart from a replay.
+fn request_fingerprint(request: &ScheduleMessageRequest) -> String {
+    let mut hasher = Sha256::new();
+    for field in [
+        request.organization_id.to_string(),
+        request.messenger.as_str().to_string(),
+        request.recipient.clone(),
+        request.text.clone(),
+        request.class.as_str().to_string(),
+        request.priority.as_str().to_string(),
+        format!("{:?}", request.requested_by),
+        request
+            .deadline
+            .map(|deadline| deadline.to_rfc3339())
+            .unwrap_or_default(),
+        request
+            .thread_parent
+            .map(|parent| parent.to_string())
+            .unwrap_or_default(),
+        format!("{:?}", request.token),
+        normalize_tags(&request.tags).join("\0"),
+    ] {
+        hasher.update(field.as_bytes());
+        hasher.update([0]);
+    }
+    hex::encode(hasher.finalize())
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::{
+    application::services::messenger::MessengerGateway,
+    domain::{
+        models::{ChatAction, MessengerType, TokenSelector},
+        repositories::MessengerTokenRepository,
+    },
+};
+
+pub struct SendChatActionUseCase {
+    token_repo: Arc<dyn MessengerTokenRepository>,
+    gateway: MessengerGateway,
+}
+
+impl SendChatActionUseCase {
+    pub fn new(token_repo: Arc<dyn MessengerTokenRepository>, gateway: MessengerGateway) -> Self {
+        Self {
+            token_repo,
+            gateway,
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        action: ChatAction,
+    ) -> anyhow::Result<()> {
+        let token = self
+            .token_repo
+            .find_active(
+                &organization_id,
+                &user_id,
+                messenger,
+                &TokenSelector::Latest,
+            )
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("no active token for messenger"))?;
+
+        let client = self
+            .gateway
+            .get(messenger)
+            .ok_or_else(|| anyhow::anyhow!("no client registered for messenger"))?;
+
+        client.send_chat_action(&token, recipient, action).await
+    }
+}
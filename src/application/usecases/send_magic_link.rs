@@ -0,0 +1,69 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+
+use crate::{
+    application::services::{
+        mailer::Mailer,
+        secret_token::{generate_secret_token, hash_secret_token},
+    },
+    domain::repositories::LoginTokenRepository,
+};
+
+pub struct MagicLinkUseCase {
+    login_token_repo: Arc<dyn LoginTokenRepository>,
+    mailer: Option<Arc<dyn Mailer>>,
+    ttl: Duration,
+    /// Link mailed for sign-in, with `{token}` standing for the token.
+    link_url: Option<String>,
+    enabled: bool,
+}
+
+impl MagicLinkUseCase {
+    pub fn new(
+        login_token_repo: Arc<dyn LoginTokenRepository>,
+        mailer: Option<Arc<dyn Mailer>>,
+        ttl: Duration,
+        link_url: Option<String>,
+        enabled: bool,
+    ) -> Self {
+        Self {
+            login_token_repo,
+            mailer,
+            ttl,
+            link_url,
+            enabled,
+        }
+    }
+
+    /// Mails a single-use sign-in link to `email`. Emails without an
+    /// account get one too, and following it creates the account.
+    pub async fn request(&self, email: &str) -> anyhow::Result<()> {
+        let mailer = match &self.mailer {
+            Some(mailer) if self.enabled => mailer,
+            _ => anyhow::bail!("magic link sign-in not available"),
+        };
+
+        let token = generate_secret_token();
+        let expires_at = Utc::now() + chrono::Duration::from_std(self.ttl)?;
+        self.login_token_repo
+            .create(&hash_secret_token(&token), email, expires_at)
+            .await?;
+
+        let link = match &self.link_url {
+            Some(url) => url.replace("{token}", &token),
+            None => token,
+        };
+        mailer
+            .send(
+                email,
+                "Your sign-in link",
+                &format!(
+                    "Sign in with\n\n{link}\n\nIt works once, until {}. If you didn't \
+                     ask for it, ignore this mail.\n",
+                    expires_at.to_rfc3339()
+                ),
+            )
+            .await
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        services::otp::{generate_code, hash_code},
+        usecases::schedule_message::{ScheduleMessageRequest, ScheduleMessageUseCase},
+    },
+    domain::{
+        models::{
+            MessageClass, MessagePriority, MessengerType, OtpCode, RequestedBy, TokenSelector,
+        },
+        repositories::OtpRepository,
+    },
+};
+
+pub struct SendOtpConfig {
+    pub code_length: u32,
+    pub ttl: Duration,
+    pub message_template: String,
+}
+
+pub struct SendOtpUseCase {
+    otp_repo: Arc<dyn OtpRepository>,
+    schedule: Arc<ScheduleMessageUseCase>,
+    config: SendOtpConfig,
+}
+
+pub struct SendOtpRequest {
+    pub user_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+}
+
+pub struct SendOtpResponse {
+    pub message_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl SendOtpUseCase {
+    pub fn new(
+        otp_repo: Arc<dyn OtpRepository>,
+        schedule: Arc<ScheduleMessageUseCase>,
+        config: SendOtpConfig,
+    ) -> Self {
+        Self {
+            otp_repo,
+            schedule,
+            config,
+        }
+    }
+
+    pub async fn execute(&self, request: SendOtpRequest) -> anyhow::Result<SendOtpResponse> {
+        let code = generate_code(self.config.code_length);
+        let now = Utc::now();
+        let expires_at = now + chrono::Duration::from_std(self.config.ttl)?;
+
+        // The code's lifetime doubles as the delivery deadline: a code that
+        // arrives after it expired is useless.
+        let scheduled = self
+            .schedule
+            .execute(ScheduleMessageRequest {
+                // Codes are the user's own, so their personal organization
+                // sends them.
+                organization_id: request.user_id,
+                user_id: request.user_id,
+                messenger: request.messenger,
+                recipient: request.recipient.clone(),
+                text: self.config.message_template.replace("{code}", &code),
+                class: MessageClass::Transactional,
+                priority: MessagePriority::High,
+                requested_by: RequestedBy::User,
+                deadline: Some(expires_at),
+                thread_parent: None,
+                idempotency_key: None,
+                token: TokenSelector::Latest,
+                tags: Vec::new(),
+            })
+            .await?;
+
+        let id = Uuid::new_v4();
+        self.otp_repo
+            .replace_active(&OtpCode {
+                id,
+                user_id: request.user_id,
+                messenger: request.messenger,
+                recipient: request.recipient,
+                code_hash: hash_code(&id, &code),
+                message_id: scheduled.message_id,
+                failed_attempts: 0,
+                expires_at,
+                verified_at: None,
+                created_at: now,
+            })
+            .await?;
+
+        Ok(SendOtpResponse {
+            message_id: scheduled.message_id,
+            expires_at,
+        })
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use uuid::Uuid;
+
+use crate::domain::{models::MessengerToken, repositories::MessengerTokenRepository};
+
+pub struct ShareTokenUseCase {
+    repo: Arc<dyn MessengerTokenRepository>,
+}
+
+impl ShareTokenUseCase {
+    pub fn new(repo: Arc<dyn MessengerTokenRepository>) -> Self {
+        Self { repo }
+    }
+
+    /// Lets every member of the organization send with the token, or only
+    /// the member who registered it. Only that member may change it.
+    pub async fn execute(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+        token_id: Uuid,
+        shared: bool,
+    ) -> anyhow::Result<MessengerToken> {
+        let token = self
+            .repo
+            .list_by_organization(&organization_id)
+            .await?
+            .into_iter()
+            .find(|token| token.id == token_id)
+            .ok_or_else(|| anyhow::anyhow!("token not found"))?;
+        if token.user_id != user_id {
+            anyhow::bail!("forbidden: token was registered by another member");
+        }
+
+        self.repo.upsert(MessengerToken { shared, ..token }).await
+    }
+}
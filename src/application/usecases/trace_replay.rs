@@ -0,0 +1,312 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::{
+        services::{frequency_cap::CapDecision, messenger::MessengerGateway},
+        usecases::{
+            manage_routing_rules::RoutingRuleUseCase,
+            preview_message::{PreviewMessageRequest, PreviewMessageUseCase},
+            schedule_message::{ScheduleMessageRequest, ScheduleMessageUseCase},
+        },
+    },
+    domain::{
+        models::{ConsentStatus, MessageClass, RoutingInput, TokenSelector},
+        repositories::{ConsentRepository, MessageHistoryRepository, MessengerTokenRepository},
+    },
+};
+
+/// One stage of the pipeline as the message would go through it now.
+pub struct TraceStep {
+    pub step: &'static str,
+    /// False when the stage would stop or hold back the message.
+    pub passed: bool,
+    pub detail: String,
+}
+
+pub struct MessageTrace {
+    pub message_id: Uuid,
+    pub steps: Vec<TraceStep>,
+    /// Whether every stage passed, so a replay would reach the messenger.
+    pub would_send: bool,
+}
+
+/// Re-runs a stored message through scheduling and dispatch without
+/// recording, queueing or sending anything.
+pub struct TraceReplayUseCase {
+    history_repo: Arc<dyn MessageHistoryRepository>,
+    token_repo: Arc<dyn MessengerTokenRepository>,
+    consent_repo: Arc<dyn ConsentRepository>,
+    routing: Arc<RoutingRuleUseCase>,
+    scheduler: Arc<ScheduleMessageUseCase>,
+    preview: Arc<PreviewMessageUseCase>,
+    gateway: MessengerGateway,
+}
+
+impl TraceReplayUseCase {
+    pub fn new(
+        history_repo: Arc<dyn MessageHistoryRepository>,
+        token_repo: Arc<dyn MessengerTokenRepository>,
+        consent_repo: Arc<dyn ConsentRepository>,
+        routing: Arc<RoutingRuleUseCase>,
+        scheduler: Arc<ScheduleMessageUseCase>,
+        preview: Arc<PreviewMessageUseCase>,
+        gateway: MessengerGateway,
+    ) -> Self {
+        Self {
+            history_repo,
+            token_repo,
+            consent_repo,
+            routing,
+            scheduler,
+            preview,
+            gateway,
+        }
+    }
+
+    pub async fn execute(&self, message_id: Uuid) -> anyhow::Result<MessageTrace> {
+        let message = self
+            .history_repo
+            .get(message_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("message not found"))?;
+        let now = Utc::now();
+        let mut steps = Vec::new();
+
+        steps.push(TraceStep {
+            step: "message",
+            passed: true,
+            detail: format!(
+                "{} {} message for {} via {}, status {:?} after {} attempt(s)",
+                message.priority.as_str(),
+                message.class.as_str(),
+                message.recipient,
+                message.messenger.as_str(),
+                message.status,
+                message.attempts
+            ),
+        });
+
+        steps.push(match message.deadline {
+            Some(deadline) if deadline <= now => TraceStep {
+                step: "deadline",
+                passed: false,
+                detail: format!("passed at {}", deadline.to_rfc3339()),
+            },
+            Some(deadline) => TraceStep {
+                step: "deadline",
+                passed: true,
+                detail: format!("open until {}", deadline.to_rfc3339()),
+            },
+            None => TraceStep {
+                step: "deadline",
+                passed: true,
+                detail: "none".to_string(),
+            },
+        });
+
+        let token = self
+            .token_repo
+            .find_active(
+                &message.organization_id,
+                &message.sender(),
+                message.messenger,
+                &TokenSelector::from_token_id(message.token_id),
+            )
+            .await?;
+        steps.push(match &token {
+            Some(token) => TraceStep {
+                step: "token",
+                passed: true,
+                detail: format!(
+                    "active token {} updated {}",
+                    token.id,
+                    token.updated_at.to_rfc3339()
+                ),
+            },
+            None => TraceStep {
+                step: "token",
+                passed: false,
+                detail: format!("no active {} token", message.messenger.as_str()),
+            },
+        });
+
+        steps.push(match message.class {
+            MessageClass::Transactional => TraceStep {
+                step: "consent",
+                passed: true,
+                detail: "not required for transactional messages".to_string(),
+            },
+            MessageClass::Marketing => {
+                let consent = self
+                    .consent_repo
+                    .find(
+                        message.organization_id,
+                        message.messenger,
+                        &message.recipient,
+                    )
+                    .await?;
+                match consent {
+                    Some(consent) => TraceStep {
+                        step: "consent",
+                        passed: consent.status == ConsentStatus::Confirmed,
+                        detail: format!(
+                            "{:?} since {} (source: {})",
+                            consent.status,
+                            consent.updated_at.to_rfc3339(),
+                            consent.source
+                        ),
+                    },
+                    None => TraceStep {
+                        step: "consent",
+                        passed: false,
+                        detail: "no consent recorded for the recipient".to_string(),
+                    },
+                }
+            }
+        });
+
+        // Tags and metadata of the original send are not stored, so rules
+        // are matched on class and text only.
+        let simulated = self
+            .routing
+            .simulate(
+                message.sender(),
+                &RoutingInput {
+                    event: None,
+                    class: message.class,
+                    tags: Vec::new(),
+                    metadata: BTreeMap::new(),
+                    text: message.content.body.clone(),
+                },
+            )
+            .await?;
+        let matched: Vec<String> = simulated
+            .iter()
+            .map(|simulated| match &simulated.result {
+                Ok(routed) if simulated.selected => format!(
+                    "{} (selected: {} to {})",
+                    simulated.rule.name,
+                    routed.messenger.as_str(),
+                    routed.recipient
+                ),
+                Ok(_) => simulated.rule.name.clone(),
+                Err(err) => format!("{} (fails: {err})", simulated.rule.name),
+            })
+            .collect();
+        steps.push(TraceStep {
+            step: "routing",
+            passed: true,
+            detail: if matched.is_empty() {
+                "no rule matches; the stored destination is used".to_string()
+            } else {
+                format!(
+                    "matching rules: {}; only sends without a destination are routed",
+                    matched.join(", ")
+                )
+            },
+        });
+
+        let (decision, cap_reason) = self
+            .scheduler
+            .check_frequency_caps(
+                &ScheduleMessageRequest {
+                    organization_id: message.organization_id,
+                    user_id: message.sender(),
+                    messenger: message.messenger,
+                    recipient: message.recipient.clone(),
+                    text: message.content.body.clone(),
+                    class: message.class,
+                    priority: message.priority,
+                    requested_by: message.requested_by.clone(),
+                    deadline: message.deadline,
+                    thread_parent: None,
+                    idempotency_key: None,
+                    token: TokenSelector::from_token_id(message.token_id),
+                    tags: message.tags.clone(),
+                },
+                now,
+            )
+            .await?;
+        steps.push(match decision {
+            CapDecision::Allow => TraceStep {
+                step: "frequency_cap",
+                passed: true,
+                detail: "within every cap".to_string(),
+            },
+            CapDecision::Defer(until) => TraceStep {
+                step: "frequency_cap",
+                passed: false,
+                detail: format!(
+                    "{cap_reason} reached, would be deferred until {}",
+                    until.to_rfc3339()
+                ),
+            },
+            CapDecision::Suppress => TraceStep {
+                step: "frequency_cap",
+                passed: false,
+                detail: format!("{cap_reason} reached, would be suppressed"),
+            },
+        });
+
+        let preview = self.preview.execute(PreviewMessageRequest {
+            messenger: message.messenger,
+            text: message.content.body.clone(),
+        });
+        let findings: Vec<&str> = preview
+            .spam
+            .findings
+            .iter()
+            .map(|finding| finding.rule)
+            .collect();
+        steps.push(TraceStep {
+            step: "render",
+            passed: true,
+            detail: format!(
+                "{} of {} {} in {} part(s), spam score {}{}",
+                preview.length.used,
+                preview.length.limit,
+                preview.length.unit.as_str(),
+                preview.length.parts,
+                preview.spam.score,
+                if findings.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", findings.join(", "))
+                }
+            ),
+        });
+
+        steps.push(match self.gateway.get(message.messenger) {
+            Some(client) => TraceStep {
+                step: "adapter",
+                passed: token.is_some(),
+                detail: format!(
+                    "would call {} send to {} with message id {}{}; not called in a replay",
+                    message.messenger.as_str(),
+                    message.recipient,
+                    message.id,
+                    if client.deduplicates_sends() {
+                        ", deduplicated by the messenger"
+                    } else {
+                        ""
+                    }
+                ),
+            },
+            None => TraceStep {
+                step: "adapter",
+                passed: false,
+                detail: format!("no client registered for {}", message.messenger.as_str()),
+            },
+        });
+
+        let would_send = steps.iter().all(|step| step.passed);
+        Ok(MessageTrace {
+            message_id,
+            steps,
+            would_send,
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    application::services::otp::hash_code,
+    domain::{
+        models::MessengerType,
+        repositories::{MessageHistoryRepository, OtpRepository},
+    },
+};
+
+pub struct VerifyOtpConfig {
+    pub max_attempts: u32,
+}
+
+pub struct VerifyOtpUseCase {
+    otp_repo: Arc<dyn OtpRepository>,
+    history_repo: Arc<dyn MessageHistoryRepository>,
+    config: VerifyOtpConfig,
+}
+
+pub struct VerifyOtpRequest {
+    pub user_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub code: String,
+}
+
+impl VerifyOtpUseCase {
+    pub fn new(
+        otp_repo: Arc<dyn OtpRepository>,
+        history_repo: Arc<dyn MessageHistoryRepository>,
+        config: VerifyOtpConfig,
+    ) -> Self {
+        Self {
+            otp_repo,
+            history_repo,
+            config,
+        }
+    }
+
+    pub async fn execute(&self, request: VerifyOtpRequest) -> anyhow::Result<()> {
+        let otp = self
+            .otp_repo
+            .find_active(request.user_id, request.messenger, &request.recipient)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("otp not found"))?;
+
+        if otp.expires_at <= Utc::now() {
+            anyhow::bail!("otp expired");
+        }
+
+        // Taken before the comparison, so concurrent guesses can't all pass
+        // the limit check before any of them is counted.
+        if !self
+            .otp_repo
+            .reserve_attempt(otp.id, self.config.max_attempts)
+            .await?
+        {
+            anyhow::bail!("too many attempts");
+        }
+
+        if hash_code(&otp.id, request.code.trim()) != otp.code_hash {
+            anyhow::bail!("invalid code");
+        }
+
+        if !self.otp_repo.mark_verified(otp.id).await? {
+            anyhow::bail!("otp not found");
+        }
+        self.history_repo.redact(otp.message_id).await?;
+
+        Ok(())
+    }
+}
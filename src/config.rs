@@ -1,4 +1,7 @@
-use std::env::var;
+use std::{
+    collections::HashMap,
+    env::{self, VarError},
+};
 
 use dotenvy::dotenv;
 
@@ -7,79 +10,506 @@ pub struct Config {
     pub scheme: String,
     pub host: String,
     pub cors_allowed_origins: Vec<String>,
+    /// Proxies whose `X-Forwarded-For` and `X-Real-IP` are believed, as
+    /// CIDRs or addresses; with none, the peer's address is the client's.
+    pub trusted_proxies: Vec<String>,
+    pub admin_emails: Vec<String>,
     pub database_url: String,
+    pub database_read_url: Option<String>,
     pub database_max_connections: u32,
+    pub database_retry_attempts: u32,
+    pub database_retry_base_ms: u64,
+    pub history_partitions_ahead: u32,
+    pub history_retention_months: Option<u32>,
+    /// Messages deleted per statement when erasing an account.
+    pub erasure_batch_size: u32,
+    /// Keys the email hash kept on erasure records; without it none is kept.
+    pub erasure_email_key: Option<String>,
     pub jwt_secret: String,
+    /// `kid:algorithm:path` entries; when given they replace `jwt_secret`
+    /// for access tokens.
+    pub jwt_keys: Vec<String>,
+    pub jwt_signing_key_id: Option<String>,
     pub jwt_ttl_seconds: u64,
     pub jwt_refresh_ttl_seconds: u64,
+    /// Tokens without these, or with other values, are refused when set.
+    pub jwt_issuer: Option<String>,
+    pub jwt_audience: Option<String>,
+    pub auth_providers: Vec<String>,
+    pub oidc_issuer: String,
+    pub oidc_audience: String,
+    pub oidc_jwks_url: Option<String>,
+    pub google_client_id: String,
+    pub google_client_secret: String,
+    pub github_client_id: String,
+    pub github_client_secret: String,
+    /// Public URL of the API the OAuth callback paths are appended to.
+    pub oauth_callback_base_url: Option<String>,
+    pub oauth_success_url: String,
+    pub password_reset_ttl_seconds: u64,
+    pub password_reset_url: Option<String>,
+    pub password_confirm_url: Option<String>,
+    pub smtp_url: Option<String>,
+    pub mail_from: String,
+    pub magic_link_ttl_seconds: u64,
+    pub magic_link_url: Option<String>,
+    pub organization_invitation_ttl_seconds: u64,
+    pub message_bus: String,
     pub nats_url: String,
     pub nats_stream: String,
     pub nats_subject: String,
     pub nats_durable: String,
     pub nats_pull_batch: usize,
+    pub nats_concurrency: usize,
+    pub nats_messenger_limits: Vec<String>,
     pub nats_ack_wait_seconds: u64,
     pub nats_max_deliver: i64,
+    pub nats_duplicate_window_seconds: u64,
+    pub nats_dead_letter_stream: String,
+    pub nats_dead_letter_subject: String,
+    pub nats_quarantine_stream: String,
+    pub nats_quarantine_subject: String,
+    pub rabbitmq_url: String,
+    pub rabbitmq_exchange: String,
+    pub rabbitmq_queue: String,
+    pub rabbitmq_prefetch: u16,
+    pub sqs_queue_url: String,
+    pub sqs_dead_letter_queue_url: Option<String>,
+    pub sqs_max_messages: i32,
+    pub sqs_wait_time_seconds: i32,
     pub system_retry_limit: u32,
+    pub retry_backoff_base_ms: u64,
+    pub retry_backoff_multiplier: f64,
+    pub retry_backoff_jitter: f64,
+    pub retry_backoff_max_ms: u64,
+    pub frequency_caps: Vec<String>,
+    pub frequency_cap_mode: String,
+    /// `memory`, `redis` or `nats`; instances share limits through Redis
+    /// or NATS, and sign-in failure counts only through Redis.
+    pub rate_limit_store: String,
+    pub redis_url: String,
+    pub rate_limit_per_user: Option<String>,
+    pub rate_limit_per_ip: Option<String>,
+    /// Share of the capacity new buckets start with after startup, rising
+    /// to full over the warm-up.
+    pub rate_limit_warmup_fill: f64,
+    pub rate_limit_warmup_seconds: u64,
+    /// Failed sign-ins before an email or address is locked out; 0 never
+    /// locks it.
+    pub login_lockout_email_after: u32,
+    pub login_lockout_ip_after: u32,
+    /// The first lockout, doubled with every further failure up to the max.
+    pub login_lockout_base_seconds: u64,
+    pub login_lockout_max_seconds: u64,
+    /// `uuid`, the default, or `base62`, which needs a non-empty secret.
+    pub public_id_mode: String,
+    pub public_id_secret: String,
+    pub status_link_ttl_seconds: u64,
+    pub status_exposure: String,
+    pub csrf_protection: String,
+    pub otp_code_length: u32,
+    pub otp_ttl_seconds: u64,
+    pub otp_max_verify_attempts: u32,
+    pub otp_message_template: String,
+    pub consent_confirmation_template: String,
+    pub spam_banned_phrases: Vec<String>,
+    pub spam_max_link_density: f64,
+    pub spam_max_caps_ratio: f64,
+    pub message_part_prices: Vec<String>,
+    pub typing_indicator_threshold_ms: u64,
+    pub sync_delivery_timeout_ms: u64,
+    pub worker_hostname: String,
+    pub worker_heartbeat_seconds: u64,
+    pub vk_id_client_id: Option<String>,
+    /// Idle connections kept per messenger API host.
+    pub messenger_pool_max_idle: usize,
+    pub messenger_pool_idle_timeout_seconds: u64,
+    pub messenger_keepalive_seconds: u64,
+    /// Share of sends to each messenger expected to succeed.
+    pub delivery_slo_target: f64,
+    /// OTLP gRPC endpoint spans are exported to; tracing is off without it.
+    pub otlp_endpoint: Option<String>,
+    pub otel_service_name: String,
 }
 
 impl Config {
-    pub fn try_parse() -> Result<Config, &'static str> {
+    /// Values in `secrets` take precedence over the environment.
+    pub fn try_parse(secrets: &HashMap<String, String>) -> Result<Config, &'static str> {
         let _ = dotenv();
+        let vars = Vars { secrets };
 
         Ok(Config {
-            port: read_var("PORT")?
+            port: vars
+                .read_var("PORT")?
                 .parse::<u16>()
                 .map_err(|_| "invalid PORT")?,
-            scheme: read_var("SCHEME")?,
-            host: read_var("HOST")?,
-            cors_allowed_origins: read_list_var("CORS_ALLOWED_ORIGINS"),
-            database_url: read_var("DATABASE_URL")?,
-            database_max_connections: read_var_or_default("DATABASE_MAX_CONNECTIONS", "8")
+            scheme: vars.read_var("SCHEME")?,
+            host: vars.read_var("HOST")?,
+            cors_allowed_origins: vars.read_list_var("CORS_ALLOWED_ORIGINS"),
+            trusted_proxies: vars.read_list_var("TRUSTED_PROXIES"),
+            admin_emails: vars.read_list_var("ADMIN_EMAILS"),
+            database_url: vars.read_var("DATABASE_URL")?,
+            database_read_url: vars
+                .var("DATABASE_READ_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            database_max_connections: vars
+                .read_var_or_default("DATABASE_MAX_CONNECTIONS", "8")
                 .parse::<u32>()
                 .map_err(|_| "invalid DATABASE_MAX_CONNECTIONS")?,
-            jwt_secret: read_var("JWT_SECRET")?,
-            jwt_ttl_seconds: read_var("JWT_TTL_SECONDS")?
+            database_retry_attempts: vars
+                .read_var_or_default("DATABASE_RETRY_ATTEMPTS", "3")
+                .parse::<u32>()
+                .map_err(|_| "invalid DATABASE_RETRY_ATTEMPTS")?,
+            database_retry_base_ms: vars
+                .read_var_or_default("DATABASE_RETRY_BASE_MS", "100")
+                .parse::<u64>()
+                .map_err(|_| "invalid DATABASE_RETRY_BASE_MS")?,
+            history_partitions_ahead: vars
+                .read_var_or_default("HISTORY_PARTITIONS_AHEAD", "3")
+                .parse::<u32>()
+                .map_err(|_| "invalid HISTORY_PARTITIONS_AHEAD")?,
+            history_retention_months: match vars
+                .read_var_or_default("HISTORY_RETENTION_MONTHS", "0")
+                .parse::<u32>()
+                .map_err(|_| "invalid HISTORY_RETENTION_MONTHS")?
+            {
+                0 => None,
+                months => Some(months),
+            },
+            erasure_batch_size: vars
+                .read_var_or_default("ERASURE_BATCH_SIZE", "500")
+                .parse::<u32>()
+                .map_err(|_| "invalid ERASURE_BATCH_SIZE")?,
+            erasure_email_key: vars
+                .var("ERASURE_EMAIL_KEY")
+                .ok()
+                .filter(|key| !key.is_empty()),
+            jwt_secret: vars.read_var("JWT_SECRET")?,
+            jwt_keys: vars.read_list_var("JWT_KEYS"),
+            jwt_signing_key_id: vars
+                .var("JWT_SIGNING_KEY_ID")
+                .ok()
+                .filter(|kid| !kid.is_empty()),
+            jwt_ttl_seconds: vars
+                .read_var("JWT_TTL_SECONDS")?
                 .parse::<u64>()
                 .map_err(|_| "invalid JWT_TTL_SECONDS")?,
-            jwt_refresh_ttl_seconds: read_var_or_default("JWT_REFRESH_TTL_SECONDS", "604800")
+            jwt_refresh_ttl_seconds: vars
+                .read_var_or_default("JWT_REFRESH_TTL_SECONDS", "604800")
                 .parse::<u64>()
                 .map_err(|_| "invalid JWT_REFRESH_TTL_SECONDS")?,
-            nats_url: read_var("NATS_URL")?,
-            nats_stream: read_var_or_default("NATS_STREAM", "MESSAGING"),
-            nats_subject: read_var_or_default("NATS_SUBJECT", "messaging.outbound"),
-            nats_durable: read_var_or_default("NATS_DURABLE", "messaging-worker"),
-            nats_pull_batch: read_var_or_default("NATS_PULL_BATCH", "32")
+            jwt_issuer: vars.var("JWT_ISSUER").ok().filter(|iss| !iss.is_empty()),
+            jwt_audience: vars.var("JWT_AUDIENCE").ok().filter(|aud| !aud.is_empty()),
+            auth_providers: vars.read_list_var("AUTH_PROVIDERS"),
+            oidc_issuer: vars.read_var_or_default("OIDC_ISSUER", ""),
+            oidc_audience: vars.read_var_or_default("OIDC_AUDIENCE", ""),
+            oidc_jwks_url: vars.var("OIDC_JWKS_URL").ok().filter(|url| !url.is_empty()),
+            google_client_id: vars.read_var_or_default("GOOGLE_CLIENT_ID", ""),
+            google_client_secret: vars.read_var_or_default("GOOGLE_CLIENT_SECRET", ""),
+            github_client_id: vars.read_var_or_default("GITHUB_CLIENT_ID", ""),
+            github_client_secret: vars.read_var_or_default("GITHUB_CLIENT_SECRET", ""),
+            oauth_callback_base_url: vars
+                .var("OAUTH_CALLBACK_BASE_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            oauth_success_url: vars.read_var_or_default("OAUTH_SUCCESS_URL", "/"),
+            password_reset_ttl_seconds: vars
+                .read_var_or_default("PASSWORD_RESET_TTL_SECONDS", "3600")
+                .parse::<u64>()
+                .map_err(|_| "invalid PASSWORD_RESET_TTL_SECONDS")?,
+            password_reset_url: vars
+                .var("PASSWORD_RESET_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            password_confirm_url: vars
+                .var("PASSWORD_CONFIRM_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            smtp_url: vars.var("SMTP_URL").ok().filter(|url| !url.is_empty()),
+            mail_from: vars.read_var_or_default("MAIL_FROM", ""),
+            magic_link_ttl_seconds: vars
+                .read_var_or_default("MAGIC_LINK_TTL_SECONDS", "900")
+                .parse::<u64>()
+                .map_err(|_| "invalid MAGIC_LINK_TTL_SECONDS")?,
+            magic_link_url: vars
+                .var("MAGIC_LINK_URL")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            organization_invitation_ttl_seconds: vars
+                .read_var_or_default("ORGANIZATION_INVITATION_TTL_SECONDS", "604800")
+                .parse::<u64>()
+                .map_err(|_| "invalid ORGANIZATION_INVITATION_TTL_SECONDS")?,
+            message_bus: vars.read_var_or_default("MESSAGE_BUS", "jetstream"),
+            nats_url: vars.read_var_or_default("NATS_URL", "nats://localhost:4222"),
+            nats_stream: vars.read_var_or_default("NATS_STREAM", "MESSAGING"),
+            nats_subject: vars.read_var_or_default("NATS_SUBJECT", "messaging.outbound"),
+            nats_durable: vars.read_var_or_default("NATS_DURABLE", "messaging-worker"),
+            nats_pull_batch: vars
+                .read_var_or_default("NATS_PULL_BATCH", "32")
                 .parse::<usize>()
                 .map_err(|_| "invalid NATS_PULL_BATCH")?,
-            nats_ack_wait_seconds: read_var_or_default("NATS_ACK_WAIT_SECONDS", "30")
+            nats_concurrency: vars
+                .read_var_or_default("NATS_CONCURRENCY", "4")
+                .parse::<usize>()
+                .map_err(|_| "invalid NATS_CONCURRENCY")?,
+            nats_messenger_limits: vars.read_list_var("NATS_MESSENGER_LIMITS"),
+            nats_ack_wait_seconds: vars
+                .read_var_or_default("NATS_ACK_WAIT_SECONDS", "30")
                 .parse::<u64>()
                 .map_err(|_| "invalid NATS_ACK_WAIT_SECONDS")?,
-            nats_max_deliver: read_var_or_default("NATS_MAX_DELIVER", "10")
+            nats_max_deliver: vars
+                .read_var_or_default("NATS_MAX_DELIVER", "10")
                 .parse::<i64>()
                 .map_err(|_| "invalid NATS_MAX_DELIVER")?,
-            system_retry_limit: read_var_or_default("SYSTEM_RETRY_LIMIT", "3")
+            nats_duplicate_window_seconds: vars
+                .read_var_or_default("NATS_DUPLICATE_WINDOW_SECONDS", "120")
+                .parse::<u64>()
+                .map_err(|_| "invalid NATS_DUPLICATE_WINDOW_SECONDS")?,
+            nats_dead_letter_stream: vars.read_var_or_default("NATS_DLQ_STREAM", "MESSAGING_DLQ"),
+            nats_dead_letter_subject: vars
+                .read_var_or_default("NATS_DLQ_SUBJECT", "messaging.dead"),
+            nats_quarantine_stream: vars
+                .read_var_or_default("NATS_QUARANTINE_STREAM", "MESSAGING_QUARANTINE"),
+            nats_quarantine_subject: vars
+                .read_var_or_default("NATS_QUARANTINE_SUBJECT", "messaging.quarantine"),
+            rabbitmq_url: vars.read_var_or_default("RABBITMQ_URL", "amqp://localhost:5672/%2f"),
+            rabbitmq_exchange: vars.read_var_or_default("RABBITMQ_EXCHANGE", "messaging"),
+            rabbitmq_queue: vars.read_var_or_default("RABBITMQ_QUEUE", "messaging.outbound"),
+            rabbitmq_prefetch: vars
+                .read_var_or_default("RABBITMQ_PREFETCH", "32")
+                .parse::<u16>()
+                .map_err(|_| "invalid RABBITMQ_PREFETCH")?,
+            sqs_queue_url: vars.read_var_or_default("SQS_QUEUE_URL", ""),
+            sqs_dead_letter_queue_url: vars.var("SQS_DLQ_URL").ok().filter(|url| !url.is_empty()),
+            sqs_max_messages: vars
+                .read_var_or_default("SQS_MAX_MESSAGES", "10")
+                .parse::<i32>()
+                .map_err(|_| "invalid SQS_MAX_MESSAGES")?,
+            sqs_wait_time_seconds: vars
+                .read_var_or_default("SQS_WAIT_TIME_SECONDS", "20")
+                .parse::<i32>()
+                .map_err(|_| "invalid SQS_WAIT_TIME_SECONDS")?,
+            system_retry_limit: vars
+                .read_var_or_default("SYSTEM_RETRY_LIMIT", "3")
                 .parse::<u32>()
                 .map_err(|_| "invalid SYSTEM_RETRY_LIMIT")?,
+            retry_backoff_base_ms: vars
+                .read_var_or_default("RETRY_BACKOFF_BASE_MS", "5000")
+                .parse::<u64>()
+                .map_err(|_| "invalid RETRY_BACKOFF_BASE_MS")?,
+            retry_backoff_multiplier: vars
+                .read_var_or_default("RETRY_BACKOFF_MULTIPLIER", "2")
+                .parse::<f64>()
+                .map_err(|_| "invalid RETRY_BACKOFF_MULTIPLIER")?,
+            retry_backoff_jitter: vars
+                .read_var_or_default("RETRY_BACKOFF_JITTER", "0.2")
+                .parse::<f64>()
+                .map_err(|_| "invalid RETRY_BACKOFF_JITTER")?,
+            retry_backoff_max_ms: vars
+                .read_var_or_default("RETRY_BACKOFF_MAX_MS", "300000")
+                .parse::<u64>()
+                .map_err(|_| "invalid RETRY_BACKOFF_MAX_MS")?,
+            frequency_caps: vars.read_list_var("FREQUENCY_CAPS"),
+            frequency_cap_mode: vars.read_var_or_default("FREQUENCY_CAP_MODE", "defer"),
+            rate_limit_store: vars.read_var_or_default("RATE_LIMIT_STORE", "memory"),
+            redis_url: vars.read_var_or_default("REDIS_URL", "redis://localhost:6379"),
+            rate_limit_per_user: Some(vars.read_var_or_default("RATE_LIMIT_PER_USER", "600/60"))
+                .filter(|limit| !limit.is_empty()),
+            rate_limit_per_ip: Some(vars.read_var_or_default("RATE_LIMIT_PER_IP", "1200/60"))
+                .filter(|limit| !limit.is_empty()),
+            rate_limit_warmup_fill: vars
+                .read_var_or_default("RATE_LIMIT_WARMUP_FILL", "1")
+                .parse::<f64>()
+                .map_err(|_| "invalid RATE_LIMIT_WARMUP_FILL")?,
+            rate_limit_warmup_seconds: vars
+                .read_var_or_default("RATE_LIMIT_WARMUP_SECONDS", "300")
+                .parse::<u64>()
+                .map_err(|_| "invalid RATE_LIMIT_WARMUP_SECONDS")?,
+            login_lockout_email_after: vars
+                .read_var_or_default("LOGIN_LOCKOUT_EMAIL_AFTER", "5")
+                .parse::<u32>()
+                .map_err(|_| "invalid LOGIN_LOCKOUT_EMAIL_AFTER")?,
+            login_lockout_ip_after: vars
+                .read_var_or_default("LOGIN_LOCKOUT_IP_AFTER", "20")
+                .parse::<u32>()
+                .map_err(|_| "invalid LOGIN_LOCKOUT_IP_AFTER")?,
+            login_lockout_base_seconds: vars
+                .read_var_or_default("LOGIN_LOCKOUT_BASE_SECONDS", "1")
+                .parse::<u64>()
+                .map_err(|_| "invalid LOGIN_LOCKOUT_BASE_SECONDS")?,
+            login_lockout_max_seconds: vars
+                .read_var_or_default("LOGIN_LOCKOUT_MAX_SECONDS", "900")
+                .parse::<u64>()
+                .map_err(|_| "invalid LOGIN_LOCKOUT_MAX_SECONDS")?,
+            public_id_mode: vars.read_var_or_default("PUBLIC_ID_MODE", "uuid"),
+            public_id_secret: vars.read_var_or_default("PUBLIC_ID_SECRET", ""),
+            status_link_ttl_seconds: vars
+                .read_var_or_default("STATUS_LINK_TTL_SECONDS", "2592000")
+                .parse::<u64>()
+                .map_err(|_| "invalid STATUS_LINK_TTL_SECONDS")?,
+            status_exposure: vars.read_var_or_default("STATUS_EXPOSURE", "minimal"),
+            csrf_protection: vars.read_var_or_default("CSRF_PROTECTION", "double_submit"),
+            otp_code_length: vars
+                .read_var_or_default("OTP_CODE_LENGTH", "6")
+                .parse::<u32>()
+                .map_err(|_| "invalid OTP_CODE_LENGTH")?,
+            otp_ttl_seconds: vars
+                .read_var_or_default("OTP_TTL_SECONDS", "300")
+                .parse::<u64>()
+                .map_err(|_| "invalid OTP_TTL_SECONDS")?,
+            otp_max_verify_attempts: vars
+                .read_var_or_default("OTP_MAX_VERIFY_ATTEMPTS", "5")
+                .parse::<u32>()
+                .map_err(|_| "invalid OTP_MAX_VERIFY_ATTEMPTS")?,
+            otp_message_template: vars
+                .read_var_or_default("OTP_MESSAGE_TEMPLATE", "Your verification code: {code}"),
+            consent_confirmation_template: vars.read_var_or_default(
+                "CONSENT_CONFIRMATION_TEMPLATE",
+                "Reply {code} to confirm you want to receive our messages.",
+            ),
+            spam_banned_phrases: vars.read_list_var("SPAM_BANNED_PHRASES"),
+            spam_max_link_density: vars
+                .read_var_or_default("SPAM_MAX_LINK_DENSITY", "0.2")
+                .parse::<f64>()
+                .map_err(|_| "invalid SPAM_MAX_LINK_DENSITY")?,
+            spam_max_caps_ratio: vars
+                .read_var_or_default("SPAM_MAX_CAPS_RATIO", "0.5")
+                .parse::<f64>()
+                .map_err(|_| "invalid SPAM_MAX_CAPS_RATIO")?,
+            message_part_prices: vars.read_list_var("MESSAGE_PART_PRICES"),
+            typing_indicator_threshold_ms: vars
+                .read_var_or_default("TYPING_INDICATOR_THRESHOLD_MS", "0")
+                .parse::<u64>()
+                .map_err(|_| "invalid TYPING_INDICATOR_THRESHOLD_MS")?,
+            sync_delivery_timeout_ms: vars
+                .read_var_or_default("SYNC_DELIVERY_TIMEOUT_MS", "3000")
+                .parse::<u64>()
+                .map_err(|_| "invalid SYNC_DELIVERY_TIMEOUT_MS")?,
+            worker_hostname: vars.read_var_or_default("HOSTNAME", "unknown"),
+            worker_heartbeat_seconds: vars
+                .read_var_or_default("WORKER_HEARTBEAT_SECONDS", "10")
+                .parse::<u64>()
+                .map_err(|_| "invalid WORKER_HEARTBEAT_SECONDS")?,
+            vk_id_client_id: vars.var("VK_ID_CLIENT_ID").ok().filter(|id| !id.is_empty()),
+            messenger_pool_max_idle: vars
+                .read_var_or_default("MESSENGER_POOL_MAX_IDLE", "32")
+                .parse::<usize>()
+                .map_err(|_| "invalid MESSENGER_POOL_MAX_IDLE")?,
+            messenger_pool_idle_timeout_seconds: vars
+                .read_var_or_default("MESSENGER_POOL_IDLE_TIMEOUT_SECONDS", "90")
+                .parse::<u64>()
+                .map_err(|_| "invalid MESSENGER_POOL_IDLE_TIMEOUT_SECONDS")?,
+            messenger_keepalive_seconds: vars
+                .read_var_or_default("MESSENGER_KEEPALIVE_SECONDS", "30")
+                .parse::<u64>()
+                .map_err(|_| "invalid MESSENGER_KEEPALIVE_SECONDS")?,
+            delivery_slo_target: vars
+                .read_var_or_default("DELIVERY_SLO_TARGET", "0.95")
+                .parse::<f64>()
+                .ok()
+                .filter(|target| (0.0..=1.0).contains(target))
+                .ok_or("invalid DELIVERY_SLO_TARGET")?,
+            otlp_endpoint: vars
+                .var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .ok()
+                .filter(|url| !url.is_empty()),
+            otel_service_name: vars.read_var_or_default("OTEL_SERVICE_NAME", "messaging"),
+        })
+    }
+}
+
+/// Where the secrets in [`Config`] come from. Read from the plain
+/// environment, as it is needed before the secrets can be fetched.
+pub struct SecretsConfig {
+    /// `env`, `vault` or `aws`.
+    pub backend: String,
+    pub vault_addr: String,
+    pub vault_token: String,
+    pub vault_secret_path: String,
+    pub aws_secret_id: String,
+    /// 0 fetches only at startup.
+    pub refresh_seconds: u64,
+    /// `reload` or `restart`, see `SecretsOnChange`.
+    pub on_change: String,
+}
+
+impl SecretsConfig {
+    pub fn try_parse() -> Result<SecretsConfig, &'static str> {
+        let _ = dotenv();
+        let vars = Vars {
+            secrets: &HashMap::new(),
+        };
+
+        Ok(SecretsConfig {
+            backend: vars.read_var_or_default("SECRETS_BACKEND", "env"),
+            vault_addr: vars.read_var_or_default("VAULT_ADDR", "http://localhost:8200"),
+            vault_token: vars.read_var_or_default("VAULT_TOKEN", ""),
+            vault_secret_path: vars
+                .read_var_or_default("VAULT_SECRET_PATH", "secret/data/messaging"),
+            aws_secret_id: vars.read_var_or_default("AWS_SECRET_ID", ""),
+            refresh_seconds: vars
+                .read_var_or_default("SECRETS_REFRESH_SECONDS", "0")
+                .parse::<u64>()
+                .map_err(|_| "invalid SECRETS_REFRESH_SECONDS")?,
+            on_change: vars.read_var_or_default("SECRETS_ON_CHANGE", "reload"),
         })
     }
 }
 
-fn read_var(name: &str) -> Result<String, &'static str> {
-    var(name).map_err(|_| "failed to read env var")
+/// How logs are written. Read from the plain environment, as logging
+/// starts before the secrets are fetched.
+pub struct LoggingConfig {
+    /// `json` or `text`.
+    pub format: String,
+    /// `RUST_LOG` directives, such as `info,messaging=debug`.
+    pub filter: String,
+}
+
+impl LoggingConfig {
+    pub fn parse() -> LoggingConfig {
+        let _ = dotenv();
+        let vars = Vars {
+            secrets: &HashMap::new(),
+        };
+
+        LoggingConfig {
+            format: vars.read_var_or_default("LOG_FORMAT", "json"),
+            filter: vars.read_var_or_default("RUST_LOG", "info"),
+        }
+    }
 }
 
-fn read_var_or_default(name: &str, default: &str) -> String {
-    var(name).unwrap_or_else(|_| default.to_string())
+/// The environment, overlaid with secrets fetched from a secret store.
+struct Vars<'a> {
+    secrets: &'a HashMap<String, String>,
 }
 
-fn read_list_var(name: &str) -> Vec<String> {
-    match var(name) {
-        Ok(value) => value
-            .split(',')
-            .map(|item| item.trim())
-            .filter(|item| !item.is_empty())
-            .map(|item| item.to_string())
-            .collect(),
-        Err(_) => Vec::new(),
+impl Vars<'_> {
+    fn var(&self, name: &str) -> Result<String, VarError> {
+        match self.secrets.get(name) {
+            Some(value) => Ok(value.clone()),
+            None => env::var(name),
+        }
+    }
+
+    fn read_var(&self, name: &str) -> Result<String, &'static str> {
+        self.var(name).map_err(|_| "failed to read env var")
+    }
+
+    fn read_var_or_default(&self, name: &str, default: &str) -> String {
+        self.var(name).unwrap_or_else(|_| default.to_string())
+    }
+
+    fn read_list_var(&self, name: &str) -> Vec<String> {
+        match self.var(name) {
+            Ok(value) => value
+                .split(',')
+                .map(|item| item.trim())
+                .filter(|item| !item.is_empty())
+                .map(|item| item.to_string())
+                .collect(),
+            Err(_) => Vec::new(),
+        }
     }
 }
@@ -1,19 +1,64 @@
+use std::collections::HashMap;
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::models::{MessageContent, MessageType, MessengerType};
+use crate::domain::models::{
+    MessageClass, MessageContent, MessagePriority, MessageType, MessengerType, RetryBackoff,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutboundMessageEvent {
     pub event_id: Uuid,
     pub message_id: Uuid,
     pub user_id: Uuid,
+    /// Events queued before organizations existed lack it; see
+    /// `organization`.
+    #[serde(default)]
+    pub organization_id: Option<Uuid>,
     pub messenger: MessengerType,
     pub recipient: String,
     pub message_type: MessageType,
     pub content: MessageContent,
+    #[serde(default)]
+    pub class: MessageClass,
+    #[serde(default)]
+    pub priority: MessagePriority,
     pub attempt: u32,
     pub max_attempts: u32,
+    /// Fixed when the message is scheduled so every worker waits the same
+    /// way between attempts.
+    #[serde(default)]
+    pub retry_backoff: RetryBackoff,
     pub scheduled_at: DateTime<Utc>,
+    #[serde(default)]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Token picked when the message was scheduled; `None` uses the latest.
+    #[serde(default)]
+    pub token_id: Option<Uuid>,
+    /// W3C trace context (`traceparent`, `tracestate`) of whatever queued
+    /// the event, so its trace carries on through the worker.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub trace_context: HashMap<String, String>,
+}
+
+impl OutboundMessageEvent {
+    /// Organization whose tokens deliver the message: the personal one of
+    /// the sender when the event does not name one.
+    pub fn organization(&self) -> Uuid {
+        self.organization_id.unwrap_or(self.user_id)
+    }
+
+    pub fn deadline_exceeded(&self, now: DateTime<Utc>) -> bool {
+        self.deadline.is_some_and(|deadline| now >= deadline)
+    }
+}
+
+/// An event that exhausted its attempts, as stored in the dead-letter queue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterEvent {
+    pub event: OutboundMessageEvent,
+    pub reason: String,
+    pub failed_at: DateTime<Utc>,
 }
@@ -0,0 +1,64 @@
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Lets a service act as the user without a browser session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    /// Start of the key, kept so the user can tell their keys apart.
+    pub prefix: String,
+    /// SHA-256 of the key; the key itself is shown once and not stored.
+    pub key_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub scopes: Vec<ApiKeyScope>,
+    /// Networks the key may be used from; any when empty.
+    pub allowed_ips: Vec<IpNet>,
+}
+
+impl ApiKey {
+    /// An unknown address only passes a key without an allowlist.
+    pub fn allows(&self, ip: Option<IpAddr>) -> bool {
+        self.allowed_ips.is_empty()
+            || ip.is_some_and(|ip| self.allowed_ips.iter().any(|net| net.contains(&ip)))
+    }
+}
+
+/// What a key may be used for, on top of what its user's role allows.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ApiKeyScope {
+    MessagesSend,
+    MessagesRead,
+    TokensManage,
+}
+
+impl ApiKeyScope {
+    pub const ALL: [ApiKeyScope; 3] = [
+        ApiKeyScope::MessagesSend,
+        ApiKeyScope::MessagesRead,
+        ApiKeyScope::TokensManage,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiKeyScope::MessagesSend => "messages:send",
+            ApiKeyScope::MessagesRead => "messages:read",
+            ApiKeyScope::TokensManage => "tokens:manage",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "messages:send" => Some(ApiKeyScope::MessagesSend),
+            "messages:read" => Some(ApiKeyScope::MessagesRead),
+            "tokens:manage" => Some(ApiKeyScope::TokensManage),
+            _ => None,
+        }
+    }
+}
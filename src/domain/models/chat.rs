@@ -19,3 +19,12 @@ pub struct MessengerChat {
     pub chat_type: MessengerChatType,
     pub can_send_messages: bool,
 }
+
+/// Transient status shown in the chat, e.g. "typing…", until the next
+/// message arrives or the platform times it out.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChatAction {
+    Typing,
+    UploadPhoto,
+}
@@ -0,0 +1,27 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::messenger::MessengerType;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ConsentStatus {
+    /// Double opt-in confirmation was sent but not answered yet.
+    Pending,
+    Confirmed,
+    Revoked,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsentRecord {
+    pub id: Uuid,
+    pub organization_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub status: ConsentStatus,
+    pub source: String,
+    pub proof: Option<String>,
+    pub confirmation_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+
+use crate::domain::models::MessengerType;
+
+/// Outcome counts of a group of messages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeliveryCounts {
+    pub total: u64,
+    pub sent: u64,
+    pub failed: u64,
+}
+
+impl DeliveryCounts {
+    /// Share of the finished messages that were sent; unset while none
+    /// has finished.
+    pub fn success_rate(&self) -> Option<f64> {
+        let finished = self.sent + self.failed;
+        (finished > 0).then(|| self.sent as f64 / finished as f64)
+    }
+
+    fn add(&mut self, other: &DeliveryCounts) {
+        self.total += other.total;
+        self.sent += other.sent;
+        self.failed += other.failed;
+    }
+}
+
+/// Counts of the messages created on one UTC day through one messenger.
+#[derive(Debug, Clone)]
+pub struct DeliveryStatsBucket {
+    pub day: NaiveDate,
+    pub messenger: MessengerType,
+    pub counts: DeliveryCounts,
+}
+
+/// A user's messages over a window, bucketed by day and messenger.
+#[derive(Debug, Clone)]
+pub struct DeliveryStats {
+    pub buckets: Vec<DeliveryStatsBucket>,
+}
+
+impl DeliveryStats {
+    pub fn by_messenger(&self) -> Vec<(MessengerType, DeliveryCounts)> {
+        let mut totals: Vec<(MessengerType, DeliveryCounts)> = Vec::new();
+        for bucket in &self.buckets {
+            match totals
+                .iter_mut()
+                .find(|(messenger, _)| *messenger == bucket.messenger)
+            {
+                Some((_, counts)) => counts.add(&bucket.counts),
+                None => totals.push((bucket.messenger, bucket.counts)),
+            }
+        }
+        totals
+    }
+
+    /// Ascending by day.
+    pub fn by_day(&self) -> Vec<(NaiveDate, DeliveryCounts)> {
+        let mut totals: BTreeMap<NaiveDate, DeliveryCounts> = BTreeMap::new();
+        for bucket in &self.buckets {
+            totals.entry(bucket.day).or_default().add(&bucket.counts);
+        }
+        totals.into_iter().collect()
+    }
+
+    pub fn total(&self) -> DeliveryCounts {
+        let mut total = DeliveryCounts::default();
+        for bucket in &self.buckets {
+            total.add(&bucket.counts);
+        }
+        total
+    }
+}
@@ -0,0 +1,17 @@
+use uuid::Uuid;
+
+/// Outcome of recording that a delivery is about to call the messenger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispatchClaim {
+    /// No earlier delivery of the message reached the messenger.
+    Claimed,
+    /// An earlier delivery sent the message but stopped before recording it.
+    AlreadySent,
+    /// An earlier delivery called the messenger and never learned the
+    /// outcome, so the message may or may not have been sent.
+    Interrupted {
+        /// Worker holding the earlier claim; unset for claims recorded
+        /// before workers were tracked.
+        worker_id: Option<Uuid>,
+    },
+}
@@ -0,0 +1,18 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The audit record of an account deletion. It outlives the user and keeps
+/// at most a keyed hash of their email, enough to tell whether an address
+/// was erased.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountErasure {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// `None` when no `ERASURE_EMAIL_KEY` was set.
+    pub email_hash: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    /// Set once the history is purged and the user deleted.
+    pub completed_at: Option<DateTime<Utc>>,
+    pub messages_purged: u64,
+}
@@ -0,0 +1,15 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A client-supplied `Idempotency-Key` and the message it produced.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    pub organization_id: Uuid,
+    pub key: String,
+    /// Fingerprint of the request the key was first used with.
+    pub request_hash: String,
+    /// `None` while the first request with this key is still being handled.
+    pub message_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A way of signing in that leads to the user's account.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserIdentity {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// Name of the auth provider, e.g. `oidc`.
+    pub provider: String,
+    /// The provider's stable id for the person: the email for email and
+    /// password sign-in, the `sub` claim for OIDC.
+    pub subject: String,
+    pub created_at: DateTime<Utc>,
+}
@@ -9,15 +9,151 @@ pub enum MessageType {
     PlainText,
 }
 
+/// Transactional messages are triggered by the recipient's own actions;
+/// marketing messages are subject to consent and throttling policies.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageClass {
+    #[default]
+    Transactional,
+    Marketing,
+}
+
+impl MessageClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageClass::Transactional => "transactional",
+            MessageClass::Marketing => "marketing",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "transactional" => Some(MessageClass::Transactional),
+            "marketing" => Some(MessageClass::Marketing),
+            _ => None,
+        }
+    }
+}
+
+/// Order in which queued messages are picked up; higher priorities are
+/// drained first.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum MessagePriority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl MessagePriority {
+    pub const ALL: [MessagePriority; 3] = [
+        MessagePriority::High,
+        MessagePriority::Normal,
+        MessagePriority::Low,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessagePriority::High => "high",
+            MessagePriority::Normal => "normal",
+            MessagePriority::Low => "low",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "high" => Some(MessagePriority::High),
+            "normal" => Some(MessagePriority::Normal),
+            "low" => Some(MessagePriority::Low),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MessageStatus {
     Pending,
     Scheduled,
     InFlight,
     Sent,
-    Retrying { reason: String, attempts: u32 },
-    Failed { reason: String, attempts: u32 },
+    Retrying {
+        reason: String,
+        attempts: u32,
+    },
+    Failed {
+        reason: String,
+        attempts: u32,
+    },
     Cancelled,
+    /// Accepted but held back by a policy until `scheduled_for`.
+    Deferred {
+        reason: String,
+    },
+    /// Accepted but never sent because a policy rejected it.
+    Suppressed {
+        reason: String,
+    },
+}
+
+/// A status without its details, for filtering by status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageStatusKind {
+    Pending,
+    Scheduled,
+    InFlight,
+    Sent,
+    Retrying,
+    Failed,
+    Cancelled,
+    Deferred,
+    Suppressed,
+}
+
+impl MessageStatus {
+    /// Whether delivery is over and the status won't change on its own.
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            MessageStatus::Sent
+                | MessageStatus::Failed { .. }
+                | MessageStatus::Cancelled
+                | MessageStatus::Suppressed { .. }
+        )
+    }
+}
+
+impl MessageStatusKind {
+    pub const ALL: [MessageStatusKind; 9] = [
+        MessageStatusKind::Pending,
+        MessageStatusKind::Scheduled,
+        MessageStatusKind::InFlight,
+        MessageStatusKind::Sent,
+        MessageStatusKind::Retrying,
+        MessageStatusKind::Failed,
+        MessageStatusKind::Cancelled,
+        MessageStatusKind::Deferred,
+        MessageStatusKind::Suppressed,
+    ];
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == value)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageStatusKind::Pending => "pending",
+            MessageStatusKind::Scheduled => "scheduled",
+            MessageStatusKind::InFlight => "in_flight",
+            MessageStatusKind::Sent => "sent",
+            MessageStatusKind::Retrying => "retrying",
+            MessageStatusKind::Failed => "failed",
+            MessageStatusKind::Cancelled => "cancelled",
+            MessageStatusKind::Deferred => "deferred",
+            MessageStatusKind::Suppressed => "suppressed",
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,15 +165,79 @@ pub struct MessageContent {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessageHistoryEntry {
     pub id: Uuid,
-    pub user_id: Uuid,
+    pub organization_id: Uuid,
+    /// Who sent it; `None` once their account was erased.
+    pub user_id: Option<Uuid>,
     pub messenger: MessengerType,
     pub recipient: String,
     pub content: MessageContent,
+    pub class: MessageClass,
+    pub priority: MessagePriority,
     pub status: MessageStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub attempts: u32,
     pub requested_by: RequestedBy,
+    pub deadline: Option<DateTime<Utc>>,
+    pub scheduled_for: DateTime<Utc>,
+    /// Token chosen for delivery; `None` uses the latest active one.
+    pub token_id: Option<Uuid>,
+    /// Labels the sender attached, e.g. `billing`.
+    pub tags: Vec<String>,
+    /// Set once the sender deleted it from their history.
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+impl MessageHistoryEntry {
+    /// Who tokens are looked up for: the sender, or the organization once
+    /// the sender's account is gone, which leaves only its shared tokens.
+    pub fn sender(&self) -> Uuid {
+        self.user_id.unwrap_or(self.organization_id)
+    }
+}
+
+/// Narrows a message listing; unset fields match every message.
+#[derive(Debug, Clone, Default)]
+pub struct MessageHistoryFilter {
+    pub status: Option<MessageStatusKind>,
+    pub messenger: Option<MessengerType>,
+    /// Case-insensitive part of the recipient.
+    pub recipient: Option<String>,
+    /// Inclusive lower bound on `created_at`.
+    pub created_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on `created_at`.
+    pub created_before: Option<DateTime<Utc>>,
+    /// Exact tag the message must carry.
+    pub tag: Option<String>,
+}
+
+/// Selects failed messages of every user for requeueing; unset fields
+/// match every failed message.
+#[derive(Debug, Clone, Default)]
+pub struct FailedMessageFilter {
+    pub messenger: Option<MessengerType>,
+    /// Inclusive lower bound on when the message failed.
+    pub failed_after: Option<DateTime<Utc>>,
+    /// Exclusive upper bound on when the message failed.
+    pub failed_before: Option<DateTime<Utc>>,
+    /// Case-insensitive part of the failure reason.
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NewMessageHistoryEntry {
+    pub organization_id: Uuid,
+    pub user_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub content: MessageContent,
+    pub class: MessageClass,
+    pub priority: MessagePriority,
+    pub requested_by: RequestedBy,
+    pub deadline: Option<DateTime<Utc>>,
+    pub scheduled_for: DateTime<Utc>,
+    pub token_id: Option<Uuid>,
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,43 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Reads as "source `kind` target", e.g. the source message was retried from
+/// the target, or the target is the thread parent of the source.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageLinkKind {
+    RetriedFrom,
+    EscalatedFrom,
+    ThreadParent,
+    DigestMember,
+}
+
+impl MessageLinkKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageLinkKind::RetriedFrom => "retried_from",
+            MessageLinkKind::EscalatedFrom => "escalated_from",
+            MessageLinkKind::ThreadParent => "thread_parent",
+            MessageLinkKind::DigestMember => "digest_member",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "retried_from" => Some(MessageLinkKind::RetriedFrom),
+            "escalated_from" => Some(MessageLinkKind::EscalatedFrom),
+            "thread_parent" => Some(MessageLinkKind::ThreadParent),
+            "digest_member" => Some(MessageLinkKind::DigestMember),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageLink {
+    pub source_id: Uuid,
+    pub target_id: Uuid,
+    pub kind: MessageLinkKind,
+    pub created_at: DateTime<Utc>,
+}
@@ -8,6 +8,8 @@ pub enum MessengerType {
 }
 
 impl MessengerType {
+    pub const ALL: [MessengerType; 2] = [MessengerType::Telegram, MessengerType::Vk];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             MessengerType::Telegram => "telegram",
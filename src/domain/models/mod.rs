@@ -1,13 +1,49 @@
+pub mod api_key;
 pub mod chat;
+pub mod consent;
+pub mod delivery_stats;
+pub mod dispatch_guard;
+pub mod erasure;
+pub mod idempotency;
+pub mod identity;
 pub mod message;
+pub mod message_link;
 pub mod messenger;
+pub mod organization;
+pub mod otp;
+pub mod overview;
+pub mod password;
+pub mod refresh_token;
+pub mod retry;
+pub mod routing;
+pub mod session;
 pub mod token;
 pub mod user;
+pub mod worker;
 
-pub use chat::{MessengerChat, MessengerChatType};
+pub use api_key::{ApiKey, ApiKeyScope};
+pub use chat::{ChatAction, MessengerChat, MessengerChatType};
+pub use consent::{ConsentRecord, ConsentStatus};
+pub use delivery_stats::{DeliveryCounts, DeliveryStats, DeliveryStatsBucket};
+pub use dispatch_guard::DispatchClaim;
+pub use erasure::AccountErasure;
+pub use idempotency::IdempotencyRecord;
+pub use identity::UserIdentity;
 pub use message::{
-    MessageAttempt, MessageContent, MessageHistoryEntry, MessageStatus, MessageType, RequestedBy,
+    FailedMessageFilter, MessageAttempt, MessageClass, MessageContent, MessageHistoryEntry,
+    MessageHistoryFilter, MessagePriority, MessageStatus, MessageStatusKind, MessageType,
+    NewMessageHistoryEntry, RequestedBy,
 };
+pub use message_link::{MessageLink, MessageLinkKind};
 pub use messenger::MessengerType;
-pub use token::{MessengerToken, MessengerTokenStatus};
-pub use user::User;
+pub use organization::{MemberRole, Organization, OrganizationInvitation, OrganizationMember};
+pub use otp::OtpCode;
+pub use overview::{AttemptOutcomes, Backlog, TokenHealth};
+pub use password::PasswordCredential;
+pub use refresh_token::RefreshToken;
+pub use retry::RetryBackoff;
+pub use routing::{RoutedMessage, RoutingInput, RoutingRule, RoutingRuleDiff, RoutingRuleVersion};
+pub use session::{Session, SessionClient};
+pub use token::{MessengerToken, MessengerTokenStatus, TokenSelector};
+pub use user::{User, UserRole};
+pub use worker::{Worker, WorkerStats};
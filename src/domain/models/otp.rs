@@ -0,0 +1,19 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::messenger::MessengerType;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtpCode {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub code_hash: String,
+    pub message_id: Uuid,
+    pub failed_attempts: u32,
+    pub expires_at: DateTime<Utc>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+
+use crate::domain::models::{MessageStatusKind, MessengerType};
+
+/// Messages of every organization that are not delivered yet.
+#[derive(Debug, Clone, Default)]
+pub struct Backlog {
+    /// Counts per undelivered status; statuses without messages are left out.
+    pub by_status: Vec<(MessageStatusKind, u64)>,
+    /// When the longest-waiting message that is due became due.
+    pub oldest_due_at: Option<DateTime<Utc>>,
+}
+
+impl Backlog {
+    pub fn count(&self, kind: MessageStatusKind) -> u64 {
+        self.by_status
+            .iter()
+            .filter(|(status, _)| *status == kind)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
+
+/// Send attempts of every organization that ended over a window.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AttemptOutcomes {
+    pub sent: u64,
+    /// Attempts that failed, whether or not the message is retried.
+    pub failed: u64,
+}
+
+impl AttemptOutcomes {
+    /// Share of the attempts that failed; unset while none ended.
+    pub fn failure_rate(&self) -> Option<f64> {
+        let ended = self.sent + self.failed;
+        (ended > 0).then(|| self.failed as f64 / ended as f64)
+    }
+}
+
+/// The tokens of one messenger, across organizations.
+#[derive(Debug, Clone)]
+pub struct TokenHealth {
+    pub messenger: MessengerType,
+    pub active: u64,
+    pub inactive: u64,
+    /// Active tokens the platform no longer accepts.
+    pub expired: u64,
+    /// Active tokens that expire soon.
+    pub expiring: u64,
+}
@@ -0,0 +1,11 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordCredential {
+    pub user_id: Uuid,
+    /// Argon2 hash in PHC string format.
+    pub password_hash: String,
+    pub updated_at: DateTime<Utc>,
+}
@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A refresh token is good for one refresh, which replaces it with the next
+/// token of the same family. Each family belongs to one session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    /// The id of the session the token belongs to.
+    pub family_id: Uuid,
+    pub user_id: Uuid,
+    /// SHA-256 of the token; the token itself lives only in the cookie.
+    pub token_hash: String,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Set once the token was exchanged for its successor.
+    pub used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Wait before the next attempt: `base · multiplier^(attempt - 1)`, spread by
+/// up to ±`jitter` of itself so failed sends don't retry in lockstep, and
+/// never longer than `max_ms`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct RetryBackoff {
+    pub base_ms: u64,
+    pub multiplier: f64,
+    /// Fraction of the delay, from 0 (none) to 1.
+    pub jitter: f64,
+    pub max_ms: u64,
+}
+
+impl Default for RetryBackoff {
+    fn default() -> Self {
+        Self {
+            base_ms: 5_000,
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_ms: 300_000,
+        }
+    }
+}
+
+impl RetryBackoff {
+    pub fn new(base_ms: u64, multiplier: f64, jitter: f64, max_ms: u64) -> anyhow::Result<Self> {
+        if multiplier < 1.0 {
+            anyhow::bail!("retry backoff multiplier must be at least 1");
+        }
+        if !(0.0..=1.0).contains(&jitter) {
+            anyhow::bail!("retry backoff jitter must be between 0 and 1");
+        }
+        if base_ms > max_ms {
+            anyhow::bail!("retry backoff base must not exceed the maximum");
+        }
+        Ok(Self {
+            base_ms,
+            multiplier,
+            jitter,
+            max_ms,
+        })
+    }
+
+    /// Delay after the given attempt failed.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let nominal = self.nominal_delay(attempt).as_millis() as f64;
+        let spread = self.jitter.clamp(0.0, 1.0) * (2.0 * unit_random() - 1.0);
+        Duration::from_millis((nominal * (1.0 + spread)).clamp(0.0, self.max_ms as f64) as u64)
+    }
+
+    /// Delay after the given attempt failed, before jitter is applied.
+    pub fn nominal_delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(64) as i32;
+        let nominal =
+            (self.base_ms as f64 * self.multiplier.powi(exponent)).min(self.max_ms as f64);
+        Duration::from_millis(nominal as u64)
+    }
+}
+
+/// Uniform in `[0, 1)` from the top 48 bits of a v4 UUID, which are random.
+fn unit_random() -> f64 {
+    (Uuid::new_v4().as_u128() >> 80) as f64 / (1u64 << 48) as f64
+}
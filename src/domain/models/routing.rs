@@ -0,0 +1,187 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::{
+    message::{MessageClass, MessagePriority},
+    messenger::MessengerType,
+};
+
+/// Chooses where a send that names no destination goes. A user's rules are
+/// tried by ascending `position`; the first enabled one that matches wins.
+/// Business events instead fire every rule that matches.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoutingRule {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub enabled: bool,
+    /// Name of the business event the rule reacts to. Rules with an event
+    /// name only match events, rules without one only match sends.
+    pub match_event: Option<String>,
+    /// Matches any class when unset.
+    pub match_class: Option<MessageClass>,
+    /// Tags the message must all carry.
+    pub match_tags: Vec<String>,
+    /// Metadata entries the message must all carry with these values.
+    pub match_metadata: BTreeMap<String, String>,
+    pub messenger: MessengerType,
+    /// `{key}` placeholders are filled from the message metadata.
+    pub recipient: String,
+    /// Replaces the message text; `{text}` stands for the text sent and
+    /// `{key}` for metadata values.
+    pub template: Option<String>,
+    /// Overrides the priority of the send.
+    pub priority: Option<MessagePriority>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What routing rules match on.
+#[derive(Debug, Clone)]
+pub struct RoutingInput {
+    /// Set for business events, unset for sends.
+    pub event: Option<String>,
+    pub class: MessageClass,
+    pub tags: Vec<String>,
+    pub metadata: BTreeMap<String, String>,
+    pub text: String,
+}
+
+/// Destination and content chosen by a rule.
+#[derive(Debug, Clone)]
+pub struct RoutedMessage {
+    pub messenger: MessengerType,
+    pub recipient: String,
+    pub text: String,
+    pub priority: Option<MessagePriority>,
+}
+
+/// A snapshot of a user's whole rule set. Every change to the rules records
+/// a new version; the active one is what the rules table holds.
+#[derive(Debug, Clone)]
+pub struct RoutingRuleVersion {
+    /// Starts at 1 and grows by one per snapshot.
+    pub version: i32,
+    pub rules: Vec<RoutingRule>,
+    /// What produced the snapshot, e.g. `rule created`.
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What it takes to go from one rule set to another.
+#[derive(Debug, Clone, Default)]
+pub struct RoutingRuleDiff {
+    pub added: Vec<RoutingRule>,
+    pub removed: Vec<RoutingRule>,
+    pub changed: Vec<RoutingRuleChange>,
+}
+
+/// A rule present on both sides whose settings differ.
+#[derive(Debug, Clone)]
+pub struct RoutingRuleChange {
+    pub rule_id: Uuid,
+    /// Name on the newer side.
+    pub name: String,
+    pub fields: Vec<&'static str>,
+}
+
+impl RoutingRuleDiff {
+    /// Rules are paired by id; timestamps are not compared.
+    pub fn between(from: &[RoutingRule], to: &[RoutingRule]) -> Self {
+        let mut diff = Self::default();
+        for rule in to {
+            match from.iter().find(|old| old.id == rule.id) {
+                Some(old) => {
+                    let fields = old.changed_fields(rule);
+                    if !fields.is_empty() {
+                        diff.changed.push(RoutingRuleChange {
+                            rule_id: rule.id,
+                            name: rule.name.clone(),
+                            fields,
+                        });
+                    }
+                }
+                None => diff.added.push(rule.clone()),
+            }
+        }
+        diff.removed = from
+            .iter()
+            .filter(|old| to.iter().all(|rule| rule.id != old.id))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl RoutingRule {
+    pub fn matches(&self, input: &RoutingInput) -> bool {
+        self.enabled
+            && self.match_event == input.event
+            && self.match_class.is_none_or(|class| class == input.class)
+            && self.match_tags.iter().all(|tag| input.tags.contains(tag))
+            && self
+                .match_metadata
+                .iter()
+                .all(|(key, value)| input.metadata.get(key) == Some(value))
+    }
+
+    /// Fails when the recipient or text would be left unusable, which
+    /// means the metadata lacks what the rule refers to.
+    pub fn apply(&self, input: &RoutingInput) -> anyhow::Result<RoutedMessage> {
+        let text = match &self.template {
+            Some(template) => render(&template.replace("{text}", &input.text), &input.metadata),
+            None => input.text.clone(),
+        };
+        let recipient = render(&self.recipient, &input.metadata);
+        if recipient.trim().is_empty() || recipient.contains('{') {
+            anyhow::bail!(
+                "routing rule {} left the recipient unresolved: metadata is missing",
+                self.id
+            );
+        }
+        if text.trim().is_empty() {
+            anyhow::bail!("routing rule {} produced an empty message", self.id);
+        }
+        Ok(RoutedMessage {
+            messenger: self.messenger,
+            recipient,
+            text,
+            priority: self.priority,
+        })
+    }
+
+    fn changed_fields(&self, other: &RoutingRule) -> Vec<&'static str> {
+        [
+            ("name", self.name != other.name),
+            ("position", self.position != other.position),
+            ("enabled", self.enabled != other.enabled),
+            ("match_event", self.match_event != other.match_event),
+            ("match_class", self.match_class != other.match_class),
+            ("match_tags", self.match_tags != other.match_tags),
+            (
+                "match_metadata",
+                self.match_metadata != other.match_metadata,
+            ),
+            ("messenger", self.messenger != other.messenger),
+            ("recipient", self.recipient != other.recipient),
+            ("template", self.template != other.template),
+            ("priority", self.priority != other.priority),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(field, _)| field)
+        .collect()
+    }
+}
+
+fn render(template: &str, metadata: &BTreeMap<String, String>) -> String {
+    metadata
+        .iter()
+        .fold(template.to_string(), |rendered, (key, value)| {
+            rendered.replace(&format!("{{{key}}}"), value)
+        })
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// One sign-in of a user. Its refresh tokens form one family, and the
+/// access tokens issued from them name it, so revoking the session signs
+/// that device out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// The `User-Agent` the session was started with.
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// When the session last refreshed its tokens.
+    pub last_seen_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Where a sign-in comes from, as recorded on its session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionClient {
+    pub device: Option<String>,
+    pub ip: Option<String>,
+}
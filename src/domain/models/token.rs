@@ -7,10 +7,22 @@ use super::messenger::MessengerType;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MessengerToken {
     pub id: Uuid,
+    /// Organization whose members send with the token.
+    pub organization_id: Uuid,
+    /// Who registered it.
     pub user_id: Uuid,
+    /// Whether every member of the organization may send with it; otherwise
+    /// only `user_id` may.
+    pub shared: bool,
     pub messenger: MessengerType,
+    /// Tells apart several tokens of the same messenger, e.g. two bots.
+    pub label: Option<String>,
     pub access_token: String,
     pub refresh_token: Option<String>,
+    /// When the platform stops accepting `access_token`, if it said.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Device the credential was issued to; VK ID binds refresh tokens to it.
+    pub device_id: Option<String>,
     pub status: MessengerTokenStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
@@ -21,3 +33,19 @@ pub enum MessengerTokenStatus {
     Active,
     Inactive,
 }
+
+/// Which of an organization's active tokens for a messenger delivers a message.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TokenSelector {
+    /// The most recently updated one.
+    #[default]
+    Latest,
+    Id(Uuid),
+    Label(String),
+}
+
+impl TokenSelector {
+    pub fn from_token_id(token_id: Option<Uuid>) -> Self {
+        token_id.map(Self::Id).unwrap_or_default()
+    }
+}
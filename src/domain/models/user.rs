@@ -7,6 +7,45 @@ pub struct User {
     pub id: Uuid,
     pub email: String,
     pub display_name: Option<String>,
+    pub role: UserRole,
+    /// Set while an admin has the account disabled; it can't sign in or use
+    /// its API keys.
+    pub disabled_at: Option<DateTime<Utc>>,
+    /// Set once the user proved `email` is theirs. Password sign-ups start
+    /// without it; only confirmed accounts take in other sign-in methods by
+    /// email.
+    pub email_verified_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
+
+/// What a user, and the API keys acting for them, may do.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum UserRole {
+    /// Operates the service and may act on every user's data.
+    Admin,
+    #[default]
+    Member,
+    /// May look at their data but not send or change anything.
+    ReadOnly,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::Member => "member",
+            UserRole::ReadOnly => "read_only",
+        }
+    }
+
+    pub fn from_str(value: &str) -> Option<Self> {
+        match value {
+            "admin" => Some(UserRole::Admin),
+            "member" => Some(UserRole::Member),
+            "read_only" => Some(UserRole::ReadOnly),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+/// A dispatcher instance as of its last heartbeat.
+#[derive(Debug, Clone)]
+pub struct Worker {
+    /// Fresh on every start, so a restarted process is a new worker.
+    pub id: Uuid,
+    pub hostname: String,
+    pub version: String,
+    /// The message bus the worker consumes from.
+    pub bus: String,
+    pub started_at: DateTime<Utc>,
+    pub heartbeat_at: DateTime<Utc>,
+    pub stats: WorkerStats,
+}
+
+/// Deliveries the worker has handled since it started.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WorkerStats {
+    pub in_flight: u64,
+    pub handled: u64,
+    pub failed: u64,
+}
+
+impl Worker {
+    /// A worker that missed heartbeats for `ttl` is taken for dead.
+    pub fn is_alive(&self, now: DateTime<Utc>, ttl: Duration) -> bool {
+        now - self.heartbeat_at < ttl
+    }
+}
@@ -1,39 +1,63 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::mpsc::Sender;
 use uuid::Uuid;
 
 use crate::domain::models::{
-    MessageAttempt, MessageContent, MessageHistoryEntry, MessageStatus, MessengerToken,
-    MessengerType, RequestedBy, User,
+    AccountErasure, ApiKey, AttemptOutcomes, Backlog, ConsentRecord, DeliveryStatsBucket,
+    DispatchClaim, FailedMessageFilter, IdempotencyRecord, MemberRole, MessageAttempt,
+    MessageClass, MessageHistoryEntry, MessageHistoryFilter, MessageLink, MessageStatus,
+    MessageStatusKind, MessengerToken, MessengerType, NewMessageHistoryEntry, Organization,
+    OrganizationInvitation, OrganizationMember, OtpCode, PasswordCredential, RefreshToken,
+    RequestedBy, RoutingRule, RoutingRuleVersion, Session, TokenHealth, TokenSelector, User,
+    UserIdentity, Worker,
 };
 
 #[async_trait]
 pub trait UserRepository: Send + Sync {
     async fn find_by_email(&self, email: &str) -> anyhow::Result<Option<User>>;
     async fn get(&self, id: &Uuid) -> anyhow::Result<Option<User>>;
+    /// Users whose email or display name contains `search`, oldest first,
+    /// and whether more follow.
+    async fn list(
+        &self,
+        search: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<User>, bool)>;
+    /// Also creates the user's personal organization the first time.
     async fn upsert(&self, user: &User) -> anyhow::Result<()>;
 }
 
 #[async_trait]
 pub trait MessengerTokenRepository: Send + Sync {
     async fn upsert(&self, token: MessengerToken) -> anyhow::Result<MessengerToken>;
+    /// Picks among the organization's tokens `sender` may use: the ones they
+    /// registered and the shared ones.
     async fn find_active(
         &self,
-        user_id: &Uuid,
+        organization_id: &Uuid,
+        sender: &Uuid,
         messenger: MessengerType,
+        selector: &TokenSelector,
     ) -> anyhow::Result<Option<MessengerToken>>;
-    async fn list_by_user(&self, user_id: &Uuid) -> anyhow::Result<Vec<MessengerToken>>;
+    async fn list_by_organization(
+        &self,
+        organization_id: &Uuid,
+    ) -> anyhow::Result<Vec<MessengerToken>>;
+
+    /// Token counts of every organization per messenger. Active tokens
+    /// expiring between `now` and `expiring_before` count as expiring.
+    async fn health(
+        &self,
+        now: DateTime<Utc>,
+        expiring_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TokenHealth>>;
 }
 
 #[async_trait]
 pub trait MessageHistoryRepository: Send + Sync {
-    async fn insert(
-        &self,
-        user_id: Uuid,
-        messenger: MessengerType,
-        recipient: String,
-        content: MessageContent,
-        requested_by: RequestedBy,
-    ) -> anyhow::Result<MessageHistoryEntry>;
+    async fn insert(&self, entry: NewMessageHistoryEntry) -> anyhow::Result<MessageHistoryEntry>;
 
     async fn update_status(
         &self,
@@ -44,13 +68,85 @@ pub trait MessageHistoryRepository: Send + Sync {
 
     async fn get(&self, message_id: Uuid) -> anyhow::Result<Option<MessageHistoryEntry>>;
 
-    async fn list_by_user(
+    /// Send slots of the chat's messages in `class` scheduled after `since`,
+    /// ascending. Suppressed and cancelled messages don't occupy a slot.
+    async fn scheduled_times_since(
         &self,
-        user_id: Uuid,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        class: MessageClass,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DateTime<Utc>>>;
+
+    async fn list_by_organization(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)>;
+
+    /// Streams every message matching `filter` into `sink`, newest first.
+    /// Stops early once the receiving side is dropped.
+    async fn export(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
+        sink: Sender<anyhow::Result<MessageHistoryEntry>>,
+    ) -> anyhow::Result<()>;
+
+    /// Messages sent to exactly this chat, newest first.
+    async fn list_by_recipient(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
         limit: Option<u32>,
         offset: Option<u32>,
     ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)>;
 
+    /// How many of the organization's messages are in each status;
+    /// statuses without messages are left out.
+    async fn count_by_status(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<(MessageStatusKind, u64)>>;
+
+    /// Undelivered messages of every organization; messages scheduled
+    /// after `now` are counted but not yet due.
+    async fn backlog(&self, now: DateTime<Utc>) -> anyhow::Result<Backlog>;
+
+    /// Outcomes of the send attempts of every organization recorded since
+    /// `since`.
+    async fn attempt_outcomes(&self, since: DateTime<Utc>) -> anyhow::Result<AttemptOutcomes>;
+
+    /// Counts of the organization's messages created in `[from, to)`,
+    /// bucketed by UTC day and messenger.
+    async fn delivery_stats(
+        &self,
+        organization_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>>;
+
+    /// As [`Self::delivery_stats`], for the messages `user_id` sent in any
+    /// organization.
+    async fn sender_delivery_stats(
+        &self,
+        user_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>>;
+
+    /// Failed messages of any user matching the filter, oldest failure
+    /// first, at most `limit`.
+    async fn list_failed(
+        &self,
+        filter: &FailedMessageFilter,
+        limit: u32,
+    ) -> anyhow::Result<Vec<MessageHistoryEntry>>;
+
     async fn log_attempt(
         &self,
         message_id: Uuid,
@@ -59,5 +155,356 @@ pub trait MessageHistoryRepository: Send + Sync {
         requested_by: RequestedBy,
     ) -> anyhow::Result<()>;
 
+    /// `update_status` and `log_attempt` in one transaction, so the message
+    /// and its attempt log can't disagree after a crash.
+    async fn record_attempt(
+        &self,
+        message_id: Uuid,
+        status: MessageStatus,
+        attempt_number: u32,
+        requested_by: RequestedBy,
+    ) -> anyhow::Result<()>;
+
     async fn get_attempts(&self, message_id: Uuid) -> anyhow::Result<Vec<MessageAttempt>>;
+
+    async fn redact(&self, message_id: Uuid) -> anyhow::Result<()>;
+
+    /// Hides the message from its organization's history. Returns `false`
+    /// when it was already deleted.
+    async fn soft_delete(&self, message_id: Uuid) -> anyhow::Result<bool>;
+
+    /// Deletes the message for good, with everything referring to it.
+    /// Returns `false` when there was no such message.
+    async fn purge(&self, message_id: Uuid) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+pub trait MessageLinkRepository: Send + Sync {
+    async fn insert(&self, link: &MessageLink) -> anyhow::Result<()>;
+
+    /// Links reachable from the message in either direction, up to `limit`.
+    async fn connected(&self, message_id: Uuid, limit: u32) -> anyhow::Result<Vec<MessageLink>>;
+}
+
+#[async_trait]
+pub trait ConsentRepository: Send + Sync {
+    async fn upsert(&self, record: &ConsentRecord) -> anyhow::Result<()>;
+
+    async fn find(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<Option<ConsentRecord>>;
+
+    async fn list_by_organization(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<ConsentRecord>>;
+}
+
+#[async_trait]
+pub trait OtpRepository: Send + Sync {
+    /// Stores a new code, dropping any unverified code for the same recipient.
+    async fn replace_active(&self, code: &OtpCode) -> anyhow::Result<()>;
+
+    async fn find_active(
+        &self,
+        user_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<Option<OtpCode>>;
+
+    /// Counts an attempt against the code before it is checked. Returns
+    /// `false` when the code has no attempts left or is already verified.
+    async fn reserve_attempt(&self, id: Uuid, max_attempts: u32) -> anyhow::Result<bool>;
+
+    /// Returns `false` when the code was already verified.
+    async fn mark_verified(&self, id: Uuid) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+pub trait IdempotencyKeyRepository: Send + Sync {
+    /// Stores the key without a message yet. Returns the existing record
+    /// instead when the user already used the key.
+    async fn claim(
+        &self,
+        organization_id: Uuid,
+        key: &str,
+        request_hash: &str,
+    ) -> anyhow::Result<Option<IdempotencyRecord>>;
+
+    async fn complete(
+        &self,
+        organization_id: Uuid,
+        key: &str,
+        message_id: Uuid,
+    ) -> anyhow::Result<()>;
+
+    /// Drops a claim whose request failed so the client can retry it.
+    async fn release(&self, organization_id: Uuid, key: &str) -> anyhow::Result<()>;
+}
+
+/// Records each `(message_id, attempt)` before the messenger is called so a
+/// redelivery after a crash can tell whether the message already went out.
+#[async_trait]
+pub trait DispatchGuardRepository: Send + Sync {
+    /// Claims the attempt for `worker_id` unless an earlier delivery of the
+    /// message already holds a guard.
+    async fn claim(
+        &self,
+        message_id: Uuid,
+        attempt: u32,
+        worker_id: Uuid,
+    ) -> anyhow::Result<DispatchClaim>;
+
+    /// Marks the message as sent by the messenger in `attempt`.
+    async fn mark_sent(&self, message_id: Uuid, attempt: u32) -> anyhow::Result<()>;
+
+    /// Drops unfinished guards of the message after the messenger reported
+    /// a failure, so a retry is free to send again.
+    async fn release(&self, message_id: Uuid) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+pub trait PasswordRepository: Send + Sync {
+    async fn find(&self, user_id: Uuid) -> anyhow::Result<Option<PasswordCredential>>;
+
+    /// Sets the password and drops the user's outstanding reset tokens.
+    async fn upsert(&self, user_id: Uuid, password_hash: &str) -> anyhow::Result<()>;
+
+    async fn create_reset(
+        &self,
+        token_hash: &str,
+        user_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<()>;
+
+    /// Deletes the reset token and returns its user, unless it expired.
+    async fn consume_reset(&self, token_hash: &str) -> anyhow::Result<Option<Uuid>>;
+}
+
+/// Single-use tokens mailed for passwordless sign-in.
+#[async_trait]
+pub trait LoginTokenRepository: Send + Sync {
+    async fn create(
+        &self,
+        token_hash: &str,
+        email: &str,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<()>;
+
+    /// Deletes the login token and returns its email, unless it expired.
+    async fn consume(&self, token_hash: &str) -> anyhow::Result<Option<String>>;
+}
+
+#[async_trait]
+pub trait IdentityRepository: Send + Sync {
+    async fn find(&self, provider: &str, subject: &str) -> anyhow::Result<Option<UserIdentity>>;
+
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<UserIdentity>>;
+
+    /// Returns `false` when the identity is already linked to an account.
+    async fn link(&self, identity: &UserIdentity) -> anyhow::Result<bool>;
+
+    /// Returns `false` when the identity is not the user's, or is the only
+    /// one they have left.
+    async fn unlink(&self, user_id: Uuid, identity_id: Uuid) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+pub trait ApiKeyRepository: Send + Sync {
+    async fn insert(&self, key: &ApiKey) -> anyhow::Result<()>;
+
+    /// The user's keys that were not revoked, oldest first.
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<ApiKey>>;
+
+    /// The key with this hash, unless it was revoked.
+    async fn find_active(&self, key_hash: &str) -> anyhow::Result<Option<ApiKey>>;
+
+    /// Revokes the key and stores `replacement` in its place, atomically.
+    /// Returns `false` when the user has no such active key.
+    async fn rotate(
+        &self,
+        user_id: Uuid,
+        key_id: Uuid,
+        replacement: &ApiKey,
+    ) -> anyhow::Result<bool>;
+
+    /// Returns `false` when the user has no such active key.
+    async fn revoke(&self, user_id: Uuid, key_id: Uuid) -> anyhow::Result<bool>;
+
+    async fn touch(&self, key_id: Uuid, used_at: DateTime<Utc>) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+pub trait RefreshTokenRepository: Send + Sync {
+    /// The token with this hash, used and revoked ones included.
+    async fn find(&self, token_hash: &str) -> anyhow::Result<Option<RefreshToken>>;
+
+    /// Marks the token used and stores `next` in its place, extending its
+    /// session, atomically. Returns `false` when the token was used or
+    /// revoked in the meantime.
+    async fn rotate(&self, token_id: Uuid, next: &RefreshToken) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+pub trait SessionRepository: Send + Sync {
+    /// Stores the session together with the first token of its family.
+    async fn insert(&self, session: &Session, token: &RefreshToken) -> anyhow::Result<()>;
+
+    /// The user's sessions that are neither revoked nor expired, most
+    /// recently used first.
+    async fn list_active(&self, user_id: Uuid) -> anyhow::Result<Vec<Session>>;
+
+    /// Revokes the session and its refresh tokens. Returns `false` when the
+    /// user has no such session that is not revoked yet.
+    async fn revoke(&self, user_id: Uuid, session_id: Uuid) -> anyhow::Result<bool>;
+
+    /// Revokes every session of the user and their refresh tokens.
+    async fn revoke_all(&self, user_id: Uuid) -> anyhow::Result<()>;
+
+    /// Ids of the sessions revoked at or after `since`, with when they were.
+    async fn revoked_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<(Uuid, DateTime<Utc>)>>;
+}
+
+#[async_trait]
+pub trait OrganizationRepository: Send + Sync {
+    /// Stores the organization together with its first member.
+    async fn create(
+        &self,
+        organization: &Organization,
+        owner: &OrganizationMember,
+    ) -> anyhow::Result<()>;
+
+    async fn get(&self, organization_id: Uuid) -> anyhow::Result<Option<Organization>>;
+
+    /// The organizations the user belongs to, with the user's role in each.
+    async fn list_for_user(&self, user_id: Uuid)
+    -> anyhow::Result<Vec<(Organization, MemberRole)>>;
+
+    async fn find_member(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> anyhow::Result<Option<OrganizationMember>>;
+
+    async fn list_members(&self, organization_id: Uuid) -> anyhow::Result<Vec<OrganizationMember>>;
+
+    async fn insert_invitation(&self, invitation: &OrganizationInvitation) -> anyhow::Result<()>;
+
+    /// Invitations not accepted yet, expired ones included, newest first.
+    async fn list_invitations(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<OrganizationInvitation>>;
+
+    async fn find_invitation(
+        &self,
+        token_hash: &str,
+    ) -> anyhow::Result<Option<OrganizationInvitation>>;
+
+    /// Marks the invitation accepted and adds `member`, atomically. Returns
+    /// `false` when it was accepted in the meantime.
+    async fn accept_invitation(
+        &self,
+        invitation_id: Uuid,
+        member: &OrganizationMember,
+    ) -> anyhow::Result<bool>;
+}
+
+#[async_trait]
+pub trait RoutingRuleRepository: Send + Sync {
+    /// The user's rules in evaluation order.
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<RoutingRule>>;
+
+    async fn get(&self, user_id: Uuid, rule_id: Uuid) -> anyhow::Result<Option<RoutingRule>>;
+
+    async fn upsert(&self, rule: &RoutingRule) -> anyhow::Result<()>;
+
+    /// Returns `false` when the user has no such rule.
+    async fn delete(&self, user_id: Uuid, rule_id: Uuid) -> anyhow::Result<bool>;
+
+    /// Records the user's current rules as the next version and makes it
+    /// the active one. Returns the new version number.
+    async fn snapshot(&self, user_id: Uuid, reason: &str) -> anyhow::Result<i32>;
+
+    /// Newest version first.
+    async fn list_versions(&self, user_id: Uuid) -> anyhow::Result<Vec<RoutingRuleVersion>>;
+
+    async fn get_version(
+        &self,
+        user_id: Uuid,
+        version: i32,
+    ) -> anyhow::Result<Option<RoutingRuleVersion>>;
+
+    /// `None` until the user's rules are first changed.
+    async fn active_version(&self, user_id: Uuid) -> anyhow::Result<Option<i32>>;
+
+    /// Replaces the user's rules with those of `version` and makes it the
+    /// active one. Returns `false` when the user has no such version.
+    async fn activate(&self, user_id: Uuid, version: i32) -> anyhow::Result<bool>;
+}
+
+/// Heartbeats of the dispatcher instances.
+#[async_trait]
+pub trait WorkerRepository: Send + Sync {
+    /// Records the worker's latest state, registering it on first call.
+    async fn heartbeat(&self, worker: &Worker) -> anyhow::Result<()>;
+
+    async fn get(&self, id: Uuid) -> anyhow::Result<Option<Worker>>;
+
+    /// Most recent heartbeat first.
+    async fn list(&self) -> anyhow::Result<Vec<Worker>>;
+
+    /// Forgets workers silent since `before`. Returns how many went.
+    async fn prune(&self, before: DateTime<Utc>) -> anyhow::Result<u64>;
+}
+
+/// Monthly partitions of message history and attempts.
+#[async_trait]
+pub trait HistoryPartitionRepository: Send + Sync {
+    /// Creates the partitions for the current month and `months_ahead`
+    /// months after it, when missing.
+    async fn ensure_partitions(&self, months_ahead: u32) -> anyhow::Result<()>;
+
+    /// Drops every partition whose month ended by `cutoff`, then removes
+    /// rows elsewhere that referred to the dropped messages. Returns the
+    /// dropped partitions.
+    async fn drop_partitions_before(&self, cutoff: DateTime<Utc>) -> anyhow::Result<Vec<String>>;
+}
+
+/// Account deletions: their audit records and the purge they drive.
+#[async_trait]
+pub trait AccountErasureRepository: Send + Sync {
+    /// Records the erasure, disables the user and deletes their
+    /// credentials: messenger tokens, API keys, password, linked identities
+    /// and reset tokens. Tokens they shared with an organization go to
+    /// another of its members instead, owners first, when there is one.
+    async fn start(&self, erasure: &AccountErasure) -> anyhow::Result<()>;
+
+    async fn find_pending(&self, user_id: Uuid) -> anyhow::Result<Option<AccountErasure>>;
+
+    /// Oldest request first.
+    async fn list_pending(&self) -> anyhow::Result<Vec<AccountErasure>>;
+
+    /// Deletes up to `limit` of the messages of the user's personal
+    /// organization, with everything referring to them, and counts them on
+    /// the erasure. Returns how many went.
+    async fn purge_messages(&self, erasure: &AccountErasure, limit: u32) -> anyhow::Result<u64>;
+
+    /// Removes the user as sender from up to `limit` of the messages they
+    /// sent in shared organizations, which keep them. Returns how many.
+    async fn anonymize_messages(&self, erasure: &AccountErasure, limit: u32)
+    -> anyhow::Result<u64>;
+
+    /// Deletes the user with their personal organization and whatever else
+    /// still refers to them, and marks the erasure completed.
+    async fn complete(&self, erasure: &AccountErasure) -> anyhow::Result<()>;
+
+    /// Most recent request first.
+    async fn list(&self, limit: u32) -> anyhow::Result<Vec<AccountErasure>>;
 }
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use anyhow::Context;
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
+use p256::elliptic_curve::sec1::ToEncodedPoint;
+use rsa::{
+    RsaPrivateKey, RsaPublicKey,
+    pkcs1::DecodeRsaPrivateKey,
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    traits::PublicKeyParts,
+};
+
+use crate::application::services::jwt::{JsonWebKey, JwtKey};
+
+/// Loads a key from `kid:algorithm:path`, e.g. `2025-01:RS256:/keys/jwt.pem`.
+/// The file holds the secret for HS256, HS384 and HS512, and a PEM key for
+/// RS256, RS384, RS512 and ES256: the private key to sign with, or just the
+/// public one to keep verifying tokens of a retired key.
+pub fn load_jwt_key(spec: &str) -> anyhow::Result<JwtKey> {
+    let mut parts = spec.splitn(3, ':');
+    let (Some(kid), Some(algorithm), Some(path)) = (parts.next(), parts.next(), parts.next())
+    else {
+        anyhow::bail!("invalid JWT key {spec}, expected kid:algorithm:path");
+    };
+    let kid = kid.trim().to_string();
+    if kid.is_empty() {
+        anyhow::bail!("invalid JWT key {spec}, the key id is empty");
+    }
+    let algorithm = Algorithm::from_str(algorithm.trim())
+        .map_err(|_| anyhow::anyhow!("unknown JWT algorithm in {spec}"))?;
+    let contents = std::fs::read_to_string(path.trim())
+        .with_context(|| format!("failed to read JWT key {kid}"))?;
+
+    match algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+            let secret = contents.trim().as_bytes();
+            Ok(JwtKey::new(
+                Some(kid),
+                algorithm,
+                Some(EncodingKey::from_secret(secret)),
+                DecodingKey::from_secret(secret),
+                None,
+            ))
+        }
+        Algorithm::RS256 | Algorithm::RS384 | Algorithm::RS512 => {
+            rsa_key(kid, algorithm, &contents)
+        }
+        Algorithm::ES256 => ec_key(kid, algorithm, &contents),
+        other => anyhow::bail!("unsupported JWT algorithm {other:?} for key {kid}"),
+    }
+}
+
+fn rsa_key(kid: String, algorithm: Algorithm, pem: &str) -> anyhow::Result<JwtKey> {
+    let (encoding, public) = if pem.contains("PRIVATE KEY") {
+        let private = RsaPrivateKey::from_pkcs8_pem(pem)
+            .or_else(|_| RsaPrivateKey::from_pkcs1_pem(pem))
+            .with_context(|| format!("invalid RSA private key {kid}"))?;
+        (
+            Some(EncodingKey::from_rsa_pem(pem.as_bytes())?),
+            private.to_public_key(),
+        )
+    } else {
+        let public = RsaPublicKey::from_public_key_pem(pem)
+            .with_context(|| format!("invalid RSA public key {kid}"))?;
+        (None, public)
+    };
+
+    let n = URL_SAFE_NO_PAD.encode(public.n().to_bytes_be());
+    let e = URL_SAFE_NO_PAD.encode(public.e().to_bytes_be());
+    let decoding = DecodingKey::from_rsa_components(&n, &e)?;
+    let jwk = JsonWebKey {
+        kty: "RSA",
+        usage: "sig",
+        kid: kid.clone(),
+        alg: format!("{algorithm:?}"),
+        n: Some(n),
+        e: Some(e),
+        crv: None,
+        x: None,
+        y: None,
+    };
+    Ok(JwtKey::new(
+        Some(kid),
+        algorithm,
+        encoding,
+        decoding,
+        Some(jwk),
+    ))
+}
+
+fn ec_key(kid: String, algorithm: Algorithm, pem: &str) -> anyhow::Result<JwtKey> {
+    let (encoding, public) = if pem.contains("PRIVATE KEY") {
+        let private = p256::SecretKey::from_pkcs8_pem(pem)
+            .with_context(|| format!("invalid P-256 private key {kid}, expected PKCS#8"))?;
+        (
+            Some(EncodingKey::from_ec_pem(pem.as_bytes())?),
+            private.public_key(),
+        )
+    } else {
+        let public = p256::PublicKey::from_public_key_pem(pem)
+            .with_context(|| format!("invalid P-256 public key {kid}"))?;
+        (None, public)
+    };
+
+    let point = public.to_encoded_point(false);
+    let (Some(x), Some(y)) = (point.x(), point.y()) else {
+        anyhow::bail!("invalid P-256 public key {kid}");
+    };
+    let x = URL_SAFE_NO_PAD.encode(x);
+    let y = URL_SAFE_NO_PAD.encode(y);
+    let decoding = DecodingKey::from_ec_components(&x, &y)?;
+    let jwk = JsonWebKey {
+        kty: "EC",
+        usage: "sig",
+        kid: kid.clone(),
+        alg: format!("{algorithm:?}"),
+        n: None,
+        e: None,
+        crv: Some("P-256"),
+        x: Some(x),
+        y: Some(y),
+    };
+    Ok(JwtKey::new(
+        Some(kid),
+        algorithm,
+        encoding,
+        decoding,
+        Some(jwk),
+    ))
+}
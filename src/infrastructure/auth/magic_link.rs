@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    application::services::{
+        auth_provider::{AuthProvider, LoginCredentials, VerifiedIdentity},
+        secret_token::hash_secret_token,
+    },
+    domain::repositories::LoginTokenRepository,
+};
+
+/// Signs in whoever follows a link mailed to the email, proving they can
+/// read its mail.
+pub struct MagicLinkAuthProvider {
+    login_token_repo: Arc<dyn LoginTokenRepository>,
+}
+
+impl MagicLinkAuthProvider {
+    pub fn new(login_token_repo: Arc<dyn LoginTokenRepository>) -> Self {
+        Self { login_token_repo }
+    }
+}
+
+#[async_trait]
+impl AuthProvider for MagicLinkAuthProvider {
+    fn name(&self) -> &'static str {
+        "magic_link"
+    }
+
+    async fn authenticate(
+        &self,
+        credentials: &LoginCredentials,
+    ) -> anyhow::Result<VerifiedIdentity> {
+        let LoginCredentials::MagicLink { token } = credentials else {
+            anyhow::bail!("unsupported credentials: magic link authentication expects a token");
+        };
+
+        let email = self
+            .login_token_repo
+            .consume(&hash_secret_token(token.trim()))
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("invalid or expired login link"))?;
+
+        Ok(VerifiedIdentity {
+            subject: email.clone(),
+            email,
+            display_name: None,
+            user_id: None,
+        })
+    }
+}
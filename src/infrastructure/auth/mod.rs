@@ -0,0 +1,6 @@
+pub mod email;
+pub mod jwt_keys;
+pub mod magic_link;
+pub mod oauth;
+pub mod oidc;
+pub mod password;
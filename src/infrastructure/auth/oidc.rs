@@ -0,0 +1,174 @@
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use jsonwebtoken::{
+    Algorithm, DecodingKey, Validation, decode, decode_header,
+    jwk::{AlgorithmParameters, EllipticCurve, Jwk, JwkSet},
+};
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::sync::RwLock;
+
+use crate::application::services::auth_provider::{
+    AuthProvider, LoginCredentials, VerifiedIdentity,
+};
+
+#[derive(Clone)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub audience: String,
+    /// Discovered from the issuer's OpenID configuration when unset.
+    pub jwks_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Discovery {
+    jwks_uri: String,
+}
+
+#[derive(Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    name: Option<String>,
+}
+
+/// Verifies ID tokens against the issuer's published signing keys. Keys are
+/// cached and refetched once when a token names a key the cache lacks, so
+/// rotations are picked up without a restart.
+pub struct OidcAuthProvider {
+    config: OidcConfig,
+    client: Client,
+    keys: RwLock<Option<JwkSet>>,
+}
+
+impl OidcAuthProvider {
+    pub fn new(config: OidcConfig) -> Self {
+        Self {
+            config,
+            client: Client::new(),
+            keys: RwLock::new(None),
+        }
+    }
+
+    async fn jwks_url(&self) -> anyhow::Result<String> {
+        if let Some(url) = &self.config.jwks_url {
+            return Ok(url.clone());
+        }
+        let discovery_url = format!(
+            "{}/.well-known/openid-configuration",
+            self.config.issuer.trim_end_matches('/')
+        );
+        let discovery: Discovery = self
+            .client
+            .get(discovery_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(discovery.jwks_uri)
+    }
+
+    async fn fetch_keys(&self) -> anyhow::Result<JwkSet> {
+        let url = self.jwks_url().await?;
+        let keys = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<JwkSet>()
+            .await?;
+        *self.keys.write().await = Some(keys.clone());
+        Ok(keys)
+    }
+
+    /// The key named `kid`, with the one algorithm it may verify.
+    async fn decoding_key(&self, kid: &str) -> anyhow::Result<(DecodingKey, Algorithm)> {
+        let cached = self
+            .keys
+            .read()
+            .await
+            .as_ref()
+            .and_then(|keys| keys.find(kid).cloned());
+        let jwk = match cached {
+            Some(jwk) => jwk,
+            None => self
+                .fetch_keys()
+                .await?
+                .find(kid)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("invalid credentials: unknown signing key"))?,
+        };
+        Ok((DecodingKey::from_jwk(&jwk)?, key_algorithm(&jwk)?))
+    }
+}
+
+/// The algorithm the key declares, or the one its type implies when it
+/// declares none. The token header is never trusted to choose it.
+fn key_algorithm(jwk: &Jwk) -> anyhow::Result<Algorithm> {
+    if let Some(algorithm) = jwk.common.key_algorithm {
+        return Algorithm::from_str(&algorithm.to_string())
+            .map_err(|_| anyhow::anyhow!("invalid credentials: unsupported key algorithm"));
+    }
+    match &jwk.algorithm {
+        AlgorithmParameters::RSA(_) => Ok(Algorithm::RS256),
+        AlgorithmParameters::EllipticCurve(params) => match params.curve {
+            EllipticCurve::P256 => Ok(Algorithm::ES256),
+            EllipticCurve::P384 => Ok(Algorithm::ES384),
+            _ => anyhow::bail!("invalid credentials: unsupported key curve"),
+        },
+        AlgorithmParameters::OctetKeyPair(_) => Ok(Algorithm::EdDSA),
+        AlgorithmParameters::OctetKey(_) => {
+            anyhow::bail!("invalid credentials: symmetric signing keys are not accepted")
+        }
+    }
+}
+
+#[async_trait]
+impl AuthProvider for OidcAuthProvider {
+    fn name(&self) -> &'static str {
+        "oidc"
+    }
+
+    async fn authenticate(
+        &self,
+        credentials: &LoginCredentials,
+    ) -> anyhow::Result<VerifiedIdentity> {
+        let LoginCredentials::IdToken { id_token } = credentials else {
+            anyhow::bail!("unsupported credentials: OIDC authentication expects an id_token");
+        };
+
+        let header = decode_header(id_token).map_err(|_| anyhow::anyhow!("invalid credentials"))?;
+        let kid = header
+            .kid
+            .ok_or_else(|| anyhow::anyhow!("invalid credentials: token names no signing key"))?;
+        let (key, algorithm) = self.decoding_key(&kid).await?;
+        if header.alg != algorithm {
+            anyhow::bail!("invalid credentials: unexpected signing algorithm");
+        }
+
+        let mut validation = Validation::new(algorithm);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.set_audience(&[&self.config.audience]);
+        let claims = decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map_err(|_| anyhow::anyhow!("invalid credentials"))?
+            .claims;
+
+        if claims.email_verified != Some(true) {
+            anyhow::bail!("invalid credentials: email is not verified");
+        }
+        let email = claims
+            .email
+            .ok_or_else(|| anyhow::anyhow!("invalid credentials: token carries no email"))?;
+
+        Ok(VerifiedIdentity {
+            subject: claims.sub,
+            email,
+            display_name: claims.name,
+            user_id: None,
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::{
+    application::services::{
+        auth_provider::{AuthProvider, LoginCredentials, VerifiedIdentity},
+        password::verify_password,
+    },
+    domain::repositories::{PasswordRepository, UserRepository},
+};
+
+/// Signs in users who registered or were given a password.
+pub struct PasswordAuthProvider {
+    user_repo: Arc<dyn UserRepository>,
+    password_repo: Arc<dyn PasswordRepository>,
+}
+
+impl PasswordAuthProvider {
+    pub fn new(
+        user_repo: Arc<dyn UserRepository>,
+        password_repo: Arc<dyn PasswordRepository>,
+    ) -> Self {
+        Self {
+            user_repo,
+            password_repo,
+        }
+    }
+}
+
+#[async_trait]
+impl AuthProvider for PasswordAuthProvider {
+    fn name(&self) -> &'static str {
+        "password"
+    }
+
+    async fn authenticate(
+        &self,
+        credentials: &LoginCredentials,
+    ) -> anyhow::Result<VerifiedIdentity> {
+        let LoginCredentials::Password { email, password } = credentials else {
+            anyhow::bail!(
+                "unsupported credentials: password authentication expects an email and password"
+            );
+        };
+
+        // Unknown users and wrong passwords fail alike.
+        let user = self
+            .user_repo
+            .find_by_email(email)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("invalid credentials"))?;
+        let credential = self
+            .password_repo
+            .find(user.id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("invalid credentials"))?;
+        if !verify_password(password, &credential.password_hash)? {
+            anyhow::bail!("invalid credentials");
+        }
+
+        Ok(VerifiedIdentity {
+            subject: user.email.clone(),
+            email: user.email,
+            display_name: user.display_name,
+            user_id: Some(user.id),
+        })
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use crate::application::services::login_throttle::LoginAttemptStore;
+
+/// Keys kept before forgotten ones are swept out.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+struct Attempts {
+    failures: u32,
+    forget_at: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Process-local counters, for single-instance deployments. Each instance
+/// counts failures on its own.
+#[derive(Default)]
+pub struct InMemoryLoginAttemptStore {
+    attempts: Mutex<HashMap<String, Attempts>>,
+}
+
+#[async_trait]
+impl LoginAttemptStore for InMemoryLoginAttemptStore {
+    async fn locked_for(&self, key: &str) -> anyhow::Result<Option<Duration>> {
+        let now = Instant::now();
+        let attempts = self.attempts.lock().expect("login attempts lock poisoned");
+        Ok(attempts
+            .get(key)
+            .and_then(|attempts| attempts.locked_until)
+            .filter(|locked_until| *locked_until > now)
+            .map(|locked_until| locked_until - now))
+    }
+
+    async fn record_failure(&self, key: &str, memory: Duration) -> anyhow::Result<u32> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().expect("login attempts lock poisoned");
+        if attempts.len() >= SWEEP_THRESHOLD {
+            attempts.retain(|_, attempts| {
+                attempts.forget_at > now || attempts.locked_until.is_some_and(|until| until > now)
+            });
+        }
+
+        let entry = attempts.entry(key.to_string()).or_insert(Attempts {
+            failures: 0,
+            forget_at: now,
+            locked_until: None,
+        });
+        if entry.forget_at <= now {
+            entry.failures = 0;
+        }
+        entry.failures += 1;
+        entry.forget_at = now + memory;
+        Ok(entry.failures)
+    }
+
+    async fn lock(&self, key: &str, duration: Duration) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let mut attempts = self.attempts.lock().expect("login attempts lock poisoned");
+        let entry = attempts.entry(key.to_string()).or_insert(Attempts {
+            failures: 0,
+            forget_at: now,
+            locked_until: None,
+        });
+        entry.locked_until = Some(now + duration);
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> anyhow::Result<()> {
+        self.attempts
+            .lock()
+            .expect("login attempts lock poisoned")
+            .remove(key);
+        Ok(())
+    }
+}
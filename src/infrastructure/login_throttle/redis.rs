@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::{AsyncCommands, aio::ConnectionManager};
+
+use crate::application::services::login_throttle::LoginAttemptStore;
+
+/// Counters shared by every instance using the same Redis, so spreading
+/// guesses over instances doesn't help.
+pub struct RedisLoginAttemptStore {
+    connection: ConnectionManager,
+    prefix: String,
+}
+
+impl RedisLoginAttemptStore {
+    pub async fn new(url: &str, prefix: &str) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            prefix: prefix.to_string(),
+        })
+    }
+
+    fn failures_key(&self, key: &str) -> String {
+        format!("{}:{key}:failures", self.prefix)
+    }
+
+    fn lock_key(&self, key: &str) -> String {
+        format!("{}:{key}:lock", self.prefix)
+    }
+}
+
+#[async_trait]
+impl LoginAttemptStore for RedisLoginAttemptStore {
+    async fn locked_for(&self, key: &str) -> anyhow::Result<Option<Duration>> {
+        // Negative when the key is missing or has no expiry.
+        let ttl: i64 = self.connection.clone().pttl(self.lock_key(key)).await?;
+        Ok((ttl > 0).then(|| Duration::from_millis(ttl as u64)))
+    }
+
+    async fn record_failure(&self, key: &str, memory: Duration) -> anyhow::Result<u32> {
+        let key = self.failures_key(key);
+        let (failures,): (u32,) = redis::pipe()
+            .atomic()
+            .incr(&key, 1)
+            .pexpire(&key, memory.as_millis().max(1) as i64)
+            .ignore()
+            .query_async(&mut self.connection.clone())
+            .await?;
+        Ok(failures)
+    }
+
+    async fn lock(&self, key: &str, duration: Duration) -> anyhow::Result<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .pset_ex(self.lock_key(key), 1, duration.as_millis().max(1) as u64)
+            .await?;
+        Ok(())
+    }
+
+    async fn reset(&self, key: &str) -> anyhow::Result<()> {
+        let _: () = self
+            .connection
+            .clone()
+            .del(&[self.failures_key(key), self.lock_key(key)])
+            .await?;
+        Ok(())
+    }
+}
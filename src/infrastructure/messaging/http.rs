@@ -0,0 +1,121 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use reqwest::{Client, IntoUrl, RequestBuilder};
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::{application::services::messenger::ConnectionPoolStats, domain::models::MessengerType};
+
+/// Keep-alive tuning for the connections to the messenger APIs.
+#[derive(Debug, Clone)]
+pub struct HttpPoolConfig {
+    /// Idle connections kept open per host for the next requests.
+    pub max_idle_per_host: usize,
+    /// How long an idle connection is kept before it is closed.
+    pub idle_timeout: Duration,
+    /// Interval of TCP keep-alive probes, and of HTTP/2 pings on
+    /// multiplexed connections.
+    pub keepalive: Duration,
+}
+
+/// An HTTP client whose connections are pooled per host. Each messenger
+/// client holds one and every worker task goes through it, so handshakes
+/// are paid once per connection rather than per message. HTTP/2 is used
+/// where the API offers it through ALPN, as Telegram's does, multiplexing
+/// concurrent sends over one connection.
+pub struct PooledHttpClient {
+    http: Client,
+    messenger: MessengerType,
+    requests: AtomicU64,
+    connections: Arc<AtomicU64>,
+}
+
+impl PooledHttpClient {
+    pub fn new(
+        messenger: MessengerType,
+        user_agent: &str,
+        config: &HttpPoolConfig,
+    ) -> reqwest::Result<Self> {
+        let connections = Arc::new(AtomicU64::new(0));
+        let http = Client::builder()
+            .user_agent(user_agent)
+            .pool_max_idle_per_host(config.max_idle_per_host)
+            .pool_idle_timeout(config.idle_timeout)
+            .tcp_keepalive(config.keepalive)
+            .http2_keep_alive_interval(config.keepalive)
+            .http2_keep_alive_while_idle(true)
+            .http2_adaptive_window(true)
+            .connector_layer(CountConnections(connections.clone()))
+            .build()?;
+        Ok(Self {
+            http,
+            messenger,
+            requests: AtomicU64::new(0),
+            connections,
+        })
+    }
+
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.http.get(url)
+    }
+
+    pub fn post<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.http.post(url)
+    }
+
+    pub fn stats(&self) -> ConnectionPoolStats {
+        ConnectionPoolStats {
+            messenger: self.messenger,
+            requests: self.requests.load(Ordering::Relaxed),
+            connections: self.connections.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counts the connections the pool dials, failed attempts included.
+#[derive(Clone)]
+struct CountConnections(Arc<AtomicU64>);
+
+impl<S> Layer<S> for CountConnections {
+    type Service = CountingConnector<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        CountingConnector {
+            inner,
+            connections: self.0.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CountingConnector<S> {
+    inner: S,
+    connections: Arc<AtomicU64>,
+}
+
+impl<S, R> Service<R> for CountingConnector<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: R) -> Self::Future {
+        self.connections.fetch_add(1, Ordering::Relaxed);
+        self.inner.call(request)
+    }
+}
@@ -1,34 +1,94 @@
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_nats::jetstream::{
-    self,
+    self, AckKind,
     consumer::{AckPolicy, PullConsumer, pull},
 };
-use tokio::task::JoinHandle;
+use opentelemetry::{KeyValue, trace::SpanKind};
+use tokio::{sync::Semaphore, task::JoinHandle};
 use tokio_stream::StreamExt;
+use uuid::Uuid;
 
 use crate::{
     application::{
-        handlers::message_dispatcher::MessageDispatchHandler, services::event_bus::MessageBus,
+        handlers::message_dispatcher::MessageDispatchHandler,
+        services::{
+            dead_letter::{DeadLetter, DeadLetterQueue},
+            event_bus::MessageBus,
+            queue_stats::{ConsumerStats, QueueMonitor, QueueStats},
+            telemetry::{self, TRACE_FIELDS, TraceCarrier},
+        },
+    },
+    domain::{
+        events::{DeadLetterEvent, OutboundMessageEvent},
+        models::{MessagePriority, MessengerType},
     },
-    domain::events::OutboundMessageEvent,
 };
 
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
 #[derive(Clone)]
 pub struct JetstreamConfig {
     pub url: String,
     pub stream: String,
+    /// Events are published to `{subject}.{messenger}.{priority}`.
     pub subject: String,
+    /// Prefix of the durable consumers, `{durable}-{messenger}-{priority}`.
     pub durable: String,
-    pub pull_batch: usize,
+    pub limits: ConsumerLimits,
+    pub messenger_limits: HashMap<MessengerType, ConsumerLimits>,
     pub ack_wait_seconds: u64,
     pub max_deliver: i64,
+    /// How long the stream remembers `Nats-Msg-Id` values to drop
+    /// duplicate publishes of the same event.
+    pub duplicate_window: Duration,
+    pub dead_letter_stream: String,
+    pub dead_letter_subject: String,
+    /// Undecodable payloads are moved here verbatim instead of redelivering
+    /// forever.
+    pub quarantine_stream: String,
+    pub quarantine_subject: String,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ConsumerLimits {
+    pub pull_batch: usize,
+    /// Messages of one messenger handled at the same time.
+    pub concurrency: usize,
+}
+
+impl ConsumerLimits {
+    /// Parses `messenger:pull_batch:concurrency`, e.g. `telegram:64:8`.
+    pub fn parse(value: &str) -> anyhow::Result<(MessengerType, Self)> {
+        let mut parts = value.split(':').map(str::trim);
+        let (Some(messenger), Some(pull_batch), Some(concurrency), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            anyhow::bail!("invalid consumer limits {value}");
+        };
+
+        let messenger = MessengerType::from_str(messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger in consumer limits {value}"))?;
+        let limits = Self {
+            pull_batch: pull_batch.parse()?,
+            concurrency: concurrency.parse()?,
+        };
+        if limits.pull_batch == 0 || limits.concurrency == 0 {
+            anyhow::bail!("consumer limits must be positive: {value}");
+        }
+
+        Ok((messenger, limits))
+    }
 }
 
 pub struct JetstreamBus {
     context: jetstream::Context,
     subject: String,
+    dead_letters: Arc<JetstreamDeadLetterQueue>,
+    monitor: Arc<JetstreamQueueMonitor>,
+    quarantine_subject: String,
 }
 
 impl JetstreamBus {
@@ -36,55 +96,262 @@ impl JetstreamBus {
         let client = async_nats::connect(&config.url).await?;
         let context = jetstream::new(client);
 
-        let stream = context
+        let subjects = vec![format!("{}.*.*", config.subject)];
+        let mut stream = context
             .get_or_create_stream(jetstream::stream::Config {
                 name: config.stream.clone(),
-                subjects: vec![config.subject.clone()],
+                subjects: subjects.clone(),
+                duplicate_window: config.duplicate_window,
                 ..Default::default()
             })
             .await?;
+        // Streams created by earlier versions bind narrower subjects.
+        let mut stream_config = stream.cached_info().config.clone();
+        if stream_config.subjects != subjects
+            || stream_config.duplicate_window != config.duplicate_window
+        {
+            stream_config.subjects = subjects;
+            stream_config.duplicate_window = config.duplicate_window;
+            context.update_stream(&stream_config).await?;
+            stream = context.get_stream(&config.stream).await?;
+        }
 
-        let consumer = stream
-            .get_or_create_consumer(
-                &config.durable,
-                pull::Config {
-                    durable_name: Some(config.durable.clone()),
-                    ack_policy: AckPolicy::Explicit,
-                    ack_wait: Duration::from_secs(config.ack_wait_seconds),
-                    max_deliver: config.max_deliver,
-                    ..Default::default()
-                },
-            )
+        let mut consumers = Vec::new();
+        let mut durables = Vec::new();
+        for messenger in MessengerType::ALL {
+            let mut by_priority = Vec::new();
+            for priority in MessagePriority::ALL {
+                let durable = format!(
+                    "{}-{}-{}",
+                    config.durable,
+                    messenger.as_str(),
+                    priority.as_str()
+                );
+                let consumer = stream
+                    .get_or_create_consumer(
+                        &durable,
+                        pull::Config {
+                            durable_name: Some(durable.clone()),
+                            filter_subject: event_subject(&config.subject, messenger, priority),
+                            ack_policy: AckPolicy::Explicit,
+                            ack_wait: Duration::from_secs(config.ack_wait_seconds),
+                            max_deliver: config.max_deliver,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                by_priority.push(consumer);
+                durables.push(durable);
+            }
+            consumers.push(MessengerConsumer {
+                messenger,
+                consumers: by_priority,
+                limits: config
+                    .messenger_limits
+                    .get(&messenger)
+                    .copied()
+                    .unwrap_or(config.limits),
+            });
+        }
+
+        let dead_letter_subjects = vec![format!("{}.*", config.dead_letter_subject)];
+        let mut dead_letter_stream = context
+            .get_or_create_stream(jetstream::stream::Config {
+                name: config.dead_letter_stream.clone(),
+                subjects: dead_letter_subjects.clone(),
+                ..Default::default()
+            })
+            .await?;
+        let mut dead_letter_config = dead_letter_stream.cached_info().config.clone();
+        if dead_letter_config.subjects != dead_letter_subjects {
+            dead_letter_config.subjects = dead_letter_subjects;
+            context.update_stream(&dead_letter_config).await?;
+            dead_letter_stream = context.get_stream(&config.dead_letter_stream).await?;
+        }
+
+        context
+            .get_or_create_stream(jetstream::stream::Config {
+                name: config.quarantine_stream.clone(),
+                subjects: vec![config.quarantine_subject.clone()],
+                ..Default::default()
+            })
             .await?;
 
         let bus = Arc::new(Self {
             context: context.clone(),
             subject: config.subject.clone(),
+            dead_letters: Arc::new(JetstreamDeadLetterQueue {
+                stream: dead_letter_stream,
+                subject: config.dead_letter_subject.clone(),
+            }),
+            monitor: Arc::new(JetstreamQueueMonitor {
+                stream,
+                consumers: durables,
+            }),
+            quarantine_subject: config.quarantine_subject.clone(),
         });
 
         let worker = JetstreamWorker {
-            consumer,
-            pull_batch: config.pull_batch,
+            consumers,
+            redelivery: RedeliveryPolicy {
+                max_deliver: config.max_deliver,
+            },
         };
 
         Ok((bus, worker))
     }
+
+    pub fn dead_letter_queue(&self) -> Arc<JetstreamDeadLetterQueue> {
+        self.dead_letters.clone()
+    }
+
+    pub fn queue_monitor(&self) -> Arc<JetstreamQueueMonitor> {
+        self.monitor.clone()
+    }
+
+    async fn send(
+        &self,
+        event: &OutboundMessageEvent,
+    ) -> anyhow::Result<jetstream::context::PublishAckFuture> {
+        let subject = event_subject(&self.subject, event.messenger, event.priority);
+        let payload = serde_json::to_vec(event)?;
+        let attributes = vec![
+            KeyValue::new("messaging.system", "nats"),
+            KeyValue::new("messaging.destination.name", subject.clone()),
+            KeyValue::new("messaging.message.id", event.event_id.to_string()),
+        ];
+        telemetry::in_span(
+            &telemetry::extract(&event.trace_context),
+            format!("{subject} publish"),
+            SpanKind::Producer,
+            attributes,
+            async {
+                // Every publish gets a fresh event_id, so a duplicate here is
+                // a client-side retry of a publish the stream already stored.
+                let mut headers = message_id_headers(&event.event_id.to_string());
+                for (name, value) in telemetry::current() {
+                    headers.insert(name.as_str(), value.as_str());
+                }
+                let ack = self
+                    .context
+                    .publish_with_headers(subject.clone(), headers, payload.into())
+                    .await?;
+                Ok(ack)
+            },
+        )
+        .await
+    }
+
+    async fn publish_dead_letter(
+        &self,
+        event: OutboundMessageEvent,
+        reason: String,
+    ) -> anyhow::Result<()> {
+        let message_id = format!("{}-{}-dead", event.event_id, event.attempt);
+        let organization_id = event.organization();
+        let payload = serde_json::to_vec(&DeadLetterEvent {
+            event,
+            reason,
+            failed_at: chrono::Utc::now(),
+        })?;
+        self.context
+            .publish_with_headers(
+                self.dead_letters.subject_for(organization_id),
+                message_id_headers(&message_id),
+                payload.into(),
+            )
+            .await?
+            .await?;
+        Ok(())
+    }
+
+    /// Keeps the raw payload with the subject it arrived on and the reason it
+    /// could not be decoded.
+    async fn quarantine(&self, message: &jetstream::Message, reason: &str) -> anyhow::Result<()> {
+        let sequence = message
+            .info()
+            .map(|info| info.stream_sequence)
+            .map_err(|e| anyhow::anyhow!("failed to read message info: {}", e))?;
+        let mut headers = message_id_headers(&format!("{sequence}-quarantine"));
+        headers.insert("Messaging-Original-Subject", message.subject.as_str());
+        headers.insert("Messaging-Quarantine-Reason", reason);
+        self.context
+            .publish_with_headers(
+                self.quarantine_subject.clone(),
+                headers,
+                message.payload.clone(),
+            )
+            .await?
+            .await?;
+        Ok(())
+    }
+}
+
+/// JetStream drops a publish whose `Nats-Msg-Id` it has already stored
+/// within the stream's duplicate window.
+fn message_id_headers(message_id: &str) -> async_nats::HeaderMap {
+    let mut headers = async_nats::HeaderMap::new();
+    headers.insert(async_nats::header::NATS_MESSAGE_ID, message_id);
+    headers
+}
+
+fn message_trace_context(message: &jetstream::Message) -> TraceCarrier {
+    let mut carrier = TraceCarrier::new();
+    if let Some(headers) = &message.headers {
+        for name in TRACE_FIELDS {
+            if let Some(value) = headers.get(name) {
+                carrier.insert(name.to_string(), value.as_str().to_string());
+            }
+        }
+    }
+    carrier
 }
 
 #[async_trait::async_trait]
 impl MessageBus for JetstreamBus {
     async fn publish(&self, event: OutboundMessageEvent) -> anyhow::Result<()> {
-        let payload = serde_json::to_vec(&event)?;
-        self.context
-            .publish(self.subject.clone(), payload.into())
-            .await?;
+        self.send(&event).await?.await?;
         Ok(())
     }
+
+    /// Sends every event before waiting for any ack, so the batch costs one
+    /// round trip instead of one per event.
+    async fn publish_batch(&self, events: Vec<OutboundMessageEvent>) -> anyhow::Result<()> {
+        let mut acks = Vec::with_capacity(events.len());
+        for event in &events {
+            acks.push(self.send(event).await?);
+        }
+        for ack in acks {
+            ack.await?;
+        }
+        Ok(())
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        match self.context.client().connection_state() {
+            async_nats::connection::State::Connected => Ok(()),
+            state => anyhow::bail!("nats connection {state}"),
+        }
+    }
 }
 
+/// One durable consumer per messenger, each pulled by its own task, so a
+/// stalled messenger only holds back its own deliveries.
 pub struct JetstreamWorker {
-    consumer: PullConsumer,
-    pull_batch: usize,
+    consumers: Vec<MessengerConsumer>,
+    redelivery: RedeliveryPolicy,
+}
+
+struct MessengerConsumer {
+    messenger: MessengerType,
+    /// Ordered from the highest priority to the lowest.
+    consumers: Vec<PullConsumer>,
+    limits: ConsumerLimits,
+}
+
+#[derive(Clone, Copy)]
+struct RedeliveryPolicy {
+    max_deliver: i64,
 }
 
 impl JetstreamWorker {
@@ -92,71 +359,288 @@ impl JetstreamWorker {
         self,
         handler: Arc<MessageDispatchHandler>,
         bus: Arc<JetstreamBus>,
-    ) -> JoinHandle<()> {
-        tokio::spawn(async move {
-            if let Err(err) = self.run(handler, bus).await {
-                eprintln!("jetstream worker stopped: {err:?}");
-            }
-        })
+    ) -> Vec<JoinHandle<()>> {
+        let redelivery = self.redelivery;
+        self.consumers
+            .into_iter()
+            .map(|consumer| {
+                let handler = handler.clone();
+                let bus = bus.clone();
+                tokio::spawn(async move {
+                    let messenger = consumer.messenger;
+                    if let Err(err) = consumer.run(redelivery, handler, bus).await {
+                        tracing::error!(
+                            messenger = messenger.as_str(),
+                            error = ?err,
+                            "jetstream worker stopped"
+                        );
+                    }
+                })
+            })
+            .collect()
     }
+}
 
+impl MessengerConsumer {
+    /// Always drains the highest non-empty priority first: after every batch
+    /// the scan starts over from the top.
     async fn run(
         self,
+        redelivery: RedeliveryPolicy,
         handler: Arc<MessageDispatchHandler>,
         bus: Arc<JetstreamBus>,
     ) -> anyhow::Result<()> {
+        let permits = Arc::new(Semaphore::new(self.limits.concurrency));
         loop {
-            let mut batch = self
-                .consumer
-                .batch()
-                .max_messages(self.pull_batch)
-                .messages()
-                .await?;
-            while let Some(message) = batch.next().await {
-                match message {
-                    Ok(msg) => {
-                        if let Err(err) =
-                            Self::process_message(msg, handler.clone(), bus.clone()).await
-                        {
-                            eprintln!("failed to process message: {err:?}");
+            let mut idle = true;
+            for consumer in &self.consumers {
+                let mut batch = consumer
+                    .fetch()
+                    .max_messages(self.limits.pull_batch)
+                    .messages()
+                    .await?;
+                while let Some(message) = batch.next().await {
+                    match message {
+                        Ok(msg) => {
+                            idle = false;
+                            let permit = permits.clone().acquire_owned().await?;
+                            let handler = handler.clone();
+                            let bus = bus.clone();
+                            tokio::spawn(async move {
+                                if let Err(err) =
+                                    redelivery.process_message(msg, handler, bus).await
+                                {
+                                    tracing::error!(error = ?err, "failed to process message");
+                                }
+                                drop(permit);
+                            });
+                        }
+                        Err(err) => {
+                            tracing::error!(error = ?err, "jetstream batch error");
                         }
                     }
-                    Err(err) => {
-                        eprintln!("jetstream batch error: {err:?}");
-                    }
+                }
+                if !idle {
+                    break;
                 }
             }
+            if idle {
+                tokio::time::sleep(IDLE_POLL_INTERVAL).await;
+            }
         }
     }
+}
 
+impl RedeliveryPolicy {
     async fn process_message(
+        &self,
         message: jetstream::Message,
         handler: Arc<MessageDispatchHandler>,
         bus: Arc<JetstreamBus>,
     ) -> anyhow::Result<()> {
-        let event: OutboundMessageEvent = serde_json::from_slice(&message.payload)?;
-        match handler.handle(event.clone()).await {
+        let mut event: OutboundMessageEvent = match serde_json::from_slice(&message.payload) {
+            Ok(event) => event,
+            Err(err) => {
+                // Redelivering a payload that can never be decoded only burns
+                // deliveries, so it is set aside and acknowledged.
+                bus.quarantine(&message, &err.to_string()).await?;
+                if let Err(e) = message.ack().await {
+                    return Err(anyhow::anyhow!("failed to ack message: {}", e));
+                }
+                tracing::warn!(
+                    subject = %message.subject,
+                    error = %err,
+                    "quarantined undecodable message"
+                );
+                return Ok(());
+            }
+        };
+        let info = message
+            .info()
+            .map_err(|e| anyhow::anyhow!("failed to read message info: {}", e))?;
+
+        // Deferred events are held back by redelivering them once they are due.
+        let now = chrono::Utc::now();
+        if event.scheduled_at > now {
+            let delay = (event.scheduled_at - now).to_std().unwrap_or_default();
+            if let Err(e) = message.ack_with(AckKind::Nak(Some(delay))).await {
+                return Err(anyhow::anyhow!("failed to nak message: {}", e));
+            }
+            return Ok(());
+        }
+
+        // Retries are redeliveries of the same stream message, so the attempt
+        // follows the delivery count. An event that was deferred when published
+        // spent one delivery waiting for its slot.
+        let deferred_on_publish = event.scheduled_at.timestamp_millis()
+            > (info.published.unix_timestamp_nanos() / 1_000_000) as i64;
+        let retries = (info.delivered - 1 - deferred_on_publish as i64).max(0) as u32;
+        event.attempt += retries;
+        let last_delivery = self.max_deliver > 0 && info.delivered >= self.max_deliver;
+
+        // The publish span travels in the headers; events published before
+        // they carried one fall back to the trace in the payload.
+        let carrier = message_trace_context(&message);
+        let parent = telemetry::extract(if carrier.is_empty() {
+            &event.trace_context
+        } else {
+            &carrier
+        });
+        let attributes = vec![
+            KeyValue::new("messaging.system", "nats"),
+            KeyValue::new("messaging.destination.name", message.subject.to_string()),
+            KeyValue::new("messaging.message.id", event.event_id.to_string()),
+            KeyValue::new("messaging.nats.delivered", info.delivered),
+        ];
+        let handled = telemetry::in_span(
+            &parent,
+            format!("{} process", message.subject),
+            SpanKind::Consumer,
+            attributes,
+            handler.handle(event.clone()),
+        )
+        .await;
+
+        match handled {
             Ok(_) => {
                 if let Err(e) = message.ack().await {
                     return Err(anyhow::anyhow!("failed to ack message: {}", e));
                 }
             }
             Err(err) => {
-                if event.attempt >= event.max_attempts {
+                if event.attempt >= event.max_attempts
+                    || last_delivery
+                    || event.deadline_exceeded(chrono::Utc::now())
+                {
+                    tracing::error!(
+                        message_id = %event.message_id,
+                        event_id = %event.event_id,
+                        error = %err,
+                        "message dead-lettered"
+                    );
+                    let reason = err.to_string();
+                    handler.record_dead_letter(&event, &reason).await?;
+                    bus.publish_dead_letter(event, reason).await?;
                     if let Err(e) = message.ack().await {
                         return Err(anyhow::anyhow!("failed to ack message: {}", e));
                     }
                 } else {
-                    let mut next = event;
-                    next.attempt += 1;
-                    bus.publish(next).await?;
-                    if let Err(e) = message.ack().await {
-                        return Err(anyhow::anyhow!("failed to ack message: {}", e));
+                    let delay = event.retry_backoff.delay(event.attempt);
+                    tracing::info!(
+                        message_id = %event.message_id,
+                        event_id = %event.event_id,
+                        retry_in_ms = delay.as_millis() as u64,
+                        "message retry scheduled"
+                    );
+                    if let Err(e) = message.ack_with(AckKind::Nak(Some(delay))).await {
+                        return Err(anyhow::anyhow!("failed to nak message: {}", e));
                     }
                 }
-                eprintln!("dispatcher error: {err:?}");
             }
         }
         Ok(())
     }
 }
+
+fn event_subject(prefix: &str, messenger: MessengerType, priority: MessagePriority) -> String {
+    format!("{prefix}.{}.{}", messenger.as_str(), priority.as_str())
+}
+
+/// Reads backlog figures for the outbound stream and its durable consumers.
+pub struct JetstreamQueueMonitor {
+    stream: jetstream::stream::Stream,
+    consumers: Vec<String>,
+}
+
+#[async_trait::async_trait]
+impl QueueMonitor for JetstreamQueueMonitor {
+    async fn stats(&self) -> anyhow::Result<QueueStats> {
+        let info = self.stream.get_info().await?;
+        let mut consumers = Vec::with_capacity(self.consumers.len());
+        for name in &self.consumers {
+            let consumer = self.stream.consumer_info(name).await?;
+            consumers.push(ConsumerStats {
+                name: name.clone(),
+                pending: consumer.num_pending,
+                ack_pending: Some(consumer.num_ack_pending as u64),
+                redelivered: Some(consumer.num_redelivered as u64),
+                waiting: Some(consumer.num_waiting as u64),
+            });
+        }
+
+        Ok(QueueStats {
+            stream: info.config.name,
+            messages: info.state.messages,
+            bytes: Some(info.state.bytes),
+            consumers,
+        })
+    }
+}
+
+/// Exhausted events live in their own stream, addressed by stream sequence,
+/// on a subject per organization so listing one doesn't read the others.
+pub struct JetstreamDeadLetterQueue {
+    stream: jetstream::stream::Stream,
+    subject: String,
+}
+
+impl JetstreamDeadLetterQueue {
+    fn subject_for(&self, organization_id: Uuid) -> String {
+        format!("{}.{}", self.subject, organization_id.simple())
+    }
+
+    async fn load(&self, sequence: u64) -> anyhow::Result<Option<DeadLetter>> {
+        let message = match self.stream.get_raw_message(sequence).await {
+            Ok(message) => message,
+            // Removed or purged sequences leave gaps in the stream.
+            Err(_) => return Ok(None),
+        };
+        let entry: DeadLetterEvent = serde_json::from_slice(&message.payload)?;
+        Ok(Some(DeadLetter {
+            id: sequence,
+            entry,
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl DeadLetterQueue for JetstreamDeadLetterQueue {
+    async fn list(&self, organization_id: Uuid, limit: u32) -> anyhow::Result<Vec<DeadLetter>> {
+        let consumer = self
+            .stream
+            .create_consumer(pull::OrderedConfig {
+                filter_subject: self.subject_for(organization_id),
+                ..Default::default()
+            })
+            .await?;
+        let pending = consumer.cached_info().num_pending as usize;
+
+        // Oldest first, keeping only the newest `limit`.
+        let mut newest = VecDeque::with_capacity(limit as usize);
+        let mut messages = consumer.messages().await?.take(pending);
+        while let Some(message) = messages.next().await {
+            let message = message?;
+            let sequence = message
+                .info()
+                .map(|info| info.stream_sequence)
+                .map_err(|e| anyhow::anyhow!("failed to read message info: {}", e))?;
+            newest.push_back(DeadLetter {
+                id: sequence,
+                entry: serde_json::from_slice(&message.payload)?,
+            });
+            if newest.len() > limit as usize {
+                newest.pop_front();
+            }
+        }
+        Ok(newest.into_iter().rev().collect())
+    }
+
+    async fn get(&self, id: u64) -> anyhow::Result<Option<DeadLetter>> {
+        self.load(id).await
+    }
+
+    async fn remove(&self, id: u64) -> anyhow::Result<()> {
+        self.stream.delete_message(id).await?;
+        Ok(())
+    }
+}
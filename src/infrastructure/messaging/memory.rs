@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use tokio::{
+    sync::mpsc::{UnboundedReceiver, UnboundedSender, unbounded_channel},
+    task::JoinHandle,
+};
+
+use crate::{
+    application::{
+        handlers::message_dispatcher::MessageDispatchHandler, services::event_bus::MessageBus,
+    },
+    domain::events::OutboundMessageEvent,
+};
+
+/// Process-local queue for development and tests. Nothing survives a
+/// restart: events still queued or deferred are lost when the process exits.
+pub struct InMemoryBus {
+    sender: UnboundedSender<OutboundMessageEvent>,
+}
+
+impl InMemoryBus {
+    pub fn new() -> (Arc<Self>, InMemoryWorker) {
+        let (sender, receiver) = unbounded_channel();
+        (Arc::new(Self { sender }), InMemoryWorker { receiver })
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageBus for InMemoryBus {
+    async fn publish(&self, event: OutboundMessageEvent) -> anyhow::Result<()> {
+        self.sender
+            .send(event)
+            .map_err(|_| anyhow::anyhow!("in-memory worker stopped"))
+    }
+
+    async fn publish_at(
+        &self,
+        mut event: OutboundMessageEvent,
+        when: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        event.scheduled_at = when;
+        let Ok(delay) = (when - Utc::now()).to_std() else {
+            return self.publish(event).await;
+        };
+        let sender = self.sender.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if sender.send(event).is_err() {
+                tracing::error!("failed to requeue deferred message: in-memory worker stopped");
+            }
+        });
+        Ok(())
+    }
+}
+
+pub struct InMemoryWorker {
+    receiver: UnboundedReceiver<OutboundMessageEvent>,
+}
+
+impl InMemoryWorker {
+    pub fn spawn(
+        self,
+        handler: Arc<MessageDispatchHandler>,
+        bus: Arc<InMemoryBus>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(self.run(handler, bus))
+    }
+
+    /// Handles events one at a time until every sender is dropped.
+    pub async fn run(mut self, handler: Arc<MessageDispatchHandler>, bus: Arc<InMemoryBus>) {
+        while let Some(event) = self.receiver.recv().await {
+            if let Err(err) = Self::process_event(event, handler.clone(), bus.clone()).await {
+                tracing::error!(error = ?err, "failed to process message");
+            }
+        }
+    }
+
+    async fn process_event(
+        event: OutboundMessageEvent,
+        handler: Arc<MessageDispatchHandler>,
+        bus: Arc<InMemoryBus>,
+    ) -> anyhow::Result<()> {
+        if event.scheduled_at > Utc::now() {
+            let when = event.scheduled_at;
+            return bus.publish_at(event, when).await;
+        }
+
+        if let Err(err) = handler.handle(event.clone()).await {
+            if event.attempt < event.max_attempts && !event.deadline_exceeded(Utc::now()) {
+                let delay = event.retry_backoff.delay(event.attempt);
+                tracing::info!(
+                    message_id = %event.message_id,
+                    event_id = %event.event_id,
+                    retry_in_ms = delay.as_millis() as u64,
+                    "message retry scheduled"
+                );
+                let when = Utc::now() + chrono::Duration::from_std(delay)?;
+                let mut next = event;
+                next.attempt += 1;
+                bus.publish_at(next, when).await?;
+            } else {
+                tracing::error!(
+                    message_id = %event.message_id,
+                    event_id = %event.event_id,
+                    error = %err,
+                    "message given up"
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+pub mod http;
 pub mod jetstream;
+pub mod memory;
+pub mod rabbitmq;
+pub mod sqs;
 pub mod telegram;
 pub mod vk;
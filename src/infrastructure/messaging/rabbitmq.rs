@@ -0,0 +1,418 @@
+use std::sync::Arc;
+
+use lapin::{
+    BasicProperties, Channel, Connection, ConnectionProperties, Consumer, ExchangeKind,
+    message::Delivery,
+    options::{
+        BasicAckOptions, BasicConsumeOptions, BasicNackOptions, BasicPublishOptions,
+        BasicQosOptions, ConfirmSelectOptions, ExchangeDeclareOptions, QueueBindOptions,
+        QueueDeclareOptions,
+    },
+    types::{AMQPValue, FieldTable},
+};
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use crate::{
+    application::{
+        handlers::message_dispatcher::MessageDispatchHandler,
+        services::{
+            event_bus::MessageBus,
+            queue_stats::{ConsumerStats, QueueMonitor, QueueStats},
+        },
+    },
+    domain::events::OutboundMessageEvent,
+};
+
+#[derive(Clone)]
+pub struct RabbitMqConfig {
+    pub url: String,
+    pub exchange: String,
+    pub queue: String,
+    pub prefetch: u16,
+}
+
+/// Publishes to a durable direct exchange. Next to the work queue the bus
+/// declares:
+/// - `{exchange}.dlx` and `{queue}.dead`, where exhausted and undecodable
+///   events are dead-lettered;
+/// - `{queue}.delay`, a consumer-less queue whose expired messages are
+///   dead-lettered back into the work queue, used to hold deferred events.
+pub struct RabbitMqBus {
+    _connection: Connection,
+    channel: Channel,
+    exchange: String,
+    queue: String,
+    monitor: Arc<RabbitMqQueueMonitor>,
+}
+
+impl RabbitMqBus {
+    pub async fn new(config: &RabbitMqConfig) -> anyhow::Result<(Arc<Self>, RabbitMqWorker)> {
+        let connection = Connection::connect(&config.url, ConnectionProperties::default()).await?;
+        let channel = connection.create_channel().await?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await?;
+
+        let dead_letter_exchange = format!("{}.dlx", config.exchange);
+        let dead_letter_queue = format!("{}.dead", config.queue);
+        let delay_queue = format!("{}.delay", config.queue);
+        let durable_exchange = ExchangeDeclareOptions {
+            durable: true,
+            ..Default::default()
+        };
+        let durable_queue = QueueDeclareOptions {
+            durable: true,
+            ..Default::default()
+        };
+
+        channel
+            .exchange_declare(
+                &config.exchange,
+                ExchangeKind::Direct,
+                durable_exchange,
+                FieldTable::default(),
+            )
+            .await?;
+        channel
+            .exchange_declare(
+                &dead_letter_exchange,
+                ExchangeKind::Direct,
+                durable_exchange,
+                FieldTable::default(),
+            )
+            .await?;
+
+        channel
+            .queue_declare(&dead_letter_queue, durable_queue, FieldTable::default())
+            .await?;
+        channel
+            .queue_bind(
+                &dead_letter_queue,
+                &dead_letter_exchange,
+                &config.queue,
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+
+        channel
+            .queue_declare(
+                &config.queue,
+                durable_queue,
+                dead_letter_args(&dead_letter_exchange, &config.queue),
+            )
+            .await?;
+        channel
+            .queue_bind(
+                &config.queue,
+                &config.exchange,
+                &config.queue,
+                QueueBindOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+
+        channel
+            .queue_declare(
+                &delay_queue,
+                durable_queue,
+                dead_letter_args(&config.exchange, &config.queue),
+            )
+            .await?;
+
+        let consumer_channel = connection.create_channel().await?;
+        consumer_channel
+            .basic_qos(config.prefetch, BasicQosOptions::default())
+            .await?;
+        let consumer = consumer_channel
+            .basic_consume(
+                &config.queue,
+                "messaging-worker",
+                BasicConsumeOptions::default(),
+                FieldTable::default(),
+            )
+            .await?;
+
+        // A failed passive declare closes its channel, so the monitor gets
+        // its own rather than risk the publishing one.
+        let monitor = Arc::new(RabbitMqQueueMonitor {
+            channel: connection.create_channel().await?,
+            queue: config.queue.clone(),
+        });
+
+        let bus = Arc::new(Self {
+            _connection: connection,
+            channel,
+            exchange: config.exchange.clone(),
+            queue: config.queue.clone(),
+            monitor,
+        });
+
+        let worker = RabbitMqWorker { consumer };
+
+        Ok((bus, worker))
+    }
+
+    pub fn queue_monitor(&self) -> Arc<RabbitMqQueueMonitor> {
+        self.monitor.clone()
+    }
+
+    async fn publish_raw(
+        &self,
+        exchange: &str,
+        routing_key: &str,
+        payload: &[u8],
+        properties: BasicProperties,
+    ) -> anyhow::Result<()> {
+        let confirmation = self
+            .channel
+            .basic_publish(
+                exchange,
+                routing_key,
+                BasicPublishOptions::default(),
+                payload,
+                properties.with_delivery_mode(2),
+            )
+            .await?
+            .await?;
+        if confirmation.is_nack() {
+            anyhow::bail!("rabbitmq broker rejected the message");
+        }
+        Ok(())
+    }
+
+    /// Parks the event in the delay queue until it is due. Per-message TTLs
+    /// only expire at the head of a queue, so a long delay holds back shorter
+    /// ones queued behind it.
+    async fn publish_delayed(
+        &self,
+        event: &OutboundMessageEvent,
+        delay: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        let payload = serde_json::to_vec(event)?;
+        let properties =
+            BasicProperties::default().with_expiration(delay.as_millis().to_string().into());
+        self.publish_raw("", &format!("{}.delay", self.queue), &payload, properties)
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageBus for RabbitMqBus {
+    async fn publish(&self, event: OutboundMessageEvent) -> anyhow::Result<()> {
+        let payload = serde_json::to_vec(&event)?;
+        self.publish_raw(
+            &self.exchange,
+            &self.queue,
+            &payload,
+            BasicProperties::default().with_content_type("application/json".into()),
+        )
+        .await
+    }
+
+    /// Events that are not yet due go straight to the delay queue.
+    async fn publish_at(
+        &self,
+        mut event: OutboundMessageEvent,
+        when: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<()> {
+        event.scheduled_at = when;
+        match (when - chrono::Utc::now()).to_std() {
+            Ok(delay) if !delay.is_zero() => self.publish_delayed(&event, delay).await,
+            _ => self.publish(event).await,
+        }
+    }
+
+    /// Publishes every event before waiting for the broker's confirms, so the
+    /// batch costs one round trip instead of one per event.
+    async fn publish_batch(&self, events: Vec<OutboundMessageEvent>) -> anyhow::Result<()> {
+        let mut confirms = Vec::with_capacity(events.len());
+        for event in &events {
+            let payload = serde_json::to_vec(event)?;
+            let confirm = self
+                .channel
+                .basic_publish(
+                    &self.exchange,
+                    &self.queue,
+                    BasicPublishOptions::default(),
+                    &payload,
+                    BasicProperties::default()
+                        .with_content_type("application/json".into())
+                        .with_delivery_mode(2),
+                )
+                .await?;
+            confirms.push(confirm);
+        }
+        for confirm in confirms {
+            if confirm.await?.is_nack() {
+                anyhow::bail!("rabbitmq broker rejected the message");
+            }
+        }
+        Ok(())
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        if !self.channel.status().connected() {
+            anyhow::bail!("rabbitmq channel {:?}", self.channel.status().state());
+        }
+        Ok(())
+    }
+}
+
+/// Reads the backlog of the work queue with a passive declare. AMQP only
+/// reports ready messages; unacknowledged ones need the management API.
+pub struct RabbitMqQueueMonitor {
+    channel: Channel,
+    queue: String,
+}
+
+#[async_trait::async_trait]
+impl QueueMonitor for RabbitMqQueueMonitor {
+    async fn stats(&self) -> anyhow::Result<QueueStats> {
+        let queue = self
+            .channel
+            .queue_declare(
+                &self.queue,
+                QueueDeclareOptions {
+                    passive: true,
+                    ..Default::default()
+                },
+                FieldTable::default(),
+            )
+            .await?;
+
+        Ok(QueueStats {
+            stream: self.queue.clone(),
+            messages: queue.message_count() as u64,
+            bytes: None,
+            consumers: vec![ConsumerStats {
+                name: self.queue.clone(),
+                pending: queue.message_count() as u64,
+                ack_pending: None,
+                redelivered: None,
+                waiting: None,
+            }],
+        })
+    }
+}
+
+pub struct RabbitMqWorker {
+    consumer: Consumer,
+}
+
+impl RabbitMqWorker {
+    pub fn spawn(
+        self,
+        handler: Arc<MessageDispatchHandler>,
+        bus: Arc<RabbitMqBus>,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(err) = self.run(handler, bus).await {
+                tracing::error!(error = ?err, "rabbitmq worker stopped");
+            }
+        })
+    }
+
+    async fn run(
+        mut self,
+        handler: Arc<MessageDispatchHandler>,
+        bus: Arc<RabbitMqBus>,
+    ) -> anyhow::Result<()> {
+        while let Some(delivery) = self.consumer.next().await {
+            match delivery {
+                Ok(delivery) => {
+                    if let Err(err) =
+                        Self::process_delivery(delivery, handler.clone(), bus.clone()).await
+                    {
+                        tracing::error!(error = ?err, "failed to process message");
+                    }
+                }
+                Err(err) => {
+                    tracing::error!(error = ?err, "rabbitmq consumer error");
+                }
+            }
+        }
+        anyhow::bail!("rabbitmq consumer closed")
+    }
+
+    async fn process_delivery(
+        delivery: Delivery,
+        handler: Arc<MessageDispatchHandler>,
+        bus: Arc<RabbitMqBus>,
+    ) -> anyhow::Result<()> {
+        let event: OutboundMessageEvent = match serde_json::from_slice(&delivery.data) {
+            Ok(event) => event,
+            Err(err) => {
+                dead_letter(&delivery).await?;
+                return Err(err.into());
+            }
+        };
+
+        let now = chrono::Utc::now();
+        if event.scheduled_at > now {
+            let delay = (event.scheduled_at - now).to_std().unwrap_or_default();
+            bus.publish_delayed(&event, delay).await?;
+            delivery.ack(BasicAckOptions::default()).await?;
+            return Ok(());
+        }
+
+        match handler.handle(event.clone()).await {
+            Ok(_) => {
+                delivery.ack(BasicAckOptions::default()).await?;
+            }
+            Err(err) => {
+                if event.attempt >= event.max_attempts
+                    || event.deadline_exceeded(chrono::Utc::now())
+                {
+                    tracing::error!(
+                        message_id = %event.message_id,
+                        event_id = %event.event_id,
+                        error = %err,
+                        "message dead-lettered"
+                    );
+                    dead_letter(&delivery).await?;
+                } else {
+                    // The wait goes through the delay queue like any other
+                    // deferred event.
+                    let delay = event.retry_backoff.delay(event.attempt);
+                    tracing::info!(
+                        message_id = %event.message_id,
+                        event_id = %event.event_id,
+                        retry_in_ms = delay.as_millis() as u64,
+                        "message retry scheduled"
+                    );
+                    let when = chrono::Utc::now() + chrono::Duration::from_std(delay)?;
+                    let mut next = event;
+                    next.attempt += 1;
+                    bus.publish_at(next, when).await?;
+                    delivery.ack(BasicAckOptions::default()).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn dead_letter(delivery: &Delivery) -> anyhow::Result<()> {
+    delivery
+        .nack(BasicNackOptions {
+            requeue: false,
+            ..Default::default()
+        })
+        .await?;
+    Ok(())
+}
+
+fn dead_letter_args(exchange: &str, routing_key: &str) -> FieldTable {
+    let mut args = FieldTable::default();
+    args.insert(
+        "x-dead-letter-exchange".into(),
+        AMQPValue::LongString(exchange.into()),
+    );
+    args.insert(
+        "x-dead-letter-routing-key".into(),
+        AMQPValue::LongString(routing_key.into()),
+    );
+    args
+}
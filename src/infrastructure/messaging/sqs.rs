@@ -0,0 +1,315 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use aws_config::BehaviorVersion;
+use aws_sdk_sqs::{
+    Client,
+    types::{
+        Message, MessageSystemAttributeName, QueueAttributeName, SendMessageBatchRequestEntry,
+    },
+};
+use tokio::task::JoinHandle;
+
+use crate::{
+    application::{
+        handlers::message_dispatcher::MessageDispatchHandler,
+        services::{
+            event_bus::MessageBus,
+            queue_stats::{ConsumerStats, QueueMonitor, QueueStats},
+        },
+    },
+    domain::events::OutboundMessageEvent,
+};
+
+// SQS caps DelaySeconds at 15 minutes; longer deferrals are re-sent until due.
+const MAX_DELAY_SECONDS: i64 = 900;
+// Visibility timeouts are capped at 12 hours.
+const MAX_VISIBILITY_SECONDS: u64 = 43_200;
+// SendMessageBatch accepts at most ten entries.
+const MAX_BATCH_ENTRIES: usize = 10;
+
+#[derive(Clone)]
+pub struct SqsConfig {
+    pub queue_url: String,
+    pub dead_letter_queue_url: Option<String>,
+    pub max_messages: i32,
+    pub wait_time_seconds: i32,
+}
+
+pub struct SqsBus {
+    client: Client,
+    queue_url: String,
+    monitor: Arc<SqsQueueMonitor>,
+}
+
+impl SqsBus {
+    /// Credentials and region come from the standard AWS environment chain.
+    pub async fn new(config: &SqsConfig) -> anyhow::Result<(Arc<Self>, SqsWorker)> {
+        let shared = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        let client = Client::new(&shared);
+
+        let bus = Arc::new(Self {
+            client: client.clone(),
+            queue_url: config.queue_url.clone(),
+            monitor: Arc::new(SqsQueueMonitor {
+                client: client.clone(),
+                queue_url: config.queue_url.clone(),
+            }),
+        });
+
+        let worker = SqsWorker {
+            client,
+            config: config.clone(),
+        };
+
+        Ok((bus, worker))
+    }
+
+    pub fn queue_monitor(&self) -> Arc<SqsQueueMonitor> {
+        self.monitor.clone()
+    }
+
+    async fn send(&self, event: &OutboundMessageEvent, delay_seconds: i32) -> anyhow::Result<()> {
+        let body = serde_json::to_string(event)?;
+        self.client
+            .send_message()
+            .queue_url(&self.queue_url)
+            .message_body(body)
+            .delay_seconds(delay_seconds)
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl MessageBus for SqsBus {
+    async fn publish(&self, event: OutboundMessageEvent) -> anyhow::Result<()> {
+        self.send(&event, 0).await
+    }
+
+    /// Delays beyond what SQS allows are covered by the worker re-sending the
+    /// event until it is due.
+    async fn publish_at(
+        &self,
+        mut event: OutboundMessageEvent,
+        when: chrono::DateTime<chrono::Utc>,
+    ) -> anyhow::Result<()> {
+        event.scheduled_at = when;
+        let delay = (when - chrono::Utc::now())
+            .num_seconds()
+            .clamp(0, MAX_DELAY_SECONDS);
+        self.send(&event, delay as i32).await
+    }
+
+    async fn publish_batch(&self, events: Vec<OutboundMessageEvent>) -> anyhow::Result<()> {
+        for chunk in events.chunks(MAX_BATCH_ENTRIES) {
+            let mut entries = Vec::with_capacity(chunk.len());
+            for (index, event) in chunk.iter().enumerate() {
+                entries.push(
+                    SendMessageBatchRequestEntry::builder()
+                        .id(index.to_string())
+                        .message_body(serde_json::to_string(event)?)
+                        .build()?,
+                );
+            }
+
+            let output = self
+                .client
+                .send_message_batch()
+                .queue_url(&self.queue_url)
+                .set_entries(Some(entries))
+                .send()
+                .await?;
+            if let Some(failure) = output.failed().first() {
+                anyhow::bail!(
+                    "sqs rejected {} of {} messages: {}",
+                    output.failed().len(),
+                    chunk.len(),
+                    failure.message().unwrap_or(failure.code())
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the queue's approximate counts. Received messages stay invisible
+/// until deleted or until their visibility timeout lapses, which includes
+/// those waiting out a retry delay.
+pub struct SqsQueueMonitor {
+    client: Client,
+    queue_url: String,
+}
+
+#[async_trait::async_trait]
+impl QueueMonitor for SqsQueueMonitor {
+    async fn stats(&self) -> anyhow::Result<QueueStats> {
+        let output = self
+            .client
+            .get_queue_attributes()
+            .queue_url(&self.queue_url)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessages)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessagesNotVisible)
+            .attribute_names(QueueAttributeName::ApproximateNumberOfMessagesDelayed)
+            .send()
+            .await?;
+        let count = |name: QueueAttributeName| -> anyhow::Result<u64> {
+            let value = output
+                .attributes()
+                .and_then(|attrs| attrs.get(&name))
+                .ok_or_else(|| anyhow::anyhow!("sqs did not report {}", name.as_str()))?;
+            Ok(value.parse()?)
+        };
+        let visible = count(QueueAttributeName::ApproximateNumberOfMessages)?;
+        let not_visible = count(QueueAttributeName::ApproximateNumberOfMessagesNotVisible)?;
+        let delayed = count(QueueAttributeName::ApproximateNumberOfMessagesDelayed)?;
+
+        Ok(QueueStats {
+            stream: self.queue_url.clone(),
+            messages: visible + not_visible + delayed,
+            bytes: None,
+            consumers: vec![ConsumerStats {
+                name: self.queue_url.clone(),
+                pending: visible,
+                ack_pending: Some(not_visible),
+                redelivered: None,
+                waiting: None,
+            }],
+        })
+    }
+}
+
+/// Failed deliveries are not deleted: the message's visibility timeout is set
+/// to the retry delay and SQS redelivers it. The queue's redrive policy moves
+/// messages that keep failing to decode to its dead-letter queue; events that
+/// exhaust their attempts are forwarded there explicitly.
+pub struct SqsWorker {
+    client: Client,
+    config: SqsConfig,
+}
+
+impl SqsWorker {
+    pub fn spawn(self, handler: Arc<MessageDispatchHandler>, bus: Arc<SqsBus>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            if let Err(err) = self.run(handler, bus).await {
+                tracing::error!(error = ?err, "sqs worker stopped");
+            }
+        })
+    }
+
+    async fn run(
+        self,
+        handler: Arc<MessageDispatchHandler>,
+        bus: Arc<SqsBus>,
+    ) -> anyhow::Result<()> {
+        loop {
+            let output = match self
+                .client
+                .receive_message()
+                .queue_url(&self.config.queue_url)
+                .max_number_of_messages(self.config.max_messages)
+                .wait_time_seconds(self.config.wait_time_seconds)
+                .message_system_attribute_names(MessageSystemAttributeName::ApproximateReceiveCount)
+                .send()
+                .await
+            {
+                Ok(output) => output,
+                Err(err) => {
+                    tracing::error!(error = ?err, "sqs receive error");
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            };
+
+            for message in output.messages.unwrap_or_default() {
+                if let Err(err) = self.process_message(message, handler.clone(), &bus).await {
+                    tracing::error!(error = ?err, "failed to process message");
+                }
+            }
+        }
+    }
+
+    async fn process_message(
+        &self,
+        message: Message,
+        handler: Arc<MessageDispatchHandler>,
+        bus: &SqsBus,
+    ) -> anyhow::Result<()> {
+        let receipt_handle = message
+            .receipt_handle()
+            .ok_or_else(|| anyhow::anyhow!("sqs message without receipt handle"))?;
+        let mut event: OutboundMessageEvent =
+            serde_json::from_str(message.body().unwrap_or_default())?;
+
+        if event.scheduled_at > chrono::Utc::now() {
+            let when = event.scheduled_at;
+            bus.publish_at(event, when).await?;
+            return self.delete(receipt_handle).await;
+        }
+
+        // Redeliveries reuse the same body, so the attempt is derived from the
+        // receive count.
+        let receive_count = message
+            .attributes()
+            .and_then(|attrs| attrs.get(&MessageSystemAttributeName::ApproximateReceiveCount))
+            .and_then(|count| count.parse::<u32>().ok())
+            .unwrap_or(1);
+        event.attempt += receive_count.saturating_sub(1);
+
+        match handler.handle(event.clone()).await {
+            Ok(_) => self.delete(receipt_handle).await?,
+            Err(err) => {
+                if event.attempt >= event.max_attempts
+                    || event.deadline_exceeded(chrono::Utc::now())
+                {
+                    tracing::error!(
+                        message_id = %event.message_id,
+                        event_id = %event.event_id,
+                        error = %err,
+                        "message given up"
+                    );
+                    if let Some(dead_letter_queue_url) = &self.config.dead_letter_queue_url {
+                        self.client
+                            .send_message()
+                            .queue_url(dead_letter_queue_url)
+                            .message_body(serde_json::to_string(&event)?)
+                            .send()
+                            .await?;
+                    }
+                    self.delete(receipt_handle).await?;
+                } else {
+                    tracing::info!(
+                        message_id = %event.message_id,
+                        event_id = %event.event_id,
+                        "message retry scheduled"
+                    );
+                    self.client
+                        .change_message_visibility()
+                        .queue_url(&self.config.queue_url)
+                        .receipt_handle(receipt_handle)
+                        .visibility_timeout(retry_visibility(&event))
+                        .send()
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, receipt_handle: &str) -> anyhow::Result<()> {
+        self.client
+            .delete_message()
+            .queue_url(&self.config.queue_url)
+            .receipt_handle(receipt_handle)
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
+/// A failed message is retried once it becomes visible again.
+fn retry_visibility(event: &OutboundMessageEvent) -> i32 {
+    let delay = event.retry_backoff.delay(event.attempt).as_secs();
+    delay.min(MAX_VISIBILITY_SECONDS) as i32
+}
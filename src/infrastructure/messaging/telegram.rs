@@ -2,28 +2,33 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use reqwest::Client;
 use serde::Deserialize;
+use uuid::Uuid;
 
 use crate::{
-    application::services::messenger::{MessengerClient, PaginatedChats, PaginationParams},
+    application::services::messenger::{
+        ConnectionPoolStats, MessengerClient, PaginatedChats, PaginationParams,
+    },
     domain::models::{
-        MessageContent, MessengerChat, MessengerChatType, MessengerToken, MessengerType,
+        ChatAction, MessageContent, MessengerChat, MessengerChatType, MessengerToken, MessengerType,
     },
+    infrastructure::messaging::http::{HttpPoolConfig, PooledHttpClient},
 };
 
 pub struct TelegramClient {
-    http: Client,
+    http: PooledHttpClient,
     base_url: String,
 }
 
 impl TelegramClient {
-    pub fn new() -> Arc<dyn MessengerClient> {
+    pub fn new(pool: &HttpPoolConfig) -> Arc<dyn MessengerClient> {
         Arc::new(Self {
-            http: Client::builder()
-                .user_agent("messaging-service/telegram")
-                .build()
-                .expect("failed to build telegram client"),
+            http: PooledHttpClient::new(
+                MessengerType::Telegram,
+                "messaging-service/telegram",
+                pool,
+            )
+            .expect("failed to build telegram client"),
             base_url: "https://api.telegram.org".to_string(),
         }) as Arc<dyn MessengerClient>
     }
@@ -32,6 +37,15 @@ impl TelegramClient {
         format!("{}/bot{}/{}", self.base_url, token.access_token, method)
     }
 
+    fn parse_chat_id(recipient: &str) -> anyhow::Result<i64> {
+        recipient.parse().map_err(|_| {
+            anyhow::anyhow!(
+                "invalid telegram chat_id format: expected integer, got '{}'",
+                recipient
+            )
+        })
+    }
+
     fn map_chat(chat: TelegramChat) -> MessengerChat {
         let chat_type = match chat.chat_type.as_str() {
             "private" => MessengerChatType::Direct,
@@ -82,20 +96,20 @@ impl MessengerClient for TelegramClient {
         MessengerType::Telegram
     }
 
+    fn pool_stats(&self) -> Option<ConnectionPoolStats> {
+        Some(self.http.stats())
+    }
+
     async fn send(
         &self,
         token: &MessengerToken,
         recipient: &str,
         content: &MessageContent,
+        _message_id: Uuid,
     ) -> anyhow::Result<()> {
         let url = self.build_url(token, "sendMessage");
 
-        let chat_id: i64 = recipient.parse().map_err(|_| {
-            anyhow::anyhow!(
-                "invalid telegram chat_id format: expected integer, got '{}'",
-                recipient
-            )
-        })?;
+        let chat_id = Self::parse_chat_id(recipient)?;
 
         let request_body = serde_json::json!({
             "chat_id": chat_id,
@@ -118,6 +132,38 @@ impl MessengerClient for TelegramClient {
         Ok(())
     }
 
+    async fn send_chat_action(
+        &self,
+        token: &MessengerToken,
+        recipient: &str,
+        action: ChatAction,
+    ) -> anyhow::Result<()> {
+        let url = self.build_url(token, "sendChatAction");
+
+        let request_body = serde_json::json!({
+            "chat_id": Self::parse_chat_id(recipient)?,
+            "action": match action {
+                ChatAction::Typing => "typing",
+                ChatAction::UploadPhoto => "upload_photo",
+            },
+        });
+
+        let response = self.http.post(&url).json(&request_body).send().await?;
+
+        let payload: TelegramApiResponse<bool> = response.json().await?;
+
+        if !payload.ok {
+            anyhow::bail!(
+                "telegram api error: {}",
+                payload
+                    .description
+                    .unwrap_or_else(|| "unknown error".to_string())
+            );
+        }
+
+        Ok(())
+    }
+
     async fn list_chats(
         &self,
         token: &MessengerToken,
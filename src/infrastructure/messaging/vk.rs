@@ -2,31 +2,40 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use reqwest::Client;
+use chrono::{Duration, Utc};
 use serde::Deserialize;
+use uuid::Uuid;
 
 use crate::{
-    application::services::messenger::{MessengerClient, PaginatedChats, PaginationParams},
+    application::services::messenger::{
+        ConnectionPoolStats, MessengerClient, PaginatedChats, PaginationParams, RefreshedToken,
+        Unauthorized,
+    },
     domain::models::{
-        MessageContent, MessengerChat, MessengerChatType, MessengerToken, MessengerType,
+        ChatAction, MessageContent, MessengerChat, MessengerChatType, MessengerToken, MessengerType,
     },
+    infrastructure::messaging::http::{HttpPoolConfig, PooledHttpClient},
 };
 
 pub struct VkClient {
-    http: Client,
+    http: PooledHttpClient,
     base_url: String,
+    id_url: String,
     api_version: String,
+    client_id: Option<String>,
 }
 
 impl VkClient {
-    pub fn new() -> Arc<dyn MessengerClient> {
+    /// `client_id` is the VK ID application tokens were issued to; without it
+    /// tokens cannot be refreshed.
+    pub fn new(client_id: Option<String>, pool: &HttpPoolConfig) -> Arc<dyn MessengerClient> {
         Arc::new(Self {
-            http: Client::builder()
-                .user_agent("messaging-service/vk")
-                .build()
+            http: PooledHttpClient::new(MessengerType::Vk, "messaging-service/vk", pool)
                 .expect("failed to build vk client"),
             base_url: "https://api.vk.com".to_string(),
+            id_url: "https://id.vk.com".to_string(),
             api_version: "5.199".to_string(),
+            client_id,
         }) as Arc<dyn MessengerClient>
     }
 
@@ -39,6 +48,13 @@ impl VkClient {
             _ => MessengerChatType::Unknown,
         }
     }
+
+    /// VK ignores a send whose `random_id` it has already seen for the peer,
+    /// so deriving it from the message makes redeliveries harmless.
+    fn random_id(message_id: Uuid) -> i32 {
+        let bytes = message_id.as_bytes();
+        (u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) >> 1) as i32
+    }
 }
 
 #[async_trait]
@@ -47,11 +63,20 @@ impl MessengerClient for VkClient {
         MessengerType::Vk
     }
 
+    fn deduplicates_sends(&self) -> bool {
+        true
+    }
+
+    fn pool_stats(&self) -> Option<ConnectionPoolStats> {
+        Some(self.http.stats())
+    }
+
     async fn send(
         &self,
         token: &MessengerToken,
         recipient: &str,
         content: &MessageContent,
+        message_id: Uuid,
     ) -> anyhow::Result<()> {
         let url = format!("{}/method/messages.send", self.base_url);
 
@@ -63,7 +88,7 @@ impl MessengerClient for VkClient {
         })?;
 
         let peer_id_str = peer_id.to_string();
-        let random_id_str = chrono::Utc::now().timestamp_millis().to_string();
+        let random_id_str = Self::random_id(message_id).to_string();
 
         let response = self
             .http
@@ -81,11 +106,7 @@ impl MessengerClient for VkClient {
         let payload: VkEnvelope<i64> = response.json().await?;
 
         if let Some(error) = payload.error {
-            anyhow::bail!(
-                "vk api error {}: {}",
-                error.error_code,
-                error.error_msg.unwrap_or_else(|| "unknown".to_string())
-            );
+            return Err(error.into_error());
         }
 
         // If response is present, message was sent successfully
@@ -93,6 +114,48 @@ impl MessengerClient for VkClient {
         Ok(())
     }
 
+    async fn send_chat_action(
+        &self,
+        token: &MessengerToken,
+        recipient: &str,
+        action: ChatAction,
+    ) -> anyhow::Result<()> {
+        let url = format!("{}/method/messages.setActivity", self.base_url);
+
+        let peer_id: i64 = recipient.parse().map_err(|_| {
+            anyhow::anyhow!(
+                "invalid vk peer_id format: expected integer, got '{}'",
+                recipient
+            )
+        })?;
+
+        let peer_id_str = peer_id.to_string();
+        let activity = match action {
+            ChatAction::Typing => "typing",
+            ChatAction::UploadPhoto => "photo",
+        };
+
+        let response = self
+            .http
+            .get(&url)
+            .query(&[
+                ("access_token", token.access_token.as_str()),
+                ("v", self.api_version.as_str()),
+                ("peer_id", &peer_id_str),
+                ("type", activity),
+            ])
+            .send()
+            .await?;
+
+        let payload: VkEnvelope<i64> = response.json().await?;
+
+        if let Some(error) = payload.error {
+            return Err(error.into_error());
+        }
+
+        Ok(())
+    }
+
     async fn list_chats(
         &self,
         token: &MessengerToken,
@@ -122,11 +185,7 @@ impl MessengerClient for VkClient {
         let payload: VkEnvelope<VkConversationsResponse> = response.json().await?;
 
         if let Some(error) = payload.error {
-            anyhow::bail!(
-                "vk api error {}: {}",
-                error.error_code,
-                error.error_msg.unwrap_or_else(|| "unknown".to_string())
-            );
+            return Err(error.into_error());
         }
 
         let data = payload
@@ -193,6 +252,56 @@ impl MessengerClient for VkClient {
             next_offset,
         })
     }
+
+    async fn refresh(&self, token: &MessengerToken) -> anyhow::Result<Option<RefreshedToken>> {
+        let (Some(client_id), Some(refresh_token), Some(device_id)) = (
+            self.client_id.as_deref(),
+            token.refresh_token.as_deref(),
+            token.device_id.as_deref(),
+        ) else {
+            return Ok(None);
+        };
+
+        let url = format!("{}/oauth2/auth", self.id_url);
+        let state = Uuid::new_v4().to_string();
+
+        let response = self
+            .http
+            .post(&url)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+                ("client_id", client_id),
+                ("device_id", device_id),
+                ("state", state.as_str()),
+            ])
+            .send()
+            .await?;
+
+        let payload: VkIdTokenResponse = response.json().await?;
+
+        if let Some(error) = payload.error {
+            anyhow::bail!(
+                "vk id refresh error {}: {}",
+                error,
+                payload
+                    .error_description
+                    .unwrap_or_else(|| "unknown".to_string())
+            );
+        }
+
+        let access_token = payload
+            .access_token
+            .ok_or_else(|| anyhow::anyhow!("vk id: empty refresh response"))?;
+
+        Ok(Some(RefreshedToken {
+            access_token,
+            refresh_token: payload.refresh_token,
+            expires_at: payload
+                .expires_in
+                .map(|seconds| Utc::now() + Duration::seconds(seconds)),
+        }))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -207,6 +316,32 @@ struct VkError {
     error_msg: Option<String>,
 }
 
+impl VkError {
+    /// Error 5 is VK's "user authorization failed", raised for expired or
+    /// revoked access tokens.
+    fn into_error(self) -> anyhow::Error {
+        let message = format!(
+            "vk api error {}: {}",
+            self.error_code,
+            self.error_msg.unwrap_or_else(|| "unknown".to_string())
+        );
+        if self.error_code == 5 {
+            Unauthorized(message).into()
+        } else {
+            anyhow::anyhow!(message)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VkIdTokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_in: Option<i64>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct VkConversationsResponse {
     count: Option<i32>,
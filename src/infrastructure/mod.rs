@@ -1,2 +1,8 @@
+pub mod auth;
+pub mod login_throttle;
+pub mod mail;
 pub mod messaging;
+pub mod rate_limit;
 pub mod repositories;
+pub mod secrets;
+pub mod telemetry;
@@ -0,0 +1,43 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use crate::application::services::rate_limit::{RateDecision, RateLimit, RateLimitStore};
+
+/// Buckets kept before full ones are swept out.
+const SWEEP_THRESHOLD: usize = 10_000;
+
+/// Process-local buckets, for single-instance deployments. Each instance
+/// enforces the limits on its own.
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, (f64, Instant)>>,
+}
+
+#[async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn take(&self, key: &str, limit: &RateLimit, fill: f64) -> anyhow::Result<RateDecision> {
+        let now = Instant::now();
+        let mut buckets = self
+            .buckets
+            .lock()
+            .expect("rate limit buckets lock poisoned");
+        if buckets.len() >= SWEEP_THRESHOLD {
+            // A bucket untouched for a whole window is full again, at least
+            // as full as a missing one.
+            buckets.retain(|_, (_, updated_at)| now.duration_since(*updated_at) < limit.window);
+        }
+
+        let (tokens, elapsed) = match buckets.get(key) {
+            Some((tokens, updated_at)) => (*tokens, now.duration_since(*updated_at)),
+            None => (f64::from(limit.capacity) * fill, Duration::ZERO),
+        };
+        let (tokens, decision) = limit.take(tokens, elapsed);
+        buckets.insert(key.to_string(), (tokens, now));
+        Ok(decision)
+    }
+}
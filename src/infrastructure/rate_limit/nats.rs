@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use async_nats::jetstream::{
+    self,
+    kv::{self, Operation, Store},
+};
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::application::services::rate_limit::{RateDecision, RateLimit, RateLimitStore};
+
+/// Attempts at writing a bucket before giving up to other instances
+/// updating it at the same time.
+const MAX_CONFLICTS: usize = 5;
+
+/// Buckets kept in a JetStream key-value bucket, for deployments running
+/// NATS without Redis. Each bucket is a `tokens updated_at_ms` value
+/// written only over the revision it was read at, so instances don't race.
+pub struct NatsRateLimitStore {
+    store: Store,
+}
+
+impl NatsRateLimitStore {
+    /// Values untouched for `max_age`, which should be the longest window,
+    /// expire: those buckets are full again anyway.
+    pub async fn new(url: &str, bucket: &str, max_age: Duration) -> anyhow::Result<Self> {
+        let client = async_nats::connect(url).await?;
+        let context = jetstream::new(client);
+        let store = match context.get_key_value(bucket).await {
+            Ok(store) => store,
+            Err(_) => {
+                context
+                    .create_key_value(kv::Config {
+                        bucket: bucket.to_string(),
+                        history: 1,
+                        max_age,
+                        ..Default::default()
+                    })
+                    .await?
+            }
+        };
+        Ok(Self { store })
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for NatsRateLimitStore {
+    async fn take(&self, key: &str, limit: &RateLimit, fill: f64) -> anyhow::Result<RateDecision> {
+        // Keys may carry characters NATS subjects can't, such as the colons
+        // of IPv6 addresses.
+        let key = hex::encode(key);
+        for _ in 0..MAX_CONFLICTS {
+            let entry = self
+                .store
+                .entry(key.as_str())
+                .await?
+                .filter(|entry| entry.operation == Operation::Put);
+            let now = Utc::now().timestamp_millis();
+            let stored = entry.as_ref().and_then(|entry| {
+                let value = std::str::from_utf8(&entry.value).ok()?;
+                let (tokens, updated_at) = value.split_once(' ')?;
+                Some((tokens.parse::<f64>().ok()?, updated_at.parse::<i64>().ok()?))
+            });
+            let (tokens, elapsed) = match stored {
+                Some((tokens, updated_at)) => (
+                    tokens,
+                    Duration::from_millis(now.saturating_sub(updated_at).max(0) as u64),
+                ),
+                None => (f64::from(limit.capacity) * fill, Duration::ZERO),
+            };
+            let (tokens, decision) = limit.take(tokens, elapsed);
+
+            let value = format!("{tokens} {now}").into();
+            let written = match &entry {
+                Some(entry) => self
+                    .store
+                    .update(key.as_str(), value, entry.revision)
+                    .await
+                    .is_ok(),
+                None => self.store.create(key.as_str(), value).await.is_ok(),
+            };
+            if written {
+                return Ok(decision);
+            }
+        }
+        anyhow::bail!("rate limit bucket kept changing while being updated")
+    }
+}
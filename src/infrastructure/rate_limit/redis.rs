@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::{Script, aio::ConnectionManager};
+
+use crate::application::services::rate_limit::{RateDecision, RateLimit, RateLimitStore};
+
+/// Refills and takes from the bucket in one step, on Redis' clock, so
+/// instances neither race nor disagree about time. Returns the milliseconds
+/// to wait, 0 when the request is allowed.
+const TAKE_SCRIPT: &str = r#"
+local capacity = tonumber(ARGV[1])
+local rate = tonumber(ARGV[2])
+local ttl = tonumber(ARGV[3])
+local fill = tonumber(ARGV[4])
+local time = redis.call('TIME')
+local now = tonumber(time[1]) * 1000 + math.floor(tonumber(time[2]) / 1000)
+
+local bucket = redis.call('HMGET', KEYS[1], 'tokens', 'updated_at')
+local tokens = tonumber(bucket[1]) or capacity * fill
+local updated_at = tonumber(bucket[2]) or now
+tokens = math.min(capacity, tokens + math.max(0, now - updated_at) * rate / 1000)
+
+local wait = 0
+if tokens >= 1 then
+    tokens = tokens - 1
+else
+    wait = math.ceil((1 - tokens) * 1000 / rate)
+end
+redis.call('HSET', KEYS[1], 'tokens', tostring(tokens), 'updated_at', now)
+redis.call('PEXPIRE', KEYS[1], ttl)
+return wait
+"#;
+
+/// Buckets shared by every instance using the same Redis.
+pub struct RedisRateLimitStore {
+    connection: ConnectionManager,
+    script: Script,
+    prefix: String,
+}
+
+impl RedisRateLimitStore {
+    pub async fn new(url: &str, prefix: &str) -> anyhow::Result<Self> {
+        let client = redis::Client::open(url)?;
+        let connection = ConnectionManager::new(client).await?;
+        Ok(Self {
+            connection,
+            script: Script::new(TAKE_SCRIPT),
+            prefix: prefix.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn take(&self, key: &str, limit: &RateLimit, fill: f64) -> anyhow::Result<RateDecision> {
+        // Buckets untouched for a window are full again and can go.
+        let ttl = limit.window.as_millis().max(1) as u64;
+        let wait: u64 = self
+            .script
+            .key(format!("{}:{key}", self.prefix))
+            .arg(limit.capacity)
+            .arg(limit.refill_rate())
+            .arg(ttl)
+            .arg(fill)
+            .invoke_async(&mut self.connection.clone())
+            .await?;
+
+        Ok(if wait == 0 {
+            RateDecision::Allow
+        } else {
+            RateDecision::Limited(Duration::from_millis(wait))
+        })
+    }
+}
@@ -1 +1,82 @@
+pub mod mysql;
 pub mod postgres;
+mod records;
+pub mod retrying;
+
+use std::sync::Arc;
+
+use crate::{
+    application::services::{database_stats::DatabaseMonitor, status_feed::StatusListener},
+    domain::repositories::{
+        AccountErasureRepository, ApiKeyRepository, ConsentRepository, DispatchGuardRepository,
+        HistoryPartitionRepository, IdempotencyKeyRepository, IdentityRepository,
+        LoginTokenRepository, MessageHistoryRepository, MessageLinkRepository,
+        MessengerTokenRepository, OrganizationRepository, OtpRepository, PasswordRepository,
+        RefreshTokenRepository, RoutingRuleRepository, SessionRepository, UserRepository,
+        WorkerRepository,
+    },
+};
+
+use retrying::{
+    RetryPolicy, RetryingDispatchGuardRepository, RetryingMessageHistoryRepository,
+    RetryingMessengerTokenRepository,
+};
+
+/// Every repository, backed by one database.
+pub struct Repositories {
+    pub users: Arc<dyn UserRepository>,
+    pub tokens: Arc<dyn MessengerTokenRepository>,
+    pub history: Arc<dyn MessageHistoryRepository>,
+    pub otp: Arc<dyn OtpRepository>,
+    pub consents: Arc<dyn ConsentRepository>,
+    pub links: Arc<dyn MessageLinkRepository>,
+    pub idempotency: Arc<dyn IdempotencyKeyRepository>,
+    pub guards: Arc<dyn DispatchGuardRepository>,
+    pub passwords: Arc<dyn PasswordRepository>,
+    pub login_tokens: Arc<dyn LoginTokenRepository>,
+    pub erasures: Arc<dyn AccountErasureRepository>,
+    pub identities: Arc<dyn IdentityRepository>,
+    pub api_keys: Arc<dyn ApiKeyRepository>,
+    pub refresh_tokens: Arc<dyn RefreshTokenRepository>,
+    pub sessions: Arc<dyn SessionRepository>,
+    pub organizations: Arc<dyn OrganizationRepository>,
+    pub routing: Arc<dyn RoutingRuleRepository>,
+    pub workers: Arc<dyn WorkerRepository>,
+    pub history_partitions: Arc<dyn HistoryPartitionRepository>,
+    pub database: Arc<dyn DatabaseMonitor>,
+    /// Relays status changes made by every instance; `None` when the
+    /// backend cannot notify.
+    pub status_listener: Option<Arc<dyn StatusListener>>,
+}
+
+/// Connects to the database `url` points at, choosing the backend by the
+/// URL scheme, and runs its migrations. History listings and statistics are
+/// read from `read_url`, a replica of the same backend, when given.
+///
+/// Repositories on the delivery path repeat calls that fail transiently
+/// under `retry`, so a failover doesn't fail the deliveries in flight.
+pub async fn connect(
+    url: &str,
+    read_url: Option<&str>,
+    max_connections: u32,
+    retry: RetryPolicy,
+) -> anyhow::Result<Repositories> {
+    let backend = |url: &str| match url.split_once("://").map(|(scheme, _)| scheme) {
+        Some("postgres" | "postgresql") => Some("postgres"),
+        Some("mysql" | "mariadb") => Some("mysql"),
+        _ => None,
+    };
+    if read_url.is_some_and(|read_url| backend(read_url) != backend(url)) {
+        anyhow::bail!("DATABASE_READ_URL must use the same backend as DATABASE_URL");
+    }
+    let mut repositories = match backend(url) {
+        Some("postgres") => postgres::connect(url, read_url, max_connections, &retry).await?,
+        Some("mysql") => mysql::connect(url, read_url, max_connections, &retry).await?,
+        _ => anyhow::bail!("unsupported DATABASE_URL scheme, expected postgres:// or mysql://"),
+    };
+    repositories.tokens = RetryingMessengerTokenRepository::new(repositories.tokens, retry.clone());
+    repositories.history =
+        RetryingMessageHistoryRepository::new(repositories.history, retry.clone());
+    repositories.guards = RetryingDispatchGuardRepository::new(repositories.guards, retry);
+    Ok(repositories)
+}
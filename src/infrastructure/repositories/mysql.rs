@@ -0,0 +1,3026 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Months, NaiveDate, NaiveTime, Utc};
+use sqlx::{
+    FromRow, MySql, Pool, Row,
+    migrate::Migrator,
+    mysql::{MySqlPoolOptions, MySqlQueryResult},
+};
+use tokio::sync::mpsc::Sender;
+use tokio_stream::StreamExt;
+use uuid::Uuid;
+
+use crate::{
+    domain::{
+        models::{
+            AccountErasure, ApiKey, AttemptOutcomes, Backlog, ConsentRecord, DeliveryStatsBucket,
+            DispatchClaim, FailedMessageFilter, IdempotencyRecord, MemberRole, MessageAttempt,
+            MessageClass, MessageHistoryEntry, MessageHistoryFilter, MessageLink, MessageStatus,
+            MessageStatusKind, MessengerToken, MessengerType, NewMessageHistoryEntry, Organization,
+            OrganizationInvitation, OrganizationMember, OtpCode, PasswordCredential, RefreshToken,
+            RequestedBy, RoutingRule, RoutingRuleVersion, Session, TokenHealth, TokenSelector,
+            User, UserIdentity, Worker,
+        },
+        repositories::{
+            AccountErasureRepository, ApiKeyRepository, ConsentRepository, DispatchGuardRepository,
+            HistoryPartitionRepository, IdempotencyKeyRepository, IdentityRepository,
+            LoginTokenRepository, MessageHistoryRepository, MessageLinkRepository,
+            MessengerTokenRepository, OrganizationRepository, OtpRepository, PasswordRepository,
+            RefreshTokenRepository, RoutingRuleRepository, SessionRepository, UserRepository,
+            WorkerRepository,
+        },
+    },
+    infrastructure::repositories::{
+        Repositories,
+        records::{
+            AccountErasureRecord, ApiKeyRecord, BacklogRecord, ConsentRecordRow,
+            DeliveryStatsRecord, IdempotencyKeyRecord, IdentityRecord, MembershipRecord,
+            MessageHistoryRecord, MessageLinkRecord, MessengerTokenRecord,
+            OrganizationInvitationRecord, OrganizationMemberRecord, OrganizationRecord,
+            OtpCodeRecord, PasswordRecord, RefreshTokenRecord, RoutingRuleRecord,
+            RoutingRuleVersionRecord, SessionRecord, TokenHealthRecord, UserRecord, WorkerRecord,
+            backlog_from_records, consent_status_to_str, escape_like, message_status_from_str,
+            message_status_to_fields, message_type_to_str, requested_by_from_str,
+            requested_by_to_str, token_selector_fields, token_status_to_str,
+        },
+        retrying::{PoolMonitor, RetryPolicy},
+    },
+};
+
+pub type MySqlPool = Pool<MySql>;
+
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations_mysql");
+
+pub async fn connect(
+    url: &str,
+    read_url: Option<&str>,
+    max_connections: u32,
+    retry: &RetryPolicy,
+) -> anyhow::Result<Repositories> {
+    // MariaDB speaks the MySQL protocol.
+    let url = url.replacen("mariadb://", "mysql://", 1);
+    let pool = MySqlPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(&url)
+        .await?;
+    MIGRATOR.run(&pool).await?;
+    let replica = match read_url {
+        Some(read_url) => Some(
+            MySqlPoolOptions::new()
+                .max_connections(max_connections)
+                .connect(&read_url.replacen("mariadb://", "mysql://", 1))
+                .await?,
+        ),
+        None => None,
+    };
+    let read_pool = replica.clone().unwrap_or_else(|| pool.clone());
+    let mut pools = vec![("primary", pool.clone())];
+    pools.extend(replica.map(|replica| ("replica", replica)));
+
+    Ok(Repositories {
+        users: MySqlUserRepository::new(pool.clone()),
+        tokens: MySqlMessengerTokenRepository::new(pool.clone()),
+        history: MySqlMessageHistoryRepository::new(pool.clone(), read_pool),
+        otp: MySqlOtpRepository::new(pool.clone()),
+        consents: MySqlConsentRepository::new(pool.clone()),
+        links: MySqlMessageLinkRepository::new(pool.clone()),
+        idempotency: MySqlIdempotencyKeyRepository::new(pool.clone()),
+        guards: MySqlDispatchGuardRepository::new(pool.clone()),
+        passwords: MySqlPasswordRepository::new(pool.clone()),
+        login_tokens: MySqlLoginTokenRepository::new(pool.clone()),
+        erasures: MySqlAccountErasureRepository::new(pool.clone()),
+        identities: MySqlIdentityRepository::new(pool.clone()),
+        api_keys: MySqlApiKeyRepository::new(pool.clone()),
+        refresh_tokens: MySqlRefreshTokenRepository::new(pool.clone()),
+        sessions: MySqlSessionRepository::new(pool.clone()),
+        organizations: MySqlOrganizationRepository::new(pool.clone()),
+        routing: MySqlRoutingRuleRepository::new(pool.clone()),
+        workers: MySqlWorkerRepository::new(pool.clone()),
+        history_partitions: MySqlHistoryPartitionRepository::new(pool),
+        database: PoolMonitor::new(pools, retry.clone(), &MIGRATOR),
+        // MySQL has no notifications; status changes are not relayed.
+        status_listener: None,
+    })
+}
+
+/// MySQL has no `ON CONFLICT DO NOTHING` that reports whether the row was
+/// new, so inserts that may race are run plainly and a duplicate key is
+/// read as having lost.
+fn inserted(result: Result<MySqlQueryResult, sqlx::Error>) -> anyhow::Result<bool> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(sqlx::Error::Database(err)) if err.is_unique_violation() => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlUserRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlUserRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl UserRepository for MySqlUserRepository {
+    async fn find_by_email(&self, email: &str) -> anyhow::Result<Option<User>> {
+        let record = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                   updated_at
+            FROM users
+            WHERE email = ?
+            "#,
+        )
+        .bind(email)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(User::try_from).transpose()
+    }
+
+    async fn get(&self, id: &Uuid) -> anyhow::Result<Option<User>> {
+        let record = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                   updated_at
+            FROM users
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(User::try_from).transpose()
+    }
+
+    async fn list(
+        &self,
+        search: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<User>, bool)> {
+        let limit = limit.unwrap_or(50).min(200) as i64;
+        let offset = offset.unwrap_or(0) as i64;
+        // Wildcards typed by the caller match literally.
+        let search = search.map(escape_like);
+
+        let rows = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                   updated_at
+            FROM users
+            WHERE ? IS NULL
+               OR email LIKE CONCAT('%', ?, '%')
+               OR display_name LIKE CONCAT('%', ?, '%')
+            ORDER BY created_at, id
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(search.as_deref())
+        .bind(search.as_deref())
+        .bind(search.as_deref())
+        .bind(limit + 1)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_more = rows.len() > limit as usize;
+        let users = rows
+            .into_iter()
+            .take(limit as usize)
+            .map(User::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((users, has_more))
+    }
+
+    async fn upsert(&self, user: &User) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO users (
+                id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                email = VALUES(email),
+                display_name = VALUES(display_name),
+                role = VALUES(role),
+                disabled_at = VALUES(disabled_at),
+                email_verified_at = COALESCE(email_verified_at, VALUES(email_verified_at)),
+                updated_at = VALUES(updated_at)
+            "#,
+        )
+        .bind(user.id)
+        .bind(&user.email)
+        .bind(&user.display_name)
+        .bind(user.role.as_str())
+        .bind(user.disabled_at)
+        .bind(user.email_verified_at)
+        .bind(user.created_at)
+        .bind(user.updated_at)
+        .execute(&mut *tx)
+        .await?;
+        // Present from the first upsert on.
+        let result = sqlx::query(
+            r#"
+            INSERT INTO organizations (id, name, created_at)
+            VALUES (?, ?, ?)
+            "#,
+        )
+        .bind(user.id)
+        .bind(user.display_name.as_deref().unwrap_or(&user.email))
+        .bind(user.created_at)
+        .execute(&mut *tx)
+        .await;
+        if inserted(result)? {
+            insert_organization_member(
+                &mut *tx,
+                &OrganizationMember {
+                    organization_id: user.id,
+                    user_id: user.id,
+                    role: MemberRole::Owner,
+                    created_at: user.created_at,
+                },
+            )
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlMessengerTokenRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlMessengerTokenRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl MessengerTokenRepository for MySqlMessengerTokenRepository {
+    async fn upsert(&self, mut token: MessengerToken) -> anyhow::Result<MessengerToken> {
+        token.updated_at = Utc::now();
+        let status = token_status_to_str(token.status);
+        sqlx::query(
+            r#"
+            INSERT INTO messenger_tokens (
+                id,
+                organization_id,
+                user_id,
+                shared,
+                messenger,
+                label,
+                access_token,
+                refresh_token,
+                expires_at,
+                device_id,
+                status,
+                created_at,
+                updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                shared = VALUES(shared),
+                label = VALUES(label),
+                access_token = VALUES(access_token),
+                refresh_token = VALUES(refresh_token),
+                expires_at = VALUES(expires_at),
+                device_id = VALUES(device_id),
+                status = VALUES(status),
+                updated_at = VALUES(updated_at)
+            "#,
+        )
+        .bind(token.id)
+        .bind(token.organization_id)
+        .bind(token.user_id)
+        .bind(token.shared)
+        .bind(token.messenger.as_str())
+        .bind(&token.label)
+        .bind(&token.access_token)
+        .bind(&token.refresh_token)
+        .bind(token.expires_at)
+        .bind(&token.device_id)
+        .bind(status)
+        .bind(token.created_at)
+        .bind(token.updated_at)
+        .execute(&self.pool)
+        .await?;
+
+        let record = sqlx::query_as::<_, MessengerTokenRecord>(
+            r#"
+            SELECT id, organization_id, user_id, shared, messenger, label, access_token,
+                   refresh_token, expires_at, device_id, status, created_at, updated_at
+            FROM messenger_tokens
+            WHERE id = ?
+            "#,
+        )
+        .bind(token.id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        record.try_into()
+    }
+
+    async fn find_active(
+        &self,
+        organization_id: &Uuid,
+        sender: &Uuid,
+        messenger: MessengerType,
+        selector: &TokenSelector,
+    ) -> anyhow::Result<Option<MessengerToken>> {
+        let (token_id, label) = token_selector_fields(selector);
+        let record = sqlx::query_as::<_, MessengerTokenRecord>(
+            r#"
+            SELECT id, organization_id, user_id, shared, messenger, label, access_token,
+                   refresh_token, expires_at, device_id, status, created_at, updated_at
+            FROM messenger_tokens
+            WHERE organization_id = ?
+              AND (shared OR user_id = ?)
+              AND messenger = ?
+              AND status = 'active'
+              AND (? IS NULL OR id = ?)
+              AND (? IS NULL OR label = ?)
+            ORDER BY updated_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(organization_id)
+        .bind(sender)
+        .bind(messenger.as_str())
+        .bind(token_id)
+        .bind(token_id)
+        .bind(label)
+        .bind(label)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(|record| record.try_into()).transpose()
+    }
+
+    async fn list_by_organization(
+        &self,
+        organization_id: &Uuid,
+    ) -> anyhow::Result<Vec<MessengerToken>> {
+        let rows = sqlx::query_as::<_, MessengerTokenRecord>(
+            r#"
+            SELECT id, organization_id, user_id, shared, messenger, label, access_token,
+                   refresh_token, expires_at, device_id, status, created_at, updated_at
+            FROM messenger_tokens
+            WHERE organization_id = ?
+            ORDER BY updated_at DESC
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(|record| record.try_into()).collect()
+    }
+
+    async fn health(
+        &self,
+        now: DateTime<Utc>,
+        expiring_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TokenHealth>> {
+        let rows = sqlx::query_as::<_, TokenHealthRecord>(
+            r#"
+            SELECT messenger,
+                   CAST(SUM(status = 'active') AS SIGNED) AS active,
+                   CAST(SUM(status <> 'active') AS SIGNED) AS inactive,
+                   CAST(SUM(status = 'active' AND expires_at <= ?) AS SIGNED) AS expired,
+                   CAST(SUM(
+                       status = 'active' AND expires_at > ? AND expires_at <= ?
+                   ) AS SIGNED) AS expiring
+            FROM messenger_tokens
+            GROUP BY messenger
+            ORDER BY messenger
+            "#,
+        )
+        .bind(now)
+        .bind(now)
+        .bind(expiring_before)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(TokenHealth::try_from).collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlMessageHistoryRepository {
+    pool: MySqlPool,
+    /// Serves listings and statistics, which tolerate replication lag.
+    /// Lookups by id stay on `pool` since they follow writes.
+    read_pool: MySqlPool,
+}
+
+impl MySqlMessageHistoryRepository {
+    pub fn new(pool: MySqlPool, read_pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool, read_pool })
+    }
+}
+
+#[async_trait]
+impl MessageHistoryRepository for MySqlMessageHistoryRepository {
+    async fn insert(&self, entry: NewMessageHistoryEntry) -> anyhow::Result<MessageHistoryEntry> {
+        let id = Uuid::new_v4();
+        let status = MessageStatus::Pending;
+        let now = Utc::now();
+        let (status_str, reason) = message_status_to_fields(&status);
+        let requested_by = requested_by_to_str(&entry.requested_by);
+
+        sqlx::query(
+            r#"
+            INSERT INTO message_history (
+                id, organization_id, user_id, messenger, recipient, body, message_type, status,
+                status_reason, attempts, requested_by, created_at, updated_at, deadline,
+                message_class, scheduled_for, priority, token_id, tags
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(id)
+        .bind(entry.organization_id)
+        .bind(entry.user_id)
+        .bind(entry.messenger.as_str())
+        .bind(&entry.recipient)
+        .bind(&entry.content.body)
+        .bind(message_type_to_str(&entry.content.message_type))
+        .bind(status_str)
+        .bind(reason)
+        .bind(0_i32)
+        .bind(requested_by)
+        .bind(now)
+        .bind(now)
+        .bind(entry.deadline)
+        .bind(entry.class.as_str())
+        .bind(entry.scheduled_for)
+        .bind(entry.priority.as_str())
+        .bind(entry.token_id)
+        .bind(serde_json::to_string(&entry.tags)?)
+        .execute(&self.pool)
+        .await?;
+
+        self.get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("message {id} vanished after insert"))
+    }
+
+    async fn update_status(
+        &self,
+        message_id: Uuid,
+        status: MessageStatus,
+        attempts: u32,
+    ) -> anyhow::Result<()> {
+        let (status_str, reason) = message_status_to_fields(&status);
+        sqlx::query(
+            r#"
+            UPDATE message_history
+            SET status = ?,
+                status_reason = ?,
+                attempts = ?,
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(status_str)
+        .bind(reason)
+        .bind(attempts as i32)
+        .bind(Utc::now())
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get(&self, message_id: Uuid) -> anyhow::Result<Option<MessageHistoryEntry>> {
+        let row = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE id = ?
+            "#,
+        )
+        .bind(message_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(MessageHistoryEntry::try_from).transpose()
+    }
+
+    async fn scheduled_times_since(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        class: MessageClass,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DateTime<Utc>>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT scheduled_for
+            FROM message_history
+            WHERE organization_id = ?
+              AND messenger = ?
+              AND recipient = ?
+              AND message_class = ?
+              AND scheduled_for > ?
+              AND status NOT IN ('suppressed', 'cancelled')
+            ORDER BY scheduled_for ASC
+            "#,
+        )
+        .bind(organization_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .bind(class.as_str())
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| row.try_get("scheduled_for").map_err(Into::into))
+            .collect()
+    }
+
+    async fn list_by_organization(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)> {
+        let limit = limit.unwrap_or(50).min(200) as i64;
+        let offset = offset.unwrap_or(0) as i64;
+        // Wildcards typed by the caller match literally.
+        let recipient = filter.recipient.as_deref().map(escape_like);
+        let status = filter.status.map(|status| status.as_str());
+        let messenger = filter.messenger.map(|messenger| messenger.as_str());
+
+        // Get one extra to check if there are more. LIKE is case-insensitive
+        // under the default collation, as ILIKE is on PostgreSQL.
+        let rows = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE organization_id = ?
+              AND deleted_at IS NULL
+              AND (? IS NULL OR status = ?)
+              AND (? IS NULL OR messenger = ?)
+              AND (? IS NULL OR recipient LIKE CONCAT('%', ?, '%'))
+              AND (? IS NULL OR created_at >= ?)
+              AND (? IS NULL OR created_at < ?)
+              AND (? IS NULL OR JSON_CONTAINS(tags, JSON_QUOTE(?)))
+            ORDER BY created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(organization_id)
+        .bind(status)
+        .bind(status)
+        .bind(messenger)
+        .bind(messenger)
+        .bind(recipient.as_deref())
+        .bind(recipient.as_deref())
+        .bind(filter.created_after)
+        .bind(filter.created_after)
+        .bind(filter.created_before)
+        .bind(filter.created_before)
+        .bind(filter.tag.as_deref())
+        .bind(filter.tag.as_deref())
+        .bind(limit + 1)
+        .bind(offset)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let has_more = rows.len() > limit as usize;
+        let entries: Vec<MessageHistoryEntry> = rows
+            .into_iter()
+            .take(limit as usize)
+            .map(MessageHistoryEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((entries, has_more))
+    }
+
+    async fn export(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
+        sink: Sender<anyhow::Result<MessageHistoryEntry>>,
+    ) -> anyhow::Result<()> {
+        let recipient = filter.recipient.as_deref().map(escape_like);
+        let status = filter.status.map(|status| status.as_str());
+        let messenger = filter.messenger.map(|messenger| messenger.as_str());
+
+        // Rows are read off the connection as they arrive, so the result set
+        // never sits in memory as a whole.
+        let mut rows = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE organization_id = ?
+              AND deleted_at IS NULL
+              AND (? IS NULL OR status = ?)
+              AND (? IS NULL OR messenger = ?)
+              AND (? IS NULL OR recipient LIKE CONCAT('%', ?, '%'))
+              AND (? IS NULL OR created_at >= ?)
+              AND (? IS NULL OR created_at < ?)
+              AND (? IS NULL OR JSON_CONTAINS(tags, JSON_QUOTE(?)))
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(organization_id)
+        .bind(status)
+        .bind(status)
+        .bind(messenger)
+        .bind(messenger)
+        .bind(recipient.as_deref())
+        .bind(recipient.as_deref())
+        .bind(filter.created_after)
+        .bind(filter.created_after)
+        .bind(filter.created_before)
+        .bind(filter.created_before)
+        .bind(filter.tag.as_deref())
+        .bind(filter.tag.as_deref())
+        .fetch(&self.read_pool);
+
+        while let Some(record) = rows.next().await {
+            let entry = MessageHistoryEntry::try_from(record?)?;
+            if sink.send(Ok(entry)).await.is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_by_recipient(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)> {
+        let limit = limit.unwrap_or(50).min(200) as i32;
+        let offset = offset.unwrap_or(0) as i32;
+
+        let rows = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE organization_id = ?
+              AND messenger = ?
+              AND recipient = ?
+              AND deleted_at IS NULL
+            ORDER BY created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(organization_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .bind(limit + 1)
+        .bind(offset)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let has_more = rows.len() > limit as usize;
+        let entries: Vec<MessageHistoryEntry> = rows
+            .into_iter()
+            .take(limit as usize)
+            .map(MessageHistoryEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((entries, has_more))
+    }
+
+    async fn count_by_status(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<(MessageStatusKind, u64)>> {
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT status, COUNT(*)
+            FROM message_history
+            WHERE organization_id = ?
+              AND deleted_at IS NULL
+            GROUP BY status
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(status, count)| {
+                let kind = MessageStatusKind::from_str(&status)
+                    .ok_or_else(|| anyhow::anyhow!("unknown message status {status}"))?;
+                Ok((kind, count as u64))
+            })
+            .collect()
+    }
+
+    async fn backlog(&self, now: DateTime<Utc>) -> anyhow::Result<Backlog> {
+        let rows = sqlx::query_as::<_, BacklogRecord>(
+            r#"
+            SELECT status,
+                   COUNT(*) AS count,
+                   MIN(CASE WHEN scheduled_for <= ? THEN scheduled_for END) AS oldest_due_at
+            FROM message_history
+            WHERE status IN ('pending', 'scheduled', 'in_flight', 'retrying', 'deferred')
+              AND deleted_at IS NULL
+            GROUP BY status
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.read_pool)
+        .await?;
+        backlog_from_records(rows)
+    }
+
+    async fn attempt_outcomes(&self, since: DateTime<Utc>) -> anyhow::Result<AttemptOutcomes> {
+        // SUM over no rows is NULL rather than 0.
+        let (sent, failed) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT CAST(COALESCE(SUM(status = 'sent'), 0) AS SIGNED),
+                   CAST(COALESCE(SUM(status IN ('failed', 'retrying')), 0) AS SIGNED)
+            FROM message_attempts
+            WHERE created_at >= ?
+            "#,
+        )
+        .bind(since)
+        .fetch_one(&self.read_pool)
+        .await?;
+        Ok(AttemptOutcomes {
+            sent: sent as u64,
+            failed: failed as u64,
+        })
+    }
+
+    async fn delivery_stats(
+        &self,
+        organization_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>> {
+        // Timestamps are stored in UTC, and SUM over a comparison is DECIMAL
+        // unless cast.
+        let rows = sqlx::query_as::<_, DeliveryStatsRecord>(
+            r#"
+            SELECT DATE(created_at) AS day,
+                   messenger,
+                   COUNT(*) AS total,
+                   CAST(SUM(status = 'sent') AS SIGNED) AS sent,
+                   CAST(SUM(status = 'failed') AS SIGNED) AS failed
+            FROM message_history
+            WHERE organization_id = ?
+              AND created_at >= ?
+              AND created_at < ?
+            GROUP BY day, messenger
+            ORDER BY day, messenger
+            "#,
+        )
+        .bind(organization_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        rows.into_iter()
+            .map(DeliveryStatsBucket::try_from)
+            .collect()
+    }
+
+    async fn sender_delivery_stats(
+        &self,
+        user_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>> {
+        let rows = sqlx::query_as::<_, DeliveryStatsRecord>(
+            r#"
+            SELECT DATE(created_at) AS day,
+                   messenger,
+                   COUNT(*) AS total,
+                   CAST(SUM(status = 'sent') AS SIGNED) AS sent,
+                   CAST(SUM(status = 'failed') AS SIGNED) AS failed
+            FROM message_history
+            WHERE user_id = ?
+              AND created_at >= ?
+              AND created_at < ?
+            GROUP BY day, messenger
+            ORDER BY day, messenger
+            "#,
+        )
+        .bind(user_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        rows.into_iter()
+            .map(DeliveryStatsBucket::try_from)
+            .collect()
+    }
+
+    async fn list_failed(
+        &self,
+        filter: &FailedMessageFilter,
+        limit: u32,
+    ) -> anyhow::Result<Vec<MessageHistoryEntry>> {
+        // Wildcards typed by the caller match literally.
+        let reason = filter.reason.as_ref().map(|reason| {
+            reason
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        });
+        let messenger = filter.messenger.map(|messenger| messenger.as_str());
+
+        let rows = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE status = 'failed'
+              AND (? IS NULL OR messenger = ?)
+              AND (? IS NULL OR updated_at >= ?)
+              AND (? IS NULL OR updated_at < ?)
+              AND (? IS NULL OR status_reason LIKE CONCAT('%', ?, '%'))
+            ORDER BY updated_at ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(messenger)
+        .bind(messenger)
+        .bind(filter.failed_after)
+        .bind(filter.failed_after)
+        .bind(filter.failed_before)
+        .bind(filter.failed_before)
+        .bind(reason.as_deref())
+        .bind(reason.as_deref())
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(MessageHistoryEntry::try_from)
+            .collect()
+    }
+
+    async fn log_attempt(
+        &self,
+        message_id: Uuid,
+        attempt_number: u32,
+        status: MessageStatus,
+        requested_by: RequestedBy,
+    ) -> anyhow::Result<()> {
+        let (status_str, reason) = message_status_to_fields(&status);
+        sqlx::query(
+            r#"
+            INSERT INTO message_attempts (
+                id, message_id, attempt_number, status, status_reason, requested_by, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(message_id)
+        .bind(attempt_number as i32)
+        .bind(status_str)
+        .bind(reason)
+        .bind(requested_by_to_str(&requested_by))
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn record_attempt(
+        &self,
+        message_id: Uuid,
+        status: MessageStatus,
+        attempt_number: u32,
+        requested_by: RequestedBy,
+    ) -> anyhow::Result<()> {
+        let (status_str, reason) = message_status_to_fields(&status);
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE message_history
+            SET status = ?,
+                status_reason = ?,
+                attempts = ?,
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(status_str)
+        .bind(&reason)
+        .bind(attempt_number as i32)
+        .bind(now)
+        .bind(message_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO message_attempts (
+                id, message_id, attempt_number, status, status_reason, requested_by, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(message_id)
+        .bind(attempt_number as i32)
+        .bind(status_str)
+        .bind(reason)
+        .bind(requested_by_to_str(&requested_by))
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get_attempts(&self, message_id: Uuid) -> anyhow::Result<Vec<MessageAttempt>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, message_id, attempt_number, status, status_reason, requested_by, created_at
+            FROM message_attempts
+            WHERE message_id = ?
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(message_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let status_str: String = row.try_get("status")?;
+                let reason: Option<String> = row.try_get("status_reason")?;
+                let status = message_status_from_str(&status_str, reason)?;
+                let requested_by_str: String = row.try_get("requested_by")?;
+                let requested_by = requested_by_from_str(&requested_by_str)?;
+
+                Ok(MessageAttempt {
+                    id: row.try_get("id")?,
+                    message_id: row.try_get("message_id")?,
+                    attempt_number: row.try_get::<i32, _>("attempt_number")? as u32,
+                    status,
+                    requested_by,
+                    created_at: row.try_get("created_at")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn redact(&self, message_id: Uuid) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE message_history
+            SET body = '[redacted]',
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn soft_delete(&self, message_id: Uuid) -> anyhow::Result<bool> {
+        let now = Utc::now();
+        let deleted = sqlx::query(
+            r#"
+            UPDATE message_history
+            SET deleted_at = ?,
+                updated_at = ?
+            WHERE id = ?
+              AND deleted_at IS NULL
+            "#,
+        )
+        .bind(now)
+        .bind(now)
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    async fn purge(&self, message_id: Uuid) -> anyhow::Result<bool> {
+        // Attempts, links, OTP codes, idempotency keys and dispatch guards
+        // go by cascade.
+        let purged = sqlx::query("DELETE FROM message_history WHERE id = ?")
+            .bind(message_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(purged > 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlOtpRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlOtpRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl OtpRepository for MySqlOtpRepository {
+    async fn replace_active(&self, code: &OtpCode) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            DELETE FROM otp_codes
+            WHERE user_id = ?
+              AND messenger = ?
+              AND recipient = ?
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(code.user_id)
+        .bind(code.messenger.as_str())
+        .bind(&code.recipient)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO otp_codes (
+                id, user_id, messenger, recipient, code_hash, message_id, failed_attempts,
+                expires_at, verified_at, created_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(code.id)
+        .bind(code.user_id)
+        .bind(code.messenger.as_str())
+        .bind(&code.recipient)
+        .bind(&code.code_hash)
+        .bind(code.message_id)
+        .bind(code.failed_attempts as i32)
+        .bind(code.expires_at)
+        .bind(code.verified_at)
+        .bind(code.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn find_active(
+        &self,
+        user_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<Option<OtpCode>> {
+        let record = sqlx::query_as::<_, OtpCodeRecord>(
+            r#"
+            SELECT id, user_id, messenger, recipient, code_hash, message_id, failed_attempts,
+                   expires_at, verified_at, created_at
+            FROM otp_codes
+            WHERE user_id = ?
+              AND messenger = ?
+              AND recipient = ?
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(OtpCode::try_from).transpose()
+    }
+
+    async fn reserve_attempt(&self, id: Uuid, max_attempts: u32) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE otp_codes
+            SET failed_attempts = failed_attempts + 1
+            WHERE id = ?
+              AND failed_attempts < ?
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(max_attempts)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn mark_verified(&self, id: Uuid) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE otp_codes
+            SET verified_at = ?
+            WHERE id = ?
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlConsentRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlConsentRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl ConsentRepository for MySqlConsentRepository {
+    async fn upsert(&self, record: &ConsentRecord) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO consents (
+                id, organization_id, messenger, recipient, status, source, proof, confirmation_hash,
+                created_at, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                status = VALUES(status),
+                source = VALUES(source),
+                proof = VALUES(proof),
+                confirmation_hash = VALUES(confirmation_hash),
+                updated_at = VALUES(updated_at)
+            "#,
+        )
+        .bind(record.id)
+        .bind(record.organization_id)
+        .bind(record.messenger.as_str())
+        .bind(&record.recipient)
+        .bind(consent_status_to_str(record.status))
+        .bind(&record.source)
+        .bind(&record.proof)
+        .bind(&record.confirmation_hash)
+        .bind(record.created_at)
+        .bind(record.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<Option<ConsentRecord>> {
+        let record = sqlx::query_as::<_, ConsentRecordRow>(
+            r#"
+            SELECT id, organization_id, messenger, recipient, status, source, proof, confirmation_hash,
+                   created_at, updated_at
+            FROM consents
+            WHERE organization_id = ?
+              AND messenger = ?
+              AND recipient = ?
+            "#,
+        )
+        .bind(organization_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(ConsentRecord::try_from).transpose()
+    }
+
+    async fn list_by_organization(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<ConsentRecord>> {
+        let rows = sqlx::query_as::<_, ConsentRecordRow>(
+            r#"
+            SELECT id, organization_id, messenger, recipient, status, source, proof, confirmation_hash,
+                   created_at, updated_at
+            FROM consents
+            WHERE organization_id = ?
+            ORDER BY updated_at DESC
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(ConsentRecord::try_from).collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlMessageLinkRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlMessageLinkRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl MessageLinkRepository for MySqlMessageLinkRepository {
+    async fn insert(&self, link: &MessageLink) -> anyhow::Result<()> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO message_links (source_id, target_id, kind, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(link.source_id)
+        .bind(link.target_id)
+        .bind(link.kind.as_str())
+        .bind(link.created_at)
+        .execute(&self.pool)
+        .await;
+        inserted(result)?;
+        Ok(())
+    }
+
+    async fn connected(&self, message_id: Uuid, limit: u32) -> anyhow::Result<Vec<MessageLink>> {
+        let rows = sqlx::query_as::<_, MessageLinkRecord>(
+            r#"
+            WITH RECURSIVE reachable (id) AS (
+                SELECT CAST(? AS BINARY(16))
+                UNION
+                SELECT CASE WHEN l.source_id = r.id THEN l.target_id ELSE l.source_id END
+                FROM message_links l
+                JOIN reachable r ON l.source_id = r.id OR l.target_id = r.id
+            )
+            SELECT source_id, target_id, kind, created_at
+            FROM message_links
+            WHERE source_id IN (SELECT id FROM reachable)
+            ORDER BY created_at ASC
+            LIMIT ?
+            "#,
+        )
+        .bind(message_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(MessageLink::try_from).collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlIdempotencyKeyRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlIdempotencyKeyRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl IdempotencyKeyRepository for MySqlIdempotencyKeyRepository {
+    async fn claim(
+        &self,
+        organization_id: Uuid,
+        key: &str,
+        request_hash: &str,
+    ) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (organization_id, `key`, request_hash, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(organization_id)
+        .bind(key)
+        .bind(request_hash)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await;
+        if inserted(result)? {
+            return Ok(None);
+        }
+
+        let record = sqlx::query_as::<_, IdempotencyKeyRecord>(
+            r#"
+            SELECT organization_id, `key`, request_hash, message_id, created_at
+            FROM idempotency_keys
+            WHERE organization_id = ?
+              AND `key` = ?
+            "#,
+        )
+        .bind(organization_id)
+        .bind(key)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Some(record.into()))
+    }
+
+    async fn complete(
+        &self,
+        organization_id: Uuid,
+        key: &str,
+        message_id: Uuid,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET message_id = ?
+            WHERE organization_id = ?
+              AND `key` = ?
+            "#,
+        )
+        .bind(message_id)
+        .bind(organization_id)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, organization_id: Uuid, key: &str) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE organization_id = ?
+              AND `key` = ?
+              AND message_id IS NULL
+            "#,
+        )
+        .bind(organization_id)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlDispatchGuardRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlDispatchGuardRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl DispatchGuardRepository for MySqlDispatchGuardRepository {
+    async fn claim(
+        &self,
+        message_id: Uuid,
+        attempt: u32,
+        worker_id: Uuid,
+    ) -> anyhow::Result<DispatchClaim> {
+        let guards = sqlx::query(
+            r#"
+            SELECT sent_at, worker_id
+            FROM dispatch_guards
+            WHERE message_id = ?
+            "#,
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut interrupted = None;
+        for guard in guards {
+            let sent_at: Option<DateTime<Utc>> = guard.try_get("sent_at")?;
+            if sent_at.is_some() {
+                return Ok(DispatchClaim::AlreadySent);
+            }
+            interrupted = Some(guard.try_get("worker_id")?);
+        }
+        if let Some(worker_id) = interrupted {
+            return Ok(DispatchClaim::Interrupted { worker_id });
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO dispatch_guards (message_id, attempt, worker_id, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(message_id)
+        .bind(attempt as i32)
+        .bind(worker_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await;
+        if inserted(result)? {
+            return Ok(DispatchClaim::Claimed);
+        }
+
+        // Losing the insert means a concurrent delivery of the same attempt
+        // got there first.
+        let winner = sqlx::query_scalar::<_, Option<Uuid>>(
+            r#"
+            SELECT worker_id
+            FROM dispatch_guards
+            WHERE message_id = ?
+              AND attempt = ?
+            "#,
+        )
+        .bind(message_id)
+        .bind(attempt as i32)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(DispatchClaim::Interrupted {
+            worker_id: winner.flatten(),
+        })
+    }
+
+    async fn mark_sent(&self, message_id: Uuid, attempt: u32) -> anyhow::Result<()> {
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO dispatch_guards (message_id, attempt, sent_at, created_at)
+            VALUES (?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE sent_at = VALUES(sent_at)
+            "#,
+        )
+        .bind(message_id)
+        .bind(attempt as i32)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, message_id: Uuid) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM dispatch_guards
+            WHERE message_id = ?
+              AND sent_at IS NULL
+            "#,
+        )
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlPasswordRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlPasswordRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl PasswordRepository for MySqlPasswordRepository {
+    async fn find(&self, user_id: Uuid) -> anyhow::Result<Option<PasswordCredential>> {
+        let record = sqlx::query_as::<_, PasswordRecord>(
+            r#"
+            SELECT user_id, password_hash, updated_at
+            FROM user_passwords
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn upsert(&self, user_id: Uuid, password_hash: &str) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO user_passwords (user_id, password_hash, updated_at)
+            VALUES (?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                password_hash = VALUES(password_hash),
+                updated_at = VALUES(updated_at)
+            "#,
+        )
+        .bind(user_id)
+        .bind(password_hash)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM password_resets
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn create_reset(
+        &self,
+        token_hash: &str,
+        user_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO password_resets (token_hash, user_id, expires_at, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(expires_at)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn consume_reset(&self, token_hash: &str) -> anyhow::Result<Option<Uuid>> {
+        // Without DELETE ... RETURNING the row is locked, read, then deleted,
+        // so two concurrent resets cannot both consume it.
+        let mut tx = self.pool.begin().await?;
+        let user_id = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT user_id
+            FROM password_resets
+            WHERE token_hash = ?
+              AND expires_at > ?
+            FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .bind(Utc::now())
+        .fetch_optional(&mut *tx)
+        .await?;
+        if user_id.is_some() {
+            sqlx::query(
+                r#"
+                DELETE FROM password_resets
+                WHERE token_hash = ?
+                "#,
+            )
+            .bind(token_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(user_id)
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlLoginTokenRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlLoginTokenRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl LoginTokenRepository for MySqlLoginTokenRepository {
+    async fn create(
+        &self,
+        token_hash: &str,
+        email: &str,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO login_tokens (token_hash, email, expires_at, created_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(token_hash)
+        .bind(email)
+        .bind(expires_at)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn consume(&self, token_hash: &str) -> anyhow::Result<Option<String>> {
+        // Locked, read, then deleted, as with password resets.
+        let mut tx = self.pool.begin().await?;
+        let email = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT email
+            FROM login_tokens
+            WHERE token_hash = ?
+              AND expires_at > ?
+            FOR UPDATE
+            "#,
+        )
+        .bind(token_hash)
+        .bind(Utc::now())
+        .fetch_optional(&mut *tx)
+        .await?;
+        if email.is_some() {
+            sqlx::query(
+                r#"
+                DELETE FROM login_tokens
+                WHERE token_hash = ?
+                "#,
+            )
+            .bind(token_hash)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(email)
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlAccountErasureRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlAccountErasureRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl AccountErasureRepository for MySqlAccountErasureRepository {
+    async fn start(&self, erasure: &AccountErasure) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO account_erasures (id, user_id, email_hash, requested_at)
+            VALUES (?, ?, ?, ?)
+            "#,
+        )
+        .bind(erasure.id)
+        .bind(erasure.user_id)
+        .bind(&erasure.email_hash)
+        .bind(erasure.requested_at)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET disabled_at = COALESCE(disabled_at, ?),
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(erasure.requested_at)
+        .bind(erasure.requested_at)
+        .bind(erasure.user_id)
+        .execute(&mut *tx)
+        .await?;
+        // Shared tokens stay with their organization, under another member.
+        sqlx::query(
+            r#"
+            UPDATE messenger_tokens
+            SET user_id = COALESCE(
+                    (
+                        SELECT members.user_id
+                        FROM organization_members members
+                        WHERE members.organization_id = messenger_tokens.organization_id
+                          AND members.user_id <> ?
+                        ORDER BY members.role = 'owner' DESC, members.created_at
+                        LIMIT 1
+                    ),
+                    user_id
+                )
+            WHERE user_id = ?
+              AND shared
+            "#,
+        )
+        .bind(erasure.user_id)
+        .bind(erasure.user_id)
+        .execute(&mut *tx)
+        .await?;
+        for statement in [
+            "DELETE FROM messenger_tokens WHERE user_id = ?",
+            "DELETE FROM api_keys WHERE user_id = ?",
+            "DELETE FROM user_passwords WHERE user_id = ?",
+            "DELETE FROM password_resets WHERE user_id = ?",
+            "DELETE FROM user_identities WHERE user_id = ?",
+        ] {
+            sqlx::query(statement)
+                .bind(erasure.user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn find_pending(&self, user_id: Uuid) -> anyhow::Result<Option<AccountErasure>> {
+        let record = sqlx::query_as::<_, AccountErasureRecord>(
+            r#"
+            SELECT id, user_id, email_hash, requested_at, completed_at, messages_purged
+            FROM account_erasures
+            WHERE user_id = ?
+              AND completed_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list_pending(&self) -> anyhow::Result<Vec<AccountErasure>> {
+        let rows = sqlx::query_as::<_, AccountErasureRecord>(
+            r#"
+            SELECT id, user_id, email_hash, requested_at, completed_at, messages_purged
+            FROM account_erasures
+            WHERE completed_at IS NULL
+            ORDER BY requested_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn purge_messages(&self, erasure: &AccountErasure, limit: u32) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        // Attempts, links, OTP codes, idempotency keys and dispatch guards
+        // go by cascade.
+        let purged = sqlx::query(
+            r#"
+            DELETE FROM message_history
+            WHERE organization_id = ?
+            LIMIT ?
+            "#,
+        )
+        .bind(erasure.user_id)
+        .bind(limit as i64)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if purged == 0 {
+            return Ok(0);
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE account_erasures
+            SET messages_purged = messages_purged + ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(purged as i64)
+        .bind(erasure.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(purged)
+    }
+
+    async fn anonymize_messages(
+        &self,
+        erasure: &AccountErasure,
+        limit: u32,
+    ) -> anyhow::Result<u64> {
+        let anonymized = sqlx::query(
+            r#"
+            UPDATE message_history
+            SET user_id = NULL
+            WHERE user_id = ?
+              AND organization_id <> ?
+            LIMIT ?
+            "#,
+        )
+        .bind(erasure.user_id)
+        .bind(erasure.user_id)
+        .bind(limit as i64)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(anonymized)
+    }
+
+    async fn complete(&self, erasure: &AccountErasure) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        // The personal organization shares the user's id.
+        for statement in [
+            "DELETE FROM organizations WHERE id = ?",
+            "DELETE FROM users WHERE id = ?",
+        ] {
+            sqlx::query(statement)
+                .bind(erasure.user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(
+            r#"
+            UPDATE account_erasures
+            SET completed_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(erasure.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list(&self, limit: u32) -> anyhow::Result<Vec<AccountErasure>> {
+        let rows = sqlx::query_as::<_, AccountErasureRecord>(
+            r#"
+            SELECT id, user_id, email_hash, requested_at, completed_at, messages_purged
+            FROM account_erasures
+            ORDER BY requested_at DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
+
+/// MySQL cannot partition tables that take part in foreign keys, so
+/// history stays in one table and retention deletes whole months of it;
+/// the foreign keys cascade the delete to attempts, links and the rest.
+#[derive(Clone)]
+pub struct MySqlHistoryPartitionRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlHistoryPartitionRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl HistoryPartitionRepository for MySqlHistoryPartitionRepository {
+    async fn ensure_partitions(&self, _months_ahead: u32) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn drop_partitions_before(&self, cutoff: DateTime<Utc>) -> anyhow::Result<Vec<String>> {
+        let months = sqlx::query_scalar::<_, String>(
+            r#"
+            SELECT DISTINCT DATE_FORMAT(created_at, '%Y%m')
+            FROM message_history
+            WHERE created_at < ?
+            ORDER BY 1
+            "#,
+        )
+        .bind(cutoff)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut dropped = Vec::new();
+        for month in months {
+            let start = NaiveDate::parse_from_str(&format!("{month}01"), "%Y%m%d")?;
+            let end = start
+                .checked_add_months(Months::new(1))
+                .ok_or_else(|| anyhow::anyhow!("history month out of range"))?;
+            if end > cutoff.date_naive() {
+                continue;
+            }
+            sqlx::query(
+                r#"
+                DELETE FROM message_history
+                WHERE created_at >= ?
+                  AND created_at < ?
+                "#,
+            )
+            .bind(start.and_time(NaiveTime::MIN).and_utc())
+            .bind(end.and_time(NaiveTime::MIN).and_utc())
+            .execute(&self.pool)
+            .await?;
+            dropped.push(format!("message_history_{month}"));
+        }
+        Ok(dropped)
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlIdentityRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlIdentityRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl IdentityRepository for MySqlIdentityRepository {
+    async fn find(&self, provider: &str, subject: &str) -> anyhow::Result<Option<UserIdentity>> {
+        let record = sqlx::query_as::<_, IdentityRecord>(
+            r#"
+            SELECT id, user_id, provider, subject, created_at
+            FROM user_identities
+            WHERE provider = ?
+              AND subject = ?
+            "#,
+        )
+        .bind(provider)
+        .bind(subject)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<UserIdentity>> {
+        let records = sqlx::query_as::<_, IdentityRecord>(
+            r#"
+            SELECT id, user_id, provider, subject, created_at
+            FROM user_identities
+            WHERE user_id = ?
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn link(&self, identity: &UserIdentity) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_identities (id, user_id, provider, subject, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(identity.id)
+        .bind(identity.user_id)
+        .bind(&identity.provider)
+        .bind(&identity.subject)
+        .bind(identity.created_at)
+        .execute(&self.pool)
+        .await;
+        inserted(result)
+    }
+
+    async fn unlink(&self, user_id: Uuid, identity_id: Uuid) -> anyhow::Result<bool> {
+        // A DELETE cannot count rows of its own table, so the user's
+        // identities are locked and counted first.
+        let mut tx = self.pool.begin().await?;
+        let linked = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id
+            FROM user_identities
+            WHERE user_id = ?
+            FOR UPDATE
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if linked.len() <= 1 || !linked.contains(&identity_id) {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            r#"
+            DELETE FROM user_identities
+            WHERE id = ?
+              AND user_id = ?
+            "#,
+        )
+        .bind(identity_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlRoutingRuleRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlRoutingRuleRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl RoutingRuleRepository for MySqlRoutingRuleRepository {
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<RoutingRule>> {
+        list_routing_rules(&self.pool, user_id).await
+    }
+
+    async fn get(&self, user_id: Uuid, rule_id: Uuid) -> anyhow::Result<Option<RoutingRule>> {
+        let row = sqlx::query_as::<_, RoutingRuleRow>(
+            r#"
+            SELECT id, user_id, name, position, enabled, match_event, match_class,
+                   CAST(match_tags AS CHAR) AS match_tags,
+                   CAST(match_metadata AS CHAR) AS match_metadata, messenger, recipient,
+                   template, priority, created_at, updated_at
+            FROM routing_rules
+            WHERE id = ?
+              AND user_id = ?
+            "#,
+        )
+        .bind(rule_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(RoutingRule::try_from).transpose()
+    }
+
+    async fn upsert(&self, rule: &RoutingRule) -> anyhow::Result<()> {
+        upsert_routing_rule(&self.pool, rule).await
+    }
+
+    async fn delete(&self, user_id: Uuid, rule_id: Uuid) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM routing_rules
+            WHERE id = ?
+              AND user_id = ?
+            "#,
+        )
+        .bind(rule_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn snapshot(&self, user_id: Uuid, reason: &str) -> anyhow::Result<i32> {
+        let mut tx = self.pool.begin().await?;
+        // Serializes snapshots of the same user so version numbers stay dense.
+        sqlx::query(
+            r#"
+            INSERT INTO routing_rule_sets (user_id, active_version)
+            VALUES (?, 0)
+            ON DUPLICATE KEY UPDATE active_version = active_version
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let rules = list_routing_rules(&mut *tx, user_id).await?;
+        let latest = sqlx::query_scalar::<_, Option<i32>>(
+            r#"
+            SELECT MAX(version)
+            FROM routing_rule_versions
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let version = latest.unwrap_or(0) + 1;
+
+        sqlx::query(
+            r#"
+            INSERT INTO routing_rule_versions (user_id, version, rules, reason, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(user_id)
+        .bind(version)
+        .bind(serde_json::to_string(&rules)?)
+        .bind(reason)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE routing_rule_sets
+            SET active_version = ?
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(version)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(version)
+    }
+
+    async fn list_versions(&self, user_id: Uuid) -> anyhow::Result<Vec<RoutingRuleVersion>> {
+        let rows = sqlx::query_as::<_, RoutingRuleVersionRecord>(
+            r#"
+            SELECT version, CAST(rules AS CHAR) AS rules, reason, created_at
+            FROM routing_rule_versions
+            WHERE user_id = ?
+            ORDER BY version DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(RoutingRuleVersion::try_from).collect()
+    }
+
+    async fn get_version(
+        &self,
+        user_id: Uuid,
+        version: i32,
+    ) -> anyhow::Result<Option<RoutingRuleVersion>> {
+        get_routing_rule_version(&self.pool, user_id, version).await
+    }
+
+    async fn active_version(&self, user_id: Uuid) -> anyhow::Result<Option<i32>> {
+        let version = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT active_version
+            FROM routing_rule_sets
+            WHERE user_id = ?
+              AND active_version > 0
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(version)
+    }
+
+    async fn activate(&self, user_id: Uuid, version: i32) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let Some(snapshot) = get_routing_rule_version(&mut *tx, user_id, version).await? else {
+            return Ok(false);
+        };
+
+        sqlx::query(
+            r#"
+            DELETE FROM routing_rules
+            WHERE user_id = ?
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        for rule in &snapshot.rules {
+            upsert_routing_rule(&mut *tx, rule).await?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO routing_rule_sets (user_id, active_version)
+            VALUES (?, ?)
+            ON DUPLICATE KEY UPDATE active_version = VALUES(active_version)
+            "#,
+        )
+        .bind(user_id)
+        .bind(version)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+async fn list_routing_rules<'e, E>(executor: E, user_id: Uuid) -> anyhow::Result<Vec<RoutingRule>>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    let rows = sqlx::query_as::<_, RoutingRuleRow>(
+        r#"
+        SELECT id, user_id, name, position, enabled, match_event, match_class,
+               CAST(match_tags AS CHAR) AS match_tags,
+               CAST(match_metadata AS CHAR) AS match_metadata, messenger, recipient,
+               template, priority, created_at, updated_at
+        FROM routing_rules
+        WHERE user_id = ?
+        ORDER BY position, created_at
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await?;
+    rows.into_iter().map(RoutingRule::try_from).collect()
+}
+
+async fn get_routing_rule_version<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    version: i32,
+) -> anyhow::Result<Option<RoutingRuleVersion>>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    let row = sqlx::query_as::<_, RoutingRuleVersionRecord>(
+        r#"
+        SELECT version, CAST(rules AS CHAR) AS rules, reason, created_at
+        FROM routing_rule_versions
+        WHERE user_id = ?
+          AND version = ?
+        "#,
+    )
+    .bind(user_id)
+    .bind(version)
+    .fetch_optional(executor)
+    .await?;
+    row.map(RoutingRuleVersion::try_from).transpose()
+}
+
+/// `ON DUPLICATE KEY UPDATE` takes no condition, so every column checks
+/// that the existing rule belongs to the same user.
+async fn upsert_routing_rule<'e, E>(executor: E, rule: &RoutingRule) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO routing_rules (
+            id, user_id, name, position, enabled, match_event, match_class, match_tags,
+            match_metadata, messenger, recipient, template, priority, created_at, updated_at
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE
+            name = IF(user_id = VALUES(user_id), VALUES(name), name),
+            position = IF(user_id = VALUES(user_id), VALUES(position), position),
+            enabled = IF(user_id = VALUES(user_id), VALUES(enabled), enabled),
+            match_event = IF(user_id = VALUES(user_id), VALUES(match_event), match_event),
+            match_class = IF(user_id = VALUES(user_id), VALUES(match_class), match_class),
+            match_tags = IF(user_id = VALUES(user_id), VALUES(match_tags), match_tags),
+            match_metadata =
+                IF(user_id = VALUES(user_id), VALUES(match_metadata), match_metadata),
+            messenger = IF(user_id = VALUES(user_id), VALUES(messenger), messenger),
+            recipient = IF(user_id = VALUES(user_id), VALUES(recipient), recipient),
+            template = IF(user_id = VALUES(user_id), VALUES(template), template),
+            priority = IF(user_id = VALUES(user_id), VALUES(priority), priority),
+            updated_at = IF(user_id = VALUES(user_id), VALUES(updated_at), updated_at)
+        "#,
+    )
+    .bind(rule.id)
+    .bind(rule.user_id)
+    .bind(&rule.name)
+    .bind(rule.position)
+    .bind(rule.enabled)
+    .bind(&rule.match_event)
+    .bind(rule.match_class.map(|class| class.as_str()))
+    .bind(serde_json::to_string(&rule.match_tags)?)
+    .bind(serde_json::to_string(&rule.match_metadata)?)
+    .bind(rule.messenger.as_str())
+    .bind(&rule.recipient)
+    .bind(&rule.template)
+    .bind(rule.priority.map(|priority| priority.as_str()))
+    .bind(rule.created_at)
+    .bind(rule.updated_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+/// Tags are a JSON array here; PostgreSQL keeps them in a `TEXT[]`.
+#[derive(FromRow)]
+struct RoutingRuleRow {
+    id: Uuid,
+    user_id: Uuid,
+    name: String,
+    position: i32,
+    enabled: bool,
+    match_event: Option<String>,
+    match_class: Option<String>,
+    match_tags: String,
+    match_metadata: String,
+    messenger: String,
+    recipient: String,
+    template: Option<String>,
+    priority: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<RoutingRuleRow> for RoutingRule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RoutingRuleRow) -> Result<Self, Self::Error> {
+        RoutingRule::try_from(RoutingRuleRecord {
+            id: value.id,
+            user_id: value.user_id,
+            name: value.name,
+            position: value.position,
+            enabled: value.enabled,
+            match_event: value.match_event,
+            match_class: value.match_class,
+            match_tags: serde_json::from_str(&value.match_tags)?,
+            match_metadata: value.match_metadata,
+            messenger: value.messenger,
+            recipient: value.recipient,
+            template: value.template,
+            priority: value.priority,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlApiKeyRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlApiKeyRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for MySqlApiKeyRepository {
+    async fn insert(&self, key: &ApiKey) -> anyhow::Result<()> {
+        insert_api_key(&self.pool, key).await
+    }
+
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<ApiKey>> {
+        let records = sqlx::query_as::<_, ApiKeyRecord>(
+            r#"
+            SELECT id, user_id, name, prefix, key_hash, created_at, last_used_at, scopes,
+                   allowed_ips
+            FROM api_keys
+            WHERE user_id = ?
+              AND revoked_at IS NULL
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn find_active(&self, key_hash: &str) -> anyhow::Result<Option<ApiKey>> {
+        let record = sqlx::query_as::<_, ApiKeyRecord>(
+            r#"
+            SELECT id, user_id, name, prefix, key_hash, created_at, last_used_at, scopes,
+                   allowed_ips
+            FROM api_keys
+            WHERE key_hash = ?
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn rotate(
+        &self,
+        user_id: Uuid,
+        key_id: Uuid,
+        replacement: &ApiKey,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        if !revoke_api_key(&mut *tx, user_id, key_id, replacement.created_at).await? {
+            return Ok(false);
+        }
+        insert_api_key(&mut *tx, replacement).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn revoke(&self, user_id: Uuid, key_id: Uuid) -> anyhow::Result<bool> {
+        revoke_api_key(&self.pool, user_id, key_id, Utc::now()).await
+    }
+
+    async fn touch(&self, key_id: Uuid, used_at: DateTime<Utc>) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(used_at)
+        .bind(key_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+async fn insert_api_key<'e, E>(executor: E, key: &ApiKey) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO api_keys (
+            id, user_id, name, prefix, key_hash, created_at, scopes, allowed_ips
+        )
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(key.id)
+    .bind(key.user_id)
+    .bind(&key.name)
+    .bind(&key.prefix)
+    .bind(&key.key_hash)
+    .bind(key.created_at)
+    .bind(
+        key.scopes
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+    .bind(
+        key.allowed_ips
+            .iter()
+            .map(|net| net.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn revoke_api_key<'e, E>(
+    executor: E,
+    user_id: Uuid,
+    key_id: Uuid,
+    revoked_at: DateTime<Utc>,
+) -> anyhow::Result<bool>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    let result = sqlx::query(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = ?
+        WHERE id = ?
+          AND user_id = ?
+          AND revoked_at IS NULL
+        "#,
+    )
+    .bind(revoked_at)
+    .bind(key_id)
+    .bind(user_id)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[derive(Clone)]
+pub struct MySqlRefreshTokenRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlRefreshTokenRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl RefreshTokenRepository for MySqlRefreshTokenRepository {
+    async fn find(&self, token_hash: &str) -> anyhow::Result<Option<RefreshToken>> {
+        let record = sqlx::query_as::<_, RefreshTokenRecord>(
+            r#"
+            SELECT id, family_id, user_id, token_hash, created_at, expires_at, used_at, revoked_at
+            FROM refresh_tokens
+            WHERE token_hash = ?
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn rotate(&self, token_id: Uuid, next: &RefreshToken) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET used_at = ?
+            WHERE id = ?
+              AND used_at IS NULL
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(next.created_at)
+        .bind(token_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        insert_refresh_token(&mut *tx, next).await?;
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET last_seen_at = ?, expires_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(next.created_at)
+        .bind(next.expires_at)
+        .bind(next.family_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+#[derive(Clone)]
+pub struct MySqlSessionRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlSessionRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl SessionRepository for MySqlSessionRepository {
+    async fn insert(&self, session: &Session, token: &RefreshToken) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, user_id, device, ip, created_at, last_seen_at, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(session.id)
+        .bind(session.user_id)
+        .bind(&session.device)
+        .bind(&session.ip)
+        .bind(session.created_at)
+        .bind(session.last_seen_at)
+        .bind(session.expires_at)
+        .execute(&mut *tx)
+        .await?;
+        insert_refresh_token(&mut *tx, token).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_active(&self, user_id: Uuid) -> anyhow::Result<Vec<Session>> {
+        let records = sqlx::query_as::<_, SessionRecord>(
+            r#"
+            SELECT id, user_id, device, ip, created_at, last_seen_at, expires_at
+            FROM sessions
+            WHERE user_id = ?
+              AND revoked_at IS NULL
+              AND expires_at > ?
+            ORDER BY last_seen_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .bind(Utc::now())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn revoke(&self, user_id: Uuid, session_id: Uuid) -> anyhow::Result<bool> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE sessions
+            SET revoked_at = ?
+            WHERE id = ?
+              AND user_id = ?
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(now)
+        .bind(session_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = ?
+            WHERE family_id = ?
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(now)
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn revoke_all(&self, user_id: Uuid) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET revoked_at = ?
+            WHERE user_id = ?
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(now)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = ?
+            WHERE user_id = ?
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(now)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn revoked_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<(Uuid, DateTime<Utc>)>> {
+        let revoked = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+            r#"
+            SELECT id, revoked_at
+            FROM sessions
+            WHERE revoked_at >= ?
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(revoked)
+    }
+}
+
+async fn insert_refresh_token<'e, E>(executor: E, token: &RefreshToken) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO refresh_tokens (id, family_id, user_id, token_hash, created_at, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(token.id)
+    .bind(token.family_id)
+    .bind(token.user_id)
+    .bind(&token.token_hash)
+    .bind(token.created_at)
+    .bind(token.expires_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct MySqlOrganizationRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlOrganizationRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl OrganizationRepository for MySqlOrganizationRepository {
+    async fn create(
+        &self,
+        organization: &Organization,
+        owner: &OrganizationMember,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO organizations (id, name, created_at)
+            VALUES (?, ?, ?)
+            "#,
+        )
+        .bind(organization.id)
+        .bind(&organization.name)
+        .bind(organization.created_at)
+        .execute(&mut *tx)
+        .await?;
+        insert_organization_member(&mut *tx, owner).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get(&self, organization_id: Uuid) -> anyhow::Result<Option<Organization>> {
+        let record = sqlx::query_as::<_, OrganizationRecord>(
+            r#"
+            SELECT id, name, created_at
+            FROM organizations
+            WHERE id = ?
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> anyhow::Result<Vec<(Organization, MemberRole)>> {
+        let records = sqlx::query_as::<_, MembershipRecord>(
+            r#"
+            SELECT o.id, o.name, o.created_at, m.role
+            FROM organization_members m
+            JOIN organizations o ON o.id = m.organization_id
+            WHERE m.user_id = ?
+            ORDER BY o.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        records.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn find_member(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> anyhow::Result<Option<OrganizationMember>> {
+        let record = sqlx::query_as::<_, OrganizationMemberRecord>(
+            r#"
+            SELECT organization_id, user_id, role, created_at
+            FROM organization_members
+            WHERE organization_id = ?
+              AND user_id = ?
+            "#,
+        )
+        .bind(organization_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(OrganizationMember::try_from).transpose()
+    }
+
+    async fn list_members(&self, organization_id: Uuid) -> anyhow::Result<Vec<OrganizationMember>> {
+        let records = sqlx::query_as::<_, OrganizationMemberRecord>(
+            r#"
+            SELECT organization_id, user_id, role, created_at
+            FROM organization_members
+            WHERE organization_id = ?
+            ORDER BY created_at
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+        records
+            .into_iter()
+            .map(OrganizationMember::try_from)
+            .collect()
+    }
+
+    async fn insert_invitation(&self, invitation: &OrganizationInvitation) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO organization_invitations (
+                id, organization_id, email, role, token_hash, invited_by, created_at, expires_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(invitation.id)
+        .bind(invitation.organization_id)
+        .bind(&invitation.email)
+        .bind(invitation.role.as_str())
+        .bind(&invitation.token_hash)
+        .bind(invitation.invited_by)
+        .bind(invitation.created_at)
+        .bind(invitation.expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_invitations(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<OrganizationInvitation>> {
+        let records = sqlx::query_as::<_, OrganizationInvitationRecord>(
+            r#"
+            SELECT id, organization_id, email, role, token_hash, invited_by, created_at,
+                   expires_at, accepted_at
+            FROM organization_invitations
+            WHERE organization_id = ?
+              AND accepted_at IS NULL
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+        records
+            .into_iter()
+            .map(OrganizationInvitation::try_from)
+            .collect()
+    }
+
+    async fn find_invitation(
+        &self,
+        token_hash: &str,
+    ) -> anyhow::Result<Option<OrganizationInvitation>> {
+        let record = sqlx::query_as::<_, OrganizationInvitationRecord>(
+            r#"
+            SELECT id, organization_id, email, role, token_hash, invited_by, created_at,
+                   expires_at, accepted_at
+            FROM organization_invitations
+            WHERE token_hash = ?
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(OrganizationInvitation::try_from).transpose()
+    }
+
+    async fn accept_invitation(
+        &self,
+        invitation_id: Uuid,
+        member: &OrganizationMember,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE organization_invitations
+            SET accepted_at = ?
+            WHERE id = ?
+              AND accepted_at IS NULL
+            "#,
+        )
+        .bind(member.created_at)
+        .bind(invitation_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        insert_organization_member(&mut *tx, member).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+/// Leaves an existing membership, and the role it has, as it is.
+async fn insert_organization_member<'e, E>(
+    executor: E,
+    member: &OrganizationMember,
+) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = MySql>,
+{
+    let result = sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id, role, created_at)
+        VALUES (?, ?, ?, ?)
+        "#,
+    )
+    .bind(member.organization_id)
+    .bind(member.user_id)
+    .bind(member.role.as_str())
+    .bind(member.created_at)
+    .execute(executor)
+    .await;
+    inserted(result)?;
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct MySqlWorkerRepository {
+    pool: MySqlPool,
+}
+
+impl MySqlWorkerRepository {
+    pub fn new(pool: MySqlPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl WorkerRepository for MySqlWorkerRepository {
+    async fn heartbeat(&self, worker: &Worker) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workers (
+                id, hostname, version, bus, started_at, heartbeat_at, in_flight, handled, failed
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                heartbeat_at = VALUES(heartbeat_at),
+                in_flight = VALUES(in_flight),
+                handled = VALUES(handled),
+                failed = VALUES(failed)
+            "#,
+        )
+        .bind(worker.id)
+        .bind(&worker.hostname)
+        .bind(&worker.version)
+        .bind(&worker.bus)
+        .bind(worker.started_at)
+        .bind(worker.heartbeat_at)
+        .bind(worker.stats.in_flight as i64)
+        .bind(worker.stats.handled as i64)
+        .bind(worker.stats.failed as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> anyhow::Result<Option<Worker>> {
+        let record = sqlx::query_as::<_, WorkerRecord>(
+            r#"
+            SELECT id, hostname, version, bus, started_at, heartbeat_at, in_flight, handled, failed
+            FROM workers
+            WHERE id = ?
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<Worker>> {
+        let records = sqlx::query_as::<_, WorkerRecord>(
+            r#"
+            SELECT id, hostname, version, bus, started_at, heartbeat_at, in_flight, handled, failed
+            FROM workers
+            ORDER BY heartbeat_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn prune(&self, before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM workers
+            WHERE heartbeat_at < ?
+            "#,
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
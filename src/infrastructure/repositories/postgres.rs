@@ -1,20 +1,111 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use sqlx::{FromRow, Pool, Postgres, Row};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use sqlx::{
+    Pool, Postgres, Row,
+    migrate::Migrator,
+    postgres::{PgListener, PgPoolOptions},
+};
+use tokio::{sync::mpsc::Sender, task::JoinHandle};
+use tokio_stream::StreamExt;
 use uuid::Uuid;
 
-use crate::domain::{
-    models::{
-        MessageAttempt, MessageContent, MessageHistoryEntry, MessageStatus, MessageType,
-        MessengerToken, MessengerTokenStatus, MessengerType, RequestedBy, User,
+use crate::{
+    application::services::status_feed::{MessageStatusChange, StatusFeed, StatusListener},
+    domain::{
+        models::{
+            AccountErasure, ApiKey, AttemptOutcomes, Backlog, ConsentRecord, DeliveryStatsBucket,
+            DispatchClaim, FailedMessageFilter, IdempotencyRecord, MemberRole, MessageAttempt,
+            MessageClass, MessageHistoryEntry, MessageHistoryFilter, MessageLink, MessageStatus,
+            MessageStatusKind, MessengerToken, MessengerType, NewMessageHistoryEntry, Organization,
+            OrganizationInvitation, OrganizationMember, OtpCode, PasswordCredential, RefreshToken,
+            RequestedBy, RoutingRule, RoutingRuleVersion, Session, TokenHealth, TokenSelector,
+            User, UserIdentity, Worker,
+        },
+        repositories::{
+            AccountErasureRepository, ApiKeyRepository, ConsentRepository, DispatchGuardRepository,
+            HistoryPartitionRepository, IdempotencyKeyRepository, IdentityRepository,
+            LoginTokenRepository, MessageHistoryRepository, MessageLinkRepository,
+            MessengerTokenRepository, OrganizationRepository, OtpRepository, PasswordRepository,
+            RefreshTokenRepository, RoutingRuleRepository, SessionRepository, UserRepository,
+            WorkerRepository,
+        },
+    },
+    infrastructure::repositories::{
+        Repositories,
+        records::{
+            AccountErasureRecord, ApiKeyRecord, BacklogRecord, ConsentRecordRow,
+            DeliveryStatsRecord, IdempotencyKeyRecord, IdentityRecord, MembershipRecord,
+            MessageHistoryRecord, MessageLinkRecord, MessengerTokenRecord,
+            OrganizationInvitationRecord, OrganizationMemberRecord, OrganizationRecord,
+            OtpCodeRecord, PasswordRecord, RefreshTokenRecord, RoutingRuleRecord,
+            RoutingRuleVersionRecord, SessionRecord, StatusNotificationRecord, TokenHealthRecord,
+            UserRecord, WorkerRecord, backlog_from_records, consent_status_to_str, escape_like,
+            message_status_from_str, message_status_to_fields, message_type_to_str,
+            requested_by_from_str, requested_by_to_str, token_selector_fields, token_status_to_str,
+        },
+        retrying::{PoolMonitor, RetryPolicy},
     },
-    repositories::{MessageHistoryRepository, MessengerTokenRepository, UserRepository},
 };
 
 pub type PgPool = Pool<Postgres>;
 
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Carries a notification for every status change of a message, delivered
+/// when the change commits.
+const STATUS_CHANNEL: &str = "message_status";
+
+pub async fn connect(
+    url: &str,
+    read_url: Option<&str>,
+    max_connections: u32,
+    retry: &RetryPolicy,
+) -> anyhow::Result<Repositories> {
+    let pool = PgPoolOptions::new()
+        .max_connections(max_connections)
+        .connect(url)
+        .await?;
+    MIGRATOR.run(&pool).await?;
+    let replica = match read_url {
+        Some(read_url) => Some(
+            PgPoolOptions::new()
+                .max_connections(max_connections)
+                .connect(read_url)
+                .await?,
+        ),
+        None => None,
+    };
+    let read_pool = replica.clone().unwrap_or_else(|| pool.clone());
+    let mut pools = vec![("primary", pool.clone())];
+    pools.extend(replica.map(|replica| ("replica", replica)));
+
+    Ok(Repositories {
+        users: PostgresUserRepository::new(pool.clone()),
+        tokens: PostgresMessengerTokenRepository::new(pool.clone()),
+        history: PostgresMessageHistoryRepository::new(pool.clone(), read_pool),
+        otp: PostgresOtpRepository::new(pool.clone()),
+        consents: PostgresConsentRepository::new(pool.clone()),
+        links: PostgresMessageLinkRepository::new(pool.clone()),
+        idempotency: PostgresIdempotencyKeyRepository::new(pool.clone()),
+        guards: PostgresDispatchGuardRepository::new(pool.clone()),
+        passwords: PostgresPasswordRepository::new(pool.clone()),
+        login_tokens: PostgresLoginTokenRepository::new(pool.clone()),
+        erasures: PostgresAccountErasureRepository::new(pool.clone()),
+        identities: PostgresIdentityRepository::new(pool.clone()),
+        api_keys: PostgresApiKeyRepository::new(pool.clone()),
+        refresh_tokens: PostgresRefreshTokenRepository::new(pool.clone()),
+        sessions: PostgresSessionRepository::new(pool.clone()),
+        organizations: PostgresOrganizationRepository::new(pool.clone()),
+        routing: PostgresRoutingRuleRepository::new(pool.clone()),
+        workers: PostgresWorkerRepository::new(pool.clone()),
+        history_partitions: PostgresHistoryPartitionRepository::new(pool.clone()),
+        database: PoolMonitor::new(pools, retry.clone(), &MIGRATOR),
+        status_listener: Some(PostgresStatusListener::new(pool.clone())),
+    })
+}
+
 #[derive(Clone)]
 pub struct PostgresUserRepository {
     pool: PgPool,
@@ -30,42 +121,124 @@ impl PostgresUserRepository {
 impl UserRepository for PostgresUserRepository {
     async fn find_by_email(&self, email: &str) -> anyhow::Result<Option<User>> {
         let record = sqlx::query_as::<_, UserRecord>(
-            r#"SELECT id, email, display_name, created_at, updated_at FROM users WHERE email = $1"#,
+            r#"
+            SELECT id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                   updated_at
+            FROM users
+            WHERE email = $1
+            "#,
         )
         .bind(email)
         .fetch_optional(&self.pool)
         .await?;
-        Ok(record.map(User::from))
+        record.map(User::try_from).transpose()
     }
 
     async fn get(&self, id: &Uuid) -> anyhow::Result<Option<User>> {
         let record = sqlx::query_as::<_, UserRecord>(
-            r#"SELECT id, email, display_name, created_at, updated_at FROM users WHERE id = $1"#,
+            r#"
+            SELECT id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                   updated_at
+            FROM users
+            WHERE id = $1
+            "#,
         )
         .bind(id)
         .fetch_optional(&self.pool)
         .await?;
-        Ok(record.map(User::from))
+        record.map(User::try_from).transpose()
+    }
+
+    async fn list(
+        &self,
+        search: Option<&str>,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<User>, bool)> {
+        let limit = limit.unwrap_or(50).min(200) as i32;
+        let offset = offset.unwrap_or(0) as i32;
+        // Wildcards typed by the caller match literally.
+        let search = search.map(escape_like);
+
+        let rows = sqlx::query_as::<_, UserRecord>(
+            r#"
+            SELECT id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                   updated_at
+            FROM users
+            WHERE $3::TEXT IS NULL
+               OR email ILIKE '%' || $3 || '%'
+               OR display_name ILIKE '%' || $3 || '%'
+            ORDER BY created_at, id
+            LIMIT $1 OFFSET $2
+            "#,
+        )
+        .bind(limit + 1)
+        .bind(offset)
+        .bind(search)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let has_more = rows.len() > limit as usize;
+        let users = rows
+            .into_iter()
+            .take(limit as usize)
+            .map(User::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((users, has_more))
     }
 
     async fn upsert(&self, user: &User) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
         sqlx::query(
             r#"
-            INSERT INTO users (id, email, display_name, created_at, updated_at)
-            VALUES ($1, $2, $3, $4, $5)
+            INSERT INTO users (
+                id, email, display_name, role, disabled_at, email_verified_at, created_at,
+                updated_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             ON CONFLICT (id) DO UPDATE
             SET email = EXCLUDED.email,
                 display_name = EXCLUDED.display_name,
+                role = EXCLUDED.role,
+                disabled_at = EXCLUDED.disabled_at,
+                email_verified_at = COALESCE(users.email_verified_at, EXCLUDED.email_verified_at),
                 updated_at = EXCLUDED.updated_at
             "#,
         )
         .bind(user.id)
         .bind(&user.email)
         .bind(&user.display_name)
+        .bind(user.role.as_str())
+        .bind(user.disabled_at)
+        .bind(user.email_verified_at)
         .bind(user.created_at)
         .bind(user.updated_at)
-        .execute(&self.pool)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            INSERT INTO organizations (id, name, created_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (id) DO NOTHING
+            "#,
+        )
+        .bind(user.id)
+        .bind(user.display_name.as_deref().unwrap_or(&user.email))
+        .bind(user.created_at)
+        .execute(&mut *tx)
         .await?;
+        insert_organization_member(
+            &mut *tx,
+            &OrganizationMember {
+                organization_id: user.id,
+                user_id: user.id,
+                role: MemberRole::Owner,
+                created_at: user.created_at,
+            },
+        )
+        .await?;
+        tx.commit().await?;
         Ok(())
     }
 }
@@ -90,35 +263,54 @@ impl MessengerTokenRepository for PostgresMessengerTokenRepository {
             r#"
             INSERT INTO messenger_tokens (
                 id,
+                organization_id,
                 user_id,
+                shared,
                 messenger,
+                label,
                 access_token,
                 refresh_token,
+                expires_at,
+                device_id,
                 status,
                 created_at,
                 updated_at
-            ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
+            ) VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13)
             ON CONFLICT (id) DO UPDATE
-            SET access_token = EXCLUDED.access_token,
+            SET shared = EXCLUDED.shared,
+                label = EXCLUDED.label,
+                access_token = EXCLUDED.access_token,
                 refresh_token = EXCLUDED.refresh_token,
+                expires_at = EXCLUDED.expires_at,
+                device_id = EXCLUDED.device_id,
                 status = EXCLUDED.status,
                 updated_at = EXCLUDED.updated_at
             RETURNING
                 id,
+                organization_id,
                 user_id,
+                shared,
                 messenger,
+                label,
                 access_token,
                 refresh_token,
+                expires_at,
+                device_id,
                 status,
                 created_at,
                 updated_at
             "#,
         )
         .bind(token.id)
+        .bind(token.organization_id)
         .bind(token.user_id)
+        .bind(token.shared)
         .bind(token.messenger.as_str())
+        .bind(&token.label)
         .bind(&token.access_token)
         .bind(&token.refresh_token)
+        .bind(token.expires_at)
+        .bind(&token.device_id)
         .bind(status)
         .bind(token.created_at)
         .bind(token.updated_at)
@@ -130,92 +322,136 @@ impl MessengerTokenRepository for PostgresMessengerTokenRepository {
 
     async fn find_active(
         &self,
-        user_id: &Uuid,
+        organization_id: &Uuid,
+        sender: &Uuid,
         messenger: MessengerType,
+        selector: &TokenSelector,
     ) -> anyhow::Result<Option<MessengerToken>> {
+        let (token_id, label) = token_selector_fields(selector);
         let record = sqlx::query_as::<_, MessengerTokenRecord>(
             r#"
-            SELECT id, user_id, messenger, access_token, refresh_token, status, created_at, updated_at
+            SELECT id, organization_id, user_id, shared, messenger, label, access_token,
+                   refresh_token, expires_at, device_id, status, created_at, updated_at
             FROM messenger_tokens
-            WHERE user_id = $1
-              AND messenger = $2
+            WHERE organization_id = $1
+              AND (shared OR user_id = $2)
+              AND messenger = $3
               AND status = 'active'
+              AND ($4::UUID IS NULL OR id = $4)
+              AND ($5::TEXT IS NULL OR label = $5)
             ORDER BY updated_at DESC
             LIMIT 1
             "#,
         )
-        .bind(user_id)
+        .bind(organization_id)
+        .bind(sender)
         .bind(messenger.as_str())
+        .bind(token_id)
+        .bind(label)
         .fetch_optional(&self.pool)
         .await?;
         record.map(|record| record.try_into()).transpose()
     }
 
-    async fn list_by_user(&self, user_id: &Uuid) -> anyhow::Result<Vec<MessengerToken>> {
+    async fn list_by_organization(
+        &self,
+        organization_id: &Uuid,
+    ) -> anyhow::Result<Vec<MessengerToken>> {
         let rows = sqlx::query_as::<_, MessengerTokenRecord>(
             r#"
-            SELECT id, user_id, messenger, access_token, refresh_token, status, created_at, updated_at
+            SELECT id, organization_id, user_id, shared, messenger, label, access_token,
+                   refresh_token, expires_at, device_id, status, created_at, updated_at
             FROM messenger_tokens
-            WHERE user_id = $1
+            WHERE organization_id = $1
             ORDER BY updated_at DESC
             "#,
         )
-        .bind(user_id)
+        .bind(organization_id)
         .fetch_all(&self.pool)
         .await?;
         rows.into_iter().map(|record| record.try_into()).collect()
     }
+
+    async fn health(
+        &self,
+        now: DateTime<Utc>,
+        expiring_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TokenHealth>> {
+        let rows = sqlx::query_as::<_, TokenHealthRecord>(
+            r#"
+            SELECT messenger,
+                   COUNT(*) FILTER (WHERE status = 'active') AS active,
+                   COUNT(*) FILTER (WHERE status <> 'active') AS inactive,
+                   COUNT(*) FILTER (WHERE status = 'active' AND expires_at <= $1) AS expired,
+                   COUNT(*) FILTER (
+                       WHERE status = 'active' AND expires_at > $1 AND expires_at <= $2
+                   ) AS expiring
+            FROM messenger_tokens
+            GROUP BY messenger
+            ORDER BY messenger
+            "#,
+        )
+        .bind(now)
+        .bind(expiring_before)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(TokenHealth::try_from).collect()
+    }
 }
 
 #[derive(Clone)]
 pub struct PostgresMessageHistoryRepository {
     pool: PgPool,
+    /// Serves listings and statistics, which tolerate replication lag.
+    /// Lookups by id stay on `pool` since they follow writes.
+    read_pool: PgPool,
 }
 
 impl PostgresMessageHistoryRepository {
-    pub fn new(pool: PgPool) -> Arc<Self> {
-        Arc::new(Self { pool })
+    pub fn new(pool: PgPool, read_pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool, read_pool })
     }
 }
 
 #[async_trait]
 impl MessageHistoryRepository for PostgresMessageHistoryRepository {
-    async fn insert(
-        &self,
-        user_id: Uuid,
-        messenger: MessengerType,
-        recipient: String,
-        content: MessageContent,
-        requested_by: RequestedBy,
-    ) -> anyhow::Result<MessageHistoryEntry> {
+    async fn insert(&self, entry: NewMessageHistoryEntry) -> anyhow::Result<MessageHistoryEntry> {
         let id = Uuid::new_v4();
         let status = MessageStatus::Pending;
         let now = Utc::now();
         let (status_str, reason) = message_status_to_fields(&status);
-        let requested_by = requested_by_to_str(&requested_by);
+        let requested_by = requested_by_to_str(&entry.requested_by);
 
-        let row = sqlx::query(
+        let row = sqlx::query_as::<_, MessageHistoryRecord>(
             r#"
             INSERT INTO message_history (
-                id, user_id, messenger, recipient, body, message_type, status, status_reason,
-                attempts, requested_by, created_at, updated_at
+                id, organization_id, user_id, messenger, recipient, body, message_type, status,
+                status_reason, attempts, requested_by, created_at, updated_at, deadline,
+                message_class, scheduled_for, priority, token_id, tags
             )
-            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12)
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10,$11,$12,$13,$14,$15,$16,$17,$18,$19)
             RETURNING *
             "#,
         )
         .bind(id)
-        .bind(user_id)
-        .bind(messenger.as_str())
-        .bind(&recipient)
-        .bind(&content.body)
-        .bind(message_type_to_str(&content.message_type))
+        .bind(entry.organization_id)
+        .bind(entry.user_id)
+        .bind(entry.messenger.as_str())
+        .bind(&entry.recipient)
+        .bind(&entry.content.body)
+        .bind(message_type_to_str(&entry.content.message_type))
         .bind(status_str)
         .bind(reason)
         .bind(0_i32)
         .bind(requested_by)
         .bind(now)
         .bind(now)
+        .bind(entry.deadline)
+        .bind(entry.class.as_str())
+        .bind(entry.scheduled_for)
+        .bind(entry.priority.as_str())
+        .bind(entry.token_id)
+        .bind(&entry.tags)
         .fetch_one(&self.pool)
         .await?;
 
@@ -231,12 +467,20 @@ impl MessageHistoryRepository for PostgresMessageHistoryRepository {
         let (status_str, reason) = message_status_to_fields(&status);
         sqlx::query(
             r#"
-            UPDATE message_history
-            SET status = $2,
-                status_reason = $3,
-                attempts = $4,
-                updated_at = $5
-            WHERE id = $1
+            WITH updated AS (
+                UPDATE message_history
+                SET status = $2,
+                    status_reason = $3,
+                    attempts = $4,
+                    updated_at = $5
+                WHERE id = $1
+                RETURNING id, organization_id, status, attempts
+            )
+            SELECT pg_notify($6, json_build_object(
+                'id', id, 'organization_id', organization_id, 'status', status,
+                'attempts', attempts
+            )::TEXT)
+            FROM updated
             "#,
         )
         .bind(message_id)
@@ -244,13 +488,14 @@ impl MessageHistoryRepository for PostgresMessageHistoryRepository {
         .bind(reason)
         .bind(attempts as i32)
         .bind(Utc::now())
+        .bind(STATUS_CHANNEL)
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
     async fn get(&self, message_id: Uuid) -> anyhow::Result<Option<MessageHistoryEntry>> {
-        let row = sqlx::query(
+        let row = sqlx::query_as::<_, MessageHistoryRecord>(
             r#"
             SELECT *
             FROM message_history
@@ -264,29 +509,164 @@ impl MessageHistoryRepository for PostgresMessageHistoryRepository {
         row.map(MessageHistoryEntry::try_from).transpose()
     }
 
-    async fn list_by_user(
+    async fn scheduled_times_since(
         &self,
-        user_id: Uuid,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        class: MessageClass,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DateTime<Utc>>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT scheduled_for
+            FROM message_history
+            WHERE organization_id = $1
+              AND messenger = $2
+              AND recipient = $3
+              AND message_class = $4
+              AND scheduled_for > $5
+              AND status NOT IN ('suppressed', 'cancelled')
+            ORDER BY scheduled_for ASC
+            "#,
+        )
+        .bind(organization_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .bind(class.as_str())
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| row.try_get("scheduled_for").map_err(Into::into))
+            .collect()
+    }
+
+    async fn list_by_organization(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
         limit: Option<u32>,
         offset: Option<u32>,
     ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)> {
         let limit = limit.unwrap_or(50).min(200) as i32;
         let offset = offset.unwrap_or(0) as i32;
+        // Wildcards typed by the caller match literally.
+        let recipient = filter.recipient.as_deref().map(escape_like);
 
         // Get one extra to check if there are more
-        let rows = sqlx::query(
+        let rows = sqlx::query_as::<_, MessageHistoryRecord>(
             r#"
             SELECT *
             FROM message_history
-            WHERE user_id = $1
+            WHERE organization_id = $1
+              AND deleted_at IS NULL
+              AND ($4::TEXT IS NULL OR status = $4)
+              AND ($5::TEXT IS NULL OR messenger = $5)
+              AND ($6::TEXT IS NULL OR recipient ILIKE '%' || $6 || '%')
+              AND ($7::TIMESTAMPTZ IS NULL OR created_at >= $7)
+              AND ($8::TIMESTAMPTZ IS NULL OR created_at < $8)
+              AND ($9::TEXT IS NULL OR tags @> ARRAY[$9])
             ORDER BY created_at DESC
             LIMIT $2 OFFSET $3
             "#,
         )
-        .bind(user_id)
+        .bind(organization_id)
         .bind(limit + 1)
         .bind(offset)
-        .fetch_all(&self.pool)
+        .bind(filter.status.map(|status| status.as_str()))
+        .bind(filter.messenger.map(|messenger| messenger.as_str()))
+        .bind(recipient)
+        .bind(filter.created_after)
+        .bind(filter.created_before)
+        .bind(filter.tag.as_deref())
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        let has_more = rows.len() > limit as usize;
+        let entries: Vec<MessageHistoryEntry> = rows
+            .into_iter()
+            .take(limit as usize)
+            .map(MessageHistoryEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok((entries, has_more))
+    }
+
+    async fn export(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
+        sink: Sender<anyhow::Result<MessageHistoryEntry>>,
+    ) -> anyhow::Result<()> {
+        let recipient = filter.recipient.as_deref().map(escape_like);
+
+        // Rows are read off the connection as they arrive, so the result set
+        // never sits in memory as a whole.
+        let mut rows = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE organization_id = $1
+              AND deleted_at IS NULL
+              AND ($2::TEXT IS NULL OR status = $2)
+              AND ($3::TEXT IS NULL OR messenger = $3)
+              AND ($4::TEXT IS NULL OR recipient ILIKE '%' || $4 || '%')
+              AND ($5::TIMESTAMPTZ IS NULL OR created_at >= $5)
+              AND ($6::TIMESTAMPTZ IS NULL OR created_at < $6)
+              AND ($7::TEXT IS NULL OR tags @> ARRAY[$7])
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(organization_id)
+        .bind(filter.status.map(|status| status.as_str()))
+        .bind(filter.messenger.map(|messenger| messenger.as_str()))
+        .bind(recipient)
+        .bind(filter.created_after)
+        .bind(filter.created_before)
+        .bind(filter.tag.as_deref())
+        .fetch(&self.read_pool);
+
+        while let Some(record) = rows.next().await {
+            let entry = MessageHistoryEntry::try_from(record?)?;
+            if sink.send(Ok(entry)).await.is_err() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn list_by_recipient(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)> {
+        let limit = limit.unwrap_or(50).min(200) as i32;
+        let offset = offset.unwrap_or(0) as i32;
+
+        let rows = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE organization_id = $1
+              AND messenger = $2
+              AND recipient = $3
+              AND deleted_at IS NULL
+            ORDER BY created_at DESC
+            LIMIT $4 OFFSET $5
+            "#,
+        )
+        .bind(organization_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .bind(limit + 1)
+        .bind(offset)
+        .fetch_all(&self.read_pool)
         .await?;
 
         let has_more = rows.len() > limit as usize;
@@ -299,6 +679,171 @@ impl MessageHistoryRepository for PostgresMessageHistoryRepository {
         Ok((entries, has_more))
     }
 
+    async fn count_by_status(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<(MessageStatusKind, u64)>> {
+        let rows = sqlx::query_as::<_, (String, i64)>(
+            r#"
+            SELECT status, COUNT(*)
+            FROM message_history
+            WHERE organization_id = $1
+              AND deleted_at IS NULL
+            GROUP BY status
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(status, count)| {
+                let kind = MessageStatusKind::from_str(&status)
+                    .ok_or_else(|| anyhow::anyhow!("unknown message status {status}"))?;
+                Ok((kind, count as u64))
+            })
+            .collect()
+    }
+
+    async fn backlog(&self, now: DateTime<Utc>) -> anyhow::Result<Backlog> {
+        let rows = sqlx::query_as::<_, BacklogRecord>(
+            r#"
+            SELECT status,
+                   COUNT(*) AS count,
+                   MIN(scheduled_for) FILTER (WHERE scheduled_for <= $1) AS oldest_due_at
+            FROM message_history
+            WHERE status IN ('pending', 'scheduled', 'in_flight', 'retrying', 'deferred')
+              AND deleted_at IS NULL
+            GROUP BY status
+            "#,
+        )
+        .bind(now)
+        .fetch_all(&self.read_pool)
+        .await?;
+        backlog_from_records(rows)
+    }
+
+    async fn attempt_outcomes(&self, since: DateTime<Utc>) -> anyhow::Result<AttemptOutcomes> {
+        let (sent, failed) = sqlx::query_as::<_, (i64, i64)>(
+            r#"
+            SELECT COUNT(*) FILTER (WHERE status = 'sent'),
+                   COUNT(*) FILTER (WHERE status IN ('failed', 'retrying'))
+            FROM message_attempts
+            WHERE created_at >= $1
+            "#,
+        )
+        .bind(since)
+        .fetch_one(&self.read_pool)
+        .await?;
+        Ok(AttemptOutcomes {
+            sent: sent as u64,
+            failed: failed as u64,
+        })
+    }
+
+    async fn delivery_stats(
+        &self,
+        organization_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>> {
+        let rows = sqlx::query_as::<_, DeliveryStatsRecord>(
+            r#"
+            SELECT (created_at AT TIME ZONE 'UTC')::DATE AS day,
+                   messenger,
+                   COUNT(*) AS total,
+                   COUNT(*) FILTER (WHERE status = 'sent') AS sent,
+                   COUNT(*) FILTER (WHERE status = 'failed') AS failed
+            FROM message_history
+            WHERE organization_id = $1
+              AND created_at >= $2
+              AND created_at < $3
+            GROUP BY day, messenger
+            ORDER BY day, messenger
+            "#,
+        )
+        .bind(organization_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        rows.into_iter()
+            .map(DeliveryStatsBucket::try_from)
+            .collect()
+    }
+
+    async fn sender_delivery_stats(
+        &self,
+        user_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>> {
+        let rows = sqlx::query_as::<_, DeliveryStatsRecord>(
+            r#"
+            SELECT (created_at AT TIME ZONE 'UTC')::DATE AS day,
+                   messenger,
+                   COUNT(*) AS total,
+                   COUNT(*) FILTER (WHERE status = 'sent') AS sent,
+                   COUNT(*) FILTER (WHERE status = 'failed') AS failed
+            FROM message_history
+            WHERE user_id = $1
+              AND created_at >= $2
+              AND created_at < $3
+            GROUP BY day, messenger
+            ORDER BY day, messenger
+            "#,
+        )
+        .bind(user_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.read_pool)
+        .await?;
+
+        rows.into_iter()
+            .map(DeliveryStatsBucket::try_from)
+            .collect()
+    }
+
+    async fn list_failed(
+        &self,
+        filter: &FailedMessageFilter,
+        limit: u32,
+    ) -> anyhow::Result<Vec<MessageHistoryEntry>> {
+        // Wildcards typed by the caller match literally.
+        let reason = filter.reason.as_ref().map(|reason| {
+            reason
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_")
+        });
+
+        let rows = sqlx::query_as::<_, MessageHistoryRecord>(
+            r#"
+            SELECT *
+            FROM message_history
+            WHERE status = 'failed'
+              AND ($2::TEXT IS NULL OR messenger = $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR updated_at >= $3)
+              AND ($4::TIMESTAMPTZ IS NULL OR updated_at < $4)
+              AND ($5::TEXT IS NULL OR status_reason ILIKE '%' || $5 || '%')
+            ORDER BY updated_at ASC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit as i32)
+        .bind(filter.messenger.map(|messenger| messenger.as_str()))
+        .bind(filter.failed_after)
+        .bind(filter.failed_before)
+        .bind(reason)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(MessageHistoryEntry::try_from)
+            .collect()
+    }
+
     async fn log_attempt(
         &self,
         message_id: Uuid,
@@ -325,6 +870,64 @@ impl MessageHistoryRepository for PostgresMessageHistoryRepository {
         Ok(())
     }
 
+    async fn record_attempt(
+        &self,
+        message_id: Uuid,
+        status: MessageStatus,
+        attempt_number: u32,
+        requested_by: RequestedBy,
+    ) -> anyhow::Result<()> {
+        let (status_str, reason) = message_status_to_fields(&status);
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            WITH updated AS (
+                UPDATE message_history
+                SET status = $2,
+                    status_reason = $3,
+                    attempts = $4,
+                    updated_at = $5
+                WHERE id = $1
+                RETURNING id, organization_id, status, attempts
+            )
+            SELECT pg_notify($6, json_build_object(
+                'id', id, 'organization_id', organization_id, 'status', status,
+                'attempts', attempts
+            )::TEXT)
+            FROM updated
+            "#,
+        )
+        .bind(message_id)
+        .bind(status_str)
+        .bind(&reason)
+        .bind(attempt_number as i32)
+        .bind(now)
+        .bind(STATUS_CHANNEL)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO message_attempts (
+                id, message_id, attempt_number, status, status_reason, requested_by, created_at
+            )
+            VALUES (gen_random_uuid(), $1, $2, $3, $4, $5, $6)
+            "#,
+        )
+        .bind(message_id)
+        .bind(attempt_number as i32)
+        .bind(status_str)
+        .bind(reason)
+        .bind(requested_by_to_str(&requested_by))
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
     async fn get_attempts(&self, message_id: Uuid) -> anyhow::Result<Vec<MessageAttempt>> {
         let rows = sqlx::query(
             r#"
@@ -335,7 +938,7 @@ impl MessageHistoryRepository for PostgresMessageHistoryRepository {
             "#,
         )
         .bind(message_id)
-        .fetch_all(&self.pool)
+        .fetch_all(&self.read_pool)
         .await?;
 
         rows.into_iter()
@@ -357,174 +960,2071 @@ impl MessageHistoryRepository for PostgresMessageHistoryRepository {
             })
             .collect()
     }
-}
-
-#[derive(FromRow)]
-struct UserRecord {
-    id: Uuid,
-    email: String,
-    display_name: Option<String>,
-    created_at: DateTime<Utc>,
-    updated_at: DateTime<Utc>,
-}
 
-impl From<UserRecord> for User {
-    fn from(value: UserRecord) -> Self {
-        Self {
-            id: value.id,
-            email: value.email,
-            display_name: value.display_name,
-            created_at: value.created_at,
-            updated_at: value.updated_at,
-        }
+    async fn redact(&self, message_id: Uuid) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE message_history
+            SET body = '[redacted]',
+                updated_at = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(message_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn soft_delete(&self, message_id: Uuid) -> anyhow::Result<bool> {
+        let now = Utc::now();
+        let deleted = sqlx::query(
+            r#"
+            UPDATE message_history
+            SET deleted_at = $2,
+                updated_at = $2
+            WHERE id = $1
+              AND deleted_at IS NULL
+            "#,
+        )
+        .bind(message_id)
+        .bind(now)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(deleted > 0)
+    }
+
+    async fn purge(&self, message_id: Uuid) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        // Nothing cascades from the partitioned history, see the
+        // partition_message_history migration.
+        for statement in [
+            "DELETE FROM message_attempts WHERE message_id = $1",
+            "DELETE FROM message_links WHERE source_id = $1 OR target_id = $1",
+            "DELETE FROM otp_codes WHERE message_id = $1",
+            "DELETE FROM idempotency_keys WHERE message_id = $1",
+            "DELETE FROM dispatch_guards WHERE message_id = $1",
+        ] {
+            sqlx::query(statement)
+                .bind(message_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let purged = sqlx::query("DELETE FROM message_history WHERE id = $1")
+            .bind(message_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(purged > 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresOtpRepository {
+    pool: PgPool,
+}
+
+impl PostgresOtpRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl OtpRepository for PostgresOtpRepository {
+    async fn replace_active(&self, code: &OtpCode) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            DELETE FROM otp_codes
+            WHERE user_id = $1
+              AND messenger = $2
+              AND recipient = $3
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(code.user_id)
+        .bind(code.messenger.as_str())
+        .bind(&code.recipient)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO otp_codes (
+                id, user_id, messenger, recipient, code_hash, message_id, failed_attempts,
+                expires_at, verified_at, created_at
+            )
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
+            "#,
+        )
+        .bind(code.id)
+        .bind(code.user_id)
+        .bind(code.messenger.as_str())
+        .bind(&code.recipient)
+        .bind(&code.code_hash)
+        .bind(code.message_id)
+        .bind(code.failed_attempts as i32)
+        .bind(code.expires_at)
+        .bind(code.verified_at)
+        .bind(code.created_at)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn find_active(
+        &self,
+        user_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<Option<OtpCode>> {
+        let record = sqlx::query_as::<_, OtpCodeRecord>(
+            r#"
+            SELECT id, user_id, messenger, recipient, code_hash, message_id, failed_attempts,
+                   expires_at, verified_at, created_at
+            FROM otp_codes
+            WHERE user_id = $1
+              AND messenger = $2
+              AND recipient = $3
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(OtpCode::try_from).transpose()
+    }
+
+    async fn reserve_attempt(&self, id: Uuid, max_attempts: u32) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE otp_codes
+            SET failed_attempts = failed_attempts + 1
+            WHERE id = $1
+              AND failed_attempts < $2
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(max_attempts as i32)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn mark_verified(&self, id: Uuid) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE otp_codes
+            SET verified_at = $2
+            WHERE id = $1
+              AND verified_at IS NULL
+            "#,
+        )
+        .bind(id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresConsentRepository {
+    pool: PgPool,
+}
+
+impl PostgresConsentRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl ConsentRepository for PostgresConsentRepository {
+    async fn upsert(&self, record: &ConsentRecord) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO consents (
+                id, organization_id, messenger, recipient, status, source, proof, confirmation_hash,
+                created_at, updated_at
+            )
+            VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9,$10)
+            ON CONFLICT (organization_id, messenger, recipient) DO UPDATE
+            SET status = EXCLUDED.status,
+                source = EXCLUDED.source,
+                proof = EXCLUDED.proof,
+                confirmation_hash = EXCLUDED.confirmation_hash,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(record.id)
+        .bind(record.organization_id)
+        .bind(record.messenger.as_str())
+        .bind(&record.recipient)
+        .bind(consent_status_to_str(record.status))
+        .bind(&record.source)
+        .bind(&record.proof)
+        .bind(&record.confirmation_hash)
+        .bind(record.created_at)
+        .bind(record.updated_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn find(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+    ) -> anyhow::Result<Option<ConsentRecord>> {
+        let record = sqlx::query_as::<_, ConsentRecordRow>(
+            r#"
+            SELECT id, organization_id, messenger, recipient, status, source, proof, confirmation_hash,
+                   created_at, updated_at
+            FROM consents
+            WHERE organization_id = $1
+              AND messenger = $2
+              AND recipient = $3
+            "#,
+        )
+        .bind(organization_id)
+        .bind(messenger.as_str())
+        .bind(recipient)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(ConsentRecord::try_from).transpose()
+    }
+
+    async fn list_by_organization(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<ConsentRecord>> {
+        let rows = sqlx::query_as::<_, ConsentRecordRow>(
+            r#"
+            SELECT id, organization_id, messenger, recipient, status, source, proof, confirmation_hash,
+                   created_at, updated_at
+            FROM consents
+            WHERE organization_id = $1
+            ORDER BY updated_at DESC
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(ConsentRecord::try_from).collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresMessageLinkRepository {
+    pool: PgPool,
+}
+
+impl PostgresMessageLinkRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl MessageLinkRepository for PostgresMessageLinkRepository {
+    async fn insert(&self, link: &MessageLink) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO message_links (source_id, target_id, kind, created_at)
+            VALUES ($1,$2,$3,$4)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(link.source_id)
+        .bind(link.target_id)
+        .bind(link.kind.as_str())
+        .bind(link.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn connected(&self, message_id: Uuid, limit: u32) -> anyhow::Result<Vec<MessageLink>> {
+        let rows = sqlx::query_as::<_, MessageLinkRecord>(
+            r#"
+            WITH RECURSIVE reachable (id) AS (
+                SELECT $1::uuid
+                UNION
+                SELECT CASE WHEN l.source_id = r.id THEN l.target_id ELSE l.source_id END
+                FROM message_links l
+                JOIN reachable r ON l.source_id = r.id OR l.target_id = r.id
+            )
+            SELECT source_id, target_id, kind, created_at
+            FROM message_links
+            WHERE source_id IN (SELECT id FROM reachable)
+            ORDER BY created_at ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(message_id)
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(MessageLink::try_from).collect()
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresIdempotencyKeyRepository {
+    pool: PgPool,
+}
+
+impl PostgresIdempotencyKeyRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl IdempotencyKeyRepository for PostgresIdempotencyKeyRepository {
+    async fn claim(
+        &self,
+        organization_id: Uuid,
+        key: &str,
+        request_hash: &str,
+    ) -> anyhow::Result<Option<IdempotencyRecord>> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO idempotency_keys (organization_id, key, request_hash, created_at)
+            VALUES ($1,$2,$3,$4)
+            ON CONFLICT (organization_id, key) DO NOTHING
+            "#,
+        )
+        .bind(organization_id)
+        .bind(key)
+        .bind(request_hash)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 1 {
+            return Ok(None);
+        }
+
+        let record = sqlx::query_as::<_, IdempotencyKeyRecord>(
+            r#"
+            SELECT organization_id, key, request_hash, message_id, created_at
+            FROM idempotency_keys
+            WHERE organization_id = $1
+              AND key = $2
+            "#,
+        )
+        .bind(organization_id)
+        .bind(key)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(Some(record.into()))
+    }
+
+    async fn complete(
+        &self,
+        organization_id: Uuid,
+        key: &str,
+        message_id: Uuid,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE idempotency_keys
+            SET message_id = $3
+            WHERE organization_id = $1
+              AND key = $2
+            "#,
+        )
+        .bind(organization_id)
+        .bind(key)
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, organization_id: Uuid, key: &str) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE organization_id = $1
+              AND key = $2
+              AND message_id IS NULL
+            "#,
+        )
+        .bind(organization_id)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresDispatchGuardRepository {
+    pool: PgPool,
+}
+
+impl PostgresDispatchGuardRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl DispatchGuardRepository for PostgresDispatchGuardRepository {
+    async fn claim(
+        &self,
+        message_id: Uuid,
+        attempt: u32,
+        worker_id: Uuid,
+    ) -> anyhow::Result<DispatchClaim> {
+        let guards = sqlx::query(
+            r#"
+            SELECT sent_at, worker_id
+            FROM dispatch_guards
+            WHERE message_id = $1
+            "#,
+        )
+        .bind(message_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut interrupted = None;
+        for guard in guards {
+            let sent_at: Option<DateTime<Utc>> = guard.try_get("sent_at")?;
+            if sent_at.is_some() {
+                return Ok(DispatchClaim::AlreadySent);
+            }
+            interrupted = Some(guard.try_get("worker_id")?);
+        }
+        if let Some(worker_id) = interrupted {
+            return Ok(DispatchClaim::Interrupted { worker_id });
+        }
+
+        let result = sqlx::query(
+            r#"
+            INSERT INTO dispatch_guards (message_id, attempt, worker_id, created_at)
+            VALUES ($1,$2,$3,$4)
+            ON CONFLICT (message_id, attempt) DO NOTHING
+            "#,
+        )
+        .bind(message_id)
+        .bind(attempt as i32)
+        .bind(worker_id)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 1 {
+            return Ok(DispatchClaim::Claimed);
+        }
+
+        // Losing the insert means a concurrent delivery of the same attempt
+        // got there first.
+        let winner = sqlx::query_scalar::<_, Option<Uuid>>(
+            r#"
+            SELECT worker_id
+            FROM dispatch_guards
+            WHERE message_id = $1
+              AND attempt = $2
+            "#,
+        )
+        .bind(message_id)
+        .bind(attempt as i32)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(DispatchClaim::Interrupted {
+            worker_id: winner.flatten(),
+        })
+    }
+
+    async fn mark_sent(&self, message_id: Uuid, attempt: u32) -> anyhow::Result<()> {
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO dispatch_guards (message_id, attempt, sent_at, created_at)
+            VALUES ($1,$2,$3,$3)
+            ON CONFLICT (message_id, attempt) DO UPDATE SET sent_at = EXCLUDED.sent_at
+            "#,
+        )
+        .bind(message_id)
+        .bind(attempt as i32)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn release(&self, message_id: Uuid) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM dispatch_guards
+            WHERE message_id = $1
+              AND sent_at IS NULL
+            "#,
+        )
+        .bind(message_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresPasswordRepository {
+    pool: PgPool,
+}
+
+impl PostgresPasswordRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl PasswordRepository for PostgresPasswordRepository {
+    async fn find(&self, user_id: Uuid) -> anyhow::Result<Option<PasswordCredential>> {
+        let record = sqlx::query_as::<_, PasswordRecord>(
+            r#"
+            SELECT user_id, password_hash, updated_at
+            FROM user_passwords
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn upsert(&self, user_id: Uuid, password_hash: &str) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO user_passwords (user_id, password_hash, updated_at)
+            VALUES ($1,$2,$3)
+            ON CONFLICT (user_id) DO UPDATE
+            SET password_hash = EXCLUDED.password_hash,
+                updated_at = EXCLUDED.updated_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(password_hash)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM password_resets
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn create_reset(
+        &self,
+        token_hash: &str,
+        user_id: Uuid,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO password_resets (token_hash, user_id, expires_at, created_at)
+            VALUES ($1,$2,$3,$4)
+            "#,
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(expires_at)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn consume_reset(&self, token_hash: &str) -> anyhow::Result<Option<Uuid>> {
+        let row = sqlx::query(
+            r#"
+            DELETE FROM password_resets
+            WHERE token_hash = $1
+              AND expires_at > $2
+            RETURNING user_id
+            "#,
+        )
+        .bind(token_hash)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|row| row.try_get("user_id").map_err(Into::into))
+            .transpose()
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresLoginTokenRepository {
+    pool: PgPool,
+}
+
+impl PostgresLoginTokenRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl LoginTokenRepository for PostgresLoginTokenRepository {
+    async fn create(
+        &self,
+        token_hash: &str,
+        email: &str,
+        expires_at: DateTime<Utc>,
+    ) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO login_tokens (token_hash, email, expires_at, created_at)
+            VALUES ($1,$2,$3,$4)
+            "#,
+        )
+        .bind(token_hash)
+        .bind(email)
+        .bind(expires_at)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn consume(&self, token_hash: &str) -> anyhow::Result<Option<String>> {
+        let row = sqlx::query(
+            r#"
+            DELETE FROM login_tokens
+            WHERE token_hash = $1
+              AND expires_at > $2
+            RETURNING email
+            "#,
+        )
+        .bind(token_hash)
+        .bind(Utc::now())
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(|row| row.try_get("email").map_err(Into::into))
+            .transpose()
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresAccountErasureRepository {
+    pool: PgPool,
+}
+
+impl PostgresAccountErasureRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl AccountErasureRepository for PostgresAccountErasureRepository {
+    async fn start(&self, erasure: &AccountErasure) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO account_erasures (id, user_id, email_hash, requested_at)
+            VALUES ($1, $2, $3, $4)
+            "#,
+        )
+        .bind(erasure.id)
+        .bind(erasure.user_id)
+        .bind(&erasure.email_hash)
+        .bind(erasure.requested_at)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE users
+            SET disabled_at = COALESCE(disabled_at, $2),
+                updated_at = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(erasure.user_id)
+        .bind(erasure.requested_at)
+        .execute(&mut *tx)
+        .await?;
+        // Shared tokens stay with their organization, under another member.
+        sqlx::query(
+            r#"
+            UPDATE messenger_tokens
+            SET user_id = COALESCE(
+                    (
+                        SELECT members.user_id
+                        FROM organization_members members
+                        WHERE members.organization_id = messenger_tokens.organization_id
+                          AND members.user_id <> $1
+                        ORDER BY members.role = 'owner' DESC, members.created_at
+                        LIMIT 1
+                    ),
+                    user_id
+                )
+            WHERE user_id = $1
+              AND shared
+            "#,
+        )
+        .bind(erasure.user_id)
+        .execute(&mut *tx)
+        .await?;
+        for statement in [
+            "DELETE FROM messenger_tokens WHERE user_id = $1",
+            "DELETE FROM api_keys WHERE user_id = $1",
+            "DELETE FROM user_passwords WHERE user_id = $1",
+            "DELETE FROM password_resets WHERE user_id = $1",
+            "DELETE FROM user_identities WHERE user_id = $1",
+        ] {
+            sqlx::query(statement)
+                .bind(erasure.user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn find_pending(&self, user_id: Uuid) -> anyhow::Result<Option<AccountErasure>> {
+        let record = sqlx::query_as::<_, AccountErasureRecord>(
+            r#"
+            SELECT id, user_id, email_hash, requested_at, completed_at, messages_purged
+            FROM account_erasures
+            WHERE user_id = $1
+              AND completed_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list_pending(&self) -> anyhow::Result<Vec<AccountErasure>> {
+        let rows = sqlx::query_as::<_, AccountErasureRecord>(
+            r#"
+            SELECT id, user_id, email_hash, requested_at, completed_at, messages_purged
+            FROM account_erasures
+            WHERE completed_at IS NULL
+            ORDER BY requested_at
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn purge_messages(&self, erasure: &AccountErasure, limit: u32) -> anyhow::Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let ids = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            SELECT id
+            FROM message_history
+            WHERE organization_id = $1
+            LIMIT $2
+            "#,
+        )
+        .bind(erasure.user_id)
+        .bind(limit as i64)
+        .fetch_all(&mut *tx)
+        .await?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        // Nothing cascades from the partitioned history, see the
+        // partition_message_history migration.
+        for statement in [
+            "DELETE FROM message_attempts WHERE message_id = ANY($1)",
+            "DELETE FROM message_links WHERE source_id = ANY($1) OR target_id = ANY($1)",
+            "DELETE FROM otp_codes WHERE message_id = ANY($1)",
+            "DELETE FROM idempotency_keys WHERE message_id = ANY($1)",
+            "DELETE FROM dispatch_guards WHERE message_id = ANY($1)",
+            "DELETE FROM message_history WHERE id = ANY($1)",
+        ] {
+            sqlx::query(statement).bind(&ids).execute(&mut *tx).await?;
+        }
+        sqlx::query(
+            r#"
+            UPDATE account_erasures
+            SET messages_purged = messages_purged + $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(erasure.id)
+        .bind(ids.len() as i64)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(ids.len() as u64)
+    }
+
+    async fn anonymize_messages(
+        &self,
+        erasure: &AccountErasure,
+        limit: u32,
+    ) -> anyhow::Result<u64> {
+        let anonymized = sqlx::query(
+            r#"
+            UPDATE message_history
+            SET user_id = NULL
+            WHERE id IN (
+                SELECT id
+                FROM message_history
+                WHERE user_id = $1
+                  AND organization_id <> $1
+                LIMIT $2
+            )
+            "#,
+        )
+        .bind(erasure.user_id)
+        .bind(limit as i64)
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        Ok(anonymized)
+    }
+
+    async fn complete(&self, erasure: &AccountErasure) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        // The personal organization shares the user's id.
+        for statement in [
+            "DELETE FROM organizations WHERE id = $1",
+            "DELETE FROM users WHERE id = $1",
+        ] {
+            sqlx::query(statement)
+                .bind(erasure.user_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        sqlx::query(
+            r#"
+            UPDATE account_erasures
+            SET completed_at = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(erasure.id)
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list(&self, limit: u32) -> anyhow::Result<Vec<AccountErasure>> {
+        let rows = sqlx::query_as::<_, AccountErasureRecord>(
+            r#"
+            SELECT id, user_id, email_hash, requested_at, completed_at, messages_purged
+            FROM account_erasures
+            ORDER BY requested_at DESC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Tables partitioned by month, see the partition_message_history migration.
+const PARTITIONED_TABLES: [&str; 2] = ["message_history", "message_attempts"];
+
+#[derive(Clone)]
+pub struct PostgresHistoryPartitionRepository {
+    pool: PgPool,
+}
+
+impl PostgresHistoryPartitionRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+
+    async fn list_partitions(&self, parent: &str) -> anyhow::Result<Vec<String>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT child.relname AS name
+            FROM pg_inherits
+            JOIN pg_class parent ON parent.oid = pg_inherits.inhparent
+            JOIN pg_class child ON child.oid = pg_inherits.inhrelid
+            WHERE parent.relname = $1
+            "#,
+        )
+        .bind(parent)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter()
+            .map(|row| row.try_get("name").map_err(Into::into))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl HistoryPartitionRepository for PostgresHistoryPartitionRepository {
+    async fn ensure_partitions(&self, months_ahead: u32) -> anyhow::Result<()> {
+        let today = Utc::now().date_naive();
+        let current = NaiveDate::from_ymd_opt(today.year(), today.month(), 1)
+            .ok_or_else(|| anyhow::anyhow!("invalid current month"))?;
+        for parent in PARTITIONED_TABLES {
+            for offset in 0..=months_ahead {
+                let month = current
+                    .checked_add_months(Months::new(offset))
+                    .ok_or_else(|| anyhow::anyhow!("partition month out of range"))?;
+                sqlx::query("SELECT ensure_monthly_partition($1, $2)")
+                    .bind(parent)
+                    .bind(month)
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn drop_partitions_before(&self, cutoff: DateTime<Utc>) -> anyhow::Result<Vec<String>> {
+        let mut dropped = Vec::new();
+        for parent in PARTITIONED_TABLES {
+            for name in self.list_partitions(parent).await? {
+                // Partitions are named <parent>_YYYYMM.
+                let month_end = name
+                    .strip_prefix(parent)
+                    .and_then(|suffix| suffix.strip_prefix('_'))
+                    .filter(|suffix| suffix.len() == 6)
+                    .and_then(|suffix| {
+                        NaiveDate::parse_from_str(&format!("{suffix}01"), "%Y%m%d").ok()
+                    })
+                    .and_then(|month| month.checked_add_months(Months::new(1)));
+                if month_end.is_none_or(|end| end > cutoff.date_naive()) {
+                    continue;
+                }
+                sqlx::query(&format!(r#"DROP TABLE IF EXISTS "{name}""#))
+                    .execute(&self.pool)
+                    .await?;
+                dropped.push(name);
+            }
+        }
+        if dropped.is_empty() {
+            return Ok(dropped);
+        }
+
+        for statement in [
+            r#"
+            DELETE FROM message_links
+            WHERE NOT EXISTS (SELECT 1 FROM message_history WHERE id = source_id)
+               OR NOT EXISTS (SELECT 1 FROM message_history WHERE id = target_id)
+            "#,
+            r#"
+            DELETE FROM otp_codes
+            WHERE NOT EXISTS (SELECT 1 FROM message_history WHERE id = otp_codes.message_id)
+            "#,
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE message_id IS NOT NULL
+              AND NOT EXISTS (
+                  SELECT 1 FROM message_history WHERE id = idempotency_keys.message_id
+              )
+            "#,
+            r#"
+            DELETE FROM dispatch_guards
+            WHERE NOT EXISTS (SELECT 1 FROM message_history WHERE id = dispatch_guards.message_id)
+            "#,
+            r#"
+            DELETE FROM message_attempts
+            WHERE NOT EXISTS (SELECT 1 FROM message_history WHERE id = message_attempts.message_id)
+            "#,
+        ] {
+            sqlx::query(statement).execute(&self.pool).await?;
+        }
+        Ok(dropped)
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresRoutingRuleRepository {
+    pool: PgPool,
+}
+
+impl PostgresRoutingRuleRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl RoutingRuleRepository for PostgresRoutingRuleRepository {
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<RoutingRule>> {
+        let rows = sqlx::query_as::<_, RoutingRuleRecord>(
+            r#"
+            SELECT id, user_id, name, position, enabled, match_event, match_class, match_tags,
+                   match_metadata::TEXT AS match_metadata, messenger, recipient, template,
+                   priority, created_at, updated_at
+            FROM routing_rules
+            WHERE user_id = $1
+            ORDER BY position, created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(RoutingRule::try_from).collect()
+    }
+
+    async fn get(&self, user_id: Uuid, rule_id: Uuid) -> anyhow::Result<Option<RoutingRule>> {
+        let row = sqlx::query_as::<_, RoutingRuleRecord>(
+            r#"
+            SELECT id, user_id, name, position, enabled, match_event, match_class, match_tags,
+                   match_metadata::TEXT AS match_metadata, messenger, recipient, template,
+                   priority, created_at, updated_at
+            FROM routing_rules
+            WHERE id = $1
+              AND user_id = $2
+            "#,
+        )
+        .bind(rule_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(RoutingRule::try_from).transpose()
+    }
+
+    async fn upsert(&self, rule: &RoutingRule) -> anyhow::Result<()> {
+        upsert_routing_rule(&self.pool, rule).await
+    }
+
+    async fn delete(&self, user_id: Uuid, rule_id: Uuid) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM routing_rules
+            WHERE id = $1
+              AND user_id = $2
+            "#,
+        )
+        .bind(rule_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn snapshot(&self, user_id: Uuid, reason: &str) -> anyhow::Result<i32> {
+        let mut tx = self.pool.begin().await?;
+        // Serializes snapshots of the same user so version numbers stay dense.
+        sqlx::query(
+            r#"
+            INSERT INTO routing_rule_sets (user_id, active_version)
+            VALUES ($1, 0)
+            ON CONFLICT (user_id) DO UPDATE
+            SET active_version = routing_rule_sets.active_version
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let rows = sqlx::query_as::<_, RoutingRuleRecord>(
+            r#"
+            SELECT id, user_id, name, position, enabled, match_event, match_class, match_tags,
+                   match_metadata::TEXT AS match_metadata, messenger, recipient, template,
+                   priority, created_at, updated_at
+            FROM routing_rules
+            WHERE user_id = $1
+            ORDER BY position, created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let rules = rows
+            .into_iter()
+            .map(RoutingRule::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let version: i32 = sqlx::query_scalar(
+            r#"
+            INSERT INTO routing_rule_versions (user_id, version, rules, reason, created_at)
+            SELECT $1, COALESCE(MAX(version), 0) + 1, $2::JSONB, $3, $4
+            FROM routing_rule_versions
+            WHERE user_id = $1
+            RETURNING version
+            "#,
+        )
+        .bind(user_id)
+        .bind(serde_json::to_string(&rules)?)
+        .bind(reason)
+        .bind(Utc::now())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE routing_rule_sets
+            SET active_version = $2
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .bind(version)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(version)
+    }
+
+    async fn list_versions(&self, user_id: Uuid) -> anyhow::Result<Vec<RoutingRuleVersion>> {
+        let rows = sqlx::query_as::<_, RoutingRuleVersionRecord>(
+            r#"
+            SELECT version, rules::TEXT AS rules, reason, created_at
+            FROM routing_rule_versions
+            WHERE user_id = $1
+            ORDER BY version DESC
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.into_iter().map(RoutingRuleVersion::try_from).collect()
+    }
+
+    async fn get_version(
+        &self,
+        user_id: Uuid,
+        version: i32,
+    ) -> anyhow::Result<Option<RoutingRuleVersion>> {
+        let row = sqlx::query_as::<_, RoutingRuleVersionRecord>(
+            r#"
+            SELECT version, rules::TEXT AS rules, reason, created_at
+            FROM routing_rule_versions
+            WHERE user_id = $1
+              AND version = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(version)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.map(RoutingRuleVersion::try_from).transpose()
+    }
+
+    async fn active_version(&self, user_id: Uuid) -> anyhow::Result<Option<i32>> {
+        let version = sqlx::query_scalar::<_, i32>(
+            r#"
+            SELECT active_version
+            FROM routing_rule_sets
+            WHERE user_id = $1
+              AND active_version > 0
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(version)
+    }
+
+    async fn activate(&self, user_id: Uuid, version: i32) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let row = sqlx::query_as::<_, RoutingRuleVersionRecord>(
+            r#"
+            SELECT version, rules::TEXT AS rules, reason, created_at
+            FROM routing_rule_versions
+            WHERE user_id = $1
+              AND version = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(version)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some(row) = row else {
+            return Ok(false);
+        };
+        let snapshot = RoutingRuleVersion::try_from(row)?;
+
+        sqlx::query(
+            r#"
+            DELETE FROM routing_rules
+            WHERE user_id = $1
+            "#,
+        )
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        for rule in &snapshot.rules {
+            upsert_routing_rule(&mut *tx, rule).await?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO routing_rule_sets (user_id, active_version)
+            VALUES ($1, $2)
+            ON CONFLICT (user_id) DO UPDATE
+            SET active_version = EXCLUDED.active_version
+            "#,
+        )
+        .bind(user_id)
+        .bind(version)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+}
+
+async fn upsert_routing_rule<'e, E>(executor: E, rule: &RoutingRule) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO routing_rules (
+            id, user_id, name, position, enabled, match_event, match_class, match_tags,
+            match_metadata, messenger, recipient, template, priority, created_at, updated_at
+        )
+        VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9::JSONB,$10,$11,$12,$13,$14,$15)
+        ON CONFLICT (id) DO UPDATE
+        SET name = EXCLUDED.name,
+            position = EXCLUDED.position,
+            enabled = EXCLUDED.enabled,
+            match_event = EXCLUDED.match_event,
+            match_class = EXCLUDED.match_class,
+            match_tags = EXCLUDED.match_tags,
+            match_metadata = EXCLUDED.match_metadata,
+            messenger = EXCLUDED.messenger,
+            recipient = EXCLUDED.recipient,
+            template = EXCLUDED.template,
+            priority = EXCLUDED.priority,
+            updated_at = EXCLUDED.updated_at
+        WHERE routing_rules.user_id = EXCLUDED.user_id
+        "#,
+    )
+    .bind(rule.id)
+    .bind(rule.user_id)
+    .bind(&rule.name)
+    .bind(rule.position)
+    .bind(rule.enabled)
+    .bind(&rule.match_event)
+    .bind(rule.match_class.map(|class| class.as_str()))
+    .bind(&rule.match_tags)
+    .bind(serde_json::to_string(&rule.match_metadata)?)
+    .bind(rule.messenger.as_str())
+    .bind(&rule.recipient)
+    .bind(&rule.template)
+    .bind(rule.priority.map(|priority| priority.as_str()))
+    .bind(rule.created_at)
+    .bind(rule.updated_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct PostgresIdentityRepository {
+    pool: PgPool,
+}
+
+impl PostgresIdentityRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl IdentityRepository for PostgresIdentityRepository {
+    async fn find(&self, provider: &str, subject: &str) -> anyhow::Result<Option<UserIdentity>> {
+        let record = sqlx::query_as::<_, IdentityRecord>(
+            r#"
+            SELECT id, user_id, provider, subject, created_at
+            FROM user_identities
+            WHERE provider = $1
+              AND subject = $2
+            "#,
+        )
+        .bind(provider)
+        .bind(subject)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<UserIdentity>> {
+        let records = sqlx::query_as::<_, IdentityRecord>(
+            r#"
+            SELECT id, user_id, provider, subject, created_at
+            FROM user_identities
+            WHERE user_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn link(&self, identity: &UserIdentity) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO user_identities (id, user_id, provider, subject, created_at)
+            VALUES ($1,$2,$3,$4,$5)
+            ON CONFLICT (provider, subject) DO NOTHING
+            "#,
+        )
+        .bind(identity.id)
+        .bind(identity.user_id)
+        .bind(&identity.provider)
+        .bind(&identity.subject)
+        .bind(identity.created_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn unlink(&self, user_id: Uuid, identity_id: Uuid) -> anyhow::Result<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM user_identities
+            WHERE id = $1
+              AND user_id = $2
+              AND (SELECT COUNT(*) FROM user_identities WHERE user_id = $2) > 1
+            "#,
+        )
+        .bind(identity_id)
+        .bind(user_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[derive(Clone)]
+pub struct PostgresApiKeyRepository {
+    pool: PgPool,
+}
+
+impl PostgresApiKeyRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+}
+
+#[async_trait]
+impl ApiKeyRepository for PostgresApiKeyRepository {
+    async fn insert(&self, key: &ApiKey) -> anyhow::Result<()> {
+        insert_api_key(&self.pool, key).await
+    }
+
+    async fn list_by_user(&self, user_id: Uuid) -> anyhow::Result<Vec<ApiKey>> {
+        let records = sqlx::query_as::<_, ApiKeyRecord>(
+            r#"
+            SELECT id, user_id, name, prefix, key_hash, created_at, last_used_at, scopes,
+                   allowed_ips
+            FROM api_keys
+            WHERE user_id = $1
+              AND revoked_at IS NULL
+            ORDER BY created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn find_active(&self, key_hash: &str) -> anyhow::Result<Option<ApiKey>> {
+        let record = sqlx::query_as::<_, ApiKeyRecord>(
+            r#"
+            SELECT id, user_id, name, prefix, key_hash, created_at, last_used_at, scopes,
+                   allowed_ips
+            FROM api_keys
+            WHERE key_hash = $1
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn rotate(
+        &self,
+        user_id: Uuid,
+        key_id: Uuid,
+        replacement: &ApiKey,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        if !revoke_api_key(&mut *tx, user_id, key_id, replacement.created_at).await? {
+            return Ok(false);
+        }
+        insert_api_key(&mut *tx, replacement).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn revoke(&self, user_id: Uuid, key_id: Uuid) -> anyhow::Result<bool> {
+        revoke_api_key(&self.pool, user_id, key_id, Utc::now()).await
+    }
+
+    async fn touch(&self, key_id: Uuid, used_at: DateTime<Utc>) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            UPDATE api_keys
+            SET last_used_at = $2
+            WHERE id = $1
+            "#,
+        )
+        .bind(key_id)
+        .bind(used_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
     }
 }
 
-#[derive(FromRow)]
-struct MessengerTokenRecord {
-    id: Uuid,
+async fn insert_api_key<'e, E>(executor: E, key: &ApiKey) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO api_keys (
+            id, user_id, name, prefix, key_hash, created_at, scopes, allowed_ips
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(key.id)
+    .bind(key.user_id)
+    .bind(&key.name)
+    .bind(&key.prefix)
+    .bind(&key.key_hash)
+    .bind(key.created_at)
+    .bind(
+        key.scopes
+            .iter()
+            .map(|scope| scope.as_str())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+    .bind(
+        key.allowed_ips
+            .iter()
+            .map(|net| net.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+async fn revoke_api_key<'e, E>(
+    executor: E,
     user_id: Uuid,
-    messenger: String,
-    access_token: String,
-    refresh_token: Option<String>,
-    status: String,
-    created_at: DateTime<Utc>,
-    updated_at: DateTime<Utc>,
-}
-
-impl TryFrom<MessengerTokenRecord> for MessengerToken {
-    type Error = anyhow::Error;
-
-    fn try_from(value: MessengerTokenRecord) -> Result<Self, Self::Error> {
-        let messenger = MessengerType::from_str(&value.messenger)
-            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
-        let status = match value.status.as_str() {
-            "active" => MessengerTokenStatus::Active,
-            "inactive" => MessengerTokenStatus::Inactive,
-            other => anyhow::bail!("unknown token status {other}"),
-        };
-        Ok(Self {
-            id: value.id,
-            user_id: value.user_id,
-            messenger,
-            access_token: value.access_token,
-            refresh_token: value.refresh_token,
-            status,
-            created_at: value.created_at,
-            updated_at: value.updated_at,
-        })
-    }
+    key_id: Uuid,
+    revoked_at: DateTime<Utc>,
+) -> anyhow::Result<bool>
+where
+    E: sqlx::Executor<'e, Database = Postgres>,
+{
+    let result = sqlx::query(
+        r#"
+        UPDATE api_keys
+        SET revoked_at = $3
+        WHERE id = $1
+          AND user_id = $2
+          AND revoked_at IS NULL
+        "#,
+    )
+    .bind(key_id)
+    .bind(user_id)
+    .bind(revoked_at)
+    .execute(executor)
+    .await?;
+    Ok(result.rows_affected() > 0)
 }
 
-impl TryFrom<sqlx::postgres::PgRow> for MessageHistoryEntry {
-    type Error = anyhow::Error;
+#[derive(Clone)]
+pub struct PostgresRefreshTokenRepository {
+    pool: PgPool,
+}
 
-    fn try_from(row: sqlx::postgres::PgRow) -> Result<Self, Self::Error> {
-        let messenger_str: String = row.try_get("messenger")?;
-        let messenger = MessengerType::from_str(&messenger_str)
-            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", messenger_str))?;
-        let message_type = row.try_get::<String, _>("message_type")?;
-        let content = MessageContent {
-            body: row.try_get("body")?,
-            message_type: str_to_message_type(&message_type)?,
-        };
-        let status_str: String = row.try_get("status")?;
-        let status_reason: Option<String> = row.try_get("status_reason")?;
-        let attempts: i32 = row.try_get("attempts")?;
-        let status = message_status_from_fields(&status_str, status_reason, attempts)?;
-        let requested_by_str: String = row.try_get("requested_by")?;
-        let requested_by = str_to_requested_by(&requested_by_str)?;
-
-        Ok(MessageHistoryEntry {
-            id: row.try_get("id")?,
-            user_id: row.try_get("user_id")?,
-            messenger,
-            recipient: row.try_get("recipient")?,
-            content,
-            status,
-            created_at: row.try_get("created_at")?,
-            updated_at: row.try_get("updated_at")?,
-            attempts: attempts as u32,
-            requested_by,
-        })
+impl PostgresRefreshTokenRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
     }
 }
 
-fn token_status_to_str(status: MessengerTokenStatus) -> &'static str {
-    match status {
-        MessengerTokenStatus::Active => "active",
-        MessengerTokenStatus::Inactive => "inactive",
+#[async_trait]
+impl RefreshTokenRepository for PostgresRefreshTokenRepository {
+    async fn find(&self, token_hash: &str) -> anyhow::Result<Option<RefreshToken>> {
+        let record = sqlx::query_as::<_, RefreshTokenRecord>(
+            r#"
+            SELECT id, family_id, user_id, token_hash, created_at, expires_at, used_at, revoked_at
+            FROM refresh_tokens
+            WHERE token_hash = $1
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn rotate(&self, token_id: Uuid, next: &RefreshToken) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET used_at = $1
+            WHERE id = $2
+              AND used_at IS NULL
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(next.created_at)
+        .bind(token_id)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        insert_refresh_token(&mut *tx, next).await?;
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET last_seen_at = $2, expires_at = $3
+            WHERE id = $1
+            "#,
+        )
+        .bind(next.family_id)
+        .bind(next.created_at)
+        .bind(next.expires_at)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
     }
 }
 
-fn message_type_to_str(message_type: &MessageType) -> &'static str {
-    match message_type {
-        MessageType::PlainText => "plain_text",
+#[derive(Clone)]
+pub struct PostgresSessionRepository {
+    pool: PgPool,
+}
+
+impl PostgresSessionRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
     }
 }
 
-fn str_to_message_type(value: &str) -> anyhow::Result<MessageType> {
-    match value {
-        "plain_text" => Ok(MessageType::PlainText),
-        other => anyhow::bail!("unknown message type {other}"),
+#[async_trait]
+impl SessionRepository for PostgresSessionRepository {
+    async fn insert(&self, session: &Session, token: &RefreshToken) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO sessions (id, user_id, device, ip, created_at, last_seen_at, expires_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(session.id)
+        .bind(session.user_id)
+        .bind(&session.device)
+        .bind(&session.ip)
+        .bind(session.created_at)
+        .bind(session.last_seen_at)
+        .bind(session.expires_at)
+        .execute(&mut *tx)
+        .await?;
+        insert_refresh_token(&mut *tx, token).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_active(&self, user_id: Uuid) -> anyhow::Result<Vec<Session>> {
+        let records = sqlx::query_as::<_, SessionRecord>(
+            r#"
+            SELECT id, user_id, device, ip, created_at, last_seen_at, expires_at
+            FROM sessions
+            WHERE user_id = $1
+              AND revoked_at IS NULL
+              AND expires_at > $2
+            ORDER BY last_seen_at DESC
+            "#,
+        )
+        .bind(user_id)
+        .bind(Utc::now())
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn revoke(&self, user_id: Uuid, session_id: Uuid) -> anyhow::Result<bool> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE sessions
+            SET revoked_at = $3
+            WHERE id = $1
+              AND user_id = $2
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(session_id)
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = $2
+            WHERE family_id = $1
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(session_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn revoke_all(&self, user_id: Uuid) -> anyhow::Result<()> {
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET revoked_at = $2
+            WHERE user_id = $1
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            r#"
+            UPDATE refresh_tokens
+            SET revoked_at = $2
+            WHERE user_id = $1
+              AND revoked_at IS NULL
+            "#,
+        )
+        .bind(user_id)
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn revoked_since(
+        &self,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<(Uuid, DateTime<Utc>)>> {
+        let revoked = sqlx::query_as::<_, (Uuid, DateTime<Utc>)>(
+            r#"
+            SELECT id, revoked_at
+            FROM sessions
+            WHERE revoked_at >= $1
+            "#,
+        )
+        .bind(since)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(revoked)
     }
 }
 
-fn requested_by_to_str(value: &RequestedBy) -> &'static str {
-    match value {
-        RequestedBy::System => "system",
-        RequestedBy::User => "user",
+async fn insert_refresh_token<'e, E>(executor: E, token: &RefreshToken) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO refresh_tokens (id, family_id, user_id, token_hash, created_at, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        "#,
+    )
+    .bind(token.id)
+    .bind(token.family_id)
+    .bind(token.user_id)
+    .bind(&token.token_hash)
+    .bind(token.created_at)
+    .bind(token.expires_at)
+    .execute(executor)
+    .await?;
+    Ok(())
+}
+
+#[derive(Clone)]
+pub struct PostgresOrganizationRepository {
+    pool: PgPool,
+}
+
+impl PostgresOrganizationRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
     }
 }
 
-fn str_to_requested_by(value: &str) -> anyhow::Result<RequestedBy> {
-    match value {
-        "system" => Ok(RequestedBy::System),
-        "user" => Ok(RequestedBy::User),
-        other => anyhow::bail!("unknown requested_by {other}"),
+#[async_trait]
+impl OrganizationRepository for PostgresOrganizationRepository {
+    async fn create(
+        &self,
+        organization: &Organization,
+        owner: &OrganizationMember,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO organizations (id, name, created_at)
+            VALUES ($1, $2, $3)
+            "#,
+        )
+        .bind(organization.id)
+        .bind(&organization.name)
+        .bind(organization.created_at)
+        .execute(&mut *tx)
+        .await?;
+        insert_organization_member(&mut *tx, owner).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn get(&self, organization_id: Uuid) -> anyhow::Result<Option<Organization>> {
+        let record = sqlx::query_as::<_, OrganizationRecord>(
+            r#"
+            SELECT id, name, created_at
+            FROM organizations
+            WHERE id = $1
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list_for_user(
+        &self,
+        user_id: Uuid,
+    ) -> anyhow::Result<Vec<(Organization, MemberRole)>> {
+        let records = sqlx::query_as::<_, MembershipRecord>(
+            r#"
+            SELECT o.id, o.name, o.created_at, m.role
+            FROM organization_members m
+            JOIN organizations o ON o.id = m.organization_id
+            WHERE m.user_id = $1
+            ORDER BY o.created_at
+            "#,
+        )
+        .bind(user_id)
+        .fetch_all(&self.pool)
+        .await?;
+        records.into_iter().map(TryInto::try_into).collect()
+    }
+
+    async fn find_member(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+    ) -> anyhow::Result<Option<OrganizationMember>> {
+        let record = sqlx::query_as::<_, OrganizationMemberRecord>(
+            r#"
+            SELECT organization_id, user_id, role, created_at
+            FROM organization_members
+            WHERE organization_id = $1
+              AND user_id = $2
+            "#,
+        )
+        .bind(organization_id)
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(OrganizationMember::try_from).transpose()
+    }
+
+    async fn list_members(&self, organization_id: Uuid) -> anyhow::Result<Vec<OrganizationMember>> {
+        let records = sqlx::query_as::<_, OrganizationMemberRecord>(
+            r#"
+            SELECT organization_id, user_id, role, created_at
+            FROM organization_members
+            WHERE organization_id = $1
+            ORDER BY created_at
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+        records
+            .into_iter()
+            .map(OrganizationMember::try_from)
+            .collect()
+    }
+
+    async fn insert_invitation(&self, invitation: &OrganizationInvitation) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO organization_invitations (
+                id, organization_id, email, role, token_hash, invited_by, created_at, expires_at
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#,
+        )
+        .bind(invitation.id)
+        .bind(invitation.organization_id)
+        .bind(&invitation.email)
+        .bind(invitation.role.as_str())
+        .bind(&invitation.token_hash)
+        .bind(invitation.invited_by)
+        .bind(invitation.created_at)
+        .bind(invitation.expires_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_invitations(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<OrganizationInvitation>> {
+        let records = sqlx::query_as::<_, OrganizationInvitationRecord>(
+            r#"
+            SELECT id, organization_id, email, role, token_hash, invited_by, created_at,
+                   expires_at, accepted_at
+            FROM organization_invitations
+            WHERE organization_id = $1
+              AND accepted_at IS NULL
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(organization_id)
+        .fetch_all(&self.pool)
+        .await?;
+        records
+            .into_iter()
+            .map(OrganizationInvitation::try_from)
+            .collect()
+    }
+
+    async fn find_invitation(
+        &self,
+        token_hash: &str,
+    ) -> anyhow::Result<Option<OrganizationInvitation>> {
+        let record = sqlx::query_as::<_, OrganizationInvitationRecord>(
+            r#"
+            SELECT id, organization_id, email, role, token_hash, invited_by, created_at,
+                   expires_at, accepted_at
+            FROM organization_invitations
+            WHERE token_hash = $1
+            "#,
+        )
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+        record.map(OrganizationInvitation::try_from).transpose()
+    }
+
+    async fn accept_invitation(
+        &self,
+        invitation_id: Uuid,
+        member: &OrganizationMember,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            UPDATE organization_invitations
+            SET accepted_at = $2
+            WHERE id = $1
+              AND accepted_at IS NULL
+            "#,
+        )
+        .bind(invitation_id)
+        .bind(member.created_at)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+        insert_organization_member(&mut *tx, member).await?;
+        tx.commit().await?;
+        Ok(true)
     }
 }
 
-fn requested_by_from_str(value: &str) -> anyhow::Result<RequestedBy> {
-    str_to_requested_by(value)
+/// Leaves an existing membership, and the role it has, as it is.
+async fn insert_organization_member<'e, E>(
+    executor: E,
+    member: &OrganizationMember,
+) -> anyhow::Result<()>
+where
+    E: sqlx::Executor<'e, Database = Postgres>,
+{
+    sqlx::query(
+        r#"
+        INSERT INTO organization_members (organization_id, user_id, role, created_at)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (organization_id, user_id) DO NOTHING
+        "#,
+    )
+    .bind(member.organization_id)
+    .bind(member.user_id)
+    .bind(member.role.as_str())
+    .bind(member.created_at)
+    .execute(executor)
+    .await?;
+    Ok(())
 }
 
-fn message_status_to_fields(status: &MessageStatus) -> (&'static str, Option<String>) {
-    match status {
-        MessageStatus::Pending => ("pending", None),
-        MessageStatus::Scheduled => ("scheduled", None),
-        MessageStatus::InFlight => ("in_flight", None),
-        MessageStatus::Sent => ("sent", None),
-        MessageStatus::Retrying { reason, .. } => ("retrying", Some(reason.clone())),
-        MessageStatus::Failed { reason, .. } => ("failed", Some(reason.clone())),
-        MessageStatus::Cancelled => ("cancelled", None),
+#[derive(Clone)]
+pub struct PostgresWorkerRepository {
+    pool: PgPool,
+}
+
+impl PostgresWorkerRepository {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
     }
 }
 
-fn message_status_from_fields(
-    status: &str,
-    reason: Option<String>,
-    attempts: i32,
-) -> anyhow::Result<MessageStatus> {
-    Ok(match status {
-        "pending" => MessageStatus::Pending,
-        "scheduled" => MessageStatus::Scheduled,
-        "in_flight" => MessageStatus::InFlight,
-        "sent" => MessageStatus::Sent,
-        "retrying" => MessageStatus::Retrying {
-            reason: reason.unwrap_or_else(|| "retrying".to_string()),
-            attempts: attempts as u32,
-        },
-        "failed" => MessageStatus::Failed {
-            reason: reason.unwrap_or_else(|| "failed".to_string()),
-            attempts: attempts as u32,
-        },
-        "cancelled" => MessageStatus::Cancelled,
-        other => anyhow::bail!("unknown message status {other}"),
-    })
+#[async_trait]
+impl WorkerRepository for PostgresWorkerRepository {
+    async fn heartbeat(&self, worker: &Worker) -> anyhow::Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO workers (
+                id, hostname, version, bus, started_at, heartbeat_at, in_flight, handled, failed
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            ON CONFLICT (id) DO UPDATE
+            SET heartbeat_at = EXCLUDED.heartbeat_at,
+                in_flight = EXCLUDED.in_flight,
+                handled = EXCLUDED.handled,
+                failed = EXCLUDED.failed
+            "#,
+        )
+        .bind(worker.id)
+        .bind(&worker.hostname)
+        .bind(&worker.version)
+        .bind(&worker.bus)
+        .bind(worker.started_at)
+        .bind(worker.heartbeat_at)
+        .bind(worker.stats.in_flight as i64)
+        .bind(worker.stats.handled as i64)
+        .bind(worker.stats.failed as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn get(&self, id: Uuid) -> anyhow::Result<Option<Worker>> {
+        let record = sqlx::query_as::<_, WorkerRecord>(
+            r#"
+            SELECT id, hostname, version, bus, started_at, heartbeat_at, in_flight, handled, failed
+            FROM workers
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(record.map(Into::into))
+    }
+
+    async fn list(&self) -> anyhow::Result<Vec<Worker>> {
+        let records = sqlx::query_as::<_, WorkerRecord>(
+            r#"
+            SELECT id, hostname, version, bus, started_at, heartbeat_at, in_flight, handled, failed
+            FROM workers
+            ORDER BY heartbeat_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(records.into_iter().map(Into::into).collect())
+    }
+
+    async fn prune(&self, before: DateTime<Utc>) -> anyhow::Result<u64> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM workers
+            WHERE heartbeat_at < $1
+            "#,
+        )
+        .bind(before)
+        .execute(&self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+/// Relays the notifications of `STATUS_CHANNEL` to the local status feed.
+pub struct PostgresStatusListener {
+    pool: PgPool,
+}
+
+impl PostgresStatusListener {
+    pub fn new(pool: PgPool) -> Arc<Self> {
+        Arc::new(Self { pool })
+    }
+
+    /// Holds one connection of the pool for as long as it listens.
+    async fn listen(&self, feed: &StatusFeed) -> anyhow::Result<()> {
+        let mut listener = PgListener::connect_with(&self.pool).await?;
+        listener.listen(STATUS_CHANNEL).await?;
+        loop {
+            let notification = listener.recv().await?;
+            match serde_json::from_str::<StatusNotificationRecord>(notification.payload())
+                .map_err(anyhow::Error::from)
+                .and_then(MessageStatusChange::try_from)
+            {
+                Ok(change) => feed.publish(change),
+                Err(err) => {
+                    tracing::warn!(error = ?err, "ignoring malformed status notification")
+                }
+            }
+        }
+    }
 }
 
-fn message_status_from_str(status: &str, reason: Option<String>) -> anyhow::Result<MessageStatus> {
-    // For attempts, we use 0 as default since we don't store attempts in message_attempts table
-    message_status_from_fields(status, reason, 0)
+impl StatusListener for PostgresStatusListener {
+    fn spawn(self: Arc<Self>, feed: Arc<StatusFeed>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = self.listen(&feed).await {
+                    tracing::error!(error = ?err, "message status listener failed");
+                }
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        })
+    }
 }
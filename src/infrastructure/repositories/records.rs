@@ -0,0 +1,886 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Deserialize;
+use sqlx::{
+    Decode, FromRow, MySql, Postgres, Type, TypeInfo,
+    error::BoxDynError,
+    mysql::{MySqlTypeInfo, MySqlValueRef},
+    postgres::{PgTypeInfo, PgValueRef},
+};
+use uuid::Uuid;
+
+use crate::{
+    application::services::status_feed::MessageStatusChange,
+    domain::models::{
+        AccountErasure, ApiKey, ApiKeyScope, Backlog, ConsentRecord, ConsentStatus, DeliveryCounts,
+        DeliveryStatsBucket, IdempotencyRecord, MemberRole, MessageClass, MessageContent,
+        MessageHistoryEntry, MessageLink, MessageLinkKind, MessagePriority, MessageStatus,
+        MessageStatusKind, MessageType, MessengerToken, MessengerTokenStatus, MessengerType,
+        Organization, OrganizationInvitation, OrganizationMember, OtpCode, PasswordCredential,
+        RefreshToken, RequestedBy, RoutingRule, RoutingRuleVersion, Session, TokenHealth,
+        TokenSelector, User, UserIdentity, UserRole, Worker, WorkerStats,
+    },
+};
+
+#[derive(FromRow)]
+pub(super) struct UserRecord {
+    id: Uuid,
+    email: String,
+    display_name: Option<String>,
+    role: String,
+    disabled_at: Option<DateTime<Utc>>,
+    email_verified_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<UserRecord> for User {
+    type Error = anyhow::Error;
+
+    fn try_from(value: UserRecord) -> Result<Self, Self::Error> {
+        let role = UserRole::from_str(&value.role)
+            .ok_or_else(|| anyhow::anyhow!("unknown user role {}", value.role))?;
+        Ok(Self {
+            id: value.id,
+            email: value.email,
+            display_name: value.display_name,
+            role,
+            disabled_at: value.disabled_at,
+            email_verified_at: value.email_verified_at,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct MessengerTokenRecord {
+    id: Uuid,
+    organization_id: Uuid,
+    user_id: Uuid,
+    shared: bool,
+    messenger: String,
+    label: Option<String>,
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+    device_id: Option<String>,
+    status: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<MessengerTokenRecord> for MessengerToken {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MessengerTokenRecord) -> Result<Self, Self::Error> {
+        let messenger = MessengerType::from_str(&value.messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
+        let status = match value.status.as_str() {
+            "active" => MessengerTokenStatus::Active,
+            "inactive" => MessengerTokenStatus::Inactive,
+            other => anyhow::bail!("unknown token status {other}"),
+        };
+        Ok(Self {
+            id: value.id,
+            organization_id: value.organization_id,
+            user_id: value.user_id,
+            shared: value.shared,
+            messenger,
+            label: value.label,
+            access_token: value.access_token,
+            refresh_token: value.refresh_token,
+            expires_at: value.expires_at,
+            device_id: value.device_id,
+            status,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct ConsentRecordRow {
+    id: Uuid,
+    organization_id: Uuid,
+    messenger: String,
+    recipient: String,
+    status: String,
+    source: String,
+    proof: Option<String>,
+    confirmation_hash: Option<String>,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<ConsentRecordRow> for ConsentRecord {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConsentRecordRow) -> Result<Self, Self::Error> {
+        let messenger = MessengerType::from_str(&value.messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
+        let status = match value.status.as_str() {
+            "pending" => ConsentStatus::Pending,
+            "confirmed" => ConsentStatus::Confirmed,
+            "revoked" => ConsentStatus::Revoked,
+            other => anyhow::bail!("unknown consent status {other}"),
+        };
+        Ok(Self {
+            id: value.id,
+            organization_id: value.organization_id,
+            messenger,
+            recipient: value.recipient,
+            status,
+            source: value.source,
+            proof: value.proof,
+            confirmation_hash: value.confirmation_hash,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct MessageLinkRecord {
+    source_id: Uuid,
+    target_id: Uuid,
+    kind: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<MessageLinkRecord> for MessageLink {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MessageLinkRecord) -> Result<Self, Self::Error> {
+        let kind = MessageLinkKind::from_str(&value.kind)
+            .ok_or_else(|| anyhow::anyhow!("unknown message link kind {}", value.kind))?;
+        Ok(Self {
+            source_id: value.source_id,
+            target_id: value.target_id,
+            kind,
+            created_at: value.created_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct IdempotencyKeyRecord {
+    organization_id: Uuid,
+    key: String,
+    request_hash: String,
+    message_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+pub(super) struct PasswordRecord {
+    user_id: Uuid,
+    password_hash: String,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+pub(super) struct RoutingRuleRecord {
+    pub(super) id: Uuid,
+    pub(super) user_id: Uuid,
+    pub(super) name: String,
+    pub(super) position: i32,
+    pub(super) enabled: bool,
+    pub(super) match_event: Option<String>,
+    pub(super) match_class: Option<String>,
+    pub(super) match_tags: Vec<String>,
+    pub(super) match_metadata: String,
+    pub(super) messenger: String,
+    pub(super) recipient: String,
+    pub(super) template: Option<String>,
+    pub(super) priority: Option<String>,
+    pub(super) created_at: DateTime<Utc>,
+    pub(super) updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<RoutingRuleRecord> for RoutingRule {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RoutingRuleRecord) -> Result<Self, Self::Error> {
+        let match_class = value
+            .match_class
+            .map(|class| {
+                MessageClass::from_str(&class)
+                    .ok_or_else(|| anyhow::anyhow!("unknown message class {class}"))
+            })
+            .transpose()?;
+        let messenger = MessengerType::from_str(&value.messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
+        let priority = value
+            .priority
+            .map(|priority| {
+                MessagePriority::from_str(&priority)
+                    .ok_or_else(|| anyhow::anyhow!("unknown message priority {priority}"))
+            })
+            .transpose()?;
+        Ok(Self {
+            id: value.id,
+            user_id: value.user_id,
+            name: value.name,
+            position: value.position,
+            enabled: value.enabled,
+            match_event: value.match_event,
+            match_class,
+            match_tags: value.match_tags,
+            match_metadata: serde_json::from_str(&value.match_metadata)?,
+            messenger,
+            recipient: value.recipient,
+            template: value.template,
+            priority,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct RoutingRuleVersionRecord {
+    version: i32,
+    rules: String,
+    reason: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<RoutingRuleVersionRecord> for RoutingRuleVersion {
+    type Error = anyhow::Error;
+
+    fn try_from(value: RoutingRuleVersionRecord) -> Result<Self, Self::Error> {
+        Ok(Self {
+            version: value.version,
+            rules: serde_json::from_str(&value.rules)?,
+            reason: value.reason,
+            created_at: value.created_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct IdentityRecord {
+    id: Uuid,
+    user_id: Uuid,
+    provider: String,
+    subject: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(FromRow)]
+pub(super) struct ApiKeyRecord {
+    id: Uuid,
+    user_id: Uuid,
+    name: String,
+    prefix: String,
+    key_hash: String,
+    created_at: DateTime<Utc>,
+    last_used_at: Option<DateTime<Utc>>,
+    scopes: String,
+    allowed_ips: String,
+}
+
+impl From<ApiKeyRecord> for ApiKey {
+    fn from(value: ApiKeyRecord) -> Self {
+        Self {
+            id: value.id,
+            user_id: value.user_id,
+            name: value.name,
+            prefix: value.prefix,
+            key_hash: value.key_hash,
+            created_at: value.created_at,
+            last_used_at: value.last_used_at,
+            scopes: value
+                .scopes
+                .split_whitespace()
+                .filter_map(ApiKeyScope::from_str)
+                .collect(),
+            allowed_ips: value
+                .allowed_ips
+                .split_whitespace()
+                .filter_map(|net| net.parse().ok())
+                .collect(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct RefreshTokenRecord {
+    id: Uuid,
+    family_id: Uuid,
+    user_id: Uuid,
+    token_hash: String,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    used_at: Option<DateTime<Utc>>,
+    revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<RefreshTokenRecord> for RefreshToken {
+    fn from(value: RefreshTokenRecord) -> Self {
+        Self {
+            id: value.id,
+            family_id: value.family_id,
+            user_id: value.user_id,
+            token_hash: value.token_hash,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            used_at: value.used_at,
+            revoked_at: value.revoked_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct AccountErasureRecord {
+    id: Uuid,
+    user_id: Uuid,
+    email_hash: Option<String>,
+    requested_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+    messages_purged: i64,
+}
+
+impl From<AccountErasureRecord> for AccountErasure {
+    fn from(value: AccountErasureRecord) -> Self {
+        Self {
+            id: value.id,
+            user_id: value.user_id,
+            email_hash: value.email_hash,
+            requested_at: value.requested_at,
+            completed_at: value.completed_at,
+            messages_purged: value.messages_purged.max(0) as u64,
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct SessionRecord {
+    id: Uuid,
+    user_id: Uuid,
+    device: Option<String>,
+    ip: Option<String>,
+    created_at: DateTime<Utc>,
+    last_seen_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+impl From<SessionRecord> for Session {
+    fn from(value: SessionRecord) -> Self {
+        Self {
+            id: value.id,
+            user_id: value.user_id,
+            device: value.device,
+            ip: value.ip,
+            created_at: value.created_at,
+            last_seen_at: value.last_seen_at,
+            expires_at: value.expires_at,
+        }
+    }
+}
+
+impl From<IdentityRecord> for UserIdentity {
+    fn from(value: IdentityRecord) -> Self {
+        Self {
+            id: value.id,
+            user_id: value.user_id,
+            provider: value.provider,
+            subject: value.subject,
+            created_at: value.created_at,
+        }
+    }
+}
+
+impl From<PasswordRecord> for PasswordCredential {
+    fn from(value: PasswordRecord) -> Self {
+        Self {
+            user_id: value.user_id,
+            password_hash: value.password_hash,
+            updated_at: value.updated_at,
+        }
+    }
+}
+
+impl From<IdempotencyKeyRecord> for IdempotencyRecord {
+    fn from(value: IdempotencyKeyRecord) -> Self {
+        Self {
+            organization_id: value.organization_id,
+            key: value.key,
+            request_hash: value.request_hash,
+            message_id: value.message_id,
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct OtpCodeRecord {
+    id: Uuid,
+    user_id: Uuid,
+    messenger: String,
+    recipient: String,
+    code_hash: String,
+    message_id: Uuid,
+    failed_attempts: i32,
+    expires_at: DateTime<Utc>,
+    verified_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<OtpCodeRecord> for OtpCode {
+    type Error = anyhow::Error;
+
+    fn try_from(value: OtpCodeRecord) -> Result<Self, Self::Error> {
+        let messenger = MessengerType::from_str(&value.messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
+        Ok(Self {
+            id: value.id,
+            user_id: value.user_id,
+            messenger,
+            recipient: value.recipient,
+            code_hash: value.code_hash,
+            message_id: value.message_id,
+            failed_attempts: value.failed_attempts as u32,
+            expires_at: value.expires_at,
+            verified_at: value.verified_at,
+            created_at: value.created_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct MessageHistoryRecord {
+    id: Uuid,
+    organization_id: Uuid,
+    user_id: Option<Uuid>,
+    messenger: String,
+    recipient: String,
+    body: String,
+    message_type: String,
+    status: String,
+    status_reason: Option<String>,
+    attempts: i32,
+    requested_by: String,
+    message_class: String,
+    priority: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    deadline: Option<DateTime<Utc>>,
+    scheduled_for: DateTime<Utc>,
+    token_id: Option<Uuid>,
+    tags: MessageTags,
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+/// `message_history.tags`: a `TEXT[]` on PostgreSQL and a JSON array on
+/// MySQL, which sqlx only reads as text.
+pub(super) struct MessageTags(Vec<String>);
+
+impl Type<Postgres> for MessageTags {
+    fn type_info() -> PgTypeInfo {
+        <Vec<String> as Type<Postgres>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for MessageTags {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        Ok(Self(<Vec<String> as Decode<Postgres>>::decode(value)?))
+    }
+}
+
+impl Type<MySql> for MessageTags {
+    fn type_info() -> MySqlTypeInfo {
+        <str as Type<MySql>>::type_info()
+    }
+
+    fn compatible(ty: &MySqlTypeInfo) -> bool {
+        ty.name() == "JSON" || <str as Type<MySql>>::compatible(ty)
+    }
+}
+
+impl<'r> Decode<'r, MySql> for MessageTags {
+    fn decode(value: MySqlValueRef<'r>) -> Result<Self, BoxDynError> {
+        let json = <&str as Decode<MySql>>::decode(value)?;
+        Ok(Self(serde_json::from_str(json)?))
+    }
+}
+
+impl TryFrom<MessageHistoryRecord> for MessageHistoryEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MessageHistoryRecord) -> Result<Self, Self::Error> {
+        let messenger = MessengerType::from_str(&value.messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
+        let content = MessageContent {
+            body: value.body,
+            message_type: str_to_message_type(&value.message_type)?,
+        };
+        let status =
+            message_status_from_fields(&value.status, value.status_reason, value.attempts)?;
+        let requested_by = str_to_requested_by(&value.requested_by)?;
+        let class = MessageClass::from_str(&value.message_class)
+            .ok_or_else(|| anyhow::anyhow!("unknown message class {}", value.message_class))?;
+        let priority = MessagePriority::from_str(&value.priority)
+            .ok_or_else(|| anyhow::anyhow!("unknown message priority {}", value.priority))?;
+
+        Ok(MessageHistoryEntry {
+            id: value.id,
+            organization_id: value.organization_id,
+            user_id: value.user_id,
+            messenger,
+            recipient: value.recipient,
+            content,
+            class,
+            priority,
+            status,
+            created_at: value.created_at,
+            updated_at: value.updated_at,
+            attempts: value.attempts as u32,
+            requested_by,
+            deadline: value.deadline,
+            scheduled_for: value.scheduled_for,
+            token_id: value.token_id,
+            tags: value.tags.0,
+            deleted_at: value.deleted_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct OrganizationRecord {
+    id: Uuid,
+    name: String,
+    created_at: DateTime<Utc>,
+}
+
+impl From<OrganizationRecord> for Organization {
+    fn from(value: OrganizationRecord) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            created_at: value.created_at,
+        }
+    }
+}
+
+/// An organization joined with the role a user has in it.
+#[derive(FromRow)]
+pub(super) struct MembershipRecord {
+    id: Uuid,
+    name: String,
+    created_at: DateTime<Utc>,
+    role: String,
+}
+
+impl TryFrom<MembershipRecord> for (Organization, MemberRole) {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MembershipRecord) -> Result<Self, Self::Error> {
+        let role = MemberRole::from_str(&value.role)
+            .ok_or_else(|| anyhow::anyhow!("unknown member role {}", value.role))?;
+        let organization = Organization {
+            id: value.id,
+            name: value.name,
+            created_at: value.created_at,
+        };
+        Ok((organization, role))
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct OrganizationMemberRecord {
+    organization_id: Uuid,
+    user_id: Uuid,
+    role: String,
+    created_at: DateTime<Utc>,
+}
+
+impl TryFrom<OrganizationMemberRecord> for OrganizationMember {
+    type Error = anyhow::Error;
+
+    fn try_from(value: OrganizationMemberRecord) -> Result<Self, Self::Error> {
+        let role = MemberRole::from_str(&value.role)
+            .ok_or_else(|| anyhow::anyhow!("unknown member role {}", value.role))?;
+        Ok(Self {
+            organization_id: value.organization_id,
+            user_id: value.user_id,
+            role,
+            created_at: value.created_at,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct OrganizationInvitationRecord {
+    id: Uuid,
+    organization_id: Uuid,
+    email: String,
+    role: String,
+    token_hash: String,
+    invited_by: Uuid,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    accepted_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<OrganizationInvitationRecord> for OrganizationInvitation {
+    type Error = anyhow::Error;
+
+    fn try_from(value: OrganizationInvitationRecord) -> Result<Self, Self::Error> {
+        let role = MemberRole::from_str(&value.role)
+            .ok_or_else(|| anyhow::anyhow!("unknown member role {}", value.role))?;
+        Ok(Self {
+            id: value.id,
+            organization_id: value.organization_id,
+            email: value.email,
+            role,
+            token_hash: value.token_hash,
+            invited_by: value.invited_by,
+            created_at: value.created_at,
+            expires_at: value.expires_at,
+            accepted_at: value.accepted_at,
+        })
+    }
+}
+
+/// Payload of a notification on the PostgreSQL status channel.
+#[derive(Deserialize)]
+pub(super) struct StatusNotificationRecord {
+    id: Uuid,
+    organization_id: Uuid,
+    status: String,
+    attempts: i32,
+}
+
+impl TryFrom<StatusNotificationRecord> for MessageStatusChange {
+    type Error = anyhow::Error;
+
+    fn try_from(value: StatusNotificationRecord) -> Result<Self, Self::Error> {
+        Ok(MessageStatusChange {
+            message_id: value.id,
+            organization_id: value.organization_id,
+            status: MessageStatusKind::from_str(&value.status)
+                .ok_or_else(|| anyhow::anyhow!("unknown message status {}", value.status))?,
+            attempts: value.attempts as u32,
+        })
+    }
+}
+
+/// Splits a selector into the optional `id` and `label` a token must match.
+pub(super) fn token_selector_fields(selector: &TokenSelector) -> (Option<Uuid>, Option<&str>) {
+    match selector {
+        TokenSelector::Latest => (None, None),
+        TokenSelector::Id(id) => (Some(*id), None),
+        TokenSelector::Label(label) => (None, Some(label.as_str())),
+    }
+}
+
+/// Escapes LIKE wildcards so a caller's search text matches literally.
+pub(super) fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+pub(super) fn token_status_to_str(status: MessengerTokenStatus) -> &'static str {
+    match status {
+        MessengerTokenStatus::Active => "active",
+        MessengerTokenStatus::Inactive => "inactive",
+    }
+}
+
+pub(super) fn consent_status_to_str(status: ConsentStatus) -> &'static str {
+    match status {
+        ConsentStatus::Pending => "pending",
+        ConsentStatus::Confirmed => "confirmed",
+        ConsentStatus::Revoked => "revoked",
+    }
+}
+
+pub(super) fn message_type_to_str(message_type: &MessageType) -> &'static str {
+    match message_type {
+        MessageType::PlainText => "plain_text",
+    }
+}
+
+pub(super) fn str_to_message_type(value: &str) -> anyhow::Result<MessageType> {
+    match value {
+        "plain_text" => Ok(MessageType::PlainText),
+        other => anyhow::bail!("unknown message type {other}"),
+    }
+}
+
+pub(super) fn requested_by_to_str(value: &RequestedBy) -> &'static str {
+    match value {
+        RequestedBy::System => "system",
+        RequestedBy::User => "user",
+    }
+}
+
+pub(super) fn str_to_requested_by(value: &str) -> anyhow::Result<RequestedBy> {
+    match value {
+        "system" => Ok(RequestedBy::System),
+        "user" => Ok(RequestedBy::User),
+        other => anyhow::bail!("unknown requested_by {other}"),
+    }
+}
+
+pub(super) fn requested_by_from_str(value: &str) -> anyhow::Result<RequestedBy> {
+    str_to_requested_by(value)
+}
+
+pub(super) fn message_status_to_fields(status: &MessageStatus) -> (&'static str, Option<String>) {
+    match status {
+        MessageStatus::Pending => ("pending", None),
+        MessageStatus::Scheduled => ("scheduled", None),
+        MessageStatus::InFlight => ("in_flight", None),
+        MessageStatus::Sent => ("sent", None),
+        MessageStatus::Retrying { reason, .. } => ("retrying", Some(reason.clone())),
+        MessageStatus::Failed { reason, .. } => ("failed", Some(reason.clone())),
+        MessageStatus::Cancelled => ("cancelled", None),
+        MessageStatus::Deferred { reason } => ("deferred", Some(reason.clone())),
+        MessageStatus::Suppressed { reason } => ("suppressed", Some(reason.clone())),
+    }
+}
+
+pub(super) fn message_status_from_fields(
+    status: &str,
+    reason: Option<String>,
+    attempts: i32,
+) -> anyhow::Result<MessageStatus> {
+    Ok(match status {
+        "pending" => MessageStatus::Pending,
+        "scheduled" => MessageStatus::Scheduled,
+        "in_flight" => MessageStatus::InFlight,
+        "sent" => MessageStatus::Sent,
+        "retrying" => MessageStatus::Retrying {
+            reason: reason.unwrap_or_else(|| "retrying".to_string()),
+            attempts: attempts as u32,
+        },
+        "failed" => MessageStatus::Failed {
+            reason: reason.unwrap_or_else(|| "failed".to_string()),
+            attempts: attempts as u32,
+        },
+        "cancelled" => MessageStatus::Cancelled,
+        "deferred" => MessageStatus::Deferred {
+            reason: reason.unwrap_or_else(|| "deferred".to_string()),
+        },
+        "suppressed" => MessageStatus::Suppressed {
+            reason: reason.unwrap_or_else(|| "suppressed".to_string()),
+        },
+        other => anyhow::bail!("unknown message status {other}"),
+    })
+}
+
+pub(super) fn message_status_from_str(
+    status: &str,
+    reason: Option<String>,
+) -> anyhow::Result<MessageStatus> {
+    // For attempts, we use 0 as default since we don't store attempts in message_attempts table
+    message_status_from_fields(status, reason, 0)
+}
+
+#[derive(FromRow)]
+pub(super) struct WorkerRecord {
+    id: Uuid,
+    hostname: String,
+    version: String,
+    bus: String,
+    started_at: DateTime<Utc>,
+    heartbeat_at: DateTime<Utc>,
+    in_flight: i64,
+    handled: i64,
+    failed: i64,
+}
+
+impl From<WorkerRecord> for Worker {
+    fn from(value: WorkerRecord) -> Self {
+        Self {
+            id: value.id,
+            hostname: value.hostname,
+            version: value.version,
+            bus: value.bus,
+            started_at: value.started_at,
+            heartbeat_at: value.heartbeat_at,
+            stats: WorkerStats {
+                in_flight: value.in_flight as u64,
+                handled: value.handled as u64,
+                failed: value.failed as u64,
+            },
+        }
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct BacklogRecord {
+    status: String,
+    count: i64,
+    oldest_due_at: Option<DateTime<Utc>>,
+}
+
+pub(super) fn backlog_from_records(records: Vec<BacklogRecord>) -> anyhow::Result<Backlog> {
+    let mut backlog = Backlog::default();
+    for record in records {
+        let kind = MessageStatusKind::from_str(&record.status)
+            .ok_or_else(|| anyhow::anyhow!("unknown message status {}", record.status))?;
+        backlog.by_status.push((kind, record.count as u64));
+        backlog.oldest_due_at = match (backlog.oldest_due_at, record.oldest_due_at) {
+            (Some(oldest), Some(due_at)) => Some(oldest.min(due_at)),
+            (oldest, due_at) => oldest.or(due_at),
+        };
+    }
+    Ok(backlog)
+}
+
+#[derive(FromRow)]
+pub(super) struct TokenHealthRecord {
+    messenger: String,
+    active: i64,
+    inactive: i64,
+    expired: i64,
+    expiring: i64,
+}
+
+impl TryFrom<TokenHealthRecord> for TokenHealth {
+    type Error = anyhow::Error;
+
+    fn try_from(value: TokenHealthRecord) -> Result<Self, Self::Error> {
+        let messenger = MessengerType::from_str(&value.messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
+        Ok(Self {
+            messenger,
+            active: value.active as u64,
+            inactive: value.inactive as u64,
+            expired: value.expired as u64,
+            expiring: value.expiring as u64,
+        })
+    }
+}
+
+#[derive(FromRow)]
+pub(super) struct DeliveryStatsRecord {
+    day: NaiveDate,
+    messenger: String,
+    total: i64,
+    sent: i64,
+    failed: i64,
+}
+
+impl TryFrom<DeliveryStatsRecord> for DeliveryStatsBucket {
+    type Error = anyhow::Error;
+
+    fn try_from(value: DeliveryStatsRecord) -> Result<Self, Self::Error> {
+        let messenger = MessengerType::from_str(&value.messenger)
+            .ok_or_else(|| anyhow::anyhow!("unknown messenger {}", value.messenger))?;
+        Ok(Self {
+            day: value.day,
+            messenger,
+            counts: DeliveryCounts {
+                total: value.total as u64,
+                sent: value.sent as u64,
+                failed: value.failed as u64,
+            },
+        })
+    }
+}
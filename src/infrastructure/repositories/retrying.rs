@@ -0,0 +1,512 @@
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    Connection, Database, Pool,
+    migrate::{Migrate, Migrator},
+};
+use tokio::sync::mpsc::Sender;
+use uuid::Uuid;
+
+use crate::{
+    application::services::database_stats::{DatabaseMonitor, DatabaseStats, PoolStats},
+    domain::{
+        models::{
+            AttemptOutcomes, Backlog, DeliveryStatsBucket, DispatchClaim, FailedMessageFilter,
+            MessageAttempt, MessageClass, MessageHistoryEntry, MessageHistoryFilter, MessageStatus,
+            MessageStatusKind, MessengerToken, MessengerType, NewMessageHistoryEntry, RequestedBy,
+            TokenHealth, TokenSelector,
+        },
+        repositories::{
+            DispatchGuardRepository, MessageHistoryRepository, MessengerTokenRepository,
+        },
+    },
+};
+
+/// How a failed call relates to the database state.
+enum Failure {
+    Permanent,
+    /// The statement was rolled back or never reached the database.
+    NotApplied,
+    /// The connection broke; the statement may have been committed.
+    Unknown,
+}
+
+fn classify(err: &anyhow::Error) -> Failure {
+    match err.downcast_ref::<sqlx::Error>() {
+        Some(sqlx::Error::PoolTimedOut) => Failure::NotApplied,
+        Some(sqlx::Error::Io(_)) => Failure::Unknown,
+        Some(sqlx::Error::Database(err)) => match err.code().as_deref() {
+            // Serialization failure and deadlock, on MySQL too.
+            Some("40001" | "40P01") => Failure::NotApplied,
+            // Connection exceptions and server shutdowns, e.g. a failover.
+            Some(code) if code.starts_with("08") || code.starts_with("57P") => Failure::Unknown,
+            _ => Failure::Permanent,
+        },
+        _ => Failure::Permanent,
+    }
+}
+
+#[derive(Default)]
+struct RetryCounters {
+    retries: AtomicU64,
+    pool_timeouts: AtomicU64,
+}
+
+/// Repeats repository calls that failed transiently, doubling the delay
+/// between tries.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    attempts: u32,
+    base_delay: Duration,
+    counters: Arc<RetryCounters>,
+}
+
+impl RetryPolicy {
+    /// `attempts` counts the first try; 1 disables retries.
+    pub fn new(attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            base_delay,
+            counters: Arc::default(),
+        }
+    }
+
+    /// Retries every transient failure; for calls that are safe to repeat.
+    async fn run<T, F, Fut>(&self, op: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.retry(true, op).await
+    }
+
+    /// Retries only failures that left nothing behind; for calls that would
+    /// insert twice when repeated.
+    async fn run_once<T, F, Fut>(&self, op: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        self.retry(false, op).await
+    }
+
+    async fn retry<T, F, Fut>(&self, repeatable: bool, mut op: F) -> anyhow::Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
+        let mut delay = self.base_delay;
+        let mut attempt = 1;
+        loop {
+            let err = match op().await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            if matches!(
+                err.downcast_ref::<sqlx::Error>(),
+                Some(sqlx::Error::PoolTimedOut)
+            ) {
+                self.counters.pool_timeouts.fetch_add(1, Ordering::Relaxed);
+            }
+            let retry = match classify(&err) {
+                Failure::Permanent => false,
+                Failure::NotApplied => true,
+                Failure::Unknown => repeatable,
+            };
+            if !retry || attempt >= self.attempts {
+                return Err(err);
+            }
+            self.counters.retries.fetch_add(1, Ordering::Relaxed);
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+}
+
+/// Reports the pools' occupancy and the retry policy's counters, and points
+/// the pools at rotated credentials.
+pub struct PoolMonitor<DB: Database> {
+    /// The primary comes first.
+    pools: Vec<(&'static str, Pool<DB>)>,
+    policy: RetryPolicy,
+    migrator: &'static Migrator,
+}
+
+impl<DB: Database> PoolMonitor<DB> {
+    pub fn new(
+        pools: Vec<(&'static str, Pool<DB>)>,
+        policy: RetryPolicy,
+        migrator: &'static Migrator,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            pools,
+            policy,
+            migrator,
+        })
+    }
+}
+
+#[async_trait]
+impl<DB: Database> DatabaseMonitor for PoolMonitor<DB>
+where
+    DB::Connection: Migrate,
+{
+    fn stats(&self) -> DatabaseStats {
+        DatabaseStats {
+            pools: self
+                .pools
+                .iter()
+                .map(|(name, pool)| PoolStats {
+                    name,
+                    size: pool.size(),
+                    idle: pool.num_idle() as u32,
+                    max_connections: pool.options().get_max_connections(),
+                })
+                .collect(),
+            retries: self.policy.counters.retries.load(Ordering::Relaxed),
+            pool_timeouts: self.policy.counters.pool_timeouts.load(Ordering::Relaxed),
+        }
+    }
+
+    async fn check(&self) -> anyhow::Result<()> {
+        for (index, (name, pool)) in self.pools.iter().enumerate() {
+            let mut conn = pool.acquire().await?;
+            conn.ping()
+                .await
+                .map_err(|err| anyhow::anyhow!("{name} database unreachable: {err}"))?;
+            if index > 0 {
+                continue;
+            }
+            let applied = conn.list_applied_migrations().await?;
+            let missing = self
+                .migrator
+                .iter()
+                .filter(|migration| !migration.migration_type.is_down_migration())
+                .find(|migration| !applied.iter().any(|a| a.version == migration.version));
+            if let Some(migration) = missing {
+                anyhow::bail!("migration {} not applied", migration.version);
+            }
+        }
+        Ok(())
+    }
+
+    fn set_urls(&self, url: &str, read_url: Option<&str>) -> anyhow::Result<()> {
+        for (name, pool) in &self.pools {
+            let url = match *name {
+                "primary" => url,
+                _ => read_url.unwrap_or(url),
+            };
+            pool.set_connect_options(url.parse()?);
+        }
+        Ok(())
+    }
+}
+
+pub struct RetryingMessengerTokenRepository {
+    inner: Arc<dyn MessengerTokenRepository>,
+    policy: RetryPolicy,
+}
+
+impl RetryingMessengerTokenRepository {
+    pub fn new(inner: Arc<dyn MessengerTokenRepository>, policy: RetryPolicy) -> Arc<Self> {
+        Arc::new(Self { inner, policy })
+    }
+}
+
+#[async_trait]
+impl MessengerTokenRepository for RetryingMessengerTokenRepository {
+    async fn upsert(&self, token: MessengerToken) -> anyhow::Result<MessengerToken> {
+        self.policy.run(|| self.inner.upsert(token.clone())).await
+    }
+
+    async fn find_active(
+        &self,
+        organization_id: &Uuid,
+        sender: &Uuid,
+        messenger: MessengerType,
+        selector: &TokenSelector,
+    ) -> anyhow::Result<Option<MessengerToken>> {
+        self.policy
+            .run(|| {
+                self.inner
+                    .find_active(organization_id, sender, messenger, selector)
+            })
+            .await
+    }
+
+    async fn list_by_organization(
+        &self,
+        organization_id: &Uuid,
+    ) -> anyhow::Result<Vec<MessengerToken>> {
+        self.policy
+            .run(|| self.inner.list_by_organization(organization_id))
+            .await
+    }
+
+    async fn health(
+        &self,
+        now: DateTime<Utc>,
+        expiring_before: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<TokenHealth>> {
+        self.policy
+            .run(|| self.inner.health(now, expiring_before))
+            .await
+    }
+}
+
+pub struct RetryingMessageHistoryRepository {
+    inner: Arc<dyn MessageHistoryRepository>,
+    policy: RetryPolicy,
+}
+
+impl RetryingMessageHistoryRepository {
+    pub fn new(inner: Arc<dyn MessageHistoryRepository>, policy: RetryPolicy) -> Arc<Self> {
+        Arc::new(Self { inner, policy })
+    }
+}
+
+#[async_trait]
+impl MessageHistoryRepository for RetryingMessageHistoryRepository {
+    async fn insert(&self, entry: NewMessageHistoryEntry) -> anyhow::Result<MessageHistoryEntry> {
+        self.policy
+            .run_once(|| self.inner.insert(entry.clone()))
+            .await
+    }
+
+    async fn update_status(
+        &self,
+        message_id: Uuid,
+        status: MessageStatus,
+        attempts: u32,
+    ) -> anyhow::Result<()> {
+        self.policy
+            .run(|| {
+                self.inner
+                    .update_status(message_id, status.clone(), attempts)
+            })
+            .await
+    }
+
+    async fn get(&self, message_id: Uuid) -> anyhow::Result<Option<MessageHistoryEntry>> {
+        self.policy.run(|| self.inner.get(message_id)).await
+    }
+
+    async fn scheduled_times_since(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        class: MessageClass,
+        since: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DateTime<Utc>>> {
+        self.policy
+            .run(|| {
+                self.inner.scheduled_times_since(
+                    organization_id,
+                    messenger,
+                    recipient,
+                    class,
+                    since,
+                )
+            })
+            .await
+    }
+
+    async fn list_by_organization(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)> {
+        self.policy
+            .run(|| {
+                self.inner
+                    .list_by_organization(organization_id, filter, limit, offset)
+            })
+            .await
+    }
+
+    async fn export(
+        &self,
+        organization_id: Uuid,
+        filter: &MessageHistoryFilter,
+        sink: Sender<anyhow::Result<MessageHistoryEntry>>,
+    ) -> anyhow::Result<()> {
+        // Rows already handed to the sink cannot be taken back, so a broken
+        // export is reported rather than started over.
+        self.inner.export(organization_id, filter, sink).await
+    }
+
+    async fn list_by_recipient(
+        &self,
+        organization_id: Uuid,
+        messenger: MessengerType,
+        recipient: &str,
+        limit: Option<u32>,
+        offset: Option<u32>,
+    ) -> anyhow::Result<(Vec<MessageHistoryEntry>, bool)> {
+        self.policy
+            .run(|| {
+                self.inner
+                    .list_by_recipient(organization_id, messenger, recipient, limit, offset)
+            })
+            .await
+    }
+
+    async fn count_by_status(
+        &self,
+        organization_id: Uuid,
+    ) -> anyhow::Result<Vec<(MessageStatusKind, u64)>> {
+        self.policy
+            .run(|| self.inner.count_by_status(organization_id))
+            .await
+    }
+
+    async fn backlog(&self, now: DateTime<Utc>) -> anyhow::Result<Backlog> {
+        self.policy.run(|| self.inner.backlog(now)).await
+    }
+
+    async fn attempt_outcomes(&self, since: DateTime<Utc>) -> anyhow::Result<AttemptOutcomes> {
+        self.policy.run(|| self.inner.attempt_outcomes(since)).await
+    }
+
+    async fn delivery_stats(
+        &self,
+        organization_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>> {
+        self.policy
+            .run(|| self.inner.delivery_stats(organization_id, from, to))
+            .await
+    }
+
+    async fn sender_delivery_stats(
+        &self,
+        user_id: Uuid,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<DeliveryStatsBucket>> {
+        self.policy
+            .run(|| self.inner.sender_delivery_stats(user_id, from, to))
+            .await
+    }
+
+    async fn list_failed(
+        &self,
+        filter: &FailedMessageFilter,
+        limit: u32,
+    ) -> anyhow::Result<Vec<MessageHistoryEntry>> {
+        self.policy
+            .run(|| self.inner.list_failed(filter, limit))
+            .await
+    }
+
+    async fn log_attempt(
+        &self,
+        message_id: Uuid,
+        attempt_number: u32,
+        status: MessageStatus,
+        requested_by: RequestedBy,
+    ) -> anyhow::Result<()> {
+        self.policy
+            .run_once(|| {
+                self.inner.log_attempt(
+                    message_id,
+                    attempt_number,
+                    status.clone(),
+                    requested_by.clone(),
+                )
+            })
+            .await
+    }
+
+    async fn record_attempt(
+        &self,
+        message_id: Uuid,
+        status: MessageStatus,
+        attempt_number: u32,
+        requested_by: RequestedBy,
+    ) -> anyhow::Result<()> {
+        self.policy
+            .run_once(|| {
+                self.inner.record_attempt(
+                    message_id,
+                    status.clone(),
+                    attempt_number,
+                    requested_by.clone(),
+                )
+            })
+            .await
+    }
+
+    async fn get_attempts(&self, message_id: Uuid) -> anyhow::Result<Vec<MessageAttempt>> {
+        self.policy
+            .run(|| self.inner.get_attempts(message_id))
+            .await
+    }
+
+    async fn redact(&self, message_id: Uuid) -> anyhow::Result<()> {
+        self.policy.run(|| self.inner.redact(message_id)).await
+    }
+
+    async fn soft_delete(&self, message_id: Uuid) -> anyhow::Result<bool> {
+        // A repeat after a lost reply would report the message as already
+        // deleted.
+        self.policy
+            .run_once(|| self.inner.soft_delete(message_id))
+            .await
+    }
+
+    async fn purge(&self, message_id: Uuid) -> anyhow::Result<bool> {
+        self.policy.run_once(|| self.inner.purge(message_id)).await
+    }
+}
+
+pub struct RetryingDispatchGuardRepository {
+    inner: Arc<dyn DispatchGuardRepository>,
+    policy: RetryPolicy,
+}
+
+impl RetryingDispatchGuardRepository {
+    pub fn new(inner: Arc<dyn DispatchGuardRepository>, policy: RetryPolicy) -> Arc<Self> {
+        Arc::new(Self { inner, policy })
+    }
+}
+
+#[async_trait]
+impl DispatchGuardRepository for RetryingDispatchGuardRepository {
+    async fn claim(
+        &self,
+        message_id: Uuid,
+        attempt: u32,
+        worker_id: Uuid,
+    ) -> anyhow::Result<DispatchClaim> {
+        // A claim that may have been taken would read as this worker's own
+        // interrupted delivery, so only claims known not to exist are repeated.
+        self.policy
+            .run_once(|| self.inner.claim(message_id, attempt, worker_id))
+            .await
+    }
+
+    async fn mark_sent(&self, message_id: Uuid, attempt: u32) -> anyhow::Result<()> {
+        self.policy
+            .run(|| self.inner.mark_sent(message_id, attempt))
+            .await
+    }
+
+    async fn release(&self, message_id: Uuid) -> anyhow::Result<()> {
+        self.policy.run(|| self.inner.release(message_id)).await
+    }
+}
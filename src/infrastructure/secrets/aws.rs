@@ -0,0 +1,42 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_secretsmanager::Client;
+use serde_json::Value;
+
+use crate::{application::services::secrets::SecretStore, infrastructure::secrets::secret_fields};
+
+/// Reads a JSON key/value secret from AWS Secrets Manager, which keeps it
+/// encrypted under the secret's KMS key.
+pub struct AwsSecretStore {
+    client: Client,
+    secret_id: String,
+}
+
+impl AwsSecretStore {
+    /// Credentials and region come from the standard AWS environment chain.
+    pub async fn new(secret_id: &str) -> Self {
+        let shared = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        Self {
+            client: Client::new(&shared),
+            secret_id: secret_id.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretStore for AwsSecretStore {
+    async fn fetch(&self) -> anyhow::Result<HashMap<String, String>> {
+        let output = self
+            .client
+            .get_secret_value()
+            .secret_id(&self.secret_id)
+            .send()
+            .await?;
+        let secret = output
+            .secret_string()
+            .ok_or_else(|| anyhow::anyhow!("secret {} has no string value", self.secret_id))?;
+        secret_fields(&serde_json::from_str::<Value>(secret)?)
+    }
+}
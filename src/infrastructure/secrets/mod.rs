@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+pub mod aws;
+pub mod vault;
+
+/// Turns a JSON object of fields into config values; non-string fields keep
+/// their JSON form, so ports and counts can be stored as numbers.
+fn secret_fields(data: &Value) -> anyhow::Result<HashMap<String, String>> {
+    let Value::Object(fields) = data else {
+        anyhow::bail!("secret is not a JSON object");
+    };
+
+    Ok(fields
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+            (name.clone(), value)
+        })
+        .collect())
+}
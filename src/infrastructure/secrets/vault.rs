@@ -0,0 +1,51 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::Value;
+
+use crate::{application::services::secrets::SecretStore, infrastructure::secrets::secret_fields};
+
+/// Reads one secret from a HashiCorp Vault KV engine, e.g. `secret/data/messaging`
+/// for KV v2 or `secret/messaging` for KV v1. Each field is a config value.
+pub struct VaultSecretStore {
+    client: Client,
+    url: String,
+    token: String,
+}
+
+impl VaultSecretStore {
+    pub fn new(addr: &str, token: &str, path: &str) -> Self {
+        Self {
+            client: Client::new(),
+            url: format!(
+                "{}/v1/{}",
+                addr.trim_end_matches('/'),
+                path.trim_start_matches('/')
+            ),
+            token: token.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SecretStore for VaultSecretStore {
+    async fn fetch(&self) -> anyhow::Result<HashMap<String, String>> {
+        let body: Value = self
+            .client
+            .get(&self.url)
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        // KV v2 nests the fields one level deeper than v1.
+        let data = body
+            .pointer("/data/data")
+            .filter(|data| data.is_object())
+            .unwrap_or(&body["data"]);
+        secret_fields(data)
+    }
+}
@@ -0,0 +1,21 @@
+use tracing_subscriber::{EnvFilter, fmt};
+
+use crate::config::LoggingConfig;
+
+/// Installs the global log subscriber. JSON lines carry the fields of the
+/// spans they were written under, so a dispatcher line names its message
+/// and a request line its request id.
+pub fn install_logging(config: &LoggingConfig) -> anyhow::Result<()> {
+    let filter = EnvFilter::try_new(&config.filter)?;
+    let builder = fmt().with_env_filter(filter);
+    let result = match config.format.as_str() {
+        "json" => builder
+            .json()
+            .with_current_span(true)
+            .with_span_list(false)
+            .try_init(),
+        "text" => builder.try_init(),
+        _ => anyhow::bail!("invalid LOG_FORMAT"),
+    };
+    result.map_err(|err| anyhow::anyhow!(err))
+}
@@ -0,0 +1,21 @@
+use opentelemetry::{KeyValue, global};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{Resource, runtime, trace::TracerProvider};
+
+/// Exports every span over OTLP gRPC in batches. The returned provider must
+/// be shut down on exit to flush the last batch.
+pub fn install_otlp_tracer(endpoint: &str, service_name: &str) -> anyhow::Result<TracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()?;
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(Resource::new([KeyValue::new(
+            "service.name",
+            service_name.to_string(),
+        )]))
+        .build();
+    global::set_tracer_provider(provider.clone());
+    Ok(provider)
+}
@@ -1,50 +1,135 @@
+use std::collections::HashMap;
+use std::future::pending;
 use std::io::Error;
 use std::sync::Arc;
 use std::time::Duration;
 
+use opentelemetry::global;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
 use poem::{
-    EndpointExt, Route, Server,
+    EndpointExt, IntoEndpoint, Route, Server, get,
     listener::TcpListener,
     middleware::{CookieJarManager, Cors},
 };
 use poem_openapi::OpenApiService;
-use tokio::main;
+use tokio::{main, task::JoinHandle};
 
 use crate::{
     application::{
         handlers::message_dispatcher::MessageDispatchHandler,
-        services::{event_bus::MessageBus, jwt::JwtServiceConfig, messenger::MessengerGateway},
+        services::{
+            account_eraser::AccountEraser,
+            auth_provider::AuthProvider,
+            content_score::{BannedPhrase, ContentScorer},
+            database_stats::DatabaseMonitor,
+            dead_letter::DeadLetterQueue,
+            delivery_semantics::DeliverySemantics,
+            event_bus::MessageBus,
+            frequency_cap::{FrequencyCap, FrequencyCapMode},
+            history_retention::HistoryRetention,
+            jwt::{JwtKeySet, JwtKeys, JwtServiceConfig},
+            login_throttle::{LockoutPolicy, LoginAttemptStore, LoginThrottle},
+            mailer::Mailer,
+            message_length::parse_part_price,
+            messenger::MessengerGateway,
+            public_id::{Base62Codec, PublicIdCodec, UuidCodec},
+            queue_stats::QueueMonitor,
+            rate_limit::{RateLimit, RateLimitStore, WarmUp},
+            readiness::ReadinessProbe,
+            secrets::{SecretStore, SecretsOnChange, watch_secrets},
+            session_revocations::SessionRevocations,
+            status_feed::StatusFeed,
+            status_token::StatusTokenService,
+            token_refresh::TokenRefresher,
+            worker_registry::{DispatchCounters, WorkerHeartbeat, worker_ttl},
+        },
         usecases::{
-            authenticate_user::AuthenticateUserUseCase,
+            authenticate_user::{AuthRepositories, AuthenticateUserUseCase},
+            delete_message::DeleteMessageUseCase,
+            erase_account::AccountErasureUseCase,
+            get_delivery_stats::GetDeliveryStatsUseCase,
             get_message::GetMessageUseCase,
             get_message_attempts::GetMessageAttemptsUseCase,
+            get_messenger_health::GetMessengerHealthUseCase,
+            get_overview::GetOverviewUseCase,
+            get_queue_stats::GetQueueStatsUseCase,
+            get_related_messages::GetRelatedMessagesUseCase,
+            ingest_event::IngestEventUseCase,
             list_chats::ListChatsUseCase,
             list_messages::ListMessagesUseCase,
             list_tokens::ListTokensUseCase,
+            list_workers::ListWorkersUseCase,
+            manage_api_keys::ApiKeyUseCase,
+            manage_consent::{ConsentConfig, ConsentUseCase},
+            manage_dead_letters::{DeadLetterConfig, DeadLetterUseCase},
+            manage_identities::IdentityUseCase,
+            manage_organizations::OrganizationUseCase,
+            manage_passwords::{PasswordConfig, PasswordUseCase},
+            manage_roles::RoleUseCase,
+            manage_routing_rules::RoutingRuleUseCase,
+            manage_sessions::SessionUseCase,
+            manage_users::UserAdminUseCase,
+            message_status_link::{MessageStatusLinkUseCase, StatusExposure},
+            preview_message::PreviewMessageUseCase,
             register_token::RegisterTokenUseCase,
             retry_message::{RetryMessageConfig, RetryMessageUseCase},
-            schedule_message::{ScheduleMessageConfig, ScheduleMessageUseCase},
+            schedule_message::{
+                ScheduleMessageConfig, ScheduleMessageRepositories, ScheduleMessageUseCase,
+            },
+            send_chat_action::SendChatActionUseCase,
+            send_magic_link::MagicLinkUseCase,
+            send_otp::{SendOtpConfig, SendOtpUseCase},
+            share_token::ShareTokenUseCase,
+            trace_replay::TraceReplayUseCase,
+            verify_otp::{VerifyOtpConfig, VerifyOtpUseCase},
         },
     },
-    config::Config,
-    domain::repositories::{MessageHistoryRepository, MessengerTokenRepository, UserRepository},
+    config::{Config, LoggingConfig, SecretsConfig},
+    domain::models::RetryBackoff,
     infrastructure::{
+        auth::{
+            email::EmailAuthProvider,
+            jwt_keys::load_jwt_key,
+            magic_link::MagicLinkAuthProvider,
+            oauth::{OAuthAuthProvider, OAuthConfig, OAuthPlatform},
+            oidc::{OidcAuthProvider, OidcConfig},
+            password::PasswordAuthProvider,
+        },
+        login_throttle::{memory::InMemoryLoginAttemptStore, redis::RedisLoginAttemptStore},
+        mail::smtp::SmtpMailer,
         messaging::{
-            jetstream::{JetstreamBus, JetstreamConfig},
+            http::HttpPoolConfig,
+            jetstream::{ConsumerLimits, JetstreamBus, JetstreamConfig},
+            memory::InMemoryBus,
+            rabbitmq::{RabbitMqBus, RabbitMqConfig},
+            sqs::{SqsBus, SqsConfig},
             telegram::TelegramClient,
             vk::VkClient,
         },
-        repositories::postgres::{
-            PostgresMessageHistoryRepository, PostgresMessengerTokenRepository,
-            PostgresUserRepository,
+        rate_limit::{
+            memory::InMemoryRateLimitStore, nats::NatsRateLimitStore, redis::RedisRateLimitStore,
         },
+        repositories::{self, Repositories, retrying::RetryPolicy},
+        secrets::{aws::AwsSecretStore, vault::VaultSecretStore},
+        telemetry::{logging::install_logging, otlp::install_otlp_tracer},
     },
-    presentation::http::endpoints::{
-        auth::AuthEndpoints, chats::ChatsEndpoints, health::HealthEndpoints,
-        messages::MessagesEndpoints, root::ApiState, tokens::TokensEndpoints,
+    presentation::http::{
+        client_ip::ClientIpResolver,
+        csrf::{CsrfMode, CsrfProtection},
+        endpoints::{
+            admin::AdminEndpoints, api_keys::ApiKeysEndpoints, auth::AuthEndpoints,
+            chats::ChatsEndpoints, consents::ConsentsEndpoints, dead_letters::DeadLettersEndpoints,
+            events::EventsEndpoints, health::HealthEndpoints, messages::MessagesEndpoints,
+            organizations::OrganizationsEndpoints, otp::OtpEndpoints,
+            public_status::PublicStatusEndpoints, root::ApiState,
+            routing_rules::RoutingRulesEndpoints, stats::StatsEndpoints, tokens::TokensEndpoints,
+        },
+        jwks::jwks,
+        rate_limit::RateLimiter,
+        request_id::{REQUEST_ID_HEADER, RequestId},
+        telemetry::RequestTracing,
     },
 };
-use sqlx::postgres::PgPoolOptions;
 
 mod application;
 mod config;
@@ -54,71 +139,461 @@ mod presentation;
 
 #[main]
 async fn main() -> Result<(), Error> {
-    let config = Config::try_parse().map_err(Error::other)?;
+    install_logging(&LoggingConfig::parse()).map_err(Error::other)?;
+    let secrets_config = SecretsConfig::try_parse().map_err(Error::other)?;
+    let secrets_on_change =
+        SecretsOnChange::parse(&secrets_config.on_change).map_err(Error::other)?;
+    let secret_store: Option<Arc<dyn SecretStore>> = match secrets_config.backend.as_str() {
+        "vault" => Some(Arc::new(VaultSecretStore::new(
+            &secrets_config.vault_addr,
+            &secrets_config.vault_token,
+            &secrets_config.vault_secret_path,
+        ))),
+        "aws" => Some(Arc::new(
+            AwsSecretStore::new(&secrets_config.aws_secret_id).await,
+        )),
+        _ => None,
+    };
+    let secrets = match &secret_store {
+        Some(store) => store.fetch().await.map_err(Error::other)?,
+        None => HashMap::new(),
+    };
+    let config = Config::try_parse(&secrets).map_err(Error::other)?;
 
-    let pool = PgPoolOptions::new()
-        .max_connections(config.database_max_connections)
-        .connect(&config.database_url)
-        .await
-        .map_err(Error::other)?;
-    sqlx::migrate!("./migrations")
-        .run(&pool)
-        .await
+    // Trace context is passed on even when this process exports nothing, so
+    // traces stay whole across services that do.
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let tracer_provider = config
+        .otlp_endpoint
+        .as_deref()
+        .map(|endpoint| install_otlp_tracer(endpoint, &config.otel_service_name))
+        .transpose()
         .map_err(Error::other)?;
 
     // infrastructure
-    let user_repo: Arc<dyn UserRepository> = PostgresUserRepository::new(pool.clone());
-    let token_repo: Arc<dyn MessengerTokenRepository> =
-        PostgresMessengerTokenRepository::new(pool.clone());
-    let history_repo: Arc<dyn MessageHistoryRepository> =
-        PostgresMessageHistoryRepository::new(pool.clone());
+    let Repositories {
+        users: user_repo,
+        tokens: token_repo,
+        history: history_repo,
+        otp: otp_repo,
+        consents: consent_repo,
+        links: link_repo,
+        idempotency: idempotency_repo,
+        guards: guard_repo,
+        passwords: password_repo,
+        login_tokens: login_token_repo,
+        erasures: erasure_repo,
+        identities: identity_repo,
+        api_keys: api_key_repo,
+        refresh_tokens: refresh_token_repo,
+        sessions: session_repo,
+        organizations: organization_repo,
+        routing: routing_repo,
+        workers: worker_repo,
+        history_partitions,
+        database: database_monitor,
+        status_listener,
+    } = repositories::connect(
+        &config.database_url,
+        config.database_read_url.as_deref(),
+        config.database_max_connections,
+        RetryPolicy::new(
+            config.database_retry_attempts,
+            Duration::from_millis(config.database_retry_base_ms),
+        ),
+    )
+    .await
+    .map_err(Error::other)?;
+
+    let history_retention = HistoryRetention::new(
+        history_partitions,
+        config.history_partitions_ahead,
+        config.history_retention_months,
+    );
+    history_retention.run_once().await.map_err(Error::other)?;
+    history_retention.spawn();
 
-    let messenger_gateway = MessengerGateway::new(vec![TelegramClient::new(), VkClient::new()]);
+    let status_feed = StatusFeed::new();
+    if let Some(status_listener) = status_listener {
+        status_listener.spawn(status_feed.clone());
+    }
 
+    let server_url = format!("{}://{}:{}", config.scheme, config.host, config.port);
+    let oauth_callback_base_url = config
+        .oauth_callback_base_url
+        .clone()
+        .unwrap_or_else(|| format!("{server_url}/api"));
+    let mut auth_providers: Vec<Arc<dyn AuthProvider>> = Vec::new();
+    for name in &config.auth_providers {
+        auth_providers.push(match name.as_str() {
+            "email" => Arc::new(EmailAuthProvider),
+            "password" => Arc::new(PasswordAuthProvider::new(
+                user_repo.clone(),
+                password_repo.clone(),
+            )),
+            "magic_link" => {
+                if config.smtp_url.is_none() {
+                    return Err(Error::other(
+                        "SMTP_URL is required for the magic_link auth provider",
+                    ));
+                }
+                Arc::new(MagicLinkAuthProvider::new(login_token_repo.clone()))
+            }
+            "oidc" => {
+                if config.oidc_issuer.is_empty() || config.oidc_audience.is_empty() {
+                    return Err(Error::other(
+                        "OIDC_ISSUER and OIDC_AUDIENCE are required for the oidc auth provider",
+                    ));
+                }
+                Arc::new(OidcAuthProvider::new(OidcConfig {
+                    issuer: config.oidc_issuer.clone(),
+                    audience: config.oidc_audience.clone(),
+                    jwks_url: config.oidc_jwks_url.clone(),
+                }))
+            }
+            "google" | "github" => {
+                let (platform, prefix, client_id, client_secret) = match name.as_str() {
+                    "google" => (
+                        OAuthPlatform::Google,
+                        "GOOGLE",
+                        &config.google_client_id,
+                        &config.google_client_secret,
+                    ),
+                    _ => (
+                        OAuthPlatform::GitHub,
+                        "GITHUB",
+                        &config.github_client_id,
+                        &config.github_client_secret,
+                    ),
+                };
+                if client_id.is_empty() || client_secret.is_empty() {
+                    return Err(Error::other(format!(
+                        "{prefix}_CLIENT_ID and {prefix}_CLIENT_SECRET are required for the \
+                         {name} auth provider"
+                    )));
+                }
+                Arc::new(OAuthAuthProvider::new(
+                    platform,
+                    OAuthConfig {
+                        client_id: client_id.clone(),
+                        client_secret: client_secret.clone(),
+                        redirect_uri: format!(
+                            "{}/auth/oauth/{name}/callback",
+                            oauth_callback_base_url.trim_end_matches('/')
+                        ),
+                    },
+                ))
+            }
+            other => {
+                return Err(Error::other(format!("unknown auth provider: {other}")));
+            }
+        });
+    }
+    if auth_providers.is_empty() {
+        auth_providers.push(Arc::new(PasswordAuthProvider::new(
+            user_repo.clone(),
+            password_repo.clone(),
+        )));
+    }
+    let password_enabled = auth_providers
+        .iter()
+        .any(|provider| provider.name() == "password");
+    let magic_link_enabled = auth_providers
+        .iter()
+        .any(|provider| provider.name() == "magic_link");
+    let mailer = config
+        .smtp_url
+        .as_deref()
+        .map(|url| SmtpMailer::new(url, &config.mail_from))
+        .transpose()
+        .map_err(Error::other)?
+        .map(|mailer| Arc::new(mailer) as Arc<dyn Mailer>);
+
+    let http_pool = HttpPoolConfig {
+        max_idle_per_host: config.messenger_pool_max_idle,
+        idle_timeout: Duration::from_secs(config.messenger_pool_idle_timeout_seconds),
+        keepalive: Duration::from_secs(config.messenger_keepalive_seconds),
+    };
+    let messenger_gateway = MessengerGateway::new(vec![
+        TelegramClient::new(&http_pool),
+        VkClient::new(config.vk_id_client_id.clone(), &http_pool),
+    ]);
+    let token_refresher = Arc::new(TokenRefresher::new(
+        token_repo.clone(),
+        messenger_gateway.clone(),
+    ));
+
+    let jwt_keys = JwtKeys::new(jwt_key_set(&config).map_err(Error::other)?);
     let jwt_config = JwtServiceConfig {
-        secret: config.jwt_secret.clone(),
+        keys: jwt_keys.clone(),
         expiration: Duration::from_secs(config.jwt_ttl_seconds),
         refresh_expiration: Duration::from_secs(config.jwt_refresh_ttl_seconds),
+        issuer: config.jwt_issuer.clone(),
+        audience: config.jwt_audience.clone(),
     };
 
+    let session_revocations = SessionRevocations::new(session_repo.clone(), jwt_config.expiration);
+    session_revocations.reload().await.map_err(Error::other)?;
+    session_revocations.clone().spawn();
+
+    let retry_backoff = RetryBackoff::new(
+        config.retry_backoff_base_ms,
+        config.retry_backoff_multiplier,
+        config.retry_backoff_jitter,
+        config.retry_backoff_max_ms,
+    )
+    .map_err(Error::other)?;
+
+    // Refuse to start when attempts, redelivery limits and retry delays
+    // contradict each other.
+    let delivery_semantics = DeliverySemantics::new(
+        &config.message_bus,
+        config.system_retry_limit,
+        config.nats_max_deliver,
+        config.nats_ack_wait_seconds,
+        retry_backoff,
+    );
+    delivery_semantics.validate().map_err(Error::other)?;
+
     let schedule_config = ScheduleMessageConfig {
         max_attempts: config.system_retry_limit,
+        frequency_caps: config
+            .frequency_caps
+            .iter()
+            .map(|cap| FrequencyCap::parse(cap))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(Error::other)?,
+        frequency_cap_mode: FrequencyCapMode::parse(&config.frequency_cap_mode)
+            .map_err(Error::other)?,
+        retry_backoff,
+        sync_timeout: Duration::from_millis(config.sync_delivery_timeout_ms),
     };
 
-    let (bus_impl, worker) = JetstreamBus::new(&JetstreamConfig {
-        url: config.nats_url.clone(),
-        stream: config.nats_stream.clone(),
-        subject: config.nats_subject.clone(),
-        durable: config.nats_durable.clone(),
-        pull_batch: config.nats_pull_batch,
-        ack_wait_seconds: config.nats_ack_wait_seconds,
-        max_deliver: config.nats_max_deliver,
-    })
-    .await
-    .map_err(Error::other)?;
+    if config.worker_heartbeat_seconds == 0 {
+        return Err(Error::other("WORKER_HEARTBEAT_SECONDS must be positive"));
+    }
+    let worker_heartbeat_interval = Duration::from_secs(config.worker_heartbeat_seconds);
+    let dispatch_counters = Arc::new(DispatchCounters::default());
+    let worker_heartbeat = WorkerHeartbeat::new(
+        worker_repo.clone(),
+        config.worker_hostname.clone(),
+        env!("CARGO_PKG_VERSION").to_string(),
+        config.message_bus.clone(),
+        dispatch_counters.clone(),
+        worker_heartbeat_interval,
+    );
+    worker_heartbeat.beat().await.map_err(Error::other)?;
+    let mut workers = vec![worker_heartbeat.clone().spawn()];
 
-    let bus: Arc<dyn MessageBus> = bus_impl.clone();
+    let dispatcher = Arc::new(MessageDispatchHandler::new(
+        token_repo.clone(),
+        history_repo.clone(),
+        guard_repo,
+        messenger_gateway.clone(),
+        token_refresher,
+        worker_heartbeat,
+        (config.typing_indicator_threshold_ms > 0)
+            .then(|| Duration::from_millis(config.typing_indicator_threshold_ms)),
+    ));
+
+    let MessageBusSetup {
+        bus,
+        dead_letter_queue,
+        queue_monitor,
+        workers: bus_workers,
+    } = match config.message_bus.as_str() {
+        "rabbitmq" => {
+            let (bus, worker) = RabbitMqBus::new(&RabbitMqConfig {
+                url: config.rabbitmq_url.clone(),
+                exchange: config.rabbitmq_exchange.clone(),
+                queue: config.rabbitmq_queue.clone(),
+                prefetch: config.rabbitmq_prefetch,
+            })
+            .await
+            .map_err(Error::other)?;
+            MessageBusSetup {
+                workers: vec![worker.spawn(dispatcher.clone(), bus.clone())],
+                queue_monitor: Some(bus.queue_monitor()),
+                dead_letter_queue: None,
+                bus,
+            }
+        }
+        "memory" => {
+            let (bus, worker) = InMemoryBus::new();
+            MessageBusSetup {
+                workers: vec![worker.spawn(dispatcher.clone(), bus.clone())],
+                queue_monitor: None,
+                dead_letter_queue: None,
+                bus,
+            }
+        }
+        "sqs" => {
+            let (bus, worker) = SqsBus::new(&SqsConfig {
+                queue_url: config.sqs_queue_url.clone(),
+                dead_letter_queue_url: config.sqs_dead_letter_queue_url.clone(),
+                max_messages: config.sqs_max_messages,
+                wait_time_seconds: config.sqs_wait_time_seconds,
+            })
+            .await
+            .map_err(Error::other)?;
+            MessageBusSetup {
+                workers: vec![worker.spawn(dispatcher.clone(), bus.clone())],
+                queue_monitor: Some(bus.queue_monitor()),
+                dead_letter_queue: None,
+                bus,
+            }
+        }
+        _ => {
+            let (bus, worker) = JetstreamBus::new(&JetstreamConfig {
+                url: config.nats_url.clone(),
+                stream: config.nats_stream.clone(),
+                subject: config.nats_subject.clone(),
+                durable: config.nats_durable.clone(),
+                limits: ConsumerLimits {
+                    pull_batch: config.nats_pull_batch,
+                    concurrency: config.nats_concurrency,
+                },
+                messenger_limits: config
+                    .nats_messenger_limits
+                    .iter()
+                    .map(|limits| ConsumerLimits::parse(limits))
+                    .collect::<anyhow::Result<_>>()
+                    .map_err(Error::other)?,
+                ack_wait_seconds: config.nats_ack_wait_seconds,
+                max_deliver: config.nats_max_deliver,
+                duplicate_window: Duration::from_secs(config.nats_duplicate_window_seconds),
+                dead_letter_stream: config.nats_dead_letter_stream.clone(),
+                dead_letter_subject: config.nats_dead_letter_subject.clone(),
+                quarantine_stream: config.nats_quarantine_stream.clone(),
+                quarantine_subject: config.nats_quarantine_subject.clone(),
+            })
+            .await
+            .map_err(Error::other)?;
+            MessageBusSetup {
+                workers: worker.spawn(dispatcher.clone(), bus.clone()),
+                queue_monitor: Some(bus.queue_monitor()),
+                dead_letter_queue: Some(bus.dead_letter_queue()),
+                bus,
+            }
+        }
+    };
+    workers.extend(bus_workers);
 
     // use-cases
+    let login_attempt_store: Arc<dyn LoginAttemptStore> = match config.rate_limit_store.as_str() {
+        "redis" => Arc::new(
+            RedisLoginAttemptStore::new(&config.redis_url, "login_throttle")
+                .await
+                .map_err(Error::other)?,
+        ),
+        _ => Arc::new(InMemoryLoginAttemptStore::default()),
+    };
+    let lockout = |after| LockoutPolicy {
+        after,
+        base: Duration::from_secs(config.login_lockout_base_seconds),
+        max: Duration::from_secs(config.login_lockout_max_seconds),
+    };
+    let login_throttle = LoginThrottle::new(
+        login_attempt_store,
+        lockout(config.login_lockout_email_after),
+        lockout(config.login_lockout_ip_after),
+    );
     let auth_usecase = Arc::new(AuthenticateUserUseCase::new(
-        user_repo.clone(),
+        AuthRepositories {
+            users: user_repo.clone(),
+            identities: identity_repo.clone(),
+            refresh_tokens: refresh_token_repo,
+            sessions: session_repo.clone(),
+        },
+        session_revocations.clone(),
+        auth_providers.clone(),
+        login_throttle,
         jwt_config.clone(),
+        config.admin_emails.clone(),
+    ));
+    let session_usecase = Arc::new(SessionUseCase::new(
+        session_repo,
+        session_revocations.clone(),
+    ));
+    let account_erasure_usecase = Arc::new(AccountErasureUseCase::new(
+        user_repo.clone(),
+        erasure_repo.clone(),
+        session_usecase.clone(),
+        config.erasure_email_key.clone(),
+    ));
+    AccountEraser::new(
+        erasure_repo,
+        config.erasure_batch_size,
+        jwt_config.expiration,
+    )
+    .spawn();
+    let identity_usecase = Arc::new(IdentityUseCase::new(
+        user_repo.clone(),
+        identity_repo.clone(),
+        auth_providers.clone(),
+    ));
+    let organization_usecase = Arc::new(OrganizationUseCase::new(
+        organization_repo,
+        Duration::from_secs(config.organization_invitation_ttl_seconds),
+    ));
+    let api_key_usecase = Arc::new(ApiKeyUseCase::new(api_key_repo, user_repo.clone()));
+    let role_usecase = Arc::new(RoleUseCase::new(user_repo.clone(), session_usecase.clone()));
+    let user_admin_usecase = Arc::new(UserAdminUseCase::new(
+        user_repo.clone(),
+        session_usecase.clone(),
+    ));
+    let password_usecase = Arc::new(PasswordUseCase::new(
+        user_repo.clone(),
+        password_repo.clone(),
+        session_usecase.clone(),
+        mailer.clone(),
+        PasswordConfig {
+            enabled: password_enabled,
+            reset_ttl: Duration::from_secs(config.password_reset_ttl_seconds),
+            reset_url: config.password_reset_url.clone(),
+            confirm_url: config.password_confirm_url.clone(),
+        },
+    ));
+    let magic_link_usecase = Arc::new(MagicLinkUseCase::new(
+        login_token_repo,
+        mailer,
+        Duration::from_secs(config.magic_link_ttl_seconds),
+        config.magic_link_url.clone(),
+        magic_link_enabled,
     ));
     let register_token_usecase = Arc::new(RegisterTokenUseCase::new(token_repo.clone()));
     let list_tokens_usecase = Arc::new(ListTokensUseCase::new(token_repo.clone()));
+    let share_token_usecase = Arc::new(ShareTokenUseCase::new(token_repo.clone()));
     let list_chats_usecase = Arc::new(ListChatsUseCase::new(
         token_repo.clone(),
         messenger_gateway.clone(),
     ));
-    let schedule_message_usecase = Arc::new(ScheduleMessageUseCase::new(
+    let send_chat_action_usecase = Arc::new(SendChatActionUseCase::new(
         token_repo.clone(),
-        history_repo.clone(),
+        messenger_gateway.clone(),
+    ));
+    let schedule_message_usecase = Arc::new(ScheduleMessageUseCase::new(
+        ScheduleMessageRepositories {
+            tokens: token_repo.clone(),
+            history: history_repo.clone(),
+            consents: consent_repo.clone(),
+            links: link_repo.clone(),
+            idempotency_keys: idempotency_repo,
+            routing_rules: routing_repo.clone(),
+        },
         bus.clone(),
+        dispatcher,
         schedule_config,
     ));
     let list_messages_usecase = Arc::new(ListMessagesUseCase::new(history_repo.clone()));
+    let routing_rule_usecase = Arc::new(RoutingRuleUseCase::new(routing_repo.clone()));
+    let ingest_event_usecase = Arc::new(IngestEventUseCase::new(
+        routing_repo.clone(),
+        schedule_message_usecase.clone(),
+    ));
     let retry_config = RetryMessageConfig {
         max_attempts: config.system_retry_limit,
+        retry_backoff,
     };
     let retry_message_usecase = Arc::new(RetryMessageUseCase::new(
         history_repo.clone(),
@@ -127,68 +602,323 @@ async fn main() -> Result<(), Error> {
         retry_config,
     ));
     let get_message_usecase = Arc::new(GetMessageUseCase::new(history_repo.clone()));
+    let delete_message_usecase = Arc::new(DeleteMessageUseCase::new(history_repo.clone()));
+    let get_delivery_stats_usecase = Arc::new(GetDeliveryStatsUseCase::new(history_repo.clone()));
     let get_message_attempts_usecase =
         Arc::new(GetMessageAttemptsUseCase::new(history_repo.clone()));
+    let get_related_messages_usecase = Arc::new(GetRelatedMessagesUseCase::new(
+        history_repo.clone(),
+        link_repo,
+    ));
 
-    let dispatcher = Arc::new(MessageDispatchHandler::new(
-        token_repo,
+    let send_otp_usecase = Arc::new(SendOtpUseCase::new(
+        otp_repo.clone(),
+        schedule_message_usecase.clone(),
+        SendOtpConfig {
+            code_length: config.otp_code_length,
+            ttl: Duration::from_secs(config.otp_ttl_seconds),
+            message_template: config.otp_message_template.clone(),
+        },
+    ));
+    let verify_otp_usecase = Arc::new(VerifyOtpUseCase::new(
+        otp_repo,
+        history_repo.clone(),
+        VerifyOtpConfig {
+            max_attempts: config.otp_max_verify_attempts,
+        },
+    ));
+
+    let consent_usecase = Arc::new(ConsentUseCase::new(
+        consent_repo.clone(),
+        schedule_message_usecase.clone(),
+        ConsentConfig {
+            confirmation_template: config.consent_confirmation_template.clone(),
+        },
+    ));
+
+    let dead_letter_usecase = Arc::new(DeadLetterUseCase::new(
+        dead_letter_queue,
+        history_repo.clone(),
+        bus.clone(),
+        DeadLetterConfig {
+            max_attempts: config.system_retry_limit,
+        },
+    ));
+
+    let content_scorer = Arc::new(ContentScorer::new(
+        config
+            .spam_banned_phrases
+            .iter()
+            .map(|phrase| BannedPhrase::parse(phrase))
+            .collect::<anyhow::Result<Vec<_>>>()
+            .map_err(Error::other)?,
+        config.spam_max_link_density,
+        config.spam_max_caps_ratio,
+    ));
+    let preview_message_usecase = Arc::new(PreviewMessageUseCase::new(
+        content_scorer,
+        config
+            .message_part_prices
+            .iter()
+            .map(|price| parse_part_price(price))
+            .collect::<anyhow::Result<_>>()
+            .map_err(Error::other)?,
+    ));
+
+    let get_overview_usecase = Arc::new(GetOverviewUseCase::new(
         history_repo.clone(),
+        token_repo.clone(),
+        queue_monitor.clone(),
+    ));
+    let get_queue_stats_usecase = Arc::new(GetQueueStatsUseCase::new(queue_monitor));
+    let get_messenger_health_usecase = Arc::new(GetMessengerHealthUseCase::new(
+        dispatch_counters,
+        config.delivery_slo_target,
+    ));
+    let trace_replay_usecase = Arc::new(TraceReplayUseCase::new(
+        history_repo.clone(),
+        token_repo.clone(),
+        consent_repo,
+        routing_rule_usecase.clone(),
+        schedule_message_usecase.clone(),
+        preview_message_usecase.clone(),
         messenger_gateway.clone(),
     ));
-    let _worker_handle = worker.spawn(dispatcher, bus_impl);
+    let list_workers_usecase = Arc::new(ListWorkersUseCase::new(
+        worker_repo,
+        worker_ttl(worker_heartbeat_interval),
+    ));
+    let message_status_link_usecase = Arc::new(MessageStatusLinkUseCase::new(
+        history_repo.clone(),
+        Arc::new(StatusTokenService::new(
+            jwt_keys.clone(),
+            Duration::from_secs(config.status_link_ttl_seconds),
+        )),
+        StatusExposure::parse(&config.status_exposure).map_err(Error::other)?,
+    ));
+
+    let public_ids: Arc<dyn PublicIdCodec> = match config.public_id_mode.as_str() {
+        "uuid" => Arc::new(UuidCodec),
+        "base62" => Arc::new(Base62Codec::new(&config.public_id_secret).map_err(Error::other)?),
+        other => return Err(Error::other(format!("unknown public id mode: {other}"))),
+    };
 
     let api_state = Arc::new(ApiState {
         auth_usecase,
+        password_usecase,
+        magic_link_usecase,
+        role_usecase,
+        user_admin_usecase,
+        identity_usecase,
+        api_key_usecase,
+        session_usecase,
+        account_erasure_usecase,
+        organization_usecase,
+        routing_rule_usecase,
+        ingest_event_usecase,
         register_token_usecase,
         list_tokens_usecase,
+        share_token_usecase,
         list_chats_usecase,
+        send_chat_action_usecase,
         schedule_message_usecase,
         list_messages_usecase,
         retry_message_usecase,
         get_message_attempts_usecase,
         get_message_usecase,
+        delete_message_usecase,
+        get_delivery_stats_usecase,
+        get_related_messages_usecase,
+        send_otp_usecase,
+        verify_otp_usecase,
+        consent_usecase,
+        dead_letter_usecase,
+        preview_message_usecase,
+        get_queue_stats_usecase,
+        get_overview_usecase,
+        get_messenger_health_usecase,
+        list_workers_usecase,
+        trace_replay_usecase,
+        message_status_link_usecase,
         jwt_config,
+        session_revocations,
+        public_ids,
+        delivery_semantics,
+        readiness: ReadinessProbe::new(database_monitor.clone(), bus.clone(), workers),
+        database_monitor: database_monitor.clone(),
+        messenger_gateway,
+        status_feed,
+        oauth_success_url: config.oauth_success_url.clone(),
     });
 
-    let server_url = format!("{}://{}:{}", config.scheme, config.host, config.port);
+    let rate_limit = |limit: Option<&str>| limit.map(RateLimit::parse).transpose();
+    let per_user_limit = rate_limit(config.rate_limit_per_user.as_deref()).map_err(Error::other)?;
+    let per_ip_limit = rate_limit(config.rate_limit_per_ip.as_deref()).map_err(Error::other)?;
+    let rate_limit_store: Arc<dyn RateLimitStore> = match config.rate_limit_store.as_str() {
+        "redis" => Arc::new(
+            RedisRateLimitStore::new(&config.redis_url, "rate_limit")
+                .await
+                .map_err(Error::other)?,
+        ),
+        "nats" => {
+            let longest_window = [per_user_limit, per_ip_limit]
+                .into_iter()
+                .flatten()
+                .map(|limit| limit.window)
+                .max()
+                .unwrap_or(Duration::from_secs(60));
+            Arc::new(
+                NatsRateLimitStore::new(&config.nats_url, "rate_limit", longest_window)
+                    .await
+                    .map_err(Error::other)?,
+            )
+        }
+        _ => Arc::new(InMemoryRateLimitStore::default()),
+    };
+    let rate_limiter = RateLimiter::new(
+        rate_limit_store,
+        per_user_limit,
+        per_ip_limit,
+        WarmUp::new(
+            config.rate_limit_warmup_fill,
+            Duration::from_secs(config.rate_limit_warmup_seconds),
+        )
+        .map_err(Error::other)?,
+        api_state.jwt_config.clone(),
+    );
 
-    println!("Starting server at {}", server_url);
+    let csrf_protection =
+        CsrfProtection::new(CsrfMode::parse(&config.csrf_protection).map_err(Error::other)?);
+    let client_ip_resolver =
+        ClientIpResolver::new(&config.trusted_proxies).map_err(Error::other)?;
+
+    tracing::info!(%server_url, "starting server");
 
     let apis = (
         HealthEndpoints::new(api_state.clone()),
         AuthEndpoints::new(api_state.clone()),
+        ApiKeysEndpoints::new(api_state.clone()),
+        OrganizationsEndpoints::new(api_state.clone()),
         TokensEndpoints::new(api_state.clone()),
         MessagesEndpoints::new(api_state.clone()),
         ChatsEndpoints::new(api_state.clone()),
+        OtpEndpoints::new(api_state.clone()),
+        ConsentsEndpoints::new(api_state.clone()),
+        DeadLettersEndpoints::new(api_state.clone()),
+        AdminEndpoints::new(api_state.clone()),
+        PublicStatusEndpoints::new(api_state.clone()),
+        RoutingRulesEndpoints::new(api_state.clone()),
+        EventsEndpoints::new(api_state.clone()),
+        StatsEndpoints::new(api_state.clone()),
     );
 
     let api_service =
         OpenApiService::new(apis, "Messaging API", "0.1.0").server(format!("{}/api", server_url));
     let ui = api_service.swagger_ui();
-    let route = Route::new().nest("/api", api_service).nest("/", ui);
-
-    let cors = if config.cors_allowed_origins.is_empty() {
-        Cors::new()
-            .allow_credentials(true)
-            .allow_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
-            .allow_headers(vec!["authorization", "content-type"])
-            .allow_origins_fn(|_| true)
-    } else {
-        let mut cors = Cors::new()
-            .allow_credentials(true)
-            .allow_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
-            .allow_headers(vec!["authorization", "content-type"]);
-
-        for origin in &config.cors_allowed_origins {
-            cors = cors.allow_origin(origin.clone());
-        }
+    let route = Route::new()
+        .nest(
+            "/api",
+            api_service
+                .into_endpoint()
+                .with(csrf_protection)
+                .with(rate_limiter),
+        )
+        .at("/.well-known/jwks.json", get(jwks).data(jwt_keys.clone()))
+        .nest("/", ui);
+
+    // Without an allowlist any origin may call the API, but browsers don't
+    // send it the session cookies: only the listed origins get credentials.
+    let mut cors = Cors::new()
+        .allow_credentials(!config.cors_allowed_origins.is_empty())
+        .allow_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE", "OPTIONS"])
+        .allow_headers(vec![
+            "authorization",
+            "content-type",
+            "idempotency-key",
+            "x-csrf-token",
+            "x-requested-with",
+        ])
+        .expose_headers(vec!["retry-after", REQUEST_ID_HEADER]);
+
+    for origin in &config.cors_allowed_origins {
+        cors = cors.allow_origin(origin.clone());
+    }
+
+    let app = route
+        .with(client_ip_resolver)
+        .with(cors)
+        .with(CookieJarManager::new())
+        .with(RequestTracing)
+        .with(RequestId);
 
-        cors
+    let secrets_changed = async move {
+        let Some(store) = secret_store.filter(|_| secrets_config.refresh_seconds > 0) else {
+            return pending::<()>().await;
+        };
+        let interval = Duration::from_secs(secrets_config.refresh_seconds);
+        let mut current = secrets;
+        loop {
+            current = watch_secrets(store.as_ref(), &current, interval).await;
+            if secrets_on_change == SecretsOnChange::Restart {
+                tracing::info!("secrets changed, shutting down to reload them");
+                return;
+            }
+            match reload_secrets(&current, &jwt_keys, database_monitor.as_ref()) {
+                Ok(()) => tracing::info!("secrets changed, reloaded them"),
+                Err(err) => {
+                    tracing::error!(error = ?err, "failed to reload secrets, keeping the old ones")
+                }
+            }
+        }
     };
 
-    let app = route.with(cors).with(CookieJarManager::new());
+    let result = Server::new(TcpListener::bind(format!("0.0.0.0:{}", config.port)))
+        .run_with_graceful_shutdown(app, secrets_changed, Some(Duration::from_secs(30)))
+        .await;
+    if let Some(provider) = tracer_provider
+        && let Err(err) = provider.shutdown()
+    {
+        tracing::error!(error = ?err, "failed to flush traces");
+    }
+    result
+}
+
+/// The single `JWT_SECRET` key, or the `JWT_KEYS` that replace it.
+fn jwt_key_set(config: &Config) -> anyhow::Result<Arc<JwtKeySet>> {
+    if config.jwt_keys.is_empty() {
+        return Ok(JwtKeySet::from_secret(&config.jwt_secret));
+    }
+    JwtKeySet::new(
+        config
+            .jwt_keys
+            .iter()
+            .map(|spec| load_jwt_key(spec))
+            .collect::<anyhow::Result<_>>()?,
+        config.jwt_signing_key_id.as_deref(),
+    )
+}
+
+/// Applies refreshed secrets to what can take them without a restart: the
+/// JWT keys and the database credentials.
+fn reload_secrets(
+    secrets: &HashMap<String, String>,
+    jwt_keys: &JwtKeys,
+    database: &dyn DatabaseMonitor,
+) -> anyhow::Result<()> {
+    let config = Config::try_parse(secrets).map_err(anyhow::Error::msg)?;
+    let keys = jwt_key_set(&config)?;
+    database.set_urls(&config.database_url, config.database_read_url.as_deref())?;
+    jwt_keys.replace(keys);
+    Ok(())
+}
 
-    Server::new(TcpListener::bind(format!("0.0.0.0:{}", config.port)))
-        .run(app)
-        .await
+/// The configured message bus, with what the API reads from it and the
+/// tasks consuming it.
+struct MessageBusSetup {
+    bus: Arc<dyn MessageBus>,
+    /// Only JetStream keeps one the API can inspect and redrive.
+    dead_letter_queue: Option<Arc<dyn DeadLetterQueue>>,
+    /// Backlog figures; the in-memory bus has none.
+    queue_monitor: Option<Arc<dyn QueueMonitor>>,
+    workers: Vec<JoinHandle<()>>,
 }
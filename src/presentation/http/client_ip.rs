@@ -0,0 +1,241 @@
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use poem::{
+    Endpoint, FromRequest, IntoResponse, Middleware, Request, RequestBody, Response,
+    Result as PoemResult,
+};
+
+const FORWARDED_FOR_HEADER: &str = "X-Forwarded-For";
+const REAL_IP_HEADER: &str = "X-Real-IP";
+
+/// The address a request came from, as `ClientIpResolver` worked it out;
+/// the peer's own address outside of it.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<'a> FromRequest<'a> for ClientIp {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> PoemResult<Self> {
+        Ok(Self::of(req))
+    }
+}
+
+impl ClientIp {
+    pub fn of(req: &Request) -> Self {
+        req.extensions()
+            .get::<ClientIp>()
+            .copied()
+            .unwrap_or_else(|| ClientIp(peer_ip(req)))
+    }
+}
+
+/// Works out each request's `ClientIp`. Forwarding headers are only
+/// believed when the peer is one of the trusted proxies, since anyone else
+/// can write whatever they like in them.
+pub struct ClientIpResolver {
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl ClientIpResolver {
+    /// CIDRs such as `10.0.0.0/8`; a bare address stands for itself alone.
+    pub fn new(trusted_proxies: &[String]) -> anyhow::Result<Self> {
+        let trusted_proxies = trusted_proxies
+            .iter()
+            .map(|proxy| match proxy.parse::<IpNet>() {
+                Ok(net) => Ok(net.trunc()),
+                Err(_) => proxy
+                    .parse::<IpAddr>()
+                    .map(IpNet::from)
+                    .map_err(|_| anyhow::anyhow!("invalid TRUSTED_PROXIES: {proxy} is not a CIDR")),
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { trusted_proxies })
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for ClientIpResolver {
+    type Output = ClientIpEndpoint<E>;
+
+    fn transform(&self, inner: E) -> Self::Output {
+        ClientIpEndpoint {
+            inner,
+            trusted_proxies: self.trusted_proxies.clone(),
+        }
+    }
+}
+
+pub struct ClientIpEndpoint<E> {
+    inner: E,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl<E> ClientIpEndpoint<E> {
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(&ip))
+    }
+
+    /// Walks `X-Forwarded-For` back from the peer, past the trusted proxies,
+    /// to the first address none of them vouches for; `X-Real-IP` stands in
+    /// when a trusted proxy sends only that.
+    fn resolve(&self, req: &Request) -> Option<IpAddr> {
+        let peer = peer_ip(req)?;
+        if !self.is_trusted(peer) {
+            return Some(peer);
+        }
+
+        let forwarded: Vec<IpAddr> = req
+            .headers()
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+            .map(|ip| ip.to_canonical())
+            .collect();
+        if let Some(first) = forwarded.first() {
+            let client = forwarded
+                .iter()
+                .rev()
+                .find(|ip| !self.is_trusted(**ip))
+                // Every hop was a proxy of ours, so the first one is as far
+                // back as anyone can tell.
+                .unwrap_or(first);
+            return Some(*client);
+        }
+
+        req.header(REAL_IP_HEADER)
+            .and_then(|value| value.trim().parse::<IpAddr>().ok())
+            .map(|ip| ip.to_canonical())
+            .or(Some(peer))
+    }
+}
+
+impl<E: Endpoint> Endpoint for ClientIpEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> PoemResult<Self::Output> {
+        let client_ip = ClientIp(self.resolve(&req));
+        req.extensions_mut().insert(client_ip);
+        self.inner.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+fn peer_ip(req: &Request) -> Option<IpAddr> {
+    req.remote_addr()
+        .as_socket_addr()
+        .map(|addr| addr.ip().to_canonical())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use poem::{
+        Body, RequestParts,
+        http::{self, uri::Scheme},
+        web::{LocalAddr, RemoteAddr},
+    };
+
+    use super::*;
+
+    fn endpoint(trusted_proxies: &[&str]) -> ClientIpEndpoint<()> {
+        let proxies: Vec<String> = trusted_proxies.iter().map(|p| p.to_string()).collect();
+        ClientIpEndpoint {
+            inner: (),
+            trusted_proxies: ClientIpResolver::new(&proxies).unwrap().trusted_proxies,
+        }
+    }
+
+    fn request(peer: &str, headers: &[(&str, &str)]) -> Request {
+        let mut builder = http::Request::builder();
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let (parts, ()) = builder.body(()).unwrap().into_parts();
+        let peer = SocketAddr::new(peer.parse().unwrap(), 40000);
+        let parts = RequestParts::from((
+            parts,
+            LocalAddr::default(),
+            RemoteAddr(peer.into()),
+            Scheme::HTTP,
+        ));
+        Request::from_parts(parts, Body::empty())
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn untrusted_peer_headers_are_ignored() {
+        let req = request(
+            "203.0.113.7",
+            &[
+                ("X-Forwarded-For", "198.51.100.1"),
+                ("X-Real-IP", "198.51.100.2"),
+            ],
+        );
+        assert_eq!(endpoint(&["10.0.0.0/8"]).resolve(&req), ip("203.0.113.7"));
+    }
+
+    #[test]
+    fn trusted_peer_walks_forwarded_for_past_trusted_hops() {
+        let req = request(
+            "10.0.0.1",
+            &[("X-Forwarded-For", "1.1.1.1, 198.51.100.1, 10.0.0.5")],
+        );
+        assert_eq!(endpoint(&["10.0.0.0/8"]).resolve(&req), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn spoofed_leading_hops_are_not_believed() {
+        let req = request(
+            "10.0.0.1",
+            &[
+                ("X-Forwarded-For", "10.0.0.9"),
+                ("X-Forwarded-For", "198.51.100.1"),
+            ],
+        );
+        assert_eq!(endpoint(&["10.0.0.0/8"]).resolve(&req), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn all_trusted_hops_resolve_to_the_first() {
+        let req = request("10.0.0.1", &[("X-Forwarded-For", "10.0.0.3, 10.0.0.2")]);
+        assert_eq!(endpoint(&["10.0.0.0/8"]).resolve(&req), ip("10.0.0.3"));
+    }
+
+    #[test]
+    fn trusted_peer_falls_back_to_real_ip_then_peer() {
+        let real_ip = request("192.168.1.1", &[("X-Real-IP", " 198.51.100.3 ")]);
+        assert_eq!(
+            endpoint(&["192.168.1.1"]).resolve(&real_ip),
+            ip("198.51.100.3")
+        );
+
+        let garbage = request(
+            "192.168.1.1",
+            &[("X-Forwarded-For", "unknown"), ("X-Real-IP", "nope")],
+        );
+        assert_eq!(
+            endpoint(&["192.168.1.1"]).resolve(&garbage),
+            ip("192.168.1.1")
+        );
+    }
+
+    #[test]
+    fn mapped_ipv4_addresses_are_canonical() {
+        let req = request(
+            "::ffff:10.0.0.1",
+            &[("X-Forwarded-For", "::ffff:198.51.100.1")],
+        );
+        assert_eq!(endpoint(&["10.0.0.0/8"]).resolve(&req), ip("198.51.100.1"));
+    }
+
+    #[test]
+    fn invalid_trusted_proxies_are_rejected() {
+        assert!(ClientIpResolver::new(&["10.0.0.0/33".to_string()]).is_err());
+        assert!(ClientIpResolver::new(&["proxy.internal".to_string()]).is_err());
+        assert!(ClientIpResolver::new(&["10.1.2.3/8".to_string(), "::1".to_string()]).is_ok());
+    }
+}
@@ -0,0 +1,279 @@
+use poem::{
+    Endpoint, Error as PoemError, IntoResponse, Middleware, Request, Response,
+    Result as PoemResult,
+    http::{Method, StatusCode},
+    web::cookie::{Cookie, CookieJar, SameSite},
+};
+
+use crate::application::services::secret_token::generate_secret_token;
+
+/// Readable by the page's scripts, unlike the session cookies, so they can
+/// echo it in `CSRF_HEADER`.
+pub const CSRF_COOKIE: &str = "csrf_token";
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+const REQUESTED_WITH_HEADER: &str = "X-Requested-With";
+
+/// Sign-in and recovery act on no session, so they need no token; refresh
+/// is let through so sessions from before a token was issued pick one up.
+const EXEMPT_PATHS: [&str; 7] = [
+    "/auth/login",
+    "/auth/register",
+    "/auth/refresh",
+    "/auth/magic-link",
+    "/auth/magic-link/verify",
+    "/auth/password/forgot",
+    "/auth/password/reset",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsrfMode {
+    /// The `csrf_token` cookie must come back in `X-CSRF-Token`.
+    DoubleSubmit,
+    /// `X-Requested-With` must be present, which cross-site forms cannot
+    /// send.
+    Header,
+    /// For API-only deployments whose clients don't use cookies.
+    Off,
+}
+
+impl CsrfMode {
+    pub fn parse(value: &str) -> anyhow::Result<Self> {
+        match value {
+            "double_submit" => Ok(CsrfMode::DoubleSubmit),
+            "header" => Ok(CsrfMode::Header),
+            "off" => Ok(CsrfMode::Off),
+            other => anyhow::bail!("unknown csrf protection mode {other}"),
+        }
+    }
+}
+
+/// Sets a new token alongside the session cookies.
+pub fn add_csrf_cookie(cookie_jar: &CookieJar) {
+    let token = generate_secret_token();
+    let mut cookie = Cookie::new_with_str(CSRF_COOKIE, token);
+    cookie.set_secure(true);
+    cookie.set_same_site(Some(SameSite::Strict));
+    cookie.set_path("/");
+    cookie_jar.add(cookie);
+}
+
+pub fn clear_csrf_cookie(cookie_jar: &CookieJar) {
+    let mut cookie = Cookie::named(CSRF_COOKIE);
+    cookie.set_secure(true);
+    cookie.set_same_site(Some(SameSite::Strict));
+    cookie.set_path("/");
+    cookie.make_removal();
+    cookie_jar.add(cookie);
+}
+
+/// Guards requests that change state and carry the session cookies. A
+/// bearer token is used over the cookies, so requests sending one are
+/// authenticated by it alone and a forged cross-site request can't have it.
+/// Other `Authorization` schemes may still fall back to the cookies.
+pub struct CsrfProtection {
+    mode: CsrfMode,
+}
+
+impl CsrfProtection {
+    pub fn new(mode: CsrfMode) -> Self {
+        Self { mode }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for CsrfProtection {
+    type Output = CsrfEndpoint<E>;
+
+    fn transform(&self, inner: E) -> Self::Output {
+        CsrfEndpoint {
+            inner,
+            mode: self.mode,
+        }
+    }
+}
+
+pub struct CsrfEndpoint<E> {
+    inner: E,
+    mode: CsrfMode,
+}
+
+impl<E> CsrfEndpoint<E> {
+    fn needs_check(&self, req: &Request) -> bool {
+        if self.mode == CsrfMode::Off
+            || matches!(
+                *req.method(),
+                Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+            )
+            || EXEMPT_PATHS.contains(&req.uri().path())
+            || req
+                .header("Authorization")
+                .and_then(|value| value.get(..7))
+                .is_some_and(|scheme| scheme.eq_ignore_ascii_case("Bearer "))
+        {
+            return false;
+        }
+        let cookies = req.cookie();
+        cookies.get("access_token").is_some() || cookies.get("refresh_token").is_some()
+    }
+
+    fn check(&self, req: &Request) -> PoemResult<()> {
+        let valid = match self.mode {
+            CsrfMode::Off => true,
+            CsrfMode::Header => req.headers().contains_key(REQUESTED_WITH_HEADER),
+            CsrfMode::DoubleSubmit => {
+                match (req.cookie().get(CSRF_COOKIE), req.header(CSRF_HEADER)) {
+                    (Some(cookie), Some(header)) => {
+                        constant_time_eq(cookie.value_str().as_bytes(), header.as_bytes())
+                    }
+                    _ => false,
+                }
+            }
+        };
+        if !valid {
+            return Err(PoemError::from_string(
+                "missing or invalid csrf token",
+                StatusCode::FORBIDDEN,
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<E: Endpoint> Endpoint for CsrfEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> PoemResult<Self::Output> {
+        if self.needs_check(&req) {
+            self.check(&req)?;
+        }
+        self.inner.call(req).await.map(IntoResponse::into_response)
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use poem::{EndpointExt, endpoint::make_sync, middleware::CookieJarManager};
+
+    use super::*;
+
+    async fn status(mode: CsrfMode, req: Request) -> StatusCode {
+        make_sync(|_| "ok")
+            .with(CsrfProtection::new(mode))
+            .with(CookieJarManager::new())
+            .get_response(req)
+            .await
+            .status()
+    }
+
+    fn post(path: &str, cookie: &str) -> poem::RequestBuilder {
+        Request::builder()
+            .method(Method::POST)
+            .uri_str(path)
+            .header("Cookie", cookie)
+    }
+
+    #[tokio::test]
+    async fn double_submit_accepts_matching_token() {
+        let req = post("/messages", "access_token=a; csrf_token=t1")
+            .header(CSRF_HEADER, "t1")
+            .finish();
+        assert_eq!(status(CsrfMode::DoubleSubmit, req).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn double_submit_rejects_missing_or_wrong_token() {
+        let missing = post("/messages", "access_token=a; csrf_token=t1").finish();
+        assert_eq!(
+            status(CsrfMode::DoubleSubmit, missing).await,
+            StatusCode::FORBIDDEN
+        );
+
+        let wrong = post("/messages", "refresh_token=r; csrf_token=t1")
+            .header(CSRF_HEADER, "t2")
+            .finish();
+        assert_eq!(
+            status(CsrfMode::DoubleSubmit, wrong).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn bearer_requests_are_exempt() {
+        for scheme in ["Bearer", "bearer", "BEARER"] {
+            let req = post("/messages", "access_token=a")
+                .header("Authorization", format!("{scheme} token"))
+                .finish();
+            assert_eq!(status(CsrfMode::DoubleSubmit, req).await, StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn other_authorization_schemes_are_checked() {
+        for value in ["Basic dXNlcjpwYXNz", "Bearer", "Bearertoken"] {
+            let req = post("/messages", "access_token=a")
+                .header("Authorization", value)
+                .finish();
+            assert_eq!(
+                status(CsrfMode::DoubleSubmit, req).await,
+                StatusCode::FORBIDDEN
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn safe_methods_exempt_paths_and_cookieless_requests_pass() {
+        let get = Request::builder()
+            .uri_str("/messages")
+            .header("Cookie", "access_token=a")
+            .finish();
+        assert_eq!(status(CsrfMode::DoubleSubmit, get).await, StatusCode::OK);
+
+        let login = post("/auth/login", "access_token=a").finish();
+        assert_eq!(status(CsrfMode::DoubleSubmit, login).await, StatusCode::OK);
+
+        let cookieless = post("/messages", "csrf_token=t1").finish();
+        assert_eq!(
+            status(CsrfMode::DoubleSubmit, cookieless).await,
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn header_mode_requires_requested_with() {
+        let with = post("/messages", "access_token=a")
+            .header(REQUESTED_WITH_HEADER, "XMLHttpRequest")
+            .finish();
+        assert_eq!(status(CsrfMode::Header, with).await, StatusCode::OK);
+
+        let without = post("/messages", "access_token=a").finish();
+        assert_eq!(
+            status(CsrfMode::Header, without).await,
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn off_mode_checks_nothing() {
+        let req = post("/messages", "access_token=a").finish();
+        assert_eq!(status(CsrfMode::Off, req).await, StatusCode::OK);
+    }
+
+    #[test]
+    fn parse_rejects_unknown_modes() {
+        assert_eq!(
+            CsrfMode::parse("double_submit").unwrap(),
+            CsrfMode::DoubleSubmit
+        );
+        assert!(CsrfMode::parse("strict").is_err());
+    }
+
+    #[test]
+    fn constant_time_eq_compares_whole_values() {
+        assert!(constant_time_eq(b"token", b"token"));
+        assert!(!constant_time_eq(b"token", b"tokem"));
+        assert!(!constant_time_eq(b"token", b"token2"));
+    }
+}
@@ -0,0 +1,489 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{
+    OpenApi,
+    param::{Path, Query},
+    payload::Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::models::FailedMessageFilter,
+    presentation::http::{
+        endpoints::root::{ApiState, EndpointsTags},
+        mappers::{
+            map_account_erasure, map_delivery_semantics, map_delivery_stats, map_messenger_health,
+            map_overview, map_queue_stats, map_trace, map_user, map_worker, resolve_message_id,
+        },
+        requests::{PasswordResetRequestDto, RequeueFailedRequestDto, UserRoleRequestDto},
+        responses::{
+            AccountErasureDto, AuthResponseDto, DeliverySemanticsDto, DeliveryStatsDto,
+            MessageTraceDto, MessengerHealthReportDto, OverviewDto, PaginatedUsersDto,
+            PasswordResetTokenDto, QueueStatsDto, RequeueFailedResponseDto, UserDto, WorkerDto,
+        },
+        security::{AdminAuth, SessionCredentials},
+    },
+};
+
+#[derive(Clone)]
+pub struct AdminEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl AdminEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl AdminEndpoints {
+    /// Backlog of the active bus, for dashboards without access to the
+    /// broker. Figures the bus does not report are unset; the in-memory bus
+    /// reports none.
+    #[oai(path = "/admin/queue", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn queue_stats(&self, auth: SessionCredentials) -> PoemResult<Json<QueueStatsDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let stats = self
+            .state
+            .get_queue_stats_usecase
+            .execute()
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_queue_stats(&stats)))
+    }
+
+    /// Delivery across every organization at a glance: backlog, failures
+    /// over the last hour and token health per messenger.
+    #[oai(path = "/admin/overview", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn overview(&self, auth: SessionCredentials) -> PoemResult<Json<OverviewDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let overview = self
+            .state
+            .get_overview_usecase
+            .execute()
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_overview(&overview)))
+    }
+
+    /// Success rate of the sends to each messenger over rolling windows,
+    /// against `DELIVERY_SLO_TARGET`. Counts are this instance's; alerts
+    /// across instances belong on the `/metrics` series.
+    #[oai(path = "/admin/messengers/health", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn messenger_health(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<MessengerHealthReportDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let report = self.state.get_messenger_health_usecase.execute();
+
+        Ok(Json(map_messenger_health(&report)))
+    }
+
+    /// Dispatcher instances with their last heartbeat and delivery counts.
+    #[oai(path = "/admin/workers", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn list_workers(&self, auth: SessionCredentials) -> PoemResult<Json<Vec<WorkerDto>>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let workers = self
+            .state
+            .list_workers_usecase
+            .execute()
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(
+            workers
+                .iter()
+                .map(|(worker, alive)| map_worker(worker, *alive))
+                .collect(),
+        ))
+    }
+
+    /// Replays the message through scheduling and dispatch as a dry run,
+    /// reporting what each stage would decide now. Nothing is recorded,
+    /// queued or sent.
+    #[oai(
+        path = "/admin/messages/:id/trace-replay",
+        method = "post",
+        tag = EndpointsTags::Admin
+    )]
+    pub async fn trace_replay(
+        &self,
+        auth: SessionCredentials,
+        id: Path<String>,
+    ) -> PoemResult<Json<MessageTraceDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let message_id = resolve_message_id(&id.0, self.state.public_ids.as_ref())?;
+
+        let trace = self
+            .state
+            .trace_replay_usecase
+            .execute(message_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_trace(&trace)))
+    }
+
+    /// Deletes a message of any user for good, with its attempts and
+    /// links, whether or not its sender deleted it first.
+    #[oai(path = "/admin/messages/:id", method = "delete", tag = EndpointsTags::Admin)]
+    pub async fn purge_message(
+        &self,
+        auth: SessionCredentials,
+        id: Path<String>,
+    ) -> PoemResult<()> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let message_id = resolve_message_id(&id.0, self.state.public_ids.as_ref())?;
+
+        self.state
+            .delete_message_usecase
+            .purge(message_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(())
+    }
+
+    /// Requeues failed messages of every user matching the filter in one
+    /// go, typically after an outage.
+    #[oai(path = "/admin/messages/requeue", method = "post", tag = EndpointsTags::Admin)]
+    pub async fn requeue_failed(
+        &self,
+        auth: SessionCredentials,
+        request: Json<RequeueFailedRequestDto>,
+    ) -> PoemResult<Json<RequeueFailedResponseDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let filter = FailedMessageFilter {
+            messenger: request.messenger.map(Into::into),
+            failed_after: request.failed_after,
+            failed_before: request.failed_before,
+            reason: request.error.clone(),
+        };
+        let result = self
+            .state
+            .retry_message_usecase
+            .requeue_failed(&filter, request.limit)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(RequeueFailedResponseDto {
+            requeued: result.requeued,
+            skipped: result.skipped,
+        }))
+    }
+
+    /// Attempts, redelivery limits and retry delays in effect for the
+    /// configured message bus.
+    #[oai(path = "/admin/delivery", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn delivery_semantics(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<DeliverySemanticsDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        Ok(Json(map_delivery_semantics(&self.state.delivery_semantics)))
+    }
+
+    /// Issues a password reset token for the user to redeem at
+    /// `POST /auth/password/reset`. Delivering it is up to the operator.
+    #[oai(path = "/admin/password-resets", method = "post", tag = EndpointsTags::Admin)]
+    pub async fn issue_password_reset(
+        &self,
+        auth: SessionCredentials,
+        request: Json<PasswordResetRequestDto>,
+    ) -> PoemResult<Json<PasswordResetTokenDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let reset = self
+            .state
+            .password_usecase
+            .issue_reset(request.email.trim())
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(PasswordResetTokenDto {
+            token: reset.token,
+            expires_at: reset.expires_at.to_rfc3339(),
+        }))
+    }
+
+    /// Makes the user an admin, a member or read-only. A changed role signs
+    /// the user out everywhere, so no token keeps the old one.
+    #[oai(path = "/admin/users/:user_id/role", method = "put", tag = EndpointsTags::Admin)]
+    pub async fn assign_role(
+        &self,
+        auth: SessionCredentials,
+        user_id: Path<Uuid>,
+        request: Json<UserRoleRequestDto>,
+    ) -> PoemResult<Json<UserDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let user = self
+            .state
+            .role_usecase
+            .assign(user_id.0, request.role.into())
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_user(&user)))
+    }
+
+    /// Users whose email or display name contains `search`, oldest first.
+    #[oai(path = "/admin/users", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn list_users(
+        &self,
+        auth: SessionCredentials,
+        search: Query<Option<String>>,
+        limit: Query<Option<u32>>,
+        offset: Query<Option<u32>>,
+    ) -> PoemResult<Json<PaginatedUsersDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let search = search.0.filter(|search| !search.trim().is_empty());
+        let result = self
+            .state
+            .user_admin_usecase
+            .list(search.as_deref().map(str::trim), limit.0, offset.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(PaginatedUsersDto {
+            users: result.users.iter().map(map_user).collect(),
+            has_more: result.has_more,
+            next_offset: result.next_offset,
+        }))
+    }
+
+    #[oai(path = "/admin/users/:user_id", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn get_user(
+        &self,
+        auth: SessionCredentials,
+        user_id: Path<Uuid>,
+    ) -> PoemResult<Json<UserDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let user = self
+            .state
+            .user_admin_usecase
+            .get(user_id.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_user(&user)))
+    }
+
+    /// Signs the user out everywhere and keeps them from signing in or
+    /// using their API keys until enabled again.
+    #[oai(
+        path = "/admin/users/:user_id/disable",
+        method = "post",
+        tag = EndpointsTags::Admin
+    )]
+    pub async fn disable_user(
+        &self,
+        auth: SessionCredentials,
+        user_id: Path<Uuid>,
+    ) -> PoemResult<Json<UserDto>> {
+        let admin = AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        if admin.user_id == user_id.0 {
+            return Err(PoemError::from_string(
+                "admins can't disable themselves",
+                StatusCode::BAD_REQUEST,
+            ));
+        }
+
+        let user = self
+            .state
+            .user_admin_usecase
+            .set_disabled(user_id.0, true)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_user(&user)))
+    }
+
+    #[oai(
+        path = "/admin/users/:user_id/enable",
+        method = "post",
+        tag = EndpointsTags::Admin
+    )]
+    pub async fn enable_user(
+        &self,
+        auth: SessionCredentials,
+        user_id: Path<Uuid>,
+    ) -> PoemResult<Json<UserDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let user = self
+            .state
+            .user_admin_usecase
+            .set_disabled(user_id.0, false)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_user(&user)))
+    }
+
+    /// Signs the user out of every session. API keys are left alone.
+    #[oai(
+        path = "/admin/users/:user_id/sessions",
+        method = "delete",
+        tag = EndpointsTags::Admin
+    )]
+    pub async fn reset_user_sessions(
+        &self,
+        auth: SessionCredentials,
+        user_id: Path<Uuid>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        self.state
+            .user_admin_usecase
+            .reset_sessions(user_id.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Delivery counts of the messages the user sent in any organization,
+    /// over the same window as `GET /stats`.
+    #[oai(
+        path = "/admin/users/:user_id/usage",
+        method = "get",
+        tag = EndpointsTags::Admin
+    )]
+    pub async fn user_usage(
+        &self,
+        auth: SessionCredentials,
+        user_id: Path<Uuid>,
+        from: Query<Option<DateTime<Utc>>>,
+        to: Query<Option<DateTime<Utc>>>,
+    ) -> PoemResult<Json<DeliveryStatsDto>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        self.state
+            .user_admin_usecase
+            .get(user_id.0)
+            .await
+            .map_err(map_error)?;
+        let (stats, from, to) = self
+            .state
+            .get_delivery_stats_usecase
+            .for_sender(user_id.0, from.0, to.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_delivery_stats(&stats, from, to)))
+    }
+
+    /// Audit records of the latest account deletions, newest first.
+    #[oai(path = "/admin/erasures", method = "get", tag = EndpointsTags::Admin)]
+    pub async fn list_erasures(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<Vec<AccountErasureDto>>> {
+        AdminAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let erasures = self
+            .state
+            .account_erasure_usecase
+            .list()
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(erasures.iter().map(map_account_erasure).collect()))
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    if message.contains("not available") {
+        PoemError::from_string(message, StatusCode::NOT_IMPLEMENTED)
+    } else if message.contains("not found") {
+        PoemError::from_string(message, StatusCode::NOT_FOUND)
+    } else if message.contains("invalid window") {
+        PoemError::from_string(message, StatusCode::BAD_REQUEST)
+    } else {
+        PoemError::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
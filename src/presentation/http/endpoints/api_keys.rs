@@ -0,0 +1,145 @@
+use std::sync::Arc;
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{OpenApi, param::Path, payload::Json};
+use uuid::Uuid;
+
+use crate::{
+    domain::models::ApiKeyScope,
+    presentation::http::{
+        endpoints::root::{ApiState, EndpointsTags},
+        mappers::{map_api_key, map_issued_api_key},
+        requests::ApiKeyRequestDto,
+        responses::{ApiKeyDto, AuthResponseDto, IssuedApiKeyDto},
+        security::{JwtAuth, SessionCredentials},
+    },
+};
+
+/// Keys are managed from a session only, so a leaked key cannot mint more.
+#[derive(Clone)]
+pub struct ApiKeysEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl ApiKeysEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl ApiKeysEndpoints {
+    #[oai(path = "/api-keys", method = "get", tag = EndpointsTags::ApiKeys)]
+    pub async fn list_api_keys(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<Vec<ApiKeyDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let keys = self
+            .state
+            .api_key_usecase
+            .list(user.user_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(keys.iter().map(map_api_key).collect()))
+    }
+
+    #[oai(path = "/api-keys", method = "post", tag = EndpointsTags::ApiKeys)]
+    pub async fn create_api_key(
+        &self,
+        auth: SessionCredentials,
+        request: Json<ApiKeyRequestDto>,
+    ) -> PoemResult<Json<IssuedApiKeyDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let issued = self
+            .state
+            .api_key_usecase
+            .issue(
+                user.user_id,
+                &request.name,
+                match &request.scopes {
+                    Some(scopes) => scopes.iter().copied().map(Into::into).collect(),
+                    None => ApiKeyScope::ALL.to_vec(),
+                },
+                request.allowed_ips.as_deref().unwrap_or_default(),
+            )
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_issued_api_key(&issued)))
+    }
+
+    /// Issues a new key under the same name and revokes the old one.
+    #[oai(
+        path = "/api-keys/:key_id/rotate",
+        method = "post",
+        tag = EndpointsTags::ApiKeys
+    )]
+    pub async fn rotate_api_key(
+        &self,
+        auth: SessionCredentials,
+        key_id: Path<Uuid>,
+    ) -> PoemResult<Json<IssuedApiKeyDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let issued = self
+            .state
+            .api_key_usecase
+            .rotate(user.user_id, key_id.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_issued_api_key(&issued)))
+    }
+
+    #[oai(
+        path = "/api-keys/:key_id",
+        method = "delete",
+        tag = EndpointsTags::ApiKeys
+    )]
+    pub async fn revoke_api_key(
+        &self,
+        auth: SessionCredentials,
+        key_id: Path<Uuid>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        self.state
+            .api_key_usecase
+            .revoke(user.user_id, key_id.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    if message.contains("not found") {
+        PoemError::from_string(message, StatusCode::NOT_FOUND)
+    } else if message.contains("invalid") {
+        PoemError::from_string(message, StatusCode::BAD_REQUEST)
+    } else {
+        PoemError::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
@@ -2,21 +2,48 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use poem::{
-    Error as PoemError, Result as PoemResult,
-    http::StatusCode,
+    Error as PoemError, Response, Result as PoemResult,
+    http::{StatusCode, header::RETRY_AFTER},
     web::cookie::{Cookie, CookieJar, SameSite},
 };
-use poem_openapi::{OpenApi, payload::Json};
+use poem_openapi::{
+    OpenApi,
+    param::{Header, Path},
+    payload::Json,
+};
+use uuid::Uuid;
 
 use crate::{
-    application::usecases::authenticate_user::AuthRequest,
+    application::{
+        services::{auth_provider::LoginCredentials, login_throttle::LoginLocked},
+        usecases::authenticate_user::AuthResponse,
+    },
+    domain::models::SessionClient,
     presentation::http::{
+        client_ip::ClientIp,
+        csrf::{add_csrf_cookie, clear_csrf_cookie},
         endpoints::root::{ApiState, EndpointsTags},
-        requests::AuthRequestDto,
-        responses::AuthResponseDto,
+        mappers::{map_account_erasure, map_identity, map_session},
+        requests::{
+            AuthRequestDto, ChangePasswordRequestDto, ConfirmEmailRequestDto, MagicLinkRequestDto,
+            OAuthCallbackParams, PasswordResetRequestDto, RegisterRequestDto,
+            ResetPasswordRequestDto, VerifyMagicLinkRequestDto,
+        },
+        responses::{
+            AccountErasureDto, AuthResponseDto, RedirectResponse, SessionDto, UserIdentityDto,
+        },
+        security::{JwtAuth, SessionCredentials},
     },
 };
 
+/// Longest `User-Agent` kept on a session.
+const MAX_DEVICE_LENGTH: usize = 256;
+
+/// Holds the provider, state and PKCE verifier of an OAuth sign-in until
+/// the provider redirects back.
+const OAUTH_ATTEMPT_COOKIE: &str = "oauth_attempt";
+const OAUTH_ATTEMPT_TTL: Duration = Duration::from_secs(600);
+
 #[derive(Clone)]
 pub struct AuthEndpoints {
     state: Arc<ApiState>,
@@ -26,28 +53,8 @@ impl AuthEndpoints {
     pub fn new(state: Arc<ApiState>) -> Self {
         Self { state }
     }
-}
-
-#[OpenApi]
-impl AuthEndpoints {
-    #[oai(path = "/auth/login", method = "post", tag = EndpointsTags::Auth)]
-    pub async fn login(
-        &self,
-        cookie_jar: &CookieJar,
-        request: Json<AuthRequestDto>,
-    ) -> PoemResult<Json<AuthResponseDto>> {
-        let payload = AuthRequest {
-            email: request.email.clone(),
-            display_name: request.display_name.clone(),
-        };
-
-        let response = self
-            .state
-            .auth_usecase
-            .execute(payload)
-            .await
-            .map_err(internal_error)?;
 
+    fn add_session_cookies(&self, cookie_jar: &CookieJar, response: AuthResponse) {
         let mut access_token_cookie = Cookie::new_with_str("access_token", response.access_token);
         access_token_cookie.set_http_only(true);
         access_token_cookie.set_secure(true);
@@ -69,6 +76,30 @@ impl AuthEndpoints {
 
         cookie_jar.add(access_token_cookie);
         cookie_jar.add(refresh_token_cookie);
+        add_csrf_cookie(cookie_jar);
+    }
+}
+
+#[OpenApi]
+impl AuthEndpoints {
+    #[oai(path = "/auth/login", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn login(
+        &self,
+        cookie_jar: &CookieJar,
+        client_ip: ClientIp,
+        #[oai(name = "User-Agent")] user_agent: Header<Option<String>>,
+        request: Json<AuthRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let credentials = map_credentials(request.0)?;
+
+        let response = self
+            .state
+            .auth_usecase
+            .execute(credentials, session_client(client_ip, user_agent.0))
+            .await
+            .map_err(map_error)?;
+
+        self.add_session_cookies(cookie_jar, response);
 
         Ok(Json(AuthResponseDto { success: true }))
     }
@@ -82,65 +113,556 @@ impl AuthEndpoints {
                 PoemError::from_string("refresh token not found", StatusCode::UNAUTHORIZED)
             })?;
 
-        let jwt_service =
-            crate::application::services::jwt::JwtService::new(self.state.jwt_config.clone());
-        let claims = jwt_service.verify(&refresh_token).map_err(|_| {
-            PoemError::from_string("invalid or expired refresh token", StatusCode::UNAUTHORIZED)
-        })?;
+        let response = self
+            .state
+            .auth_usecase
+            .refresh(&refresh_token)
+            .await
+            .map_err(map_refresh_error)?;
+
+        self.add_session_cookies(cookie_jar, response);
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Mails a single-use sign-in link to the email.
+    #[oai(path = "/auth/magic-link", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn request_magic_link(
+        &self,
+        request: Json<MagicLinkRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        self.state
+            .magic_link_usecase
+            .request(request.email.trim())
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Signs in with the token of a link mailed through
+    /// `POST /auth/magic-link`, setting the session cookies as login does.
+    #[oai(path = "/auth/magic-link/verify", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn verify_magic_link(
+        &self,
+        cookie_jar: &CookieJar,
+        client_ip: ClientIp,
+        #[oai(name = "User-Agent")] user_agent: Header<Option<String>>,
+        request: Json<VerifyMagicLinkRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let response = self
+            .state
+            .auth_usecase
+            .execute(
+                LoginCredentials::MagicLink {
+                    token: request.0.token,
+                },
+                session_client(client_ip, user_agent.0),
+            )
+            .await
+            .map_err(map_error)?;
+
+        self.add_session_cookies(cookie_jar, response);
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Starts signing in with an OAuth provider (`google` or `github`) by
+    /// redirecting the browser to it. The provider sends it back to
+    /// `/auth/oauth/{provider}/callback`.
+    #[oai(path = "/auth/oauth/:provider", method = "get", tag = EndpointsTags::Auth)]
+    pub async fn oauth_authorize(
+        &self,
+        cookie_jar: &CookieJar,
+        provider: Path<String>,
+    ) -> PoemResult<RedirectResponse> {
+        let (url, attempt) = self
+            .state
+            .auth_usecase
+            .authorize(&provider.0)
+            .map_err(map_error)?;
+
+        let mut attempt_cookie = Cookie::new_with_str(
+            OAUTH_ATTEMPT_COOKIE,
+            format!("{}:{}:{}", provider.0, attempt.state, attempt.code_verifier),
+        );
+        attempt_cookie.set_http_only(true);
+        attempt_cookie.set_secure(true);
+        // Lax, or the cookie would stay behind on the provider's redirect.
+        attempt_cookie.set_same_site(Some(SameSite::Lax));
+        attempt_cookie.set_path("/");
+        attempt_cookie.set_max_age(OAUTH_ATTEMPT_TTL);
+        cookie_jar.add(attempt_cookie);
+
+        Ok(RedirectResponse::Found(url))
+    }
+
+    /// Finishes an OAuth sign-in: redeems the code, starts a session and
+    /// redirects to the configured landing page.
+    #[oai(
+        path = "/auth/oauth/:provider/callback",
+        method = "get",
+        tag = EndpointsTags::Auth
+    )]
+    pub async fn oauth_callback(
+        &self,
+        cookie_jar: &CookieJar,
+        client_ip: ClientIp,
+        #[oai(name = "User-Agent")] user_agent: Header<Option<String>>,
+        provider: Path<String>,
+        callback: OAuthCallbackParams,
+    ) -> PoemResult<RedirectResponse> {
+        let attempt = cookie_jar
+            .get(OAUTH_ATTEMPT_COOKIE)
+            .map(|cookie| cookie.value_str().to_string());
+        cookie_jar.remove(OAUTH_ATTEMPT_COOKIE);
+
+        if let Some(error) = callback.error {
+            return Err(PoemError::from_string(
+                format!("invalid credentials: {error}"),
+                StatusCode::UNAUTHORIZED,
+            ));
+        }
+        let code = callback
+            .code
+            .ok_or_else(|| PoemError::from_string("code is required", StatusCode::BAD_REQUEST))?;
+        // The state must be the one this browser was sent off with.
+        let code_verifier = attempt
+            .as_deref()
+            .and_then(|attempt| {
+                let mut parts = attempt.splitn(3, ':');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(attempted), Some(expected), Some(verifier))
+                        if attempted == provider.0
+                            && callback.state.as_deref() == Some(expected) =>
+                    {
+                        Some(verifier.to_string())
+                    }
+                    _ => None,
+                }
+            })
+            .ok_or_else(|| {
+                PoemError::from_string(
+                    "invalid or expired sign-in attempt",
+                    StatusCode::BAD_REQUEST,
+                )
+            })?;
 
         let response = self
             .state
             .auth_usecase
-            .refresh(claims.sub)
+            .execute(
+                LoginCredentials::AuthorizationCode {
+                    provider: provider.0,
+                    code,
+                    code_verifier,
+                },
+                session_client(client_ip, user_agent.0),
+            )
+            .await
+            .map_err(map_error)?;
+
+        self.add_session_cookies(cookie_jar, response);
+
+        Ok(RedirectResponse::Found(
+            self.state.oauth_success_url.clone(),
+        ))
+    }
+
+    /// Creates an account that signs in with a password. Its email stays
+    /// unconfirmed until the token mailed for it is redeemed at
+    /// `POST /auth/register/confirm`.
+    #[oai(path = "/auth/register", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn register(
+        &self,
+        request: Json<RegisterRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let request = request.0;
+        self.state
+            .password_usecase
+            .register(
+                request.email.trim(),
+                &request.password,
+                request.display_name,
+            )
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Confirms the email of an account created with a password.
+    #[oai(path = "/auth/register/confirm", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn confirm_email(
+        &self,
+        request: Json<ConfirmEmailRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        self.state
+            .password_usecase
+            .confirm(&request.token)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Signs out every session, this one included.
+    #[oai(path = "/auth/password", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn change_password(
+        &self,
+        auth: SessionCredentials,
+        request: Json<ChangePasswordRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        self.state
+            .password_usecase
+            .change(
+                user.user_id,
+                &request.current_password,
+                &request.new_password,
+            )
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Mails a reset token to the account's email. Answers alike whether or
+    /// not the email has an account.
+    #[oai(path = "/auth/password/forgot", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn forgot_password(
+        &self,
+        request: Json<PasswordResetRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        self.state
+            .password_usecase
+            .request_reset(request.email.trim())
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Sets a new password with a token mailed through
+    /// `POST /auth/password/forgot` or issued through
+    /// `POST /admin/password-resets`, and signs out every session.
+    #[oai(path = "/auth/password/reset", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn reset_password(
+        &self,
+        request: Json<ResetPasswordRequestDto>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        self.state
+            .password_usecase
+            .reset(&request.token, &request.new_password)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Ways of signing in that lead to the current account.
+    #[oai(path = "/auth/identities", method = "get", tag = EndpointsTags::Auth)]
+    pub async fn list_identities(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<Vec<UserIdentityDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let identities = self
+            .state
+            .identity_usecase
+            .list(user.user_id)
             .await
             .map_err(internal_error)?;
 
-        let mut access_token_cookie = Cookie::new_with_str("access_token", response.access_token);
-        access_token_cookie.set_http_only(true);
-        access_token_cookie.set_secure(true);
-        access_token_cookie.set_same_site(Some(SameSite::Strict));
-        access_token_cookie.set_path("/");
-        access_token_cookie.set_max_age(Duration::from_secs(
-            self.state.jwt_config.expiration.as_secs(),
-        ));
+        Ok(Json(identities.iter().map(map_identity).collect()))
+    }
 
-        let mut refresh_token_cookie =
-            Cookie::new_with_str("refresh_token", response.refresh_token);
-        refresh_token_cookie.set_http_only(true);
-        refresh_token_cookie.set_secure(true);
-        refresh_token_cookie.set_same_site(Some(SameSite::Strict));
-        refresh_token_cookie.set_path("/");
-        refresh_token_cookie.set_max_age(Duration::from_secs(
-            self.state.jwt_config.refresh_expiration.as_secs(),
-        ));
+    /// Links another sign-in method to the current account. The body takes
+    /// the same credentials as `POST /auth/login`, which are verified first.
+    #[oai(path = "/auth/identities", method = "post", tag = EndpointsTags::Auth)]
+    pub async fn link_identity(
+        &self,
+        auth: SessionCredentials,
+        request: Json<AuthRequestDto>,
+    ) -> PoemResult<Json<UserIdentityDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let credentials = map_credentials(request.0)?;
 
-        cookie_jar.add(access_token_cookie);
-        cookie_jar.add(refresh_token_cookie);
+        let identity = self
+            .state
+            .identity_usecase
+            .link(user.user_id, credentials)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_identity(&identity)))
+    }
+
+    #[oai(
+        path = "/auth/identities/:identity_id",
+        method = "delete",
+        tag = EndpointsTags::Auth
+    )]
+    pub async fn unlink_identity(
+        &self,
+        auth: SessionCredentials,
+        identity_id: Path<Uuid>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        self.state
+            .identity_usecase
+            .unlink(user.user_id, identity_id.0)
+            .await
+            .map_err(map_error)?;
 
         Ok(Json(AuthResponseDto { success: true }))
     }
 
+    /// Clears the session cookies and revokes the refresh token with every
+    /// token issued from the same sign-in.
     #[oai(path = "/auth/logout", method = "post", tag = EndpointsTags::Auth)]
     pub async fn logout(&self, cookie_jar: &CookieJar) -> PoemResult<Json<AuthResponseDto>> {
-        let mut access_token_cookie = Cookie::named("access_token");
-        access_token_cookie.set_http_only(true);
-        access_token_cookie.set_secure(true);
-        access_token_cookie.set_same_site(Some(SameSite::Strict));
-        access_token_cookie.set_path("/");
-        access_token_cookie.make_removal();
+        if let Some(refresh_token) = cookie_jar.get("refresh_token") {
+            self.state
+                .auth_usecase
+                .logout(refresh_token.value_str())
+                .await
+                .map_err(internal_error)?;
+        }
 
-        let mut refresh_token_cookie = Cookie::named("refresh_token");
-        refresh_token_cookie.set_http_only(true);
-        refresh_token_cookie.set_secure(true);
-        refresh_token_cookie.set_same_site(Some(SameSite::Strict));
-        refresh_token_cookie.set_path("/");
-        refresh_token_cookie.make_removal();
+        clear_session_cookies(cookie_jar);
 
-        cookie_jar.add(access_token_cookie);
-        cookie_jar.add(refresh_token_cookie);
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Sessions of the signed-in user that can still refresh.
+    #[oai(path = "/auth/sessions", method = "get", tag = EndpointsTags::Auth)]
+    pub async fn list_sessions(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<Vec<SessionDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let sessions = self
+            .state
+            .session_usecase
+            .list(user.user_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(
+            sessions
+                .iter()
+                .map(|session| map_session(session, user.session_id))
+                .collect(),
+        ))
+    }
+
+    /// Signs out every session of the user, this one included.
+    #[oai(path = "/auth/sessions/all", method = "delete", tag = EndpointsTags::Auth)]
+    pub async fn revoke_all_sessions(
+        &self,
+        auth: SessionCredentials,
+        cookie_jar: &CookieJar,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        self.state
+            .session_usecase
+            .revoke_all(user.user_id)
+            .await
+            .map_err(map_error)?;
+        clear_session_cookies(cookie_jar);
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Signs the session out, e.g. a device that was lost. Its access
+    /// tokens stop working within seconds on every instance.
+    #[oai(
+        path = "/auth/sessions/:session_id",
+        method = "delete",
+        tag = EndpointsTags::Auth
+    )]
+    pub async fn revoke_session(
+        &self,
+        auth: SessionCredentials,
+        cookie_jar: &CookieJar,
+        session_id: Path<Uuid>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        self.state
+            .session_usecase
+            .revoke(user.user_id, session_id.0)
+            .await
+            .map_err(map_error)?;
+        if user.session_id == Some(session_id.0) {
+            clear_session_cookies(cookie_jar);
+        }
 
         Ok(Json(AuthResponseDto { success: true }))
     }
+
+    /// Deletes the account for good: signs out every session, deletes
+    /// messenger tokens, API keys and sign-in methods at once, then purges
+    /// the personal message history in the background and deletes the user.
+    /// Shared organizations keep the tokens shared with them and the
+    /// messages sent in them, without the sender. The returned record is
+    /// kept as proof of the erasure.
+    #[oai(path = "/me", method = "delete", tag = EndpointsTags::Auth)]
+    pub async fn delete_account(
+        &self,
+        auth: SessionCredentials,
+        cookie_jar: &CookieJar,
+    ) -> PoemResult<Json<AccountErasureDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let erasure = self
+            .state
+            .account_erasure_usecase
+            .request(user.user_id)
+            .await
+            .map_err(map_error)?;
+        clear_session_cookies(cookie_jar);
+
+        Ok(Json(map_account_erasure(&erasure)))
+    }
+}
+
+fn clear_session_cookies(cookie_jar: &CookieJar) {
+    let mut access_token_cookie = Cookie::named("access_token");
+    access_token_cookie.set_http_only(true);
+    access_token_cookie.set_secure(true);
+    access_token_cookie.set_same_site(Some(SameSite::Strict));
+    access_token_cookie.set_path("/");
+    access_token_cookie.make_removal();
+
+    let mut refresh_token_cookie = Cookie::named("refresh_token");
+    refresh_token_cookie.set_http_only(true);
+    refresh_token_cookie.set_secure(true);
+    refresh_token_cookie.set_same_site(Some(SameSite::Strict));
+    refresh_token_cookie.set_path("/");
+    refresh_token_cookie.make_removal();
+
+    cookie_jar.add(access_token_cookie);
+    cookie_jar.add(refresh_token_cookie);
+    clear_csrf_cookie(cookie_jar);
+}
+
+fn session_client(client_ip: ClientIp, user_agent: Option<String>) -> SessionClient {
+    SessionClient {
+        device: user_agent.map(|device| device.chars().take(MAX_DEVICE_LENGTH).collect()),
+        ip: client_ip.0.map(|ip| ip.to_string()),
+    }
+}
+
+fn map_credentials(request: AuthRequestDto) -> PoemResult<LoginCredentials> {
+    match request {
+        AuthRequestDto {
+            id_token: Some(id_token),
+            ..
+        } => Ok(LoginCredentials::IdToken { id_token }),
+        AuthRequestDto {
+            email: Some(email),
+            password: Some(password),
+            ..
+        } => Ok(LoginCredentials::Password {
+            email: email.trim().to_string(),
+            password,
+        }),
+        AuthRequestDto {
+            email: Some(email),
+            display_name,
+            ..
+        } => Ok(LoginCredentials::Email {
+            email: email.trim().to_string(),
+            display_name,
+        }),
+        _ => Err(PoemError::from_string(
+            "email or id_token is required",
+            StatusCode::BAD_REQUEST,
+        )),
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    if let Some(locked) = err.downcast_ref::<LoginLocked>() {
+        // Rounded up, so retrying on time isn't refused again.
+        let seconds = locked.retry_after.as_millis().div_ceil(1000).max(1);
+        return PoemError::from_response(
+            Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header(RETRY_AFTER, seconds.to_string())
+                .body(locked.to_string()),
+        );
+    }
+    let message = err.to_string();
+    let status = if message.contains("invalid credentials") {
+        StatusCode::UNAUTHORIZED
+    } else if message.contains("unsupported credentials")
+        || message.contains("password must")
+        || message.contains("invalid or expired")
+        || message.contains("last sign-in method")
+    {
+        StatusCode::BAD_REQUEST
+    } else if message.contains("account disabled") {
+        StatusCode::FORBIDDEN
+    } else if message.contains("already registered") || message.contains("already linked") {
+        StatusCode::CONFLICT
+    } else if message.contains("not found") {
+        StatusCode::NOT_FOUND
+    } else if message.contains("not available") {
+        StatusCode::NOT_IMPLEMENTED
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    PoemError::from_string(message, status)
+}
+
+fn map_refresh_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    let status = if message.contains("invalid or expired") || message.contains("reuse detected") {
+        StatusCode::UNAUTHORIZED
+    } else {
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+    PoemError::from_string(message, status)
 }
 
 fn internal_error(err: anyhow::Error) -> PoemError {
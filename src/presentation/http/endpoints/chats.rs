@@ -1,19 +1,21 @@
 use std::sync::Arc;
 
-use poem::{Error as PoemError, Result as PoemResult, web::cookie::CookieJar};
+use poem::{Error as PoemError, Result as PoemResult};
 use poem_openapi::{
     OpenApi,
-    param::{Path, Query},
+    param::{Header, Path, Query},
     payload::Json,
 };
+use uuid::Uuid;
 
 use crate::{
     application::services::messenger::PaginationParams,
     presentation::http::{
         endpoints::root::{ApiState, EndpointsTags},
         mappers::map_chat,
+        requests::ChatActionRequestDto,
         responses::PaginatedChatsDto,
-        security::JwtAuth,
+        security::{JwtAuth, SessionCredentials},
     },
     presentation::models::MessengerKind,
 };
@@ -38,12 +40,20 @@ impl ChatsEndpoints {
     )]
     pub async fn list_chats(
         &self,
-        cookie_jar: &CookieJar,
+        auth: SessionCredentials,
         messenger: Path<MessengerKind>,
         limit: Query<Option<u32>>,
         offset: Query<Option<u32>>,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
     ) -> PoemResult<Json<PaginatedChatsDto>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
 
         let pagination = PaginationParams {
             limit: limit.0,
@@ -53,7 +63,12 @@ impl ChatsEndpoints {
         let result = self
             .state
             .list_chats_usecase
-            .execute(user.user_id, messenger.0.into(), pagination)
+            .execute(
+                organization_id,
+                user.user_id,
+                messenger.0.into(),
+                pagination,
+            )
             .await
             .map_err(bad_request)?;
 
@@ -63,6 +78,45 @@ impl ChatsEndpoints {
             next_offset: result.next_offset,
         }))
     }
+
+    /// Shows a transient status such as "typing…" in the chat.
+    #[oai(
+        path = "/messengers/:messenger/chats/:chat_id/actions",
+        method = "post",
+        tag = EndpointsTags::Chats,
+    )]
+    pub async fn send_chat_action(
+        &self,
+        auth: SessionCredentials,
+        messenger: Path<MessengerKind>,
+        chat_id: Path<String>,
+        request: Json<ChatActionRequestDto>,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+    ) -> PoemResult<()> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+
+        self.state
+            .send_chat_action_usecase
+            .execute(
+                organization_id,
+                user.user_id,
+                messenger.0.into(),
+                &chat_id.0,
+                request.action.into(),
+            )
+            .await
+            .map_err(bad_request)?;
+
+        Ok(())
+    }
 }
 
 fn bad_request(err: anyhow::Error) -> PoemError {
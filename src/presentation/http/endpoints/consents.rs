@@ -0,0 +1,215 @@
+use std::sync::Arc;
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{OpenApi, param::Header, payload::Json};
+use uuid::Uuid;
+
+use crate::{
+    application::usecases::manage_consent::{
+        ConfirmOptInRequest, RecordConsentRequest, RequestOptInRequest,
+    },
+    presentation::http::{
+        endpoints::root::{ApiState, EndpointsTags},
+        mappers::map_consent,
+        requests::{
+            ConfirmOptInRequestDto, RecordConsentRequestDto, RequestOptInRequestDto,
+            RevokeConsentRequestDto,
+        },
+        responses::ConsentDto,
+        security::{JwtAuth, SessionCredentials},
+    },
+};
+
+#[derive(Clone)]
+pub struct ConsentsEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl ConsentsEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl ConsentsEndpoints {
+    #[oai(path = "/consents", method = "get", tag = EndpointsTags::Consents)]
+    pub async fn list_consents(
+        &self,
+        auth: SessionCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+    ) -> PoemResult<Json<Vec<ConsentDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+
+        let records = self
+            .state
+            .consent_usecase
+            .list(organization_id)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Json(records.iter().map(map_consent).collect()))
+    }
+
+    #[oai(path = "/consents", method = "post", tag = EndpointsTags::Consents)]
+    pub async fn record_consent(
+        &self,
+        auth: SessionCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        request: Json<RecordConsentRequestDto>,
+    ) -> PoemResult<Json<ConsentDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        user.require_write()?;
+
+        let record = self
+            .state
+            .consent_usecase
+            .record(RecordConsentRequest {
+                organization_id,
+                messenger: request.messenger.into(),
+                recipient: request.recipient.clone(),
+                source: request.source.clone(),
+                proof: request.proof.clone(),
+            })
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Json(map_consent(&record)))
+    }
+
+    #[oai(
+        path = "/consents/opt-in",
+        method = "post",
+        tag = EndpointsTags::Consents,
+    )]
+    pub async fn request_opt_in(
+        &self,
+        auth: SessionCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        request: Json<RequestOptInRequestDto>,
+    ) -> PoemResult<Json<ConsentDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        user.require_write()?;
+
+        let record = self
+            .state
+            .consent_usecase
+            .request_opt_in(RequestOptInRequest {
+                organization_id,
+                user_id: user.user_id,
+                messenger: request.messenger.into(),
+                recipient: request.recipient.clone(),
+                source: request.source.clone(),
+            })
+            .await
+            .map_err(bad_request)?;
+
+        Ok(Json(map_consent(&record)))
+    }
+
+    #[oai(
+        path = "/consents/confirm",
+        method = "post",
+        tag = EndpointsTags::Consents,
+    )]
+    pub async fn confirm_opt_in(
+        &self,
+        auth: SessionCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        request: Json<ConfirmOptInRequestDto>,
+    ) -> PoemResult<Json<ConsentDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        user.require_write()?;
+
+        let record = self
+            .state
+            .consent_usecase
+            .confirm(ConfirmOptInRequest {
+                organization_id,
+                messenger: request.messenger.into(),
+                recipient: request.recipient.clone(),
+                reply: request.reply.clone(),
+            })
+            .await
+            .map_err(bad_request)?;
+
+        Ok(Json(map_consent(&record)))
+    }
+
+    #[oai(
+        path = "/consents/revoke",
+        method = "post",
+        tag = EndpointsTags::Consents,
+    )]
+    pub async fn revoke_consent(
+        &self,
+        auth: SessionCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        request: Json<RevokeConsentRequestDto>,
+    ) -> PoemResult<Json<ConsentDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        user.require_write()?;
+
+        let record = self
+            .state
+            .consent_usecase
+            .revoke(
+                organization_id,
+                request.messenger.into(),
+                &request.recipient,
+            )
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("not found") {
+                    PoemError::from_string("consent not found", StatusCode::NOT_FOUND)
+                } else {
+                    internal_error(e)
+                }
+            })?;
+
+        Ok(Json(map_consent(&record)))
+    }
+}
+
+fn internal_error(err: anyhow::Error) -> PoemError {
+    PoemError::from_string(err.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn bad_request(err: anyhow::Error) -> PoemError {
+    PoemError::from_string(err.to_string(), StatusCode::BAD_REQUEST)
+}
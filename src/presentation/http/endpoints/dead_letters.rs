@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{
+    OpenApi,
+    param::{Header, Query},
+    payload::Json,
+};
+use uuid::Uuid;
+
+use crate::presentation::http::{
+    endpoints::root::{ApiState, EndpointsTags},
+    mappers::map_dead_letter,
+    requests::RedriveDeadLetterRequestDto,
+    responses::{DeadLetterDto, SendMessageResponseDto},
+    security::{JwtAuth, SessionCredentials},
+};
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+
+#[derive(Clone)]
+pub struct DeadLettersEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl DeadLettersEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl DeadLettersEndpoints {
+    #[oai(path = "/dead-letters", method = "get", tag = EndpointsTags::Messages)]
+    pub async fn list_dead_letters(
+        &self,
+        auth: SessionCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        limit: Query<Option<u32>>,
+    ) -> PoemResult<Json<Vec<DeadLetterDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let limit = limit.0.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+        let dead_letters = self
+            .state
+            .dead_letter_usecase
+            .list(organization_id, limit)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(
+            dead_letters
+                .iter()
+                .map(|dead_letter| map_dead_letter(dead_letter, self.state.public_ids.as_ref()))
+                .collect(),
+        ))
+    }
+
+    #[oai(
+        path = "/dead-letters/actions/redrive",
+        method = "post",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn redrive_dead_letter(
+        &self,
+        auth: SessionCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        request: Json<RedriveDeadLetterRequestDto>,
+    ) -> PoemResult<Json<SendMessageResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+
+        let message_id = self
+            .state
+            .dead_letter_usecase
+            .redrive(organization_id, request.id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(SendMessageResponseDto {
+            message_id,
+            reference: self.state.public_ids.encode(&message_id),
+            sent: None,
+        }))
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    if message.contains("forbidden") {
+        PoemError::from_string("forbidden", StatusCode::FORBIDDEN)
+    } else if message.contains("not found") {
+        PoemError::from_string("dead letter not found", StatusCode::NOT_FOUND)
+    } else if message.contains("not available") {
+        PoemError::from_string(message, StatusCode::NOT_IMPLEMENTED)
+    } else if message.contains("deadline") {
+        PoemError::from_string(message, StatusCode::BAD_REQUEST)
+    } else {
+        PoemError::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
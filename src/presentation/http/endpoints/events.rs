@@ -0,0 +1,139 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{OpenApi, param::Header, payload::Json};
+use serde_json::Value;
+
+use crate::{
+    application::usecases::ingest_event::IngestEventRequest,
+    domain::models::ApiKeyScope,
+    presentation::http::{
+        endpoints::{
+            messages::validate_idempotency_key,
+            root::{ApiState, EndpointsTags},
+        },
+        requests::EventRequestDto,
+        responses::{EventResponseDto, FiredRuleDto},
+        security::{ApiKeyAuth, ServiceCredentials},
+    },
+};
+
+#[derive(Clone)]
+pub struct EventsEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl EventsEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl EventsEndpoints {
+    /// Schedules a message for every routing rule that matches the event
+    /// and reports which rules fired.
+    #[oai(path = "/events", method = "post", tag = EndpointsTags::Routing)]
+    pub async fn ingest_event(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "Idempotency-Key")] idempotency_key: Header<Option<String>>,
+        request: Json<EventRequestDto>,
+    ) -> PoemResult<Json<EventResponseDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+        user.require_write()?;
+        let idempotency_key = validate_idempotency_key(idempotency_key.0)?;
+        let request = request.0;
+
+        let mut metadata = BTreeMap::new();
+        flatten_payload("", &request.payload, &mut metadata);
+        let fired = self
+            .state
+            .ingest_event_usecase
+            .execute(IngestEventRequest {
+                user_id: user.user_id,
+                name: request.name.clone(),
+                class: request.class.into(),
+                tags: request.tags,
+                metadata,
+                deadline: request.deadline,
+                idempotency_key,
+            })
+            .await
+            .map_err(internal_error)?;
+
+        let mut rules = Vec::with_capacity(fired.len());
+        let mut scheduled = 0;
+        let mut failed = 0;
+        for rule in fired {
+            match rule.result {
+                Ok(response) => {
+                    scheduled += 1;
+                    rules.push(FiredRuleDto {
+                        rule_id: rule.rule_id,
+                        rule_name: rule.rule_name,
+                        success: true,
+                        message_id: Some(response.message_id),
+                        reference: Some(self.state.public_ids.encode(&response.message_id)),
+                        error: None,
+                    });
+                }
+                Err(err) => {
+                    failed += 1;
+                    rules.push(FiredRuleDto {
+                        rule_id: rule.rule_id,
+                        rule_name: rule.rule_name,
+                        success: false,
+                        message_id: None,
+                        reference: None,
+                        error: Some(err.to_string()),
+                    });
+                }
+            }
+        }
+
+        Ok(Json(EventResponseDto {
+            event: request.name,
+            rules,
+            scheduled,
+            failed,
+        }))
+    }
+}
+
+/// Nested objects become dotted keys; arrays are kept as JSON and nulls
+/// are dropped.
+pub(crate) fn flatten_payload<'a>(
+    prefix: &str,
+    payload: impl IntoIterator<Item = (&'a String, &'a Value)>,
+    out: &mut BTreeMap<String, String>,
+) {
+    for (key, value) in payload {
+        let key = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{prefix}.{key}")
+        };
+        match value {
+            Value::Null => {}
+            Value::String(value) => {
+                out.insert(key, value.clone());
+            }
+            Value::Object(object) => flatten_payload(&key, object, out),
+            other => {
+                out.insert(key, other.to_string());
+            }
+        }
+    }
+}
+
+fn internal_error(err: anyhow::Error) -> PoemError {
+    PoemError::from_string(err.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+}
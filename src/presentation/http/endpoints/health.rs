@@ -1,8 +1,28 @@
-use std::sync::Arc;
+use std::{fmt::Write, sync::Arc};
 
-use poem_openapi::{OpenApi, payload::PlainText};
+use poem_openapi::{
+    OpenApi,
+    payload::{Json, PlainText},
+};
 
-use crate::presentation::http::endpoints::root::{ApiState, EndpointsTags};
+use crate::{
+    application::{
+        services::{
+            database_stats::{DatabaseStats, PoolStats},
+            messenger::ConnectionPoolStats,
+            queue_stats::{ConsumerStats, QueueStats},
+        },
+        usecases::get_messenger_health::MessengerHealthReport,
+    },
+    presentation::http::{
+        endpoints::root::{ApiState, EndpointsTags},
+        mappers::map_readiness,
+        responses::ReadinessResponse,
+    },
+};
+
+/// A metric name, its help text and how to read it off one sample.
+type Gauge<T, V> = (&'static str, &'static str, fn(&T) -> V);
 
 #[derive(Clone)]
 pub struct HealthEndpoints {
@@ -22,4 +42,209 @@ impl HealthEndpoints {
         let _ = &self.state;
         PlainText("OK")
     }
+
+    /// Liveness: answers as long as the process serves requests, whatever
+    /// state its dependencies are in, so only a hung process gets restarted.
+    #[oai(path = "/health/live", method = "get", tag = EndpointsTags::Health)]
+    pub async fn live(&self) -> PlainText<&'static str> {
+        PlainText("OK")
+    }
+
+    /// Readiness: 503 while the database, the bus or the workers are down,
+    /// with the failing checks in the body.
+    #[oai(path = "/health/ready", method = "get", tag = EndpointsTags::Health)]
+    pub async fn ready(&self) -> ReadinessResponse {
+        let readiness = self.state.readiness.check().await;
+        let dto = map_readiness(&readiness);
+        if readiness.is_ready() {
+            ReadinessResponse::Ready(Json(dto))
+        } else {
+            ReadinessResponse::NotReady(Json(dto))
+        }
+    }
+
+    /// Prometheus text exposition. Left unauthenticated like `/health` so
+    /// scrapers can reach it; queue figures are omitted when the bus has none.
+    #[oai(path = "/metrics", method = "get", tag = EndpointsTags::Health)]
+    pub async fn metrics(&self) -> PlainText<String> {
+        let mut body = String::new();
+        write_database_metrics(&mut body, &self.state.database_monitor.stats());
+        write_http_pool_metrics(&mut body, &self.state.messenger_gateway.pool_stats());
+        write_delivery_metrics(
+            &mut body,
+            &self.state.get_messenger_health_usecase.execute(),
+        );
+        if let Ok(stats) = self.state.get_queue_stats_usecase.execute().await {
+            write_queue_metrics(&mut body, &stats);
+        }
+        PlainText(body)
+    }
+}
+
+fn write_database_metrics(body: &mut String, stats: &DatabaseStats) {
+    let gauges: [Gauge<PoolStats, u32>; 3] = [
+        (
+            "messaging_db_pool_connections",
+            "Open database connections, idle or in use.",
+            |pool| pool.size,
+        ),
+        (
+            "messaging_db_pool_idle",
+            "Open database connections not in use.",
+            |pool| pool.idle,
+        ),
+        (
+            "messaging_db_pool_max_connections",
+            "Connections the pool may open.",
+            |pool| pool.max_connections,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        let _ = writeln!(body, "# HELP {name} {help}\n# TYPE {name} gauge");
+        for pool in &stats.pools {
+            let _ = writeln!(body, "{name}{{pool=\"{}\"}} {}", pool.name, value(pool));
+        }
+    }
+
+    let _ = writeln!(
+        body,
+        "# HELP messaging_db_retries_total Repository calls repeated after a transient error.\n\
+         # TYPE messaging_db_retries_total counter\n\
+         messaging_db_retries_total {}\n\
+         # HELP messaging_db_pool_timeouts_total Calls that found no free connection in time.\n\
+         # TYPE messaging_db_pool_timeouts_total counter\n\
+         messaging_db_pool_timeouts_total {}",
+        stats.retries, stats.pool_timeouts
+    );
+}
+
+fn write_http_pool_metrics(body: &mut String, pools: &[ConnectionPoolStats]) {
+    let _ = writeln!(
+        body,
+        "# HELP messaging_messenger_requests_total Requests made to the messenger API.\n\
+         # TYPE messaging_messenger_requests_total counter"
+    );
+    for pool in pools {
+        let _ = writeln!(
+            body,
+            "messaging_messenger_requests_total{{messenger=\"{}\"}} {}",
+            pool.messenger.as_str(),
+            pool.requests
+        );
+    }
+    let _ = writeln!(
+        body,
+        "# HELP messaging_messenger_connections_total Connections dialled to the messenger \
+         API; requests beyond these reused one.\n\
+         # TYPE messaging_messenger_connections_total counter"
+    );
+    for pool in pools {
+        let _ = writeln!(
+            body,
+            "messaging_messenger_connections_total{{messenger=\"{}\"}} {}",
+            pool.messenger.as_str(),
+            pool.connections
+        );
+    }
+}
+
+fn write_delivery_metrics(body: &mut String, report: &MessengerHealthReport) {
+    let _ = writeln!(
+        body,
+        "# HELP messaging_messenger_deliveries_total Sends to the messenger by outcome.\n\
+         # TYPE messaging_messenger_deliveries_total counter"
+    );
+    for deliveries in &report.messengers {
+        let messenger = deliveries.messenger.as_str();
+        let _ = writeln!(
+            body,
+            "messaging_messenger_deliveries_total{{messenger=\"{messenger}\",outcome=\"sent\"}} {}\n\
+             messaging_messenger_deliveries_total{{messenger=\"{messenger}\",outcome=\"failed\"}} {}",
+            deliveries.total.sent, deliveries.total.failed
+        );
+    }
+    // Windows without sends have no rate and are left out rather than
+    // reported as 0.
+    let _ = writeln!(
+        body,
+        "# HELP messaging_messenger_success_ratio Share of the sends to the messenger that \
+         succeeded over the window.\n\
+         # TYPE messaging_messenger_success_ratio gauge"
+    );
+    for deliveries in &report.messengers {
+        for (window, counts) in &deliveries.windows {
+            if let Some(rate) = counts.success_rate() {
+                let _ = writeln!(
+                    body,
+                    "messaging_messenger_success_ratio{{messenger=\"{}\",window=\"{}s\"}} {rate}",
+                    deliveries.messenger.as_str(),
+                    window.num_seconds()
+                );
+            }
+        }
+    }
+    let _ = writeln!(
+        body,
+        "# HELP messaging_messenger_success_target Success ratio the messengers should reach.\n\
+         # TYPE messaging_messenger_success_target gauge\n\
+         messaging_messenger_success_target {}",
+        report.target
+    );
+}
+
+fn write_queue_metrics(body: &mut String, stats: &QueueStats) {
+    let stream = &stats.stream;
+    let _ = writeln!(
+        body,
+        "# HELP messaging_queue_messages Messages stored in the outbound stream.\n\
+         # TYPE messaging_queue_messages gauge\n\
+         messaging_queue_messages{{stream=\"{stream}\"}} {}",
+        stats.messages
+    );
+    if let Some(bytes) = stats.bytes {
+        let _ = writeln!(
+            body,
+            "# HELP messaging_queue_bytes Bytes stored in the outbound stream.\n\
+             # TYPE messaging_queue_bytes gauge\n\
+             messaging_queue_bytes{{stream=\"{stream}\"}} {bytes}"
+        );
+    }
+
+    // Figures the bus does not report are left out rather than exported as 0.
+    let gauges: [Gauge<ConsumerStats, Option<u64>>; 3] = [
+        (
+            "messaging_consumer_pending",
+            "Messages not yet delivered to the consumer.",
+            |consumer| Some(consumer.pending),
+        ),
+        (
+            "messaging_consumer_ack_pending",
+            "Messages delivered but not yet acknowledged.",
+            |consumer| consumer.ack_pending,
+        ),
+        (
+            "messaging_consumer_redelivered",
+            "Unacknowledged messages delivered more than once.",
+            |consumer| consumer.redelivered,
+        ),
+    ];
+    for (name, help, value) in gauges {
+        if stats
+            .consumers
+            .iter()
+            .all(|consumer| value(consumer).is_none())
+        {
+            continue;
+        }
+        let _ = writeln!(body, "# HELP {name} {help}\n# TYPE {name} gauge");
+        for consumer in &stats.consumers {
+            if let Some(value) = value(consumer) {
+                let _ = writeln!(
+                    body,
+                    "{name}{{stream=\"{stream}\",consumer=\"{}\"}} {value}",
+                    consumer.name
+                );
+            }
+        }
+    }
 }
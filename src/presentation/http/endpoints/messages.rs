@@ -1,24 +1,48 @@
-use std::sync::Arc;
+use std::{pin::Pin, sync::Arc, time::Duration};
 
-use poem::{Result as PoemResult, web::cookie::CookieJar};
-use poem_openapi::{OpenApi, param::Query, payload::Json};
+use poem::{Body, Result as PoemResult};
+use poem_openapi::{
+    OpenApi,
+    param::{Header, Query},
+    payload::{Binary, EventStream, Json},
+};
+use tokio_stream::{Stream, StreamExt, wrappers::ReceiverStream};
+use uuid::Uuid;
 
 use crate::{
     application::usecases::{
-        retry_message::RetryMessageRequest, schedule_message::ScheduleMessageRequest,
+        preview_message::PreviewMessageRequest, retry_message::RetryMessageRequest,
+        schedule_message::ScheduleMessageRequest,
     },
-    presentation::http::{
-        endpoints::root::{ApiState, EndpointsTags},
-        mappers::{map_attempt, map_history},
-        requests::{BatchSendRequestDto, RetryMessageRequestDto, SendMessageRequestDto},
-        responses::{
-            BatchSendItemResultDto, BatchSendResponseDto, MessageAttemptDto, MessageHistoryDto,
-            PaginatedMessagesDto, SendMessageResponseDto,
+    domain::models::{ApiKeyScope, MessageHistoryFilter, RoutingInput, TokenSelector},
+    presentation::{
+        http::{
+            endpoints::root::{ApiState, EndpointsTags},
+            mappers::{
+                HISTORY_CSV_HEADER, map_attempt, map_history, map_history_csv, map_history_jsonl,
+                map_link, map_preview, map_status_change, map_status_counts, resolve_message_id,
+            },
+            requests::{
+                BatchSendRequestDto, MessageFilterParams, PreviewMessageRequestDto,
+                RetryMessageRequestDto, SendMessageRequestDto,
+            },
+            responses::{
+                BatchSendItemResultDto, BatchSendResponseDto, MessageAttemptDto,
+                MessageExportResponse, MessageHistoryDto, MessagePreviewDto,
+                MessageStatusCountsDto, MessageStatusEventDto, PaginatedMessagesDto,
+                RelatedMessagesDto, SendMessageResponseDto, StatusLinkDto,
+            },
+            security::{ApiKeyAuth, ServiceCredentials},
         },
-        security::JwtAuth,
+        models::{DeliveryModeKind, ExportFormatKind, MessengerKind},
     },
 };
 
+/// Comments sent on an idle event stream so proxies keep it open.
+const STATUS_EVENTS_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+type StatusEvents = Pin<Box<dyn Stream<Item = MessageStatusEventDto> + Send>>;
+
 #[derive(Clone)]
 pub struct MessagesEndpoints {
     state: Arc<ApiState>,
@@ -28,10 +52,79 @@ impl MessagesEndpoints {
     pub fn new(state: Arc<ApiState>) -> Self {
         Self { state }
     }
+
+    /// Sends that name no destination are routed by the user's rules.
+    async fn schedule_request(
+        &self,
+        organization_id: Uuid,
+        user_id: Uuid,
+        request: &SendMessageRequestDto,
+        thread_parent: Option<Uuid>,
+        idempotency_key: Option<String>,
+    ) -> anyhow::Result<ScheduleMessageRequest> {
+        let (messenger, recipient, text, priority) = match (request.messenger, &request.recipient) {
+            (Some(messenger), Some(recipient)) => (
+                messenger.into(),
+                recipient.clone(),
+                request.text.clone(),
+                request.priority.into(),
+            ),
+            (None, None) => {
+                let routed = self
+                    .state
+                    .schedule_message_usecase
+                    .route(
+                        user_id,
+                        &RoutingInput {
+                            event: None,
+                            class: request.class.into(),
+                            tags: request.tags.clone(),
+                            metadata: request.metadata.clone(),
+                            text: request.text.clone(),
+                        },
+                    )
+                    .await?;
+                (
+                    routed.messenger,
+                    routed.recipient,
+                    routed.text,
+                    routed.priority.unwrap_or(request.priority.into()),
+                )
+            }
+            _ => anyhow::bail!("messenger and recipient must be given together"),
+        };
+        let token = match (request.token_id, &request.token_label) {
+            (None, None) => TokenSelector::Latest,
+            (Some(id), None) => TokenSelector::Id(id),
+            (None, Some(label)) => TokenSelector::Label(label.clone()),
+            (Some(_), Some(_)) => {
+                anyhow::bail!("token_id and token_label cannot be given together")
+            }
+        };
+
+        Ok(ScheduleMessageRequest {
+            organization_id,
+            user_id,
+            messenger,
+            recipient,
+            text,
+            class: request.class.into(),
+            priority,
+            requested_by: request.requested_by.into(),
+            deadline: request.deadline,
+            thread_parent,
+            idempotency_key,
+            token,
+            tags: request.tags.clone(),
+        })
+    }
 }
 
 #[OpenApi]
 impl MessagesEndpoints {
+    /// With `delivery=sync` the message is sent within the request, for
+    /// latency-sensitive sends such as OTPs. It is queued instead when that
+    /// fails or exceeds `SYNC_DELIVERY_TIMEOUT_MS`.
     #[oai(
         path = "/messages",
         method = "post",
@@ -39,30 +132,105 @@ impl MessagesEndpoints {
     )]
     pub async fn send_message(
         &self,
-        cookie_jar: &CookieJar,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        #[oai(name = "Idempotency-Key")] idempotency_key: Header<Option<String>>,
+        delivery: Query<Option<DeliveryModeKind>>,
         request: Json<SendMessageRequestDto>,
     ) -> PoemResult<Json<SendMessageResponseDto>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
-        let payload = ScheduleMessageRequest {
-            user_id: user.user_id,
-            messenger: request.messenger.into(),
-            recipient: request.recipient.clone(),
-            text: request.text.clone(),
-            requested_by: request.requested_by.into(),
-        };
-
-        let response = self
-            .state
-            .schedule_message_usecase
-            .execute(payload)
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+        user.require_write()?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let idempotency_key = validate_idempotency_key(idempotency_key.0)?;
+        let thread_parent = request
+            .thread_parent
+            .as_deref()
+            .map(|value| resolve_message_id(value, self.state.public_ids.as_ref()))
+            .transpose()?;
+        let payload = self
+            .schedule_request(
+                organization_id,
+                user.user_id,
+                &request,
+                thread_parent,
+                idempotency_key,
+            )
             .await
-            .map_err(internal_error)?;
+            .map_err(routing_error)?;
+
+        let usecase = &self.state.schedule_message_usecase;
+        let result = match delivery.0.unwrap_or_default() {
+            DeliveryModeKind::Queued => usecase
+                .execute(payload)
+                .await
+                .map(|response| (response, None)),
+            DeliveryModeKind::Sync => usecase
+                .execute_sync(payload)
+                .await
+                .map(|(response, sent)| (response, Some(sent))),
+        };
+        let (response, sent) = result.map_err(|e| {
+            if e.to_string().contains("idempotency key conflict") {
+                poem::Error::from_string(e.to_string(), poem::http::StatusCode::CONFLICT)
+            } else if e.to_string().contains("idempotency key") {
+                poem::Error::from_string(
+                    e.to_string(),
+                    poem::http::StatusCode::UNPROCESSABLE_ENTITY,
+                )
+            } else {
+                internal_error(e)
+            }
+        })?;
 
         Ok(Json(SendMessageResponseDto {
             message_id: response.message_id,
+            reference: self.state.public_ids.encode(&response.message_id),
+            sent,
         }))
     }
 
+    #[oai(
+        path = "/messages/preview",
+        method = "post",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn preview_message(
+        &self,
+        auth: ServiceCredentials,
+        request: Json<PreviewMessageRequestDto>,
+    ) -> PoemResult<Json<MessagePreviewDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+
+        let preview = self
+            .state
+            .preview_message_usecase
+            .execute(PreviewMessageRequest {
+                messenger: request.messenger.into(),
+                text: request.text.clone(),
+            });
+
+        Ok(Json(map_preview(&preview)))
+    }
+
+    /// Messages of the organization, narrowed by the `status`, `messenger`,
+    /// `recipient`, `created_after`, `created_before` and `tag` query
+    /// parameters. `user_id` stands for that user's personal organization.
     #[oai(
         path = "/messages",
         method = "get",
@@ -70,26 +238,233 @@ impl MessagesEndpoints {
     )]
     pub async fn list_messages(
         &self,
-        cookie_jar: &CookieJar,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        limit: Query<Option<u32>>,
+        offset: Query<Option<u32>>,
+        filter: MessageFilterParams,
+    ) -> PoemResult<Json<PaginatedMessagesDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(
+                organization_id.0.or(filter.user_id),
+                &self.state.organization_usecase,
+            )
+            .await?;
+        let filter = map_filter(filter);
+
+        let result = self
+            .state
+            .list_messages_usecase
+            .execute(organization_id, filter, limit.0, offset.0)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("must be earlier") {
+                    bad_request(e)
+                } else {
+                    internal_error(e)
+                }
+            })?;
+
+        Ok(Json(PaginatedMessagesDto {
+            messages: result
+                .messages
+                .iter()
+                .map(|entry| map_history(entry, self.state.public_ids.as_ref()))
+                .collect(),
+            has_more: result.has_more,
+            next_offset: result.next_offset,
+        }))
+    }
+
+    /// The organization's whole history, filtered as in `GET /messages`,
+    /// streamed as CSV or JSON Lines. `user_id` stands for that user's
+    /// personal organization.
+    #[oai(
+        path = "/messages/export",
+        method = "get",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn export_messages(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        format: Query<ExportFormatKind>,
+        filter: MessageFilterParams,
+    ) -> PoemResult<MessageExportResponse> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(
+                organization_id.0.or(filter.user_id),
+                &self.state.organization_usecase,
+            )
+            .await?;
+        let filter = map_filter(filter);
+
+        let entries = self
+            .state
+            .list_messages_usecase
+            .export(organization_id, filter)
+            .map_err(bad_request)?;
+
+        let ids = self.state.public_ids.clone();
+        let map_entry = match format.0 {
+            ExportFormatKind::Csv => map_history_csv,
+            ExportFormatKind::Jsonl => map_history_jsonl,
+        };
+        let header = match format.0 {
+            ExportFormatKind::Csv => Some(Ok(HISTORY_CSV_HEADER.to_string())),
+            ExportFormatKind::Jsonl => None,
+        };
+        let lines = ReceiverStream::new(entries).map(move |entry| {
+            entry
+                .map(|entry| map_entry(&entry, ids.as_ref()))
+                .map_err(|err| std::io::Error::other(err.to_string()))
+        });
+        let body = Body::from_bytes_stream(tokio_stream::iter(header).chain(lines));
+
+        Ok(match format.0 {
+            ExportFormatKind::Csv => MessageExportResponse::Csv(
+                Binary(body),
+                "attachment; filename=\"messages.csv\"".to_string(),
+            ),
+            ExportFormatKind::Jsonl => MessageExportResponse::Jsonl(
+                Binary(body),
+                "attachment; filename=\"messages.jsonl\"".to_string(),
+            ),
+        })
+    }
+
+    /// Server-sent events for every status change of the organization's
+    /// messages from now on. Needs PostgreSQL, which relays the changes made
+    /// by every instance; on MySQL the stream stays silent.
+    #[oai(
+        path = "/messages/events",
+        method = "get",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn message_events(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+    ) -> PoemResult<EventStream<StatusEvents>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+
+        let ids = self.state.public_ids.clone();
+        let events = ReceiverStream::new(self.state.status_feed.subscribe(organization_id))
+            .map(move |change| map_status_change(&change, ids.as_ref()));
+        Ok(EventStream::new(Box::pin(events) as StatusEvents).keep_alive(STATUS_EVENTS_KEEP_ALIVE))
+    }
+
+    /// Messages sent to exactly `recipient`, newest first. Unlike the
+    /// `recipient` filter of `GET /messages` this is an exact, indexed match.
+    #[oai(
+        path = "/messages/by-recipient",
+        method = "get",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn list_messages_by_recipient(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        messenger: Query<MessengerKind>,
+        recipient: Query<String>,
         limit: Query<Option<u32>>,
         offset: Query<Option<u32>>,
     ) -> PoemResult<Json<PaginatedMessagesDto>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
 
         let result = self
             .state
             .list_messages_usecase
-            .execute(user.user_id, limit.0, offset.0)
+            .by_recipient(
+                organization_id,
+                messenger.0.into(),
+                &recipient.0,
+                limit.0,
+                offset.0,
+            )
             .await
             .map_err(internal_error)?;
 
         Ok(Json(PaginatedMessagesDto {
-            messages: result.messages.iter().map(map_history).collect(),
+            messages: result
+                .messages
+                .iter()
+                .map(|entry| map_history(entry, self.state.public_ids.as_ref()))
+                .collect(),
             has_more: result.has_more,
             next_offset: result.next_offset,
         }))
     }
 
+    /// Number of the organization's messages in each status.
+    #[oai(
+        path = "/messages/counts",
+        method = "get",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn count_messages(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+    ) -> PoemResult<Json<MessageStatusCountsDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+
+        let counts = self
+            .state
+            .list_messages_usecase
+            .count_by_status(organization_id)
+            .await
+            .map_err(internal_error)?;
+
+        Ok(Json(map_status_counts(&counts)))
+    }
+
     #[oai(
         path = "/messages/:message_id/attempts",
         method = "get",
@@ -97,15 +472,27 @@ impl MessagesEndpoints {
     )]
     pub async fn get_message_attempts(
         &self,
-        cookie_jar: &CookieJar,
-        message_id: poem_openapi::param::Path<uuid::Uuid>,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        message_id: poem_openapi::param::Path<String>,
     ) -> PoemResult<Json<Vec<MessageAttemptDto>>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let message_id = resolve_message_id(&message_id.0, self.state.public_ids.as_ref())?;
 
         let attempts = self
             .state
             .get_message_attempts_usecase
-            .execute(message_id.0, user.user_id)
+            .execute(message_id, user.message_owner(organization_id))
             .await
             .map_err(|e| {
                 if e.to_string().contains("forbidden") {
@@ -127,27 +514,190 @@ impl MessagesEndpoints {
     )]
     pub async fn get_message(
         &self,
-        cookie_jar: &CookieJar,
-        message_id: poem_openapi::param::Path<uuid::Uuid>,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        message_id: poem_openapi::param::Path<String>,
     ) -> PoemResult<Json<MessageHistoryDto>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let message_id = resolve_message_id(&message_id.0, self.state.public_ids.as_ref())?;
 
         let message = self
             .state
             .get_message_usecase
-            .execute(message_id.0, user.user_id)
+            .execute(message_id, user.message_owner(organization_id))
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("forbidden") {
+                    poem::Error::from_string("forbidden", poem::http::StatusCode::FORBIDDEN)
+                } else if e.to_string().contains("not found") {
+                    poem::Error::from_string("message not found", poem::http::StatusCode::NOT_FOUND)
+                } else {
+                    internal_error(e)
+                }
+            })?;
+
+        Ok(Json(map_history(&message, self.state.public_ids.as_ref())))
+    }
+
+    /// Removes a delivered, failed or cancelled message from the
+    /// organization's history. Admins can still look it up until it is
+    /// purged.
+    #[oai(
+        path = "/messages/:message_id",
+        method = "delete",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn delete_message(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        message_id: poem_openapi::param::Path<String>,
+    ) -> PoemResult<()> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+        user.require_write()?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let message_id = resolve_message_id(&message_id.0, self.state.public_ids.as_ref())?;
+
+        self.state
+            .delete_message_usecase
+            .execute(message_id, organization_id)
             .await
             .map_err(|e| {
                 if e.to_string().contains("forbidden") {
                     poem::Error::from_string("forbidden", poem::http::StatusCode::FORBIDDEN)
                 } else if e.to_string().contains("not found") {
                     poem::Error::from_string("message not found", poem::http::StatusCode::NOT_FOUND)
+                } else if e.to_string().contains("conflict") {
+                    poem::Error::from_string(
+                        "message is still being delivered",
+                        poem::http::StatusCode::CONFLICT,
+                    )
                 } else {
                     internal_error(e)
                 }
             })?;
 
-        Ok(Json(map_history(&message)))
+        Ok(())
+    }
+
+    #[oai(
+        path = "/messages/:message_id/related",
+        method = "get",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn get_related_messages(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        message_id: poem_openapi::param::Path<String>,
+    ) -> PoemResult<Json<RelatedMessagesDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let message_id = resolve_message_id(&message_id.0, self.state.public_ids.as_ref())?;
+
+        let related = self
+            .state
+            .get_related_messages_usecase
+            .execute(message_id, user.message_owner(organization_id))
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("forbidden") {
+                    poem::Error::from_string("forbidden", poem::http::StatusCode::FORBIDDEN)
+                } else if e.to_string().contains("not found") {
+                    poem::Error::from_string("message not found", poem::http::StatusCode::NOT_FOUND)
+                } else {
+                    internal_error(e)
+                }
+            })?;
+
+        let ids = self.state.public_ids.as_ref();
+        Ok(Json(RelatedMessagesDto {
+            messages: related
+                .messages
+                .iter()
+                .map(|entry| map_history(entry, ids))
+                .collect(),
+            links: related
+                .links
+                .iter()
+                .map(|link| map_link(link, ids))
+                .collect(),
+        }))
+    }
+
+    /// Creates a token for `GET /public/status/:token`, which shows the
+    /// message's delivery status without signing in.
+    #[oai(
+        path = "/messages/:message_id/status-link",
+        method = "post",
+        tag = EndpointsTags::Messages,
+    )]
+    pub async fn create_status_link(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        message_id: poem_openapi::param::Path<String>,
+    ) -> PoemResult<Json<StatusLinkDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let message_id = resolve_message_id(&message_id.0, self.state.public_ids.as_ref())?;
+
+        let link = self
+            .state
+            .message_status_link_usecase
+            .issue(organization_id, message_id)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("forbidden") {
+                    poem::Error::from_string("forbidden", poem::http::StatusCode::FORBIDDEN)
+                } else if e.to_string().contains("not found") {
+                    poem::Error::from_string("message not found", poem::http::StatusCode::NOT_FOUND)
+                } else {
+                    internal_error(e)
+                }
+            })?;
+
+        Ok(Json(StatusLinkDto {
+            token: link.token,
+            expires_at: link.expires_at.to_rfc3339(),
+        }))
     }
 
     #[oai(
@@ -157,10 +707,24 @@ impl MessagesEndpoints {
     )]
     pub async fn batch_send(
         &self,
-        cookie_jar: &CookieJar,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        #[oai(name = "Idempotency-Key")] idempotency_key: Header<Option<String>>,
         request: Json<BatchSendRequestDto>,
     ) -> PoemResult<Json<BatchSendResponseDto>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+        user.require_write()?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let idempotency_key = validate_idempotency_key(idempotency_key.0)?;
 
         if request.messages.is_empty() {
             return Err(poem::Error::from_string(
@@ -176,26 +740,63 @@ impl MessagesEndpoints {
             ));
         }
 
-        let mut results = Vec::new();
-        let mut successful = 0;
-        let mut failed = 0;
-
+        let mut outcomes = Vec::with_capacity(request.messages.len());
+        let mut payloads = Vec::new();
         for (index, msg) in request.messages.iter().enumerate() {
-            let payload = ScheduleMessageRequest {
-                user_id: user.user_id,
-                messenger: msg.messenger.into(),
-                recipient: msg.recipient.clone(),
-                text: msg.text.clone(),
-                requested_by: msg.requested_by.into(),
+            let thread_parent = match msg
+                .thread_parent
+                .as_deref()
+                .map(|value| resolve_message_id(value, self.state.public_ids.as_ref()))
+                .transpose()
+            {
+                Ok(thread_parent) => thread_parent,
+                Err(err) => {
+                    outcomes.push(Some(Err(err.to_string())));
+                    continue;
+                }
             };
+            // Each item is replayed on its own, so items that failed the
+            // first time are attempted again.
+            let item_key = idempotency_key.as_ref().map(|key| format!("{key}:{index}"));
+            match self
+                .schedule_request(organization_id, user.user_id, msg, thread_parent, item_key)
+                .await
+            {
+                Ok(payload) => {
+                    outcomes.push(None);
+                    payloads.push(payload);
+                }
+                Err(err) => outcomes.push(Some(Err(err.to_string()))),
+            }
+        }
 
-            match self.state.schedule_message_usecase.execute(payload).await {
+        // Scheduled results come back in order and fill the slots left open
+        // above.
+        let mut scheduled = self
+            .state
+            .schedule_message_usecase
+            .execute_batch(payloads)
+            .await
+            .into_iter();
+        let mut results = Vec::with_capacity(outcomes.len());
+        let mut successful = 0;
+        let mut failed = 0;
+        for (index, outcome) in outcomes.into_iter().enumerate() {
+            let outcome = outcome
+                .or_else(|| {
+                    scheduled
+                        .next()
+                        .map(|result| result.map_err(|e| e.to_string()))
+                })
+                .unwrap_or_else(|| Err("message was not scheduled".to_string()));
+            match outcome {
                 Ok(response) => {
                     successful += 1;
                     results.push(BatchSendItemResultDto {
                         index: index as u32,
                         success: true,
                         message_id: Some(response.message_id),
+                        reference: Some(self.state.public_ids.encode(&response.message_id)),
                         error: None,
                     });
                 }
@@ -205,7 +806,8 @@ impl MessagesEndpoints {
                         index: index as u32,
                         success: false,
                         message_id: None,
-                        error: Some(err.to_string()),
+                        reference: None,
+                        error: Some(err),
                     });
                 }
             }
@@ -226,16 +828,29 @@ impl MessagesEndpoints {
     )]
     pub async fn retry_message(
         &self,
-        cookie_jar: &CookieJar,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
         request: Json<RetryMessageRequestDto>,
     ) -> PoemResult<()> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+        user.require_write()?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+        let message_id = resolve_message_id(&request.message_id, self.state.public_ids.as_ref())?;
 
         self.state
             .retry_message_usecase
             .execute(RetryMessageRequest {
-                user_id: user.user_id,
-                message_id: request.message_id,
+                organization_id,
+                message_id,
             })
             .await
             .map_err(bad_request)?;
@@ -244,6 +859,21 @@ impl MessagesEndpoints {
     }
 }
 
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
+
+pub(crate) fn validate_idempotency_key(key: Option<String>) -> PoemResult<Option<String>> {
+    let Some(key) = key.map(|key| key.trim().to_string()) else {
+        return Ok(None);
+    };
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+        return Err(poem::Error::from_string(
+            format!("Idempotency-Key must be 1 to {MAX_IDEMPOTENCY_KEY_LENGTH} characters"),
+            poem::http::StatusCode::BAD_REQUEST,
+        ));
+    }
+    Ok(Some(key))
+}
+
 fn internal_error(err: anyhow::Error) -> poem::Error {
     poem::Error::from_string(
         err.to_string(),
@@ -251,6 +881,26 @@ fn internal_error(err: anyhow::Error) -> poem::Error {
     )
 }
 
+fn routing_error(err: anyhow::Error) -> poem::Error {
+    let message = err.to_string();
+    if message.contains("routing rule") || message.contains("given together") {
+        poem::Error::from_string(message, poem::http::StatusCode::UNPROCESSABLE_ENTITY)
+    } else {
+        internal_error(err)
+    }
+}
+
 fn bad_request(err: anyhow::Error) -> poem::Error {
     poem::Error::from_string(err.to_string(), poem::http::StatusCode::BAD_REQUEST)
 }
+
+fn map_filter(params: MessageFilterParams) -> MessageHistoryFilter {
+    MessageHistoryFilter {
+        status: params.status.map(Into::into),
+        messenger: params.messenger.map(Into::into),
+        recipient: params.recipient.filter(|recipient| !recipient.is_empty()),
+        created_after: params.created_after,
+        created_before: params.created_before,
+        tag: params.tag.filter(|tag| !tag.is_empty()),
+    }
+}
@@ -1,6 +1,16 @@
+pub mod admin;
+pub mod api_keys;
 pub mod auth;
 pub mod chats;
+pub mod consents;
+pub mod dead_letters;
+pub mod events;
 pub mod health;
 pub mod messages;
+pub mod organizations;
+pub mod otp;
+pub mod public_status;
 pub mod root;
+pub mod routing_rules;
+pub mod stats;
 pub mod tokens;
@@ -0,0 +1,232 @@
+use std::sync::Arc;
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{OpenApi, param::Path, payload::Json};
+use uuid::Uuid;
+
+use crate::{
+    domain::models::MemberRole,
+    presentation::http::{
+        endpoints::root::{ApiState, EndpointsTags},
+        mappers::{map_invitation, map_issued_invitation, map_member, map_organization},
+        requests::{AcceptInvitationRequestDto, InvitationRequestDto, OrganizationRequestDto},
+        responses::{
+            IssuedInvitationDto, OrganizationDto, OrganizationInvitationDto, OrganizationMemberDto,
+        },
+        security::{JwtAuth, SessionCredentials},
+    },
+};
+
+/// Every user has a personal organization with the user's own id. Other
+/// endpoints act for the one named by the `X-Organization-Id` header.
+#[derive(Clone)]
+pub struct OrganizationsEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl OrganizationsEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl OrganizationsEndpoints {
+    /// Organizations the caller belongs to, with the caller's role in each.
+    #[oai(
+        path = "/organizations",
+        method = "get",
+        tag = EndpointsTags::Organizations
+    )]
+    pub async fn list_organizations(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<Vec<OrganizationDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let organizations = self
+            .state
+            .organization_usecase
+            .list(user.user_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(
+            organizations
+                .iter()
+                .map(|(organization, role)| map_organization(organization, *role, user.user_id))
+                .collect(),
+        ))
+    }
+
+    /// Creates an organization owned by the caller.
+    #[oai(
+        path = "/organizations",
+        method = "post",
+        tag = EndpointsTags::Organizations
+    )]
+    pub async fn create_organization(
+        &self,
+        auth: SessionCredentials,
+        request: Json<OrganizationRequestDto>,
+    ) -> PoemResult<Json<OrganizationDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+
+        let organization = self
+            .state
+            .organization_usecase
+            .create(user.user_id, &request.name)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_organization(
+            &organization,
+            MemberRole::Owner,
+            user.user_id,
+        )))
+    }
+
+    #[oai(
+        path = "/organizations/:organization_id/members",
+        method = "get",
+        tag = EndpointsTags::Organizations
+    )]
+    pub async fn list_members(
+        &self,
+        auth: SessionCredentials,
+        organization_id: Path<Uuid>,
+    ) -> PoemResult<Json<Vec<OrganizationMemberDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(Some(organization_id.0), &self.state.organization_usecase)
+            .await?;
+
+        let members = self
+            .state
+            .organization_usecase
+            .members(organization_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(members.iter().map(map_member).collect()))
+    }
+
+    /// Pending invitations of the organization.
+    #[oai(
+        path = "/organizations/:organization_id/invitations",
+        method = "get",
+        tag = EndpointsTags::Organizations
+    )]
+    pub async fn list_invitations(
+        &self,
+        auth: SessionCredentials,
+        organization_id: Path<Uuid>,
+    ) -> PoemResult<Json<Vec<OrganizationInvitationDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let organization_id = user
+            .organization(Some(organization_id.0), &self.state.organization_usecase)
+            .await?;
+
+        let invitations = self
+            .state
+            .organization_usecase
+            .invitations(organization_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(invitations.iter().map(map_invitation).collect()))
+    }
+
+    /// Invites `email` to the organization; only owners may. The token is
+    /// returned once and must reach the invitee out of band.
+    #[oai(
+        path = "/organizations/:organization_id/invitations",
+        method = "post",
+        tag = EndpointsTags::Organizations
+    )]
+    pub async fn invite(
+        &self,
+        auth: SessionCredentials,
+        organization_id: Path<Uuid>,
+        request: Json<InvitationRequestDto>,
+    ) -> PoemResult<Json<IssuedInvitationDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+
+        let issued = self
+            .state
+            .organization_usecase
+            .invite(
+                organization_id.0,
+                user.user_id,
+                &request.email,
+                request.role.map(Into::into).unwrap_or_default(),
+            )
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_issued_invitation(&issued)))
+    }
+
+    /// Joins the organization an invitation addressed to the caller's email
+    /// is for.
+    #[oai(
+        path = "/organizations/invitations/accept",
+        method = "post",
+        tag = EndpointsTags::Organizations
+    )]
+    pub async fn accept_invitation(
+        &self,
+        auth: SessionCredentials,
+        request: Json<AcceptInvitationRequestDto>,
+    ) -> PoemResult<Json<OrganizationDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let (organization, role) = self
+            .state
+            .organization_usecase
+            .accept(user.user_id, &user.email, &request.token)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_organization(&organization, role, user.user_id)))
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    if message.contains("forbidden") {
+        PoemError::from_string(message, StatusCode::FORBIDDEN)
+    } else if message.contains("invalid") {
+        PoemError::from_string(message, StatusCode::BAD_REQUEST)
+    } else if message.contains("not found") {
+        PoemError::from_string(message, StatusCode::NOT_FOUND)
+    } else {
+        PoemError::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{OpenApi, payload::Json};
+
+use crate::{
+    application::usecases::{send_otp::SendOtpRequest, verify_otp::VerifyOtpRequest},
+    domain::models::ApiKeyScope,
+    presentation::http::{
+        endpoints::root::{ApiState, EndpointsTags},
+        requests::{SendOtpRequestDto, VerifyOtpRequestDto},
+        responses::{SendOtpResponseDto, VerifyOtpResponseDto},
+        security::{ApiKeyAuth, ServiceCredentials},
+    },
+};
+
+#[derive(Clone)]
+pub struct OtpEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl OtpEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl OtpEndpoints {
+    #[oai(path = "/otp/send", method = "post", tag = EndpointsTags::Otp)]
+    pub async fn send(
+        &self,
+        auth: ServiceCredentials,
+        request: Json<SendOtpRequestDto>,
+    ) -> PoemResult<Json<SendOtpResponseDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+        user.require_write()?;
+
+        let response = self
+            .state
+            .send_otp_usecase
+            .execute(SendOtpRequest {
+                user_id: user.user_id,
+                messenger: request.messenger.into(),
+                recipient: request.recipient.clone(),
+            })
+            .await
+            .map_err(bad_request)?;
+
+        Ok(Json(SendOtpResponseDto {
+            message_id: response.message_id,
+            reference: self.state.public_ids.encode(&response.message_id),
+            expires_at: response.expires_at.to_rfc3339(),
+        }))
+    }
+
+    #[oai(path = "/otp/verify", method = "post", tag = EndpointsTags::Otp)]
+    pub async fn verify(
+        &self,
+        auth: ServiceCredentials,
+        request: Json<VerifyOtpRequestDto>,
+    ) -> PoemResult<Json<VerifyOtpResponseDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesSend)?;
+
+        self.state
+            .verify_otp_usecase
+            .execute(VerifyOtpRequest {
+                user_id: user.user_id,
+                messenger: request.messenger.into(),
+                recipient: request.recipient.clone(),
+                code: request.code.clone(),
+            })
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("too many attempts") {
+                    PoemError::from_string("too many attempts", StatusCode::TOO_MANY_REQUESTS)
+                } else if e.to_string().contains("not found") {
+                    PoemError::from_string("otp not found", StatusCode::NOT_FOUND)
+                } else {
+                    bad_request(e)
+                }
+            })?;
+
+        Ok(Json(VerifyOtpResponseDto { verified: true }))
+    }
+}
+
+fn bad_request(err: anyhow::Error) -> PoemError {
+    PoemError::from_string(err.to_string(), StatusCode::BAD_REQUEST)
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{OpenApi, param::Path, payload::Json};
+
+use crate::presentation::http::{
+    endpoints::root::{ApiState, EndpointsTags},
+    mappers::map_public_status,
+    responses::PublicMessageStatusDto,
+};
+
+/// Unauthenticated: the token in the path is the only credential.
+#[derive(Clone)]
+pub struct PublicStatusEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl PublicStatusEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl PublicStatusEndpoints {
+    #[oai(path = "/public/status/:token", method = "get", tag = EndpointsTags::Messages)]
+    pub async fn public_status(
+        &self,
+        token: Path<String>,
+    ) -> PoemResult<Json<PublicMessageStatusDto>> {
+        let status = self
+            .state
+            .message_status_link_usecase
+            .lookup(&token.0)
+            .await
+            .map_err(|e| {
+                if e.to_string().contains("not found") {
+                    PoemError::from_string("status not found", StatusCode::NOT_FOUND)
+                } else {
+                    PoemError::from_string(e.to_string(), StatusCode::INTERNAL_SERVER_ERROR)
+                }
+            })?;
+
+        Ok(Json(map_public_status(&status)))
+    }
+}
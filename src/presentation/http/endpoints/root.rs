@@ -2,27 +2,80 @@ use std::sync::Arc;
 
 use poem_openapi::Tags;
 
-use crate::application::services::jwt::JwtServiceConfig;
+use crate::application::services::{
+    database_stats::DatabaseMonitor, delivery_semantics::DeliverySemantics, jwt::JwtServiceConfig,
+    messenger::MessengerGateway, public_id::PublicIdCodec, readiness::ReadinessProbe,
+    session_revocations::SessionRevocations, status_feed::StatusFeed,
+};
 use crate::application::usecases::{
-    authenticate_user::AuthenticateUserUseCase, get_message::GetMessageUseCase,
-    get_message_attempts::GetMessageAttemptsUseCase, list_chats::ListChatsUseCase,
+    authenticate_user::AuthenticateUserUseCase, delete_message::DeleteMessageUseCase,
+    erase_account::AccountErasureUseCase, get_delivery_stats::GetDeliveryStatsUseCase,
+    get_message::GetMessageUseCase, get_message_attempts::GetMessageAttemptsUseCase,
+    get_messenger_health::GetMessengerHealthUseCase, get_overview::GetOverviewUseCase,
+    get_queue_stats::GetQueueStatsUseCase, get_related_messages::GetRelatedMessagesUseCase,
+    ingest_event::IngestEventUseCase, list_chats::ListChatsUseCase,
     list_messages::ListMessagesUseCase, list_tokens::ListTokensUseCase,
-    register_token::RegisterTokenUseCase, retry_message::RetryMessageUseCase,
-    schedule_message::ScheduleMessageUseCase,
+    list_workers::ListWorkersUseCase, manage_api_keys::ApiKeyUseCase,
+    manage_consent::ConsentUseCase, manage_dead_letters::DeadLetterUseCase,
+    manage_identities::IdentityUseCase, manage_organizations::OrganizationUseCase,
+    manage_passwords::PasswordUseCase, manage_roles::RoleUseCase,
+    manage_routing_rules::RoutingRuleUseCase, manage_sessions::SessionUseCase,
+    manage_users::UserAdminUseCase, message_status_link::MessageStatusLinkUseCase,
+    preview_message::PreviewMessageUseCase, register_token::RegisterTokenUseCase,
+    retry_message::RetryMessageUseCase, schedule_message::ScheduleMessageUseCase,
+    send_chat_action::SendChatActionUseCase, send_magic_link::MagicLinkUseCase,
+    send_otp::SendOtpUseCase, share_token::ShareTokenUseCase, trace_replay::TraceReplayUseCase,
+    verify_otp::VerifyOtpUseCase,
 };
 
 #[derive(Clone)]
 pub struct ApiState {
     pub auth_usecase: Arc<AuthenticateUserUseCase>,
+    pub password_usecase: Arc<PasswordUseCase>,
+    pub magic_link_usecase: Arc<MagicLinkUseCase>,
+    pub role_usecase: Arc<RoleUseCase>,
+    pub user_admin_usecase: Arc<UserAdminUseCase>,
+    pub identity_usecase: Arc<IdentityUseCase>,
+    pub api_key_usecase: Arc<ApiKeyUseCase>,
+    pub session_usecase: Arc<SessionUseCase>,
+    pub account_erasure_usecase: Arc<AccountErasureUseCase>,
+    pub organization_usecase: Arc<OrganizationUseCase>,
+    pub routing_rule_usecase: Arc<RoutingRuleUseCase>,
+    pub ingest_event_usecase: Arc<IngestEventUseCase>,
     pub register_token_usecase: Arc<RegisterTokenUseCase>,
     pub list_tokens_usecase: Arc<ListTokensUseCase>,
+    pub share_token_usecase: Arc<ShareTokenUseCase>,
     pub list_chats_usecase: Arc<ListChatsUseCase>,
+    pub send_chat_action_usecase: Arc<SendChatActionUseCase>,
     pub schedule_message_usecase: Arc<ScheduleMessageUseCase>,
     pub list_messages_usecase: Arc<ListMessagesUseCase>,
     pub retry_message_usecase: Arc<RetryMessageUseCase>,
     pub get_message_attempts_usecase: Arc<GetMessageAttemptsUseCase>,
     pub get_message_usecase: Arc<GetMessageUseCase>,
+    pub delete_message_usecase: Arc<DeleteMessageUseCase>,
+    pub get_delivery_stats_usecase: Arc<GetDeliveryStatsUseCase>,
+    pub get_related_messages_usecase: Arc<GetRelatedMessagesUseCase>,
+    pub send_otp_usecase: Arc<SendOtpUseCase>,
+    pub verify_otp_usecase: Arc<VerifyOtpUseCase>,
+    pub consent_usecase: Arc<ConsentUseCase>,
+    pub dead_letter_usecase: Arc<DeadLetterUseCase>,
+    pub preview_message_usecase: Arc<PreviewMessageUseCase>,
+    pub get_queue_stats_usecase: Arc<GetQueueStatsUseCase>,
+    pub get_overview_usecase: Arc<GetOverviewUseCase>,
+    pub get_messenger_health_usecase: Arc<GetMessengerHealthUseCase>,
+    pub list_workers_usecase: Arc<ListWorkersUseCase>,
+    pub trace_replay_usecase: Arc<TraceReplayUseCase>,
+    pub message_status_link_usecase: Arc<MessageStatusLinkUseCase>,
     pub jwt_config: JwtServiceConfig,
+    pub session_revocations: Arc<SessionRevocations>,
+    pub public_ids: Arc<dyn PublicIdCodec>,
+    pub delivery_semantics: DeliverySemantics,
+    pub database_monitor: Arc<dyn DatabaseMonitor>,
+    pub readiness: Arc<ReadinessProbe>,
+    pub messenger_gateway: MessengerGateway,
+    pub status_feed: Arc<StatusFeed>,
+    /// Where the browser lands after an OAuth sign-in.
+    pub oauth_success_url: String,
 }
 
 /// Enum of API sections (tags)
@@ -30,7 +83,13 @@ pub struct ApiState {
 pub enum EndpointsTags {
     Health,
     Auth,
+    ApiKeys,
+    Organizations,
     Tokens,
     Messages,
     Chats,
+    Otp,
+    Consents,
+    Routing,
+    Admin,
 }
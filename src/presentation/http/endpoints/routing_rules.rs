@@ -0,0 +1,288 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{
+    OpenApi,
+    param::{Path, Query},
+    payload::Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    application::usecases::manage_routing_rules::RoutingRuleDraft,
+    domain::models::RoutingInput,
+    presentation::http::{
+        endpoints::{
+            events::flatten_payload,
+            root::{ApiState, EndpointsTags},
+        },
+        mappers::{
+            map_routing_rule, map_routing_rule_diff, map_routing_rule_version, map_simulated_rule,
+        },
+        requests::{RoutingRuleRequestDto, RoutingSimulationRequestDto},
+        responses::{
+            AuthResponseDto, RoutingRuleDiffDto, RoutingRuleDto, RoutingRuleVersionDto,
+            SimulatedRuleDto,
+        },
+        security::{JwtAuth, SessionCredentials},
+    },
+};
+
+#[derive(Clone)]
+pub struct RoutingRulesEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl RoutingRulesEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl RoutingRulesEndpoints {
+    /// Rules in the order they are evaluated.
+    #[oai(path = "/routing-rules", method = "get", tag = EndpointsTags::Routing)]
+    pub async fn list_routing_rules(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<Vec<RoutingRuleDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let rules = self
+            .state
+            .routing_rule_usecase
+            .list(user.user_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(rules.iter().map(map_routing_rule).collect()))
+    }
+
+    #[oai(path = "/routing-rules", method = "post", tag = EndpointsTags::Routing)]
+    pub async fn create_routing_rule(
+        &self,
+        auth: SessionCredentials,
+        request: Json<RoutingRuleRequestDto>,
+    ) -> PoemResult<Json<RoutingRuleDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+
+        let rule = self
+            .state
+            .routing_rule_usecase
+            .create(user.user_id, map_draft(request.0))
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_routing_rule(&rule)))
+    }
+
+    #[oai(
+        path = "/routing-rules/:rule_id",
+        method = "put",
+        tag = EndpointsTags::Routing
+    )]
+    pub async fn update_routing_rule(
+        &self,
+        auth: SessionCredentials,
+        rule_id: Path<Uuid>,
+        request: Json<RoutingRuleRequestDto>,
+    ) -> PoemResult<Json<RoutingRuleDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+
+        let rule = self
+            .state
+            .routing_rule_usecase
+            .update(user.user_id, rule_id.0, map_draft(request.0))
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_routing_rule(&rule)))
+    }
+
+    /// Shows which rules a send or event would match and what they would
+    /// deliver, without scheduling anything.
+    #[oai(path = "/routing/simulate", method = "post", tag = EndpointsTags::Routing)]
+    pub async fn simulate_routing(
+        &self,
+        auth: SessionCredentials,
+        request: Json<RoutingSimulationRequestDto>,
+    ) -> PoemResult<Json<Vec<SimulatedRuleDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        let request = request.0;
+
+        let mut metadata = BTreeMap::new();
+        flatten_payload("", &request.payload, &mut metadata);
+        let simulated = self
+            .state
+            .routing_rule_usecase
+            .simulate(
+                user.user_id,
+                &RoutingInput {
+                    event: request.event.filter(|event| !event.is_empty()),
+                    class: request.class.into(),
+                    tags: request.tags,
+                    metadata,
+                    text: request.text,
+                },
+            )
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(simulated.iter().map(map_simulated_rule).collect()))
+    }
+
+    #[oai(
+        path = "/routing-rules/:rule_id",
+        method = "delete",
+        tag = EndpointsTags::Routing
+    )]
+    pub async fn delete_routing_rule(
+        &self,
+        auth: SessionCredentials,
+        rule_id: Path<Uuid>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+
+        self.state
+            .routing_rule_usecase
+            .delete(user.user_id, rule_id.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+
+    /// Every change to the rules records a version of the whole set.
+    #[oai(path = "/routing/versions", method = "get", tag = EndpointsTags::Routing)]
+    pub async fn list_routing_versions(
+        &self,
+        auth: SessionCredentials,
+    ) -> PoemResult<Json<Vec<RoutingRuleVersionDto>>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let (versions, active) = self
+            .state
+            .routing_rule_usecase
+            .list_versions(user.user_id)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(
+            versions
+                .iter()
+                .map(|version| map_routing_rule_version(version, active))
+                .collect(),
+        ))
+    }
+
+    /// Changes from `against`, by default the previous version, to
+    /// `version`.
+    #[oai(
+        path = "/routing/versions/:version/diff",
+        method = "get",
+        tag = EndpointsTags::Routing
+    )]
+    pub async fn diff_routing_version(
+        &self,
+        auth: SessionCredentials,
+        version: Path<i32>,
+        against: Query<Option<i32>>,
+    ) -> PoemResult<Json<RoutingRuleDiffDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+
+        let diff = self
+            .state
+            .routing_rule_usecase
+            .diff(user.user_id, version.0, against.0)
+            .await
+            .map_err(map_error)?;
+        let against = against.0.unwrap_or(version.0 - 1).max(0);
+
+        Ok(Json(map_routing_rule_diff(&diff, version.0, against)))
+    }
+
+    /// Replaces the current rules with those of `version`, which rolls
+    /// back a bad change.
+    #[oai(
+        path = "/routing/versions/:version/activate",
+        method = "post",
+        tag = EndpointsTags::Routing
+    )]
+    pub async fn activate_routing_version(
+        &self,
+        auth: SessionCredentials,
+        version: Path<i32>,
+    ) -> PoemResult<Json<AuthResponseDto>> {
+        let user = JwtAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+        )?;
+        user.require_write()?;
+
+        self.state
+            .routing_rule_usecase
+            .activate(user.user_id, version.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(AuthResponseDto { success: true }))
+    }
+}
+
+fn map_draft(request: RoutingRuleRequestDto) -> RoutingRuleDraft {
+    RoutingRuleDraft {
+        name: request.name,
+        position: request.position,
+        enabled: request.enabled.unwrap_or(true),
+        match_event: request.match_event.filter(|event| !event.is_empty()),
+        match_class: request.match_class.map(Into::into),
+        match_tags: request.match_tags,
+        match_metadata: request.match_metadata,
+        messenger: request.messenger.into(),
+        recipient: request.recipient,
+        template: request.template,
+        priority: request.priority.map(Into::into),
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    if message.contains("not found") {
+        PoemError::from_string(message, StatusCode::NOT_FOUND)
+    } else {
+        PoemError::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
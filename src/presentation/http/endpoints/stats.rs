@@ -0,0 +1,76 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{
+    OpenApi,
+    param::{Header, Query},
+    payload::Json,
+};
+use uuid::Uuid;
+
+use crate::{
+    domain::models::ApiKeyScope,
+    presentation::http::{
+        endpoints::root::{ApiState, EndpointsTags},
+        mappers::map_delivery_stats,
+        responses::DeliveryStatsDto,
+        security::{ApiKeyAuth, ServiceCredentials},
+    },
+};
+
+#[derive(Clone)]
+pub struct StatsEndpoints {
+    state: Arc<ApiState>,
+}
+
+impl StatsEndpoints {
+    pub fn new(state: Arc<ApiState>) -> Self {
+        Self { state }
+    }
+}
+
+#[OpenApi]
+impl StatsEndpoints {
+    /// Delivery counts and success rates of the organization's messages
+    /// created in `[from, to)`, by messenger and by UTC day. Defaults to the last 30
+    /// days; windows over 366 days are rejected.
+    #[oai(path = "/stats", method = "get", tag = EndpointsTags::Messages)]
+    pub async fn delivery_stats(
+        &self,
+        auth: ServiceCredentials,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+        from: Query<Option<DateTime<Utc>>>,
+        to: Query<Option<DateTime<Utc>>>,
+    ) -> PoemResult<Json<DeliveryStatsDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::MessagesRead)?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+
+        let (stats, from, to) = self
+            .state
+            .get_delivery_stats_usecase
+            .execute(organization_id, from.0, to.0)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_delivery_stats(&stats, from, to)))
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    if message.contains("invalid window") {
+        PoemError::from_string(message, StatusCode::BAD_REQUEST)
+    } else {
+        PoemError::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
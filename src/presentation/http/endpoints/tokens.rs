@@ -1,16 +1,22 @@
 use std::sync::Arc;
 
-use poem::{Result as PoemResult, web::cookie::CookieJar};
-use poem_openapi::{OpenApi, payload::Json};
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::{
+    OpenApi,
+    param::{Header, Path, Query},
+    payload::Json,
+};
+use uuid::Uuid;
 
 use crate::{
     application::usecases::register_token::RegisterTokenRequest,
+    domain::models::ApiKeyScope,
     presentation::http::{
         endpoints::root::{ApiState, EndpointsTags},
         mappers::map_token,
-        requests::RegisterTokenRequestDto,
+        requests::{RegisterTokenRequestDto, TokenSharingRequestDto},
         responses::MessengerTokenDto,
-        security::JwtAuth,
+        security::{ApiKeyAuth, ServiceCredentials},
     },
 };
 
@@ -25,6 +31,11 @@ impl TokensEndpoints {
     }
 }
 
+/// Tokens belong to the organization named by `X-Organization-Id`, the
+/// caller's personal one by default. `user_id` stands for that user's
+/// personal organization, which only admins may name that way.
+///
+/// A member sends with the tokens they registered and the shared ones.
 #[OpenApi]
 impl TokensEndpoints {
     #[oai(
@@ -34,15 +45,37 @@ impl TokensEndpoints {
     )]
     pub async fn register_token(
         &self,
-        cookie_jar: &CookieJar,
+        auth: ServiceCredentials,
         request: Json<RegisterTokenRequestDto>,
+        user_id: Query<Option<Uuid>>,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
     ) -> PoemResult<Json<MessengerTokenDto>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::TokensManage)?;
+        user.require_write()?;
+        let user_id = user.acting_for(user_id.0)?;
+        let organization_id = user
+            .organization(
+                organization_id.0.or(Some(user_id)),
+                &self.state.organization_usecase,
+            )
+            .await?;
         let payload = RegisterTokenRequest {
-            user_id: user.user_id,
+            organization_id,
+            user_id,
             messenger: request.messenger.into(),
+            label: request.label.clone(),
             access_token: request.access_token.clone(),
             refresh_token: request.refresh_token.clone(),
+            expires_at: request.expires_at,
+            device_id: request.device_id.clone(),
+            shared: request.shared,
         };
 
         let token = self
@@ -62,19 +95,83 @@ impl TokensEndpoints {
     )]
     pub async fn list_tokens(
         &self,
-        cookie_jar: &CookieJar,
+        auth: ServiceCredentials,
+        user_id: Query<Option<Uuid>>,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
     ) -> PoemResult<Json<Vec<MessengerTokenDto>>> {
-        let user = JwtAuth::from_cookies(cookie_jar, &self.state.jwt_config)?;
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::TokensManage)?;
+        let user_id = user.acting_for(user_id.0)?;
+        let organization_id = user
+            .organization(
+                organization_id.0.or(Some(user_id)),
+                &self.state.organization_usecase,
+            )
+            .await?;
 
         let tokens = self
             .state
             .list_tokens_usecase
-            .execute(user.user_id)
+            .execute(organization_id, user_id)
             .await
             .map_err(internal_error)?;
 
         Ok(Json(tokens.iter().map(map_token).collect()))
     }
+
+    /// Shares the token with every member of the organization, or stops
+    /// sharing it. Only the member who registered it may.
+    #[oai(
+        path = "/messengers/tokens/:token_id/sharing",
+        method = "put",
+        tag = EndpointsTags::Tokens,
+    )]
+    pub async fn share_token(
+        &self,
+        auth: ServiceCredentials,
+        token_id: Path<Uuid>,
+        request: Json<TokenSharingRequestDto>,
+        #[oai(name = "X-Organization-Id")] organization_id: Header<Option<Uuid>>,
+    ) -> PoemResult<Json<MessengerTokenDto>> {
+        let user = ApiKeyAuth::from_credentials(
+            &auth,
+            &self.state.jwt_config,
+            &self.state.session_revocations,
+            &self.state.api_key_usecase,
+        )
+        .await?;
+        user.require_scope(ApiKeyScope::TokensManage)?;
+        user.require_write()?;
+        let organization_id = user
+            .organization(organization_id.0, &self.state.organization_usecase)
+            .await?;
+
+        let token = self
+            .state
+            .share_token_usecase
+            .execute(organization_id, user.user_id, token_id.0, request.shared)
+            .await
+            .map_err(map_error)?;
+
+        Ok(Json(map_token(&token)))
+    }
+}
+
+fn map_error(err: anyhow::Error) -> PoemError {
+    let message = err.to_string();
+    if message.contains("forbidden") {
+        PoemError::from_string(message, StatusCode::FORBIDDEN)
+    } else if message.contains("not found") {
+        PoemError::from_string(message, StatusCode::NOT_FOUND)
+    } else {
+        PoemError::from_string(message, StatusCode::INTERNAL_SERVER_ERROR)
+    }
 }
 
 fn internal_error(err: anyhow::Error) -> poem::Error {
@@ -0,0 +1,15 @@
+use std::sync::Arc;
+
+use poem::{
+    IntoResponse, handler,
+    web::{Data, Json},
+};
+
+use crate::application::services::jwt::JwtKeys;
+
+/// Public keys access tokens are signed with, for other services to verify
+/// them. Keys shared as secrets (HS256) are never listed.
+#[handler]
+pub fn jwks(keys: Data<&Arc<JwtKeys>>) -> impl IntoResponse {
+    Json(keys.current().jwks()).with_header("Cache-Control", "public, max-age=300")
+}
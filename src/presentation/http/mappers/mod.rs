@@ -1,12 +1,42 @@
+use chrono::{DateTime, Utc};
+use poem::{Error as PoemError, Result as PoemResult, http::StatusCode};
+use poem_openapi::types::ToJSON;
+use uuid::Uuid;
+
 use crate::{
+    application::{
+        services::{
+            dead_letter::DeadLetter, delivery_semantics::DeliverySemantics,
+            public_id::PublicIdCodec, queue_stats::QueueStats, readiness::Readiness,
+            status_feed::MessageStatusChange,
+        },
+        usecases::{
+            get_messenger_health::MessengerHealthReport, get_overview::OperationalOverview,
+            manage_api_keys::IssuedApiKey, manage_organizations::IssuedInvitation,
+            manage_routing_rules::SimulatedRule, message_status_link::PublicMessageStatus,
+            preview_message::MessagePreview, trace_replay::MessageTrace,
+        },
+    },
     domain::models::{
-        MessageAttempt, MessageHistoryEntry, MessageStatus, MessengerChat, MessengerToken,
-        MessengerTokenStatus,
+        AccountErasure, ApiKey, ConsentRecord, DeliveryCounts, DeliveryStats, MemberRole,
+        MessageAttempt, MessageHistoryEntry, MessageLink, MessageStatus, MessageStatusKind,
+        MessengerChat, MessengerToken, MessengerTokenStatus, Organization, OrganizationInvitation,
+        OrganizationMember, RequestedBy, RoutingRule, RoutingRuleDiff, RoutingRuleVersion, Session,
+        User, UserIdentity, Worker,
     },
     presentation::{
         http::responses::{
-            MessageAttemptDto, MessageHistoryDto, MessengerChatDto, MessengerTokenDto,
-            MessengerTokenStatusDto,
+            AccountErasureDto, ApiKeyDto, AttemptOutcomesDto, ConsentDto, ConsumerStatsDto,
+            ContentFindingDto, DailyDeliveryStatsDto, DeadLetterDto, DeliveryCountsDto,
+            DeliverySemanticsDto, DeliveryStatsDto, DeliveryWindowDto, IssuedApiKeyDto,
+            IssuedInvitationDto, MessageAttemptDto, MessageHistoryDto, MessageLengthDto,
+            MessageLinkDto, MessagePreviewDto, MessageStatusCountsDto, MessageStatusEventDto,
+            MessageTraceDto, MessengerChatDto, MessengerDeliveryStatsDto, MessengerHealthDto,
+            MessengerHealthReportDto, MessengerTokenDto, MessengerTokenStatusDto, OrganizationDto,
+            OrganizationInvitationDto, OrganizationMemberDto, OverviewDto, PublicMessageStatusDto,
+            QueueStatsDto, ReadinessCheckDto, ReadinessDto, RoutingRuleChangeDto,
+            RoutingRuleDiffDto, RoutingRuleDto, RoutingRuleVersionDto, SessionDto,
+            SimulatedRuleDto, TokenHealthDto, TraceStepDto, UserDto, UserIdentityDto, WorkerDto,
         },
         models::{ChatTypeKind, MessageStatusDto, RequestedByKind},
     },
@@ -16,26 +46,39 @@ pub fn map_token(token: &MessengerToken) -> MessengerTokenDto {
     MessengerTokenDto {
         id: token.id,
         messenger: token.messenger.into(),
+        label: token.label.clone(),
+        registered_by: token.user_id,
+        shared: token.shared,
         status: match token.status {
             MessengerTokenStatus::Active => MessengerTokenStatusDto::Active,
             MessengerTokenStatus::Inactive => MessengerTokenStatusDto::Inactive,
         },
+        expires_at: token.expires_at.map(|at| at.to_rfc3339()),
         updated_at: token.updated_at.to_rfc3339(),
     }
 }
 
-pub fn map_history(entry: &MessageHistoryEntry) -> MessageHistoryDto {
+pub fn map_history(entry: &MessageHistoryEntry, ids: &dyn PublicIdCodec) -> MessageHistoryDto {
     MessageHistoryDto {
         id: entry.id,
+        reference: ids.encode(&entry.id),
         messenger: entry.messenger.into(),
         recipient: entry.recipient.clone(),
         status: MessageStatusDto::from(&entry.status),
         attempts: entry.attempts,
         body: entry.content.body.clone(),
+        class: entry.class.into(),
+        priority: entry.priority.into(),
         last_error: extract_error(&entry.status),
         requested_by: entry.requested_by.clone().into(),
+        requested_by_user: entry.user_id,
+        deadline: entry.deadline.map(|deadline| deadline.to_rfc3339()),
+        scheduled_for: entry.scheduled_for.to_rfc3339(),
+        token_id: entry.token_id,
+        tags: entry.tags.clone(),
         created_at: entry.created_at.to_rfc3339(),
         updated_at: entry.updated_at.to_rfc3339(),
+        deleted_at: entry.deleted_at.map(|deleted_at| deleted_at.to_rfc3339()),
     }
 }
 
@@ -43,10 +86,67 @@ fn extract_error(status: &MessageStatus) -> Option<String> {
     match status {
         MessageStatus::Retrying { reason, .. } => Some(reason.clone()),
         MessageStatus::Failed { reason, .. } => Some(reason.clone()),
+        MessageStatus::Deferred { reason } => Some(reason.clone()),
+        MessageStatus::Suppressed { reason } => Some(reason.clone()),
         _ => None,
     }
 }
 
+pub const HISTORY_CSV_HEADER: &str = "id,reference,messenger,recipient,status,attempts,class,\
+priority,requested_by,requested_by_user,deadline,scheduled_for,token_id,tags,created_at,\
+updated_at,last_error,body\n";
+
+/// One CSV row, with the columns of `HISTORY_CSV_HEADER`.
+pub fn map_history_csv(entry: &MessageHistoryEntry, ids: &dyn PublicIdCodec) -> String {
+    let status = MessageStatusKind::from(MessageStatusDto::from(&entry.status));
+    let requested_by = match entry.requested_by {
+        RequestedBy::System => "system",
+        RequestedBy::User => "user",
+    };
+    let fields = [
+        entry.id.to_string(),
+        ids.encode(&entry.id),
+        entry.messenger.as_str().to_string(),
+        entry.recipient.clone(),
+        status.as_str().to_string(),
+        entry.attempts.to_string(),
+        entry.class.as_str().to_string(),
+        entry.priority.as_str().to_string(),
+        requested_by.to_string(),
+        entry.user_id.map(|id| id.to_string()).unwrap_or_default(),
+        entry
+            .deadline
+            .map(|deadline| deadline.to_rfc3339())
+            .unwrap_or_default(),
+        entry.scheduled_for.to_rfc3339(),
+        entry.token_id.map(|id| id.to_string()).unwrap_or_default(),
+        entry.tags.join(";"),
+        entry.created_at.to_rfc3339(),
+        entry.updated_at.to_rfc3339(),
+        extract_error(&entry.status).unwrap_or_default(),
+        entry.content.body.clone(),
+    ];
+
+    let mut row = fields.map(|field| csv_field(&field)).join(",");
+    row.push('\n');
+    row
+}
+
+/// One line of JSON Lines, shaped like `MessageHistoryDto`.
+pub fn map_history_jsonl(entry: &MessageHistoryEntry, ids: &dyn PublicIdCodec) -> String {
+    let mut line = map_history(entry, ids).to_json_string();
+    line.push('\n');
+    line
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 pub fn map_chat(chat: &MessengerChat) -> MessengerChatDto {
     MessengerChatDto {
         messenger: chat.messenger.into(),
@@ -68,3 +168,463 @@ pub fn map_attempt(attempt: &MessageAttempt) -> MessageAttemptDto {
         created_at: attempt.created_at.to_rfc3339(),
     }
 }
+
+/// Accepts either a raw UUID or a public reference produced by the configured codec.
+pub fn resolve_message_id(value: &str, ids: &dyn PublicIdCodec) -> PoemResult<Uuid> {
+    Uuid::parse_str(value)
+        .ok()
+        .or_else(|| ids.decode(value))
+        .ok_or_else(|| PoemError::from_string("message not found", StatusCode::NOT_FOUND))
+}
+
+pub fn map_consent(record: &ConsentRecord) -> ConsentDto {
+    ConsentDto {
+        id: record.id,
+        messenger: record.messenger.into(),
+        recipient: record.recipient.clone(),
+        status: record.status.into(),
+        source: record.source.clone(),
+        proof: record.proof.clone(),
+        created_at: record.created_at.to_rfc3339(),
+        updated_at: record.updated_at.to_rfc3339(),
+    }
+}
+
+pub fn map_api_key(key: &ApiKey) -> ApiKeyDto {
+    ApiKeyDto {
+        id: key.id,
+        name: key.name.clone(),
+        prefix: key.prefix.clone(),
+        created_at: key.created_at.to_rfc3339(),
+        last_used_at: key.last_used_at.map(|at| at.to_rfc3339()),
+        scopes: key.scopes.iter().copied().map(Into::into).collect(),
+        allowed_ips: key.allowed_ips.iter().map(|net| net.to_string()).collect(),
+    }
+}
+
+pub fn map_account_erasure(erasure: &AccountErasure) -> AccountErasureDto {
+    AccountErasureDto {
+        id: erasure.id,
+        user_id: erasure.user_id,
+        email_hash: erasure.email_hash.clone(),
+        requested_at: erasure.requested_at.to_rfc3339(),
+        completed_at: erasure
+            .completed_at
+            .map(|completed_at| completed_at.to_rfc3339()),
+        messages_purged: erasure.messages_purged,
+    }
+}
+
+pub fn map_session(session: &Session, current_session_id: Option<Uuid>) -> SessionDto {
+    SessionDto {
+        id: session.id,
+        device: session.device.clone(),
+        ip: session.ip.clone(),
+        created_at: session.created_at.to_rfc3339(),
+        last_seen_at: session.last_seen_at.to_rfc3339(),
+        current: current_session_id == Some(session.id),
+    }
+}
+
+pub fn map_issued_api_key(issued: &IssuedApiKey) -> IssuedApiKeyDto {
+    IssuedApiKeyDto {
+        key: issued.secret.clone(),
+        api_key: map_api_key(&issued.key),
+    }
+}
+
+pub fn map_user(user: &User) -> UserDto {
+    UserDto {
+        id: user.id,
+        email: user.email.clone(),
+        display_name: user.display_name.clone(),
+        role: user.role.into(),
+        disabled_at: user.disabled_at.map(|disabled_at| disabled_at.to_rfc3339()),
+        created_at: user.created_at.to_rfc3339(),
+    }
+}
+
+pub fn map_organization(
+    organization: &Organization,
+    role: MemberRole,
+    user_id: Uuid,
+) -> OrganizationDto {
+    OrganizationDto {
+        id: organization.id,
+        name: organization.name.clone(),
+        personal: organization.id == user_id,
+        role: role.into(),
+        created_at: organization.created_at.to_rfc3339(),
+    }
+}
+
+pub fn map_member(member: &OrganizationMember) -> OrganizationMemberDto {
+    OrganizationMemberDto {
+        user_id: member.user_id,
+        role: member.role.into(),
+        joined_at: member.created_at.to_rfc3339(),
+    }
+}
+
+pub fn map_invitation(invitation: &OrganizationInvitation) -> OrganizationInvitationDto {
+    OrganizationInvitationDto {
+        id: invitation.id,
+        email: invitation.email.clone(),
+        role: invitation.role.into(),
+        created_at: invitation.created_at.to_rfc3339(),
+        expires_at: invitation.expires_at.to_rfc3339(),
+    }
+}
+
+pub fn map_issued_invitation(issued: &IssuedInvitation) -> IssuedInvitationDto {
+    IssuedInvitationDto {
+        token: issued.token.clone(),
+        invitation: map_invitation(&issued.invitation),
+    }
+}
+
+pub fn map_identity(identity: &UserIdentity) -> UserIdentityDto {
+    UserIdentityDto {
+        id: identity.id,
+        provider: identity.provider.clone(),
+        subject: identity.subject.clone(),
+        created_at: identity.created_at.to_rfc3339(),
+    }
+}
+
+pub fn map_routing_rule(rule: &RoutingRule) -> RoutingRuleDto {
+    RoutingRuleDto {
+        id: rule.id,
+        name: rule.name.clone(),
+        position: rule.position,
+        enabled: rule.enabled,
+        match_event: rule.match_event.clone(),
+        match_class: rule.match_class.map(Into::into),
+        match_tags: rule.match_tags.clone(),
+        match_metadata: rule.match_metadata.clone(),
+        messenger: rule.messenger.into(),
+        recipient: rule.recipient.clone(),
+        template: rule.template.clone(),
+        priority: rule.priority.map(Into::into),
+        created_at: rule.created_at.to_rfc3339(),
+        updated_at: rule.updated_at.to_rfc3339(),
+    }
+}
+
+pub fn map_simulated_rule(simulated: &SimulatedRule) -> SimulatedRuleDto {
+    let routed = simulated.result.as_ref().ok();
+    SimulatedRuleDto {
+        rule_id: simulated.rule.id,
+        rule_name: simulated.rule.name.clone(),
+        position: simulated.rule.position,
+        selected: simulated.selected,
+        messenger: routed.map(|routed| routed.messenger.into()),
+        recipient: routed.map(|routed| routed.recipient.clone()),
+        text: routed.map(|routed| routed.text.clone()),
+        priority: routed.and_then(|routed| routed.priority).map(Into::into),
+        error: simulated.result.as_ref().err().map(|err| err.to_string()),
+    }
+}
+
+pub fn map_routing_rule_version(
+    version: &RoutingRuleVersion,
+    active: Option<i32>,
+) -> RoutingRuleVersionDto {
+    RoutingRuleVersionDto {
+        version: version.version,
+        reason: version.reason.clone(),
+        rule_count: version.rules.len() as u32,
+        active: active == Some(version.version),
+        created_at: version.created_at.to_rfc3339(),
+    }
+}
+
+pub fn map_routing_rule_diff(
+    diff: &RoutingRuleDiff,
+    version: i32,
+    against: i32,
+) -> RoutingRuleDiffDto {
+    RoutingRuleDiffDto {
+        version,
+        against,
+        added: diff.added.iter().map(map_routing_rule).collect(),
+        removed: diff.removed.iter().map(map_routing_rule).collect(),
+        changed: diff
+            .changed
+            .iter()
+            .map(|change| RoutingRuleChangeDto {
+                rule_id: change.rule_id,
+                name: change.name.clone(),
+                fields: change
+                    .fields
+                    .iter()
+                    .map(|field| field.to_string())
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+pub fn map_link(link: &MessageLink, ids: &dyn PublicIdCodec) -> MessageLinkDto {
+    MessageLinkDto {
+        source: ids.encode(&link.source_id),
+        target: ids.encode(&link.target_id),
+        kind: link.kind.into(),
+        created_at: link.created_at.to_rfc3339(),
+    }
+}
+
+pub fn map_dead_letter(dead_letter: &DeadLetter, ids: &dyn PublicIdCodec) -> DeadLetterDto {
+    let event = &dead_letter.entry.event;
+    DeadLetterDto {
+        id: dead_letter.id,
+        message_id: event.message_id,
+        reference: ids.encode(&event.message_id),
+        messenger: event.messenger.into(),
+        recipient: event.recipient.clone(),
+        attempt: event.attempt,
+        reason: dead_letter.entry.reason.clone(),
+        failed_at: dead_letter.entry.failed_at.to_rfc3339(),
+    }
+}
+
+pub fn map_preview(preview: &MessagePreview) -> MessagePreviewDto {
+    MessagePreviewDto {
+        spam_score: preview.spam.score,
+        findings: preview
+            .spam
+            .findings
+            .iter()
+            .map(|finding| ContentFindingDto {
+                rule: finding.rule.to_string(),
+                penalty: finding.penalty,
+                recommendation: finding.recommendation.clone(),
+            })
+            .collect(),
+        length: MessageLengthDto {
+            used: preview.length.used as u32,
+            limit: preview.length.limit as u32,
+            unit: preview.length.unit.as_str().to_string(),
+            parts: preview.length.parts as u32,
+        },
+        estimated_cost: preview.estimated_cost,
+    }
+}
+
+pub fn map_queue_stats(stats: &QueueStats) -> QueueStatsDto {
+    QueueStatsDto {
+        stream: stats.stream.clone(),
+        messages: stats.messages,
+        bytes: stats.bytes,
+        consumers: stats
+            .consumers
+            .iter()
+            .map(|consumer| ConsumerStatsDto {
+                name: consumer.name.clone(),
+                pending: consumer.pending,
+                ack_pending: consumer.ack_pending,
+                redelivered: consumer.redelivered,
+                waiting: consumer.waiting,
+            })
+            .collect(),
+    }
+}
+
+pub fn map_overview(overview: &OperationalOverview) -> OverviewDto {
+    OverviewDto {
+        generated_at: overview.generated_at.to_rfc3339(),
+        queue_depth: overview.queue_depth,
+        in_flight: overview.backlog.count(MessageStatusKind::InFlight),
+        unsent: map_status_counts(&overview.backlog.by_status),
+        oldest_unsent_age_seconds: overview.oldest_unsent_age().map(|age| age.num_seconds()),
+        last_hour: AttemptOutcomesDto {
+            sent: overview.attempts.sent,
+            failed: overview.attempts.failed,
+            failure_rate: overview.attempts.failure_rate(),
+        },
+        tokens: overview
+            .tokens
+            .iter()
+            .map(|health| TokenHealthDto {
+                messenger: health.messenger.into(),
+                active: health.active,
+                inactive: health.inactive,
+                expired: health.expired,
+                expiring: health.expiring,
+            })
+            .collect(),
+    }
+}
+
+pub fn map_messenger_health(report: &MessengerHealthReport) -> MessengerHealthReportDto {
+    MessengerHealthReportDto {
+        target: report.target,
+        messengers: report
+            .messengers
+            .iter()
+            .map(|deliveries| {
+                let windows: Vec<DeliveryWindowDto> = deliveries
+                    .windows
+                    .iter()
+                    .map(|(window, counts)| DeliveryWindowDto {
+                        window_seconds: window.num_seconds(),
+                        sent: counts.sent,
+                        failed: counts.failed,
+                        success_rate: counts.success_rate(),
+                        meets_target: report.meets_target(counts),
+                    })
+                    .collect();
+                MessengerHealthDto {
+                    messenger: deliveries.messenger.into(),
+                    sent_total: deliveries.total.sent,
+                    failed_total: deliveries.total.failed,
+                    healthy: windows
+                        .iter()
+                        .all(|window| window.meets_target != Some(false)),
+                    windows,
+                }
+            })
+            .collect(),
+    }
+}
+
+pub fn map_readiness(readiness: &Readiness) -> ReadinessDto {
+    ReadinessDto {
+        ready: readiness.is_ready(),
+        checks: readiness
+            .checks
+            .iter()
+            .map(|check| ReadinessCheckDto {
+                name: check.name.to_string(),
+                ok: check.error.is_none(),
+                error: check.error.clone(),
+            })
+            .collect(),
+    }
+}
+
+fn map_delivery_counts(counts: &DeliveryCounts) -> DeliveryCountsDto {
+    DeliveryCountsDto {
+        total: counts.total,
+        sent: counts.sent,
+        failed: counts.failed,
+        success_rate: counts.success_rate(),
+    }
+}
+
+pub fn map_delivery_stats(
+    stats: &DeliveryStats,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> DeliveryStatsDto {
+    DeliveryStatsDto {
+        from: from.to_rfc3339(),
+        to: to.to_rfc3339(),
+        total: map_delivery_counts(&stats.total()),
+        by_messenger: stats
+            .by_messenger()
+            .iter()
+            .map(|(messenger, counts)| MessengerDeliveryStatsDto {
+                messenger: (*messenger).into(),
+                counts: map_delivery_counts(counts),
+            })
+            .collect(),
+        by_day: stats
+            .by_day()
+            .iter()
+            .map(|(day, counts)| DailyDeliveryStatsDto {
+                day: day.to_string(),
+                counts: map_delivery_counts(counts),
+            })
+            .collect(),
+    }
+}
+
+pub fn map_status_change(
+    change: &MessageStatusChange,
+    ids: &dyn PublicIdCodec,
+) -> MessageStatusEventDto {
+    MessageStatusEventDto {
+        message_id: change.message_id,
+        reference: ids.encode(&change.message_id),
+        status: change.status.into(),
+        attempts: change.attempts,
+    }
+}
+
+pub fn map_status_counts(counts: &[(MessageStatusKind, u64)]) -> MessageStatusCountsDto {
+    let mut dto = MessageStatusCountsDto::default();
+    for (kind, count) in counts {
+        let field = match kind {
+            MessageStatusKind::Pending => &mut dto.pending,
+            MessageStatusKind::Scheduled => &mut dto.scheduled,
+            MessageStatusKind::InFlight => &mut dto.in_flight,
+            MessageStatusKind::Sent => &mut dto.sent,
+            MessageStatusKind::Retrying => &mut dto.retrying,
+            MessageStatusKind::Failed => &mut dto.failed,
+            MessageStatusKind::Cancelled => &mut dto.cancelled,
+            MessageStatusKind::Deferred => &mut dto.deferred,
+            MessageStatusKind::Suppressed => &mut dto.suppressed,
+        };
+        *field += count;
+        dto.total += count;
+    }
+    dto
+}
+
+pub fn map_trace(trace: &MessageTrace) -> MessageTraceDto {
+    MessageTraceDto {
+        message_id: trace.message_id,
+        would_send: trace.would_send,
+        steps: trace
+            .steps
+            .iter()
+            .map(|step| TraceStepDto {
+                step: step.step.to_string(),
+                passed: step.passed,
+                detail: step.detail.clone(),
+            })
+            .collect(),
+    }
+}
+
+pub fn map_worker(worker: &Worker, alive: bool) -> WorkerDto {
+    WorkerDto {
+        id: worker.id,
+        hostname: worker.hostname.clone(),
+        version: worker.version.clone(),
+        bus: worker.bus.clone(),
+        started_at: worker.started_at.to_rfc3339(),
+        heartbeat_at: worker.heartbeat_at.to_rfc3339(),
+        alive,
+        in_flight: worker.stats.in_flight,
+        handled: worker.stats.handled,
+        failed: worker.stats.failed,
+    }
+}
+
+pub fn map_public_status(status: &PublicMessageStatus) -> PublicMessageStatusDto {
+    PublicMessageStatusDto {
+        state: status.state.into(),
+        status: status.status.as_ref().map(MessageStatusDto::from),
+        messenger: status.messenger.map(Into::into),
+        updated_at: status.updated_at.map(|updated_at| updated_at.to_rfc3339()),
+    }
+}
+
+pub fn map_delivery_semantics(semantics: &DeliverySemantics) -> DeliverySemanticsDto {
+    DeliverySemanticsDto {
+        bus: semantics.bus.clone(),
+        durable: semantics.durable,
+        max_attempts: semantics.max_attempts,
+        max_deliver: semantics.max_deliver,
+        ack_wait_seconds: semantics.ack_wait.map(|ack_wait| ack_wait.as_secs()),
+        retry_delays_ms: semantics
+            .retry_delays()
+            .iter()
+            .map(|delay| delay.as_millis() as u64)
+            .collect(),
+        retry_jitter: semantics.retry_backoff.jitter,
+        longest_retry_delay_ms: semantics.longest_retry_delay().as_millis() as u64,
+    }
+}
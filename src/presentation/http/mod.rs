@@ -1,5 +1,11 @@
+pub mod client_ip;
+pub mod csrf;
 pub mod endpoints;
+pub mod jwks;
 pub mod mappers;
+pub mod rate_limit;
+pub mod request_id;
 pub mod requests;
 pub mod responses;
 pub mod security;
+pub mod telemetry;
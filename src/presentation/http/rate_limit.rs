@@ -0,0 +1,141 @@
+use std::{sync::Arc, time::Duration};
+
+use poem::{
+    Endpoint, IntoResponse, Middleware, Request, Response, Result as PoemResult,
+    http::{StatusCode, header::RETRY_AFTER},
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    application::services::{
+        jwt::{JwtService, JwtServiceConfig},
+        rate_limit::{RateDecision, RateLimit, RateLimitStore, WarmUp},
+    },
+    presentation::http::client_ip::ClientIp,
+};
+
+/// Probes and scrapers poll these, and they are cheap.
+const UNLIMITED_PATHS: [&str; 2] = ["/health", "/metrics"];
+
+/// Limits requests per caller, identified by API key or session, and per
+/// client IP; a request must fit in both. Answers 429 with `Retry-After`
+/// once a bucket is empty.
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    per_user: Option<RateLimit>,
+    per_ip: Option<RateLimit>,
+    warm_up: WarmUp,
+    jwt: Arc<JwtService>,
+}
+
+impl RateLimiter {
+    pub fn new(
+        store: Arc<dyn RateLimitStore>,
+        per_user: Option<RateLimit>,
+        per_ip: Option<RateLimit>,
+        warm_up: WarmUp,
+        jwt_config: JwtServiceConfig,
+    ) -> Self {
+        Self {
+            store,
+            per_user,
+            per_ip,
+            warm_up,
+            jwt: Arc::new(JwtService::new(jwt_config)),
+        }
+    }
+}
+
+impl<E: Endpoint> Middleware<E> for RateLimiter {
+    type Output = RateLimiterEndpoint<E>;
+
+    fn transform(&self, inner: E) -> Self::Output {
+        RateLimiterEndpoint {
+            inner,
+            store: self.store.clone(),
+            per_user: self.per_user,
+            per_ip: self.per_ip,
+            warm_up: self.warm_up,
+            jwt: self.jwt.clone(),
+        }
+    }
+}
+
+pub struct RateLimiterEndpoint<E> {
+    inner: E,
+    store: Arc<dyn RateLimitStore>,
+    per_user: Option<RateLimit>,
+    per_ip: Option<RateLimit>,
+    warm_up: WarmUp,
+    jwt: Arc<JwtService>,
+}
+
+impl<E> RateLimiterEndpoint<E> {
+    /// The API key's hash, so keys never reach the store, or the user of
+    /// a valid access token. Anonymous requests are limited by IP only.
+    fn caller(&self, req: &Request) -> Option<String> {
+        let authorization = req.header("Authorization");
+        if let Some(key) = authorization.and_then(|value| value.strip_prefix("ApiKey ")) {
+            return Some(format!(
+                "key:{}",
+                hex::encode(Sha256::digest(key.trim().as_bytes()))
+            ));
+        }
+
+        let token = match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+            Some(token) => token.trim().to_string(),
+            None => req.cookie().get("access_token")?.value_str().to_string(),
+        };
+        let claims = self.jwt.verify(&token).ok()?;
+        Some(format!("user:{}", claims.sub))
+    }
+
+    /// Stores that can't be reached let requests through rather than take
+    /// the API down with them.
+    async fn take(&self, key: &str, limit: &RateLimit) -> Option<Duration> {
+        match self.store.take(key, limit, self.warm_up.fill()).await {
+            Ok(RateDecision::Allow) => None,
+            Ok(RateDecision::Limited(retry_after)) => Some(retry_after),
+            Err(err) => {
+                tracing::error!(error = ?err, "rate limit store failed");
+                None
+            }
+        }
+    }
+
+    async fn retry_after(&self, req: &Request) -> Option<Duration> {
+        if let (Some(limit), Some(caller)) = (&self.per_user, self.caller(req)) {
+            let retry_after = self.take(&caller, limit).await;
+            if retry_after.is_some() {
+                return retry_after;
+            }
+        }
+        if let Some(limit) = &self.per_ip {
+            let ip = ClientIp::of(req).0?;
+            return self.take(&format!("ip:{ip}"), limit).await;
+        }
+        None
+    }
+}
+
+impl<E: Endpoint> Endpoint for RateLimiterEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> PoemResult<Self::Output> {
+        let limited = if UNLIMITED_PATHS.contains(&req.uri().path()) {
+            None
+        } else {
+            self.retry_after(&req).await
+        };
+        if let Some(retry_after) = limited {
+            // Rounded up, so retrying on time finds a token.
+            let seconds = retry_after.as_millis().div_ceil(1000).max(1);
+            return Ok(Response::builder()
+                .status(StatusCode::TOO_MANY_REQUESTS)
+                .header(RETRY_AFTER, seconds.to_string())
+                .body("too many requests"));
+        }
+
+        self.inner.call(req).await.map(IntoResponse::into_response)
+    }
+}
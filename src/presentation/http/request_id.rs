@@ -0,0 +1,62 @@
+use std::time::Instant;
+
+use poem::{Endpoint, IntoResponse, Middleware, Request, Response, Result as PoemResult};
+use tracing::{Instrument, info_span};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Logs every request under a span carrying its request id, taken from
+/// `X-Request-Id` when a proxy in front already assigned one. The id is
+/// echoed back so clients can quote it.
+pub struct RequestId;
+
+impl<E: Endpoint> Middleware<E> for RequestId {
+    type Output = RequestIdEndpoint<E>;
+
+    fn transform(&self, inner: E) -> Self::Output {
+        RequestIdEndpoint { inner }
+    }
+}
+
+pub struct RequestIdEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for RequestIdEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> PoemResult<Self::Output> {
+        let request_id = req
+            .header(REQUEST_ID_HEADER)
+            .filter(|id| !id.is_empty() && id.len() <= 128)
+            .map(str::to_string)
+            .unwrap_or_else(|| Uuid::new_v4().to_string());
+        let span = info_span!(
+            "request",
+            request_id = %request_id,
+            method = %req.method(),
+            path = %req.uri().path(),
+        );
+
+        let started = Instant::now();
+        let mut response = match self.inner.call(req).instrument(span.clone()).await {
+            Ok(response) => response.into_response(),
+            Err(err) => err.into_response(),
+        };
+        let status = response.status();
+        let latency_ms = started.elapsed().as_millis() as u64;
+        span.in_scope(|| {
+            if status.is_server_error() {
+                tracing::error!(status = status.as_u16(), latency_ms, "request failed");
+            } else {
+                tracing::info!(status = status.as_u16(), latency_ms, "request completed");
+            }
+        });
+
+        if let Ok(value) = request_id.parse() {
+            response.headers_mut().insert(REQUEST_ID_HEADER, value);
+        }
+        Ok(response)
+    }
+}
@@ -1,39 +1,381 @@
-use poem_openapi::Object;
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use poem::{FromRequest, Request, RequestBody, Result as PoemResult};
+use poem_openapi::{Object, error::ParseParamError, types::ParseFromParameter};
 use uuid::Uuid;
 
-use crate::presentation::models::{MessengerKind, RequestedByKind};
+use crate::presentation::models::{
+    ApiKeyScopeKind, ChatActionKind, MemberRoleKind, MessageClassKind, MessagePriorityKind,
+    MessageStatusDto, MessengerKind, RequestedByKind, UserRoleKind,
+};
 
+/// Which fields are needed depends on the configured provider: `email`
+/// alone, `email` with `password`, or an OIDC `id_token`.
 #[derive(Object, Debug)]
 pub struct AuthRequestDto {
+    pub email: Option<String>,
+    pub display_name: Option<String>,
+    pub password: Option<String>,
+    pub id_token: Option<String>,
+}
+
+#[derive(Object, Debug)]
+pub struct RegisterRequestDto {
+    #[oai(validator(min_length = 1))]
     pub email: String,
+    pub password: String,
     pub display_name: Option<String>,
 }
 
+#[derive(Object, Debug)]
+pub struct ChangePasswordRequestDto {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Object, Debug)]
+pub struct ConfirmEmailRequestDto {
+    #[oai(validator(min_length = 1))]
+    pub token: String,
+}
+
+#[derive(Object, Debug)]
+pub struct ResetPasswordRequestDto {
+    #[oai(validator(min_length = 1))]
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Object, Debug)]
+pub struct PasswordResetRequestDto {
+    #[oai(validator(min_length = 1))]
+    pub email: String,
+}
+
+#[derive(Object, Debug)]
+pub struct MagicLinkRequestDto {
+    #[oai(validator(min_length = 1, max_length = 320))]
+    pub email: String,
+}
+
+#[derive(Object, Debug)]
+pub struct VerifyMagicLinkRequestDto {
+    #[oai(validator(min_length = 1))]
+    pub token: String,
+}
+
+#[derive(Object, Debug)]
+pub struct UserRoleRequestDto {
+    pub role: UserRoleKind,
+}
+
+#[derive(Object, Debug)]
+pub struct OrganizationRequestDto {
+    #[oai(validator(min_length = 1, max_length = 128))]
+    pub name: String,
+}
+
+#[derive(Object, Debug)]
+pub struct InvitationRequestDto {
+    /// Only the user signing in with this email may accept.
+    #[oai(validator(min_length = 1, max_length = 320))]
+    pub email: String,
+    /// `member` unless given.
+    pub role: Option<MemberRoleKind>,
+}
+
+#[derive(Object, Debug)]
+pub struct AcceptInvitationRequestDto {
+    #[oai(validator(min_length = 1))]
+    pub token: String,
+}
+
+#[derive(Object, Debug)]
+pub struct ApiKeyRequestDto {
+    /// What the key is for, e.g. `billing cron`.
+    #[oai(validator(min_length = 1, max_length = 64))]
+    pub name: String,
+    /// Every scope unless given; grant only what the key's job needs.
+    pub scopes: Option<Vec<ApiKeyScopeKind>>,
+    /// CIDRs such as `10.0.0.0/8` the key may be used from; any address
+    /// unless given.
+    pub allowed_ips: Option<Vec<String>>,
+}
+
 #[derive(Object, Debug)]
 pub struct RegisterTokenRequestDto {
     pub messenger: MessengerKind,
+    /// Registering a label again replaces the caller's token with that label;
+    /// tokens with different labels stay active side by side.
+    #[oai(validator(min_length = 1, max_length = 64))]
+    pub label: Option<String>,
     #[oai(validator(min_length = 1))]
     pub access_token: String,
     pub refresh_token: Option<String>,
+    /// When `access_token` expires; it is refreshed shortly before.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Device the token was issued to, required to refresh VK ID tokens.
+    pub device_id: Option<String>,
+    /// Lets every member of the organization send with the token. Kept as
+    /// it was when replacing a token unless given.
+    pub shared: Option<bool>,
+}
+
+#[derive(Object, Debug)]
+pub struct TokenSharingRequestDto {
+    pub shared: bool,
 }
 
+/// Omit `messenger` and `recipient` to have the sender's routing rules pick
+/// them from `class`, `tags` and `metadata`.
 #[derive(Object, Debug)]
 pub struct SendMessageRequestDto {
-    pub messenger: MessengerKind,
+    pub messenger: Option<MessengerKind>,
     #[oai(validator(min_length = 1))]
-    pub recipient: String,
+    pub recipient: Option<String>,
     #[oai(validator(min_length = 1, max_length = 4096))]
     pub text: String,
     #[oai(default)]
     pub requested_by: RequestedByKind,
+    #[oai(default)]
+    pub class: MessageClassKind,
+    #[oai(default)]
+    pub priority: MessagePriorityKind,
+    /// Delivery is abandoned once this moment passes.
+    pub deadline: Option<DateTime<Utc>>,
+    /// Id or reference of an earlier message this one replies to.
+    pub thread_parent: Option<String>,
+    /// Also stored with the message; `GET /messages` filters by them.
+    #[oai(default, validator(max_items = 16, min_length = 1, max_length = 64))]
+    pub tags: Vec<String>,
+    #[oai(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// Token that delivers the message; the latest registered one by default.
+    pub token_id: Option<Uuid>,
+    /// Selects the token by its label instead of `token_id`.
+    #[oai(validator(min_length = 1, max_length = 64))]
+    pub token_label: Option<String>,
+}
+
+#[derive(Object, Debug)]
+pub struct EventRequestDto {
+    /// Matched against the `match_event` of routing rules.
+    #[oai(validator(min_length = 1, max_length = 128))]
+    pub name: String,
+    #[oai(default)]
+    pub class: MessageClassKind,
+    /// Also stored with every message the event schedules.
+    #[oai(default, validator(max_items = 16, min_length = 1, max_length = 64))]
+    pub tags: Vec<String>,
+    /// Nested fields are addressed with dots in rules and templates, e.g.
+    /// `{order.id}`.
+    #[oai(default)]
+    pub payload: BTreeMap<String, serde_json::Value>,
+    /// Delivery of the resulting messages is abandoned once this passes.
+    pub deadline: Option<DateTime<Utc>>,
+}
+
+/// A send when `event` is unset, otherwise a business event.
+#[derive(Object, Debug)]
+pub struct RoutingSimulationRequestDto {
+    pub event: Option<String>,
+    #[oai(default)]
+    pub class: MessageClassKind,
+    #[oai(default)]
+    pub tags: Vec<String>,
+    /// Metadata of a send or payload of an event.
+    #[oai(default)]
+    pub payload: BTreeMap<String, serde_json::Value>,
+    /// Text of a send; events have none.
+    #[oai(default)]
+    pub text: String,
+}
+
+#[derive(Object, Debug)]
+pub struct RoutingRuleRequestDto {
+    #[oai(validator(min_length = 1, max_length = 128))]
+    pub name: String,
+    /// Rules are evaluated by ascending position. New rules go last when
+    /// unset; updates keep the current position.
+    pub position: Option<i32>,
+    /// Defaults to `true`.
+    pub enabled: Option<bool>,
+    /// Makes the rule react to `POST /events` with this name instead of
+    /// to sends.
+    pub match_event: Option<String>,
+    pub match_class: Option<MessageClassKind>,
+    #[oai(default)]
+    pub match_tags: Vec<String>,
+    #[oai(default)]
+    pub match_metadata: BTreeMap<String, String>,
+    pub messenger: MessengerKind,
+    /// May contain `{key}` placeholders filled from the message metadata.
+    #[oai(validator(min_length = 1))]
+    pub recipient: String,
+    /// Replaces the message text; `{text}` is the text sent.
+    #[oai(validator(max_length = 4096))]
+    pub template: Option<String>,
+    pub priority: Option<MessagePriorityKind>,
 }
 
 #[derive(Object, Debug)]
 pub struct RetryMessageRequestDto {
-    pub message_id: Uuid,
+    /// Id or reference of the message.
+    pub message_id: String,
+}
+
+/// Failed messages of every user to requeue; unset fields match all.
+#[derive(Object, Debug)]
+pub struct RequeueFailedRequestDto {
+    pub messenger: Option<MessengerKind>,
+    pub failed_after: Option<DateTime<Utc>>,
+    pub failed_before: Option<DateTime<Utc>>,
+    /// Case-insensitive part of the failure reason.
+    #[oai(validator(min_length = 1))]
+    pub error: Option<String>,
+    /// At most this many are requeued per call, oldest failure first.
+    /// Defaults to 500, capped at 1000.
+    pub limit: Option<u32>,
 }
 
 #[derive(Object, Debug)]
 pub struct BatchSendRequestDto {
     pub messages: Vec<SendMessageRequestDto>,
 }
+
+#[derive(Object, Debug)]
+pub struct SendOtpRequestDto {
+    pub messenger: MessengerKind,
+    #[oai(validator(min_length = 1))]
+    pub recipient: String,
+}
+
+#[derive(Object, Debug)]
+pub struct VerifyOtpRequestDto {
+    pub messenger: MessengerKind,
+    #[oai(validator(min_length = 1))]
+    pub recipient: String,
+    #[oai(validator(min_length = 1, max_length = 16))]
+    pub code: String,
+}
+
+#[derive(Object, Debug)]
+pub struct RecordConsentRequestDto {
+    pub messenger: MessengerKind,
+    #[oai(validator(min_length = 1))]
+    pub recipient: String,
+    #[oai(validator(min_length = 1))]
+    pub source: String,
+    pub proof: Option<String>,
+}
+
+#[derive(Object, Debug)]
+pub struct RequestOptInRequestDto {
+    pub messenger: MessengerKind,
+    #[oai(validator(min_length = 1))]
+    pub recipient: String,
+    #[oai(validator(min_length = 1))]
+    pub source: String,
+}
+
+#[derive(Object, Debug)]
+pub struct ConfirmOptInRequestDto {
+    pub messenger: MessengerKind,
+    #[oai(validator(min_length = 1))]
+    pub recipient: String,
+    /// The recipient's reply to the confirmation message.
+    #[oai(validator(min_length = 1))]
+    pub reply: String,
+}
+
+#[derive(Object, Debug)]
+pub struct RevokeConsentRequestDto {
+    pub messenger: MessengerKind,
+    #[oai(validator(min_length = 1))]
+    pub recipient: String,
+}
+
+#[derive(Object, Debug)]
+pub struct RedriveDeadLetterRequestDto {
+    pub id: u64,
+}
+
+#[derive(Object, Debug)]
+pub struct PreviewMessageRequestDto {
+    pub messenger: MessengerKind,
+    #[oai(validator(min_length = 1))]
+    pub text: String,
+}
+
+#[derive(Object, Debug)]
+pub struct ChatActionRequestDto {
+    pub action: ChatActionKind,
+}
+
+/// Filters shared by `GET /messages` and `GET /messages/export`, read from
+/// the query string. `user_id` stands for that user's personal
+/// organization.
+#[derive(Debug)]
+pub struct MessageFilterParams {
+    pub status: Option<MessageStatusDto>,
+    pub messenger: Option<MessengerKind>,
+    pub recipient: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub tag: Option<String>,
+    pub user_id: Option<Uuid>,
+}
+
+impl<'a> FromRequest<'a> for MessageFilterParams {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> PoemResult<Self> {
+        let query = req.params::<HashMap<String, String>>()?;
+        Ok(Self {
+            status: query_param(&query, "status")?,
+            messenger: query_param(&query, "messenger")?,
+            recipient: query_param(&query, "recipient")?,
+            created_after: query_param(&query, "created_after")?,
+            created_before: query_param(&query, "created_before")?,
+            tag: query_param(&query, "tag")?,
+            user_id: query_param(&query, "user_id")?,
+        })
+    }
+}
+
+/// What the provider sends the browser back to the OAuth callback with:
+/// a `code` and the `state` it was sent off with, or an `error`.
+#[derive(Debug)]
+pub struct OAuthCallbackParams {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub error: Option<String>,
+}
+
+impl<'a> FromRequest<'a> for OAuthCallbackParams {
+    async fn from_request(req: &'a Request, _body: &mut RequestBody) -> PoemResult<Self> {
+        let query = req.params::<HashMap<String, String>>()?;
+        Ok(Self {
+            code: query_param(&query, "code")?,
+            state: query_param(&query, "state")?,
+            error: query_param(&query, "error")?,
+        })
+    }
+}
+
+fn query_param<T: ParseFromParameter>(
+    query: &HashMap<String, String>,
+    name: &'static str,
+) -> PoemResult<Option<T>> {
+    query
+        .get(name)
+        .map(|value| {
+            T::parse_from_parameter(value).map_err(|err| {
+                ParseParamError {
+                    name,
+                    reason: err.into_message(),
+                }
+                .into()
+            })
+        })
+        .transpose()
+}
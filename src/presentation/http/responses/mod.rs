@@ -1,7 +1,17 @@
-use poem_openapi::{Enum, Object};
+use std::collections::BTreeMap;
+
+use poem::Body;
+use poem_openapi::{
+    ApiResponse, Enum, Object,
+    payload::{Binary, Json},
+};
 use uuid::Uuid;
 
-use crate::presentation::models::{ChatTypeKind, MessageStatusDto, MessengerKind, RequestedByKind};
+use crate::presentation::models::{
+    ApiKeyScopeKind, ChatTypeKind, ConsentStatusKind, DeliveryStateKind, MemberRoleKind,
+    MessageClassKind, MessageLinkKindDto, MessagePriorityKind, MessageStatusDto, MessengerKind,
+    RequestedByKind, UserRoleKind,
+};
 
 #[derive(Object)]
 pub struct AuthResponseDto {
@@ -12,7 +22,13 @@ pub struct AuthResponseDto {
 pub struct MessengerTokenDto {
     pub id: Uuid,
     pub messenger: MessengerKind,
+    pub label: Option<String>,
+    /// Member who registered the token.
+    pub registered_by: Uuid,
+    /// Whether every member of the organization may send with it.
+    pub shared: bool,
     pub status: MessengerTokenStatusDto,
+    pub expires_at: Option<String>,
     pub updated_at: String,
 }
 
@@ -25,20 +41,36 @@ pub enum MessengerTokenStatusDto {
 #[derive(Object)]
 pub struct SendMessageResponseDto {
     pub message_id: Uuid,
+    pub reference: String,
+    /// Only set for `delivery=sync`: whether the message went out within
+    /// the request rather than being queued.
+    pub sent: Option<bool>,
 }
 
 #[derive(Object)]
 pub struct MessageHistoryDto {
     pub id: Uuid,
+    pub reference: String,
     pub messenger: MessengerKind,
     pub recipient: String,
     pub status: MessageStatusDto,
     pub attempts: u32,
     pub body: String,
+    pub class: MessageClassKind,
+    pub priority: MessagePriorityKind,
     pub last_error: Option<String>,
     pub requested_by: RequestedByKind,
+    /// Member who requested the send; absent once their account was erased.
+    pub requested_by_user: Option<Uuid>,
+    pub deadline: Option<String>,
+    pub scheduled_for: String,
+    /// Token selected for delivery; unset when the latest one is used.
+    pub token_id: Option<Uuid>,
+    pub tags: Vec<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// When the sender deleted it; only admins see deleted messages.
+    pub deleted_at: Option<String>,
 }
 
 #[derive(Object)]
@@ -57,6 +89,28 @@ pub struct PaginatedChatsDto {
     pub next_offset: Option<u32>,
 }
 
+#[derive(Object, Default)]
+pub struct MessageStatusCountsDto {
+    pub total: u64,
+    pub pending: u64,
+    pub scheduled: u64,
+    pub in_flight: u64,
+    pub sent: u64,
+    pub retrying: u64,
+    pub failed: u64,
+    pub cancelled: u64,
+    pub deferred: u64,
+    pub suppressed: u64,
+}
+
+#[derive(Object)]
+pub struct MessageStatusEventDto {
+    pub message_id: Uuid,
+    pub reference: String,
+    pub status: MessageStatusDto,
+    pub attempts: u32,
+}
+
 #[derive(Object)]
 pub struct PaginatedMessagesDto {
     pub messages: Vec<MessageHistoryDto>,
@@ -80,6 +134,7 @@ pub struct BatchSendItemResultDto {
     pub index: u32,
     pub success: bool,
     pub message_id: Option<Uuid>,
+    pub reference: Option<String>,
     pub error: Option<String>,
 }
 
@@ -90,3 +145,500 @@ pub struct BatchSendResponseDto {
     pub successful: u32,
     pub failed: u32,
 }
+
+#[derive(Object)]
+pub struct SendOtpResponseDto {
+    pub message_id: Uuid,
+    pub reference: String,
+    pub expires_at: String,
+}
+
+#[derive(Object)]
+pub struct VerifyOtpResponseDto {
+    pub verified: bool,
+}
+
+#[derive(Object)]
+pub struct ConsentDto {
+    pub id: Uuid,
+    pub messenger: MessengerKind,
+    pub recipient: String,
+    pub status: ConsentStatusKind,
+    pub source: String,
+    pub proof: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Object)]
+pub struct MessageLinkDto {
+    pub source: String,
+    pub target: String,
+    pub kind: MessageLinkKindDto,
+    pub created_at: String,
+}
+
+#[derive(Object)]
+pub struct RelatedMessagesDto {
+    pub messages: Vec<MessageHistoryDto>,
+    pub links: Vec<MessageLinkDto>,
+}
+
+#[derive(Object)]
+pub struct DeadLetterDto {
+    pub id: u64,
+    pub message_id: Uuid,
+    pub reference: String,
+    pub messenger: MessengerKind,
+    pub recipient: String,
+    pub attempt: u32,
+    pub reason: String,
+    pub failed_at: String,
+}
+
+#[derive(Object)]
+pub struct ContentFindingDto {
+    pub rule: String,
+    pub penalty: u32,
+    pub recommendation: String,
+}
+
+#[derive(Object)]
+pub struct MessageLengthDto {
+    pub used: u32,
+    pub limit: u32,
+    /// `utf16_code_units` or `characters`, as counted by the messenger.
+    pub unit: String,
+    pub parts: u32,
+}
+
+#[derive(Object)]
+pub struct MessagePreviewDto {
+    /// 0 (clean) to 100 (likely to be flagged by the platform).
+    pub spam_score: u32,
+    pub findings: Vec<ContentFindingDto>,
+    pub length: MessageLengthDto,
+    pub estimated_cost: Option<f64>,
+}
+
+#[derive(Object)]
+pub struct ConsumerStatsDto {
+    pub name: String,
+    pub pending: u64,
+    /// Unset when the bus does not report it, as for RabbitMQ.
+    pub ack_pending: Option<u64>,
+    /// Unset when the bus does not report it; only JetStream does.
+    pub redelivered: Option<u64>,
+    pub waiting: Option<u64>,
+}
+
+#[derive(Object)]
+pub struct QueueStatsDto {
+    /// The JetStream stream, RabbitMQ queue or SQS queue URL.
+    pub stream: String,
+    pub messages: u64,
+    /// Unset when the bus does not report it; only JetStream does.
+    pub bytes: Option<u64>,
+    pub consumers: Vec<ConsumerStatsDto>,
+}
+
+#[derive(Object)]
+pub struct OverviewDto {
+    pub generated_at: String,
+    /// Messages in the bus not yet handed to a worker; unset when the bus
+    /// reports no backlog.
+    pub queue_depth: Option<u64>,
+    /// Messages being sent right now.
+    pub in_flight: u64,
+    /// Undelivered messages by status.
+    pub unsent: MessageStatusCountsDto,
+    /// Seconds the longest-waiting due message has waited; unset when none
+    /// is due.
+    pub oldest_unsent_age_seconds: Option<i64>,
+    /// Send attempts over the last hour.
+    pub last_hour: AttemptOutcomesDto,
+    pub tokens: Vec<TokenHealthDto>,
+}
+
+#[derive(Object)]
+pub struct AttemptOutcomesDto {
+    pub sent: u64,
+    pub failed: u64,
+    /// Failed among ended attempts; unset while none ended.
+    pub failure_rate: Option<f64>,
+}
+
+#[derive(Object)]
+pub struct TokenHealthDto {
+    pub messenger: MessengerKind,
+    pub active: u64,
+    pub inactive: u64,
+    /// Active tokens the platform no longer accepts.
+    pub expired: u64,
+    /// Active tokens expiring within a day.
+    pub expiring: u64,
+}
+
+#[derive(Object)]
+pub struct MessengerHealthReportDto {
+    /// Share of sends expected to succeed.
+    pub target: f64,
+    pub messengers: Vec<MessengerHealthDto>,
+}
+
+#[derive(Object)]
+pub struct MessengerHealthDto {
+    pub messenger: MessengerKind,
+    /// Since this instance started.
+    pub sent_total: u64,
+    pub failed_total: u64,
+    pub windows: Vec<DeliveryWindowDto>,
+    /// False once any window with sends falls short of the target.
+    pub healthy: bool,
+}
+
+#[derive(Object)]
+pub struct DeliveryWindowDto {
+    pub window_seconds: i64,
+    pub sent: u64,
+    pub failed: u64,
+    /// Unset while nothing was sent in the window.
+    pub success_rate: Option<f64>,
+    pub meets_target: Option<bool>,
+}
+
+#[derive(Object)]
+pub struct ReadinessDto {
+    pub ready: bool,
+    pub checks: Vec<ReadinessCheckDto>,
+}
+
+#[derive(Object)]
+pub struct ReadinessCheckDto {
+    /// `database`, `bus` or `workers`.
+    pub name: String,
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+#[derive(ApiResponse)]
+pub enum ReadinessResponse {
+    #[oai(status = 200)]
+    Ready(Json<ReadinessDto>),
+    #[oai(status = 503)]
+    NotReady(Json<ReadinessDto>),
+}
+
+#[derive(Object)]
+pub struct DeliveryCountsDto {
+    pub total: u64,
+    pub sent: u64,
+    pub failed: u64,
+    /// Sent among sent and failed; unset while none has finished.
+    pub success_rate: Option<f64>,
+}
+
+#[derive(Object)]
+pub struct MessengerDeliveryStatsDto {
+    pub messenger: MessengerKind,
+    #[oai(flatten)]
+    pub counts: DeliveryCountsDto,
+}
+
+#[derive(Object)]
+pub struct DailyDeliveryStatsDto {
+    /// UTC day, `YYYY-MM-DD`.
+    pub day: String,
+    #[oai(flatten)]
+    pub counts: DeliveryCountsDto,
+}
+
+#[derive(Object)]
+pub struct DeliveryStatsDto {
+    pub from: String,
+    pub to: String,
+    pub total: DeliveryCountsDto,
+    pub by_messenger: Vec<MessengerDeliveryStatsDto>,
+    pub by_day: Vec<DailyDeliveryStatsDto>,
+}
+
+#[derive(Object)]
+pub struct TraceStepDto {
+    /// One of `message`, `deadline`, `token`, `consent`, `routing`,
+    /// `frequency_cap`, `render` or `adapter`.
+    pub step: String,
+    /// False when the stage would stop or hold back the message.
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Object)]
+pub struct MessageTraceDto {
+    pub message_id: Uuid,
+    pub would_send: bool,
+    pub steps: Vec<TraceStepDto>,
+}
+
+#[derive(Object)]
+pub struct RequeueFailedResponseDto {
+    pub requeued: Vec<Uuid>,
+    /// Left failed: their deadline passed or the user has no active token
+    /// for the messenger.
+    pub skipped: Vec<Uuid>,
+}
+
+#[derive(Object)]
+pub struct WorkerDto {
+    pub id: Uuid,
+    pub hostname: String,
+    pub version: String,
+    pub bus: String,
+    pub started_at: String,
+    pub heartbeat_at: String,
+    /// False once the worker missed enough heartbeats; its unfinished
+    /// claims are then taken over by other workers.
+    pub alive: bool,
+    pub in_flight: u64,
+    pub handled: u64,
+    pub failed: u64,
+}
+
+#[derive(Object)]
+pub struct StatusLinkDto {
+    /// Path segment for `GET /public/status/:token`.
+    pub token: String,
+    pub expires_at: String,
+}
+
+#[derive(Object)]
+pub struct PublicMessageStatusDto {
+    pub state: DeliveryStateKind,
+    /// Omitted unless the deployment exposes detailed status.
+    pub status: Option<MessageStatusDto>,
+    pub messenger: Option<MessengerKind>,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Object)]
+pub struct DeliverySemanticsDto {
+    pub bus: String,
+    /// Whether queued and deferred messages survive a restart.
+    pub durable: bool,
+    pub max_attempts: u32,
+    /// Deliveries the broker allows per message, when it limits them.
+    pub max_deliver: Option<u32>,
+    pub ack_wait_seconds: Option<u64>,
+    /// Wait after each failed attempt that is retried, before jitter.
+    pub retry_delays_ms: Vec<u64>,
+    pub retry_jitter: f64,
+    pub longest_retry_delay_ms: u64,
+}
+
+#[derive(Object)]
+pub struct PasswordResetTokenDto {
+    /// Hand to the user out of band; it is shown only once.
+    pub token: String,
+    pub expires_at: String,
+}
+
+#[derive(Object)]
+pub struct ApiKeyDto {
+    pub id: Uuid,
+    pub name: String,
+    /// Start of the key, to tell keys apart.
+    pub prefix: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub scopes: Vec<ApiKeyScopeKind>,
+    /// Empty when the key may be used from any address.
+    pub allowed_ips: Vec<String>,
+}
+
+#[derive(Object)]
+pub struct IssuedApiKeyDto {
+    /// Sent as `Authorization: ApiKey <key>`. Shown only this once.
+    pub key: String,
+    pub api_key: ApiKeyDto,
+}
+
+/// The audit record of an account deletion.
+#[derive(Object)]
+pub struct AccountErasureDto {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    /// HMAC-SHA256 of the trimmed, lowercased email under
+    /// `ERASURE_EMAIL_KEY`; absent without one.
+    pub email_hash: Option<String>,
+    pub requested_at: String,
+    /// Unset while the history is still being purged.
+    pub completed_at: Option<String>,
+    pub messages_purged: u64,
+}
+
+#[derive(Object)]
+pub struct SessionDto {
+    pub id: Uuid,
+    /// `User-Agent` of the sign-in.
+    pub device: Option<String>,
+    pub ip: Option<String>,
+    pub created_at: String,
+    pub last_seen_at: String,
+    /// Whether this is the session making the request.
+    pub current: bool,
+}
+
+#[derive(Object)]
+pub struct UserDto {
+    pub id: Uuid,
+    pub email: String,
+    pub display_name: Option<String>,
+    pub role: UserRoleKind,
+    pub disabled_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Object)]
+pub struct PaginatedUsersDto {
+    pub users: Vec<UserDto>,
+    pub has_more: bool,
+    pub next_offset: Option<u32>,
+}
+
+#[derive(Object)]
+pub struct OrganizationDto {
+    pub id: Uuid,
+    pub name: String,
+    /// Whether this is the caller's personal organization, which shares
+    /// their user id.
+    pub personal: bool,
+    /// The caller's role in it.
+    pub role: MemberRoleKind,
+    pub created_at: String,
+}
+
+#[derive(Object)]
+pub struct OrganizationMemberDto {
+    pub user_id: Uuid,
+    pub role: MemberRoleKind,
+    pub joined_at: String,
+}
+
+#[derive(Object)]
+pub struct OrganizationInvitationDto {
+    pub id: Uuid,
+    pub email: String,
+    pub role: MemberRoleKind,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+#[derive(Object)]
+pub struct IssuedInvitationDto {
+    /// Hand to the invitee out of band; it is shown only once.
+    pub token: String,
+    pub invitation: OrganizationInvitationDto,
+}
+
+#[derive(Object)]
+pub struct UserIdentityDto {
+    pub id: Uuid,
+    pub provider: String,
+    pub subject: String,
+    pub created_at: String,
+}
+
+#[derive(Object)]
+pub struct RoutingRuleDto {
+    pub id: Uuid,
+    pub name: String,
+    pub position: i32,
+    pub enabled: bool,
+    pub match_event: Option<String>,
+    pub match_class: Option<MessageClassKind>,
+    pub match_tags: Vec<String>,
+    pub match_metadata: BTreeMap<String, String>,
+    pub messenger: MessengerKind,
+    pub recipient: String,
+    pub template: Option<String>,
+    pub priority: Option<MessagePriorityKind>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Object)]
+pub struct FiredRuleDto {
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub success: bool,
+    pub message_id: Option<Uuid>,
+    pub reference: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Object)]
+pub struct EventResponseDto {
+    pub event: String,
+    /// Rules that matched the event, in evaluation order.
+    pub rules: Vec<FiredRuleDto>,
+    pub scheduled: u32,
+    pub failed: u32,
+}
+
+#[derive(Object)]
+pub struct SimulatedRuleDto {
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub position: i32,
+    /// Whether the rule would deliver; a send only uses the first match.
+    pub selected: bool,
+    pub messenger: Option<MessengerKind>,
+    pub recipient: Option<String>,
+    pub text: Option<String>,
+    pub priority: Option<MessagePriorityKind>,
+    pub error: Option<String>,
+}
+
+#[derive(Object)]
+pub struct RoutingRuleVersionDto {
+    pub version: i32,
+    /// What produced the version, e.g. `updated rule alerts`.
+    pub reason: String,
+    pub rule_count: u32,
+    /// Whether the current rules are this version.
+    pub active: bool,
+    pub created_at: String,
+}
+
+#[derive(Object)]
+pub struct RoutingRuleDiffDto {
+    pub version: i32,
+    /// Version compared with; 0 stands for an empty rule set.
+    pub against: i32,
+    pub added: Vec<RoutingRuleDto>,
+    pub removed: Vec<RoutingRuleDto>,
+    pub changed: Vec<RoutingRuleChangeDto>,
+}
+
+#[derive(Object)]
+pub struct RoutingRuleChangeDto {
+    pub rule_id: Uuid,
+    pub name: String,
+    /// Names of the settings that differ.
+    pub fields: Vec<String>,
+}
+
+#[derive(ApiResponse)]
+pub enum RedirectResponse {
+    #[oai(status = 302)]
+    Found(#[oai(header = "Location")] String),
+}
+
+#[derive(ApiResponse)]
+pub enum MessageExportResponse {
+    /// One header row, then a row per message.
+    #[oai(status = 200, content_type = "text/csv")]
+    Csv(Binary<Body>, #[oai(header = "Content-Disposition")] String),
+    /// One `MessageHistoryDto` object per line.
+    #[oai(status = 200, content_type = "application/x-ndjson")]
+    Jsonl(Binary<Body>, #[oai(header = "Content-Disposition")] String),
+}
@@ -1,32 +1,195 @@
-use poem::{Error as PoemError, Result as PoemResult, http::StatusCode, web::cookie::CookieJar};
+use std::net::IpAddr;
+
+use poem::{Error as PoemError, Request, Result as PoemResult, http::StatusCode};
+use poem_openapi::{
+    SecurityScheme,
+    auth::{ApiKey, Bearer},
+};
 use uuid::Uuid;
 
-use crate::application::services::jwt::{JwtService, JwtServiceConfig};
+use crate::{
+    application::{
+        services::{
+            jwt::{JwtService, JwtServiceConfig},
+            session_revocations::SessionRevocations,
+        },
+        usecases::{
+            manage_api_keys::{ApiKeyNotAllowed, ApiKeyUseCase},
+            manage_organizations::OrganizationUseCase,
+        },
+    },
+    domain::models::{ApiKeyScope, UserRole},
+    presentation::http::client_ip::ClientIp,
+};
+
+/// Browsers send the `access_token` cookie set at sign-in; other clients
+/// may send the same token as `Authorization: Bearer <token>`.
+#[derive(SecurityScheme)]
+pub enum SessionCredentials {
+    Bearer(BearerToken),
+    Cookie(AccessTokenCookie),
+}
+
+/// As `SessionCredentials`, or `Authorization: ApiKey <key>` for services.
+#[derive(SecurityScheme)]
+pub enum ServiceCredentials {
+    Bearer(BearerToken),
+    ApiKey(ApiKeyHeader),
+    Cookie(AccessTokenCookie),
+}
+
+/// The access token a sign-in returns in the `access_token` cookie.
+#[derive(SecurityScheme)]
+#[oai(ty = "bearer", bearer_format = "JWT")]
+pub struct BearerToken(Bearer);
+
+#[derive(SecurityScheme)]
+#[oai(ty = "api_key", key_name = "access_token", key_in = "cookie")]
+pub struct AccessTokenCookie(ApiKey);
 
+/// The whole header value, `ApiKey <key>`.
+#[derive(SecurityScheme)]
+#[oai(
+    ty = "api_key",
+    key_name = "Authorization",
+    key_in = "header",
+    checker = "presented_api_key"
+)]
+pub struct ApiKeyHeader(PresentedApiKey);
+
+/// The `Authorization` header and the address it came from, which a key's
+/// allowlist is checked against.
+pub struct PresentedApiKey {
+    header: String,
+    client_ip: Option<IpAddr>,
+}
+
+async fn presented_api_key(req: &Request, api_key: ApiKey) -> Option<PresentedApiKey> {
+    Some(PresentedApiKey {
+        header: api_key.key,
+        client_ip: ClientIp::of(req).0,
+    })
+}
+
+/// Accepts an access token unless its session was revoked.
 pub struct JwtAuth;
 
+/// Accepts an API key, or an access token as `JwtAuth` does.
+pub struct ApiKeyAuth;
+
+/// Accepts users with the admin role only.
+pub struct AdminAuth;
+
 pub struct AuthenticatedUser {
     pub user_id: Uuid,
     pub email: String,
+    pub role: UserRole,
+    /// `None` when signed in with an API key.
+    pub session_id: Option<Uuid>,
+    /// What the API key signed in with may do; `None` for sessions, which
+    /// may do whatever the role allows.
+    pub scopes: Option<Vec<ApiKeyScope>>,
+}
+
+impl AuthenticatedUser {
+    pub fn is_admin(&self) -> bool {
+        self.role == UserRole::Admin
+    }
+
+    /// Read-only users, and their keys, may look but not send or change
+    /// anything.
+    pub fn require_write(&self) -> PoemResult<()> {
+        if self.role == UserRole::ReadOnly {
+            return Err(PoemError::from_string(
+                "read-only users cannot do this",
+                StatusCode::FORBIDDEN,
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn require_scope(&self, scope: ApiKeyScope) -> PoemResult<()> {
+        match &self.scopes {
+            Some(scopes) if !scopes.contains(&scope) => Err(PoemError::from_string(
+                format!("api key lacks the {} scope", scope.as_str()),
+                StatusCode::FORBIDDEN,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The user a request acts for: the one asked for, which only admins
+    /// may choose freely, or the caller.
+    pub fn acting_for(&self, user_id: Option<Uuid>) -> PoemResult<Uuid> {
+        match user_id {
+            Some(user_id) if user_id != self.user_id && !self.is_admin() => {
+                Err(PoemError::from_string("forbidden", StatusCode::FORBIDDEN))
+            }
+            Some(user_id) => Ok(user_id),
+            None => Ok(self.user_id),
+        }
+    }
+
+    /// The organization a request acts in: the one asked for, usually with
+    /// `X-Organization-Id`, or the caller's personal one. Only members and
+    /// admins may act in an organization.
+    pub async fn organization(
+        &self,
+        organization_id: Option<Uuid>,
+        organizations: &OrganizationUseCase,
+    ) -> PoemResult<Uuid> {
+        let Some(organization_id) = organization_id else {
+            return Ok(self.user_id);
+        };
+        if organization_id == self.user_id || self.is_admin() {
+            return Ok(organization_id);
+        }
+        match organizations.is_member(organization_id, self.user_id).await {
+            Ok(true) => Ok(organization_id),
+            Ok(false) => Err(PoemError::from_string("forbidden", StatusCode::FORBIDDEN)),
+            Err(err) => Err(PoemError::from_string(
+                err.to_string(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+
+    /// The organization a message must belong to to be shown: any for
+    /// admins.
+    pub fn message_owner(&self, organization_id: Uuid) -> Option<Uuid> {
+        (!self.is_admin()).then_some(organization_id)
+    }
 }
 
 impl JwtAuth {
-    pub fn from_cookies(
-        cookie_jar: &CookieJar,
+    pub fn from_credentials(
+        credentials: &SessionCredentials,
         config: &JwtServiceConfig,
+        revocations: &SessionRevocations,
     ) -> PoemResult<AuthenticatedUser> {
-        let token = cookie_jar
-            .get("access_token")
-            .map(|c| c.value_str().to_string())
-            .ok_or_else(|| {
-                PoemError::from_string("access token not found", StatusCode::UNAUTHORIZED)
-            })?;
+        let token = match credentials {
+            SessionCredentials::Bearer(BearerToken(bearer)) => &bearer.token,
+            SessionCredentials::Cookie(AccessTokenCookie(cookie)) => &cookie.key,
+        };
+        Self::verify(token, config, revocations)
+    }
 
+    fn verify(
+        token: &str,
+        config: &JwtServiceConfig,
+        revocations: &SessionRevocations,
+    ) -> PoemResult<AuthenticatedUser> {
         let service = JwtService::new(config.clone());
-        match service.verify(&token) {
+        match service.verify(token) {
+            Ok(claims) if claims.sid.is_some_and(|sid| revocations.is_revoked(sid)) => Err(
+                PoemError::from_string("session revoked", StatusCode::UNAUTHORIZED),
+            ),
             Ok(claims) => Ok(AuthenticatedUser {
                 user_id: claims.sub,
                 email: claims.email,
+                role: claims.role,
+                session_id: claims.sid,
+                scopes: None,
             }),
             Err(_) => Err(PoemError::from_string(
                 "invalid or expired token",
@@ -35,3 +198,64 @@ impl JwtAuth {
         }
     }
 }
+
+impl ApiKeyAuth {
+    pub async fn from_credentials(
+        credentials: &ServiceCredentials,
+        config: &JwtServiceConfig,
+        revocations: &SessionRevocations,
+        api_keys: &ApiKeyUseCase,
+    ) -> PoemResult<AuthenticatedUser> {
+        let presented = match credentials {
+            ServiceCredentials::Bearer(BearerToken(bearer)) => {
+                return JwtAuth::verify(&bearer.token, config, revocations);
+            }
+            ServiceCredentials::Cookie(AccessTokenCookie(cookie)) => {
+                return JwtAuth::verify(&cookie.key, config, revocations);
+            }
+            ServiceCredentials::ApiKey(ApiKeyHeader(presented)) => presented,
+        };
+        let Some(key) = presented.header.strip_prefix("ApiKey ") else {
+            return Err(PoemError::from_string(
+                "unsupported authorization scheme",
+                StatusCode::UNAUTHORIZED,
+            ));
+        };
+
+        match api_keys.authenticate(key.trim(), presented.client_ip).await {
+            Ok(Some((user, scopes))) => Ok(AuthenticatedUser {
+                user_id: user.id,
+                email: user.email,
+                role: user.role,
+                session_id: None,
+                scopes: Some(scopes),
+            }),
+            Ok(None) => Err(PoemError::from_string(
+                "invalid or revoked api key",
+                StatusCode::UNAUTHORIZED,
+            )),
+            Err(err) if err.is::<ApiKeyNotAllowed>() => Err(PoemError::from_string(
+                err.to_string(),
+                StatusCode::FORBIDDEN,
+            )),
+            Err(err) => Err(PoemError::from_string(
+                err.to_string(),
+                StatusCode::INTERNAL_SERVER_ERROR,
+            )),
+        }
+    }
+}
+
+impl AdminAuth {
+    pub fn from_credentials(
+        credentials: &SessionCredentials,
+        config: &JwtServiceConfig,
+        revocations: &SessionRevocations,
+    ) -> PoemResult<AuthenticatedUser> {
+        let user = JwtAuth::from_credentials(credentials, config, revocations)?;
+        if !user.is_admin() {
+            return Err(PoemError::from_string("forbidden", StatusCode::FORBIDDEN));
+        }
+        Ok(user)
+    }
+}
@@ -0,0 +1,70 @@
+use opentelemetry::{
+    KeyValue, global,
+    trace::{FutureExt, SpanKind, Status, TraceContextExt, Tracer},
+};
+use poem::{Endpoint, IntoResponse, Middleware, Request, Response, Result as PoemResult};
+
+use crate::application::services::telemetry::{self, TRACE_FIELDS, TRACER, TraceCarrier};
+
+/// Opens a server span for every request, continuing the caller's trace
+/// when it sends `traceparent`. Work the request queues carries the span on
+/// through the message bus.
+pub struct RequestTracing;
+
+impl<E: Endpoint> Middleware<E> for RequestTracing {
+    type Output = RequestTracingEndpoint<E>;
+
+    fn transform(&self, inner: E) -> Self::Output {
+        RequestTracingEndpoint { inner }
+    }
+}
+
+pub struct RequestTracingEndpoint<E> {
+    inner: E,
+}
+
+impl<E: Endpoint> Endpoint for RequestTracingEndpoint<E> {
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> PoemResult<Self::Output> {
+        let mut carrier = TraceCarrier::new();
+        for name in TRACE_FIELDS {
+            if let Some(value) = req.header(name) {
+                carrier.insert(name.to_string(), value.to_string());
+            }
+        }
+        let parent = telemetry::extract(&carrier);
+
+        let tracer = global::tracer(TRACER);
+        let span = tracer
+            .span_builder(req.method().to_string())
+            .with_kind(SpanKind::Server)
+            .with_attributes([
+                KeyValue::new("http.request.method", req.method().to_string()),
+                KeyValue::new("url.path", req.uri().path().to_string()),
+            ])
+            .start_with_context(&tracer, &parent);
+        let cx = parent.with_span(span);
+
+        let result = self
+            .inner
+            .call(req)
+            .with_context(cx.clone())
+            .await
+            .map(IntoResponse::into_response);
+        let status = match &result {
+            Ok(response) => response.status(),
+            Err(err) => err.status(),
+        };
+        let span = cx.span();
+        span.set_attribute(KeyValue::new(
+            "http.response.status_code",
+            i64::from(status.as_u16()),
+        ));
+        if status.is_server_error() {
+            span.set_status(Status::error(status.to_string()));
+        }
+        span.end();
+        result
+    }
+}
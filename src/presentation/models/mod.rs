@@ -1,6 +1,11 @@
 use poem_openapi::Enum;
 
-use crate::domain::models::{MessageStatus, MessengerChatType, MessengerType, RequestedBy};
+use crate::application::usecases::message_status_link::DeliveryState;
+use crate::domain::models::{
+    ApiKeyScope, ChatAction, ConsentStatus, MemberRole, MessageClass, MessageLinkKind,
+    MessagePriority, MessageStatus, MessageStatusKind, MessengerChatType, MessengerType,
+    RequestedBy, UserRole,
+};
 
 #[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
 pub enum MessengerKind {
@@ -60,6 +65,76 @@ impl From<RequestedBy> for RequestedByKind {
     }
 }
 
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum MessageClassKind {
+    #[default]
+    #[oai(rename = "transactional")]
+    Transactional,
+    #[oai(rename = "marketing")]
+    Marketing,
+}
+
+impl From<MessageClassKind> for MessageClass {
+    fn from(value: MessageClassKind) -> Self {
+        match value {
+            MessageClassKind::Transactional => MessageClass::Transactional,
+            MessageClassKind::Marketing => MessageClass::Marketing,
+        }
+    }
+}
+
+impl From<MessageClass> for MessageClassKind {
+    fn from(value: MessageClass) -> Self {
+        match value {
+            MessageClass::Transactional => MessageClassKind::Transactional,
+            MessageClass::Marketing => MessageClassKind::Marketing,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum MessagePriorityKind {
+    #[oai(rename = "high")]
+    High,
+    #[default]
+    #[oai(rename = "normal")]
+    Normal,
+    #[oai(rename = "low")]
+    Low,
+}
+
+impl From<MessagePriorityKind> for MessagePriority {
+    fn from(value: MessagePriorityKind) -> Self {
+        match value {
+            MessagePriorityKind::High => MessagePriority::High,
+            MessagePriorityKind::Normal => MessagePriority::Normal,
+            MessagePriorityKind::Low => MessagePriority::Low,
+        }
+    }
+}
+
+impl From<MessagePriority> for MessagePriorityKind {
+    fn from(value: MessagePriority) -> Self {
+        match value {
+            MessagePriority::High => MessagePriorityKind::High,
+            MessagePriority::Normal => MessagePriorityKind::Normal,
+            MessagePriority::Low => MessagePriorityKind::Low,
+        }
+    }
+}
+
+/// How `POST /messages` hands the message to the messenger.
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq, Default)]
+pub enum DeliveryModeKind {
+    /// Through the message bus.
+    #[default]
+    #[oai(rename = "queued")]
+    Queued,
+    /// Within the request, falling back to the bus on timeout or failure.
+    #[oai(rename = "sync")]
+    Sync,
+}
+
 #[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
 pub enum MessageStatusDto {
     Pending,
@@ -69,6 +144,40 @@ pub enum MessageStatusDto {
     Retrying,
     Failed,
     Cancelled,
+    Deferred,
+    Suppressed,
+}
+
+impl From<MessageStatusDto> for MessageStatusKind {
+    fn from(value: MessageStatusDto) -> Self {
+        match value {
+            MessageStatusDto::Pending => MessageStatusKind::Pending,
+            MessageStatusDto::Scheduled => MessageStatusKind::Scheduled,
+            MessageStatusDto::InFlight => MessageStatusKind::InFlight,
+            MessageStatusDto::Sent => MessageStatusKind::Sent,
+            MessageStatusDto::Retrying => MessageStatusKind::Retrying,
+            MessageStatusDto::Failed => MessageStatusKind::Failed,
+            MessageStatusDto::Cancelled => MessageStatusKind::Cancelled,
+            MessageStatusDto::Deferred => MessageStatusKind::Deferred,
+            MessageStatusDto::Suppressed => MessageStatusKind::Suppressed,
+        }
+    }
+}
+
+impl From<MessageStatusKind> for MessageStatusDto {
+    fn from(value: MessageStatusKind) -> Self {
+        match value {
+            MessageStatusKind::Pending => MessageStatusDto::Pending,
+            MessageStatusKind::Scheduled => MessageStatusDto::Scheduled,
+            MessageStatusKind::InFlight => MessageStatusDto::InFlight,
+            MessageStatusKind::Sent => MessageStatusDto::Sent,
+            MessageStatusKind::Retrying => MessageStatusDto::Retrying,
+            MessageStatusKind::Failed => MessageStatusDto::Failed,
+            MessageStatusKind::Cancelled => MessageStatusDto::Cancelled,
+            MessageStatusKind::Deferred => MessageStatusDto::Deferred,
+            MessageStatusKind::Suppressed => MessageStatusDto::Suppressed,
+        }
+    }
 }
 
 impl From<&MessageStatus> for MessageStatusDto {
@@ -81,6 +190,8 @@ impl From<&MessageStatus> for MessageStatusDto {
             MessageStatus::Retrying { .. } => MessageStatusDto::Retrying,
             MessageStatus::Failed { .. } => MessageStatusDto::Failed,
             MessageStatus::Cancelled => MessageStatusDto::Cancelled,
+            MessageStatus::Deferred { .. } => MessageStatusDto::Deferred,
+            MessageStatus::Suppressed { .. } => MessageStatusDto::Suppressed,
         }
     }
 }
@@ -110,3 +221,180 @@ impl From<MessengerChatType> for ChatTypeKind {
         }
     }
 }
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ChatActionKind {
+    #[oai(rename = "typing")]
+    Typing,
+    #[oai(rename = "upload_photo")]
+    UploadPhoto,
+}
+
+impl From<ChatActionKind> for ChatAction {
+    fn from(value: ChatActionKind) -> Self {
+        match value {
+            ChatActionKind::Typing => ChatAction::Typing,
+            ChatActionKind::UploadPhoto => ChatAction::UploadPhoto,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConsentStatusKind {
+    #[oai(rename = "pending")]
+    Pending,
+    #[oai(rename = "confirmed")]
+    Confirmed,
+    #[oai(rename = "revoked")]
+    Revoked,
+}
+
+impl From<ConsentStatus> for ConsentStatusKind {
+    fn from(value: ConsentStatus) -> Self {
+        match value {
+            ConsentStatus::Pending => ConsentStatusKind::Pending,
+            ConsentStatus::Confirmed => ConsentStatusKind::Confirmed,
+            ConsentStatus::Revoked => ConsentStatusKind::Revoked,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MessageLinkKindDto {
+    #[oai(rename = "retried_from")]
+    RetriedFrom,
+    #[oai(rename = "escalated_from")]
+    EscalatedFrom,
+    #[oai(rename = "thread_parent")]
+    ThreadParent,
+    #[oai(rename = "digest_member")]
+    DigestMember,
+}
+
+impl From<MessageLinkKind> for MessageLinkKindDto {
+    fn from(value: MessageLinkKind) -> Self {
+        match value {
+            MessageLinkKind::RetriedFrom => MessageLinkKindDto::RetriedFrom,
+            MessageLinkKind::EscalatedFrom => MessageLinkKindDto::EscalatedFrom,
+            MessageLinkKind::ThreadParent => MessageLinkKindDto::ThreadParent,
+            MessageLinkKind::DigestMember => MessageLinkKindDto::DigestMember,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DeliveryStateKind {
+    #[oai(rename = "pending")]
+    Pending,
+    #[oai(rename = "delivered")]
+    Delivered,
+    #[oai(rename = "not_delivered")]
+    NotDelivered,
+}
+
+impl From<DeliveryState> for DeliveryStateKind {
+    fn from(value: DeliveryState) -> Self {
+        match value {
+            DeliveryState::Pending => DeliveryStateKind::Pending,
+            DeliveryState::Delivered => DeliveryStateKind::Delivered,
+            DeliveryState::NotDelivered => DeliveryStateKind::NotDelivered,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExportFormatKind {
+    #[oai(rename = "csv")]
+    Csv,
+    #[oai(rename = "jsonl")]
+    Jsonl,
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ApiKeyScopeKind {
+    /// Send messages, events and OTPs.
+    #[oai(rename = "messages:send")]
+    MessagesSend,
+    /// Read message history, statuses and stats.
+    #[oai(rename = "messages:read")]
+    MessagesRead,
+    /// Register, list and share messenger tokens.
+    #[oai(rename = "tokens:manage")]
+    TokensManage,
+}
+
+impl From<ApiKeyScopeKind> for ApiKeyScope {
+    fn from(value: ApiKeyScopeKind) -> Self {
+        match value {
+            ApiKeyScopeKind::MessagesSend => ApiKeyScope::MessagesSend,
+            ApiKeyScopeKind::MessagesRead => ApiKeyScope::MessagesRead,
+            ApiKeyScopeKind::TokensManage => ApiKeyScope::TokensManage,
+        }
+    }
+}
+
+impl From<ApiKeyScope> for ApiKeyScopeKind {
+    fn from(value: ApiKeyScope) -> Self {
+        match value {
+            ApiKeyScope::MessagesSend => ApiKeyScopeKind::MessagesSend,
+            ApiKeyScope::MessagesRead => ApiKeyScopeKind::MessagesRead,
+            ApiKeyScope::TokensManage => ApiKeyScopeKind::TokensManage,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UserRoleKind {
+    #[oai(rename = "admin")]
+    Admin,
+    #[oai(rename = "member")]
+    Member,
+    #[oai(rename = "read_only")]
+    ReadOnly,
+}
+
+impl From<UserRoleKind> for UserRole {
+    fn from(value: UserRoleKind) -> Self {
+        match value {
+            UserRoleKind::Admin => UserRole::Admin,
+            UserRoleKind::Member => UserRole::Member,
+            UserRoleKind::ReadOnly => UserRole::ReadOnly,
+        }
+    }
+}
+
+impl From<UserRole> for UserRoleKind {
+    fn from(value: UserRole) -> Self {
+        match value {
+            UserRole::Admin => UserRoleKind::Admin,
+            UserRole::Member => UserRoleKind::Member,
+            UserRole::ReadOnly => UserRoleKind::ReadOnly,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Debug, Eq, PartialEq)]
+pub enum MemberRoleKind {
+    #[oai(rename = "owner")]
+    Owner,
+    #[oai(rename = "member")]
+    Member,
+}
+
+impl From<MemberRoleKind> for MemberRole {
+    fn from(value: MemberRoleKind) -> Self {
+        match value {
+            MemberRoleKind::Owner => MemberRole::Owner,
+            MemberRoleKind::Member => MemberRole::Member,
+        }
+    }
+}
+
+impl From<MemberRole> for MemberRoleKind {
+    fn from(value: MemberRole) -> Self {
+        match value {
+            MemberRole::Owner => MemberRoleKind::Owner,
+            MemberRole::Member => MemberRoleKind::Member,
+        }
+    }
+}